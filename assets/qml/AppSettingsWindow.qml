@@ -698,6 +698,22 @@ ApplicationWindow {
                             Layout.fillWidth: true
                         }
 
+                        CheckBox {
+                            id: search_history_checkbox
+                            text: "Record Search History"
+                            font.pointSize: root.pointSize
+                            onCheckedChanged: {
+                                SuttaBridge.set_search_history_enabled(checked);
+                            }
+                        }
+
+                        Label {
+                            text: "Searches are kept on this device with their results, to run them again and to suggest queries while typing. The history can be cleared in Windows > Search History."
+                            font.pointSize: root.pointSize - 2
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                        }
+
                         // Snippet Preview section
                         Label {
                             text: "Snippet Preview"
@@ -1210,6 +1226,7 @@ ApplicationWindow {
         // Load initial state for Find tab settings
         search_as_you_type_checkbox.checked = SuttaBridge.get_search_as_you_type();
         open_find_in_results_checkbox.checked = SuttaBridge.get_open_find_in_sutta_results();
+        search_history_checkbox.checked = SuttaBridge.get_search_history_enabled();
 
         // Snippet display settings
         snippet_chars_before_spin.value = SuttaBridge.get_snippet_chars_before();
@@ -39,6 +39,7 @@ ColumnLayout {
             property var chapter_list: []
             property bool use_toc: false
             property var expanded_items: ({}) // Track expanded state of items with children
+            property var reading_progress: JSON.parse(SuttaBridge.get_book_reading_progress_json(modelData.uid))
 
             Component.onCompleted: {
                 // If auto_expand is true, load the spine items immediately
@@ -239,6 +240,14 @@ ColumnLayout {
                             Layout.fillWidth: true
                         }
 
+                        Label {
+                            visible: book_item_wrapper.reading_progress.chapters_started > 0
+                            text: Math.round(book_item_wrapper.reading_progress.percent_read) + "% read"
+                            font.pointSize: root.pointSize - 2
+                            color: book_item_wrapper.is_selected ? palette.highlightedText : palette.mid
+                            Layout.fillWidth: true
+                        }
+
                         Label {
                             visible: (book_item_wrapper.modelData.document_type === "epub" || book_item_wrapper.modelData.document_type === "html") && book_item_wrapper.modelData.enable_embedded_css === false
                             text: "Embedded CSS: Off"
@@ -0,0 +1,168 @@
+pragma ComponentBehavior: Bound
+
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls
+
+import com.profoundlabs.simsapa
+
+// Chant text with the word or syllable at the playback position highlighted,
+// driven by the recording's timing track (backend/src/audio/timing_track.rs).
+ColumnLayout {
+    id: root
+
+    // Input properties
+    property string section_uid: ""
+    property string recording_uid: ""
+    property int playback_position_ms: 0
+    property int pointSize: 12
+
+    property string granularity: "syllable"
+    property var track: null
+    property bool track_saved: false
+    property var lines: []           // [[{index, text, space_after}]]
+    property int current_index: -1
+    property real current_progress: 0
+    property string error_text: ""
+
+    signal seek_requested(int position_ms)
+
+    spacing: 4
+
+    function load_track() {
+        if (root.section_uid === "" || root.recording_uid === "") return;
+        let result;
+        try {
+            result = JSON.parse(SuttaBridge.get_timing_track(root.section_uid, root.recording_uid, root.granularity));
+        } catch (e) {
+            result = { error: "Failed to parse timing track" };
+        }
+        if (result.error) {
+            root.error_text = result.error;
+            root.track = null;
+            root.lines = [];
+            return;
+        }
+        root.error_text = "";
+        root.track = result.track;
+        root.track_saved = result.saved;
+        root.granularity = result.track.granularity;
+
+        let lines = [];
+        let tokens = result.track.tokens;
+        for (let i = 0; i < tokens.length; i++) {
+            let t = tokens[i];
+            while (lines.length <= t.line) lines.push([]);
+            let next = i + 1 < tokens.length ? tokens[i + 1] : null;
+            let space_after = next !== null && next.line === t.line && next.word !== t.word;
+            lines[t.line].push({ index: i, text: t.text, space_after: space_after });
+        }
+        root.lines = lines;
+        root.update_position();
+    }
+
+    function update_position() {
+        if (root.track === null) return;
+        let pos = JSON.parse(SuttaBridge.get_timing_position(root.section_uid, root.recording_uid, root.playback_position_ms));
+        root.current_index = pos.index !== undefined ? pos.index : -1;
+        root.current_progress = pos.progress !== undefined ? pos.progress : 0;
+    }
+
+    function save_track() {
+        if (root.track === null) return;
+        let result = JSON.parse(SuttaBridge.save_timing_track(root.section_uid, root.recording_uid, JSON.stringify(root.track)));
+        if (result.ok) {
+            root.track_saved = true;
+            root.error_text = "";
+        } else {
+            root.error_text = result.error || "Failed to save timing";
+        }
+    }
+
+    onPlayback_position_msChanged: root.update_position()
+    onRecording_uidChanged: root.load_track()
+    Component.onCompleted: root.load_track()
+
+    RowLayout {
+        Layout.fillWidth: true
+        spacing: 6
+
+        ComboBox {
+            id: granularity_combo
+            model: ["syllable", "word"]
+            currentIndex: root.granularity === "word" ? 1 : 0
+            onActivated: {
+                root.granularity = currentText;
+                root.load_track();
+            }
+        }
+
+        Label {
+            text: root.track_saved ? "Saved timing" : "Estimated from markers"
+            color: palette.placeholderText
+            font.pointSize: 10
+        }
+
+        Item { Layout.fillWidth: true }
+
+        Button {
+            text: "Save Timing"
+            enabled: root.track !== null && !root.track_saved
+            onClicked: root.save_track()
+
+            ToolTip.visible: hovered
+            ToolTip.text: "Keep this timing for the recording (exported with the section)"
+        }
+    }
+
+    Label {
+        Layout.fillWidth: true
+        visible: root.error_text !== ""
+        text: root.error_text
+        color: "red"
+        wrapMode: Text.WordWrap
+    }
+
+    Repeater {
+        model: root.lines
+
+        delegate: Flow {
+            id: line_flow
+            required property var modelData
+            Layout.fillWidth: true
+
+            Repeater {
+                model: line_flow.modelData
+
+                delegate: Text {
+                    id: token_text
+                    required property var modelData
+                    property bool is_current: token_text.modelData.index === root.current_index
+                    property bool is_past: root.current_index >= 0 && token_text.modelData.index < root.current_index
+
+                    text: token_text.modelData.text + (token_text.modelData.space_after ? " " : "")
+                    font.pointSize: root.pointSize
+                    font.bold: is_current
+                    color: is_current ? palette.highlightedText : (is_past ? palette.placeholderText : palette.text)
+
+                    Rectangle {
+                        z: -1
+                        anchors.fill: parent
+                        visible: token_text.is_current
+                        color: palette.highlight
+                        radius: 2
+                    }
+
+                    MouseArea {
+                        anchors.fill: parent
+                        cursorShape: Qt.PointingHandCursor
+                        onClicked: {
+                            let t = root.track.tokens[token_text.modelData.index];
+                            root.seek_requested(t.start_ms);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
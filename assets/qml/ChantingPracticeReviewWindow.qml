@@ -198,6 +198,140 @@ ApplicationWindow {
             update_model(reference_model);
             update_model(user_model);
         }
+
+        function onPracticeComparisonReady(user_recording_uid: string, report_json: string) {
+            if (user_recording_uid !== comparison_dialog.user_uid) return;
+            comparison_dialog.is_loading = false;
+            try {
+                let report = JSON.parse(report_json);
+                if (report.error) {
+                    comparison_dialog.error_text = report.error;
+                    comparison_dialog.report = null;
+                } else {
+                    comparison_dialog.error_text = "";
+                    comparison_dialog.report = report;
+                }
+            } catch (e) {
+                logger.error("Failed to parse practice comparison: " + e);
+                comparison_dialog.error_text = "Invalid comparison result";
+            }
+        }
+    }
+
+    // Compare a user recording against the first reference recording
+    function compare_with_reference(user_uid: string, user_label: string) {
+        if (reference_model.count === 0) return;
+        let ref = reference_model.get(0);
+        comparison_dialog.user_uid = user_uid;
+        comparison_dialog.title = "Compare: " + user_label + " / " + ref.label;
+        comparison_dialog.report = null;
+        comparison_dialog.error_text = "";
+        comparison_dialog.is_loading = true;
+        comparison_dialog.open();
+        SuttaBridge.compare_practice_recording(root.current_section_uid, ref.uid, user_uid);
+    }
+
+    function format_signed_ms(ms: int): string {
+        let secs = (ms / 1000).toFixed(2);
+        return (ms > 0 ? "+" : "") + secs + " s";
+    }
+
+    // Practice comparison report
+    Dialog {
+        id: scansion_dialog
+        title: "Scansion"
+        standardButtons: Dialog.Close
+        anchors.centerIn: parent
+        width: Math.min(root.width - 20, 700)
+        height: Math.min(root.height - 40, 600)
+        modal: true
+
+        property string html: ""
+
+        ScrollView {
+            anchors.fill: parent
+            clip: true
+
+            Label {
+                width: scansion_dialog.availableWidth
+                text: scansion_dialog.html
+                textFormat: Text.RichText
+                wrapMode: Text.WordWrap
+                font.pointSize: root.pointSize + 2
+                font.family: "serif"
+            }
+        }
+    }
+
+    Dialog {
+        id: comparison_dialog
+        standardButtons: Dialog.Close
+        anchors.centerIn: parent
+        width: Math.min(root.width - 20, 600)
+        modal: true
+
+        property string user_uid: ""
+        property var report: null
+        property string error_text: ""
+        property bool is_loading: false
+
+        ColumnLayout {
+            anchors.fill: parent
+            spacing: 8
+
+            BusyIndicator {
+                Layout.alignment: Qt.AlignHCenter
+                running: comparison_dialog.is_loading
+                visible: running
+            }
+
+            Label {
+                visible: comparison_dialog.error_text !== ""
+                text: comparison_dialog.error_text
+                color: "red"
+                wrapMode: Text.WordWrap
+                Layout.fillWidth: true
+            }
+
+            Label {
+                visible: comparison_dialog.report !== null
+                font.pointSize: root.pointSize + 2
+                font.bold: true
+                text: comparison_dialog.report ? "Score: " + Math.round(comparison_dialog.report.score) : ""
+            }
+
+            Label {
+                visible: comparison_dialog.report !== null
+                wrapMode: Text.WordWrap
+                Layout.fillWidth: true
+                text: {
+                    let r = comparison_dialog.report;
+                    if (!r) return "";
+                    let lines = ["Tempo: " + Math.round(r.tempo_ratio * 100) + "% of the reference"];
+                    if (r.key_offset_cents !== null) {
+                        lines.push("Key: " + (r.key_offset_cents > 0 ? "+" : "") + Math.round(r.key_offset_cents) + " cents");
+                    }
+                    if (r.mean_pitch_deviation_cents !== null) {
+                        lines.push("Pitch deviation: " + Math.round(r.mean_pitch_deviation_cents) + " cents");
+                    }
+                    return lines.join("\n");
+                }
+            }
+
+            Repeater {
+                model: comparison_dialog.report ? comparison_dialog.report.markers : []
+                delegate: Label {
+                    required property var modelData
+                    Layout.fillWidth: true
+                    elide: Text.ElideRight
+                    text: modelData.label
+                        + "  drift " + root.format_signed_ms(modelData.start_drift_ms)
+                        + "  length " + Math.round(modelData.duration_ratio * 100) + "%"
+                        + (modelData.pitch_deviation_cents !== null
+                            ? "  pitch ±" + Math.round(modelData.pitch_deviation_cents) + "¢" : "")
+                }
+            }
+        }
     }
 
     // Confirmation dialog for deleting a recording
@@ -368,12 +502,28 @@ ApplicationWindow {
                 }
             }
 
-            Button {
-                text: "Gloss Chanting Text"
+            RowLayout {
                 Layout.alignment: Qt.AlignLeft
-                enabled: pali_text.text.trim().length > 0
-                onClicked: {
-                    SuttaBridge.run_gloss_in_sutta_window(root.window_id, pali_text.text);
+                spacing: 6
+
+                Button {
+                    text: "Gloss Chanting Text"
+                    enabled: pali_text.text.trim().length > 0
+                    onClicked: {
+                        SuttaBridge.run_gloss_in_sutta_window(root.window_id, pali_text.text);
+                    }
+                }
+
+                Button {
+                    text: "Scansion"
+                    enabled: pali_text.text.trim().length > 0
+                    onClicked: {
+                        scansion_dialog.html = SuttaBridge.get_scansion_html(pali_text.text);
+                        scansion_dialog.open();
+                    }
+
+                    ToolTip.visible: hovered
+                    ToolTip.text: "Mark garu / lahu syllables and identify the metre of each verse"
                 }
             }
 
@@ -545,6 +695,7 @@ ApplicationWindow {
                                             width: ref_playback_loader.width
 
                                             recording_uid: ref_delegate.uid
+                                            section_uid: root.current_section_uid
                                             file_path: ref_delegate.computed_file_path
                                             label: ref_delegate.label
                                             recording_type: "reference"
@@ -651,6 +802,15 @@ ApplicationWindow {
                                             color: user_delegate.is_open ? palette.highlightedText : palette.text
                                         }
 
+                                        Button {
+                                            text: "Compare"
+                                            visible: reference_model.count > 0
+                                            onClicked: root.compare_with_reference(user_delegate.uid, user_delegate.label)
+
+                                            ToolTip.visible: hovered
+                                            ToolTip.text: "Compare timing and pitch with the reference recording"
+                                        }
+
                                         Button {
                                             id: user_open_close_btn
                                             text: user_delegate.is_open ? "Close" : "Open"
@@ -700,6 +860,7 @@ ApplicationWindow {
                                             width: user_playback_loader.width
 
                                             recording_uid: user_delegate.uid
+                                            section_uid: root.current_section_uid
                                             file_path: user_delegate.computed_file_path
                                             label: user_delegate.label
                                             recording_type: "user"
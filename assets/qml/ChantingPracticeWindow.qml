@@ -525,6 +525,104 @@ ApplicationWindow {
         }
     }
 
+    // --- Export Audio Options Dialog ---
+
+    Dialog {
+        id: export_audio_dialog
+        parent: Overlay.overlay
+        anchors.centerIn: parent
+        width: 420
+        title: "Export Audio"
+        modal: true
+        standardButtons: Dialog.Ok | Dialog.Cancel
+
+        function options() {
+            return {
+                format: export_audio_format.currentValue,
+                recording_type: export_audio_recording_type.currentValue,
+                trim_to_markers: export_audio_trim.checked,
+                normalize_loudness: export_audio_normalize.checked,
+                concatenate: export_audio_concatenate.checked,
+            };
+        }
+
+        ColumnLayout {
+            anchors.fill: parent
+            spacing: 8
+
+            Label { text: "Format:"; font.pointSize: root.pointSize }
+            ComboBox {
+                id: export_audio_format
+                Layout.fillWidth: true
+                textRole: "text"
+                valueRole: "value"
+                model: [
+                    { text: "WAV", value: "wav" },
+                    { text: "FLAC", value: "flac" },
+                ]
+            }
+
+            Label { text: "Recordings:"; font.pointSize: root.pointSize }
+            ComboBox {
+                id: export_audio_recording_type
+                Layout.fillWidth: true
+                textRole: "text"
+                valueRole: "value"
+                model: [
+                    { text: "Reference", value: "reference" },
+                    { text: "User practice", value: "user" },
+                ]
+            }
+
+            CheckBox {
+                id: export_audio_trim
+                text: "Trim to range markers"
+            }
+            CheckBox {
+                id: export_audio_normalize
+                text: "Normalize loudness"
+                checked: true
+            }
+            CheckBox {
+                id: export_audio_concatenate
+                text: "Join into a single file with chapters"
+            }
+        }
+
+        onAccepted: export_audio_folder_dialog.open()
+    }
+
+    // --- Export Audio FolderDialog ---
+
+    FolderDialog {
+        id: export_audio_folder_dialog
+        title: "Export Audio To Folder"
+
+        onAccepted: {
+            const dest_dir = root.file_url_to_path(selectedFolder.toString());
+            const selected_uids = tree_list.get_selected_uids();
+            const result_str = SuttaBridge.export_chanting_audio(
+                JSON.stringify(selected_uids), dest_dir, JSON.stringify(export_audio_dialog.options()));
+
+            let result;
+            try {
+                result = JSON.parse(result_str);
+            } catch (e) {
+                result = { error: "Failed to parse result" };
+            }
+
+            root.export_selection_mode = false;
+            tree_list.clear_selection();
+
+            if (result.ok) {
+                export_result_dialog.result_message = "Exported " + result.audio_files.length + " audio file(s).";
+            } else {
+                export_result_dialog.result_message = "Audio export failed: " + (result.error || "Unknown error");
+            }
+            export_result_dialog.open();
+        }
+    }
+
     // --- Import Open FileDialog ---
 
     FileDialog {
@@ -674,6 +772,20 @@ ApplicationWindow {
                 }
             }
 
+            Button {
+                text: "Export Audio"
+                visible: root.export_selection_mode
+
+                onClicked: {
+                    const selected_uids = tree_list.get_selected_uids();
+                    if (selected_uids.sections.length === 0) {
+                        export_no_selection_dialog.open();
+                        return;
+                    }
+                    export_audio_dialog.open();
+                }
+            }
+
             Button {
                 text: "Cancel"
                 visible: root.export_selection_mode
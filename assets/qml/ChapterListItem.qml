@@ -90,10 +90,17 @@ ItemDelegate {
             const file_path = hash_index >= 0 ? content_path.substring(0, hash_index) : content_path;
             const anchor = hash_index >= 0 ? content_path.substring(hash_index) : "";
 
-            const spine_item_uid = SuttaBridge.get_spine_item_uid_by_path(
-                chapter_item.book_uid,
-                file_path
-            );
+            // PDF pages have the whole path, e.g. document.pdf#page=12
+            let spine_item_uid = "";
+            if (hash_index >= 0) {
+                spine_item_uid = SuttaBridge.get_spine_item_uid_by_path(chapter_item.book_uid, content_path);
+            }
+            if (spine_item_uid.length === 0) {
+                spine_item_uid = SuttaBridge.get_spine_item_uid_by_path(
+                    chapter_item.book_uid,
+                    file_path
+                );
+            }
 
             if (spine_item_uid.length > 0) {
                 chapter_item.chapter_clicked(
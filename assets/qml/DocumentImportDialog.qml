@@ -22,6 +22,8 @@ Dialog {
     property string selected_file_path: ""
     property string document_type: ""
     property bool is_importing: false
+    property bool ocr_available: false
+    readonly property bool is_mobile: Qt.platform.os === "android" || Qt.platform.os === "ios"
 
     signal import_completed(bool success, string message)
 
@@ -34,6 +36,8 @@ Dialog {
         uid_field.text = "";
         split_chapters_checkbox.checked = false;
         split_tag_dropdown.currentIndex = 0;
+        ocr_checkbox.checked = root.ocr_available;
+        ocr_languages_field.text = "eng";
         is_importing = false;
         progress_bar.visible = false;
         status_label.text = "";
@@ -44,6 +48,10 @@ Dialog {
         if (lower_path.endsWith(".epub")) return "epub";
         if (lower_path.endsWith(".pdf")) return "pdf";
         if (lower_path.endsWith(".html") || lower_path.endsWith(".htm")) return "html";
+        if (lower_path.endsWith(".md") || lower_path.endsWith(".markdown")) return "markdown";
+        if (lower_path.endsWith(".docx")) return "docx";
+        if (lower_path.endsWith(".odt")) return "odt";
+        if (lower_path.endsWith(".fb2")) return "fb2";
         return "";
     }
 
@@ -54,35 +62,36 @@ Dialog {
         return dot_index > 0 ? filename.substring(0, dot_index) : filename;
     }
 
+    function url_to_local_path(url) {
+        // Handle file:// URLs - convert to local path
+        const file_url_str = url.toString();
+        if (file_url_str.startsWith("file:///")) {
+            // On Windows: file:///C:/path -> C:/path
+            // On Unix: file:///path -> /path
+            const without_prefix = file_url_str.substring(8); // Remove "file:///"
+            if (Qt.platform.os === "windows" && without_prefix.match(/^[A-Za-z]:/)) {
+                // Windows path like "C:/Users/..." - use as-is
+                return decodeURIComponent(without_prefix);
+            } else {
+                // Unix path - add leading slash back
+                return "/" + decodeURIComponent(without_prefix);
+            }
+        } else if (file_url_str.startsWith("file://")) {
+            // file://path (no third slash) - just remove prefix
+            return decodeURIComponent(file_url_str.substring(7));
+        }
+        // Not a file URL (e.g., content:// on Android)
+        return file_url_str;
+    }
+
     FileDialog {
         id: file_dialog
         title: "Select Document to Import"
         fileMode: FileDialog.OpenFile
-        nameFilters: ["Documents (*.epub *.pdf *.html *.htm)", "EPUB files (*.epub)", "PDF files (*.pdf)", "HTML files (*.html *.htm)"]
+        nameFilters: ["Documents (*.epub *.pdf *.html *.htm *.md *.markdown *.docx *.odt *.fb2)", "EPUB files (*.epub)", "PDF files (*.pdf)", "HTML files (*.html *.htm)", "Markdown files (*.md *.markdown)", "Word documents (*.docx)", "OpenDocument text (*.odt)", "FictionBook files (*.fb2)"]
 
         onAccepted: {
-            let file_path = "";
-
-            // Handle file:// URLs - convert to local path
-            const file_url_str = selectedFile.toString();
-            if (file_url_str.startsWith("file:///")) {
-                // On Windows: file:///C:/path -> C:/path
-                // On Unix: file:///path -> /path
-                const without_prefix = file_url_str.substring(8); // Remove "file:///"
-                if (Qt.platform.os === "windows" && without_prefix.match(/^[A-Za-z]:/)) {
-                    // Windows path like "C:/Users/..." - use as-is
-                    file_path = decodeURIComponent(without_prefix);
-                } else {
-                    // Unix path - add leading slash back
-                    file_path = "/" + decodeURIComponent(without_prefix);
-                }
-            } else if (file_url_str.startsWith("file://")) {
-                // file://path (no third slash) - just remove prefix
-                file_path = decodeURIComponent(file_url_str.substring(7));
-            } else {
-                // Not a file URL (e.g., content:// on Android)
-                file_path = file_url_str;
-            }
+            let file_path = root.url_to_local_path(selectedFile);
 
             // On Android, if we got a content:// URI, copy it to a temp file
             if (Qt.platform.os === "android" && file_path.startsWith("content://")) {
@@ -130,6 +139,28 @@ Dialog {
         }
     }
 
+    // A folder of scanned page images, one image per page, or of Markdown
+    // files, one file per chapter
+    FolderDialog {
+        id: folder_dialog
+        property string folder_document_type: "images"
+        title: folder_document_type === "markdown" ? "Select Folder of Markdown Files" : "Select Folder of Page Images"
+
+        onAccepted: {
+            const folder_path = root.url_to_local_path(selectedFolder);
+            const parts = folder_path.replace(/\/+$/, "").split("/");
+            const folder_name = parts[parts.length - 1];
+
+            root.selected_file_path = folder_path;
+            root.document_type = folder_document_type;
+            uid_field.text = folder_name.replace(/[^a-zA-Z0-9-_]/g, "-").toLowerCase();
+            title_field.text = folder_name;
+            author_field.text = "";
+
+            status_label.text = "Selected: " + folder_path;
+        }
+    }
+
     ColumnLayout {
         anchors.fill: parent
         spacing: 10
@@ -156,6 +187,24 @@ Dialog {
                 text: "Browse..."
                 onClicked: file_dialog.open()
             }
+
+            Button {
+                text: "Image Folder..."
+                visible: !root.is_mobile
+                onClicked: {
+                    folder_dialog.folder_document_type = "images";
+                    folder_dialog.open();
+                }
+            }
+
+            Button {
+                text: "Markdown Folder..."
+                visible: !root.is_mobile
+                onClicked: {
+                    folder_dialog.folder_document_type = "markdown";
+                    folder_dialog.open();
+                }
+            }
         }
 
         // Title field
@@ -285,6 +334,51 @@ Dialog {
             }
         }
 
+        // OCR options for scanned PDFs and page images
+        GroupBox {
+            visible: root.document_type === "pdf" || root.document_type === "images"
+            Layout.fillWidth: true
+            title: "Text Recognition (OCR)"
+
+            ColumnLayout {
+                anchors.fill: parent
+                spacing: 5
+
+                CheckBox {
+                    id: ocr_checkbox
+                    text: root.document_type === "pdf" ? "Recognize the text of scanned pages" : "Recognize the text of the pages"
+                    enabled: root.ocr_available
+                    checked: false
+                }
+
+                RowLayout {
+                    enabled: ocr_checkbox.checked
+                    spacing: 10
+
+                    Label {
+                        text: "Languages:"
+                    }
+
+                    TextField {
+                        id: ocr_languages_field
+                        Layout.fillWidth: true
+                        text: "eng"
+                        placeholderText: "Tesseract languages, e.g. eng+pli"
+                        EnterKey.type: Qt.EnterKeyDone
+                        MobileKeyboardHelper {}
+                    }
+                }
+
+                Label {
+                    visible: !root.ocr_available
+                    Layout.fillWidth: true
+                    wrapMode: Text.WordWrap
+                    color: palette.mid
+                    text: "Install Tesseract OCR (and Poppler for PDFs) to recognize the text of scanned pages."
+                }
+            }
+        }
+
         // Progress indicator
         ProgressBar {
             id: progress_bar
@@ -329,6 +423,7 @@ Dialog {
                     status_label.text = "Starting import...";
 
                     const split_tag = split_chapters_checkbox.checked ? split_tag_dropdown.currentText : "";
+                    const ocr_languages = (ocr_checkbox.checked && ocr_checkbox.enabled) ? ocr_languages_field.text.trim() : "";
 
                     SuttaBridge.import_document(
                         root.selected_file_path,
@@ -337,7 +432,8 @@ Dialog {
                         author_field.text,
                         language_field.text.trim(),
                         root.document_type,
-                        split_tag
+                        split_tag,
+                        ocr_languages
                     );
                 }
             }
@@ -369,6 +465,7 @@ Dialog {
     }
 
     onAboutToShow: {
+        root.ocr_available = SuttaBridge.is_ocr_available();
         reset_form();
     }
 
@@ -176,6 +176,8 @@ ApplicationWindow {
 
     StorageDialog { id: storage_dialog }
 
+    DownloadSettingsDialog { id: download_settings_dialog }
+
     Dialog {
         id: error_dialog
         title: "Error"
@@ -590,6 +592,14 @@ ApplicationWindow {
 
                     Item { Layout.fillWidth: true }
 
+                    Button {
+                        text: "Download Settings"
+                        font.pointSize: root.pointSize
+                        onClicked: download_settings_dialog.open()
+                    }
+
+                    Item { Layout.fillWidth: true }
+
                     Button {
                         text: "Download"
                         font.pointSize: root.pointSize
@@ -637,6 +647,13 @@ ApplicationWindow {
                         onClicked: storage_dialog.open()
                     }
 
+                    Button {
+                        text: "Download Settings"
+                        font.pointSize: root.pointSize
+                        Layout.fillWidth: true
+                        onClicked: download_settings_dialog.open()
+                    }
+
                     Button {
                         text: "Quit"
                         font.pointSize: root.pointSize
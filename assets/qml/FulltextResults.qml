@@ -68,16 +68,22 @@ ColumnLayout {
     // generic "No results found." empty state. Defaults true so QML preview /
     // desktop tooling are unaffected.
     property bool db_ready: true
+    // "Did you mean" queries sent with the first page of a search without
+    // results, and the function to run one of them. See
+    // docs/did-you-mean-suggestions.md.
+    property var suggestions: []
+    property var run_suggestion_fn: null
     property alias currentIndex: fulltext_list.currentIndex
     property alias currentItem: fulltext_list.currentItem
 
     function set_search_result_page(search_result_page) {
-        // SearchResultPage { total_hits, page_len, page_num, results }
+        // SearchResultPage { total_hits, page_len, page_num, results, suggestions }
         let d = search_result_page;
         root.total_hits = d.total_hits;
         root.page_len = d.page_len;
         root.page_num = d.page_num;
         root.current_results = d.results;
+        root.suggestions = d.suggestions || [];
         root.update_page();
     }
 
@@ -270,6 +276,28 @@ ColumnLayout {
         Layout.fillWidth: true
     }
 
+    Text {
+        id: suggestions_text
+        visible: empty_state.visible && root.total_hits === 0 && root.suggestions.length > 0
+        text: {
+            let links = root.suggestions.map(s => {
+                let q = s.query_text.replace(/&/g, "&amp;").replace(/</g, "&lt;").replace(/>/g, "&gt;");
+                return `<a href="${encodeURIComponent(s.query_text)}">${q}</a>`;
+            });
+            return "Did you mean: " + links.join(", ") + "?";
+        }
+        textFormat: Text.RichText
+        horizontalAlignment: Text.AlignHCenter
+        color: palette.text
+        wrapMode: Text.WordWrap
+        Layout.fillWidth: true
+        onLinkActivated: function(link) {
+            if (root.run_suggestion_fn) {
+                root.run_suggestion_fn(decodeURIComponent(link)); // qmllint disable use-proper-function
+            }
+        }
+    }
+
     // While the database is loading, show the app logo and a "Loading..."
     // message instead of the empty "No results found." state. On mobile this
     // panel is the main view the user is waiting on while the app loads.
@@ -0,0 +1,322 @@
+pragma ComponentBehavior: Bound
+
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls
+import QtQuick.Dialogs
+
+import com.profoundlabs.simsapa
+
+// Reading plans: today's items of the enrolled plans, the plan list with
+// enrollment, and importing, exporting and generating plans.
+// See docs/reading-plans.md.
+Dialog {
+    id: root
+    title: "Reading Plans"
+    modal: true
+    standardButtons: Dialog.Close
+
+    property int pointSize: 10
+    property var todays_items: []
+    property var plans: []
+    property string export_plan_uid: ""
+
+    signal open_item(string item_uid, string table_name, string title)
+
+    onOpened: root.reload()
+
+    function reload() {
+        try {
+            root.todays_items = JSON.parse(SuttaBridge.get_todays_reading_json());
+            root.plans = JSON.parse(SuttaBridge.get_reading_plans_json());
+        } catch (e) {
+            root.todays_items = [];
+            root.plans = [];
+        }
+    }
+
+    function file_url_to_path(file_url_str) {
+        if (file_url_str.startsWith("file:///")) {
+            const without_prefix = file_url_str.substring(8);
+            if (Qt.platform.os === "windows" && without_prefix.match(/^[A-Za-z]:/)) {
+                return decodeURIComponent(without_prefix);
+            } else {
+                return "/" + decodeURIComponent(without_prefix);
+            }
+        } else if (file_url_str.startsWith("file://")) {
+            return decodeURIComponent(file_url_str.substring(7));
+        }
+        return file_url_str;
+    }
+
+    function show_result(result_str: string, ok_message: string) {
+        let result;
+        try {
+            result = JSON.parse(result_str);
+        } catch (e) {
+            result = { error: "Failed to parse result" };
+        }
+        status_label.text = result.ok ? ok_message : "Error: " + (result.error || "Unknown error");
+        root.reload();
+    }
+
+    function plan_status(plan_summary): string {
+        const p = plan_summary;
+        let text = `${p.items_completed} / ${p.items_total} read`;
+        if (!p.enrollment) {
+            return `${p.items_total} items, ${p.days_total} days`;
+        }
+        if (p.plan.schedule === "daily" && p.current_day !== null) {
+            text += `, day ${p.current_day} of ${p.days_total}`;
+        }
+        if (!p.enrollment.is_active) {
+            text += " (paused)";
+        }
+        return text;
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        spacing: 10
+
+        Label {
+            text: "Today"
+            font.bold: true
+            font.pointSize: root.pointSize + 2
+        }
+
+        Label {
+            visible: root.todays_items.length === 0
+            text: "Nothing to read today from the enrolled plans."
+            font.pointSize: root.pointSize
+        }
+
+        Repeater {
+            model: root.todays_items
+            delegate: RowLayout {
+                id: today_row
+                required property var modelData
+                Layout.fillWidth: true
+                spacing: 5
+
+                CheckBox {
+                    checked: today_row.modelData.is_completed
+                    onToggled: {
+                        SuttaBridge.set_reading_plan_item_completed(today_row.modelData.plan_uid, today_row.modelData.item_index, checked);
+                        root.reload();
+                    }
+                }
+
+                Label {
+                    Layout.fillWidth: true
+                    text: (today_row.modelData.title || today_row.modelData.item_uid)
+                          + (today_row.modelData.is_overdue ? ` (day ${today_row.modelData.day_number})` : "")
+                          + ` · ${today_row.modelData.plan_title}`
+                    font.pointSize: root.pointSize
+                    elide: Text.ElideRight
+                }
+
+                Button {
+                    text: "Open"
+                    onClicked: root.open_item(today_row.modelData.item_uid, today_row.modelData.table_name, today_row.modelData.title || "")
+                }
+            }
+        }
+
+        Label {
+            text: "Plans"
+            font.bold: true
+            font.pointSize: root.pointSize + 2
+            Layout.topMargin: 10
+        }
+
+        ScrollView {
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            Layout.preferredHeight: 250
+            clip: true
+
+            ListView {
+                model: root.plans
+                spacing: 8
+                delegate: ColumnLayout {
+                    id: plan_row
+                    required property var modelData
+                    width: ListView.view.width
+                    spacing: 2
+
+                    Label {
+                        text: plan_row.modelData.plan.title
+                        font.bold: true
+                        font.pointSize: root.pointSize
+                        wrapMode: Text.WordWrap
+                        Layout.fillWidth: true
+                    }
+
+                    Label {
+                        text: root.plan_status(plan_row.modelData)
+                              + (plan_row.modelData.plan.author ? ` · ${plan_row.modelData.plan.author}` : "")
+                        font.pointSize: root.pointSize - 1
+                        color: palette.mid
+                        Layout.fillWidth: true
+                    }
+
+                    RowLayout {
+                        spacing: 5
+
+                        Button {
+                            text: plan_row.modelData.enrollment ? "Restart" : "Start Today"
+                            onClicked: {
+                                SuttaBridge.enroll_in_reading_plan(plan_row.modelData.plan.uid, "");
+                                root.reload();
+                            }
+                        }
+
+                        Button {
+                            visible: plan_row.modelData.enrollment !== null
+                            text: plan_row.modelData.enrollment && plan_row.modelData.enrollment.is_active ? "Pause" : "Resume"
+                            onClicked: {
+                                SuttaBridge.set_reading_plan_active(plan_row.modelData.plan.uid, !plan_row.modelData.enrollment.is_active);
+                                root.reload();
+                            }
+                        }
+
+                        Button {
+                            visible: plan_row.modelData.enrollment !== null
+                            text: "Leave"
+                            onClicked: {
+                                SuttaBridge.unenroll_from_reading_plan(plan_row.modelData.plan.uid);
+                                root.reload();
+                            }
+                        }
+
+                        Button {
+                            text: "Export..."
+                            onClicked: {
+                                root.export_plan_uid = plan_row.modelData.plan.uid;
+                                export_file_dialog.open();
+                            }
+                        }
+
+                        Button {
+                            text: "Delete"
+                            onClicked: {
+                                SuttaBridge.delete_reading_plan(plan_row.modelData.plan.uid);
+                                root.reload();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Generate a plan over a nikāya or a sutta range
+        GridLayout {
+            columns: 2
+            Layout.fillWidth: true
+
+            Label { text: "New plan:" }
+            TextField {
+                id: new_title_input
+                Layout.fillWidth: true
+                placeholderText: "Title, e.g. The Majjhima in a year"
+            }
+
+            Label { text: "Suttas:" }
+            TextField {
+                id: new_range_input
+                Layout.fillWidth: true
+                placeholderText: "A nikāya (mn), or a range (sn56.1/en/sujato sn56.20/en/sujato)"
+            }
+
+            Label { text: "Days:" }
+            RowLayout {
+                SpinBox {
+                    id: new_days_input
+                    from: 0
+                    to: 3650
+                    value: 0
+                    editable: true
+                }
+                Label {
+                    text: "0 is one a day"
+                    color: palette.mid
+                }
+                CheckBox {
+                    id: new_sequential_input
+                    text: "At my own pace"
+                }
+            }
+        }
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            Button {
+                text: "Create Plan"
+                enabled: new_title_input.text.trim().length > 0 && new_range_input.text.trim().length > 0
+                onClicked: {
+                    const title = new_title_input.text.trim();
+                    const parts = new_range_input.text.trim().split(/\s+/);
+                    let items;
+                    if (parts[0].indexOf("/") === -1 && parts.length === 1) {
+                        items = { source: "nikaya", nikaya: parts[0], language: "en", source_uid: null };
+                    } else {
+                        items = { source: "sutta_range", first_uid: parts[0], last_uid: parts.length > 1 ? parts[1] : null };
+                    }
+                    const options = {
+                        uid: title.toLowerCase().replace(/[^a-z0-9]+/g, "-").replace(/^-|-$/g, "") || "plan",
+                        title: title,
+                        schedule: new_sequential_input.checked ? "sequential" : "daily",
+                        days: new_days_input.value > 0 ? new_days_input.value : null,
+                        items: items,
+                    };
+                    root.show_result(SuttaBridge.generate_reading_plan(JSON.stringify(options)), `Created: ${title}`);
+                }
+            }
+
+            Button {
+                text: "Import Plan..."
+                onClicked: import_file_dialog.open()
+            }
+
+            Label {
+                id: status_label
+                Layout.fillWidth: true
+                wrapMode: Text.WordWrap
+                font.pointSize: root.pointSize - 1
+            }
+        }
+    }
+
+    FileDialog {
+        id: import_file_dialog
+        title: "Import Reading Plan"
+        fileMode: FileDialog.OpenFile
+        nameFilters: ["Reading plans (*.json)", "All files (*)"]
+
+        onAccepted: {
+            let file_path = root.file_url_to_path(selectedFile.toString());
+            if (Qt.platform.os === "android" && file_path.startsWith("content://")) {
+                file_path = SuttaBridge.copy_content_uri_to_temp(file_path);
+            }
+            root.show_result(SuttaBridge.import_reading_plan_file(file_path), "Plan imported.");
+        }
+    }
+
+    FileDialog {
+        id: export_file_dialog
+        title: "Export Reading Plan"
+        fileMode: FileDialog.SaveFile
+        nameFilters: ["Reading plans (*.json)"]
+        currentFile: "file:///" + root.export_plan_uid + ".json"
+
+        onAccepted: {
+            let dest_path = root.file_url_to_path(selectedFile.toString());
+            if (!dest_path.toLowerCase().endsWith(".json")) {
+                dest_path += ".json";
+            }
+            root.show_result(SuttaBridge.export_reading_plan_file(root.export_plan_uid, dest_path), "Plan exported.");
+        }
+    }
+}
@@ -26,6 +26,8 @@ Item {
     property bool is_new_recording: false
     property real volume: 1.0
     property int playback_position_ms: 0
+    // Section the recording belongs to, used to align detected phrases to the text lines
+    property string section_uid: ""
 
     // Signals (6.7, 6.8)
     signal closed()
@@ -55,6 +57,8 @@ Item {
     property var waveform_data: []
     property int waveform_num_bars: 0  // total number of bars stored in cached waveform
     property bool waveform_loading: false
+    property bool markers_detecting: false
+    property bool karaoke_visible: false
 
     // Range creation state: "idle", "waiting_start", "waiting_end"
     property string range_create_state: "idle"
@@ -230,6 +234,21 @@ Item {
     // Handle async waveform data from background thread
     Connections {
         target: SuttaBridge
+        function onRecordingMarkersDetected(recording_uid: string, result_json: string) {
+            if (recording_uid !== root.recording_uid) return;
+            root.markers_detecting = false;
+            try {
+                let result = JSON.parse(result_json);
+                if (result.error) {
+                    root.error_message = "Phrase detection failed: " + result.error;
+                    return;
+                }
+                root.markers_json = result.markers_json;
+            } catch (e) {
+                logger.error("Failed to parse detected markers: " + e);
+            }
+        }
+
         function onWaveformDataReady(recording_uid: string, waveform_json: string) {
             if (recording_uid !== root.recording_uid) return;
             root.waveform_loading = false;
@@ -685,6 +704,28 @@ Item {
                     : "Click on the waveform to set the range end (or click here to cancel)"
             }
 
+            Button {
+                text: root.markers_detecting ? "Detecting..." : "Detect Phrases"
+                enabled: audio.duration_ms > 0 && !root.is_new_recording && !root.markers_detecting
+                onClicked: {
+                    root.markers_detecting = true;
+                    SuttaBridge.detect_recording_markers(root.recording_uid, root.file_path, root.section_uid);
+                }
+
+                ToolTip.visible: hovered
+                ToolTip.text: "Add range markers at the pauses between phrases (replaces previously detected ones)"
+            }
+
+            CheckBox {
+                text: "Karaoke"
+                enabled: root.section_uid !== "" && !root.is_new_recording
+                checked: root.karaoke_visible
+                onCheckedChanged: root.karaoke_visible = checked
+
+                ToolTip.visible: hovered
+                ToolTip.text: "Show the chant text with the current syllable highlighted"
+            }
+
             Item { Layout.fillWidth: true }
 
             CheckBox {
@@ -717,6 +758,25 @@ Item {
             }
         }
 
+        // Chant text synced to playback
+        Loader {
+            Layout.fillWidth: true
+            active: root.karaoke_visible && !root.is_recording && root.file_path !== "" && !root.file_not_found
+            visible: active
+
+            sourceComponent: ChantingKaraokeText {
+                section_uid: root.section_uid
+                recording_uid: root.recording_uid
+                playback_position_ms: audio.position_ms
+
+                onSeek_requested: function(position_ms) {
+                    root.stop_range_playback();
+                    audio.seek(position_ms);
+                    position_save_timer.restart();
+                }
+            }
+        }
+
         // Marker list (8.6, 8.7, 8.8, 8.10, 8.11)
         ColumnLayout {
             id: marker_list_column
@@ -0,0 +1,327 @@
+pragma ComponentBehavior: Bound
+
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls
+
+import com.profoundlabs.simsapa
+
+// Saved searches: save the last search with its area, mode and filters, run a
+// saved search again, and list the results which are new since the earlier
+// runs, e.g. after a language pack, a dictionary or a book was imported.
+// See docs/saved-searches.md.
+Dialog {
+    id: root
+    title: "Saved Searches"
+    modal: true
+    standardButtons: Dialog.Close
+
+    property int pointSize: 10
+    // The last search of the window, which can be saved
+    property string current_query_text: ""
+    property string current_search_area: ""
+    property var current_params: null
+
+    property var saved_searches: []
+    property int selected_id: -1
+    property string selected_name: ""
+    property var selected_hits: []
+    property int rename_id: -1
+
+    signal run_search(string query_text, string search_area, string params_json)
+    signal open_item(string item_uid, string table_name, string title)
+
+    onOpened: {
+        name_input.text = root.current_query_text;
+        status_label.text = "";
+        root.reload();
+    }
+
+    function reload() {
+        try {
+            root.saved_searches = JSON.parse(SuttaBridge.get_saved_searches_json());
+        } catch (e) {
+            root.saved_searches = [];
+        }
+        if (root.selected_id >= 0) {
+            root.show_new_hits(root.selected_id, root.selected_name);
+        }
+    }
+
+    function show_new_hits(saved_search_id: int, name: string) {
+        root.selected_id = saved_search_id;
+        root.selected_name = name;
+        try {
+            root.selected_hits = JSON.parse(SuttaBridge.get_saved_search_hits_json(saved_search_id, true));
+        } catch (e) {
+            root.selected_hits = [];
+        }
+    }
+
+    function search_status(s): string {
+        let text = `${s.search_area} · ${s.search_mode}`;
+        if (s.last_run_at) {
+            text += ` · ${s.last_hits_count} hits, run ${s.last_run_at.substring(0, 10)}`;
+        }
+        return text;
+    }
+
+    Connections {
+        target: SuttaBridge
+        function onSavedSearchesRunReady(report_json: string) {
+            let report;
+            try {
+                report = JSON.parse(report_json);
+            } catch (e) {
+                return;
+            }
+            if (report.runs.length === 1 && report.reasons.length === 0) {
+                const run = report.runs[0];
+                status_label.text = `${run.saved_search.name}: ${run.hits_count} hits, ${run.new_hits.length} new`;
+            }
+            root.reload();
+        }
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        spacing: 10
+
+        // Save the last search of the window
+        RowLayout {
+            Layout.fillWidth: true
+            spacing: 5
+
+            TextField {
+                id: name_input
+                Layout.fillWidth: true
+                placeholderText: "Name of the search"
+                enabled: root.current_query_text.length > 0
+            }
+
+            Button {
+                text: "Save Current Search"
+                enabled: root.current_query_text.length > 0 && name_input.text.trim().length > 0
+                onClicked: {
+                    const result_str = SuttaBridge.save_search(name_input.text.trim(),
+                                                               root.current_query_text,
+                                                               root.current_search_area,
+                                                               JSON.stringify(root.current_params || {}));
+                    let result;
+                    try {
+                        result = JSON.parse(result_str);
+                    } catch (e) {
+                        result = { error: "Failed to parse result" };
+                    }
+                    status_label.text = result.ok ? `Saved: ${name_input.text.trim()}` : "Error: " + (result.error || "Unknown error");
+                    root.reload();
+                }
+            }
+        }
+
+        Label {
+            visible: root.current_query_text.length === 0
+            text: "Run a search to save it."
+            font.pointSize: root.pointSize - 1
+            color: palette.mid
+        }
+
+        Label {
+            id: status_label
+            Layout.fillWidth: true
+            visible: text.length > 0
+            wrapMode: Text.WordWrap
+            font.pointSize: root.pointSize - 1
+        }
+
+        Label {
+            visible: root.saved_searches.length === 0
+            text: "No saved searches."
+            font.pointSize: root.pointSize
+        }
+
+        ScrollView {
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            Layout.preferredHeight: 250
+            clip: true
+
+            ListView {
+                model: root.saved_searches
+                spacing: 8
+                delegate: ColumnLayout {
+                    id: search_row
+                    required property var modelData
+                    width: ListView.view.width
+                    spacing: 2
+
+                    RowLayout {
+                        Layout.fillWidth: true
+
+                        Label {
+                            visible: root.rename_id !== search_row.modelData.id
+                            text: search_row.modelData.name
+                            font.bold: true
+                            font.pointSize: root.pointSize
+                            elide: Text.ElideRight
+                            Layout.fillWidth: true
+                        }
+
+                        TextField {
+                            id: rename_input
+                            visible: root.rename_id === search_row.modelData.id
+                            text: search_row.modelData.name
+                            Layout.fillWidth: true
+                            onAccepted: {
+                                SuttaBridge.rename_saved_search(search_row.modelData.id, rename_input.text);
+                                root.rename_id = -1;
+                                root.reload();
+                            }
+                        }
+
+                        Label {
+                            visible: search_row.modelData.new_hits_count > 0
+                            text: `${search_row.modelData.new_hits_count} new`
+                            font.bold: true
+                            font.pointSize: root.pointSize - 1
+                            color: palette.highlight
+                        }
+                    }
+
+                    Label {
+                        text: `"${search_row.modelData.query_text}" · ${root.search_status(search_row.modelData)}`
+                        font.pointSize: root.pointSize - 1
+                        color: palette.mid
+                        elide: Text.ElideRight
+                        Layout.fillWidth: true
+                    }
+
+                    RowLayout {
+                        spacing: 5
+
+                        Button {
+                            text: "Run"
+                            onClicked: {
+                                SuttaBridge.mark_saved_search_seen(search_row.modelData.id);
+                                root.run_search(search_row.modelData.query_text,
+                                                search_row.modelData.search_area,
+                                                search_row.modelData.params_json);
+                            }
+                        }
+
+                        Button {
+                            visible: search_row.modelData.new_hits_count > 0
+                            text: "Show New"
+                            onClicked: root.show_new_hits(search_row.modelData.id, search_row.modelData.name)
+                        }
+
+                        Button {
+                            text: "Check Now"
+                            onClicked: {
+                                status_label.text = `Running: ${search_row.modelData.name}...`;
+                                SuttaBridge.rerun_saved_search(search_row.modelData.id);
+                            }
+                        }
+
+                        Button {
+                            text: root.rename_id === search_row.modelData.id ? "Save Name" : "Rename"
+                            onClicked: {
+                                if (root.rename_id === search_row.modelData.id) {
+                                    SuttaBridge.rename_saved_search(search_row.modelData.id, rename_input.text);
+                                    root.rename_id = -1;
+                                    root.reload();
+                                } else {
+                                    root.rename_id = search_row.modelData.id;
+                                }
+                            }
+                        }
+
+                        Button {
+                            text: "Delete"
+                            onClicked: {
+                                if (root.selected_id === search_row.modelData.id) {
+                                    root.selected_id = -1;
+                                    root.selected_hits = [];
+                                }
+                                SuttaBridge.delete_saved_search(search_row.modelData.id);
+                                root.reload();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // The new results of the selected search
+        ColumnLayout {
+            visible: root.selected_id >= 0
+            Layout.fillWidth: true
+            spacing: 5
+
+            RowLayout {
+                Layout.fillWidth: true
+
+                Label {
+                    Layout.fillWidth: true
+                    text: `New results: ${root.selected_name}`
+                    font.bold: true
+                    font.pointSize: root.pointSize + 1
+                    elide: Text.ElideRight
+                }
+
+                Button {
+                    text: "Mark Seen"
+                    enabled: root.selected_hits.length > 0
+                    onClicked: {
+                        SuttaBridge.mark_saved_search_seen(root.selected_id);
+                        root.reload();
+                    }
+                }
+
+                Button {
+                    text: "Hide"
+                    onClicked: {
+                        root.selected_id = -1;
+                        root.selected_hits = [];
+                    }
+                }
+            }
+
+            Label {
+                visible: root.selected_hits.length === 0
+                text: "No new results."
+                font.pointSize: root.pointSize
+            }
+
+            ScrollView {
+                visible: root.selected_hits.length > 0
+                Layout.fillWidth: true
+                Layout.preferredHeight: 150
+                clip: true
+
+                ListView {
+                    model: root.selected_hits
+                    delegate: RowLayout {
+                        id: hit_row
+                        required property var modelData
+                        width: ListView.view.width
+                        spacing: 5
+
+                        Label {
+                            Layout.fillWidth: true
+                            text: (hit_row.modelData.title || hit_row.modelData.uid)
+                                  + (hit_row.modelData.title ? ` · ${hit_row.modelData.uid}` : "")
+                            font.pointSize: root.pointSize
+                            elide: Text.ElideRight
+                        }
+
+                        Button {
+                            text: "Open"
+                            onClicked: root.open_item(hit_row.modelData.uid, hit_row.modelData.table_name, hit_row.modelData.title || "")
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -107,6 +107,80 @@ Frame {
     function user_typed() {
         // TODO self._show_search_normal_icon()
         if (root.search_as_you_type_checked) root.search_timer.restart();
+        if (root.suggestions_enabled && !root.suppress_suggestions && search_input.activeFocus) {
+            suggestions_timer.restart();
+        }
+    }
+
+    // === Query suggestions ===
+    // From the search history, the sutta titles and the dictionary words.
+    // Desktop only, a popup over the mobile webview would be hidden.
+    // See docs/search-history.md.
+    readonly property bool suggestions_enabled: root.is_desktop
+    property bool suppress_suggestions: false
+    property var suggestions: []
+
+    function close_suggestions() {
+        suggestions_timer.stop();
+        suggestions_popup.close();
+    }
+
+    function accept_suggestion(index: int) {
+        const suggestion = root.suggestions[index];
+        root.close_suggestions();
+        if (!suggestion) {
+            return;
+        }
+        root.suppress_suggestions = true;
+        search_input.text = suggestion.text;
+        root.suppress_suggestions = false;
+        root.handle_query_fn(suggestion.text, 1); // qmllint disable use-proper-function
+    }
+
+    function suggestion_source_label(suggestion): string {
+        switch (suggestion.source) {
+        case "history": return `${suggestion.hits_count} hits`;
+        case "sutta_title": return "title";
+        case "dict_word": return "DPD";
+        default: return "";
+        }
+    }
+
+    Timer {
+        id: suggestions_timer
+        interval: 250
+        repeat: false
+        onTriggered: {
+            const query = search_input.text.trim();
+            if (query.length < 2 || !search_input.activeFocus) {
+                suggestions_popup.close();
+                return;
+            }
+            SuttaBridge.get_query_suggestions(query, root.search_area, 8);
+        }
+    }
+
+    Connections {
+        target: SuttaBridge
+        function onQuerySuggestionsReady(query_text: string, suggestions_json: string) {
+            // A stale list, or for the search input of another window
+            if (query_text !== search_input.text.trim() || !search_input.activeFocus) {
+                return;
+            }
+            let list = [];
+            try {
+                list = JSON.parse(suggestions_json);
+            } catch (e) {
+                list = [];
+            }
+            root.suggestions = list;
+            suggestions_list.currentIndex = -1;
+            if (list.length > 0) {
+                suggestions_popup.open();
+            } else {
+                suggestions_popup.close();
+            }
+        }
     }
 
     Flow {
@@ -165,16 +239,96 @@ Frame {
                     return "Search in suttas";
                 }
 
-                onAccepted: search_btn.clicked()
+                onAccepted: {
+                    if (suggestions_popup.opened && suggestions_list.currentIndex >= 0) {
+                        root.accept_suggestion(suggestions_list.currentIndex);
+                        return;
+                    }
+                    root.close_suggestions();
+                    search_btn.clicked();
+                }
                 onTextChanged: root.user_typed()
                 selectByMouse: true
 
+                Keys.onDownPressed: (event) => {
+                    if (suggestions_popup.opened) {
+                        suggestions_list.incrementCurrentIndex();
+                        event.accepted = true;
+                    } else {
+                        event.accepted = false;
+                    }
+                }
+                Keys.onUpPressed: (event) => {
+                    if (suggestions_popup.opened) {
+                        suggestions_list.decrementCurrentIndex();
+                        event.accepted = true;
+                    } else {
+                        event.accepted = false;
+                    }
+                }
+                Keys.onEscapePressed: (event) => {
+                    if (suggestions_popup.opened) {
+                        root.close_suggestions();
+                        event.accepted = true;
+                    } else {
+                        event.accepted = false;
+                    }
+                }
+
                 // Keyboard diagnostics: report focus transitions so we can see
                 // whether tapping the field actually moves active focus to it
                 // (the precondition for the IME to be raised).
-                onActiveFocusChanged: logger.debug("search_input: activeFocus="
-                    + search_input.activeFocus + " inputMethod.visible="
-                    + Qt.inputMethod.visible)
+                onActiveFocusChanged: {
+                    logger.debug("search_input: activeFocus="
+                        + search_input.activeFocus + " inputMethod.visible="
+                        + Qt.inputMethod.visible);
+                    if (!search_input.activeFocus) {
+                        root.close_suggestions();
+                    }
+                }
+
+                Popup {
+                    id: suggestions_popup
+                    y: search_input.height
+                    width: search_input.width
+                    padding: 0
+                    // Typing stays in the search input
+                    focus: false
+                    closePolicy: Popup.CloseOnEscape | Popup.CloseOnPressOutsideParent
+
+                    contentItem: ListView {
+                        id: suggestions_list
+                        implicitHeight: contentHeight
+                        model: root.suggestions
+                        currentIndex: -1
+                        clip: true
+
+                        delegate: ItemDelegate {
+                            id: suggestion_item
+                            required property var modelData
+                            required property int index
+                            width: ListView.view.width
+                            focusPolicy: Qt.NoFocus
+                            highlighted: ListView.isCurrentItem
+                            onClicked: root.accept_suggestion(suggestion_item.index)
+
+                            contentItem: RowLayout {
+                                Label {
+                                    text: suggestion_item.modelData.text
+                                    font.pointSize: 11
+                                    font.italic: suggestion_item.modelData.source === "history"
+                                    elide: Text.ElideRight
+                                    Layout.fillWidth: true
+                                }
+                                Label {
+                                    text: root.suggestion_source_label(suggestion_item.modelData)
+                                    font.pointSize: 9
+                                    color: palette.mid
+                                }
+                            }
+                        }
+                    }
+                }
 
                 // Reliably raise the Android/ChromeOS soft keyboard on the
                 // first tap. See docs/android-soft-keyboard.md.
@@ -0,0 +1,182 @@
+pragma ComponentBehavior: Bound
+
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls
+
+import com.profoundlabs.simsapa
+
+// Search history: the recent searches with their area, mode, hits and the
+// result opened from them, the most repeated searches and the searches
+// without results. Recording can be turned off, and the history cleared.
+// See docs/search-history.md.
+Dialog {
+    id: root
+    title: "Search History"
+    modal: true
+    standardButtons: Dialog.Close
+
+    property int pointSize: 10
+    property var recent_searches: []
+    property var stats: null
+
+    signal run_search(string query_text, string search_area, string params_json)
+    signal open_item(string item_uid, string table_name, string title)
+
+    onOpened: {
+        history_enabled_checkbox.checked = SuttaBridge.get_search_history_enabled();
+        root.reload();
+    }
+
+    function reload() {
+        try {
+            root.recent_searches = JSON.parse(SuttaBridge.get_search_history_json(200));
+            root.stats = JSON.parse(SuttaBridge.get_search_history_stats_json());
+        } catch (e) {
+            root.recent_searches = [];
+            root.stats = null;
+        }
+    }
+
+    function stats_text(): string {
+        const s = root.stats;
+        if (!s || s.searches_count === undefined || s.queries_count === 0) {
+            return "";
+        }
+        const areas = s.area_counts.map(a => `${a[0]}: ${a[1]}`).join(", ");
+        return `${s.searches_count} searches of ${s.queries_count} queries (${areas}), ${s.zero_hits_count} without results`;
+    }
+
+    function current_list(): var {
+        if (!root.stats || root.stats.top_searches === undefined) {
+            return tabs.currentIndex === 0 ? root.recent_searches : [];
+        }
+        switch (tabs.currentIndex) {
+        case 1: return root.stats.top_searches;
+        case 2: return root.stats.zero_hits_searches;
+        default: return root.recent_searches;
+        }
+    }
+
+    ColumnLayout {
+        anchors.fill: parent
+        spacing: 10
+
+        RowLayout {
+            Layout.fillWidth: true
+
+            CheckBox {
+                id: history_enabled_checkbox
+                text: "Record search history"
+                font.pointSize: root.pointSize
+                onToggled: SuttaBridge.set_search_history_enabled(checked)
+            }
+
+            Item { Layout.fillWidth: true }
+
+            Button {
+                text: "Clear History"
+                enabled: root.recent_searches.length > 0
+                onClicked: {
+                    SuttaBridge.clear_search_history();
+                    root.reload();
+                }
+            }
+        }
+
+        Label {
+            Layout.fillWidth: true
+            visible: text.length > 0
+            text: root.stats_text()
+            wrapMode: Text.WordWrap
+            font.pointSize: root.pointSize - 1
+            color: palette.mid
+        }
+
+        TabBar {
+            id: tabs
+            Layout.fillWidth: true
+            TabButton { text: "Recent" }
+            TabButton { text: "Most Searched" }
+            TabButton { text: "No Results" }
+        }
+
+        Label {
+            visible: root.current_list().length === 0
+            text: history_enabled_checkbox.checked ? "No searches." : "The search history is not recorded."
+            font.pointSize: root.pointSize
+        }
+
+        ScrollView {
+            Layout.fillWidth: true
+            Layout.fillHeight: true
+            Layout.preferredHeight: 300
+            clip: true
+
+            ListView {
+                model: root.current_list()
+                spacing: 8
+                delegate: ColumnLayout {
+                    id: history_row
+                    required property var modelData
+                    width: ListView.view.width
+                    spacing: 2
+
+                    Label {
+                        text: history_row.modelData.query_text
+                        font.bold: true
+                        font.pointSize: root.pointSize
+                        elide: Text.ElideRight
+                        Layout.fillWidth: true
+                    }
+
+                    Label {
+                        text: `${history_row.modelData.search_area} · ${history_row.modelData.search_mode}`
+                              + ` · ${history_row.modelData.hits_count} hits`
+                              + (history_row.modelData.search_count > 1 ? ` · ${history_row.modelData.search_count} times` : "")
+                              + ` · ${history_row.modelData.last_searched_at.substring(0, 10)}`
+                        font.pointSize: root.pointSize - 1
+                        color: palette.mid
+                        elide: Text.ElideRight
+                        Layout.fillWidth: true
+                    }
+
+                    Label {
+                        visible: !!history_row.modelData.opened_uid
+                        text: "Opened: " + (history_row.modelData.opened_title || history_row.modelData.opened_uid || "")
+                        font.pointSize: root.pointSize - 1
+                        elide: Text.ElideRight
+                        Layout.fillWidth: true
+                    }
+
+                    RowLayout {
+                        spacing: 5
+
+                        Button {
+                            text: "Run"
+                            onClicked: root.run_search(history_row.modelData.query_text,
+                                                       history_row.modelData.search_area,
+                                                       history_row.modelData.params_json)
+                        }
+
+                        Button {
+                            visible: !!history_row.modelData.opened_uid
+                            text: "Open Result"
+                            onClicked: root.open_item(history_row.modelData.opened_uid,
+                                                      history_row.modelData.opened_table_name || "suttas",
+                                                      history_row.modelData.opened_title || "")
+                        }
+
+                        Button {
+                            text: "Delete"
+                            onClicked: {
+                                SuttaBridge.delete_search_history_item(history_row.modelData.id);
+                                root.reload();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
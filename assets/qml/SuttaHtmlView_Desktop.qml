@@ -167,7 +167,13 @@ Item {
             const book_uid = SuttaBridge.get_book_uid_for_spine_item(spine_item_uid);
             const enc_book_uid = book_uid.split("/").map(encodeURIComponent).join("/");
             const pdf_url = `${api_url}/book_resources/${enc_book_uid}/document.pdf`;
-            web.url = `${api_url}/assets/pdf-viewer/web/viewer.html?file=${encodeURIComponent(pdf_url)}`;
+            // Open at the page of the spine item, or of a #page=N anchor
+            let page = SuttaBridge.get_pdf_page_for_spine_item(spine_item_uid);
+            if (root.anchor && root.anchor.replace(/^#/, "").startsWith("page=")) {
+                page = parseInt(root.anchor.replace(/^#/, "").substring(5)) || page;
+            }
+            const page_fragment = page > 0 ? `#page=${page}` : "";
+            web.url = `${api_url}/assets/pdf-viewer/web/viewer.html?file=${encodeURIComponent(pdf_url)}${page_fragment}`;
         } else {
             // Regular book content
             // Append anchor to URL for native browser scrolling (works on all platforms)
@@ -83,7 +83,7 @@ ApplicationWindow {
     // Gate the webview on the DB being ready so that the "Loading..." icon and
     // message are shown unobscured while the database is loading.
     property bool db_ready: SuttaBridge.db_loaded && SuttaBridge.searcher_ready
-    property bool webview_visible: root.db_ready && (root.is_desktop || (!mobile_menu.visible && !about_dialog.visible && !models_dialog.visible && !anki_export_dialog.visible && !gloss_tab.commonWordsDialog.visible && !tab_list_dialog.visible && !database_validation_dialog.visible && !app_settings_window.visible && !info_dialog.visible && !related_sutta_not_found_dialog.visible))
+    property bool webview_visible: root.db_ready && (root.is_desktop || (!mobile_menu.visible && !about_dialog.visible && !models_dialog.visible && !anki_export_dialog.visible && !gloss_tab.commonWordsDialog.visible && !tab_list_dialog.visible && !database_validation_dialog.visible && !app_settings_window.visible && !info_dialog.visible && !related_sutta_not_found_dialog.visible && !reading_plans_dialog.visible && !saved_searches_dialog.visible && !search_history_dialog.visible && !sync_dialog.visible))
 
     // Collapsible advanced sub-sections
     property bool is_filters_collapsed: false
@@ -97,7 +97,15 @@ ApplicationWindow {
     // 6.0) bind to these. See docs/search-snippet-highlight-pipeline.md.
     property bool show_all_snippets: false
     property string snippet_exclude_text: ""
+    // Session-only fuzzy distance for Fulltext and Headword Match (0 = off):
+    // Pāli phonetic and edit-distance matching. See docs/fuzzy-phonetic-search.md.
+    property int fuzzy_distance: 0
     property var last_params: null
+    // Search history: whether the running search is recorded when its results
+    // arrive (explicit searches), its history item id, and its hits.
+    property bool record_search_pending: false
+    property int last_search_history_id: -1
+    property int last_hits_count: 0
     property string pending_find_query: ""
     property real pending_bookmark_scroll: 0.0
 
@@ -245,6 +253,14 @@ ApplicationWindow {
             }
 
             fulltext_results.set_search_result_page(d);
+
+            if (d.page_num === 0) {
+                root.last_hits_count = d.total_hits;
+                if (root.record_search_pending) {
+                    root.record_search_pending = false;
+                    root.record_search_history();
+                }
+            }
         }
 
         function onDebugQueryReady(debug_json: string) {
@@ -273,6 +289,24 @@ ApplicationWindow {
                 root.show_result_in_html_view_with_json(result_data_json);
             }
         }
+
+        function onDocumentImportCompleted(success: bool, message: string) {
+            if (success) {
+                SuttaBridge.run_saved_searches_if_requested();
+            }
+        }
+
+        function onSavedSearchesRunReady(report_json: string) {
+            root.show_new_saved_search_results(report_json);
+        }
+    }
+
+    // New content imported before a restart (language packs, dictionaries) is
+    // searchable once the database and the searcher are ready.
+    onDb_readyChanged: {
+        if (root.db_ready) {
+            SuttaBridge.run_saved_searches_if_requested();
+        }
     }
 
     function update_window_title(item_uid: string, sutta_ref: string, sutta_title: string) {
@@ -457,6 +491,71 @@ ApplicationWindow {
         }
     }
 
+    // Records the reading state of the current sutta or book chapter while
+    // the window is active: the scroll position, the furthest point read and
+    // the reading time. See docs/reading-progress.md.
+    Timer {
+        id: reading_progress_timer
+        interval: 30000
+        repeat: true
+        running: root.active && root.db_ready
+        onTriggered: root.record_reading_progress(interval / 1000)
+    }
+
+    function current_reading_item() {
+        const current_key = sutta_html_view_layout.current_key;
+        if (!current_key || !sutta_html_view_layout.items_map[current_key]) return null;
+        const item = sutta_html_view_layout.items_map[current_key];
+        const item_uid = item.get_data_value('item_uid');
+        const table_name = item.get_data_value('table_name');
+        if (!item_uid || (table_name !== "suttas" && table_name !== "book_spine_items")) return null;
+        return { item_uid: item_uid, table_name: table_name };
+    }
+
+    function record_reading_progress(seconds: int) {
+        const reading_item = root.current_reading_item();
+        if (!reading_item) return;
+        let html_view = sutta_html_view_layout.get_current_item();
+        if (!html_view || !html_view.item || !html_view.item.web) return;
+        let js = `(function() {
+            const h = document.documentElement.scrollHeight || 1;
+            return JSON.stringify([window.scrollY / h, (window.scrollY + window.innerHeight) / h]);
+        })()`;
+        html_view.item.web.runJavaScript(js, function(result) {
+            if (!result) return;
+            const pos = JSON.parse(result);
+            SuttaBridge.record_reading_progress(reading_item.item_uid, reading_item.table_name, pos[0], pos[1], seconds);
+        });
+    }
+
+    function toggle_reading_finished() {
+        const reading_item = root.current_reading_item();
+        if (!reading_item) {
+            sutta_html_view_layout.show_transient_message("Not a sutta or book chapter tab");
+            return;
+        }
+        const state_json = SuttaBridge.get_reading_state_json(reading_item.item_uid, reading_item.table_name);
+        const finished = state_json.length > 0 ? !JSON.parse(state_json).is_finished : true;
+        SuttaBridge.set_reading_finished(reading_item.item_uid, reading_item.table_name, finished);
+        sutta_html_view_layout.show_transient_message(finished ? "Marked as read" : "Marked as unread");
+    }
+
+    // Scroll a newly loaded sutta or chapter to where it was last read, unless
+    // it was read to the end.
+    function restore_reading_position() {
+        const reading_item = root.current_reading_item();
+        if (!reading_item) return;
+        const state_json = SuttaBridge.get_reading_state_json(reading_item.item_uid, reading_item.table_name);
+        if (state_json.length === 0) return;
+        const state = JSON.parse(state_json);
+        if (state.is_finished || state.scroll_position <= 0.0) return;
+        let html_view = sutta_html_view_layout.get_current_item();
+        if (html_view && html_view.item && html_view.item.web) {
+            let js = `setTimeout(function() { if (window.scrollY === 0 && !window.location.hash) { window.scrollTo(0, ${state.scroll_position} * document.documentElement.scrollHeight); } }, 200);`;
+            html_view.item.web.runJavaScript(js);
+        }
+    }
+
     // Timer for debug query debounce
     Timer {
         id: debug_query_timer
@@ -485,6 +584,8 @@ ApplicationWindow {
     }
 
     function handle_query(query_text_orig: string, min_length=4) {
+        const is_explicit_search = min_length <= 1;
+
         // Gate on db_loaded && searcher_ready: search button is already
         // disabled until db_loaded, but keyboard shortcuts, drawer items,
         // and programmatic triggers (e.g. search-as-you-type, results-tab
@@ -551,6 +652,11 @@ ApplicationWindow {
             /* partial(self._search_query_finished), */
             params,
         )
+
+        // The search button and Enter search with min_length 1, search as you
+        // type with the default. Only the explicit searches are recorded in
+        // the history right away.
+        root.record_search_pending = is_explicit_search;
     }
 
     function start_search_query_workers(
@@ -561,6 +667,9 @@ ApplicationWindow {
         root.last_query_text = query_text;
         root.last_search_area = search_area;
         root.last_params = params;
+        root.record_search_pending = false;
+        root.last_search_history_id = -1;
+        root.last_hits_count = 0;
 
         // FIXME: page number
         root.results_page(query_text, 0, search_area, params);
@@ -640,7 +749,7 @@ ApplicationWindow {
             source: null,
             source_include: true,
             enable_regex: false,
-            fuzzy_distance: 0,
+            fuzzy_distance: root.fuzzy_distance,
             include_cst_mula: SuttaBridge.get_include_cst_mula_in_search_results(),
             include_cst_commentary: SuttaBridge.get_include_cst_commentary_in_search_results(),
             nikaya_prefix: nikaya_prefix.length > 0 ? nikaya_prefix : null,
@@ -843,6 +952,123 @@ ApplicationWindow {
         }
     }
 
+    // Open the library chapters citing the sutta of the current tab as results tabs.
+    function open_citing_chapters() {
+        const current_key = sutta_html_view_layout.current_key;
+        if (!current_key || !sutta_html_view_layout.items_map[current_key]) {
+            sutta_html_view_layout.show_transient_message("No sutta currently loaded");
+            return;
+        }
+
+        const item_uid = sutta_html_view_layout.items_map[current_key].get_data_value('item_uid');
+        const table_name = sutta_html_view_layout.items_map[current_key].get_data_value('table_name');
+
+        if (!item_uid || table_name !== "suttas") {
+            sutta_html_view_layout.show_transient_message("Not a sutta tab");
+            return;
+        }
+
+        const chapters = JSON.parse(SuttaBridge.get_chapters_citing_sutta_json(item_uid));
+        if (chapters.length === 0) {
+            sutta_html_view_layout.show_transient_message("Not cited in the library");
+            return;
+        }
+
+        let first_id_key = null;
+        for (let i = 0; i < chapters.length; i++) {
+            const ch = chapters[i];
+            let tab_data = root.new_tab_data({
+                item_uid: ch.spine_item_uid,
+                table_name: "book_spine_items",
+                sutta_title: ch.chapter_title || ch.book_title,
+                sutta_ref: ch.book_title,
+                find_query: ch.ref_text,
+            });
+            tabs_results_model.append(tab_data);
+            if (first_id_key === null) {
+                first_id_key = tab_data.id_key;
+            }
+        }
+        root.focus_on_tab_with_id_key(first_id_key);
+        sutta_html_view_layout.show_transient_message(`Cited in ${chapters.length} library chapter(s)`);
+    }
+
+    // Open an item of a reading plan or a saved search result as a results tab.
+    function open_item_in_tab(item_uid: string, table_name: string, title: string) {
+        let tab_data = root.new_tab_data({
+            item_uid: item_uid,
+            table_name: table_name,
+            sutta_title: title,
+            sutta_ref: "",
+        });
+        tabs_results_model.append(tab_data);
+        root.focus_on_tab_with_id_key(tab_data.id_key);
+    }
+
+    // Run a saved search or a search from the history with its own search
+    // area, mode and filters, showing them in the search bar.
+    function run_search_with_params(query_text: string, search_area: string, params_json: string) {
+        let params;
+        try {
+            params = JSON.parse(params_json);
+        } catch (e) {
+            logger.error("Invalid saved search params: " + e);
+            return;
+        }
+
+        if (!show_sidebar_btn.checked) {
+            show_sidebar_btn.checked = true;
+        }
+        rightside_tabs.setCurrentIndex(0);
+
+        search_bar_input.set_search_area(search_area);
+        const dropdown = search_bar_input.search_mode_dropdown;
+        const mode_idx = (dropdown.search_mode_label_wide[search_area] || []).indexOf(params.mode);
+        if (mode_idx >= 0) {
+            dropdown.currentIndex = mode_idx;
+        }
+        search_bar_input.search_input.text = query_text;
+
+        root.start_search_query_workers(query_text, search_area, params);
+        root.record_search_pending = true;
+    }
+
+    function record_search_history() {
+        root.last_search_history_id = SuttaBridge.record_search_history(
+            root.last_query_text, root.last_search_area, JSON.stringify(root.last_params), root.last_hits_count);
+    }
+
+    // Remember the result opened from the last search. A search run while
+    // typing is recorded when one of its results is opened.
+    function record_search_result_opened(result_data: var) {
+        if (!result_data || result_data.is_section_header || root.last_query_text.length === 0) {
+            return;
+        }
+        if (root.last_search_history_id < 0) {
+            root.record_search_pending = false;
+            root.record_search_history();
+        }
+        SuttaBridge.set_search_history_opened(root.last_search_history_id,
+                                              result_data.item_uid,
+                                              result_data.table_name,
+                                              result_data.sutta_title || "");
+    }
+
+    // Report the saved searches with new results after new content was imported.
+    function show_new_saved_search_results(report_json: string) {
+        let report;
+        try {
+            report = JSON.parse(report_json);
+        } catch (e) {
+            return;
+        }
+        if (report.reasons.length === 0 || report.runs.length === 0) {
+            return;
+        }
+        const names = report.runs.map(run => `${run.saved_search.name} (${run.new_hits.length})`);
+        sutta_html_view_layout.show_transient_message(`New results for saved searches: ${names.join(", ")}`);
+    }
+
     function run_sutta_menu_action(action: string, query_text: string) {
         /* logger.info("run_sutta_menu_action():", action, query_text.slice(0, 30)); */
 
@@ -935,6 +1161,14 @@ ${query_text}`;
             root.open_related_sutta("mula");
             break;
 
+        case "open-citing-chapters":
+            root.open_citing_chapters();
+            break;
+
+        case "toggle-finished":
+            root.toggle_reading_finished();
+            break;
+
         case "copy-link-sutta":
         case "copy-sutta-url":
             let msg = `TODO: ${action}`;
@@ -1302,6 +1536,12 @@ ${query_text}`;
         search_bar_input.search_input.text = text;
     }
 
+    // Run a "did you mean" query in the current search area and mode.
+    function run_suggestion_query(query_text: string) {
+        search_bar_input.search_input.text = query_text;
+        root.handle_query(query_text, 1);
+    }
+
     function run_lookup_query(query_text: string) {
         // Set search area to Dictionary
         search_bar_input.set_search_area("Dictionary");
@@ -1666,6 +1906,36 @@ ${query_text}`;
                 }
             }
 
+            CMenuItem {
+                action: Action {
+                    id: action_search_history
+                    text: "Search &History..."
+                    onTriggered: {
+                        search_history_dialog.open()
+                    }
+                }
+            }
+
+            CMenuItem {
+                action: Action {
+                    id: action_saved_searches
+                    text: "Sa&ved Searches..."
+                    onTriggered: {
+                        saved_searches_dialog.open()
+                    }
+                }
+            }
+
+            CMenuItem {
+                action: Action {
+                    id: action_reading_plans
+                    text: "Reading &Plans..."
+                    onTriggered: {
+                        reading_plans_dialog.open()
+                    }
+                }
+            }
+
             CMenuItem {
                 action: Action {
                     id: action_chanting_practice
@@ -1675,6 +1945,16 @@ ${query_text}`;
                     }
                 }
             }
+
+            CMenuItem {
+                action: Action {
+                    id: action_sync_devices
+                    text: "S&ync Devices..."
+                    onTriggered: {
+                        sync_dialog.open()
+                    }
+                }
+            }
         }
 
         Menu {
@@ -2180,6 +2460,66 @@ ${query_text}`;
         }
     }
 
+    ReadingPlansDialog {
+        id: reading_plans_dialog
+        anchors.centerIn: parent
+        width: Math.min(root.width - 40, 700)
+        height: Math.min(root.height - 40, 700)
+        pointSize: root.is_mobile ? 12 : 10
+
+        onOpen_item: (item_uid, table_name, title) => {
+            reading_plans_dialog.close();
+            root.open_item_in_tab(item_uid, table_name, title);
+        }
+    }
+
+    SavedSearchesDialog {
+        id: saved_searches_dialog
+        anchors.centerIn: parent
+        width: Math.min(root.width - 40, 700)
+        height: Math.min(root.height - 40, 700)
+        pointSize: root.is_mobile ? 12 : 10
+        current_query_text: root.last_query_text
+        current_search_area: root.last_search_area
+        current_params: root.last_params
+
+        onRun_search: (query_text, search_area, params_json) => {
+            saved_searches_dialog.close();
+            root.run_search_with_params(query_text, search_area, params_json);
+        }
+
+        onOpen_item: (item_uid, table_name, title) => {
+            saved_searches_dialog.close();
+            root.open_item_in_tab(item_uid, table_name, title);
+        }
+    }
+
+    SearchHistoryDialog {
+        id: search_history_dialog
+        anchors.centerIn: parent
+        width: Math.min(root.width - 40, 700)
+        height: Math.min(root.height - 40, 700)
+        pointSize: root.is_mobile ? 12 : 10
+
+        onRun_search: (query_text, search_area, params_json) => {
+            search_history_dialog.close();
+            root.run_search_with_params(query_text, search_area, params_json);
+        }
+
+        onOpen_item: (item_uid, table_name, title) => {
+            search_history_dialog.close();
+            root.open_item_in_tab(item_uid, table_name, title);
+        }
+    }
+
+    SyncDialog {
+        id: sync_dialog
+        anchors.centerIn: parent
+        width: Math.min(root.width - 40, 700)
+        height: Math.min(root.height - 40, 700)
+        pointSize: root.is_mobile ? 12 : 10
+    }
+
     Dialog {
         id: related_sutta_not_found_dialog
         title: "Related Text Not Found"
@@ -2494,6 +2834,46 @@ ${query_text}`;
                 }
             }
 
+            // Fuzzy distance for Fulltext Match in the Suttas and Dictionary
+            // indexes, and for Headword Match on the DPD headwords
+            // (session-only, root.fuzzy_distance). See
+            // docs/fuzzy-phonetic-search.md.
+            RowLayout {
+                spacing: 4
+                visible: search_bar_input.search_area === "Suttas" || search_bar_input.search_area === "Dictionary"
+
+                Label {
+                    text: "Fuzzy distance:"
+                    font.pointSize: root.is_mobile ? 12 : 10
+                    Layout.alignment: Qt.AlignVCenter
+                }
+
+                SpinBox {
+                    id: fuzzy_distance_spinbox
+                    from: 0
+                    to: 2
+                    value: root.fuzzy_distance
+                    Layout.preferredHeight: root.icon_size
+                    font.pointSize: root.is_mobile ? 12 : 10
+                    onValueModified: {
+                        root.fuzzy_distance = value;
+                        root.advanced_options_changed();
+                    }
+                }
+
+                Button {
+                    icon.source: "icons/32x32/fa_circle-info-solid.png"
+                    flat: true
+                    implicitWidth: root.icon_size
+                    implicitHeight: root.icon_size
+                    onClicked: {
+                        info_dialog.title = "Fuzzy distance";
+                        info_dialog.message = "For Fulltext Match and Headword Match: also find Pāli words typed without diacritics, doubled consonants or aspirates (satipatana, sati patthana → satipaṭṭhāna), and words within this many typos. Closer matches are listed first. 0 turns it off.";
+                        info_dialog.open();
+                    }
+                }
+            }
+
             // "Show All Snippets" + snippet exclusion filter (Suttas + Library
             // only; session-only state held on root.show_all_snippets /
             // root.snippet_exclude_text). See
@@ -3347,6 +3727,9 @@ ${query_text}`;
                                                 let js = `setTimeout(function() { window.scrollTo(0, ${scroll_ratio} * document.documentElement.scrollHeight); }, 200);`;
                                                 html_view.item.web.runJavaScript(js);
                                             }
+                                        } else if (root.pending_find_query.length === 0) {
+                                            // Otherwise continue from the last reading position
+                                            root.restore_reading_position();
                                         }
 
                                         if (root.pending_find_query.length > 0) {
@@ -3530,6 +3913,7 @@ ${query_text}`;
                                 item_height_use_default: root.item_height_use_default
                                 item_height_fixed: root.item_height_fixed
                                 new_results_page_fn: root.new_results_page
+                                run_suggestion_fn: root.run_suggestion_query
                                 // Cleaned, comma-joined exclude terms so an
                                 // all-excluded page can name the active filter.
                                 snippet_exclude_terms: {
@@ -3545,6 +3929,7 @@ ${query_text}`;
                                         return;
                                     }
                                     root.show_result_in_html_view(result_data);
+                                    root.record_search_result_opened(result_data);
                                 }
 
                                 onCurrentIndexChanged: fulltext_results.update_item()
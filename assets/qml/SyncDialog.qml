@@ -0,0 +1,359 @@
+pragma ComponentBehavior: Bound
+
+import QtQuick
+import QtQuick.Layouts
+import QtQuick.Controls
+import QtQuick.Dialogs
+
+import com.profoundlabs.simsapa
+
+// Sync of the user data with other devices on the LAN: this device's name,
+// serving the sync routes on the LAN, pairing codes, pairing with another
+// device, the paired devices and syncing with them, and the shared sync
+// folder. See docs/lan-sync.md.
+Dialog {
+    id: root
+    title: "Sync Devices"
+    modal: true
+    standardButtons: Dialog.Close
+
+    property int pointSize: 10
+    property var lan_sync: null
+    property var peers: []
+    property string pairing_code: ""
+    property string status_text: ""
+    property bool is_busy: false
+
+    onOpened: {
+        root.pairing_code = "";
+        root.status_text = "";
+        root.reload();
+        device_name_field.text = root.lan_sync ? root.lan_sync.device_name : "";
+        lan_enabled_checkbox.checked = root.lan_sync ? root.lan_sync.enabled : false;
+        lan_port_spinbox.value = root.lan_sync ? root.lan_sync.port : 4849;
+        sync_folder_field.text = root.lan_sync && root.lan_sync.sync_folder ? root.lan_sync.sync_folder : "";
+    }
+
+    // Mirrors the logic in DictionaryImportDialog.qml.
+    function strip_file_scheme(url: string): string {
+        const url_str = String(url);
+        if (url_str.startsWith("file:///")) {
+            const without_prefix = url_str.substring(8);
+            if (Qt.platform.os === "windows" && without_prefix.match(/^[A-Za-z]:/)) {
+                return decodeURIComponent(without_prefix);
+            }
+            return "/" + decodeURIComponent(without_prefix);
+        }
+        if (url_str.startsWith("file://")) {
+            return decodeURIComponent(url_str.substring(7));
+        }
+        return url_str;
+    }
+
+    function save_sync_folder(folder: string) {
+        SuttaBridge.set_sync_folder(folder.trim());
+        root.reload();
+        sync_folder_field.text = root.lan_sync && root.lan_sync.sync_folder ? root.lan_sync.sync_folder : "";
+    }
+
+    function reload() {
+        try {
+            root.lan_sync = JSON.parse(SuttaBridge.get_lan_sync_json());
+            root.peers = JSON.parse(SuttaBridge.get_sync_peers_json());
+        } catch (e) {
+            root.lan_sync = null;
+            root.peers = [];
+        }
+    }
+
+    function create_pairing_code() {
+        let code;
+        try {
+            code = JSON.parse(SuttaBridge.create_sync_pairing_code());
+        } catch (e) {
+            return;
+        }
+        if (code.error) {
+            root.status_text = code.error;
+            return;
+        }
+        root.pairing_code = code.code;
+    }
+
+    Connections {
+        target: SuttaBridge
+        function onLanSyncFinished(result_json: string) {
+            root.is_busy = false;
+            let result;
+            try {
+                result = JSON.parse(result_json);
+            } catch (e) {
+                return;
+            }
+            root.status_text = result.error ? result.error : result.message;
+            root.reload();
+        }
+    }
+
+    FolderDialog {
+        id: sync_folder_dialog
+        title: "Choose the sync folder"
+        onAccepted: root.save_sync_folder(root.strip_file_scheme(selectedFolder))
+    }
+
+    ScrollView {
+        id: sync_scroll
+        anchors.fill: parent
+        clip: true
+        contentWidth: availableWidth
+
+        ColumnLayout {
+            width: sync_scroll.availableWidth
+            spacing: 10
+
+            Label {
+                text: "This Device"
+                font.bold: true
+                font.pointSize: root.pointSize
+            }
+
+            RowLayout {
+                Layout.fillWidth: true
+
+                Label {
+                    text: "Name:"
+                    font.pointSize: root.pointSize
+                }
+
+                TextField {
+                    id: device_name_field
+                    Layout.fillWidth: true
+                    font.pointSize: root.pointSize
+                    placeholderText: "The name other devices see"
+                }
+
+                Button {
+                    text: "Save"
+                    enabled: device_name_field.text.trim().length > 0
+                    onClicked: {
+                        SuttaBridge.set_sync_device_name(device_name_field.text);
+                        root.reload();
+                    }
+                }
+            }
+
+            CheckBox {
+                id: lan_enabled_checkbox
+                text: "Allow other devices on the LAN to pair and sync with this one"
+                font.pointSize: root.pointSize
+                onToggled: {
+                    SuttaBridge.set_sync_lan_enabled(checked);
+                    root.reload();
+                }
+            }
+
+            RowLayout {
+                Label {
+                    text: "Port:"
+                    font.pointSize: root.pointSize
+                }
+
+                SpinBox {
+                    id: lan_port_spinbox
+                    from: 1024
+                    to: 65535
+                    editable: true
+                    font.pointSize: root.pointSize
+                    textFromValue: (value, locale) => value.toString()
+                    onValueModified: SuttaBridge.set_sync_lan_port(value)
+                }
+            }
+
+            Label {
+                Layout.fillWidth: true
+                text: "Only pairing and syncing are served on the LAN, the rest of the app's server stays on this computer. Changes take effect after restarting Simsapa."
+                wrapMode: Text.WordWrap
+                font.pointSize: root.pointSize - 1
+                color: palette.mid
+            }
+
+            RowLayout {
+                Layout.fillWidth: true
+                visible: lan_enabled_checkbox.checked
+
+                Label {
+                    Layout.fillWidth: true
+                    text: root.lan_sync && root.lan_sync.url
+                          ? `Address: ${root.lan_sync.url}`
+                          : "No network address found."
+                    wrapMode: Text.WordWrap
+                    font.pointSize: root.pointSize
+                }
+
+                Button {
+                    text: "Create Pairing Code"
+                    onClicked: root.create_pairing_code()
+                }
+            }
+
+            Label {
+                visible: root.pairing_code.length > 0
+                text: `Pairing code: ${root.pairing_code} (valid once, for 10 minutes)`
+                font.bold: true
+                font.pointSize: root.pointSize + 2
+            }
+
+            Label {
+                text: "Pair With a Device"
+                font.bold: true
+                font.pointSize: root.pointSize
+            }
+
+            RowLayout {
+                Layout.fillWidth: true
+
+                TextField {
+                    id: pair_url_field
+                    Layout.fillWidth: true
+                    font.pointSize: root.pointSize
+                    placeholderText: "Address, e.g. http://192.168.1.20:4849"
+                }
+
+                TextField {
+                    id: pair_code_field
+                    Layout.preferredWidth: 100
+                    font.pointSize: root.pointSize
+                    placeholderText: "Code"
+                    inputMethodHints: Qt.ImhDigitsOnly
+                }
+
+                Button {
+                    text: "Pair"
+                    enabled: !root.is_busy && pair_url_field.text.trim().length > 0 && pair_code_field.text.trim().length > 0
+                    onClicked: {
+                        root.is_busy = true;
+                        root.status_text = "Pairing...";
+                        SuttaBridge.pair_sync_device(pair_url_field.text, pair_code_field.text);
+                        pair_code_field.text = "";
+                    }
+                }
+            }
+
+            RowLayout {
+                Layout.fillWidth: true
+
+                Label {
+                    text: "Paired Devices"
+                    font.bold: true
+                    font.pointSize: root.pointSize
+                }
+
+                Item { Layout.fillWidth: true }
+
+                Button {
+                    text: "Sync Now"
+                    enabled: !root.is_busy && root.peers.some(p => !!p.url)
+                    onClicked: {
+                        root.is_busy = true;
+                        root.status_text = "Syncing...";
+                        SuttaBridge.sync_lan_now();
+                    }
+                }
+            }
+
+            Label {
+                visible: root.peers.length === 0
+                text: "No paired devices."
+                font.pointSize: root.pointSize
+            }
+
+            Repeater {
+                model: root.peers
+                delegate: RowLayout {
+                    id: peer_row
+                    required property var modelData
+                    Layout.fillWidth: true
+
+                    ColumnLayout {
+                        Layout.fillWidth: true
+                        spacing: 2
+
+                        Label {
+                            text: peer_row.modelData.name
+                            font.pointSize: root.pointSize
+                            elide: Text.ElideRight
+                            Layout.fillWidth: true
+                        }
+
+                        Label {
+                            text: (peer_row.modelData.url || "Syncs with this device")
+                                  + " · last synced: "
+                                  + (peer_row.modelData.last_synced_at ? peer_row.modelData.last_synced_at.substring(0, 16).replace("T", " ") : "never")
+                            font.pointSize: root.pointSize - 1
+                            color: palette.mid
+                            elide: Text.ElideRight
+                            Layout.fillWidth: true
+                        }
+                    }
+
+                    Button {
+                        text: "Unpair"
+                        onClicked: {
+                            SuttaBridge.unpair_sync_device(peer_row.modelData.device_id);
+                            root.reload();
+                        }
+                    }
+                }
+            }
+
+            Label {
+                text: "Sync Folder"
+                font.bold: true
+                font.pointSize: root.pointSize
+            }
+
+            RowLayout {
+                Layout.fillWidth: true
+
+                TextField {
+                    id: sync_folder_field
+                    Layout.fillWidth: true
+                    font.pointSize: root.pointSize
+                    placeholderText: "A folder shared with the other devices, e.g. ~/Sync/simsapa"
+                    onEditingFinished: root.save_sync_folder(text)
+                }
+
+                Button {
+                    text: "Choose..."
+                    onClicked: sync_folder_dialog.open()
+                }
+
+                Button {
+                    text: "Sync Now"
+                    enabled: !root.is_busy && !!(root.lan_sync && root.lan_sync.sync_folder)
+                    onClicked: {
+                        root.is_busy = true;
+                        root.status_text = "Syncing the folder...";
+                        SuttaBridge.sync_folder_now();
+                    }
+                }
+            }
+
+            Label {
+                Layout.fillWidth: true
+                text: "The app syncs with this folder on startup and on exit. Clear the field to stop syncing with it."
+                wrapMode: Text.WordWrap
+                font.pointSize: root.pointSize - 1
+                color: palette.mid
+            }
+
+            Label {
+                Layout.fillWidth: true
+                visible: root.status_text.length > 0
+                text: root.status_text
+                wrapMode: Text.WordWrap
+                font.pointSize: root.pointSize
+            }
+        }
+    }
+}
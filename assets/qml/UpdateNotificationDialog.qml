@@ -50,6 +50,13 @@ ApplicationWindow {
     property string release_notes: ""
     property var languages: []
 
+    // Delta update of the databases, when the release has one for the installed version
+    property string delta_url: ""
+    property string delta_sha256: ""
+    property int delta_size: 0
+    property string delta_status: ""
+    property bool delta_in_progress: false
+
     // Theme support
     property bool is_dark: theme_helper.is_dark
 
@@ -86,6 +93,10 @@ ApplicationWindow {
             root.current_version = info.current_version || "";
             root.release_notes = info.release_notes || "";
             root.languages = info.languages || [];
+            root.delta_url = info.delta ? info.delta.url : "";
+            root.delta_sha256 = info.delta ? info.delta.sha256 : "";
+            root.delta_size = info.delta ? info.delta.size : 0;
+            root.delta_status = "";
         } catch (e) {
             logger.error("Failed to parse update info JSON:", e);
             root.version = "";
@@ -94,9 +105,12 @@ ApplicationWindow {
             root.current_version = "";
             root.release_notes = "";
             root.languages = [];
+            root.delta_url = "";
         }
     }
 
+    AssetManager { id: asset_manager }
+
     function show_app_update(update_info_json: string) {
         root.parse_update_info(update_info_json);
         root.dialog_type = "app";
@@ -458,6 +472,15 @@ ApplicationWindow {
                             Layout.fillWidth: true
                         }
 
+                        Label {
+                            visible: root.delta_url.length > 0
+                            text: root.delta_status || `A quick update with only the changes (${(root.delta_size / 1024 / 1024).toFixed(1)} MB) is available. It is applied when the app restarts.`
+                            font.pointSize: root.pointSize
+                            wrapMode: Text.WordWrap
+                            Layout.fillWidth: true
+                            Layout.topMargin: 10
+                        }
+
                         Item { Layout.fillHeight: true }
                     }
                 }
@@ -476,10 +499,21 @@ ApplicationWindow {
                         onClicked: root.close()
                     }
 
+                    Button {
+                        visible: root.delta_url.length > 0
+                        text: "Quick Update"
+                        font.pointSize: root.pointSize
+                        enabled: !root.delta_in_progress && !root.export_in_progress
+                        onClicked: {
+                            root.delta_in_progress = true;
+                            asset_manager.stage_delta_update(root.delta_url, root.delta_sha256);
+                        }
+                    }
+
                     Button {
                         text: root.export_in_progress ? "Exporting user data…" : "Yes"
                         font.pointSize: root.pointSize
-                        enabled: !root.export_in_progress
+                        enabled: !root.export_in_progress && !root.delta_in_progress
                         onClicked: {
                             root.upgrade_initiated_here = true;
                             root.export_in_progress = true;
@@ -893,4 +927,14 @@ ApplicationWindow {
             root.dialog_type = "closing";
         }
     }
+
+    Connections {
+        target: asset_manager
+        function onDownloadShowMsg(message) {
+            root.delta_status = message;
+        }
+        function onDownloadsCompleted(value) {
+            root.delta_in_progress = false;
+        }
+    }
 }
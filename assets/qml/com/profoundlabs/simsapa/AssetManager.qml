@@ -43,6 +43,19 @@ Item {
         console.log("remove_sutta_languages():", language_codes);
     }
 
+    function stage_delta_update(source: string, sha256: string) {
+        console.log("stage_delta_update():", source, sha256);
+    }
+
+    function get_download_settings_json(): string {
+        return '{"mirrors":[],"proxy":null,"bandwidth_limit_kib":0}';
+    }
+
+    function save_download_settings_json(settings_json: string): string {
+        console.log("save_download_settings_json():", settings_json);
+        return "";
+    }
+
     signal downloadProgressChanged(op_msg: string, downloaded_bytes: int, total_bytes: int);
     signal downloadShowMsg(message: string);
     signal downloadsCompleted(message: string);
@@ -23,6 +23,8 @@ Item {
     signal ankiCsvExportReady(results_json: string);
     signal ankiPreviewReady(preview_html: string);
     signal databaseValidationResult(database_name: string, is_valid: bool, message: string);
+    signal integrityReportReady(report_json: string);
+    signal integrityRepairFinished(success: bool, message: string);
     signal showChapterFromLibrary(window_id: string, result_data_json: string);
     signal showSuttaFromReferenceSearch(window_id: string, result_data_json: string);
     signal bookMetadataUpdated(success: bool, message: string);
@@ -93,6 +95,14 @@ Item {
         console.log("dictionary_first_query()");
     }
 
+    function verify_installation(full: bool) {
+        console.log("verify_installation()", full);
+    }
+
+    function repair_installation(repair_json: string) {
+        console.log("repair_installation()", repair_json);
+    }
+
     function reset_app_settings_to_defaults(): bool {
         console.log("reset_app_settings_to_defaults()");
         return true;
@@ -274,7 +284,11 @@ Item {
         return '{"title": "", "author": ""}';
     }
 
-    function import_document(file_path: string, book_uid: string, title: string, author: string, language: string, document_type: string, split_tag: string) {
+    function import_document(file_path: string, book_uid: string, title: string, author: string, language: string, document_type: string, split_tag: string, ocr_languages: string) {
+    }
+
+    function is_ocr_available(): bool {
+        return false;
     }
 
     function copy_content_uri_to_temp(content_uri: string): string {
@@ -293,6 +307,10 @@ Item {
         return '';
     }
 
+    function get_pdf_page_for_spine_item(spine_item_uid: string): int {
+        return 0;
+    }
+
     function get_api_key(key_name: string): string {
         return 'key_value';
     }
@@ -313,6 +331,10 @@ Item {
         return '[]';
     }
 
+    function get_chapters_citing_sutta_json(sutta_uid: string): string {
+        return '[]';
+    }
+
     function get_spine_item_uid_by_path(book_uid: string, resource_path: string): string {
         return '';
     }
@@ -935,6 +957,10 @@ Item {
         return '{"ok": true}';
     }
 
+    function export_chanting_audio(json_selected_uids: string, dest_dir: string, options_json: string): string {
+        return '{"ok": true, "audio_files": [], "cue_file": null, "chapters": []}';
+    }
+
     function import_chanting_data(zip_path: string): string {
         return '{"ok": true}';
     }
@@ -1008,15 +1034,50 @@ Item {
     }
 
     signal waveformDataReady(recording_uid: string, waveform_json: string)
+    signal recordingMarkersDetected(recording_uid: string, result_json: string)
+    signal practiceComparisonReady(user_recording_uid: string, report_json: string)
 
     // Gloss / Prompts history signals
     signal historyListReady(item_type: string, json: string)
     signal historySaved(item_type: string, session_id: string)
     signal historyChanged(item_type: string)
 
+    // Saved searches: a NewContentReport JSON
+    signal savedSearchesRunReady(report_json: string)
+    signal querySuggestionsReady(query_text: string, suggestions_json: string)
+
+    // LAN sync: {"ok": true, "message": ...} or {"error": ...}
+    signal lanSyncFinished(result_json: string)
+
     function generate_waveform_data(recording_uid: string, file_path: string, num_bars: int) {
     }
 
+    function detect_recording_markers(recording_uid: string, file_path: string, section_uid: string) {
+    }
+
+    function compare_practice_recording(section_uid: string, reference_uid: string, user_uid: string) {
+    }
+
+    function get_scansion_html(text: string): string {
+        return "";
+    }
+
+    function get_sutta_scansion_html(uid: string): string {
+        return "";
+    }
+
+    function get_timing_track(section_uid: string, recording_uid: string, granularity: string): string {
+        return '{"saved": false, "track": {"version": 1, "granularity": "syllable", "tokens": []}}';
+    }
+
+    function save_timing_track(section_uid: string, recording_uid: string, track_json: string): string {
+        return '{"ok": true}';
+    }
+
+    function get_timing_position(section_uid: string, recording_uid: string, position_ms: int): string {
+        return '{}';
+    }
+
     // Logger functions
     function log_debug(message: string) {
         console.log("[DEBUG]", message);
@@ -1089,6 +1150,186 @@ Item {
         console.log("move_bookmark_items_to_folder()");
     }
 
+    function record_reading_progress(item_uid: string, table_name: string, scroll_position: real, progress: real, seconds: int) {
+        console.log("record_reading_progress()");
+    }
+
+    function get_reading_state_json(item_uid: string, table_name: string): string {
+        return '';
+    }
+
+    function set_reading_finished(item_uid: string, table_name: string, finished: bool) {
+        console.log("set_reading_finished()");
+    }
+
+    function get_book_reading_progress_json(book_uid: string): string {
+        return '{}';
+    }
+
+    function get_canon_reading_progress_json(nikaya: string, group_path_prefix: string): string {
+        return '[]';
+    }
+
+    function get_reading_statistics_json(): string {
+        return '{}';
+    }
+
+    function get_reading_plans_json(): string {
+        return '[]';
+    }
+
+    function get_reading_plan_items_json(plan_uid: string): string {
+        return '[]';
+    }
+
+    function get_todays_reading_json(): string {
+        return '[]';
+    }
+
+    function import_reading_plan_file(file_path: string): string {
+        return '{"ok": true, "uid": ""}';
+    }
+
+    function export_reading_plan_file(plan_uid: string, dest_path: string): string {
+        return '{"ok": true}';
+    }
+
+    function generate_reading_plan(options_json: string): string {
+        return '{"ok": true, "uid": "", "items": 0}';
+    }
+
+    function delete_reading_plan(plan_uid: string) {
+        console.log("delete_reading_plan()");
+    }
+
+    function enroll_in_reading_plan(plan_uid: string, start_date: string): bool {
+        return true;
+    }
+
+    function unenroll_from_reading_plan(plan_uid: string) {
+        console.log("unenroll_from_reading_plan()");
+    }
+
+    function set_reading_plan_active(plan_uid: string, active: bool) {
+        console.log("set_reading_plan_active()");
+    }
+
+    function set_reading_plan_item_completed(plan_uid: string, item_index: int, completed: bool) {
+        console.log("set_reading_plan_item_completed()");
+    }
+
+    function save_search(name: string, query_text: string, search_area: string, params_json: string): string {
+        return '{"ok": true, "id": 1}';
+    }
+
+    function get_saved_searches_json(): string {
+        return '[]';
+    }
+
+    function get_saved_search_hits_json(saved_search_id: int, only_new: bool): string {
+        return '[]';
+    }
+
+    function rerun_saved_search(saved_search_id: int) {
+        console.log("rerun_saved_search()");
+    }
+
+    function run_saved_searches_if_requested() {
+        console.log("run_saved_searches_if_requested()");
+    }
+
+    function rename_saved_search(saved_search_id: int, name: string): bool {
+        return true;
+    }
+
+    function delete_saved_search(saved_search_id: int) {
+        console.log("delete_saved_search()");
+    }
+
+    function mark_saved_search_seen(saved_search_id: int) {
+        console.log("mark_saved_search_seen()");
+    }
+
+    function record_search_history(query_text: string, search_area: string, params_json: string, hits_count: int): int {
+        return 1;
+    }
+
+    function set_search_history_opened(history_id: int, item_uid: string, table_name: string, title: string) {
+        console.log("set_search_history_opened()");
+    }
+
+    function get_search_history_json(limit: int): string {
+        return '[]';
+    }
+
+    function get_search_history_stats_json(): string {
+        return '{}';
+    }
+
+    function delete_search_history_item(history_id: int) {
+        console.log("delete_search_history_item()");
+    }
+
+    function clear_search_history() {
+        console.log("clear_search_history()");
+    }
+
+    function get_search_history_enabled(): bool {
+        return true;
+    }
+
+    function set_search_history_enabled(enabled: bool) {
+        console.log("set_search_history_enabled():", enabled);
+    }
+
+    function get_query_suggestions(query_text: string, search_area: string, limit: int) {
+        console.log("get_query_suggestions()");
+    }
+
+    function get_lan_sync_json(): string {
+        return '{"device_id": "", "device_name": "", "enabled": false, "port": 4849, "url": null, "sync_folder": null}';
+    }
+
+    function set_sync_lan_enabled(enabled: bool) {
+        console.log("set_sync_lan_enabled():", enabled);
+    }
+
+    function set_sync_lan_port(port: int) {
+        console.log("set_sync_lan_port():", port);
+    }
+
+    function set_sync_device_name(name: string) {
+        console.log("set_sync_device_name():", name);
+    }
+
+    function set_sync_folder(folder: string) {
+        console.log("set_sync_folder():", folder);
+    }
+
+    function sync_folder_now() {
+        console.log("sync_folder_now()");
+    }
+
+    function create_sync_pairing_code(): string {
+        return '{"code": "123456", "expires_at": 0}';
+    }
+
+    function get_sync_peers_json(): string {
+        return '[]';
+    }
+
+    function unpair_sync_device(device_id: string): bool {
+        return true;
+    }
+
+    function pair_sync_device(url: string, code: string) {
+        console.log("pair_sync_device():", url);
+    }
+
+    function sync_lan_now() {
+        console.log("sync_lan_now()");
+    }
+
     function save_last_session(windows_json: string) {
         console.log("save_last_session()");
     }
@@ -0,0 +1,8 @@
+DROP INDEX IF EXISTS idx_sync_ids_uid;
+DROP INDEX IF EXISTS idx_sync_fields_seq;
+
+DROP TABLE IF EXISTS sync_state;
+DROP TABLE IF EXISTS sync_pairing_codes;
+DROP TABLE IF EXISTS sync_peers;
+DROP TABLE IF EXISTS sync_ids;
+DROP TABLE IF EXISTS sync_fields;
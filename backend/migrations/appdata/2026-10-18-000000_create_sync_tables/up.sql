@@ -0,0 +1,58 @@
+-- Per-field change log of the synced user data. One row per (entity, uid,
+-- field) holding the last known value, when and on which device it was
+-- written, and the local sequence number used as the sync cursor.
+-- A deleted row is recorded as the '_deleted' field set to true.
+CREATE TABLE sync_fields (
+    entity VARCHAR NOT NULL,
+    uid VARCHAR NOT NULL,
+    field VARCHAR NOT NULL,
+    value_json TEXT NOT NULL,
+    modified_at BIGINT NOT NULL,
+    device_id VARCHAR NOT NULL,
+    seq BIGINT NOT NULL,
+    PRIMARY KEY (entity, uid, field)
+);
+
+-- Stable sync uids for the rows which only have a local integer id
+-- (bookmark folders and items, history). row_created_at tells a re-used id
+-- apart from the row it was first given to.
+CREATE TABLE sync_ids (
+    entity VARCHAR NOT NULL,
+    local_id INTEGER NOT NULL,
+    uid VARCHAR NOT NULL,
+    row_created_at VARCHAR,
+    PRIMARY KEY (entity, local_id)
+);
+
+-- Paired devices. url is set on the device which starts the syncs and
+-- empty on the device which serves them.
+CREATE TABLE sync_peers (
+    id INTEGER NOT NULL,
+    device_id VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    url VARCHAR,
+    secret VARCHAR NOT NULL,
+    pulled_seq BIGINT NOT NULL DEFAULT 0,
+    pushed_seq BIGINT NOT NULL DEFAULT 0,
+    last_synced_at DATETIME,
+    created_at DATETIME DEFAULT (CURRENT_TIMESTAMP),
+    PRIMARY KEY (id),
+    UNIQUE (device_id)
+);
+
+-- One-time pairing codes, valid until expires_at (unix ms).
+CREATE TABLE sync_pairing_codes (
+    code VARCHAR NOT NULL,
+    expires_at BIGINT NOT NULL,
+    PRIMARY KEY (code)
+);
+
+-- This device's id and name.
+CREATE TABLE sync_state (
+    "key" VARCHAR NOT NULL,
+    value VARCHAR NOT NULL,
+    PRIMARY KEY ("key")
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_fields_seq ON sync_fields(seq);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sync_ids_uid ON sync_ids(entity, uid);
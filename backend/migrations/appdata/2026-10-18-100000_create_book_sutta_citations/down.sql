@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_book_sutta_citations_sutta_uid;
+DROP INDEX IF EXISTS idx_book_sutta_citations_book_uid;
+DROP INDEX IF EXISTS idx_book_sutta_citations_book_id;
+
+DROP TABLE IF EXISTS book_sutta_citations;
@@ -0,0 +1,20 @@
+-- Sutta references cited in the chapters of library books, one row per
+-- (chapter, sutta). sutta_uid is without the /lang/author suffix, e.g. mn10
+-- or dhp179-196. ref_text is the reference as first written in the chapter.
+CREATE TABLE book_sutta_citations (
+    id INTEGER NOT NULL,
+    book_id INTEGER NOT NULL,
+    book_uid VARCHAR NOT NULL,
+    spine_item_uid VARCHAR NOT NULL,
+    sutta_uid VARCHAR NOT NULL,
+    ref_text VARCHAR NOT NULL,
+    ref_count INTEGER NOT NULL DEFAULT 1,
+    created_at DATETIME DEFAULT (CURRENT_TIMESTAMP),
+    PRIMARY KEY (id),
+    UNIQUE (spine_item_uid, sutta_uid),
+    FOREIGN KEY(book_id) REFERENCES books (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_book_sutta_citations_book_id ON book_sutta_citations(book_id);
+CREATE INDEX IF NOT EXISTS idx_book_sutta_citations_book_uid ON book_sutta_citations(book_uid);
+CREATE INDEX IF NOT EXISTS idx_book_sutta_citations_sutta_uid ON book_sutta_citations(sutta_uid);
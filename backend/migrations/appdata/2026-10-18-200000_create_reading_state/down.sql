@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_reading_state_last_read_at;
+DROP INDEX IF EXISTS idx_reading_state_book_uid;
+DROP INDEX IF EXISTS idx_reading_state_sutta_ref_uid;
+
+DROP TABLE IF EXISTS reading_state;
@@ -0,0 +1,27 @@
+-- Automatic reading state of suttas and library book chapters, one row per
+-- (item_uid, table_name). table_name is 'suttas' or 'book_spine_items'.
+-- sutta_ref_uid is the sutta uid without /lang/author (mn10), so that the
+-- canon progress counts a sutta read in any translation. book_uid is set for
+-- the chapters.
+-- scroll_position is the last position and progress the furthest position
+-- read, both as a ratio of the page height.
+CREATE TABLE reading_state (
+    id INTEGER NOT NULL,
+    item_uid VARCHAR NOT NULL,
+    table_name VARCHAR NOT NULL,
+    sutta_ref_uid VARCHAR,
+    book_uid VARCHAR,
+    scroll_position FLOAT NOT NULL DEFAULT 0,
+    progress FLOAT NOT NULL DEFAULT 0,
+    time_spent_secs INTEGER NOT NULL DEFAULT 0,
+    is_finished BOOLEAN NOT NULL DEFAULT 0,
+    finished_at DATETIME,
+    last_read_at DATETIME,
+    created_at DATETIME DEFAULT (CURRENT_TIMESTAMP),
+    PRIMARY KEY (id),
+    UNIQUE (item_uid, table_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reading_state_sutta_ref_uid ON reading_state(sutta_ref_uid);
+CREATE INDEX IF NOT EXISTS idx_reading_state_book_uid ON reading_state(book_uid);
+CREATE INDEX IF NOT EXISTS idx_reading_state_last_read_at ON reading_state(last_read_at);
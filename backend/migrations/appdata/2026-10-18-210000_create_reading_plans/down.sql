@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS reading_plan_completions;
+DROP TABLE IF EXISTS reading_plan_enrollments;
+DROP INDEX IF EXISTS idx_reading_plan_items_plan_day;
+DROP TABLE IF EXISTS reading_plan_items;
+DROP TABLE IF EXISTS reading_plans;
@@ -0,0 +1,47 @@
+-- Reading plans: ordered lists of suttas and book chapters, read on a
+-- schedule. schedule is 'daily' (item day_number is the day from the start
+-- date) or 'sequential' (the next unread item, at the reader's own pace).
+CREATE TABLE reading_plans (
+    id INTEGER NOT NULL,
+    uid VARCHAR NOT NULL UNIQUE,
+    title VARCHAR NOT NULL,
+    description VARCHAR,
+    author VARCHAR,
+    schedule VARCHAR NOT NULL DEFAULT 'daily',
+    is_user_added BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME DEFAULT (CURRENT_TIMESTAMP),
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE reading_plan_items (
+    id INTEGER NOT NULL,
+    plan_id INTEGER NOT NULL REFERENCES reading_plans (id) ON DELETE CASCADE,
+    item_index INTEGER NOT NULL,
+    day_number INTEGER NOT NULL,
+    item_uid VARCHAR NOT NULL,
+    table_name VARCHAR NOT NULL,
+    title VARCHAR,
+    PRIMARY KEY (id),
+    UNIQUE (plan_id, item_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reading_plan_items_plan_day ON reading_plan_items(plan_id, day_number);
+
+-- One enrollment per plan. Enrolling again restarts the plan.
+CREATE TABLE reading_plan_enrollments (
+    id INTEGER NOT NULL,
+    plan_id INTEGER NOT NULL UNIQUE REFERENCES reading_plans (id) ON DELETE CASCADE,
+    start_date DATE NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME DEFAULT (CURRENT_TIMESTAMP),
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE reading_plan_completions (
+    id INTEGER NOT NULL,
+    enrollment_id INTEGER NOT NULL REFERENCES reading_plan_enrollments (id) ON DELETE CASCADE,
+    item_index INTEGER NOT NULL,
+    completed_at DATETIME NOT NULL,
+    PRIMARY KEY (id),
+    UNIQUE (enrollment_id, item_index)
+);
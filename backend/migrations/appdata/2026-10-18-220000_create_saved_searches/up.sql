@@ -0,0 +1,29 @@
+-- Saved searches: a named query with its search area, mode and filters.
+-- params_json is the SearchParams of the search as JSON.
+CREATE TABLE saved_searches (
+    id INTEGER NOT NULL,
+    name VARCHAR NOT NULL UNIQUE,
+    query_text VARCHAR NOT NULL,
+    search_area VARCHAR NOT NULL,
+    search_mode VARCHAR NOT NULL,
+    params_json VARCHAR NOT NULL,
+    last_run_at DATETIME,
+    last_hits_count INTEGER NOT NULL DEFAULT 0,
+    new_hits_count INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT (CURRENT_TIMESTAMP),
+    PRIMARY KEY (id)
+);
+
+-- The results of a saved search seen so far. A result found on a later run
+-- is_new until the user has seen it.
+CREATE TABLE saved_search_hits (
+    id INTEGER NOT NULL,
+    saved_search_id INTEGER NOT NULL REFERENCES saved_searches (id) ON DELETE CASCADE,
+    table_name VARCHAR NOT NULL,
+    uid VARCHAR NOT NULL,
+    title VARCHAR,
+    first_seen_at DATETIME NOT NULL,
+    is_new BOOLEAN NOT NULL DEFAULT 0,
+    PRIMARY KEY (id),
+    UNIQUE (saved_search_id, table_name, uid)
+);
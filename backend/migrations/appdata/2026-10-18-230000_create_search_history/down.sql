@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_search_history_last_searched_at;
+DROP TABLE IF EXISTS search_history;
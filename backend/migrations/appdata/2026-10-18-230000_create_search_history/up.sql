@@ -0,0 +1,22 @@
+-- Search history: the searches run in the search window. A search with the
+-- same query, area and params (the SearchParams JSON, with the mode and the
+-- filters) is one row, counted in search_count.
+CREATE TABLE search_history (
+    id INTEGER NOT NULL,
+    query_text VARCHAR NOT NULL,
+    search_area VARCHAR NOT NULL,
+    search_mode VARCHAR NOT NULL,
+    params_json VARCHAR NOT NULL,
+    hits_count INTEGER NOT NULL DEFAULT 0,
+    search_count INTEGER NOT NULL DEFAULT 1,
+    -- The last result opened from the search
+    opened_uid VARCHAR,
+    opened_table_name VARCHAR,
+    opened_title VARCHAR,
+    created_at DATETIME DEFAULT (CURRENT_TIMESTAMP),
+    last_searched_at DATETIME NOT NULL,
+    PRIMARY KEY (id),
+    UNIQUE (query_text, search_area, params_json)
+);
+
+CREATE INDEX idx_search_history_last_searched_at ON search_history (last_searched_at);
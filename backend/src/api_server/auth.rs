@@ -0,0 +1,167 @@
+//! The local API token which guards the user data routes (bookmarks, history).
+//!
+//! The search and text routes stay open, as the browser extension has always
+//! used them. The user data routes require the token in an
+//! `Authorization: Bearer <token>` or `X-Simsapa-Token: <token>` header. The
+//! token is created on first use and kept in `api-token.txt` in the Simsapa
+//! folder, so a script running as the same user can read it; the
+//! `SIMSAPA_API_TOKEN` env variable overrides it.
+//!
+//! `/v1/sync/exchange` is called by another device instead, with the secret
+//! it got when pairing (`PeerSecret`).
+//!
+//! `/shutdown` and `/logger` need no token on a loopback address, where the
+//! desktop app calls them, and the API token on any other (`ServerControl`).
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+
+use crate::db::DbManager;
+use crate::helpers::random_hex;
+use crate::sync::{self, SyncPeer};
+use super::{ApiServerConfig, GuardFailure, Writable};
+
+/// Read the API token from `SIMSAPA_API_TOKEN` or `token_path`, creating the
+/// file with a new random token when neither is set.
+pub fn load_or_create_api_token(token_path: &Path) -> Result<String> {
+    let existing = std::env::var("SIMSAPA_API_TOKEN").ok()
+        .filter(|t| !t.trim().is_empty())
+        .or_else(|| fs::read_to_string(token_path).ok().filter(|t| !t.trim().is_empty()));
+    if let Some(token) = existing {
+        return Ok(token.trim().to_string());
+    }
+
+    let token = generate_token();
+    fs::write(token_path, &token)
+        .with_context(|| format!("Can't write the API token to {}", token_path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(token_path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Can't set permissions of {}", token_path.display()))?;
+    }
+
+    Ok(token)
+}
+
+/// 256 bits as hex, see `random_hex()`.
+fn generate_token() -> String {
+    random_hex(4)
+}
+
+/// Compare without returning early on the first differing byte.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Request guard for the user data routes. Fails with `401 Unauthorized` on a
+/// missing or wrong token, and with `403 Forbidden` when the server has no
+/// token configured.
+pub struct ApiToken;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiToken {
+    type Error = &'static str;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let expected = req.rocket().state::<ApiServerConfig>().and_then(|c| c.api_token.as_deref());
+        let Some(expected) = expected else {
+            return GuardFailure::fail(req, Status::Forbidden, "The user data routes are disabled: no API token is configured.");
+        };
+
+        match bearer_token(req) {
+            Some(token) if tokens_match(token, expected) => Outcome::Success(ApiToken),
+            _ => GuardFailure::fail(req, Status::Unauthorized, "Missing or wrong API token."),
+        }
+    }
+}
+
+/// Request guard for the routes which control the server itself (shutdown,
+/// logging): `Writable`, and when the server listens on an address other
+/// than loopback, the API token. Fails with `403 Forbidden` when that server
+/// has no token configured, and `401 Unauthorized` on a missing or wrong
+/// token.
+pub struct ServerControl;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ServerControl {
+    type Error = &'static str;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        rocket::outcome::try_outcome!(req.guard::<Writable>().await);
+
+        let Some(config) = req.rocket().state::<ApiServerConfig>() else {
+            return Outcome::Success(ServerControl);
+        };
+        if config.is_loopback() {
+            return Outcome::Success(ServerControl);
+        }
+        let Some(expected) = config.api_token.as_deref() else {
+            return GuardFailure::fail(req, Status::Forbidden, "The server listens on the network and has no API token configured.");
+        };
+
+        match bearer_token(req) {
+            Some(token) if tokens_match(token, expected) => Outcome::Success(ServerControl),
+            _ => GuardFailure::fail(req, Status::Unauthorized, "Missing or wrong API token."),
+        }
+    }
+}
+
+fn bearer_token<'r>(req: &'r Request<'_>) -> Option<&'r str> {
+    let headers = req.headers();
+    headers.get_one("Authorization")
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .or_else(|| headers.get_one("X-Simsapa-Token"))
+        .map(str::trim)
+}
+
+/// Request guard for `/v1/sync/exchange`: the bearer secret of a paired
+/// device, see `crate::sync`. Fails with `401 Unauthorized` otherwise.
+pub struct PeerSecret(pub SyncPeer);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PeerSecret {
+    type Error = &'static str;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let peer = match (bearer_token(req), req.rocket().state::<Arc<DbManager>>()) {
+            (Some(secret), Some(dbm)) if !secret.is_empty() => sync::list_peers(dbm).into_iter()
+                .find(|p| tokens_match(secret, &p.secret)),
+            _ => None,
+        };
+
+        match peer {
+            Some(peer) => Outcome::Success(PeerSecret(peer)),
+            None => GuardFailure::fail(req, Status::Unauthorized, "Not paired with this device, or a wrong secret."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_token() {
+        let a = generate_token();
+        let b = generate_token();
+        assert_eq!(a.len(), 64);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("abc123", "abc123"));
+        assert!(!tokens_match("abc124", "abc123"));
+        assert!(!tokens_match("abc12", "abc123"));
+        assert!(!tokens_match("", "abc123"));
+    }
+}
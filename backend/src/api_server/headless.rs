@@ -0,0 +1,91 @@
+//! Stand-ins for the routes which drive the desktop app's windows (open a
+//! sutta tab, run a lookup query, copy to the clipboard, ...). Without the Qt
+//! app there is nothing to call back into, so `simsapa_cli serve` answers them
+//! with `501 Not Implemented` instead of a bare `404`, telling the caller the
+//! route exists but needs the GUI.
+
+use rocket::http::Status;
+use rocket::{get, post, routes, Route};
+
+const GUI_ONLY: &str = "This route opens a Simsapa app window and is not available in headless mode (simsapa_cli serve).";
+
+type GuiOnly = (Status, &'static str);
+
+fn gui_only() -> GuiOnly {
+    (Status::NotImplemented, GUI_ONLY)
+}
+
+#[get("/lookup_window_query/<_>")]
+fn lookup_window_query() -> GuiOnly { gui_only() }
+
+#[post("/lookup_window_query")]
+fn lookup_window_query_post() -> GuiOnly { gui_only() }
+
+#[get("/summary_query/<_>/<_>")]
+fn summary_query() -> GuiOnly { gui_only() }
+
+#[get("/toggle_reading_mode/<_>/<_>")]
+fn toggle_reading_mode() -> GuiOnly { gui_only() }
+
+#[post("/sutta_menu_action")]
+fn sutta_menu_action() -> GuiOnly { gui_only() }
+
+#[post("/dppn_lookup")]
+fn dppn_lookup() -> GuiOnly { gui_only() }
+
+#[post("/open_book_page_tab/<_>")]
+fn open_book_page_tab() -> GuiOnly { gui_only() }
+
+#[get("/prev_chapter/<_>/<_..>")]
+fn prev_chapter() -> GuiOnly { gui_only() }
+
+#[get("/next_chapter/<_>/<_..>")]
+fn next_chapter() -> GuiOnly { gui_only() }
+
+#[get("/prev_sutta/<_>/<_..>")]
+fn prev_sutta() -> GuiOnly { gui_only() }
+
+#[get("/next_sutta/<_>/<_..>")]
+fn next_sutta() -> GuiOnly { gui_only() }
+
+#[post("/copy_to_clipboard")]
+fn copy_to_clipboard() -> GuiOnly { gui_only() }
+
+#[post("/open_external_url")]
+fn open_external_url() -> GuiOnly { gui_only() }
+
+#[get("/app-assets-list")]
+fn app_assets_list() -> GuiOnly { gui_only() }
+
+#[get("/open_sutta_window/<_..>")]
+fn open_sutta_window() -> GuiOnly { gui_only() }
+
+#[get("/open_sutta_tab/<_>/<_..>")]
+fn open_sutta_tab() -> GuiOnly { gui_only() }
+
+#[get("/suttas/<_..>")]
+fn open_sutta_by_uid() -> GuiOnly { gui_only() }
+
+/// The GUI-only paths mounted by `simsapa_cli serve`, mirroring the
+/// window-callback routes in `bridges/src/api.rs`.
+pub fn routes() -> Vec<Route> {
+    routes![
+        lookup_window_query,
+        lookup_window_query_post,
+        summary_query,
+        toggle_reading_mode,
+        sutta_menu_action,
+        dppn_lookup,
+        open_book_page_tab,
+        prev_chapter,
+        next_chapter,
+        prev_sutta,
+        next_sutta,
+        copy_to_clipboard,
+        open_external_url,
+        app_assets_list,
+        open_sutta_window,
+        open_sutta_tab,
+        open_sutta_by_uid,
+    ]
+}
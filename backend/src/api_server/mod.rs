@@ -0,0 +1,162 @@
+//! The localhost HTTP API (Rocket) without the Qt app.
+//!
+//! `routes` holds every route which only needs the databases and the bundled
+//! assets, `v1` the versioned API with its OpenAPI document. The desktop app (`bridges/src/api.rs`) builds its server with
+//! `build_rocket()` and mounts its window-callback routes on top; the headless
+//! `simsapa_cli serve` mounts `headless::routes()` instead, which answer those
+//! paths with `501 Not Implemented`.
+//!
+//! Built only with the `api-server` feature, so the Android and plain backend
+//! builds don't pull in Rocket.
+
+pub mod routes;
+pub mod headless;
+pub mod auth;
+pub mod v1;
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{Build, Rocket};
+use rocket_cors::{AllowedOrigins, CorsOptions};
+
+use crate::db::DbManager;
+use crate::logger::info;
+
+pub use routes::AssetsHandler;
+pub use auth::{load_or_create_api_token, ApiToken, PeerSecret, ServerControl};
+
+/// Options of one API server instance, managed as Rocket state.
+#[derive(Debug, Clone)]
+pub struct ApiServerConfig {
+    /// Address to listen on, e.g. `127.0.0.1` or `0.0.0.0`.
+    pub bind: String,
+    pub port: u16,
+    /// Reject state-changing requests (see `Writable`) with `403 Forbidden`.
+    pub read_only: bool,
+    /// Origins allowed by CORS. Empty allows any origin, which is what the
+    /// desktop app has always done for the browser extension.
+    pub cors_origins: Vec<String>,
+    /// Token required by the user data routes (see `auth`). `None` disables
+    /// those routes.
+    pub api_token: Option<String>,
+}
+
+impl Default for ApiServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1".to_string(),
+            port: 4848,
+            read_only: false,
+            cors_origins: Vec::new(),
+            api_token: None,
+        }
+    }
+}
+
+impl ApiServerConfig {
+    /// Whether the server only accepts connections from this computer.
+    pub fn is_loopback(&self) -> bool {
+        self.bind == "localhost"
+            || self.bind.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+    }
+
+    fn figment(&self) -> rocket::figment::Figment {
+        rocket::Config::figment()
+            .merge(("log_level", rocket::config::LogLevel::Off))
+            .merge(("address", self.bind.clone()))
+            .merge(("port", self.port))
+    }
+
+    fn cors(&self) -> Result<rocket_cors::Cors> {
+        let allowed_origins = if self.cors_origins.is_empty() {
+            AllowedOrigins::all()
+        } else {
+            AllowedOrigins::some_exact(&self.cors_origins)
+        };
+
+        CorsOptions { allowed_origins, ..Default::default() }
+            .to_cors()
+            .map_err(|e| anyhow::anyhow!("Cors options error: {}", e))
+    }
+}
+
+/// The message of a failed request guard, cached on the request so that the
+/// `/v1` error catcher can put it in the error body.
+pub(crate) struct GuardFailure(pub(crate) Option<&'static str>);
+
+impl GuardFailure {
+    pub(crate) fn fail<T>(req: &Request<'_>, status: Status, message: &'static str) -> Outcome<T, &'static str> {
+        req.local_cache(|| GuardFailure(Some(message)));
+        Outcome::Error((status, message))
+    }
+}
+
+/// Request guard for routes which change state (shutdown, logging, user data).
+/// Fails with `403 Forbidden` when the server runs read-only. Shutdown and
+/// logging also check the API token off loopback, see `ServerControl`.
+pub struct Writable;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Writable {
+    type Error = &'static str;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match req.rocket().state::<ApiServerConfig>() {
+            Some(config) if config.read_only => GuardFailure::fail(req, Status::Forbidden, "The server is read-only."),
+            _ => Outcome::Success(Writable),
+        }
+    }
+}
+
+/// Configure a Rocket instance with CORS, the shared routes and their state.
+/// Callers mount their own additional routes before launching.
+pub fn build_rocket(config: ApiServerConfig, db_manager: Arc<DbManager>) -> Result<Rocket<Build>> {
+    let cors = config.cors()?;
+
+    Ok(rocket::custom(config.figment())
+        .attach(cors)
+        .mount("/", routes::routes())
+        .mount("/v1", v1::routes())
+        .register("/v1", v1::catchers())
+        .manage(AssetsHandler::default())
+        .manage(db_manager)
+        .manage(config))
+}
+
+/// The LAN side of the app's sync: only `POST /v1/sync/pair` and
+/// `POST /v1/sync/exchange`, which are authorized by the pairing code and
+/// the peer's secret. The app server with the GUI, search and user data
+/// routes keeps listening on localhost. See docs/lan-sync.md.
+pub fn build_sync_rocket(config: ApiServerConfig, db_manager: Arc<DbManager>) -> Result<Rocket<Build>> {
+    Ok(rocket::custom(config.figment())
+        .mount("/v1", v1::sync::peer_routes())
+        .register("/v1", v1::catchers())
+        .manage(db_manager)
+        .manage(config))
+}
+
+/// Run the API without the Qt app until shutdown (Ctrl-C, or `POST /shutdown`
+/// when not read-only). Expects `init_app_data()` to have run.
+pub async fn serve_headless(config: ApiServerConfig) -> Result<()> {
+    let db_manager = Arc::new(DbManager::new().context("Can't create DbManager")?);
+
+    info(&format!("Serving the Simsapa API on http://{}:{} (read-only: {})",
+                  config.bind, config.port, config.read_only));
+
+    build_rocket(config, db_manager)?
+        .mount("/", headless::routes())
+        .launch()
+        .await
+        .map_err(|e| anyhow::anyhow!("Webserver error: {}", e))?;
+
+    Ok(())
+}
+
+/// Blocking wrapper for `serve_headless()`, running it on Rocket's own async
+/// runtime so the caller doesn't need one.
+pub fn run_headless(config: ApiServerConfig) -> Result<()> {
+    rocket::execute(serve_headless(config))
+}
@@ -0,0 +1,274 @@
+//! The versioned `/v1` API.
+//!
+//! Unlike the legacy routes in `routes.rs`, which keep the shapes the browser
+//! extension was written against, `/v1` has its own request and response types
+//! (`types.rs`), a single error body (`{"error": {status, code, message}}`) for
+//! every failure including unmatched paths and malformed JSON, pagination
+//! metadata on search, and an OpenAPI document (`openapi.rs`) at
+//! `/v1/openapi.json`. The search itself runs through the same code as
+//! `POST /search`. See docs/simsapa-localhost-api-search-endpoints.md.
+
+pub mod types;
+pub mod openapi;
+pub mod user_data;
+pub mod sync;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::{self, Json};
+use rocket::{catch, catchers, get, post, routes, Catcher, Route, State};
+use serde_json::Value;
+
+use crate::app_data::ResolvedWordKind;
+use crate::db::DbManager;
+use crate::get_app_data;
+use crate::helpers::strip_html;
+use crate::logger::{error, info};
+
+use super::{ApiServerConfig, GuardFailure};
+use super::routes::{
+    convert_verse_ref_to_sutta_uid, health_info, lookup_sutta_with_fallback, pathbuf_to_forward_slash_string,
+    run_area_search, search_options, ApiSearchRequest, HealthInfo, SearchOptions,
+};
+use types::{
+    AreaV1, ErrorBodyV1, ErrorDetailV1, SearchHitV1, SearchRequestV1, SearchResponseV1, SegmentV1, SuttaV1, WordV1,
+    DEFAULT_PAGE_LEN, MAX_PAGE_LEN,
+};
+
+/// A `/v1` error, rendered as `ErrorBodyV1` with its status.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: Status,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: Status, code: &'static str, message: impl Into<String>) -> Self {
+        Self { status, code, message: message.into() }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(Status::BadRequest, "bad_request", message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(Status::NotFound, "not_found", message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(Status::InternalServerError, "internal_error", message)
+    }
+
+    /// The error for a body which isn't valid JSON for the route's type.
+    pub(crate) fn from_json(e: json::Error<'_>) -> Self {
+        match e {
+            json::Error::Io(e) => Self::bad_request(format!("Can't read the request body: {}", e)),
+            json::Error::Parse(_, e) => Self::bad_request(format!("Invalid request body: {}", e)),
+        }
+    }
+
+    pub fn body(&self) -> ErrorBodyV1 {
+        ErrorBodyV1 {
+            error: ErrorDetailV1 {
+                status: self.status.code,
+                code: self.code.to_string(),
+                message: self.message.clone(),
+            },
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let status = self.status;
+        Response::build_from(Json(self.body()).respond_to(req)?)
+            .status(status)
+            .ok()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// Errors raised by Rocket itself under `/v1` (no matching route, a failing
+/// request guard such as `Writable`) get the same body as route errors.
+#[catch(default)]
+fn default_catcher(status: Status, req: &Request<'_>) -> ApiError {
+    let code = match status.code {
+        400 => "bad_request",
+        401 => "unauthorized",
+        403 => "forbidden",
+        404 => "not_found",
+        422 => "unprocessable_entity",
+        500.. => "internal_error",
+        _ => "error",
+    };
+    let message = req.local_cache(|| GuardFailure(None)).0
+        .or(status.reason())
+        .unwrap_or("Error");
+    ApiError::new(status, code, message)
+}
+
+/// POST /v1/search
+#[post("/search", data = "<request>")]
+fn search(request: Result<Json<SearchRequestV1>, json::Error<'_>>, dbm: &State<Arc<DbManager>>) -> ApiResult<SearchResponseV1> {
+    let Json(req) = request.map_err(ApiError::from_json)?;
+
+    if req.query.trim().is_empty() {
+        return Err(ApiError::bad_request("'query' is empty"));
+    }
+    let page_len = req.page_len.unwrap_or(DEFAULT_PAGE_LEN);
+    if page_len == 0 || page_len > MAX_PAGE_LEN {
+        return Err(ApiError::bad_request(format!("'page_len' must be between 1 and {}", MAX_PAGE_LEN)));
+    }
+    let page_num = i32::try_from(req.page).map_err(|_| ApiError::bad_request("'page' is too large"))?;
+
+    let mode = req.mode.unwrap_or_else(|| req.area.default_mode());
+
+    // The language filter goes to the suttas or the dictionary fields of the
+    // legacy request, depending on the area.
+    let (suttas_lang, dict_lang) = match req.area {
+        AreaV1::Dictionary => (None, req.lang.clone()),
+        _ => (req.lang.clone(), None),
+    };
+    let legacy_request = ApiSearchRequest {
+        query_text: req.query.clone(),
+        page_num: Some(page_num),
+        suttas_lang,
+        suttas_lang_include: req.lang_include,
+        dict_lang,
+        dict_lang_include: req.lang_include,
+        dict_dict: req.source.clone(),
+        dict_dict_include: req.source_include,
+        mode: None,
+        search_area: None,
+        page_len: Some(page_len as i32),
+        show_all_snippets: req.show_all_snippets,
+        snippet_exclude: req.snippet_exclude.clone(),
+        fuzzy_distance: None,
+    };
+
+    let Json(result) = run_area_search(&legacy_request, req.area.to_search_area(), mode.to_search_mode(), dbm.inner());
+
+    let total_hits = result.hits.max(0) as usize;
+
+    Ok(Json(SearchResponseV1 {
+        query: req.query,
+        area: req.area,
+        mode,
+        page: req.page,
+        page_len,
+        total_hits,
+        has_more: (req.page + 1).saturating_mul(page_len) < total_hits,
+        results: result.results.into_iter().map(SearchHitV1::from).collect(),
+        deconstructor: result.deconstructor.unwrap_or_default(),
+    }))
+}
+
+/// GET /v1/search/options
+#[get("/search/options")]
+fn get_search_options(dbm: &State<Arc<DbManager>>) -> Json<SearchOptions> {
+    Json(search_options(dbm))
+}
+
+/// GET /v1/suttas/<uid..>
+#[get("/suttas/<uid..>")]
+fn get_sutta(uid: PathBuf, dbm: &State<Arc<DbManager>>) -> ApiResult<SuttaV1> {
+    let uid = convert_verse_ref_to_sutta_uid(&pathbuf_to_forward_slash_string(&uid));
+    info(&format!("v1 get_sutta(): uid={}", uid));
+
+    let sutta = lookup_sutta_with_fallback(dbm, &uid)
+        .ok_or_else(|| ApiError::not_found(format!("No sutta for uid '{}'", uid)))?;
+
+    let (segments, text) = if sutta.content_json.as_deref().is_some_and(|s| !s.is_empty()) {
+        let segments = get_app_data().sutta_to_segments_json(&sutta, false, false)
+            .map_err(|e| {
+                error(&format!("v1 get_sutta(): {}", e));
+                ApiError::internal(format!("Can't read the segments of '{}'", sutta.uid))
+            })?
+            .into_iter()
+            .map(|(id, html)| SegmentV1 { id, text: strip_html(&html).trim().to_string() })
+            .filter(|seg| !seg.text.is_empty())
+            .collect();
+        (Some(segments), None)
+    } else {
+        let text = match (&sutta.content_plain, &sutta.content_html) {
+            (Some(plain), _) if !plain.is_empty() => plain.clone(),
+            (_, Some(html)) => strip_html(html),
+            _ => String::new(),
+        };
+        (None, Some(text))
+    };
+
+    Ok(Json(SuttaV1 {
+        uid: sutta.uid,
+        sutta_ref: sutta.sutta_ref,
+        nikaya: sutta.nikaya,
+        lang: sutta.language,
+        title: sutta.title,
+        title_pali: sutta.title_pali,
+        source_uid: sutta.source_uid,
+        segments,
+        text,
+    }))
+}
+
+/// GET /v1/words/<uid..>
+#[get("/words/<uid..>")]
+fn get_word(uid: PathBuf) -> ApiResult<WordV1> {
+    let uid = pathbuf_to_forward_slash_string(&uid);
+    info(&format!("v1 get_word(): uid={}", uid));
+
+    let resolved = get_app_data().resolve_word_uid(&uid)
+        .ok_or_else(|| ApiError::not_found(format!("No word for uid '{}'", uid)))?;
+
+    let kind = match resolved.kind() {
+        ResolvedWordKind::BoldDefinition => "bold_definition",
+        ResolvedWordKind::DpdHeadword => "dpd_headword",
+        ResolvedWordKind::DpdRoot => "dpd_root",
+        ResolvedWordKind::DictWord => "dict_word",
+    };
+
+    Ok(Json(WordV1 {
+        uid: resolved.canonical_uid().to_string(),
+        kind: kind.to_string(),
+        data: resolved.as_json().clone(),
+        query_uid: uid,
+    }))
+}
+
+/// GET /v1/health
+#[get("/health")]
+fn health(dbm: &State<Arc<DbManager>>, config: &State<ApiServerConfig>) -> Json<HealthInfo> {
+    Json(health_info(dbm, config))
+}
+
+/// GET /v1/openapi.json
+#[get("/openapi.json")]
+fn openapi_json() -> Json<Value> {
+    Json(openapi::openapi_document(&crate::update_checker::get_app_version()))
+}
+
+/// The `/v1` routes, to be mounted at `/v1`.
+pub fn routes() -> Vec<Route> {
+    let mut routes = routes![
+        search,
+        get_search_options,
+        get_sutta,
+        get_word,
+        health,
+        openapi_json,
+    ];
+    routes.extend(user_data::routes());
+    routes.extend(sync::routes());
+    routes
+}
+
+/// The JSON error catchers, to be registered at `/v1`.
+pub fn catchers() -> Vec<Catcher> {
+    catchers![default_catcher]
+}
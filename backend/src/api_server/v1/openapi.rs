@@ -0,0 +1,516 @@
+//! The OpenAPI 3 document served at `GET /v1/openapi.json`.
+//!
+//! `ENDPOINTS` lists every `/v1` route. The integration test
+//! `backend/tests/test_api_v1.rs` checks it against the mounted routes, so a
+//! route can't be added without documenting it here.
+
+use serde_json::{json, Map, Value};
+
+use crate::api_server::routes::{HealthInfo, SearchOptions};
+
+use crate::db::appdata_models::{BookmarkFolder, BookmarkItem, BookmarkItemUpdate, GlossPromptsHistory};
+use crate::sync::{PairRequest, PairResponse, PairingCode, SyncPeer, SyncRequest, SyncResponse};
+
+use super::types::{
+    all_schemas, schema_ref, ApiSchema, ErrorBodyV1, FolderNameV1, IdListV1, MoveItemsV1, NewBookmarkItemV1, SearchRequestV1,
+    SearchResponseV1, SuttaV1, WordV1, HISTORY_ITEM_TYPES,
+};
+
+/// One documented operation.
+pub struct Endpoint {
+    pub method: &'static str,
+    /// OpenAPI path template, e.g. `/v1/suttas/{uid}`.
+    pub path: &'static str,
+    pub operation_id: &'static str,
+    pub summary: &'static str,
+    /// Path parameters, `(name, JSON type, description)`.
+    pub path_params: &'static [(&'static str, &'static str, &'static str)],
+    pub request: Option<fn() -> Value>,
+    /// Success status, with the response body schema unless it is `204`.
+    pub status: u16,
+    pub response: Option<fn() -> Value>,
+    /// Error statuses besides the generic `default` one.
+    pub errors: &'static [u16],
+    pub auth: Auth,
+}
+
+/// The credentials of an endpoint, see `api_server::auth`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Auth {
+    None,
+    /// The local API token.
+    ApiToken,
+    /// The secret of a paired device.
+    PeerSecret,
+}
+
+pub const ENDPOINTS: &[Endpoint] = &[
+    Endpoint {
+        method: "post",
+        path: "/v1/search",
+        operation_id: "search",
+        summary: "Search suttas, library books or dictionaries, one page at a time.",
+        path_params: &[],
+        request: Some(schema_ref::<SearchRequestV1>),
+        status: 200,
+        response: Some(schema_ref::<SearchResponseV1>),
+        errors: &[400],
+        auth: Auth::None,
+    },
+    Endpoint {
+        method: "get",
+        path: "/v1/search/options",
+        operation_id: "searchOptions",
+        summary: "Languages and dictionary sources available as search filters.",
+        path_params: &[],
+        request: None,
+        status: 200,
+        response: Some(schema_ref::<SearchOptions>),
+        errors: &[],
+        auth: Auth::None,
+    },
+    Endpoint {
+        method: "get",
+        path: "/v1/suttas/{uid}",
+        operation_id: "getSutta",
+        summary: "One sutta as plain-text segments. Accepts verse references (dhp33) and uids without a source (mn1).",
+        path_params: &[("uid", "string", "Sutta uid, e.g. mn1/pli/ms. May contain slashes.")],
+        request: None,
+        status: 200,
+        response: Some(schema_ref::<SuttaV1>),
+        errors: &[404],
+        auth: Auth::None,
+    },
+    Endpoint {
+        method: "get",
+        path: "/v1/words/{uid}",
+        operation_id: "getWord",
+        summary: "One dictionary entry. Accepts DPD ids (12345/dpd) and human forms (dhamma 1.01).",
+        path_params: &[("uid", "string", "Word uid, e.g. dhamma-1-01/dpd. May contain slashes.")],
+        request: None,
+        status: 200,
+        response: Some(schema_ref::<WordV1>),
+        errors: &[404],
+        auth: Auth::None,
+    },
+    Endpoint {
+        method: "get",
+        path: "/v1/health",
+        operation_id: "health",
+        summary: "Version, database paths, row counts and readiness of the running instance.",
+        path_params: &[],
+        request: None,
+        status: 200,
+        response: Some(schema_ref::<HealthInfo>),
+        errors: &[],
+        auth: Auth::None,
+    },
+    Endpoint {
+        method: "get",
+        path: "/v1/openapi.json",
+        operation_id: "openapi",
+        summary: "This document.",
+        path_params: &[],
+        request: None,
+        status: 200,
+        response: Some(any_object),
+        errors: &[],
+        auth: Auth::None,
+    },
+    Endpoint {
+        method: "get",
+        path: "/v1/bookmarks/folders",
+        operation_id: "listBookmarkFolders",
+        summary: "All bookmark folders, in their sort order.",
+        path_params: &[],
+        request: None,
+        status: 200,
+        response: Some(array_of::<BookmarkFolder>),
+        errors: &[401],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "post",
+        path: "/v1/bookmarks/folders",
+        operation_id: "createBookmarkFolder",
+        summary: "Create a folder at the end of the list.",
+        path_params: &[],
+        request: Some(schema_ref::<FolderNameV1>),
+        status: 201,
+        response: Some(schema_ref::<BookmarkFolder>),
+        errors: &[400, 401],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "post",
+        path: "/v1/bookmarks/folders/reorder",
+        operation_id: "reorderBookmarkFolders",
+        summary: "Set the folder order. Returns all folders.",
+        path_params: &[],
+        request: Some(schema_ref::<IdListV1>),
+        status: 200,
+        response: Some(array_of::<BookmarkFolder>),
+        errors: &[401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "patch",
+        path: "/v1/bookmarks/folders/{folder_id}",
+        operation_id: "renameBookmarkFolder",
+        summary: "Rename a folder.",
+        path_params: &[FOLDER_ID],
+        request: Some(schema_ref::<FolderNameV1>),
+        status: 200,
+        response: Some(schema_ref::<BookmarkFolder>),
+        errors: &[400, 401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/v1/bookmarks/folders/{folder_id}",
+        operation_id: "deleteBookmarkFolder",
+        summary: "Delete a folder with its items.",
+        path_params: &[FOLDER_ID],
+        request: None,
+        status: 204,
+        response: None,
+        errors: &[401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "get",
+        path: "/v1/bookmarks/folders/{folder_id}/items",
+        operation_id: "listBookmarkItems",
+        summary: "The items of a folder, in their sort order.",
+        path_params: &[FOLDER_ID],
+        request: None,
+        status: 200,
+        response: Some(array_of::<BookmarkItem>),
+        errors: &[401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "post",
+        path: "/v1/bookmarks/folders/{folder_id}/items",
+        operation_id: "createBookmarkItem",
+        summary: "Add an item at the end of a folder.",
+        path_params: &[FOLDER_ID],
+        request: Some(schema_ref::<NewBookmarkItemV1>),
+        status: 201,
+        response: Some(schema_ref::<BookmarkItem>),
+        errors: &[400, 401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "post",
+        path: "/v1/bookmarks/folders/{folder_id}/items/reorder",
+        operation_id: "reorderBookmarkItems",
+        summary: "Set the item order within a folder. Returns the folder's items.",
+        path_params: &[FOLDER_ID],
+        request: Some(schema_ref::<IdListV1>),
+        status: 200,
+        response: Some(array_of::<BookmarkItem>),
+        errors: &[400, 401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "post",
+        path: "/v1/bookmarks/items/move",
+        operation_id: "moveBookmarkItems",
+        summary: "Move items to the end of another folder. Returns the target folder's items.",
+        path_params: &[],
+        request: Some(schema_ref::<MoveItemsV1>),
+        status: 200,
+        response: Some(array_of::<BookmarkItem>),
+        errors: &[401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "patch",
+        path: "/v1/bookmarks/items/{item_id}",
+        operation_id: "updateBookmarkItem",
+        summary: "Change fields of an item.",
+        path_params: &[ITEM_ID],
+        request: Some(schema_ref::<BookmarkItemUpdate>),
+        status: 200,
+        response: Some(schema_ref::<BookmarkItem>),
+        errors: &[400, 401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/v1/bookmarks/items/{item_id}",
+        operation_id: "deleteBookmarkItem",
+        summary: "Delete an item.",
+        path_params: &[ITEM_ID],
+        request: None,
+        status: 204,
+        response: None,
+        errors: &[401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "get",
+        path: "/v1/history/{item_type}",
+        operation_id: "listHistory",
+        summary: "Saved gloss or prompt sessions, newest first.",
+        path_params: &[ITEM_TYPE],
+        request: None,
+        status: 200,
+        response: Some(array_of::<GlossPromptsHistory>),
+        errors: &[400, 401],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/v1/history/{item_type}",
+        operation_id: "clearHistory",
+        summary: "Delete all history of a type.",
+        path_params: &[ITEM_TYPE],
+        request: None,
+        status: 204,
+        response: None,
+        errors: &[400, 401],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/v1/history/{item_type}/{id}",
+        operation_id: "deleteHistoryItem",
+        summary: "Delete one history item.",
+        path_params: &[ITEM_TYPE, ("id", "integer", "History item id.")],
+        request: None,
+        status: 204,
+        response: None,
+        errors: &[400, 401, 404],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "post",
+        path: "/v1/sync/pairing-code",
+        operation_id: "createSyncPairingCode",
+        summary: "A one-time code, valid for ten minutes, for another device to pair with this one.",
+        path_params: &[],
+        request: None,
+        status: 201,
+        response: Some(schema_ref::<PairingCode>),
+        errors: &[401],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "post",
+        path: "/v1/sync/pair",
+        operation_id: "syncPair",
+        summary: "Pair the calling device using the code shown on this one. A wrong code cancels every open code.",
+        path_params: &[],
+        request: Some(schema_ref::<PairRequest>),
+        status: 200,
+        response: Some(schema_ref::<PairResponse>),
+        errors: &[400, 401],
+        auth: Auth::None,
+    },
+    Endpoint {
+        method: "post",
+        path: "/v1/sync/exchange",
+        operation_id: "syncExchange",
+        summary: "Send the calling device's changes and receive this device's changes since the cursor.",
+        path_params: &[],
+        request: Some(schema_ref::<SyncRequest>),
+        status: 200,
+        response: Some(schema_ref::<SyncResponse>),
+        errors: &[400, 401],
+        auth: Auth::PeerSecret,
+    },
+    Endpoint {
+        method: "get",
+        path: "/v1/sync/peers",
+        operation_id: "listSyncPeers",
+        summary: "The paired devices.",
+        path_params: &[],
+        request: None,
+        status: 200,
+        response: Some(array_of::<SyncPeer>),
+        errors: &[401],
+        auth: Auth::ApiToken,
+    },
+    Endpoint {
+        method: "delete",
+        path: "/v1/sync/peers/{device_id}",
+        operation_id: "removeSyncPeer",
+        summary: "Forget a paired device. It can't sync until paired again.",
+        path_params: &[("device_id", "string", "The peer's device id.")],
+        request: None,
+        status: 204,
+        response: None,
+        errors: &[401, 404],
+        auth: Auth::ApiToken,
+    },
+];
+
+const FOLDER_ID: (&str, &str, &str) = ("folder_id", "integer", "Bookmark folder id.");
+const ITEM_ID: (&str, &str, &str) = ("item_id", "integer", "Bookmark item id.");
+const ITEM_TYPE: (&str, &str, &str) = ("item_type", "string", "gloss or prompts.");
+
+fn array_of<T: ApiSchema>() -> Value {
+    json!({ "type": "array", "items": schema_ref::<T>() })
+}
+
+fn any_object() -> Value {
+    json!({ "type": "object" })
+}
+
+fn error_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema_ref::<ErrorBodyV1>() } },
+    })
+}
+
+fn operation(ep: &Endpoint) -> Value {
+    let mut responses = Map::new();
+    let success = match ep.response {
+        Some(schema) => json!({
+            "description": if ep.status == 201 { "Created" } else { "OK" },
+            "content": { "application/json": { "schema": schema() } },
+        }),
+        None => json!({ "description": "No Content" }),
+    };
+    responses.insert(ep.status.to_string(), success);
+    for status in ep.errors {
+        let description = match status {
+            400 => "Invalid request",
+            401 if ep.auth == Auth::ApiToken => "Missing or wrong API token",
+            401 if ep.auth == Auth::PeerSecret => "Not a paired device",
+            401 => "Unauthorized",
+            404 => "Not found",
+            _ => "Error",
+        };
+        responses.insert(status.to_string(), error_response(description));
+    }
+    responses.insert("default".to_string(), error_response("Error"));
+
+    let mut op = json!({
+        "operationId": ep.operation_id,
+        "summary": ep.summary,
+        "responses": responses,
+    });
+
+    if !ep.path_params.is_empty() {
+        op["parameters"] = ep.path_params.iter()
+            .map(|(name, type_name, description)| {
+                let mut schema = json!({ "type": type_name });
+                if *name == "item_type" {
+                    schema["enum"] = json!(HISTORY_ITEM_TYPES);
+                }
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "description": description,
+                    "schema": schema,
+                })
+            })
+            .collect();
+    }
+
+    if let Some(request) = ep.request {
+        op["requestBody"] = json!({
+            "required": true,
+            "content": { "application/json": { "schema": request() } },
+        });
+    }
+
+    match ep.auth {
+        Auth::None => {}
+        Auth::ApiToken => op["security"] = json!([{ "apiToken": [] }]),
+        Auth::PeerSecret => op["security"] = json!([{ "peerSecret": [] }]),
+    }
+
+    op
+}
+
+/// Build the OpenAPI 3.1 document for the `/v1` API.
+pub fn openapi_document(app_version: &str) -> Value {
+    let mut paths = Map::new();
+    for ep in ENDPOINTS {
+        let item = paths.entry(ep.path.to_string()).or_insert_with(|| json!({}));
+        item[ep.method] = operation(ep);
+    }
+
+    let schemas: Map<String, Value> = all_schemas()
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect();
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Simsapa API",
+            "version": app_version,
+            "description": "Local HTTP API of the Simsapa Dhamma Reader. See docs/simsapa-localhost-api-search-endpoints.md.",
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "apiToken": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "The token in api-token.txt in the Simsapa folder, or SIMSAPA_API_TOKEN.",
+                },
+                "peerSecret": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "The secret a device got from POST /v1/sync/pair.",
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every `$ref` in the document resolves to a schema in `components`.
+    fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(r)) = map.get("$ref") {
+                    refs.push(r.clone());
+                }
+                map.values().for_each(|v| collect_refs(v, refs));
+            }
+            Value::Array(items) => items.iter().for_each(|v| collect_refs(v, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_refs_resolve() {
+        let doc = openapi_document("0.0.0");
+        let mut refs = Vec::new();
+        collect_refs(&doc, &mut refs);
+        assert!(!refs.is_empty());
+
+        for r in refs {
+            let name = r.strip_prefix("#/components/schemas/").expect("local schema ref");
+            assert!(doc["components"]["schemas"].get(name).is_some(), "unresolved {}", r);
+        }
+    }
+
+    #[test]
+    fn test_path_params_are_declared() {
+        let doc = openapi_document("0.0.0");
+        for ep in ENDPOINTS {
+            let op = &doc["paths"][ep.path][ep.method];
+            assert!(op.is_object(), "{} {} missing", ep.method, ep.path);
+
+            let template_params: Vec<&str> = ep.path.split('/')
+                .filter_map(|seg| seg.strip_prefix('{').and_then(|s| s.strip_suffix('}')))
+                .collect();
+            let declared: Vec<&str> = ep.path_params.iter().map(|(name, _, _)| *name).collect();
+            assert_eq!(template_params, declared, "{}", ep.path);
+        }
+    }
+}
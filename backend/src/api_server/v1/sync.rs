@@ -0,0 +1,97 @@
+//! `/v1` routes of the LAN sync between devices, see `crate::sync`.
+//!
+//! Creating pairing codes and managing peers is for the local user and
+//! requires the API token. Pairing is authorized by the one-time code, and the
+//! exchange by the secret the paired device got for it (`PeerSecret`).
+
+use std::sync::Arc;
+
+use rocket::http::Status;
+use rocket::serde::json::{self, Json};
+use rocket::{delete, get, post, routes, Route, State};
+
+use crate::db::DbManager;
+use crate::logger::error;
+use crate::sync::{self, PairRequest, PairResponse, PairingCode, SyncPeer, SyncRequest, SyncResponse};
+
+use super::{ApiError, ApiResult};
+use crate::api_server::{ApiToken, PeerSecret, Writable};
+
+type Body<'r, T> = Result<Json<T>, json::Error<'r>>;
+
+fn sync_error(context: &str, e: anyhow::Error) -> ApiError {
+    error(&format!("{}: {}", context, e));
+    ApiError::internal(format!("{} failed", context))
+}
+
+/// POST /v1/sync/pairing-code
+#[post("/sync/pairing-code")]
+fn create_pairing_code(_t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> Result<(Status, Json<PairingCode>), ApiError> {
+    let code = sync::create_pairing_code(dbm)
+        .map_err(|e| sync_error("create_pairing_code", e))?;
+    Ok((Status::Created, Json(code)))
+}
+
+/// POST /v1/sync/pair
+#[post("/sync/pair", data = "<body>")]
+fn pair(body: Body<'_, PairRequest>, _w: Writable, dbm: &State<Arc<DbManager>>) -> ApiResult<PairResponse> {
+    let Json(req) = body.map_err(ApiError::from_json)?;
+    if req.device_id.trim().is_empty() {
+        return Err(ApiError::bad_request("'device_id' is empty"));
+    }
+
+    match sync::accept_pairing(dbm, &req).map_err(|e| sync_error("accept_pairing", e))? {
+        Some(resp) => Ok(Json(resp)),
+        None => Err(ApiError::new(Status::Unauthorized, "unauthorized", "Wrong or expired pairing code.")),
+    }
+}
+
+/// POST /v1/sync/exchange
+#[post("/sync/exchange", data = "<body>")]
+fn exchange(body: Body<'_, SyncRequest>, peer: PeerSecret, _w: Writable, dbm: &State<Arc<DbManager>>) -> ApiResult<SyncResponse> {
+    let Json(req) = body.map_err(ApiError::from_json)?;
+    let PeerSecret(peer) = peer;
+    if req.device_id != peer.device_id {
+        return Err(ApiError::bad_request("'device_id' is not the device paired with this secret"));
+    }
+
+    let resp = sync::handle_sync_request(dbm, &peer, req)
+        .map_err(|e| sync_error("handle_sync_request", e))?;
+    Ok(Json(resp))
+}
+
+/// GET /v1/sync/peers
+#[get("/sync/peers")]
+fn list_peers(_t: ApiToken, dbm: &State<Arc<DbManager>>) -> Json<Vec<SyncPeer>> {
+    Json(sync::list_peers(dbm))
+}
+
+/// DELETE /v1/sync/peers/<device_id>
+#[delete("/sync/peers/<device_id>")]
+fn remove_peer(device_id: &str, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> Result<Status, ApiError> {
+    let removed = sync::remove_peer(dbm, device_id)
+        .map_err(|e| sync_error("remove_peer", e))?;
+    if !removed {
+        return Err(ApiError::not_found(format!("No peer with device id '{}'", device_id)));
+    }
+    Ok(Status::NoContent)
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        create_pairing_code,
+        pair,
+        exchange,
+        list_peers,
+        remove_peer,
+    ]
+}
+
+/// The routes another device calls to pair and sync, without the ones for
+/// the local user. Served on the LAN by `build_sync_rocket()`.
+pub fn peer_routes() -> Vec<Route> {
+    routes![
+        pair,
+        exchange,
+    ]
+}
@@ -0,0 +1,1001 @@
+//! Request and response types of the `/v1` API.
+//!
+//! These are the stable JSON contract: fields are only ever added, never
+//! renamed or removed, within `/v1`. They are deliberately separate from the
+//! internal `SearchResult` / `ApiSearchResult` so those can change without
+//! breaking API clients.
+//!
+//! Each type also describes itself as an OpenAPI 3 schema (`ApiSchema`), which
+//! `openapi.rs` collects into `components.schemas`. The tests below check that
+//! the serialized JSON and the schema agree.
+
+use rocket::serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::db::appdata_models::{BookmarkFolder, BookmarkItem, BookmarkItemUpdate, GlossPromptsHistory};
+use crate::sync::{PairRequest, PairResponse, PairingCode, SyncPeer, SyncRecord, SyncRequest, SyncResponse};
+use crate::types::{SearchArea, SearchMode, SearchResult};
+use crate::api_server::routes::{HealthInfo, SearchOptions};
+
+/// Default and maximum `page_len` of `POST /v1/search`.
+pub const DEFAULT_PAGE_LEN: usize = 20;
+pub const MAX_PAGE_LEN: usize = 100;
+
+/// A type with an OpenAPI 3 schema under `#/components/schemas/<NAME>`.
+pub trait ApiSchema {
+    const NAME: &'static str;
+    fn schema() -> Value;
+}
+
+/// `$ref` to the schema of `T`.
+pub fn schema_ref<T: ApiSchema>() -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", T::NAME) })
+}
+
+fn nullable(type_name: &str) -> Value {
+    json!({ "type": [type_name, "null"] })
+}
+
+// ============================================================================
+// Search
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AreaV1 {
+    #[default]
+    Suttas,
+    Library,
+    Dictionary,
+}
+
+impl AreaV1 {
+    pub const ALL: [&'static str; 3] = ["suttas", "library", "dictionary"];
+
+    pub fn to_search_area(self) -> SearchArea {
+        match self {
+            AreaV1::Suttas => SearchArea::Suttas,
+            AreaV1::Library => SearchArea::Library,
+            AreaV1::Dictionary => SearchArea::Dictionary,
+        }
+    }
+
+    /// The mode used when the request doesn't name one.
+    pub fn default_mode(self) -> ModeV1 {
+        match self {
+            AreaV1::Dictionary => ModeV1::Combined,
+            _ => ModeV1::Fulltext,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModeV1 {
+    Combined,
+    Fulltext,
+    Contains,
+    Headword,
+    Title,
+    DpdId,
+    DpdLookup,
+    Uid,
+    Regex,
+}
+
+impl ModeV1 {
+    pub const ALL: [&'static str; 9] = [
+        "combined", "fulltext", "contains", "headword", "title",
+        "dpd_id", "dpd_lookup", "uid", "regex",
+    ];
+
+    pub fn to_search_mode(self) -> SearchMode {
+        match self {
+            ModeV1::Combined => SearchMode::Combined,
+            ModeV1::Fulltext => SearchMode::FulltextMatch,
+            ModeV1::Contains => SearchMode::ContainsMatch,
+            ModeV1::Headword => SearchMode::HeadwordMatch,
+            ModeV1::Title => SearchMode::TitleMatch,
+            ModeV1::DpdId => SearchMode::DpdIdMatch,
+            ModeV1::DpdLookup => SearchMode::DpdLookup,
+            ModeV1::Uid => SearchMode::UidMatch,
+            ModeV1::Regex => SearchMode::RegExMatch,
+        }
+    }
+}
+
+/// Body of `POST /v1/search`. Only `query` is required.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchRequestV1 {
+    pub query: String,
+    #[serde(default)]
+    pub area: AreaV1,
+    /// Defaults to `fulltext` for suttas and library, `combined` for the
+    /// dictionary (uid or DPD lookup, with the deconstructor).
+    #[serde(default)]
+    pub mode: Option<ModeV1>,
+    /// Zero-based page index.
+    #[serde(default)]
+    pub page: usize,
+    #[serde(default)]
+    pub page_len: Option<usize>,
+    /// Language code filter, e.g. `pli` or `en`.
+    #[serde(default)]
+    pub lang: Option<String>,
+    /// `false` excludes `lang` instead of restricting to it.
+    #[serde(default)]
+    pub lang_include: Option<bool>,
+    /// Dictionary source filter (dictionary area only), e.g. `dpd`.
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub source_include: Option<bool>,
+    #[serde(default)]
+    pub show_all_snippets: Option<bool>,
+    #[serde(default)]
+    pub snippet_exclude: Option<Vec<String>>,
+}
+
+impl ApiSchema for SearchRequestV1 {
+    const NAME: &'static str = "SearchRequest";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["query"],
+            "properties": {
+                "query": { "type": "string", "minLength": 1 },
+                "area": { "type": "string", "enum": AreaV1::ALL, "default": "suttas" },
+                "mode": {
+                    "type": "string",
+                    "enum": ModeV1::ALL,
+                    "description": "Defaults to fulltext for suttas and library, combined for the dictionary.",
+                },
+                "page": { "type": "integer", "minimum": 0, "default": 0 },
+                "page_len": { "type": "integer", "minimum": 1, "maximum": MAX_PAGE_LEN, "default": DEFAULT_PAGE_LEN },
+                "lang": { "type": "string", "description": "Language code, e.g. pli or en." },
+                "lang_include": { "type": "boolean", "default": true },
+                "source": { "type": "string", "description": "Dictionary source, e.g. dpd. Dictionary area only." },
+                "source_include": { "type": "boolean", "default": true },
+                "show_all_snippets": { "type": "boolean", "default": false },
+                "snippet_exclude": { "type": "array", "items": { "type": "string" } },
+            },
+        })
+    }
+}
+
+/// One search result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHitV1 {
+    pub uid: String,
+    /// The table the hit came from: `suttas`, `book_spine_items`,
+    /// `dict_words`, `dpd_headwords`, `dpd_roots` or `bold_definitions`.
+    pub kind: String,
+    pub title: String,
+    pub sutta_ref: Option<String>,
+    pub nikaya: Option<String>,
+    pub author: Option<String>,
+    pub lang: Option<String>,
+    pub source_uid: Option<String>,
+    /// HTML snippet with the matches in `<span class='match'>`.
+    pub snippet: String,
+    pub page_number: Option<i32>,
+    pub score: Option<f32>,
+}
+
+impl From<SearchResult> for SearchHitV1 {
+    fn from(r: SearchResult) -> Self {
+        Self {
+            uid: r.uid,
+            kind: r.table_name,
+            title: r.title,
+            sutta_ref: r.sutta_ref,
+            nikaya: r.nikaya,
+            author: r.author,
+            lang: r.lang,
+            source_uid: r.source_uid,
+            snippet: r.snippet,
+            page_number: r.page_number,
+            score: r.score,
+        }
+    }
+}
+
+impl ApiSchema for SearchHitV1 {
+    const NAME: &'static str = "SearchHit";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["uid", "kind", "title", "sutta_ref", "nikaya", "author", "lang",
+                         "source_uid", "snippet", "page_number", "score"],
+            "properties": {
+                "uid": { "type": "string" },
+                "kind": {
+                    "type": "string",
+                    "enum": ["suttas", "book_spine_items", "dict_words", "dpd_headwords", "dpd_roots", "bold_definitions"],
+                },
+                "title": { "type": "string" },
+                "sutta_ref": nullable("string"),
+                "nikaya": nullable("string"),
+                "author": nullable("string"),
+                "lang": nullable("string"),
+                "source_uid": nullable("string"),
+                "snippet": { "type": "string", "description": "HTML fragment." },
+                "page_number": nullable("integer"),
+                "score": nullable("number"),
+            },
+        })
+    }
+}
+
+/// Response of `POST /v1/search`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResponseV1 {
+    pub query: String,
+    pub area: AreaV1,
+    /// The mode which was run, after defaults.
+    pub mode: ModeV1,
+    pub page: usize,
+    pub page_len: usize,
+    pub total_hits: usize,
+    pub has_more: bool,
+    pub results: Vec<SearchHitV1>,
+    /// Compound deconstructions of the query (dictionary area only).
+    pub deconstructor: Vec<String>,
+}
+
+impl ApiSchema for SearchResponseV1 {
+    const NAME: &'static str = "SearchResponse";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["query", "area", "mode", "page", "page_len", "total_hits", "has_more",
+                         "results", "deconstructor"],
+            "properties": {
+                "query": { "type": "string" },
+                "area": { "type": "string", "enum": AreaV1::ALL },
+                "mode": { "type": "string", "enum": ModeV1::ALL },
+                "page": { "type": "integer", "minimum": 0 },
+                "page_len": { "type": "integer", "minimum": 1 },
+                "total_hits": { "type": "integer", "minimum": 0 },
+                "has_more": { "type": "boolean" },
+                "results": { "type": "array", "items": schema_ref::<SearchHitV1>() },
+                "deconstructor": { "type": "array", "items": { "type": "string" } },
+            },
+        })
+    }
+}
+
+impl ApiSchema for SearchOptions {
+    const NAME: &'static str = "SearchOptions";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["sutta_languages", "dict_languages", "dict_sources"],
+            "properties": {
+                "sutta_languages": { "type": "array", "items": { "type": "string" } },
+                "dict_languages": { "type": "array", "items": { "type": "string" } },
+                "dict_sources": { "type": "array", "items": { "type": "string" } },
+            },
+        })
+    }
+}
+
+// ============================================================================
+// Suttas and words
+// ============================================================================
+
+/// One Bilara segment of a sutta, markup removed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentV1 {
+    pub id: String,
+    pub text: String,
+}
+
+impl ApiSchema for SegmentV1 {
+    const NAME: &'static str = "Segment";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "text"],
+            "properties": {
+                "id": { "type": "string", "description": "Segment id, e.g. mn1:1.1" },
+                "text": { "type": "string" },
+            },
+        })
+    }
+}
+
+/// Response of `GET /v1/suttas/<uid>`. Segmented texts fill `segments`, texts
+/// stored as HTML fill `text` instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuttaV1 {
+    pub uid: String,
+    pub sutta_ref: String,
+    pub nikaya: String,
+    pub lang: String,
+    pub title: Option<String>,
+    pub title_pali: Option<String>,
+    pub source_uid: Option<String>,
+    pub segments: Option<Vec<SegmentV1>>,
+    pub text: Option<String>,
+}
+
+impl ApiSchema for SuttaV1 {
+    const NAME: &'static str = "Sutta";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["uid", "sutta_ref", "nikaya", "lang", "title", "title_pali", "source_uid",
+                         "segments", "text"],
+            "properties": {
+                "uid": { "type": "string", "description": "Canonical uid, e.g. mn1/pli/ms" },
+                "sutta_ref": { "type": "string" },
+                "nikaya": { "type": "string" },
+                "lang": { "type": "string" },
+                "title": nullable("string"),
+                "title_pali": nullable("string"),
+                "source_uid": nullable("string"),
+                "segments": {
+                    "type": ["array", "null"],
+                    "items": schema_ref::<SegmentV1>(),
+                },
+                "text": nullable("string"),
+            },
+        })
+    }
+}
+
+/// Response of `GET /v1/words/<uid>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordV1 {
+    /// The uid as requested.
+    pub query_uid: String,
+    /// The stored uid it resolved to.
+    pub uid: String,
+    /// `dpd_headword`, `dpd_root`, `dict_word` or `bold_definition`.
+    pub kind: String,
+    /// The database row; its fields depend on `kind`.
+    pub data: Value,
+}
+
+impl ApiSchema for WordV1 {
+    const NAME: &'static str = "Word";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["query_uid", "uid", "kind", "data"],
+            "properties": {
+                "query_uid": { "type": "string" },
+                "uid": { "type": "string" },
+                "kind": { "type": "string", "enum": ["dpd_headword", "dpd_root", "dict_word", "bold_definition"] },
+                "data": { "type": "object", "description": "The database row, fields depend on kind." },
+            },
+        })
+    }
+}
+
+impl ApiSchema for HealthInfo {
+    const NAME: &'static str = "Health";
+
+    fn schema() -> Value {
+        let strings = json!({ "type": "array", "items": { "type": "string" } });
+        json!({
+            "type": "object",
+            "required": ["app_version", "api_port", "read_only", "db_paths", "fulltext_searcher_ready",
+                         "counts", "sutta_languages", "dict_sources"],
+            "properties": {
+                "app_version": { "type": "string" },
+                "api_port": { "type": "integer" },
+                "read_only": { "type": "boolean" },
+                "db_paths": {
+                    "type": "object",
+                    "required": ["appdata", "dictionaries", "dpd"],
+                    "properties": {
+                        "appdata": { "type": "string" },
+                        "dictionaries": { "type": "string" },
+                        "dpd": { "type": "string" },
+                    },
+                },
+                "fulltext_searcher_ready": { "type": "boolean" },
+                "counts": {
+                    "type": "object",
+                    "description": "null when the count query failed.",
+                    "required": ["suttas", "dict_words", "dpd_headwords"],
+                    "properties": {
+                        "suttas": nullable("integer"),
+                        "dict_words": nullable("integer"),
+                        "dpd_headwords": nullable("integer"),
+                    },
+                },
+                "sutta_languages": strings,
+                "dict_sources": strings,
+            },
+        })
+    }
+}
+
+// ============================================================================
+// User data: bookmarks and history
+// ============================================================================
+//
+// The responses are the database structs themselves, as the app's bridges
+// return them.
+
+impl ApiSchema for BookmarkFolder {
+    const NAME: &'static str = "BookmarkFolder";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "name", "sort_order", "is_last_session", "is_user_added"],
+            "properties": {
+                "id": { "type": "integer" },
+                "name": { "type": "string" },
+                "sort_order": { "type": "integer" },
+                "is_last_session": { "type": "boolean", "description": "Tabs saved from the last app session." },
+                "is_user_added": { "type": "boolean" },
+            },
+        })
+    }
+}
+
+impl ApiSchema for BookmarkItem {
+    const NAME: &'static str = "BookmarkItem";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "folder_id", "item_uid", "table_name", "title", "tab_group", "scroll_position",
+                         "find_query", "find_match_index", "sort_order", "is_user_added"],
+            "properties": {
+                "id": { "type": "integer" },
+                "folder_id": { "type": "integer" },
+                "item_uid": { "type": "string", "description": "e.g. mn1/pli/ms" },
+                "table_name": { "type": "string", "description": "e.g. suttas, dict_words, book_spine_items" },
+                "title": nullable("string"),
+                "tab_group": { "type": "string" },
+                "scroll_position": { "type": "number" },
+                "find_query": { "type": "string" },
+                "find_match_index": { "type": "integer" },
+                "sort_order": { "type": "integer" },
+                "is_user_added": { "type": "boolean" },
+            },
+        })
+    }
+}
+
+impl ApiSchema for BookmarkItemUpdate {
+    const NAME: &'static str = "BookmarkItemUpdate";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "description": "Fields to change; absent or null fields are left as they are.",
+            "properties": {
+                "item_uid": nullable("string"),
+                "title": nullable("string"),
+                "tab_group": nullable("string"),
+                "find_query": nullable("string"),
+                "find_match_index": nullable("integer"),
+            },
+        })
+    }
+}
+
+impl ApiSchema for GlossPromptsHistory {
+    const NAME: &'static str = "HistoryItem";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "item_type", "data_json", "created_at", "updated_at"],
+            "properties": {
+                "id": { "type": "integer" },
+                "item_type": { "type": "string", "enum": HISTORY_ITEM_TYPES },
+                "data_json": { "type": "string", "description": "The saved gloss or prompt session, as a JSON string." },
+                "created_at": { "type": ["string", "null"], "description": "UTC, e.g. 2026-01-31T12:00:00" },
+                "updated_at": { "type": ["string", "null"], "description": "UTC, e.g. 2026-01-31T12:00:00" },
+            },
+        })
+    }
+}
+
+/// The `<item_type>` values of the history routes.
+pub const HISTORY_ITEM_TYPES: [&str; 2] = ["gloss", "prompts"];
+
+/// Body of `POST /v1/bookmarks/folders` and `PATCH /v1/bookmarks/folders/<id>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FolderNameV1 {
+    pub name: String,
+}
+
+impl ApiSchema for FolderNameV1 {
+    const NAME: &'static str = "FolderName";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+            },
+        })
+    }
+}
+
+/// Body of `POST /v1/bookmarks/folders/<id>/items`. The folder comes from the
+/// path and the item is appended at the end of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewBookmarkItemV1 {
+    pub item_uid: String,
+    pub table_name: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub tab_group: String,
+    #[serde(default)]
+    pub scroll_position: f32,
+    #[serde(default)]
+    pub find_query: String,
+    #[serde(default)]
+    pub find_match_index: i32,
+}
+
+impl ApiSchema for NewBookmarkItemV1 {
+    const NAME: &'static str = "NewBookmarkItem";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["item_uid", "table_name"],
+            "properties": {
+                "item_uid": { "type": "string", "minLength": 1 },
+                "table_name": { "type": "string", "minLength": 1 },
+                "title": { "type": "string" },
+                "tab_group": { "type": "string", "default": "" },
+                "scroll_position": { "type": "number", "default": 0 },
+                "find_query": { "type": "string", "default": "" },
+                "find_match_index": { "type": "integer", "default": 0 },
+            },
+        })
+    }
+}
+
+/// Body of the reorder routes: every id of the folder (or of the folders) in
+/// the new order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdListV1 {
+    pub ids: Vec<i32>,
+}
+
+impl ApiSchema for IdListV1 {
+    const NAME: &'static str = "IdList";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["ids"],
+            "properties": {
+                "ids": { "type": "array", "items": { "type": "integer" } },
+            },
+        })
+    }
+}
+
+/// Body of `POST /v1/bookmarks/items/move`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MoveItemsV1 {
+    pub item_ids: Vec<i32>,
+    pub folder_id: i32,
+}
+
+impl ApiSchema for MoveItemsV1 {
+    const NAME: &'static str = "MoveItems";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["item_ids", "folder_id"],
+            "properties": {
+                "item_ids": { "type": "array", "items": { "type": "integer" } },
+                "folder_id": { "type": "integer", "description": "The target folder; the items are appended to it." },
+            },
+        })
+    }
+}
+
+// ============================================================================
+// Sync between devices
+// ============================================================================
+//
+// The types of `crate::sync`, which both ends of a sync share. The bodies
+// accept unknown fields, so an older device can sync with a newer one.
+
+impl ApiSchema for SyncRecord {
+    const NAME: &'static str = "SyncRecord";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "description": "The value of one field of one synced row.",
+            "required": ["entity", "uid", "field", "value", "modified_at", "device_id"],
+            "properties": {
+                "entity": { "type": "string", "description": "e.g. bookmark_folder, bookmark_item, history, setting" },
+                "uid": { "type": "string", "description": "The row's uid, the same on every device." },
+                "field": { "type": "string", "description": "A column name, or _deleted for a deleted row." },
+                "value": { "description": "Any JSON value." },
+                "modified_at": { "type": "integer", "description": "Unix time in milliseconds." },
+                "device_id": { "type": "string", "description": "The device which made the change." },
+            },
+        })
+    }
+}
+
+impl ApiSchema for SyncPeer {
+    const NAME: &'static str = "SyncPeer";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "device_id", "name", "url", "pulled_seq", "pushed_seq", "last_synced_at"],
+            "properties": {
+                "id": { "type": "integer" },
+                "device_id": { "type": "string" },
+                "name": { "type": "string" },
+                "url": { "type": ["string", "null"], "description": "Set on the device which starts the syncs." },
+                "pulled_seq": { "type": "integer" },
+                "pushed_seq": { "type": "integer" },
+                "last_synced_at": { "type": ["string", "null"], "description": "UTC, e.g. 2026-01-31T12:00:00" },
+            },
+        })
+    }
+}
+
+impl ApiSchema for PairingCode {
+    const NAME: &'static str = "PairingCode";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["code", "expires_at"],
+            "properties": {
+                "code": { "type": "string", "description": "Six digits, valid once." },
+                "expires_at": { "type": "integer", "description": "Unix time in milliseconds." },
+            },
+        })
+    }
+}
+
+impl ApiSchema for PairRequest {
+    const NAME: &'static str = "PairRequest";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["code", "device_id", "device_name"],
+            "properties": {
+                "code": { "type": "string" },
+                "device_id": { "type": "string", "minLength": 1 },
+                "device_name": { "type": "string" },
+            },
+        })
+    }
+}
+
+impl ApiSchema for PairResponse {
+    const NAME: &'static str = "PairResponse";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["device_id", "device_name", "secret"],
+            "properties": {
+                "device_id": { "type": "string" },
+                "device_name": { "type": "string" },
+                "secret": { "type": "string", "description": "The bearer secret of /v1/sync/exchange." },
+            },
+        })
+    }
+}
+
+impl ApiSchema for SyncRequest {
+    const NAME: &'static str = "SyncRequest";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["device_id", "since", "records"],
+            "properties": {
+                "device_id": { "type": "string", "description": "The calling device, as paired." },
+                "since": { "type": "integer", "description": "The cursor of the previous response, 0 at first." },
+                "records": { "type": "array", "items": schema_ref::<SyncRecord>() },
+            },
+        })
+    }
+}
+
+impl ApiSchema for SyncResponse {
+    const NAME: &'static str = "SyncResponse";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["device_id", "cursor", "records"],
+            "properties": {
+                "device_id": { "type": "string" },
+                "cursor": { "type": "integer", "description": "The since of the next request." },
+                "records": { "type": "array", "items": schema_ref::<SyncRecord>() },
+            },
+        })
+    }
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorDetailV1 {
+    /// The HTTP status code, repeated for clients which only see the body.
+    pub status: u16,
+    /// Machine-readable, e.g. `bad_request`, `not_found`, `read_only`.
+    pub code: String,
+    pub message: String,
+}
+
+/// The body of every `/v1` error response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBodyV1 {
+    pub error: ErrorDetailV1,
+}
+
+impl ApiSchema for ErrorBodyV1 {
+    const NAME: &'static str = "Error";
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["error"],
+            "properties": {
+                "error": {
+                    "type": "object",
+                    "required": ["status", "code", "message"],
+                    "properties": {
+                        "status": { "type": "integer" },
+                        "code": { "type": "string" },
+                        "message": { "type": "string" },
+                    },
+                },
+            },
+        })
+    }
+}
+
+/// Every schema of the `/v1` API, as `(name, schema)`.
+pub fn all_schemas() -> Vec<(&'static str, Value)> {
+    vec![
+        (SearchRequestV1::NAME, SearchRequestV1::schema()),
+        (SearchResponseV1::NAME, SearchResponseV1::schema()),
+        (SearchHitV1::NAME, SearchHitV1::schema()),
+        (SearchOptions::NAME, SearchOptions::schema()),
+        (SuttaV1::NAME, SuttaV1::schema()),
+        (SegmentV1::NAME, SegmentV1::schema()),
+        (WordV1::NAME, WordV1::schema()),
+        (HealthInfo::NAME, HealthInfo::schema()),
+        (BookmarkFolder::NAME, BookmarkFolder::schema()),
+        (BookmarkItem::NAME, BookmarkItem::schema()),
+        (BookmarkItemUpdate::NAME, BookmarkItemUpdate::schema()),
+        (GlossPromptsHistory::NAME, GlossPromptsHistory::schema()),
+        (FolderNameV1::NAME, FolderNameV1::schema()),
+        (NewBookmarkItemV1::NAME, NewBookmarkItemV1::schema()),
+        (IdListV1::NAME, IdListV1::schema()),
+        (MoveItemsV1::NAME, MoveItemsV1::schema()),
+        (SyncRecord::NAME, SyncRecord::schema()),
+        (SyncPeer::NAME, SyncPeer::schema()),
+        (PairingCode::NAME, PairingCode::schema()),
+        (PairRequest::NAME, PairRequest::schema()),
+        (PairResponse::NAME, PairResponse::schema()),
+        (SyncRequest::NAME, SyncRequest::schema()),
+        (SyncResponse::NAME, SyncResponse::schema()),
+        (ErrorBodyV1::NAME, ErrorBodyV1::schema()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The serialized object has exactly the schema's properties, and every
+    /// required one.
+    fn assert_matches_schema<T: ApiSchema + Serialize>(value: &T) {
+        let json = serde_json::to_value(value).unwrap();
+        let schema = T::schema();
+        let obj = json.as_object().expect("object");
+        let props = schema["properties"].as_object().expect("properties");
+
+        for key in obj.keys() {
+            assert!(props.contains_key(key), "{}: '{}' is not in the schema", T::NAME, key);
+        }
+        for key in props.keys() {
+            assert!(obj.contains_key(key), "{}: schema property '{}' is not serialized", T::NAME, key);
+        }
+        for req in schema["required"].as_array().cloned().unwrap_or_default() {
+            assert!(obj.contains_key(req.as_str().unwrap()), "{}: missing required {}", T::NAME, req);
+        }
+    }
+
+    fn sample_hit() -> SearchHitV1 {
+        SearchHitV1 {
+            uid: "mn1/pli/ms".to_string(),
+            kind: "suttas".to_string(),
+            title: "Mūlapariyāyasutta".to_string(),
+            sutta_ref: Some("MN 1".to_string()),
+            nikaya: Some("mn".to_string()),
+            author: None,
+            lang: Some("pli".to_string()),
+            source_uid: Some("ms".to_string()),
+            snippet: "<span class='match'>dhamma</span>".to_string(),
+            page_number: None,
+            score: Some(1.5),
+        }
+    }
+
+    #[test]
+    fn test_response_types_match_schemas() {
+        assert_matches_schema(&sample_hit());
+        assert_matches_schema(&SearchResponseV1 {
+            query: "dhamma".to_string(),
+            area: AreaV1::Suttas,
+            mode: ModeV1::Fulltext,
+            page: 0,
+            page_len: 20,
+            total_hits: 1,
+            has_more: false,
+            results: vec![sample_hit()],
+            deconstructor: Vec::new(),
+        });
+        assert_matches_schema(&SuttaV1 {
+            uid: "mn1/pli/ms".to_string(),
+            sutta_ref: "MN 1".to_string(),
+            nikaya: "mn".to_string(),
+            lang: "pli".to_string(),
+            title: None,
+            title_pali: None,
+            source_uid: None,
+            segments: Some(vec![SegmentV1 { id: "mn1:1.1".to_string(), text: "Evaṁ me sutaṁ".to_string() }]),
+            text: None,
+        });
+        assert_matches_schema(&WordV1 {
+            query_uid: "dhamma 1.01".to_string(),
+            uid: "dhamma-1-01/dpd".to_string(),
+            kind: "dict_word".to_string(),
+            data: json!({}),
+        });
+        assert_matches_schema(&ErrorBodyV1 {
+            error: ErrorDetailV1 { status: 404, code: "not_found".to_string(), message: "x".to_string() },
+        });
+        assert_matches_schema(&BookmarkFolder {
+            id: 1,
+            name: "Reading".to_string(),
+            sort_order: 1,
+            is_last_session: false,
+            is_user_added: true,
+        });
+        assert_matches_schema(&BookmarkItem {
+            id: 1,
+            folder_id: 1,
+            item_uid: "mn1/pli/ms".to_string(),
+            table_name: "suttas".to_string(),
+            title: None,
+            tab_group: String::new(),
+            scroll_position: 0.0,
+            find_query: String::new(),
+            find_match_index: 0,
+            sort_order: 1,
+            is_user_added: true,
+        });
+        assert_matches_schema(&GlossPromptsHistory {
+            id: 1,
+            item_type: "gloss".to_string(),
+            data_json: "{}".to_string(),
+            created_at: None,
+            updated_at: None,
+        });
+        assert_matches_schema(&BookmarkItemUpdate {
+            item_uid: None,
+            title: Some("x".to_string()),
+            tab_group: None,
+            find_query: None,
+            find_match_index: None,
+        });
+        assert_matches_schema(&NewBookmarkItemV1 {
+            item_uid: "mn1/pli/ms".to_string(),
+            table_name: "suttas".to_string(),
+            title: None,
+            tab_group: String::new(),
+            scroll_position: 0.0,
+            find_query: String::new(),
+            find_match_index: 0,
+        });
+        assert_matches_schema(&MoveItemsV1 { item_ids: vec![1], folder_id: 2 });
+        assert_matches_schema(&IdListV1 { ids: vec![1, 2] });
+        assert_matches_schema(&FolderNameV1 { name: "Reading".to_string() });
+        assert_matches_schema(&SearchOptions {
+            sutta_languages: Vec::new(),
+            dict_languages: Vec::new(),
+            dict_sources: Vec::new(),
+        });
+
+        let record = SyncRecord {
+            entity: "bookmark_folder".to_string(),
+            uid: "0123abcd".to_string(),
+            field: "name".to_string(),
+            value: json!("Reading"),
+            modified_at: 1_700_000_000_000,
+            device_id: "d1".to_string(),
+        };
+        assert_matches_schema(&record);
+        assert_matches_schema(&SyncRequest { device_id: "d1".to_string(), since: 0, records: vec![record.clone()] });
+        assert_matches_schema(&SyncResponse { device_id: "d2".to_string(), cursor: 3, records: vec![record] });
+        assert_matches_schema(&PairingCode { code: "123456".to_string(), expires_at: 0 });
+        assert_matches_schema(&PairRequest { code: "123456".to_string(), device_id: "d1".to_string(), device_name: "Phone".to_string() });
+        assert_matches_schema(&PairResponse { device_id: "d2".to_string(), device_name: "Desktop".to_string(), secret: "s".to_string() });
+        // The secret is not in the peer list.
+        assert_matches_schema(&SyncPeer {
+            id: 1,
+            device_id: "d1".to_string(),
+            name: "Phone".to_string(),
+            url: None,
+            secret: "s".to_string(),
+            pulled_seq: 0,
+            pushed_seq: 0,
+            last_synced_at: None,
+        });
+    }
+
+    #[test]
+    fn test_enum_values_match_schema() {
+        for name in ModeV1::ALL {
+            let mode: ModeV1 = serde_json::from_value(json!(name)).unwrap();
+            assert_eq!(serde_json::to_value(mode).unwrap(), json!(name));
+        }
+        for name in AreaV1::ALL {
+            let area: AreaV1 = serde_json::from_value(json!(name)).unwrap();
+            assert_eq!(serde_json::to_value(area).unwrap(), json!(name));
+        }
+    }
+
+    #[test]
+    fn test_search_request_defaults_and_unknown_fields() {
+        let req: SearchRequestV1 = serde_json::from_value(json!({ "query": "dhamma" })).unwrap();
+        assert_eq!(req.area, AreaV1::Suttas);
+        assert_eq!(req.page, 0);
+        assert!(req.mode.is_none());
+
+        let props = SearchRequestV1::schema()["properties"].as_object().unwrap().clone();
+        let full = serde_json::to_value(&req).unwrap();
+        for key in full.as_object().unwrap().keys() {
+            assert!(props.contains_key(key), "'{}' is not in the SearchRequest schema", key);
+        }
+
+        let unknown = serde_json::from_value::<SearchRequestV1>(json!({ "query": "a", "query_text": "a" }));
+        assert!(unknown.is_err());
+    }
+}
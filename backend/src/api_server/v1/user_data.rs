@@ -0,0 +1,261 @@
+//! `/v1` routes for the user's bookmarks and gloss / prompts history, for
+//! syncing reading lists from the browser extension and scripts.
+//!
+//! Every route requires the local API token (`ApiToken`), and the ones which
+//! change data also `Writable`. The database work is the same
+//! `AppdataDbHandle` functions the bridges call, and the responses are the
+//! same database structs.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rocket::http::Status;
+use rocket::serde::json::{self, Json};
+use rocket::{delete, get, patch, post, routes, Route, State};
+
+use crate::db::appdata_models::{
+    BookmarkFolder, BookmarkItem, BookmarkItemUpdate, GlossPromptsHistory, HistoryItemType, NewBookmarkItem,
+};
+use crate::db::DbManager;
+use crate::logger::{error, info};
+
+use super::types::{FolderNameV1, IdListV1, MoveItemsV1, NewBookmarkItemV1};
+use super::{ApiError, ApiResult};
+use crate::api_server::{ApiToken, Writable};
+
+type Body<'r, T> = Result<Json<T>, json::Error<'r>>;
+
+fn db_error(context: &str, e: anyhow::Error) -> ApiError {
+    error(&format!("{}: {}", context, e));
+    ApiError::internal(format!("{} failed", context))
+}
+
+fn find_folder(dbm: &DbManager, folder_id: i32) -> Result<BookmarkFolder, ApiError> {
+    dbm.appdata.get_bookmark_folder(folder_id)
+        .ok_or_else(|| ApiError::not_found(format!("No bookmark folder with id {}", folder_id)))
+}
+
+fn find_item(dbm: &DbManager, item_id: i32) -> Result<BookmarkItem, ApiError> {
+    dbm.appdata.get_bookmark_item(item_id)
+        .ok_or_else(|| ApiError::not_found(format!("No bookmark item with id {}", item_id)))
+}
+
+fn non_empty_name(body: FolderNameV1) -> Result<String, ApiError> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::bad_request("'name' is empty"));
+    }
+    Ok(name.to_string())
+}
+
+// --- Bookmark folders ---
+
+/// GET /v1/bookmarks/folders
+#[get("/bookmarks/folders")]
+fn list_folders(_t: ApiToken, dbm: &State<Arc<DbManager>>) -> Json<Vec<BookmarkFolder>> {
+    Json(dbm.appdata.get_all_bookmark_folders())
+}
+
+/// POST /v1/bookmarks/folders
+#[post("/bookmarks/folders", data = "<body>")]
+fn create_folder(body: Body<'_, FolderNameV1>, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> Result<(Status, Json<BookmarkFolder>), ApiError> {
+    let Json(body) = body.map_err(ApiError::from_json)?;
+    let name = non_empty_name(body)?;
+
+    let folder_id = dbm.appdata.create_bookmark_folder(&name, false)
+        .map_err(|e| db_error("create_bookmark_folder", e))?;
+    info(&format!("v1 create_folder(): id={}", folder_id));
+
+    Ok((Status::Created, Json(find_folder(dbm, folder_id)?)))
+}
+
+/// POST /v1/bookmarks/folders/reorder
+#[post("/bookmarks/folders/reorder", data = "<body>")]
+fn reorder_folders(body: Body<'_, IdListV1>, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> ApiResult<Vec<BookmarkFolder>> {
+    let Json(body) = body.map_err(ApiError::from_json)?;
+    for folder_id in &body.ids {
+        find_folder(dbm, *folder_id)?;
+    }
+
+    dbm.appdata.reorder_bookmark_folders(&body.ids)
+        .map_err(|e| db_error("reorder_bookmark_folders", e))?;
+
+    Ok(Json(dbm.appdata.get_all_bookmark_folders()))
+}
+
+/// PATCH /v1/bookmarks/folders/<folder_id>
+#[patch("/bookmarks/folders/<folder_id>", data = "<body>")]
+fn rename_folder(folder_id: i32, body: Body<'_, FolderNameV1>, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> ApiResult<BookmarkFolder> {
+    let Json(body) = body.map_err(ApiError::from_json)?;
+    let name = non_empty_name(body)?;
+    find_folder(dbm, folder_id)?;
+
+    dbm.appdata.update_bookmark_folder(folder_id, &name)
+        .map_err(|e| db_error("update_bookmark_folder", e))?;
+
+    Ok(Json(find_folder(dbm, folder_id)?))
+}
+
+/// DELETE /v1/bookmarks/folders/<folder_id>
+/// Deletes the folder's items too (`ON DELETE CASCADE`).
+#[delete("/bookmarks/folders/<folder_id>")]
+fn delete_folder(folder_id: i32, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> Result<Status, ApiError> {
+    find_folder(dbm, folder_id)?;
+
+    dbm.appdata.delete_bookmark_folder(folder_id)
+        .map_err(|e| db_error("delete_bookmark_folder", e))?;
+
+    Ok(Status::NoContent)
+}
+
+// --- Bookmark items ---
+
+/// GET /v1/bookmarks/folders/<folder_id>/items
+#[get("/bookmarks/folders/<folder_id>/items")]
+fn list_items(folder_id: i32, _t: ApiToken, dbm: &State<Arc<DbManager>>) -> ApiResult<Vec<BookmarkItem>> {
+    find_folder(dbm, folder_id)?;
+    Ok(Json(dbm.appdata.get_bookmark_items_for_folder(folder_id)))
+}
+
+/// POST /v1/bookmarks/folders/<folder_id>/items
+#[post("/bookmarks/folders/<folder_id>/items", data = "<body>")]
+fn create_item(folder_id: i32, body: Body<'_, NewBookmarkItemV1>, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> Result<(Status, Json<BookmarkItem>), ApiError> {
+    let Json(body) = body.map_err(ApiError::from_json)?;
+    if body.item_uid.trim().is_empty() || body.table_name.trim().is_empty() {
+        return Err(ApiError::bad_request("'item_uid' and 'table_name' must not be empty"));
+    }
+    find_folder(dbm, folder_id)?;
+
+    // sort_order is set by create_bookmark_item to the end of the folder.
+    let new_item = NewBookmarkItem {
+        folder_id,
+        item_uid: body.item_uid,
+        table_name: body.table_name,
+        title: body.title,
+        tab_group: body.tab_group,
+        scroll_position: body.scroll_position,
+        find_query: body.find_query,
+        find_match_index: body.find_match_index,
+        sort_order: 0,
+        is_user_added: true,
+    };
+    let item_id = dbm.appdata.create_bookmark_item(&new_item)
+        .map_err(|e| db_error("create_bookmark_item", e))?;
+    info(&format!("v1 create_item(): id={}, folder_id={}", item_id, folder_id));
+
+    Ok((Status::Created, Json(find_item(dbm, item_id)?)))
+}
+
+/// POST /v1/bookmarks/folders/<folder_id>/items/reorder
+#[post("/bookmarks/folders/<folder_id>/items/reorder", data = "<body>")]
+fn reorder_items(folder_id: i32, body: Body<'_, IdListV1>, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> ApiResult<Vec<BookmarkItem>> {
+    let Json(body) = body.map_err(ApiError::from_json)?;
+    find_folder(dbm, folder_id)?;
+    for item_id in &body.ids {
+        if find_item(dbm, *item_id)?.folder_id != folder_id {
+            return Err(ApiError::bad_request(format!("Bookmark item {} is not in folder {}", item_id, folder_id)));
+        }
+    }
+
+    dbm.appdata.reorder_bookmark_items(folder_id, &body.ids)
+        .map_err(|e| db_error("reorder_bookmark_items", e))?;
+
+    Ok(Json(dbm.appdata.get_bookmark_items_for_folder(folder_id)))
+}
+
+/// POST /v1/bookmarks/items/move
+/// Appends the items to the target folder, keeping their given order.
+#[post("/bookmarks/items/move", data = "<body>")]
+fn move_items(body: Body<'_, MoveItemsV1>, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> ApiResult<Vec<BookmarkItem>> {
+    let Json(body) = body.map_err(ApiError::from_json)?;
+    find_folder(dbm, body.folder_id)?;
+    for item_id in &body.item_ids {
+        find_item(dbm, *item_id)?;
+    }
+
+    dbm.appdata.move_bookmark_items_to_folder(&body.item_ids, body.folder_id)
+        .map_err(|e| db_error("move_bookmark_items_to_folder", e))?;
+
+    Ok(Json(dbm.appdata.get_bookmark_items_for_folder(body.folder_id)))
+}
+
+/// PATCH /v1/bookmarks/items/<item_id>
+#[patch("/bookmarks/items/<item_id>", data = "<body>")]
+fn update_item(item_id: i32, body: Body<'_, BookmarkItemUpdate>, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> ApiResult<BookmarkItem> {
+    let Json(body) = body.map_err(ApiError::from_json)?;
+    find_item(dbm, item_id)?;
+
+    dbm.appdata.update_bookmark_item(item_id, &body)
+        .map_err(|e| db_error("update_bookmark_item", e))?;
+
+    Ok(Json(find_item(dbm, item_id)?))
+}
+
+/// DELETE /v1/bookmarks/items/<item_id>
+#[delete("/bookmarks/items/<item_id>")]
+fn delete_item(item_id: i32, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> Result<Status, ApiError> {
+    find_item(dbm, item_id)?;
+
+    dbm.appdata.delete_bookmark_item(item_id)
+        .map_err(|e| db_error("delete_bookmark_item", e))?;
+
+    Ok(Status::NoContent)
+}
+
+// --- History ---
+
+fn parse_history_type(item_type: &str) -> Result<HistoryItemType, ApiError> {
+    HistoryItemType::from_str(item_type).map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// GET /v1/history/<item_type>
+/// Newest first.
+#[get("/history/<item_type>")]
+fn list_history(item_type: &str, _t: ApiToken, dbm: &State<Arc<DbManager>>) -> ApiResult<Vec<GlossPromptsHistory>> {
+    let item_type = parse_history_type(item_type)?;
+    Ok(Json(dbm.appdata.get_history_for_type(item_type)))
+}
+
+/// DELETE /v1/history/<item_type>
+#[delete("/history/<item_type>")]
+fn clear_history(item_type: &str, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> Result<Status, ApiError> {
+    let item_type = parse_history_type(item_type)?;
+
+    dbm.appdata.clear_history(item_type)
+        .map_err(|e| db_error("clear_history", e))?;
+
+    Ok(Status::NoContent)
+}
+
+/// DELETE /v1/history/<item_type>/<id>
+#[delete("/history/<item_type>/<id>")]
+fn delete_history_item(item_type: &str, id: i32, _t: ApiToken, _w: Writable, dbm: &State<Arc<DbManager>>) -> Result<Status, ApiError> {
+    let item_type = parse_history_type(item_type)?;
+    if !dbm.appdata.get_history_for_type(item_type).iter().any(|h| h.id == id) {
+        return Err(ApiError::not_found(format!("No {} history item with id {}", item_type.as_str(), id)));
+    }
+
+    dbm.appdata.delete_history_item(id)
+        .map_err(|e| db_error("delete_history_item", e))?;
+
+    Ok(Status::NoContent)
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        list_folders,
+        create_folder,
+        reorder_folders,
+        rename_folder,
+        delete_folder,
+        list_items,
+        create_item,
+        reorder_items,
+        move_items,
+        update_item,
+        delete_item,
+        list_history,
+        clear_history,
+        delete_history_item,
+    ]
+}
@@ -1382,6 +1382,17 @@ impl AppData {
         }
     }
 
+    pub fn get_search_history_enabled(&self) -> bool {
+        let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
+        app_settings.search_history_enabled
+    }
+
+    pub fn set_search_history_enabled(&self, enabled: bool) {
+        let mut app_settings = self.app_settings_cache.write().expect("Failed to write app settings");
+        app_settings.search_history_enabled = enabled;
+        self.persist_app_settings(&app_settings);
+    }
+
     pub fn get_search_as_you_type(&self) -> bool {
         let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
         app_settings.search_as_you_type
@@ -1712,6 +1723,15 @@ impl AppData {
         }
     }
 
+    /// Link the sutta references in the chapters of an imported book, and
+    /// index its citations. A failure is logged, the book stays imported.
+    fn link_book_sutta_refs(db_conn: &mut SqliteConnection, book_uid: &str) {
+        match crate::sutta_ref_links::link_sutta_refs_in_book(db_conn, book_uid) {
+            Ok(n) => info(&format!("Linked {} sutta citations in book: {}", n, book_uid)),
+            Err(e) => warn(&format!("Failed to link the sutta references in book {}: {}", book_uid, e)),
+        }
+    }
+
     /// Import an EPUB document into the database
     #[allow(clippy::too_many_arguments)]
     pub fn import_epub_to_db(&self, epub_path: &std::path::Path, book_uid: &str, custom_title: Option<&str>, custom_author: Option<&str>, custom_language: Option<&str>, custom_enable_embedded_css: Option<bool>, is_user_added: bool) -> Result<()> {
@@ -1719,6 +1739,7 @@ impl AppData {
             let db_conn = &mut self.dbm.appdata.get_conn()
                 .context("Failed to get database connection")?;
             crate::epub_import::import_epub_to_db(db_conn, epub_path, book_uid, custom_title, custom_author, custom_language, custom_enable_embedded_css, is_user_added)?;
+            Self::link_book_sutta_refs(db_conn, book_uid);
         }
         // Refresh stats: a new book adds rows to books / book_spine_items /
         // book_resources / books_fts. See docs/user-data-and-sqlite-analyze.md.
@@ -1733,11 +1754,64 @@ impl AppData {
             let db_conn = &mut self.dbm.appdata.get_conn()
                 .context("Failed to get database connection")?;
             crate::pdf_import::import_pdf_to_db(db_conn, pdf_path, book_uid, custom_title, custom_author, custom_language, custom_enable_embedded_css, is_user_added)?;
+            Self::link_book_sutta_refs(db_conn, book_uid);
         }
         self.dbm.appdata.analyze("appdata");
         Ok(())
     }
 
+    /// Import a PDF document, recognizing the text of scanned pages with `ocr`
+    #[allow(clippy::too_many_arguments)]
+    pub fn import_pdf_to_db_with_ocr(&self, pdf_path: &std::path::Path, book_uid: &str, custom_title: Option<&str>, custom_author: Option<&str>, custom_language: Option<&str>, is_user_added: bool, ocr: Option<&crate::ocr::Ocr>) -> Result<()> {
+        {
+            let db_conn = &mut self.dbm.appdata.get_conn()
+                .context("Failed to get database connection")?;
+            crate::pdf_import::import_pdf_to_db_with_ocr(db_conn, pdf_path, book_uid, custom_title, custom_author, custom_language, None, is_user_added, ocr)?;
+            Self::link_book_sutta_refs(db_conn, book_uid);
+        }
+        self.dbm.appdata.analyze("appdata");
+        Ok(())
+    }
+
+    /// Import a folder of page images as a book, recognizing their text with `ocr`
+    #[allow(clippy::too_many_arguments)]
+    pub fn import_images_to_db(&self, folder: &std::path::Path, book_uid: &str, custom_title: Option<&str>, custom_author: Option<&str>, custom_language: Option<&str>, is_user_added: bool, ocr: Option<&crate::ocr::Ocr>) -> Result<()> {
+        {
+            let db_conn = &mut self.dbm.appdata.get_conn()
+                .context("Failed to get database connection")?;
+            crate::image_import::import_images_to_db(db_conn, folder, book_uid, custom_title, custom_author, custom_language, is_user_added, ocr)?;
+            Self::link_book_sutta_refs(db_conn, book_uid);
+        }
+        self.dbm.appdata.analyze("appdata");
+        Ok(())
+    }
+
+    /// The OCR of the library imports, with the Pāli spelling repair from the
+    /// DPD word forms. None when Tesseract is not installed.
+    pub fn ocr_for_import(&self, languages: &str) -> Option<crate::ocr::Ocr> {
+        use crate::ocr::{Ocr, OcrEngine, PaliSpellingCorrector};
+
+        let engine = OcrEngine::new(languages);
+        if !engine.is_available() {
+            warn("OCR: the tesseract command is not available.");
+            return None;
+        }
+        let corrector = match self.dbm.dpd.get_conn() {
+            Ok(mut db_conn) => match PaliSpellingCorrector::from_dpd(&mut db_conn) {
+                Ok(corrector) => Some(corrector),
+                Err(e) => {
+                    warn(&format!("OCR: no Pāli spelling repair: {}", e));
+                    None
+                }
+            },
+            Err(e) => {
+                warn(&format!("OCR: no Pāli spelling repair: {}", e));
+                None
+            }
+        };
+        Some(Ocr::new(engine, corrector))
+    }
+
     /// Import an HTML document into the database
     #[allow(clippy::too_many_arguments)]
     pub fn import_html_to_db(&self, html_path: &std::path::Path, book_uid: &str, custom_title: Option<&str>, custom_author: Option<&str>, custom_language: Option<&str>, custom_enable_embedded_css: Option<bool>, is_user_added: bool) -> Result<()> {
@@ -1745,6 +1819,27 @@ impl AppData {
             let db_conn = &mut self.dbm.appdata.get_conn()
                 .context("Failed to get database connection")?;
             crate::html_import::import_html_to_db(db_conn, html_path, book_uid, custom_title, custom_author, custom_language, custom_enable_embedded_css, is_user_added)?;
+            Self::link_book_sutta_refs(db_conn, book_uid);
+        }
+        self.dbm.appdata.analyze("appdata");
+        Ok(())
+    }
+
+    /// Import a Markdown (file or folder), DOCX, ODT or FB2 document into the
+    /// database. `document_type` is "markdown", "docx", "odt" or "fb2".
+    #[allow(clippy::too_many_arguments)]
+    pub fn import_converted_document_to_db(&self, path: &std::path::Path, document_type: &str, book_uid: &str, custom_title: Option<&str>, custom_author: Option<&str>, custom_language: Option<&str>, is_user_added: bool) -> Result<()> {
+        {
+            let db_conn = &mut self.dbm.appdata.get_conn()
+                .context("Failed to get database connection")?;
+            match document_type {
+                "markdown" => crate::markdown_import::import_markdown_to_db(db_conn, path, book_uid, custom_title, custom_author, custom_language, is_user_added)?,
+                "docx" => crate::docx_import::import_docx_to_db(db_conn, path, book_uid, custom_title, custom_author, custom_language, is_user_added)?,
+                "odt" => crate::odt_import::import_odt_to_db(db_conn, path, book_uid, custom_title, custom_author, custom_language, is_user_added)?,
+                "fb2" => crate::fb2_import::import_fb2_to_db(db_conn, path, book_uid, custom_title, custom_author, custom_language, is_user_added)?,
+                _ => return Err(anyhow!("Unsupported document type: {}", document_type)),
+            }
+            Self::link_book_sutta_refs(db_conn, book_uid);
         }
         self.dbm.appdata.analyze("appdata");
         Ok(())
@@ -1984,6 +2079,77 @@ impl AppData {
         self.persist_app_settings(&app_settings);
     }
 
+    pub fn get_sync_folder(&self) -> Option<String> {
+        let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
+        app_settings.sync_folder.clone()
+    }
+
+    /// Set the shared folder to sync the user data through, `None` or an
+    /// empty path to stop.
+    pub fn set_sync_folder(&self, folder: Option<String>) {
+        let mut app_settings = self.app_settings_cache.write().expect("Failed to write app settings");
+        app_settings.sync_folder = folder.filter(|f| !f.trim().is_empty());
+        self.persist_app_settings(&app_settings);
+    }
+
+    pub fn get_sync_lan_enabled(&self) -> bool {
+        let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
+        app_settings.sync_lan_enabled
+    }
+
+    /// Serve the sync routes on the LAN from the next start.
+    pub fn set_sync_lan_enabled(&self, enabled: bool) {
+        let mut app_settings = self.app_settings_cache.write().expect("Failed to write app settings");
+        app_settings.sync_lan_enabled = enabled;
+        self.persist_app_settings(&app_settings);
+    }
+
+    pub fn get_sync_lan_port(&self) -> u16 {
+        let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
+        app_settings.sync_lan_port
+    }
+
+    pub fn set_sync_lan_port(&self, port: u16) {
+        let mut app_settings = self.app_settings_cache.write().expect("Failed to write app settings");
+        app_settings.sync_lan_port = port;
+        self.persist_app_settings(&app_settings);
+    }
+
+    pub fn get_backup_interval_hours(&self) -> u32 {
+        let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
+        app_settings.backup_interval_hours
+    }
+
+    /// Set the hours between the automatic backups, 0 to turn them off.
+    pub fn set_backup_interval_hours(&self, hours: u32) {
+        let mut app_settings = self.app_settings_cache.write().expect("Failed to write app settings");
+        app_settings.backup_interval_hours = hours;
+        self.persist_app_settings(&app_settings);
+    }
+
+    pub fn get_backup_keep(&self) -> usize {
+        let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
+        app_settings.backup_keep
+    }
+
+    pub fn set_backup_keep(&self, keep: usize) {
+        let mut app_settings = self.app_settings_cache.write().expect("Failed to write app settings");
+        app_settings.backup_keep = keep.max(1);
+        self.persist_app_settings(&app_settings);
+    }
+
+    pub fn get_backup_folder(&self) -> Option<String> {
+        let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
+        app_settings.backup_folder.clone()
+    }
+
+    /// Set the folder of the backups, `None` or an empty path for the default.
+    pub fn set_backup_folder(&self, folder: Option<String>) {
+        let mut app_settings = self.app_settings_cache.write().expect("Failed to write app settings");
+        app_settings.backup_folder = folder.filter(|f| !f.trim().is_empty());
+        self.persist_app_settings(&app_settings);
+    }
+
     /// Get the current keybindings as a JSON string.
     pub fn get_keybindings_json(&self) -> String {
         let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
@@ -2097,6 +2263,7 @@ impl AppData {
     /// - download_languages.txt: CSV list of languages in the database (except 'san', 'en', 'pli')
     /// - download_select_sanskrit_bundle.txt: If 'san' language is present
     /// - appdata.sqlite3: A database with user-imported books and their related data
+    /// - sync/: The device's sync identity, change log and paired devices
     ///
     /// Per-category failures do not short-circuit the remaining exports — every
     /// category is attempted and errors are collected. Returns `Err(Vec<(category,
@@ -2178,6 +2345,22 @@ impl AppData {
             errors.push(("bookmarks".to_string(), format!("{:#}", e)));
         }
 
+        if let Err(e) = self.export_user_reading_state(&import_dir) {
+            errors.push(("reading_state".to_string(), format!("{:#}", e)));
+        }
+
+        if let Err(e) = self.export_user_reading_plans(&import_dir) {
+            errors.push(("reading_plans".to_string(), format!("{:#}", e)));
+        }
+
+        if let Err(e) = self.export_user_saved_searches(&import_dir) {
+            errors.push(("saved_searches".to_string(), format!("{:#}", e)));
+        }
+
+        if let Err(e) = self.export_user_search_history(&import_dir) {
+            errors.push(("search_history".to_string(), format!("{:#}", e)));
+        }
+
         if let Err(e) = self.export_user_chanting_data(&import_dir) {
             errors.push(("chanting".to_string(), format!("{:#}", e)));
         }
@@ -2186,6 +2369,10 @@ impl AppData {
             errors.push(("user_dictionaries".to_string(), format!("{:#}", e)));
         }
 
+        if let Err(e) = crate::sync::folder::export_for_upgrade(&self.dbm, &import_dir.join("sync")) {
+            errors.push(("sync".to_string(), format!("{:#}", e)));
+        }
+
         // One-shot legacy bridge: if userdata.sqlite3 still exists (alpha testers
         // upgrading from the pre-consolidation two-DB layout), pull its user data
         // into the standard per-table import files and keep a safety-net copy.
@@ -2280,7 +2467,7 @@ impl AppData {
     }
 
     /// Export app settings to JSON file.
-    fn export_app_settings_json(&self, import_dir: &Path) -> Result<()> {
+    pub(crate) fn export_app_settings_json(&self, import_dir: &Path) -> Result<()> {
         let app_settings = self.app_settings_cache.read().expect("Failed to read app settings");
         let settings_json = serde_json::to_string_pretty(&*app_settings)
             .context("Failed to serialize app settings to JSON")?;
@@ -2332,7 +2519,7 @@ impl AppData {
     /// Export user-imported books to a new appdata.sqlite3 database.
     ///
     /// User-imported books are those not in the original dataset (identified by their UIDs).
-    fn export_user_books(&self, import_dir: &Path) -> Result<()> {
+    pub(crate) fn export_user_books(&self, import_dir: &Path) -> Result<()> {
         use crate::db::appdata_schema::{books, book_spine_items, book_resources};
         use crate::db::APPDATA_MIGRATIONS;
         use diesel::sqlite::SqliteConnection;
@@ -2456,6 +2643,7 @@ impl AppData {
     /// This reads from the "import-me" folder in the simsapa directory and imports:
     /// - app_settings.json: Restores the application settings
     /// - appdata-books.sqlite3: Imports user books back into the new database
+    /// - sync/: Restores the sync state, see `sync::folder::import_after_upgrade`
     ///
     /// After successful import, the import-me folder is deleted.
     ///
@@ -2491,11 +2679,37 @@ impl AppData {
             error(&format!("Failed to import user bookmarks: {}", e));
         }
 
+        // Import the reading state of suttas and book chapters
+        if let Err(e) = self.import_user_reading_state(&import_dir) {
+            error(&format!("Failed to import reading state: {}", e));
+        }
+
+        // Import the reading plans and enrollments
+        if let Err(e) = self.import_user_reading_plans(&import_dir) {
+            error(&format!("Failed to import reading plans: {}", e));
+        }
+
+        // Import the saved searches and the results seen so far
+        if let Err(e) = self.import_user_saved_searches(&import_dir) {
+            error(&format!("Failed to import saved searches: {}", e));
+        }
+
+        // Import the search history
+        if let Err(e) = self.import_user_search_history(&import_dir) {
+            error(&format!("Failed to import search history: {}", e));
+        }
+
         // Import user chanting data and recordings
         if let Err(e) = self.import_user_chanting_data(&import_dir) {
             error(&format!("Failed to import user chanting data: {}", e));
         }
 
+        // After the bookmarks and chanting, which the sync state is attached
+        // to again.
+        if let Err(e) = crate::sync::folder::import_after_upgrade(&self.dbm, &import_dir.join("sync")) {
+            error(&format!("Failed to import the sync state: {:#}", e));
+        }
+
         // Import user-imported dictionaries snapshot. Failure here must NOT
         // wipe the snapshot — the file remains in import-me/ for the next
         // startup attempt (the cleanup step below excludes it).
@@ -2610,7 +2824,7 @@ impl AppData {
     }
 
     /// Import app settings from JSON file.
-    fn import_app_settings_json(&self, import_dir: &Path) -> Result<()> {
+    pub(crate) fn import_app_settings_json(&self, import_dir: &Path) -> Result<()> {
         use crate::db::appdata_schema::app_settings;
 
         let settings_path = import_dir.join("app_settings.json");
@@ -2653,7 +2867,7 @@ impl AppData {
     /// Import user books from the export database.
     ///
     /// Reads books from the import-me/appdata-books.sqlite3 and inserts them into the new database.
-    fn import_user_books(&self, import_dir: &Path) -> Result<()> {
+    pub(crate) fn import_user_books(&self, import_dir: &Path) -> Result<()> {
         use crate::db::appdata_schema::{books, book_spine_items, book_resources};
         use diesel::sqlite::SqliteConnection;
 
@@ -2779,6 +2993,9 @@ impl AppData {
                     .with_context(|| format!("Failed to insert resource: {}", resource.resource_path))?;
             }
 
+            // Rebuild the citations index, which is not exported
+            Self::link_book_sutta_refs(db_conn, &book.uid);
+
             info(&format!("Imported book: {} with {} spine items and {} resources",
                          book.uid, spine_items.len(), resources.len()));
         }
@@ -2791,7 +3008,7 @@ impl AppData {
     ///
     /// Skips the "Last Session" folder (is_last_session = true) since it is
     /// transient state that should not survive a database upgrade.
-    fn export_user_bookmarks(&self, import_dir: &Path) -> Result<()> {
+    pub(crate) fn export_user_bookmarks(&self, import_dir: &Path) -> Result<()> {
         use crate::db::appdata_schema::{bookmark_folders, bookmark_items};
         use crate::db::APPDATA_MIGRATIONS;
         use diesel::sqlite::SqliteConnection;
@@ -2891,7 +3108,7 @@ impl AppData {
     ///
     /// Reads `appdata-bookmarks.sqlite3` and inserts all folders and items into the
     /// new database, remapping folder ids as needed.
-    fn import_user_bookmarks(&self, import_dir: &Path) -> Result<()> {
+    pub(crate) fn import_user_bookmarks(&self, import_dir: &Path) -> Result<()> {
         use crate::db::appdata_schema::{bookmark_folders, bookmark_items};
         use diesel::sqlite::SqliteConnection;
 
@@ -3016,12 +3233,357 @@ impl AppData {
         Ok(())
     }
 
+    /// Export the reading state to the import-me folder.
+    ///
+    /// Creates `appdata-reading-state.sqlite3` with the positions, progress,
+    /// reading time and finished marks of suttas and book chapters.
+    pub(crate) fn export_user_reading_state(&self, import_dir: &Path) -> Result<()> {
+        use crate::db::appdata_schema::reading_state;
+        use crate::db::APPDATA_MIGRATIONS;
+        use diesel::sqlite::SqliteConnection;
+        use diesel_migrations::MigrationHarness;
+
+        let db_conn = &mut self.dbm.appdata.get_conn()
+            .context("Failed to get appdata connection for reading state export")?;
+
+        let states: Vec<ReadingState> = reading_state::table
+            .select(ReadingState::as_select())
+            .load(db_conn)
+            .context("Failed to load reading state")?;
+
+        if states.is_empty() {
+            info("No reading state to export");
+            return Ok(());
+        }
+
+        let sqlite_path = import_dir.join("appdata-reading-state.sqlite3");
+        if let Ok(true) = sqlite_path.try_exists() {
+            std::fs::remove_file(&sqlite_path)
+                .with_context(|| format!("Failed to remove existing reading state export database: {}", sqlite_path.display()))?;
+        }
+
+        let db_url = format!("sqlite://{}", sqlite_path.display());
+        let mut export_conn = SqliteConnection::establish(&db_url)
+            .with_context(|| format!("Failed to create reading state export database: {}", sqlite_path.display()))?;
+
+        export_conn.run_pending_migrations(APPDATA_MIGRATIONS)
+            .map_err(|e| anyhow!("Failed to run migrations on reading state export database: {}", e))?;
+
+        for state in &states {
+            diesel::insert_into(reading_state::table)
+                .values(&state.as_new())
+                .execute(&mut export_conn)
+                .with_context(|| format!("Failed to insert reading state: {}", state.item_uid))?;
+        }
+
+        info(&format!("Exported {} reading state items to {}", states.len(), sqlite_path.display()));
+
+        Ok(())
+    }
+
+    /// Import the reading state from the import-me folder after database upgrade.
+    ///
+    /// Items which already have a reading state in the new database are kept.
+    pub(crate) fn import_user_reading_state(&self, import_dir: &Path) -> Result<()> {
+        use crate::db::appdata_schema::reading_state;
+        use diesel::sqlite::SqliteConnection;
+
+        let sqlite_path = import_dir.join("appdata-reading-state.sqlite3");
+        match sqlite_path.try_exists() {
+            Ok(true) => {}
+            _ => {
+                info("No appdata-reading-state.sqlite3 found in import-me folder");
+                return Ok(());
+            }
+        }
+
+        info(&format!("Importing reading state from {}", sqlite_path.display()));
+
+        let db_url = format!("sqlite://{}", sqlite_path.display());
+        let mut import_conn = SqliteConnection::establish(&db_url)
+            .with_context(|| format!("Failed to open reading state import database: {}", sqlite_path.display()))?;
+
+        let states: Vec<ReadingState> = reading_state::table
+            .select(ReadingState::as_select())
+            .load(&mut import_conn)
+            .context("Failed to load reading state from import database")?;
+
+        let db_conn = &mut self.dbm.appdata.get_conn()
+            .context("Failed to get appdata connection for reading state import")?;
+
+        let mut imported = 0usize;
+        for state in &states {
+            let exists = reading_state::table
+                .filter(reading_state::item_uid.eq(&state.item_uid))
+                .filter(reading_state::table_name.eq(&state.table_name))
+                .select(reading_state::id)
+                .first::<i32>(db_conn)
+                .optional()
+                .unwrap_or(None)
+                .is_some();
+
+            if exists {
+                continue;
+            }
+
+            diesel::insert_into(reading_state::table)
+                .values(&state.as_new())
+                .execute(db_conn)
+                .with_context(|| format!("Failed to insert reading state: {}", state.item_uid))?;
+            imported += 1;
+        }
+
+        info(&format!("Imported {} reading state items", imported));
+
+        Ok(())
+    }
+
+    /// Export the reading plans and the enrollments in them to the import-me
+    /// folder, as `reading-plans.json`.
+    pub(crate) fn export_user_reading_plans(&self, import_dir: &Path) -> Result<()> {
+        use crate::reading_plans::EnrolledReadingPlanJson;
+
+        let appdata = &self.dbm.appdata;
+        let summaries = appdata.get_reading_plans(crate::reading_plans::today())?;
+        if summaries.is_empty() {
+            info("No reading plans to export");
+            return Ok(());
+        }
+
+        let mut plans = Vec::new();
+        for summary in summaries.iter().filter(|s| s.plan.is_user_added) {
+            let Some(plan) = appdata.get_reading_plan(&summary.plan.uid)? else {
+                continue;
+            };
+            let enrollment = appdata.get_reading_plan_enrollment_json(&summary.plan.uid)?;
+            plans.push(EnrolledReadingPlanJson { plan, enrollment });
+        }
+
+        let json_path = import_dir.join("reading-plans.json");
+        let json = serde_json::to_string_pretty(&plans)
+            .context("Failed to serialize reading plans")?;
+        std::fs::write(&json_path, json)
+            .with_context(|| format!("Failed to write {}", json_path.display()))?;
+
+        info(&format!("Exported {} reading plans to {}", plans.len(), json_path.display()));
+        Ok(())
+    }
+
+    /// Import the reading plans and enrollments from the import-me folder
+    /// after database upgrade. Plans which are already in the database are
+    /// kept with their enrollment.
+    pub(crate) fn import_user_reading_plans(&self, import_dir: &Path) -> Result<()> {
+        use crate::reading_plans::EnrolledReadingPlanJson;
+
+        let json_path = import_dir.join("reading-plans.json");
+        match json_path.try_exists() {
+            Ok(true) => {}
+            _ => {
+                info("No reading-plans.json found in import-me folder");
+                return Ok(());
+            }
+        }
+
+        let json = std::fs::read_to_string(&json_path)
+            .with_context(|| format!("Failed to read {}", json_path.display()))?;
+        let plans: Vec<EnrolledReadingPlanJson> = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse {}", json_path.display()))?;
+
+        let appdata = &self.dbm.appdata;
+        let mut imported = 0;
+        for entry in &plans {
+            if appdata.get_reading_plan(&entry.plan.uid)?.is_some() {
+                continue;
+            }
+            appdata.save_reading_plan(&entry.plan)
+                .with_context(|| format!("Failed to import reading plan: {}", entry.plan.uid))?;
+            if let Some(enrollment) = &entry.enrollment {
+                appdata.enroll_in_reading_plan(&entry.plan.uid, enrollment.start_date)?;
+                appdata.set_reading_plan_active(&entry.plan.uid, enrollment.is_active)?;
+                for item_index in &enrollment.completed_items {
+                    appdata.set_reading_plan_item_completed(&entry.plan.uid, *item_index, true)?;
+                }
+            }
+            imported += 1;
+        }
+
+        info(&format!("Imported {} reading plans", imported));
+        Ok(())
+    }
+
+    /// Export the saved searches with the results seen so far to the
+    /// import-me folder, as `saved-searches.json`.
+    pub(crate) fn export_user_saved_searches(&self, import_dir: &Path) -> Result<()> {
+        let searches = crate::saved_searches::export_saved_searches(&self.dbm)?;
+        if searches.is_empty() {
+            info("No saved searches to export");
+            return Ok(());
+        }
+
+        let json_path = import_dir.join("saved-searches.json");
+        let json = serde_json::to_string_pretty(&searches)
+            .context("Failed to serialize saved searches")?;
+        std::fs::write(&json_path, json)
+            .with_context(|| format!("Failed to write {}", json_path.display()))?;
+
+        info(&format!("Exported {} saved searches to {}", searches.len(), json_path.display()));
+        Ok(())
+    }
+
+    /// Import the saved searches from the import-me folder after database
+    /// upgrade. They are run on the next start, to report the results found
+    /// in the new databases.
+    pub(crate) fn import_user_saved_searches(&self, import_dir: &Path) -> Result<()> {
+        if self.restore_user_saved_searches(import_dir)? > 0 {
+            crate::saved_searches::request_saved_searches_run("Database upgrade");
+        }
+        Ok(())
+    }
+
+    /// Add the saved searches of `saved-searches.json` in `import_dir` which
+    /// are not in the database, by name. Returns how many were added.
+    pub(crate) fn restore_user_saved_searches(&self, import_dir: &Path) -> Result<usize> {
+        use crate::saved_searches::SavedSearchExport;
+
+        let json_path = import_dir.join("saved-searches.json");
+        match json_path.try_exists() {
+            Ok(true) => {}
+            _ => {
+                info("No saved-searches.json found in import-me folder");
+                return Ok(0);
+            }
+        }
+
+        let json = std::fs::read_to_string(&json_path)
+            .with_context(|| format!("Failed to read {}", json_path.display()))?;
+        let searches: Vec<SavedSearchExport> = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse {}", json_path.display()))?;
+
+        let mut count = 0;
+        for entry in &searches {
+            let restored = self.dbm.appdata.restore_saved_search(&entry.saved_search, &entry.hits)
+                .with_context(|| format!("Failed to import saved search: {}", entry.saved_search.name))?;
+            if restored.is_some() {
+                count += 1;
+            }
+        }
+
+        info(&format!("Imported {} saved searches", count));
+        Ok(count)
+    }
+
+    /// Export the search history to the import-me folder.
+    ///
+    /// Creates `appdata-search-history.sqlite3` with the searches, their
+    /// counts and the last result opened from each.
+    pub(crate) fn export_user_search_history(&self, import_dir: &Path) -> Result<()> {
+        use crate::db::appdata_schema::search_history;
+        use crate::db::APPDATA_MIGRATIONS;
+        use diesel::sqlite::SqliteConnection;
+        use diesel_migrations::MigrationHarness;
+
+        let db_conn = &mut self.dbm.appdata.get_conn()
+            .context("Failed to get appdata connection for search history export")?;
+
+        let items: Vec<SearchHistoryItem> = search_history::table
+            .order(search_history::id.asc())
+            .select(SearchHistoryItem::as_select())
+            .load(db_conn)
+            .context("Failed to load search history")?;
+
+        if items.is_empty() {
+            info("No search history to export");
+            return Ok(());
+        }
+
+        let sqlite_path = import_dir.join("appdata-search-history.sqlite3");
+        if let Ok(true) = sqlite_path.try_exists() {
+            std::fs::remove_file(&sqlite_path)
+                .with_context(|| format!("Failed to remove existing search history export database: {}", sqlite_path.display()))?;
+        }
+
+        let db_url = format!("sqlite://{}", sqlite_path.display());
+        let mut export_conn = SqliteConnection::establish(&db_url)
+            .with_context(|| format!("Failed to create search history export database: {}", sqlite_path.display()))?;
+
+        export_conn.run_pending_migrations(APPDATA_MIGRATIONS)
+            .map_err(|e| anyhow!("Failed to run migrations on search history export database: {}", e))?;
+
+        for item in &items {
+            insert_search_history_copy(&mut export_conn, item)
+                .with_context(|| format!("Failed to insert search history: {}", item.query_text))?;
+        }
+
+        info(&format!("Exported {} search history items to {}", items.len(), sqlite_path.display()));
+
+        Ok(())
+    }
+
+    /// Import the search history from the import-me folder after database
+    /// upgrade.
+    ///
+    /// Searches which are already in the new database (same query, area and
+    /// params) are kept.
+    pub(crate) fn import_user_search_history(&self, import_dir: &Path) -> Result<()> {
+        use crate::db::appdata_schema::search_history;
+        use diesel::sqlite::SqliteConnection;
+
+        let sqlite_path = import_dir.join("appdata-search-history.sqlite3");
+        match sqlite_path.try_exists() {
+            Ok(true) => {}
+            _ => {
+                info("No appdata-search-history.sqlite3 found in import-me folder");
+                return Ok(());
+            }
+        }
+
+        info(&format!("Importing search history from {}", sqlite_path.display()));
+
+        let db_url = format!("sqlite://{}", sqlite_path.display());
+        let mut import_conn = SqliteConnection::establish(&db_url)
+            .with_context(|| format!("Failed to open search history import database: {}", sqlite_path.display()))?;
+
+        let items: Vec<SearchHistoryItem> = search_history::table
+            .order(search_history::id.asc())
+            .select(SearchHistoryItem::as_select())
+            .load(&mut import_conn)
+            .context("Failed to load search history from import database")?;
+
+        let db_conn = &mut self.dbm.appdata.get_conn()
+            .context("Failed to get appdata connection for search history import")?;
+
+        let mut imported = 0usize;
+        for item in &items {
+            let exists = search_history::table
+                .filter(search_history::query_text.eq(&item.query_text))
+                .filter(search_history::search_area.eq(&item.search_area))
+                .filter(search_history::params_json.eq(&item.params_json))
+                .select(search_history::id)
+                .first::<i32>(db_conn)
+                .optional()
+                .unwrap_or(None)
+                .is_some();
+
+            if exists {
+                continue;
+            }
+
+            insert_search_history_copy(db_conn, item)
+                .with_context(|| format!("Failed to insert search history: {}", item.query_text))?;
+            imported += 1;
+        }
+
+        info(&format!("Imported {} search history items", imported));
+
+        Ok(())
+    }
+
     /// Export user chanting data and all recordings to the import-me folder.
     ///
     /// Creates `appdata-chanting.sqlite3` containing user-added collections/chants/sections
     /// and ALL recordings (including those on pre-shipped sections).
     /// Copies the entire `chanting-recordings/` directory into the import folder.
-    fn export_user_chanting_data(&self, import_dir: &Path) -> Result<()> {
+    pub(crate) fn export_user_chanting_data(&self, import_dir: &Path) -> Result<()> {
         use crate::db::appdata_schema::chanting_collections::dsl as col_dsl;
         use crate::db::appdata_schema::chanting_chants::dsl as chant_dsl;
         use crate::db::appdata_schema::chanting_sections::dsl as sec_dsl;
@@ -3758,6 +4320,34 @@ impl AppData {
     }
 }
 
+/// Insert a copy of a search history row, with its count and the result
+/// opened from it, which `NewSearchHistoryItem` leaves to later updates.
+fn insert_search_history_copy(db_conn: &mut SqliteConnection, item: &SearchHistoryItem) -> QueryResult<()> {
+    use crate::db::appdata_schema::search_history;
+
+    let history_id = diesel::insert_into(search_history::table)
+        .values(&NewSearchHistoryItem {
+            query_text: &item.query_text,
+            search_area: &item.search_area,
+            search_mode: &item.search_mode,
+            params_json: &item.params_json,
+            hits_count: item.hits_count,
+            last_searched_at: item.last_searched_at,
+        })
+        .returning(search_history::id)
+        .get_result::<i32>(db_conn)?;
+
+    diesel::update(search_history::table.find(history_id))
+        .set((
+            search_history::search_count.eq(item.search_count),
+            search_history::opened_uid.eq(&item.opened_uid),
+            search_history::opened_table_name.eq(&item.opened_table_name),
+            search_history::opened_title.eq(&item.opened_title),
+        ))
+        .execute(db_conn)
+        .map(|_| ())
+}
+
 impl AppData {
     /// Persist the in-memory AppSettings cache back to the appdata DB.
     fn persist_app_settings(&self, settings: &AppSettings) {
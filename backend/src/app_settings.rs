@@ -113,6 +113,10 @@ pub struct AppSettings {
     /// `"Language"` and any missing entry both mean "no language filter".
     #[serde(default)]
     pub search_last_language: IndexMap<String, String>,
+    /// Whether the searches are recorded in the search history, which also
+    /// provides the query suggestions.
+    #[serde(default = "default_true")]
+    pub search_history_enabled: bool,
     /// Cached set of distinct `dict_words.dict_label` values for non-user-imported
     /// dictionaries. Populated at startup (and refreshed after user-dict
     /// import / delete / rename) so the dictionary search bar doesn't run a
@@ -181,6 +185,40 @@ pub struct AppSettings {
     /// Fixed item height in pixels (used when item_height_use_default is false)
     #[serde(default)]
     pub item_height_fixed: usize,
+    /// Folder shared with the other devices by e.g. Syncthing, to sync the
+    /// user data through on startup and exit. See `sync::folder`.
+    #[serde(default)]
+    pub sync_folder: Option<String>,
+    /// Serve the sync routes on the LAN, so that other devices can pair and
+    /// sync with this one. Off by default, the app server only listens on
+    /// localhost. See `api_server::build_sync_rocket()`.
+    #[serde(default)]
+    pub sync_lan_enabled: bool,
+    /// Port of the LAN sync listener.
+    #[serde(default = "default_sync_lan_port")]
+    pub sync_lan_port: u16,
+    /// Hours between the automatic backups of the user data, 0 to turn them
+    /// off. See `backup`.
+    #[serde(default = "default_backup_interval_hours")]
+    pub backup_interval_hours: u32,
+    /// Number of backups to keep.
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: usize,
+    /// Folder of the backups, `None` for `backups/` in the Simsapa folder.
+    #[serde(default)]
+    pub backup_folder: Option<String>,
+}
+
+fn default_backup_interval_hours() -> u32 {
+    24
+}
+
+fn default_backup_keep() -> usize {
+    7
+}
+
+fn default_sync_lan_port() -> u16 {
+    4849
 }
 
 fn default_true() -> bool {
@@ -350,6 +388,7 @@ table tr td \{ text-align: left; padding: 0.1em 0.5em; }
             dict_search_dict_enabled: IndexMap::new(),
             search_last_mode: IndexMap::new(),
             search_last_language: IndexMap::new(),
+            search_history_enabled: true,
             cached_shipped_source_uids: Vec::new(),
             cached_commentary_definitions_source_uids: Vec::new(),
             cached_sutta_languages: Vec::new(),
@@ -365,6 +404,12 @@ table tr td \{ text-align: left; padding: 0.1em 0.5em; }
             snippet_all_chars_after: default_snippet_all_chars_after(),
             item_height_use_default: true,
             item_height_fixed: 100,
+            sync_folder: None,
+            sync_lan_enabled: false,
+            sync_lan_port: default_sync_lan_port(),
+            backup_interval_hours: default_backup_interval_hours(),
+            backup_keep: default_backup_keep(),
+            backup_folder: None,
         }
     }
 }
@@ -0,0 +1,745 @@
+//! Delta updates of the shipped databases, in place of downloading the whole
+//! `.tar.bz2` archives of a new release.
+//!
+//! The bootstrap compares the databases of two releases and writes the rows
+//! which changed into a zip of small SQLite files, one per database, with a
+//! manifest. The app applies it to its databases in one transaction across
+//! all of them, and sets the new `db_version`. If anything fails or doesn't
+//! match, the transaction is rolled back and the databases are left as they
+//! were, and the full download is still there as before.
+//!
+//! Rows are matched between the releases by a key column, such as `uid`, and
+//! never by their `id`: the user's own rows (imported books and dictionaries,
+//! downloaded sutta languages) share the tables and the ids. A delta therefore
+//! leaves the rows it doesn't know about alone. See `TableSpec` for how the
+//! rows which reference a parent row by its `id` are handled.
+//!
+//! The app applies deltas on startup before opening the databases
+//! (`apply_pending_deltas()`), from the `delta-updates/` folder of the app
+//! assets, where `stage_delta()` puts them: downloaded, or copied from a USB
+//! stick for offline installs. See `docs/delta-updates.md`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Text};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::write::SimpleFileOptions;
+
+use crate::downloader;
+use crate::logger::{error, info, warn};
+
+/// Version of the delta layout. Newer deltas are not applied.
+pub const DELTA_FORMAT: u32 = 1;
+
+/// Folder of the app assets where the deltas wait for the next start.
+pub const PENDING_DELTAS_DIR: &str = "delta-updates";
+
+const MANIFEST_FILE: &str = "delta-manifest.json";
+const APPDATA_FILE: &str = "appdata.sqlite3";
+
+/// A parent row referenced by `id` from the `column` of the child table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParentRef {
+    pub column: String,
+    /// A keyed table with a single key column, listed before the child.
+    pub table: String,
+}
+
+/// How the rows of a table are matched between the releases.
+///
+/// - With a `key`, a row is updated in place (keeping its `id`), inserted or
+///   deleted by the key. The `id` column is left to the database unless it is
+///   the key, which only works for databases the app doesn't add rows to.
+/// - With a `parent` as well, the parent's key is stored in place of the
+///   parent's `id`, and looked up again when applying.
+/// - Without a `key`, the rows are the children of the `parent`, and all the
+///   children of a parent are replaced when any of them changed.
+///
+/// The `ignore` columns, such as `created_at`, which differ in every
+/// bootstrap, are neither compared nor updated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableSpec {
+    pub table: String,
+    #[serde(default)]
+    pub key: Vec<String>,
+    #[serde(default)]
+    pub parent: Option<ParentRef>,
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+impl TableSpec {
+    pub fn keyed(table: &str, key: &[&str]) -> Self {
+        TableSpec {
+            table: table.to_string(),
+            key: key.iter().map(|k| k.to_string()).collect(),
+            parent: None,
+            ignore: Vec::new(),
+        }
+    }
+
+    pub fn keyed_child(table: &str, key: &[&str], column: &str, parent: &str) -> Self {
+        TableSpec {
+            parent: Some(ParentRef { column: column.to_string(), table: parent.to_string() }),
+            ..Self::keyed(table, key)
+        }
+    }
+
+    pub fn children(table: &str, column: &str, parent: &str) -> Self {
+        Self::keyed_child(table, &[], column, parent)
+    }
+
+    pub fn ignoring(mut self, columns: &[&str]) -> Self {
+        self.ignore = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+}
+
+/// The tables of the shipped databases which a release delta updates. The
+/// language databases are downloaded separately and are not included.
+pub fn release_delta_tables() -> Vec<(&'static str, Vec<TableSpec>)> {
+    let ts = ["created_at", "updated_at"];
+    let dpd_tables: [(&str, &[&str]); 12] = [
+        ("bold_definitions", &["id"]),
+        ("db_info", &["id"]),
+        ("dpd_headwords", &["id"]),
+        ("dpd_roots", &["root"]),
+        ("family_compound", &["compound_family"]),
+        ("family_idiom", &["idiom"]),
+        ("family_root", &["root_family_key", "root_key"]),
+        ("family_set", &["set_col"]),
+        ("family_word", &["word_family"]),
+        ("inflection_templates", &["pattern"]),
+        ("lookup", &["lookup_key"]),
+        ("sutta_info", &["dpd_sutta"]),
+    ];
+
+    vec![
+        (APPDATA_FILE, vec![
+            TableSpec::keyed("suttas", &["uid"]).ignoring(&ts),
+            TableSpec::children("sutta_variants", "sutta_id", "suttas").ignoring(&ts),
+            TableSpec::children("sutta_comments", "sutta_id", "suttas").ignoring(&ts),
+            TableSpec::children("sutta_glosses", "sutta_id", "suttas").ignoring(&ts),
+            TableSpec::keyed("books", &["uid"]).ignoring(&ts),
+            TableSpec::keyed_child("book_spine_items", &["spine_item_uid"], "book_id", "books").ignoring(&ts),
+            TableSpec::children("book_resources", "book_id", "books").ignoring(&ts),
+            TableSpec::keyed("chanting_collections", &["uid"]).ignoring(&ts),
+            TableSpec::keyed("chanting_chants", &["uid"]).ignoring(&ts),
+            TableSpec::keyed("chanting_sections", &["uid"]).ignoring(&ts),
+        ]),
+        ("dictionaries.sqlite3", vec![
+            TableSpec::keyed("dictionaries", &["label"]).ignoring(&ts),
+            TableSpec::keyed_child("dict_words", &["uid"], "dictionary_id", "dictionaries").ignoring(&ts),
+            TableSpec::children("dict_resources", "dictionary_id", "dictionaries").ignoring(&ts),
+        ]),
+        ("dpd.sqlite3", dpd_tables.iter().map(|(t, key)| TableSpec::keyed(t, key).ignoring(&ts)).collect()),
+    ]
+}
+
+/// Changes of one table in the delta.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableDelta {
+    #[serde(flatten)]
+    pub spec: TableSpec,
+    /// Rows to insert or update.
+    pub rows: usize,
+    /// Rows to delete, or for children, the parents whose children are replaced.
+    pub deletes: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbDelta {
+    /// Database file in the app assets, e.g. `dpd.sqlite3`.
+    pub file: String,
+    /// The delta's SQLite file in the zip.
+    pub delta_file: String,
+    pub size: u64,
+    pub sha256: String,
+    pub tables: Vec<TableDelta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaManifest {
+    pub format: u32,
+    /// The `db_version` the delta applies to.
+    pub from_version: String,
+    pub to_version: String,
+    pub databases: Vec<DbDelta>,
+}
+
+impl DeltaManifest {
+    /// Rows inserted, updated or deleted by the delta.
+    pub fn total_changes(&self) -> usize {
+        self.databases.iter().flat_map(|d| &d.tables).map(|t| t.rows + t.deletes).sum()
+    }
+}
+
+/// Quote an SQL identifier.
+fn q(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Quote an SQL string literal.
+fn lit(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn key_list(prefix: &str, key: &[String]) -> String {
+    key.iter().map(|k| format!("{}{}", prefix, q(k))).collect::<Vec<_>>().join(", ")
+}
+
+/// Wrap a list of columns as a row value when it has more than one.
+fn row_value(cols: String, n: usize) -> String {
+    if n > 1 { format!("({})", cols) } else { cols }
+}
+
+#[derive(QueryableByName)]
+struct NameRow {
+    #[diesel(sql_type = Text)]
+    name: String,
+}
+
+#[derive(QueryableByName)]
+struct CountRow {
+    #[diesel(sql_type = BigInt)]
+    n: i64,
+}
+
+fn count(db_conn: &mut SqliteConnection, sql: &str) -> Result<usize> {
+    let row: CountRow = sql_query(sql).get_result(db_conn).with_context(|| format!("Query failed: {}", sql))?;
+    Ok(row.n as usize)
+}
+
+fn columns(db_conn: &mut SqliteConnection, schema: &str, table: &str) -> Result<Vec<String>> {
+    let rows: Vec<NameRow> = sql_query(format!("SELECT name FROM pragma_table_info({}, {})", lit(table), lit(schema)))
+        .load(db_conn)?;
+    if rows.is_empty() {
+        bail!("Table {}.{} not found", schema, table);
+    }
+    Ok(rows.into_iter().map(|r| r.name).collect())
+}
+
+fn sqlite_url(path: &Path) -> String {
+    format!("sqlite://{}", path.display())
+}
+
+/// SHA-256 checksum of a file, as lowercase hex.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Schema name of a database when attached, from its file name.
+fn schema_alias(file: &str) -> String {
+    let stem = file.strip_suffix(".sqlite3").unwrap_or(file);
+    stem.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+/// The columns of a table the delta stores: all but the `id` (unless it is
+/// part of the key), the ignored ones, and the parent reference, which is
+/// stored as `_parent_key`.
+fn data_columns(all: &[String], spec: &TableSpec) -> Vec<String> {
+    all.iter()
+        .filter(|c| c.as_str() != "id" || spec.key.contains(c))
+        .filter(|c| !spec.ignore.contains(c))
+        .filter(|c| spec.parent.as_ref().is_none_or(|p| &p.column != *c))
+        .cloned()
+        .collect()
+}
+
+/// The key column of a parent table, which must be a single column.
+fn parent_key<'a>(specs: &'a [TableSpec], parent: &ParentRef) -> Result<&'a str> {
+    let spec = specs.iter().find(|s| s.table == parent.table)
+        .ok_or_else(|| anyhow!("The parent table {} is not in the delta", parent.table))?;
+    match spec.key.as_slice() {
+        [k] => Ok(k),
+        _ => bail!("The parent table {} needs a single key column", parent.table),
+    }
+}
+
+/// The rows of a table as the delta stores them, from the database `schema`.
+fn projection(schema: &str, spec: &TableSpec, cols: &[String], parent_key: Option<&str>) -> String {
+    let select = cols.iter().map(|c| format!("c.{}", q(c))).collect::<Vec<_>>().join(", ");
+    match (&spec.parent, parent_key) {
+        (Some(p), Some(pk)) => format!(
+            "SELECT {}, p.{} AS _parent_key FROM {}.{} AS c LEFT JOIN {}.{} AS p ON p.id = c.{}",
+            select, q(pk), q(schema), q(&spec.table), q(schema), q(&p.table), q(&p.column)
+        ),
+        _ => format!("SELECT {} FROM {}.{} AS c", select, q(schema), q(&spec.table)),
+    }
+}
+
+/// Write the changed rows of the tables from `old_db` to `new_db` into the
+/// SQLite file `out`.
+fn diff_database(old_db: &Path, new_db: &Path, specs: &[TableSpec], out: &Path) -> Result<Vec<TableDelta>> {
+    let mut db_conn = SqliteConnection::establish(&sqlite_url(out))
+        .with_context(|| format!("Failed to create {}", out.display()))?;
+    db_conn.batch_execute(&format!(
+        "ATTACH DATABASE {} AS o; ATTACH DATABASE {} AS n;",
+        lit(&old_db.to_string_lossy()), lit(&new_db.to_string_lossy())
+    ))?;
+
+    let mut tables = Vec::new();
+    for spec in specs {
+        let t = &spec.table;
+        let new_cols = columns(&mut db_conn, "n", t)?;
+        let mut old_cols = columns(&mut db_conn, "o", t)?;
+        let mut sorted = new_cols.clone();
+        sorted.sort();
+        old_cols.sort();
+        if sorted != old_cols {
+            bail!("The columns of {} changed between the releases, the delta can't update it", t);
+        }
+
+        let cols = data_columns(&new_cols, spec);
+        let pk = match &spec.parent {
+            Some(p) => Some(parent_key(specs, p)?),
+            None => None,
+        };
+        let new_rows = projection("n", spec, &cols, pk);
+        let old_rows = projection("o", spec, &cols, pk);
+
+        if !spec.key.is_empty() {
+            let keys = key_list("", &spec.key);
+            db_conn.batch_execute(&format!(
+                "CREATE TABLE {t} AS SELECT * FROM ({new_rows} EXCEPT {old_rows});
+                 CREATE TABLE {deleted} AS SELECT {keys} FROM o.{t} EXCEPT SELECT {keys} FROM n.{t};",
+                t = q(t), deleted = q(&format!("_deleted_{}", t)),
+            )).with_context(|| format!("Failed to compare {}", t))?;
+            tables.push(TableDelta {
+                spec: spec.clone(),
+                rows: count(&mut db_conn, &format!("SELECT COUNT(*) AS n FROM {}", q(t)))?,
+                deletes: count(&mut db_conn, &format!("SELECT COUNT(*) AS n FROM {}", q(&format!("_deleted_{}", t))))?,
+            });
+        } else {
+            if spec.parent.is_none() {
+                bail!("{} needs a key or a parent", t);
+            }
+            let reset = q(&format!("_reset_{}", t));
+            db_conn.batch_execute(&format!(
+                "CREATE TABLE {reset} AS
+                     SELECT _parent_key FROM ({new_rows} EXCEPT {old_rows})
+                     UNION SELECT _parent_key FROM ({old_rows} EXCEPT {new_rows});
+                 CREATE TABLE {t} AS SELECT * FROM ({new_rows}) WHERE _parent_key IN (SELECT _parent_key FROM {reset});",
+                t = q(t),
+            )).with_context(|| format!("Failed to compare {}", t))?;
+            tables.push(TableDelta {
+                spec: spec.clone(),
+                rows: count(&mut db_conn, &format!("SELECT COUNT(*) AS n FROM {}", q(t)))?,
+                deletes: count(&mut db_conn, &format!("SELECT COUNT(*) AS n FROM {}", reset))?,
+            });
+        }
+    }
+
+    db_conn.batch_execute("DETACH DATABASE o; DETACH DATABASE n; VACUUM;")?;
+    Ok(tables)
+}
+
+/// Write the delta from the databases in `old_dir` (release `from_version`)
+/// to the ones in `new_dir` (release `to_version`) as the zip file `out`.
+/// `databases` lists the database files with the tables to compare.
+pub fn create_delta(
+    old_dir: &Path,
+    new_dir: &Path,
+    databases: &[(&str, Vec<TableSpec>)],
+    from_version: &str,
+    to_version: &str,
+    out: &Path,
+) -> Result<DeltaManifest> {
+    let staging = tempfile::tempdir().context("Failed to create temp directory")?;
+
+    let mut manifest = DeltaManifest {
+        format: DELTA_FORMAT,
+        from_version: from_version.to_string(),
+        to_version: to_version.to_string(),
+        databases: Vec::new(),
+    };
+
+    for (file, specs) in databases {
+        let delta_file = format!("{}.delta.sqlite3", schema_alias(file));
+        let delta_path = staging.path().join(&delta_file);
+        let tables = diff_database(&old_dir.join(file), &new_dir.join(file), specs, &delta_path)
+            .with_context(|| format!("Failed to compare {}", file))?;
+        manifest.databases.push(DbDelta {
+            file: file.to_string(),
+            size: fs::metadata(&delta_path)?.len(),
+            sha256: sha256_file(&delta_path)?,
+            delta_file,
+            tables,
+        });
+    }
+
+    let mut zip_writer = zip::ZipWriter::new(fs::File::create(out)
+        .with_context(|| format!("Failed to create {}", out.display()))?);
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+    zip_writer.start_file(MANIFEST_FILE, options)?;
+    zip_writer.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    for db in &manifest.databases {
+        zip_writer.start_file(db.delta_file.as_str(), options)?;
+        io::copy(&mut fs::File::open(staging.path().join(&db.delta_file))?, &mut zip_writer)?;
+    }
+    zip_writer.finish()?;
+
+    info(&format!(
+        "Created delta {} from {} to {} with {} changes",
+        out.display(), from_version, to_version, manifest.total_changes()
+    ));
+    Ok(manifest)
+}
+
+/// Read the manifest of a delta.
+pub fn read_delta_manifest(path: &Path) -> Result<DeltaManifest> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?)
+        .with_context(|| format!("{} is not a zip file", path.display()))?;
+    let mut text = String::new();
+    archive.by_name(MANIFEST_FILE)
+        .map_err(|_| anyhow!("{} is not a delta update", path.display()))?
+        .read_to_string(&mut text)?;
+    let manifest: DeltaManifest = serde_json::from_str(&text).context("Failed to parse the delta manifest")?;
+    if manifest.format > DELTA_FORMAT {
+        bail!("The delta update needs a newer version of the app (format {})", manifest.format);
+    }
+    Ok(manifest)
+}
+
+/// Extract the delta's SQLite files to `dest`, checking their checksums.
+fn extract_delta(path: &Path, manifest: &DeltaManifest, dest: &Path) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+    for db in &manifest.databases {
+        let mut entry = archive.by_name(&db.delta_file)
+            .map_err(|_| anyhow!("The delta update is missing {}", db.delta_file))?;
+        let out_path = dest.join(&db.delta_file);
+        let mut hasher = Sha256::new();
+        let mut out = fs::File::create(&out_path)?;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = entry.read(&mut buf).with_context(|| format!("{} is damaged", db.delta_file))?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            out.write_all(&buf[..n])?;
+        }
+        if format!("{:x}", hasher.finalize()) != db.sha256 {
+            bail!("The checksum of {} doesn't match", db.delta_file);
+        }
+    }
+    Ok(())
+}
+
+/// Check that a delta is complete and its checksums match. Returns the manifest.
+pub fn verify_delta(path: &Path) -> Result<DeltaManifest> {
+    let manifest = read_delta_manifest(path)?;
+    let check_dir = tempfile::tempdir().context("Failed to create temp directory")?;
+    extract_delta(path, &manifest, check_dir.path())?;
+    Ok(manifest)
+}
+
+/// The `db_version` of the databases in the app assets.
+pub fn read_db_version(assets_dir: &Path) -> Result<Option<String>> {
+    #[derive(QueryableByName)]
+    struct ValueRow {
+        #[diesel(sql_type = diesel::sql_types::Nullable<Text>)]
+        value: Option<String>,
+    }
+
+    let path = assets_dir.join(APPDATA_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let mut db_conn = SqliteConnection::establish(&sqlite_url(&path))?;
+    let row: Option<ValueRow> = sql_query("SELECT value FROM app_settings WHERE key = 'db_version'")
+        .get_result(&mut db_conn)
+        .optional()?;
+    Ok(row.and_then(|r| r.value))
+}
+
+/// Apply the changes of one table. `live` and `delta` are the schema names of
+/// the app's database and of the delta.
+fn apply_table(db_conn: &mut SqliteConnection, live: &str, delta: &str, specs: &[TableSpec], spec: &TableSpec) -> Result<()> {
+    let t = &spec.table;
+    let (l, d) = (q(live), q(delta));
+    let cols: Vec<String> = columns(db_conn, delta, t)?.into_iter().filter(|c| c != "_parent_key").collect();
+    let col_list = cols.iter().map(|c| q(c)).collect::<Vec<_>>().join(", ");
+    let x_cols = cols.iter().map(|c| format!("x.{}", q(c))).collect::<Vec<_>>().join(", ");
+
+    // The parent's id in the app's database, by the parent key.
+    let (insert_cols, insert_values) = match &spec.parent {
+        Some(p) => {
+            let pk = parent_key(specs, p)?;
+            let missing = count(db_conn, &format!(
+                "SELECT COUNT(*) AS n FROM {d}.{t} AS x WHERE NOT EXISTS (SELECT 1 FROM {l}.{p} WHERE {pk} = x._parent_key)",
+                t = q(t), p = q(&p.table), pk = q(pk),
+            ))?;
+            if missing > 0 {
+                bail!("{} rows of {} have no parent in {}", missing, t, p.table);
+            }
+            (
+                format!("{}, {}", col_list, q(&p.column)),
+                format!("{}, (SELECT id FROM {l}.{p} WHERE {pk} = x._parent_key)", x_cols, p = q(&p.table), pk = q(pk)),
+            )
+        }
+        None => (col_list.clone(), x_cols.clone()),
+    };
+
+    if spec.key.is_empty() {
+        // The old children were removed in `reset_children()`.
+        db_conn.batch_execute(&format!(
+            "INSERT INTO {l}.{t} ({insert_cols}) SELECT {insert_values} FROM {d}.{t} AS x;",
+            t = q(t),
+        ))?;
+        return Ok(());
+    }
+
+    let n = spec.key.len();
+    let keys = row_value(key_list("", &spec.key), n);
+    let x_keys = row_value(key_list("x.", &spec.key), n);
+    let match_key = spec.key.iter()
+        .map(|k| format!("x.{k} = {l}.{t}.{k}", k = q(k), t = q(t)))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let deleted = q(&format!("_deleted_{}", t));
+
+    db_conn.batch_execute(&format!(
+        "DELETE FROM {l}.{t} WHERE {keys} IN (SELECT {key_cols} FROM {d}.{deleted});
+         UPDATE {l}.{t} SET ({insert_cols}) = (SELECT {insert_values} FROM {d}.{t} AS x WHERE {match_key})
+             WHERE {keys} IN (SELECT {key_cols} FROM {d}.{t});
+         INSERT INTO {l}.{t} ({insert_cols}) SELECT {insert_values} FROM {d}.{t} AS x
+             WHERE {x_keys} NOT IN (SELECT {key_cols} FROM {l}.{t});",
+        t = q(t), key_cols = key_list("", &spec.key),
+    )).with_context(|| format!("Failed to update {}", t))?;
+
+    // Every row of the delta is there now, and none of the deleted ones.
+    let missing = count(db_conn, &format!(
+        "SELECT COUNT(*) AS n FROM {d}.{t} AS x WHERE {x_keys} NOT IN (SELECT {key_cols} FROM {l}.{t})",
+        t = q(t), key_cols = key_list("", &spec.key),
+    ))? + count(db_conn, &format!(
+        "SELECT COUNT(*) AS n FROM {d}.{deleted} AS x WHERE {x_keys} IN (SELECT {key_cols} FROM {l}.{t})",
+        t = q(t), key_cols = key_list("", &spec.key),
+    ))?;
+    if missing > 0 {
+        bail!("{} rows of {} were not updated", missing, t);
+    }
+    Ok(())
+}
+
+/// Remove the children whose parents the delta replaces the children of.
+fn reset_children(db_conn: &mut SqliteConnection, live: &str, delta: &str, specs: &[TableSpec], spec: &TableSpec) -> Result<()> {
+    let Some(p) = &spec.parent else {
+        return Ok(());
+    };
+    let pk = parent_key(specs, p)?;
+    db_conn.batch_execute(&format!(
+        "DELETE FROM {l}.{t} WHERE {c} IN (SELECT id FROM {l}.{p} WHERE {pk} IN (SELECT _parent_key FROM {d}.{reset}));",
+        l = q(live), d = q(delta), t = q(&spec.table), c = q(&p.column), p = q(&p.table), pk = q(pk),
+        reset = q(&format!("_reset_{}", spec.table)),
+    )).with_context(|| format!("Failed to remove the old rows of {}", spec.table))?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaReport {
+    pub from_version: String,
+    pub to_version: String,
+    /// Rows inserted, updated or deleted, per `database/table`.
+    pub changes: BTreeMap<String, usize>,
+}
+
+/// Apply the delta at `path` to the databases in `assets_dir`, which must not
+/// be open elsewhere, all in one transaction. Leaves the fulltext index to be
+/// rebuilt, since the indexed rows changed.
+pub fn apply_delta(path: &Path, assets_dir: &Path) -> Result<DeltaReport> {
+    let manifest = read_delta_manifest(path)?;
+    let current = read_db_version(assets_dir)?;
+    if current.as_deref() != Some(manifest.from_version.as_str()) {
+        bail!(
+            "The delta updates the databases from {} to {}, but they are {}",
+            manifest.from_version, manifest.to_version, current.as_deref().unwrap_or("not installed")
+        );
+    }
+    for db in &manifest.databases {
+        if !assets_dir.join(&db.file).is_file() {
+            bail!("{} is not installed", db.file);
+        }
+    }
+
+    let staging = tempfile::tempdir().context("Failed to create temp directory")?;
+    extract_delta(path, &manifest, staging.path())?;
+
+    // The transaction is atomic across the attached databases, with appdata as
+    // the main one, which holds the db_version.
+    let mut db_conn = SqliteConnection::establish(&sqlite_url(&assets_dir.join(APPDATA_FILE)))?;
+    db_conn.batch_execute("PRAGMA busy_timeout = 5000;")?;
+    let mut schemas = Vec::new();
+    for db in &manifest.databases {
+        let live = if db.file == APPDATA_FILE {
+            "main".to_string()
+        } else {
+            let alias = schema_alias(&db.file);
+            db_conn.batch_execute(&format!(
+                "ATTACH DATABASE {} AS {};", lit(&assets_dir.join(&db.file).to_string_lossy()), q(&alias)
+            ))?;
+            alias
+        };
+        let delta = format!("delta_{}", schema_alias(&db.file));
+        db_conn.batch_execute(&format!(
+            "ATTACH DATABASE {} AS {};", lit(&staging.path().join(&db.delta_file).to_string_lossy()), q(&delta)
+        ))?;
+        schemas.push((live, delta));
+    }
+
+    let mut changes = BTreeMap::new();
+    db_conn.immediate_transaction::<_, anyhow::Error, _>(|db_conn| {
+        for (db, (live, delta)) in manifest.databases.iter().zip(&schemas) {
+            let specs: Vec<TableSpec> = db.tables.iter().map(|t| t.spec.clone()).collect();
+            // Children go before their parents are deleted, and come back after
+            // the parents are inserted.
+            for spec in specs.iter().filter(|s| s.key.is_empty()).rev() {
+                reset_children(db_conn, live, delta, &specs, spec)?;
+            }
+            for spec in specs.iter().filter(|s| !s.key.is_empty()) {
+                apply_table(db_conn, live, delta, &specs, spec)?;
+            }
+            for spec in specs.iter().filter(|s| s.key.is_empty()) {
+                apply_table(db_conn, live, delta, &specs, spec)?;
+            }
+            for t in &db.tables {
+                changes.insert(format!("{}/{}", db.file, t.spec.table), t.rows + t.deletes);
+            }
+        }
+
+        sql_query("UPDATE main.app_settings SET value = ? WHERE key = 'db_version'")
+            .bind::<Text, _>(&manifest.to_version)
+            .execute(db_conn)?;
+        Ok(())
+    })?;
+
+    let version_file = assets_dir.join("index").join("VERSION");
+    if version_file.is_file() && manifest.total_changes() > 0
+        && let Err(e) = fs::remove_file(&version_file) {
+            warn(&format!("Failed to mark the search index for rebuilding: {}", e));
+        }
+
+    info(&format!("Applied the delta update from {} to {}", manifest.from_version, manifest.to_version));
+    Ok(DeltaReport { from_version: manifest.from_version, to_version: manifest.to_version, changes })
+}
+
+/// Check a delta and copy it into the pending deltas of `assets_dir`, to be
+/// applied on the next start. Returns the manifest.
+pub fn stage_delta(path: &Path, assets_dir: &Path) -> Result<DeltaManifest> {
+    let manifest = verify_delta(path)?;
+    let pending = assets_dir.join(PENDING_DELTAS_DIR);
+    fs::create_dir_all(&pending).with_context(|| format!("Failed to create {}", pending.display()))?;
+
+    let dest = pending.join(format!("{}-to-{}.zip", manifest.from_version, manifest.to_version));
+    if path != dest {
+        fs::copy(path, &dest).with_context(|| format!("Failed to copy {} to {}", path.display(), dest.display()))?;
+    }
+    info(&format!("Staged the delta update to {} at {}", manifest.to_version, dest.display()));
+    Ok(manifest)
+}
+
+/// Apply the pending deltas of `assets_dir` in order from the installed
+/// version. Applied deltas and the ones which don't apply are removed. A
+/// failed delta leaves the databases as they were, and is kept with the ones
+/// after it, to be tried again on the next start.
+pub fn apply_pending_deltas(assets_dir: &Path) -> Vec<DeltaReport> {
+    let pending = assets_dir.join(PENDING_DELTAS_DIR);
+    let mut reports = Vec::new();
+    if !pending.is_dir() {
+        return reports;
+    }
+
+    let mut deltas: Vec<(PathBuf, DeltaManifest)> = Vec::new();
+    for entry in fs::read_dir(&pending).into_iter().flatten().flatten() {
+        let path = entry.path();
+        match read_delta_manifest(&path) {
+            Ok(m) => deltas.push((path, m)),
+            Err(e) => {
+                error(&format!("Removing {}: {:#}", path.display(), e));
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+
+    loop {
+        let current = match read_db_version(assets_dir) {
+            Ok(v) => v,
+            Err(e) => {
+                error(&format!("Failed to read the database version, keeping the delta updates: {:#}", e));
+                return reports;
+            }
+        };
+        let Some(i) = deltas.iter().position(|(_, m)| Some(&m.from_version) == current.as_ref()) else {
+            break;
+        };
+        let (path, _) = deltas.remove(i);
+        match apply_delta(&path, assets_dir) {
+            Ok(report) => {
+                reports.push(report);
+                let _ = fs::remove_file(&path);
+            }
+            Err(e) => {
+                error(&format!("Failed to apply the delta update {}, keeping it to try again: {:#}", path.display(), e));
+                return reports;
+            }
+        }
+    }
+
+    for (path, m) in deltas {
+        warn(&format!("Removing the delta update {} from {}, which doesn't apply", path.display(), m.from_version));
+        let _ = fs::remove_file(&path);
+    }
+    let _ = fs::remove_dir(&pending);
+    reports
+}
+
+/// Download a delta to `dest_dir`, checking its SHA-256 checksum.
+pub fn download_delta(url: &str, sha256: &str, dest_dir: &Path) -> Result<PathBuf> {
+    let file_name = url.rsplit('/').next().filter(|s| !s.is_empty()).unwrap_or("delta.zip");
+    fs::create_dir_all(dest_dir)?;
+    let dest = dest_dir.join(file_name);
+
+    downloader::download_file(url, &dest)?;
+    if !sha256_file(&dest)?.eq_ignore_ascii_case(sha256) {
+        let _ = fs::remove_file(&dest);
+        bail!("The checksum of the downloaded {} doesn't match", file_name);
+    }
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_columns() {
+        let all: Vec<String> = ["id", "uid", "dictionary_id", "word"].iter().map(|s| s.to_string()).collect();
+        let keyed = TableSpec::keyed("dict_words", &["uid"]);
+        assert_eq!(data_columns(&all, &keyed), vec!["uid", "dictionary_id", "word"]);
+        let child = TableSpec::keyed_child("dict_words", &["uid"], "dictionary_id", "dictionaries");
+        assert_eq!(data_columns(&all, &child), vec!["uid", "word"]);
+        let by_id = TableSpec::keyed("dpd_headwords", &["id"]);
+        assert_eq!(data_columns(&all, &by_id), all);
+        let ignoring = TableSpec::keyed("dict_words", &["uid"]).ignoring(&["word"]);
+        assert_eq!(data_columns(&all, &ignoring), vec!["uid", "dictionary_id"]);
+    }
+
+    #[test]
+    fn test_schema_alias() {
+        assert_eq!(schema_alias("dpd.sqlite3"), "dpd");
+        assert_eq!(schema_alias("suttas-lang.sqlite3"), "suttas_lang");
+    }
+}
@@ -10,6 +10,7 @@ use crate::db::appdata_models::Sutta;
 use crate::db::appdata_schema::suttas;
 use crate::normalize_path_for_sqlite;
 use crate::helpers::sutta_range_from_ref;
+use crate::saved_searches::request_saved_searches_run;
 
 /// Import suttas from language database files into appdata
 ///
@@ -55,6 +56,9 @@ pub fn import_suttas_lang_to_appdata(extract_temp_dir: &Path, target_database_ur
                     // Remove the language db file after successful import
                     let _ = std::fs::remove_file(&path);
                     imported_any = true;
+
+                    let lang = file_name.trim_start_matches("suttas_lang_").trim_end_matches(".sqlite3");
+                    request_saved_searches_run(&format!("Language pack: {}", lang));
                 }
                 Err(e) => {
                     error(&format!("Failed to import {}: {}", file_name, e));
@@ -0,0 +1,332 @@
+//! Export of chanting recordings for sharing outside the app.
+//!
+//! Recordings are stored as canonical FLAC, which many music players on
+//! phones don't handle. This module decodes selected recordings, optionally
+//! trims them to their range markers and normalizes their loudness
+//! ([`crate::audio::loudness`]), and writes either one file per recording or
+//! a single concatenated file with a CUE sheet, e.g. a full evening chanting
+//! for lay members.
+//!
+//! WAV and FLAC are pure Rust. Ogg Opus is left out, as there is no stable
+//! Rust Opus encoder release (see `docs/pure-rust-audio-backend.md`).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::format::{f32_to_i16, resample_mono_to, CANONICAL_SAMPLE_RATE};
+use crate::audio::loudness::{normalize_loudness, DEFAULT_TARGET_LUFS};
+use crate::audio::player::decode_to_mono;
+use crate::audio::practice_compare::reference_segments_from_markers_json;
+use crate::audio::recorder::encode_canonical_pcm_to_flac;
+use crate::logger::{info, warn};
+
+/// Output container / codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioExportFormat {
+    Wav,
+    Flac,
+}
+
+impl AudioExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioExportFormat::Wav => "wav",
+            AudioExportFormat::Flac => "flac",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "wav" => Some(AudioExportFormat::Wav),
+            "flac" => Some(AudioExportFormat::Flac),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioExportOptions {
+    pub format: AudioExportFormat,
+    /// Export only the range markers of each recording, one clip per range.
+    pub trim_to_markers: bool,
+    pub normalize_loudness: bool,
+    pub target_lufs: f32,
+    /// Write all clips into one file with a CUE sheet instead of one file each.
+    pub concatenate: bool,
+    /// Silence inserted between clips when concatenating.
+    pub gap_ms: u32,
+}
+
+impl Default for AudioExportOptions {
+    fn default() -> Self {
+        AudioExportOptions {
+            format: AudioExportFormat::Wav,
+            trim_to_markers: false,
+            normalize_loudness: true,
+            target_lufs: DEFAULT_TARGET_LUFS,
+            concatenate: false,
+            gap_ms: 1500,
+        }
+    }
+}
+
+/// One recording to export, in playlist order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioExportItem {
+    pub title: String,
+    pub file_path: PathBuf,
+    pub markers_json: Option<String>,
+}
+
+/// A titled span of the exported audio, in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioChapter {
+    pub title: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// Files written by [`export_audio`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioExportResult {
+    pub audio_files: Vec<PathBuf>,
+    pub cue_file: Option<PathBuf>,
+    pub chapters: Vec<AudioChapter>,
+}
+
+/// A decoded, processed piece of audio at the canonical rate.
+struct Clip {
+    title: String,
+    samples: Vec<f32>,
+}
+
+fn ms_to_samples(ms: i64) -> usize {
+    (ms.max(0) as u64 * CANONICAL_SAMPLE_RATE as u64 / 1000) as usize
+}
+
+fn samples_to_ms(n: usize) -> i64 {
+    (n as u64 * 1000 / CANONICAL_SAMPLE_RATE as u64) as i64
+}
+
+/// Decode an item to canonical-rate mono and cut it into clips.
+fn load_clips(item: &AudioExportItem, options: &AudioExportOptions) -> Result<Vec<Clip>> {
+    let (mono, rate) = decode_to_mono(&item.file_path)?;
+    let samples = resample_mono_to(&mono, rate, CANONICAL_SAMPLE_RATE)?;
+
+    let segments = if options.trim_to_markers {
+        item.markers_json
+            .as_deref()
+            .map(reference_segments_from_markers_json)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let mut clips: Vec<Clip> = if segments.is_empty() {
+        vec![Clip { title: item.title.clone(), samples }]
+    } else {
+        segments
+            .iter()
+            .filter_map(|seg| {
+                let start = ms_to_samples(seg.start_ms).min(samples.len());
+                let end = ms_to_samples(seg.end_ms).min(samples.len());
+                if end <= start {
+                    return None;
+                }
+                let title = if seg.label.is_empty() {
+                    item.title.clone()
+                } else {
+                    format!("{} – {}", item.title, seg.label)
+                };
+                Some(Clip { title, samples: samples[start..end].to_vec() })
+            })
+            .collect()
+    };
+
+    if options.normalize_loudness {
+        for clip in clips.iter_mut() {
+            if normalize_loudness(&mut clip.samples, options.target_lufs).is_none() {
+                warn(&format!("Loudness not measurable, left unchanged: {}", clip.title));
+            }
+        }
+    }
+
+    Ok(clips)
+}
+
+/// Write 16-bit mono PCM as a RIFF/WAVE file.
+pub fn write_wav(path: &Path, pcm: &[i16], sample_rate: u32) -> Result<()> {
+    let data_len = (pcm.len() * 2) as u32;
+    let mut buf: Vec<u8> = Vec::with_capacity(44 + data_len as usize);
+    buf.extend_from_slice(b"RIFF");
+    buf.extend_from_slice(&(36 + data_len).to_le_bytes());
+    buf.extend_from_slice(b"WAVE");
+    buf.extend_from_slice(b"fmt ");
+    buf.extend_from_slice(&16u32.to_le_bytes());
+    buf.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    buf.extend_from_slice(&1u16.to_le_bytes()); // mono
+    buf.extend_from_slice(&sample_rate.to_le_bytes());
+    buf.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    buf.extend_from_slice(&2u16.to_le_bytes()); // block align
+    buf.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&data_len.to_le_bytes());
+    for s in pcm {
+        buf.extend_from_slice(&s.to_le_bytes());
+    }
+    fs::write(path, buf).with_context(|| format!("Failed to write WAV file: {}", path.display()))
+}
+
+/// Write canonical PCM in the requested format.
+fn write_audio(path: &Path, samples: &[f32], format: AudioExportFormat) -> Result<()> {
+    let pcm = f32_to_i16(samples);
+    match format {
+        AudioExportFormat::Wav => write_wav(path, &pcm, CANONICAL_SAMPLE_RATE),
+        AudioExportFormat::Flac => encode_canonical_pcm_to_flac(&pcm, path),
+    }
+}
+
+/// Format a CUE sheet time as `MM:SS:FF` (75 frames per second).
+fn cue_time(ms: i64) -> String {
+    let ms = ms.max(0);
+    let frames = (ms % 1000) * 75 / 1000;
+    format!("{:02}:{:02}:{:02}", ms / 60_000, (ms / 1000) % 60, frames)
+}
+
+fn cue_escape(s: &str) -> String {
+    s.replace('"', "'")
+}
+
+/// Render a CUE sheet describing `chapters` as tracks of `audio_file_name`.
+pub fn render_cue_sheet(title: &str, audio_file_name: &str, chapters: &[AudioChapter]) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("TITLE \"{}\"\n", cue_escape(title)));
+    out.push_str(&format!("FILE \"{}\" WAVE\n", cue_escape(audio_file_name)));
+    for (i, ch) in chapters.iter().enumerate() {
+        out.push_str(&format!("  TRACK {:02} AUDIO\n", i + 1));
+        out.push_str(&format!("    TITLE \"{}\"\n", cue_escape(&ch.title)));
+        out.push_str(&format!("    INDEX 01 {}\n", cue_time(ch.start_ms)));
+    }
+    out
+}
+
+/// Make a title safe to use as a file name.
+pub fn sanitize_file_stem(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' { c } else { '_' })
+        .collect();
+    let trimmed = cleaned.trim().replace(' ', "_");
+    if trimmed.is_empty() { "recording".to_string() } else { trimmed }
+}
+
+/// Export `items` into `dest_dir`.
+///
+/// With `options.concatenate`, all clips go into `{playlist_title}.{ext}` with
+/// `options.gap_ms` of silence between them, plus a `.cue` sheet listing them.
+/// Otherwise each clip is written to its own numbered file.
+pub fn export_audio(
+    items: &[AudioExportItem],
+    dest_dir: &Path,
+    playlist_title: &str,
+    options: &AudioExportOptions,
+) -> Result<AudioExportResult> {
+    if items.is_empty() {
+        return Err(anyhow!("Nothing to export"));
+    }
+
+    fs::create_dir_all(dest_dir)
+        .with_context(|| format!("Failed to create export directory: {}", dest_dir.display()))?;
+
+    let mut clips: Vec<Clip> = Vec::new();
+    for item in items {
+        match load_clips(item, options) {
+            Ok(c) => clips.extend(c),
+            Err(e) => warn(&format!("Skipping recording {}: {}", item.file_path.display(), e)),
+        }
+    }
+    if clips.is_empty() {
+        return Err(anyhow!("None of the recordings could be decoded"));
+    }
+
+    let ext = options.format.extension();
+    let mut result = AudioExportResult { audio_files: Vec::new(), cue_file: None, chapters: Vec::new() };
+
+    if options.concatenate {
+        let gap = vec![0.0f32; ms_to_samples(options.gap_ms as i64)];
+        let mut joined: Vec<f32> = Vec::new();
+        for (i, clip) in clips.iter().enumerate() {
+            if i > 0 {
+                joined.extend_from_slice(&gap);
+            }
+            let start_ms = samples_to_ms(joined.len());
+            joined.extend_from_slice(&clip.samples);
+            result.chapters.push(AudioChapter {
+                title: clip.title.clone(),
+                start_ms,
+                end_ms: samples_to_ms(joined.len()),
+            });
+        }
+
+        let stem = sanitize_file_stem(playlist_title);
+        let audio_path = dest_dir.join(format!("{}.{}", stem, ext));
+        write_audio(&audio_path, &joined, options.format)?;
+
+        let cue_path = dest_dir.join(format!("{}.cue", stem));
+        let file_name = audio_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        fs::write(&cue_path, render_cue_sheet(playlist_title, file_name, &result.chapters))
+            .with_context(|| format!("Failed to write CUE sheet: {}", cue_path.display()))?;
+
+        result.audio_files.push(audio_path);
+        result.cue_file = Some(cue_path);
+    } else {
+        for (i, clip) in clips.iter().enumerate() {
+            let audio_path = dest_dir.join(format!("{:02}_{}.{}", i + 1, sanitize_file_stem(&clip.title), ext));
+            let chapter = AudioChapter { title: clip.title.clone(), start_ms: 0, end_ms: samples_to_ms(clip.samples.len()) };
+            write_audio(&audio_path, &clip.samples, options.format)?;
+            result.audio_files.push(audio_path);
+            result.chapters.push(chapter);
+        }
+    }
+
+    info(&format!("Exported {} audio file(s) to {}", result.audio_files.len(), dest_dir.display()));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cue_sheet_lists_chapters_with_frame_times() {
+        let chapters = vec![
+            AudioChapter { title: "Namo \"tassa\"".to_string(), start_ms: 0, end_ms: 5000 },
+            AudioChapter { title: "Tisaraṇa".to_string(), start_ms: 65_500, end_ms: 90_000 },
+        ];
+        let cue = render_cue_sheet("Evening Chanting", "Evening_Chanting.wav", &chapters);
+        assert!(cue.contains("FILE \"Evening_Chanting.wav\" WAVE"));
+        assert!(cue.contains("TRACK 01 AUDIO\n    TITLE \"Namo 'tassa'\"\n    INDEX 01 00:00:00"));
+        assert!(cue.contains("TRACK 02 AUDIO\n    TITLE \"Tisaraṇa\"\n    INDEX 01 01:05:37"));
+    }
+
+    #[test]
+    fn file_stems_are_sanitized() {
+        assert_eq!(sanitize_file_stem("Evening / Chanting: 1"), "Evening___Chanting__1");
+        assert_eq!(sanitize_file_stem("Tisaraṇa"), "Tisaraṇa");
+        assert_eq!(sanitize_file_stem("  "), "recording");
+    }
+
+    #[test]
+    fn format_names_round_trip() {
+        for f in [AudioExportFormat::Wav, AudioExportFormat::Flac] {
+            assert_eq!(AudioExportFormat::from_name(f.extension()), Some(f));
+        }
+        assert_eq!(AudioExportFormat::from_name("mp3"), None);
+        assert_eq!(AudioExportFormat::from_name("opus"), None);
+    }
+}
@@ -90,7 +90,7 @@ pub fn resample_mono_to(mono: &[f32], in_rate: u32, out_rate: u32) -> Result<Vec
 
 /// Convert a slice of `f32` samples in the range `[-1.0, 1.0]` to `i16` PCM,
 /// clamping out-of-range values.
-fn f32_to_i16(samples: &[f32]) -> Vec<i16> {
+pub(crate) fn f32_to_i16(samples: &[f32]) -> Vec<i16> {
     samples
         .iter()
         .map(|&s| {
@@ -0,0 +1,172 @@
+//! EBU R128 loudness measurement and normalization.
+//!
+//! Implements the ITU-R BS.1770-4 integrated loudness of a mono signal at the
+//! canonical 48 kHz rate: K-weighting (high shelf + high pass), 400 ms blocks
+//! with 75% overlap, an absolute gate at -70 LUFS and a relative gate 10 LU
+//! below the ungated mean. Used by the audio export to bring recordings made
+//! on different devices to a common level.
+
+use crate::audio::format::CANONICAL_SAMPLE_RATE;
+
+/// Loudness target used when exporting for sharing (music-player level).
+pub const DEFAULT_TARGET_LUFS: f32 = -16.0;
+/// Peak ceiling applied after normalization, in dBFS.
+pub const PEAK_CEILING_DBFS: f32 = -1.0;
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// A direct-form I biquad section.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Biquad { b, a, x: [0.0; 2], y: [0.0; 2] }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        let out = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [out, self.y[0]];
+        out
+    }
+}
+
+/// Apply the BS.1770 K-weighting filter (coefficients for 48 kHz).
+fn k_weighted(samples: &[f32]) -> Vec<f64> {
+    let mut shelf = Biquad::new(
+        [1.535_124_859_586_97, -2.691_696_189_406_38, 1.198_392_810_852_85],
+        [-1.690_659_293_182_41, 0.732_480_774_215_85],
+    );
+    let mut high_pass = Biquad::new(
+        [1.0, -2.0, 1.0],
+        [-1.990_047_454_833_98, 0.990_072_250_366_21],
+    );
+    samples
+        .iter()
+        .map(|&s| high_pass.process(shelf.process(s as f64)))
+        .collect()
+}
+
+fn block_loudness(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+/// Integrated loudness in LUFS of a mono signal at [`CANONICAL_SAMPLE_RATE`].
+/// Returns `None` when the signal is shorter than one block or entirely below
+/// the absolute gate (silence).
+pub fn integrated_loudness(samples: &[f32]) -> Option<f32> {
+    let rate = CANONICAL_SAMPLE_RATE as usize;
+    let block_len = rate * 400 / 1000;
+    let step = rate * 100 / 1000;
+    if samples.len() < block_len {
+        return None;
+    }
+
+    let weighted = k_weighted(samples);
+
+    let mut block_powers = Vec::with_capacity((weighted.len() - block_len) / step + 1);
+    let mut start = 0;
+    while start + block_len <= weighted.len() {
+        let sum: f64 = weighted[start..start + block_len].iter().map(|v| v * v).sum();
+        block_powers.push(sum / block_len as f64);
+        start += step;
+    }
+
+    let above_absolute: Vec<f64> = block_powers
+        .into_iter()
+        .filter(|&p| p > 0.0 && block_loudness(p) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if above_absolute.is_empty() {
+        return None;
+    }
+
+    let ungated_mean = above_absolute.iter().sum::<f64>() / above_absolute.len() as f64;
+    let relative_gate = block_loudness(ungated_mean) + RELATIVE_GATE_LU;
+
+    let gated: Vec<f64> = above_absolute
+        .into_iter()
+        .filter(|&p| block_loudness(p) > relative_gate)
+        .collect();
+    if gated.is_empty() {
+        return None;
+    }
+
+    let mean = gated.iter().sum::<f64>() / gated.len() as f64;
+    Some(block_loudness(mean) as f32)
+}
+
+/// Scale `samples` in place towards `target_lufs`, limiting the gain so the
+/// sample peak stays under [`PEAK_CEILING_DBFS`]. Returns the applied gain in
+/// dB, or `None` if the loudness could not be measured (left unchanged).
+pub fn normalize_loudness(samples: &mut [f32], target_lufs: f32) -> Option<f32> {
+    let measured = integrated_loudness(samples)?;
+    let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+
+    let mut gain_db = target_lufs - measured;
+    if peak > 0.0 {
+        let peak_db = 20.0 * peak.log10();
+        gain_db = gain_db.min(PEAK_CEILING_DBFS - peak_db);
+    }
+
+    let gain = 10f32.powf(gain_db / 20.0);
+    for s in samples.iter_mut() {
+        *s *= gain;
+    }
+    Some(gain_db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, amplitude: f32, seconds: f32) -> Vec<f32> {
+        let n = (CANONICAL_SAMPLE_RATE as f32 * seconds) as usize;
+        (0..n)
+            .map(|i| {
+                let t = i as f32 / CANONICAL_SAMPLE_RATE as f32;
+                (2.0 * std::f32::consts::PI * freq * t).sin() * amplitude
+            })
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_1k_sine_measures_minus_3_lufs() {
+        // BS.1770 reference: a 0 dBFS 997 Hz sine in one channel reads -3.01 LKFS.
+        let lufs = integrated_loudness(&sine(997.0, 1.0, 3.0)).unwrap();
+        assert!((lufs + 3.01).abs() < 0.2, "got {lufs}");
+
+        let lufs_half = integrated_loudness(&sine(997.0, 0.5, 3.0)).unwrap();
+        assert!((lufs_half + 9.03).abs() < 0.2, "got {lufs_half}");
+    }
+
+    #[test]
+    fn silence_and_short_signals_are_unmeasurable() {
+        assert_eq!(integrated_loudness(&vec![0.0; CANONICAL_SAMPLE_RATE as usize]), None);
+        assert_eq!(integrated_loudness(&sine(997.0, 0.5, 0.1)), None);
+    }
+
+    #[test]
+    fn normalization_reaches_target() {
+        let mut quiet = sine(440.0, 0.05, 3.0);
+        let gain = normalize_loudness(&mut quiet, -16.0).unwrap();
+        assert!(gain > 0.0);
+        let lufs = integrated_loudness(&quiet).unwrap();
+        assert!((lufs + 16.0).abs() < 0.3, "got {lufs}");
+    }
+
+    #[test]
+    fn normalization_respects_peak_ceiling() {
+        let mut loud = sine(440.0, 0.9, 3.0);
+        normalize_loudness(&mut loud, 0.0).unwrap();
+        let peak = loud.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(20.0 * peak.log10() <= PEAK_CEILING_DBFS + 0.01, "peak {peak}");
+    }
+}
@@ -1,7 +1,17 @@
 //! Pure-Rust audio stack for chanting practice: capture + FLAC encode
 //! (recorder) and decode + cpal output (player), replacing Qt Multimedia's
-//! FFmpeg backend. See `docs/pure-rust-audio-backend.md`.
+//! FFmpeg backend. See `docs/pure-rust-audio-backend.md`. `phrase_markers`
+//! proposes range markers from the pauses in a recording, and
+//! `practice_compare` aligns a user recording to a reference for feedback.
+//! `export` writes recordings to WAV / FLAC for sharing, with
+//! `loudness` (EBU R128) normalization. `timing_track` maps the words or
+//! syllables of the chant text to times in a recording for karaoke display.
 
+pub mod export;
 pub mod format;
+pub mod loudness;
+pub mod phrase_markers;
 pub mod player;
+pub mod practice_compare;
 pub mod recorder;
+pub mod timing_track;
@@ -0,0 +1,394 @@
+//! Automatic phrase markers for chanting recordings.
+//!
+//! Detects the pauses between chanted phrases with a simple energy-based voice
+//! activity detector over the decoded mono PCM, and proposes range markers at
+//! the phrase boundaries. When the section's `content_pali` is available, the
+//! detected phrases are merged across the shortest pauses until there is one
+//! phrase per text line, and each marker is labelled with the line it covers.
+//!
+//! The markers use the same JSON shape as the ones placed by hand in
+//! `RecordingPlaybackItem.qml` (`type: "range"`, `start_ms`, `end_ms`), so a
+//! freshly recorded or imported chant becomes loopable phrase-by-phrase.
+
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::audio::player::decode_to_mono;
+
+/// Prefix of the marker ids produced by [`detect_phrase_markers`]. Re-running
+/// detection replaces markers with this prefix and keeps the user's own ones.
+pub const AUTO_MARKER_ID_PREFIX: &str = "auto_";
+
+/// Tuning parameters for the pause detector. The defaults suit a single voice
+/// chanting at a normal pace with breath pauses between lines.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PhraseDetectionParams {
+    /// Analysis frame length in milliseconds.
+    pub frame_ms: u32,
+    /// Hop between analysis frames in milliseconds.
+    pub hop_ms: u32,
+    /// Position of the voicing threshold between the noise floor (0.0) and the
+    /// loud speech level (1.0), on the dB scale.
+    pub threshold_ratio: f32,
+    /// Minimum distance in dB between the noise floor and the threshold, so a
+    /// near-silent recording doesn't split on background noise.
+    pub min_snr_db: f32,
+    /// Pauses shorter than this are bridged (consonant closures, short breaths).
+    pub min_silence_ms: u32,
+    /// Voiced runs shorter than this are discarded (clicks, coughs).
+    pub min_phrase_ms: u32,
+    /// Padding added before and after each detected phrase.
+    pub padding_ms: u32,
+}
+
+impl Default for PhraseDetectionParams {
+    fn default() -> Self {
+        PhraseDetectionParams {
+            frame_ms: 20,
+            hop_ms: 10,
+            threshold_ratio: 0.3,
+            min_snr_db: 6.0,
+            min_silence_ms: 350,
+            min_phrase_ms: 250,
+            padding_ms: 80,
+        }
+    }
+}
+
+/// A detected voiced region, in milliseconds from the start of the recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhraseSegment {
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// A proposed range marker, serialized in the `markers_json` format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhraseMarker {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub marker_type: String,
+    pub label: String,
+    pub comment: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// Per-frame RMS level in dBFS. Silent frames are clamped to -100 dB.
+pub(crate) fn frame_levels_db(samples: &[f32], frame_len: usize, hop: usize) -> Vec<f32> {
+    if samples.is_empty() || frame_len == 0 || hop == 0 {
+        return Vec::new();
+    }
+
+    let mut levels = Vec::with_capacity(samples.len() / hop + 1);
+    let mut start = 0;
+    while start < samples.len() {
+        let end = (start + frame_len).min(samples.len());
+        let frame = &samples[start..end];
+        let energy: f32 = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+        let db = if energy > 0.0 { 10.0 * energy.log10() } else { -100.0 };
+        levels.push(db.max(-100.0));
+        start += hop;
+    }
+    levels
+}
+
+/// Value at the given percentile (0.0–1.0) of an unsorted slice.
+pub(crate) fn percentile(values: &[f32], p: f32) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let idx = ((sorted.len() - 1) as f32 * p.clamp(0.0, 1.0)).round() as usize;
+    sorted[idx]
+}
+
+/// Detect voiced phrases in a mono buffer.
+///
+/// The noise floor and the speech level are estimated from the 10th and 95th
+/// percentiles of the frame levels, so the detector adapts to the recording
+/// gain without calibration. Frames above the threshold are voiced; pauses
+/// shorter than `min_silence_ms` are bridged and phrases shorter than
+/// `min_phrase_ms` are dropped.
+pub fn detect_phrases(samples: &[f32], sample_rate: u32, params: &PhraseDetectionParams) -> Vec<PhraseSegment> {
+    if samples.is_empty() || sample_rate == 0 {
+        return Vec::new();
+    }
+
+    let ms_to_samples = |ms: u32| -> usize { (sample_rate as u64 * ms as u64 / 1000) as usize };
+    let frame_len = ms_to_samples(params.frame_ms).max(1);
+    let hop = ms_to_samples(params.hop_ms).max(1);
+    let hop_ms = params.hop_ms.max(1) as i64;
+
+    let levels = frame_levels_db(samples, frame_len, hop);
+    if levels.is_empty() {
+        return Vec::new();
+    }
+
+    let noise_floor = percentile(&levels, 0.10);
+    let speech_level = percentile(&levels, 0.95);
+    if speech_level - noise_floor < params.min_snr_db {
+        // No usable contrast: either all silence or all sound.
+        if speech_level <= -90.0 {
+            return Vec::new();
+        }
+        let duration_ms = samples.len() as i64 * 1000 / sample_rate as i64;
+        return vec![PhraseSegment { start_ms: 0, end_ms: duration_ms }];
+    }
+
+    let threshold = (noise_floor + (speech_level - noise_floor) * params.threshold_ratio)
+        .max(noise_floor + params.min_snr_db);
+
+    // Collect raw voiced runs as frame index ranges.
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    let mut run_start: Option<usize> = None;
+    for (i, &db) in levels.iter().enumerate() {
+        match (db >= threshold, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(s)) => {
+                runs.push((s, i));
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = run_start {
+        runs.push((s, levels.len()));
+    }
+
+    // Bridge short pauses.
+    let min_gap_frames = (params.min_silence_ms as i64 / hop_ms) as usize;
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(runs.len());
+    for run in runs {
+        match merged.last_mut() {
+            Some(last) if run.0.saturating_sub(last.1) < min_gap_frames => last.1 = run.1,
+            _ => merged.push(run),
+        }
+    }
+
+    // Drop short blips.
+    let min_phrase_frames = (params.min_phrase_ms as i64 / hop_ms) as usize;
+    let duration_ms = samples.len() as i64 * 1000 / sample_rate as i64;
+    let padding = params.padding_ms as i64;
+
+    merged
+        .into_iter()
+        .filter(|(s, e)| e - s >= min_phrase_frames)
+        .map(|(s, e)| PhraseSegment {
+            start_ms: (s as i64 * hop_ms - padding).max(0),
+            end_ms: (e as i64 * hop_ms + params.frame_ms as i64 + padding).min(duration_ms),
+        })
+        .collect()
+}
+
+/// Merge phrases across the shortest pauses until at most `target` remain.
+/// Used to align the detected phrases with the lines of the chant text, where
+/// a line often contains a short breath that the detector treats as a pause.
+pub fn merge_to_count(phrases: &[PhraseSegment], target: usize) -> Vec<PhraseSegment> {
+    let mut out = phrases.to_vec();
+    if target == 0 {
+        return out;
+    }
+    while out.len() > target {
+        let Some(idx) = (0..out.len() - 1)
+            .min_by_key(|&i| out[i + 1].start_ms - out[i].end_ms)
+        else {
+            break;
+        };
+        let next = out.remove(idx + 1);
+        out[idx].end_ms = next.end_ms;
+    }
+    out
+}
+
+/// The non-empty lines of a chant text, trimmed.
+pub fn content_lines(content_pali: &str) -> Vec<String> {
+    content_pali
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Shorten a text line for use as a marker label.
+fn line_label(line: &str) -> String {
+    const MAX_CHARS: usize = 40;
+    if line.chars().count() <= MAX_CHARS {
+        line.to_string()
+    } else {
+        let truncated: String = line.chars().take(MAX_CHARS).collect();
+        format!("{}…", truncated.trim_end())
+    }
+}
+
+/// Turn phrases into range markers. When the number of phrases matches the
+/// number of text lines, the markers are labelled with the lines; otherwise
+/// they're numbered.
+pub fn phrases_to_markers(phrases: &[PhraseSegment], lines: &[String]) -> Vec<PhraseMarker> {
+    let use_lines = !lines.is_empty() && lines.len() == phrases.len();
+    phrases
+        .iter()
+        .enumerate()
+        .map(|(i, p)| PhraseMarker {
+            id: format!("{}range_{}", AUTO_MARKER_ID_PREFIX, i + 1),
+            marker_type: "range".to_string(),
+            label: if use_lines { line_label(&lines[i]) } else { format!("Phrase {}", i + 1) },
+            comment: String::new(),
+            start_ms: p.start_ms,
+            end_ms: p.end_ms,
+        })
+        .collect()
+}
+
+/// Decode `file_path` and propose phrase markers. If `content_pali` is given,
+/// phrases are aligned to its lines (see [`merge_to_count`]).
+pub fn detect_phrase_markers(
+    file_path: &Path,
+    content_pali: Option<&str>,
+    params: &PhraseDetectionParams,
+) -> Result<Vec<PhraseMarker>> {
+    let (mono, sample_rate) = decode_to_mono(file_path)?;
+    let mut phrases = detect_phrases(&mono, sample_rate, params);
+
+    let lines = content_pali.map(content_lines).unwrap_or_default();
+    if !lines.is_empty() {
+        phrases = merge_to_count(&phrases, lines.len());
+    }
+
+    Ok(phrases_to_markers(&phrases, &lines))
+}
+
+/// Combine proposed markers with a recording's existing `markers_json`.
+///
+/// Markers from a previous detection run (ids starting with
+/// [`AUTO_MARKER_ID_PREFIX`]) are replaced, markers the user placed by hand are
+/// kept. Invalid existing JSON is treated as an empty list.
+pub fn merge_with_existing_markers(existing_markers_json: Option<&str>, proposed: &[PhraseMarker]) -> Result<String> {
+    let existing: Vec<Value> = existing_markers_json
+        .and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default();
+
+    let mut markers: Vec<Value> = existing
+        .into_iter()
+        .filter(|m| {
+            !m.get("id")
+                .and_then(|v| v.as_str())
+                .is_some_and(|id| id.starts_with(AUTO_MARKER_ID_PREFIX))
+        })
+        .collect();
+
+    for m in proposed {
+        markers.push(serde_json::to_value(m)?);
+    }
+
+    Ok(serde_json::to_string(&markers)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    /// Build a signal of alternating tone / silence blocks, durations in ms.
+    fn blocks(spec: &[(bool, u32)]) -> Vec<f32> {
+        let mut out = Vec::new();
+        for &(voiced, ms) in spec {
+            let n = (RATE as u64 * ms as u64 / 1000) as usize;
+            for i in 0..n {
+                let t = i as f32 / RATE as f32;
+                let v = if voiced {
+                    (2.0 * std::f32::consts::PI * 220.0 * t).sin() * 0.5
+                } else {
+                    // Low-level noise floor so the dB percentiles are finite.
+                    ((i * 7919 % 97) as f32 / 97.0 - 0.5) * 0.001
+                };
+                out.push(v);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn detects_three_phrases_separated_by_pauses() {
+        let pcm = blocks(&[
+            (false, 300), (true, 1000), (false, 600), (true, 800),
+            (false, 600), (true, 1200), (false, 300),
+        ]);
+        let phrases = detect_phrases(&pcm, RATE, &PhraseDetectionParams::default());
+        assert_eq!(phrases.len(), 3, "{:?}", phrases);
+        // First phrase starts near 300 ms minus padding.
+        assert!((150..=300).contains(&phrases[0].start_ms), "{:?}", phrases[0]);
+        assert!(phrases.windows(2).all(|w| w[0].end_ms <= w[1].start_ms));
+    }
+
+    #[test]
+    fn short_pauses_are_bridged() {
+        let pcm = blocks(&[(false, 300), (true, 800), (false, 100), (true, 800), (false, 300)]);
+        let phrases = detect_phrases(&pcm, RATE, &PhraseDetectionParams::default());
+        assert_eq!(phrases.len(), 1, "{:?}", phrases);
+    }
+
+    #[test]
+    fn silence_yields_no_phrases() {
+        let pcm = vec![0.0f32; RATE as usize];
+        assert!(detect_phrases(&pcm, RATE, &PhraseDetectionParams::default()).is_empty());
+    }
+
+    #[test]
+    fn merge_to_count_joins_across_shortest_gap() {
+        let phrases = vec![
+            PhraseSegment { start_ms: 0, end_ms: 1000 },
+            PhraseSegment { start_ms: 1100, end_ms: 2000 },
+            PhraseSegment { start_ms: 2800, end_ms: 4000 },
+        ];
+        let merged = merge_to_count(&phrases, 2);
+        assert_eq!(merged, vec![
+            PhraseSegment { start_ms: 0, end_ms: 2000 },
+            PhraseSegment { start_ms: 2800, end_ms: 4000 },
+        ]);
+    }
+
+    #[test]
+    fn markers_are_labelled_with_lines_when_counts_match() {
+        let phrases = vec![
+            PhraseSegment { start_ms: 0, end_ms: 1000 },
+            PhraseSegment { start_ms: 1500, end_ms: 2500 },
+        ];
+        let lines = content_lines("Namo tassa bhagavato\n\n  arahato sammāsambuddhassa \n");
+        let markers = phrases_to_markers(&phrases, &lines);
+        assert_eq!(markers[0].label, "Namo tassa bhagavato");
+        assert_eq!(markers[1].label, "arahato sammāsambuddhassa");
+        assert!(markers.iter().all(|m| m.id.starts_with(AUTO_MARKER_ID_PREFIX)));
+
+        let numbered = phrases_to_markers(&phrases, &lines[..1]);
+        assert_eq!(numbered[1].label, "Phrase 2");
+    }
+
+    #[test]
+    fn merge_keeps_user_markers_and_replaces_auto_ones() {
+        let existing = r#"[
+            {"id": "pos_1", "type": "position", "label": "Mark", "comment": "", "position_ms": 500},
+            {"id": "auto_range_1", "type": "range", "label": "Old", "comment": "", "start_ms": 0, "end_ms": 10}
+        ]"#;
+        let proposed = vec![PhraseMarker {
+            id: "auto_range_1".to_string(),
+            marker_type: "range".to_string(),
+            label: "New".to_string(),
+            comment: String::new(),
+            start_ms: 0,
+            end_ms: 900,
+        }];
+        let json = merge_with_existing_markers(Some(existing), &proposed).unwrap();
+        let parsed: Vec<Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0]["id"], "pos_1");
+        assert_eq!(parsed[1]["label"], "New");
+        assert_eq!(parsed[1]["type"], "range");
+    }
+}
@@ -0,0 +1,611 @@
+//! Offline comparison of a user's chanting recording against a reference.
+//!
+//! Both recordings are decoded to mono, resampled to a low analysis rate and
+//! reduced to one feature frame every [`HOP_MS`]: the frame level (z-scored per
+//! recording) and the pitch (autocorrelation estimate, in semitones relative to
+//! the recording's median pitch, so chanting in a different key is not an
+//! error). Dynamic time warping over these frames aligns the user recording to
+//! the reference, and the alignment is read back per reference marker to
+//! report timing drift, duration ratio and pitch deviation.
+//!
+//! The result is a [`PracticeComparisonReport`], serialized to JSON for the
+//! review window.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::format::resample_mono_to;
+use crate::audio::phrase_markers::{detect_phrases, frame_levels_db, percentile, PhraseDetectionParams};
+use crate::audio::player::decode_to_mono;
+
+/// Sample rate the features are computed at. Chanting pitch is well below
+/// 1 kHz, so 8 kHz keeps the autocorrelation cheap.
+pub const ANALYSIS_SAMPLE_RATE: u32 = 8_000;
+/// Analysis frame length in milliseconds.
+pub const FRAME_MS: u32 = 40;
+/// Hop between analysis frames in milliseconds (the time resolution of the
+/// alignment).
+pub const HOP_MS: u32 = 20;
+
+const MIN_PITCH_HZ: f32 = 70.0;
+const MAX_PITCH_HZ: f32 = 500.0;
+/// Minimum normalized autocorrelation for a frame to count as pitched.
+const VOICING_CORRELATION: f32 = 0.5;
+/// Interval of the `alignment` samples included in the report.
+const ALIGNMENT_SAMPLE_MS: i64 = 250;
+
+/// Raw per-frame features of one recording.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameFeatures {
+    /// RMS level in dBFS.
+    pub level_db: f32,
+    /// Fundamental frequency, `None` for silent or unpitched frames.
+    pub pitch_hz: Option<f32>,
+}
+
+/// Features normalized per recording, used as the DTW input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct NormFrame {
+    energy: f32,
+    semitones: Option<f32>,
+}
+
+/// A time range of the reference recording to report on, usually a range
+/// marker from its `markers_json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceSegment {
+    pub id: String,
+    pub label: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// Comparison results for one reference segment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarkerComparison {
+    pub marker_id: String,
+    pub label: String,
+    pub ref_start_ms: i64,
+    pub ref_end_ms: i64,
+    pub user_start_ms: i64,
+    pub user_end_ms: i64,
+    /// How far the user is ahead (negative) or behind (positive) the reference
+    /// at the start of this segment, relative to the start of the first one.
+    pub start_drift_ms: i64,
+    /// User segment duration divided by the reference segment duration.
+    pub duration_ratio: f32,
+    /// Mean absolute pitch difference over the pitched aligned frames, after
+    /// removing the overall key offset. `None` if no frames were pitched.
+    pub pitch_deviation_cents: Option<f32>,
+}
+
+/// The full report returned to the review window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PracticeComparisonReport {
+    pub reference_duration_ms: i64,
+    pub user_duration_ms: i64,
+    /// Overall similarity, 100 for identical feature sequences.
+    pub score: f32,
+    /// Mean DTW distance per aligned frame pair.
+    pub mean_frame_cost: f32,
+    /// Median pitch difference of the user against the reference in cents
+    /// (positive when chanting higher).
+    pub key_offset_cents: Option<f32>,
+    /// Mean absolute pitch deviation over the whole recording, key offset
+    /// removed.
+    pub mean_pitch_deviation_cents: Option<f32>,
+    /// User chanting span divided by the reference chanting span (>1 is slower).
+    pub tempo_ratio: f32,
+    pub markers: Vec<MarkerComparison>,
+    /// `[reference_ms, user_ms]` points along the alignment path, for drawing.
+    pub alignment: Vec<[i64; 2]>,
+}
+
+/// Estimate the fundamental frequency of a frame by normalized
+/// autocorrelation with parabolic peak interpolation. To avoid octave errors,
+/// the shortest lag within 90% of the best correlation wins.
+fn estimate_pitch(frame: &[f32], sample_rate: u32) -> Option<f32> {
+    let min_lag = (sample_rate as f32 / MAX_PITCH_HZ).floor() as usize;
+    let max_lag = (sample_rate as f32 / MIN_PITCH_HZ).ceil() as usize;
+    if min_lag < 2 || frame.len() < max_lag * 2 {
+        return None;
+    }
+
+    let corr_at = |lag: usize| -> f32 {
+        let (mut xy, mut xx, mut yy) = (0.0f32, 0.0f32, 0.0f32);
+        for (&x, &y) in frame.iter().zip(&frame[lag..]) {
+            xy += x * y;
+            xx += x * x;
+            yy += y * y;
+        }
+        let denom = (xx * yy).sqrt();
+        if denom > 0.0 { xy / denom } else { 0.0 }
+    };
+
+    let corrs: Vec<f32> = (min_lag..=max_lag).map(corr_at).collect();
+    let best = corrs.iter().copied().fold(f32::MIN, f32::max);
+    if best < VOICING_CORRELATION {
+        return None;
+    }
+
+    // First local maximum close to the global best.
+    let mut idx = corrs.iter().position(|&c| c >= best * 0.9)?;
+    while idx + 1 < corrs.len() && corrs[idx + 1] > corrs[idx] {
+        idx += 1;
+    }
+
+    let mut lag = (min_lag + idx) as f32;
+    if idx > 0 && idx + 1 < corrs.len() {
+        let (a, b, c) = (corrs[idx - 1], corrs[idx], corrs[idx + 1]);
+        let denom = a - 2.0 * b + c;
+        if denom.abs() > f32::EPSILON {
+            lag += 0.5 * (a - c) / denom;
+        }
+    }
+
+    Some(sample_rate as f32 / lag)
+}
+
+/// Compute the per-frame features of a mono buffer.
+pub fn extract_features(samples: &[f32], sample_rate: u32) -> Result<Vec<FrameFeatures>> {
+    if sample_rate == 0 {
+        return Err(anyhow!("Invalid sample rate"));
+    }
+    let mono = resample_mono_to(samples, sample_rate, ANALYSIS_SAMPLE_RATE)?;
+
+    let frame_len = (ANALYSIS_SAMPLE_RATE * FRAME_MS / 1000) as usize;
+    let hop = (ANALYSIS_SAMPLE_RATE * HOP_MS / 1000) as usize;
+
+    let levels = frame_levels_db(&mono, frame_len, hop);
+    let noise_floor = percentile(&levels, 0.10);
+    let speech_level = percentile(&levels, 0.95);
+    let gate = noise_floor + (speech_level - noise_floor) * 0.3;
+
+    let features = levels
+        .iter()
+        .enumerate()
+        .map(|(i, &level_db)| {
+            let start = i * hop;
+            let end = (start + frame_len).min(mono.len());
+            let pitch_hz = if level_db >= gate && speech_level - noise_floor > 6.0 {
+                estimate_pitch(&mono[start..end], ANALYSIS_SAMPLE_RATE)
+            } else {
+                None
+            };
+            FrameFeatures { level_db, pitch_hz }
+        })
+        .collect();
+
+    Ok(features)
+}
+
+fn semitones(hz: f32, reference_hz: f32) -> f32 {
+    12.0 * (hz / reference_hz).log2()
+}
+
+fn median_pitch(features: &[FrameFeatures]) -> Option<f32> {
+    let pitches: Vec<f32> = features.iter().filter_map(|f| f.pitch_hz).collect();
+    if pitches.is_empty() {
+        None
+    } else {
+        Some(percentile(&pitches, 0.5))
+    }
+}
+
+fn normalize(features: &[FrameFeatures]) -> Vec<NormFrame> {
+    let n = features.len().max(1) as f32;
+    let mean = features.iter().map(|f| f.level_db).sum::<f32>() / n;
+    let var = features.iter().map(|f| (f.level_db - mean).powi(2)).sum::<f32>() / n;
+    let std = var.sqrt().max(1.0);
+    let median = median_pitch(features);
+
+    features
+        .iter()
+        .map(|f| NormFrame {
+            energy: (f.level_db - mean) / std,
+            semitones: match (f.pitch_hz, median) {
+                (Some(hz), Some(m)) => Some(semitones(hz, m)),
+                _ => None,
+            },
+        })
+        .collect()
+}
+
+fn frame_distance(a: &NormFrame, b: &NormFrame) -> f32 {
+    let energy = (a.energy - b.energy).abs();
+    let pitch = match (a.semitones, b.semitones) {
+        (Some(x), Some(y)) => ((x - y).abs() / 2.0).min(3.0),
+        (None, None) => 0.0,
+        _ => 1.0,
+    };
+    energy + pitch
+}
+
+/// Dynamic time warping within a band around the (length-scaled) diagonal.
+/// Returns the alignment path as `(reference_frame, user_frame)` pairs from
+/// start to end, and the total path cost.
+fn dtw_path(reference: &[NormFrame], user: &[NormFrame]) -> (Vec<(usize, usize)>, f32) {
+    let n = reference.len();
+    let m = user.len();
+    if n == 0 || m == 0 {
+        return (Vec::new(), 0.0);
+    }
+
+    // Band: 5 s of local tempo variation or 10% of the longer recording.
+    let band = ((5000 / HOP_MS) as usize).max(n.max(m) / 10);
+    let row_range = |i: usize| -> (usize, usize) {
+        let center = if n > 1 { i * (m - 1) / (n - 1) } else { 0 };
+        (center.saturating_sub(band), (center + band).min(m - 1))
+    };
+
+    // Backpointers per row over the row's band: 0 diagonal, 1 from (i-1, j),
+    // 2 from (i, j-1).
+    let mut steps: Vec<Vec<u8>> = Vec::with_capacity(n);
+    let mut prev: Vec<f32> = vec![f32::INFINITY; m];
+    let mut curr: Vec<f32> = vec![f32::INFINITY; m];
+
+    for (i, ref_frame) in reference.iter().enumerate() {
+        let (lo, hi) = row_range(i);
+        let mut row_steps = vec![0u8; hi - lo + 1];
+        curr.iter_mut().for_each(|c| *c = f32::INFINITY);
+
+        for j in lo..=hi {
+            let d = frame_distance(ref_frame, &user[j]);
+            let (best, step) = if i == 0 && j == 0 {
+                (0.0, 0)
+            } else {
+                let diag = if i > 0 && j > 0 { prev[j - 1] } else { f32::INFINITY };
+                let up = if i > 0 { prev[j] } else { f32::INFINITY };
+                let left = if j > 0 { curr[j - 1] } else { f32::INFINITY };
+                if diag <= up && diag <= left {
+                    (diag, 0)
+                } else if up <= left {
+                    (up, 1)
+                } else {
+                    (left, 2)
+                }
+            };
+            curr[j] = best + d;
+            row_steps[j - lo] = step;
+        }
+
+        steps.push(row_steps);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    let total = prev[m - 1];
+
+    let mut path = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (n - 1, m - 1);
+    loop {
+        path.push((i, j));
+        if i == 0 && j == 0 {
+            break;
+        }
+        let (lo, _) = row_range(i);
+        let step = if j >= lo { steps[i].get(j - lo).copied().unwrap_or(1) } else { 1 };
+        match step {
+            0 if i > 0 && j > 0 => {
+                i -= 1;
+                j -= 1;
+            }
+            1 if i > 0 => i -= 1,
+            _ if j > 0 => j -= 1,
+            _ => i -= 1,
+        }
+    }
+    path.reverse();
+
+    (path, total)
+}
+
+/// Range markers of a reference recording's `markers_json`. Position markers
+/// and malformed entries are skipped.
+pub fn reference_segments_from_markers_json(markers_json: &str) -> Vec<ReferenceSegment> {
+    let markers: Vec<serde_json::Value> = serde_json::from_str(markers_json).unwrap_or_default();
+    let mut segments: Vec<ReferenceSegment> = markers
+        .iter()
+        .filter(|m| m.get("type").and_then(|v| v.as_str()) == Some("range"))
+        .filter_map(|m| {
+            let start_ms = m.get("start_ms")?.as_i64()?;
+            let end_ms = m.get("end_ms")?.as_i64()?;
+            if end_ms <= start_ms {
+                return None;
+            }
+            Some(ReferenceSegment {
+                id: m.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                label: m.get("label").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                start_ms,
+                end_ms,
+            })
+        })
+        .collect();
+    segments.sort_by_key(|s| s.start_ms);
+    segments
+}
+
+/// First and last pitched frame, `None` when there are fewer than two.
+fn voiced_range(features: &[FrameFeatures]) -> Option<(usize, usize)> {
+    let first = features.iter().position(|f| f.pitch_hz.is_some())?;
+    let last = features.iter().rposition(|f| f.pitch_hz.is_some())?;
+    (last > first).then_some((first, last))
+}
+
+fn span_ms(features: &[FrameFeatures]) -> i64 {
+    match voiced_range(features) {
+        Some((a, b)) => ((b - a) as i64 + 1) * HOP_MS as i64,
+        None => features.len() as i64 * HOP_MS as i64,
+    }
+}
+
+/// Append the straight line of frame pairs from the end of `path` to `to`.
+fn extend_path_to(path: &mut Vec<(usize, usize)>, to: (usize, usize)) {
+    let from = path.last().copied().unwrap_or((0, 0));
+    let (dr, du) = (to.0 - from.0, to.1 - from.1);
+    let steps = dr.max(du);
+    for k in 0..=steps {
+        let p = (from.0 + dr * k / steps.max(1), from.1 + du * k / steps.max(1));
+        if path.last() != Some(&p) {
+            path.push(p);
+        }
+    }
+}
+
+/// DTW over the voiced part of both recordings. The path has to start at the
+/// first frames, so a pause before the user starts chanting would otherwise
+/// be stretched over the reference's first phrase (and likewise at the end).
+/// The silence around the voiced part is joined to the path with straight
+/// lines. Returns the path and the mean frame cost of the voiced part.
+fn align_voiced(ref_features: &[FrameFeatures], user_features: &[FrameFeatures]) -> (Vec<(usize, usize)>, f32) {
+    let (r0, r1) = voiced_range(ref_features).unwrap_or((0, ref_features.len() - 1));
+    let (u0, u1) = voiced_range(user_features).unwrap_or((0, user_features.len() - 1));
+
+    let (inner, total_cost) = dtw_path(&normalize(&ref_features[r0..=r1]), &normalize(&user_features[u0..=u1]));
+
+    let mean_cost = total_cost / inner.len().max(1) as f32;
+
+    let mut path = Vec::with_capacity(inner.len() + r0 + u0 + 2);
+    extend_path_to(&mut path, (r0, u0));
+    for (r, u) in inner {
+        extend_path_to(&mut path, (r0 + r, u0 + u));
+    }
+    extend_path_to(&mut path, (ref_features.len() - 1, user_features.len() - 1));
+    (path, mean_cost)
+}
+
+/// Compare two mono buffers. When `segments` is empty, the reference's
+/// phrases are detected from its pauses and used instead.
+pub fn compare_samples(
+    reference: &[f32],
+    reference_rate: u32,
+    user: &[f32],
+    user_rate: u32,
+    segments: &[ReferenceSegment],
+) -> Result<PracticeComparisonReport> {
+    let ref_features = extract_features(reference, reference_rate)?;
+    let user_features = extract_features(user, user_rate)?;
+    if ref_features.is_empty() || user_features.is_empty() {
+        return Err(anyhow!("Recording is too short to compare"));
+    }
+
+    let (path, mean_frame_cost) = align_voiced(&ref_features, &user_features);
+
+    let hop = HOP_MS as i64;
+    let ref_duration_ms = reference.len() as i64 * 1000 / reference_rate as i64;
+    let user_duration_ms = user.len() as i64 * 1000 / user_rate as i64;
+
+    // Raw cents difference at each aligned pitched pair, with its ref frame.
+    let pitch_diffs: Vec<(usize, f32)> = path
+        .iter()
+        .filter_map(|&(r, u)| match (ref_features[r].pitch_hz, user_features[u].pitch_hz) {
+            (Some(a), Some(b)) => Some((r, 1200.0 * (b / a).log2())),
+            _ => None,
+        })
+        .collect();
+    let key_offset_cents = if pitch_diffs.is_empty() {
+        None
+    } else {
+        let diffs: Vec<f32> = pitch_diffs.iter().map(|(_, c)| *c).collect();
+        Some(percentile(&diffs, 0.5))
+    };
+    let mean_deviation = |from: usize, to: usize| -> Option<f32> {
+        let offset = key_offset_cents?;
+        let devs: Vec<f32> = pitch_diffs
+            .iter()
+            .filter(|(r, _)| *r >= from && *r < to)
+            .map(|(_, c)| (c - offset).abs())
+            .collect();
+        if devs.is_empty() {
+            None
+        } else {
+            Some(devs.iter().sum::<f32>() / devs.len() as f32)
+        }
+    };
+
+    // For each reference frame, the last user frame aligned to it.
+    let mut last_user = vec![0usize; ref_features.len()];
+    for &(r, u) in &path {
+        last_user[r] = last_user[r].max(u);
+    }
+
+    let segments: Vec<ReferenceSegment> = if segments.is_empty() {
+        detect_phrases(reference, reference_rate, &PhraseDetectionParams::default())
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| ReferenceSegment {
+                id: format!("phrase_{}", i + 1),
+                label: format!("Phrase {}", i + 1),
+                start_ms: p.start_ms,
+                end_ms: p.end_ms,
+            })
+            .collect()
+    } else {
+        segments.to_vec()
+    };
+
+    let last_ref_frame = ref_features.len() - 1;
+    let mut markers = Vec::with_capacity(segments.len());
+    let mut origin: Option<(i64, i64)> = None;
+    for seg in &segments {
+        let from = ((seg.start_ms / hop).max(0) as usize).min(last_ref_frame);
+        let to = (((seg.end_ms + hop - 1) / hop) as usize).clamp(from + 1, last_ref_frame + 1);
+
+        // Several user frames aligned to one reference frame means the user
+        // paused before the phrase (at its start) or held it longer (at its
+        // end), so both boundaries take the last user frame of the run.
+        let user_start_ms = last_user[from] as i64 * hop;
+        let user_end_ms = (last_user[to - 1] as i64 + 1) * hop;
+
+        let (ref_origin, user_origin) = *origin.get_or_insert((seg.start_ms, user_start_ms));
+        let ref_len = (seg.end_ms - seg.start_ms).max(1);
+
+        markers.push(MarkerComparison {
+            marker_id: seg.id.clone(),
+            label: seg.label.clone(),
+            ref_start_ms: seg.start_ms,
+            ref_end_ms: seg.end_ms,
+            user_start_ms,
+            user_end_ms,
+            start_drift_ms: (user_start_ms - user_origin) - (seg.start_ms - ref_origin),
+            duration_ratio: (user_end_ms - user_start_ms).max(0) as f32 / ref_len as f32,
+            pitch_deviation_cents: mean_deviation(from, to),
+        });
+    }
+
+    let mut alignment = Vec::new();
+    let mut next_ms = 0i64;
+    for &(r, u) in &path {
+        let ref_ms = r as i64 * hop;
+        if ref_ms >= next_ms {
+            alignment.push([ref_ms, u as i64 * hop]);
+            next_ms = ref_ms + ALIGNMENT_SAMPLE_MS;
+        }
+    }
+
+    let ref_span = span_ms(&ref_features);
+    let tempo_ratio = span_ms(&user_features) as f32 / ref_span.max(1) as f32;
+
+    Ok(PracticeComparisonReport {
+        reference_duration_ms: ref_duration_ms,
+        user_duration_ms,
+        score: 100.0 / (1.0 + mean_frame_cost),
+        mean_frame_cost,
+        key_offset_cents,
+        mean_pitch_deviation_cents: mean_deviation(0, ref_features.len()),
+        tempo_ratio,
+        markers,
+        alignment,
+    })
+}
+
+/// Decode both recordings and compare them. `reference_markers_json` is the
+/// reference recording's `markers_json`; its range markers define the
+/// segments reported on.
+pub fn compare_recordings(
+    reference_path: &Path,
+    user_path: &Path,
+    reference_markers_json: Option<&str>,
+) -> Result<PracticeComparisonReport> {
+    let (reference, reference_rate) = decode_to_mono(reference_path)?;
+    let (user, user_rate) = decode_to_mono(user_path)?;
+    let segments = reference_markers_json
+        .map(reference_segments_from_markers_json)
+        .unwrap_or_default();
+    compare_samples(&reference, reference_rate, &user, user_rate, &segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    /// Notes as `(frequency_hz, ms)`; a frequency of 0 is a pause.
+    fn notes(spec: &[(f32, u32)]) -> Vec<f32> {
+        let mut out = Vec::new();
+        for &(freq, ms) in spec {
+            let n = (RATE as u64 * ms as u64 / 1000) as usize;
+            for i in 0..n {
+                let t = i as f32 / RATE as f32;
+                let v = if freq > 0.0 {
+                    let w = 2.0 * std::f32::consts::PI * freq * t;
+                    (w.sin() + 0.3 * (2.0 * w).sin()) * 0.4
+                } else {
+                    ((i * 7919 % 97) as f32 / 97.0 - 0.5) * 0.001
+                };
+                out.push(v);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn pitch_estimate_is_accurate() {
+        let sr = ANALYSIS_SAMPLE_RATE;
+        let frame: Vec<f32> = (0..(sr * FRAME_MS / 1000))
+            .map(|i| (2.0 * std::f32::consts::PI * 196.0 * i as f32 / sr as f32).sin())
+            .collect();
+        let hz = estimate_pitch(&frame, sr).expect("pitched");
+        assert!((hz - 196.0).abs() < 2.0, "got {hz}");
+
+        let silence = vec![0.0f32; frame.len()];
+        assert_eq!(estimate_pitch(&silence, sr), None);
+    }
+
+    #[test]
+    fn identical_recordings_align_on_the_diagonal() {
+        let pcm = notes(&[(0.0, 200), (220.0, 500), (0.0, 300), (262.0, 500), (0.0, 200)]);
+        let report = compare_samples(&pcm, RATE, &pcm, RATE, &[]).unwrap();
+        assert!(report.score > 99.0, "score {}", report.score);
+        assert!(report.alignment.iter().all(|[r, u]| r == u));
+        assert_eq!(report.key_offset_cents, Some(0.0));
+        assert!(report.markers.iter().all(|m| m.start_drift_ms == 0));
+    }
+
+    #[test]
+    fn slower_transposed_chant_reports_drift_and_key_offset() {
+        let semitone = 2f32.powf(1.0 / 12.0);
+        let reference = notes(&[
+            (220.0, 600), (0.0, 300), (247.0, 600), (0.0, 300), (262.0, 800), (0.0, 200),
+        ]);
+        let user = notes(&[
+            (0.0, 200), (220.0 * semitone, 750), (0.0, 375), (247.0 * semitone, 750),
+            (0.0, 375), (262.0 * semitone, 1000), (0.0, 200),
+        ]);
+        let segments = vec![
+            ReferenceSegment { id: "a".into(), label: "A".into(), start_ms: 0, end_ms: 600 },
+            ReferenceSegment { id: "b".into(), label: "B".into(), start_ms: 900, end_ms: 1500 },
+            ReferenceSegment { id: "c".into(), label: "C".into(), start_ms: 1800, end_ms: 2600 },
+        ];
+        let report = compare_samples(&reference, RATE, &user, RATE, &segments).unwrap();
+
+        let key = report.key_offset_cents.expect("pitched");
+        assert!((key - 100.0).abs() < 25.0, "key offset {key}");
+        assert!(report.tempo_ratio > 1.1, "tempo {}", report.tempo_ratio);
+
+        assert_eq!(report.markers.len(), 3);
+        assert_eq!(report.markers[0].start_drift_ms, 0);
+        assert!(report.markers[1].start_drift_ms > 100, "{:?}", report.markers[1]);
+        assert!(report.markers[2].start_drift_ms > report.markers[1].start_drift_ms, "{:?}", report.markers);
+        for m in &report.markers {
+            assert!((1.05..=1.5).contains(&m.duration_ratio), "{:?}", m);
+            let dev = m.pitch_deviation_cents.expect("pitched");
+            assert!(dev < 30.0, "{:?}", m);
+        }
+    }
+
+    #[test]
+    fn segments_from_markers_json_keep_ranges_only() {
+        let json = r#"[
+            {"id": "pos_1", "type": "position", "label": "Mark", "position_ms": 10},
+            {"id": "r2", "type": "range", "label": "Two", "start_ms": 900, "end_ms": 1500},
+            {"id": "r1", "type": "range", "label": "One", "start_ms": 0, "end_ms": 600},
+            {"id": "bad", "type": "range", "label": "Bad", "start_ms": 700, "end_ms": 700}
+        ]"#;
+        let segments = reference_segments_from_markers_json(json);
+        let ids: Vec<&str> = segments.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["r1", "r2"]);
+    }
+}
@@ -0,0 +1,372 @@
+//! Word / syllable timing tracks for karaoke-style chanting text.
+//!
+//! A timing track maps the tokens of a section's `content_pali` (words, or
+//! syllables split by [`crate::prosody`]) to millisecond ranges in one
+//! recording. Tracks are stored in the section's `metadata_json` under
+//! `"timing_tracks"`, keyed by recording uid, so they travel with the section
+//! through the chanting export / import.
+//!
+//! A first track is generated from the recording's range markers (one per
+//! line, see [`crate::audio::phrase_markers`]) by spreading each line over
+//! its syllables in proportion to their length (garu = 2, lahu = 1 mattā).
+//! The user can then adjust it and save it back.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::audio::phrase_markers::content_lines;
+use crate::audio::practice_compare::reference_segments_from_markers_json;
+use crate::prosody::{syllabify_line, SyllableWeight};
+
+/// Key of the timing tracks object in `ChantingSection::metadata_json`.
+pub const TIMING_TRACKS_KEY: &str = "timing_tracks";
+pub const TIMING_TRACK_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimingGranularity {
+    Word,
+    #[default]
+    Syllable,
+}
+
+impl TimingGranularity {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "word" => Some(TimingGranularity::Word),
+            "syllable" => Some(TimingGranularity::Syllable),
+            _ => None,
+        }
+    }
+}
+
+/// One word or syllable with its time range. `line` and `word` index into
+/// the non-empty lines of `content_pali` and the words of that line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimedToken {
+    pub text: String,
+    pub line: usize,
+    pub word: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<SyllableWeight>,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimingTrack {
+    pub version: u32,
+    pub granularity: TimingGranularity,
+    pub tokens: Vec<TimedToken>,
+}
+
+/// The token playing at a given position, as returned to the player UI.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimingPosition {
+    pub index: usize,
+    pub line: usize,
+    pub word: usize,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Fraction of the token already sung, 0.0 – 1.0.
+    pub progress: f32,
+}
+
+/// Split `content_pali` into untimed tokens.
+pub fn tokenize_content(content_pali: &str, granularity: TimingGranularity) -> Vec<TimedToken> {
+    let mut tokens = Vec::new();
+    for (line_idx, line) in content_lines(content_pali).iter().enumerate() {
+        let syllables = syllabify_line(line);
+        match granularity {
+            TimingGranularity::Syllable => {
+                tokens.extend(syllables.into_iter().map(|s| TimedToken {
+                    text: s.text,
+                    line: line_idx,
+                    word: s.word_index,
+                    weight: s.weight,
+                    start_ms: 0,
+                    end_ms: 0,
+                }));
+            }
+            TimingGranularity::Word => {
+                for (word_idx, word) in line.split_whitespace().enumerate() {
+                    tokens.push(TimedToken {
+                        text: word.to_string(),
+                        line: line_idx,
+                        word: word_idx,
+                        weight: None,
+                        start_ms: 0,
+                        end_ms: 0,
+                    });
+                }
+            }
+        }
+    }
+    tokens
+}
+
+/// Relative duration of each token: the sum of the syllable lengths in it.
+fn token_morae(content_pali: &str, tokens: &[TimedToken], granularity: TimingGranularity) -> Vec<u32> {
+    match granularity {
+        TimingGranularity::Syllable => tokens.iter().map(|t| t.weight.map(|w| w.morae()).unwrap_or(0)).collect(),
+        TimingGranularity::Word => {
+            let mut per_word: HashMap<(usize, usize), u32> = HashMap::new();
+            for (line_idx, line) in content_lines(content_pali).iter().enumerate() {
+                for s in syllabify_line(line) {
+                    *per_word.entry((line_idx, s.word_index)).or_default() += s.weight.map(|w| w.morae()).unwrap_or(0);
+                }
+            }
+            tokens.iter().map(|t| per_word.get(&(t.line, t.word)).copied().unwrap_or(0)).collect()
+        }
+    }
+}
+
+/// Spread `tokens` over `start_ms..end_ms` in proportion to `weights`.
+fn distribute(tokens: &mut [TimedToken], weights: &[u32], start_ms: i64, end_ms: i64) {
+    let total: u32 = weights.iter().sum();
+    let span = (end_ms - start_ms).max(0);
+    let mut acc: u32 = 0;
+    for (token, &w) in tokens.iter_mut().zip(weights) {
+        let offset = |units: u32| if total == 0 { 0 } else { span * units as i64 / total as i64 };
+        token.start_ms = start_ms + offset(acc);
+        acc += w;
+        token.end_ms = start_ms + offset(acc);
+    }
+}
+
+impl TimingTrack {
+    /// Generate a track for `content_pali` in a recording of `duration_ms`.
+    ///
+    /// When `markers_json` has exactly one range marker per text line, each
+    /// line is placed within its marker; otherwise the lines are spread over
+    /// the whole recording by their length.
+    pub fn generate(
+        content_pali: &str,
+        granularity: TimingGranularity,
+        markers_json: Option<&str>,
+        duration_ms: i64,
+    ) -> TimingTrack {
+        let mut tokens = tokenize_content(content_pali, granularity);
+        let morae = token_morae(content_pali, &tokens, granularity);
+        let line_count = tokens.last().map(|t| t.line + 1).unwrap_or(0);
+
+        let segments = markers_json.map(reference_segments_from_markers_json).unwrap_or_default();
+        let line_ranges: Vec<(i64, i64)> = if !segments.is_empty() && segments.len() == line_count {
+            segments.iter().map(|s| (s.start_ms, s.end_ms)).collect()
+        } else {
+            let line_morae: Vec<u32> = (0..line_count)
+                .map(|l| tokens.iter().zip(&morae).filter(|(t, _)| t.line == l).map(|(_, m)| m).sum())
+                .collect();
+            let total: u32 = line_morae.iter().sum();
+            let mut acc: u32 = 0;
+            line_morae
+                .iter()
+                .map(|&m| {
+                    let at = |units: u32| if total == 0 { 0 } else { duration_ms * units as i64 / total as i64 };
+                    let range = (at(acc), at(acc + m));
+                    acc += m;
+                    range
+                })
+                .collect()
+        };
+
+        let mut start = 0;
+        for (line, &(line_start, line_end)) in line_ranges.iter().enumerate() {
+            let end = start + tokens[start..].iter().take_while(|t| t.line == line).count();
+            distribute(&mut tokens[start..end], &morae[start..end], line_start, line_end);
+            start = end;
+        }
+
+        TimingTrack { version: TIMING_TRACK_VERSION, granularity, tokens }
+    }
+
+    /// Check that every token has a non-negative range and that tokens are in
+    /// time order without overlapping.
+    pub fn validate(&self) -> Result<()> {
+        let mut prev_end = 0;
+        for (i, t) in self.tokens.iter().enumerate() {
+            if t.start_ms < 0 || t.end_ms < t.start_ms {
+                bail!("Token {} '{}' has an invalid range {}..{}", i, t.text, t.start_ms, t.end_ms);
+            }
+            if t.start_ms < prev_end {
+                bail!("Token {} '{}' starts at {} before the previous token ends at {}", i, t.text, t.start_ms, prev_end);
+            }
+            prev_end = t.end_ms;
+        }
+        Ok(())
+    }
+
+    /// Index of the token sounding at `position_ms`, if any. Zero-length
+    /// tokens (punctuation) are never current.
+    pub fn token_index_at(&self, position_ms: i64) -> Option<usize> {
+        let after = self.tokens.partition_point(|t| t.start_ms <= position_ms);
+        (0..after)
+            .rev()
+            .find(|&i| self.tokens[i].start_ms < self.tokens[i].end_ms)
+            .filter(|&i| position_ms < self.tokens[i].end_ms)
+    }
+
+    pub fn position_at(&self, position_ms: i64) -> Option<TimingPosition> {
+        let index = self.token_index_at(position_ms)?;
+        let t = &self.tokens[index];
+        let progress = (position_ms - t.start_ms) as f32 / (t.end_ms - t.start_ms) as f32;
+        Some(TimingPosition {
+            index,
+            line: t.line,
+            word: t.word,
+            start_ms: t.start_ms,
+            end_ms: t.end_ms,
+            progress: progress.clamp(0.0, 1.0),
+        })
+    }
+}
+
+fn parse_metadata(metadata_json: Option<&str>) -> Result<Map<String, Value>> {
+    match metadata_json.map(str::trim).filter(|s| !s.is_empty()) {
+        None => Ok(Map::new()),
+        Some(s) => match serde_json::from_str(s).context("Invalid section metadata_json")? {
+            Value::Object(map) => Ok(map),
+            _ => bail!("Section metadata_json is not a JSON object"),
+        },
+    }
+}
+
+fn serialize_metadata(map: Map<String, Value>) -> Option<String> {
+    if map.is_empty() {
+        None
+    } else {
+        Some(Value::Object(map).to_string())
+    }
+}
+
+/// Read the timing track of `recording_uid` from a section's `metadata_json`.
+pub fn timing_track_from_metadata(metadata_json: Option<&str>, recording_uid: &str) -> Option<TimingTrack> {
+    let map = parse_metadata(metadata_json).ok()?;
+    let track = map.get(TIMING_TRACKS_KEY)?.get(recording_uid)?;
+    serde_json::from_value(track.clone()).ok()
+}
+
+/// Store (or with `None`, remove) the timing track of `recording_uid` in a
+/// section's `metadata_json`, keeping other metadata keys. Returns the new
+/// `metadata_json`.
+pub fn set_timing_track_in_metadata(
+    metadata_json: Option<&str>,
+    recording_uid: &str,
+    track: Option<&TimingTrack>,
+) -> Result<Option<String>> {
+    let mut map = parse_metadata(metadata_json)?;
+    let mut tracks = match map.remove(TIMING_TRACKS_KEY) {
+        Some(Value::Object(tracks)) => tracks,
+        _ => Map::new(),
+    };
+    match track {
+        Some(track) => {
+            track.validate()?;
+            tracks.insert(recording_uid.to_string(), serde_json::to_value(track)?);
+        }
+        None => {
+            tracks.remove(recording_uid);
+        }
+    }
+    if !tracks.is_empty() {
+        map.insert(TIMING_TRACKS_KEY.to_string(), Value::Object(tracks));
+    }
+    Ok(serialize_metadata(map))
+}
+
+/// Rewrite the recording uid keys of the timing tracks in a section's
+/// `metadata_json` after import assigned new uids. Tracks of recordings not in
+/// `uid_map` are dropped. Metadata that cannot be parsed is returned as is.
+pub fn remap_timing_track_recordings(metadata_json: Option<String>, uid_map: &HashMap<String, String>) -> Option<String> {
+    let Ok(mut map) = parse_metadata(metadata_json.as_deref()) else {
+        return metadata_json;
+    };
+    let Some(Value::Object(tracks)) = map.remove(TIMING_TRACKS_KEY) else {
+        return metadata_json;
+    };
+    let remapped: Map<String, Value> = tracks
+        .into_iter()
+        .filter_map(|(uid, track)| uid_map.get(&uid).map(|new_uid| (new_uid.clone(), track)))
+        .collect();
+    if !remapped.is_empty() {
+        map.insert(TIMING_TRACKS_KEY.to_string(), Value::Object(remapped));
+    }
+    serialize_metadata(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "Namo tassa\n\nbhagavato";
+
+    #[test]
+    fn syllable_tokens_follow_lines() {
+        let tokens = tokenize_content(CONTENT, TimingGranularity::Syllable);
+        let texts: Vec<&str> = tokens.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec!["Na", "mo", "tas", "sa", "bha", "ga", "va", "to"]);
+        assert_eq!(tokens[4].line, 1);
+        assert_eq!(tokens[3].word, 1);
+    }
+
+    #[test]
+    fn generate_uses_line_markers_and_morae() {
+        let markers = r#"[
+            {"id": "a", "type": "range", "label": "", "start_ms": 1000, "end_ms": 1600},
+            {"id": "b", "type": "range", "label": "", "start_ms": 2000, "end_ms": 2500}
+        ]"#;
+        let track = TimingTrack::generate(CONTENT, TimingGranularity::Syllable, Some(markers), 3000);
+        track.validate().unwrap();
+
+        // Line 1 morae: na 1, mo 2, tas 2, sa 1 = 6 over 600 ms.
+        let ranges: Vec<(i64, i64)> = track.tokens[..4].iter().map(|t| (t.start_ms, t.end_ms)).collect();
+        assert_eq!(ranges, vec![(1000, 1100), (1100, 1300), (1300, 1500), (1500, 1600)]);
+        // Line 2 morae: bha 1, ga 1, va 1, to 2 = 5 over 500 ms.
+        assert_eq!(track.tokens[7].start_ms, 2300);
+        assert_eq!(track.tokens[7].end_ms, 2500);
+    }
+
+    #[test]
+    fn generate_without_markers_spreads_over_duration() {
+        let track = TimingTrack::generate(CONTENT, TimingGranularity::Word, None, 1100);
+        let ranges: Vec<(i64, i64)> = track.tokens.iter().map(|t| (t.start_ms, t.end_ms)).collect();
+        // Words: namo 3, tassa 3, bhagavato 5 morae.
+        assert_eq!(ranges, vec![(0, 300), (300, 600), (600, 1100)]);
+    }
+
+    #[test]
+    fn position_lookup() {
+        let track = TimingTrack::generate(CONTENT, TimingGranularity::Word, None, 1100);
+        assert_eq!(track.position_at(0).unwrap().index, 0);
+        let pos = track.position_at(450).unwrap();
+        assert_eq!((pos.index, pos.line, pos.word), (1, 0, 1));
+        assert!((pos.progress - 0.5).abs() < 1e-6);
+        assert_eq!(track.position_at(700).unwrap().line, 1);
+        assert!(track.position_at(1100).is_none());
+    }
+
+    #[test]
+    fn metadata_round_trip_and_remap() {
+        let track = TimingTrack::generate(CONTENT, TimingGranularity::Word, None, 1100);
+        let meta = set_timing_track_in_metadata(Some(r#"{"note": "x"}"#), "rec-1", Some(&track)).unwrap();
+        assert_eq!(timing_track_from_metadata(meta.as_deref(), "rec-1"), Some(track.clone()));
+
+        let uid_map = HashMap::from([("rec-1".to_string(), "rec-9".to_string())]);
+        let remapped = remap_timing_track_recordings(meta, &uid_map);
+        assert!(timing_track_from_metadata(remapped.as_deref(), "rec-1").is_none());
+        assert_eq!(timing_track_from_metadata(remapped.as_deref(), "rec-9"), Some(track));
+
+        let cleared = set_timing_track_in_metadata(remapped.as_deref(), "rec-9", None).unwrap();
+        assert_eq!(cleared.as_deref(), Some(r#"{"note":"x"}"#));
+    }
+
+    #[test]
+    fn overlapping_tokens_are_rejected() {
+        let mut track = TimingTrack::generate(CONTENT, TimingGranularity::Word, None, 1100);
+        track.tokens[1].start_ms = 200;
+        assert!(set_timing_track_in_metadata(None, "rec-1", Some(&track)).is_err());
+    }
+}
@@ -0,0 +1,705 @@
+//! Automatic backups of the user's own data, as zip files of the same
+//! per-category files the import-me upgrade export writes (see
+//! `AppData::export_user_data_to_assets`):
+//!
+//! - `app_settings.json`
+//! - `appdata-bookmarks.sqlite3`: bookmark folders and items, not the last session
+//! - `appdata-books.sqlite3`: user-imported books with their spine items and resources
+//! - `appdata-reading-state.sqlite3`: positions, progress and finished marks of suttas and chapters
+//! - `reading-plans.json`: user-added reading plans with the enrollment in them
+//! - `saved-searches.json`: saved searches with the results seen so far
+//! - `appdata-search-history.sqlite3`: the searches run, with their counts
+//! - `appdata-chanting.sqlite3` and `chanting-recordings/`: user chanting data and recordings
+//! - `user_dictionaries.sqlite3`: user-imported dictionaries with their words
+//! - `history.json`: the gloss and prompts history
+//! - `backup-manifest.json`: format, app version, row counts and the file list
+//!
+//! Restoring runs the import-me importers, which add what is missing and skip
+//! what is already there, so a restore doesn't duplicate rows. The app
+//! settings are replaced with the backup's.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Local, NaiveDateTime};
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::Text;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+
+use crate::app_data::AppData;
+use crate::db::appdata_models::{GlossPromptsHistory, NewGlossPromptsHistory};
+use crate::get_app_globals;
+use crate::logger::{error, info};
+
+/// Version of the backup layout. Newer backups are not restored.
+pub const BACKUP_FORMAT: u32 = 1;
+
+const MANIFEST_FILE: &str = "backup-manifest.json";
+const HISTORY_FILE: &str = "history.json";
+const FILE_PREFIX: &str = "simsapa-backup-";
+const NAME_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupFileEntry {
+    /// Path in the zip, with `/` separators.
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: u32,
+    pub created_at: NaiveDateTime,
+    pub app_version: String,
+    /// Rows per category, see `CATEGORIES`.
+    pub counts: BTreeMap<String, usize>,
+    /// Every file of the backup other than the manifest.
+    pub files: Vec<BackupFileEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub created_at: NaiveDateTime,
+    pub size_bytes: u64,
+}
+
+/// What a restore adds in one category.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryDiff {
+    pub category: String,
+    pub in_backup: usize,
+    /// Rows in the backup which are not in the app.
+    pub to_add: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestorePlan {
+    pub manifest: BackupManifest,
+    pub categories: Vec<CategoryDiff>,
+    /// `AppSettings` keys whose value the restore changes.
+    pub settings_changed: Vec<String>,
+    /// False for a dry run.
+    pub applied: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Db {
+    Appdata,
+    Dictionaries,
+}
+
+/// A category of rows in one of the backup's SQLite files, identified by
+/// `key` the same way the importer tells which rows it already has.
+struct Category {
+    name: &'static str,
+    file: &'static str,
+    db: Db,
+    /// Rows the importer takes from the backup file.
+    backup_sql: &'static str,
+    /// The same keys in the live database.
+    live_sql: &'static str,
+}
+
+const CATEGORIES: &[Category] = &[
+    Category {
+        name: "bookmark_folders",
+        file: "appdata-bookmarks.sqlite3",
+        db: Db::Appdata,
+        backup_sql: "SELECT name AS key FROM bookmark_folders WHERE is_last_session = 0 AND is_user_added = 1",
+        live_sql: "SELECT name AS key FROM bookmark_folders",
+    },
+    Category {
+        name: "bookmark_items",
+        file: "appdata-bookmarks.sqlite3",
+        db: Db::Appdata,
+        backup_sql: "SELECT f.name || char(31) || i.item_uid AS key FROM bookmark_items i JOIN bookmark_folders f ON f.id = i.folder_id \
+                     WHERE f.is_last_session = 0 AND f.is_user_added = 1 AND i.is_user_added = 1",
+        live_sql: "SELECT f.name || char(31) || i.item_uid AS key FROM bookmark_items i JOIN bookmark_folders f ON f.id = i.folder_id",
+    },
+    Category {
+        name: "books",
+        file: "appdata-books.sqlite3",
+        db: Db::Appdata,
+        backup_sql: "SELECT uid AS key FROM books WHERE is_user_added = 1",
+        live_sql: "SELECT uid AS key FROM books",
+    },
+    Category {
+        name: "reading_state",
+        file: "appdata-reading-state.sqlite3",
+        db: Db::Appdata,
+        backup_sql: "SELECT table_name || char(31) || item_uid AS key FROM reading_state",
+        live_sql: "SELECT table_name || char(31) || item_uid AS key FROM reading_state",
+    },
+    Category {
+        name: "search_history",
+        file: "appdata-search-history.sqlite3",
+        db: Db::Appdata,
+        backup_sql: "SELECT query_text || char(31) || search_area || char(31) || params_json AS key FROM search_history",
+        live_sql: "SELECT query_text || char(31) || search_area || char(31) || params_json AS key FROM search_history",
+    },
+    Category {
+        name: "chanting_collections",
+        file: "appdata-chanting.sqlite3",
+        db: Db::Appdata,
+        backup_sql: "SELECT uid AS key FROM chanting_collections",
+        live_sql: "SELECT uid AS key FROM chanting_collections",
+    },
+    Category {
+        name: "chanting_chants",
+        file: "appdata-chanting.sqlite3",
+        db: Db::Appdata,
+        backup_sql: "SELECT uid AS key FROM chanting_chants",
+        live_sql: "SELECT uid AS key FROM chanting_chants",
+    },
+    Category {
+        name: "chanting_sections",
+        file: "appdata-chanting.sqlite3",
+        db: Db::Appdata,
+        backup_sql: "SELECT uid AS key FROM chanting_sections",
+        live_sql: "SELECT uid AS key FROM chanting_sections",
+    },
+    Category {
+        name: "chanting_recordings",
+        file: "appdata-chanting.sqlite3",
+        db: Db::Appdata,
+        backup_sql: "SELECT uid AS key FROM chanting_recordings",
+        live_sql: "SELECT uid AS key FROM chanting_recordings",
+    },
+    Category {
+        name: "dictionaries",
+        file: "user_dictionaries.sqlite3",
+        db: Db::Dictionaries,
+        backup_sql: "SELECT label AS key FROM dictionaries",
+        live_sql: "SELECT label AS key FROM dictionaries",
+    },
+];
+
+/// A category of entries in one of the backup's JSON files, identified by
+/// `key` the same way the importer tells which entries it already has.
+struct JsonCategory {
+    name: &'static str,
+    file: &'static str,
+    /// Keys of the entries in the file's JSON.
+    backup_keys: fn(&str) -> Result<Vec<String>>,
+    /// The same keys in the live appdata.
+    live_sql: &'static str,
+}
+
+const JSON_CATEGORIES: &[JsonCategory] = &[
+    JsonCategory {
+        name: "reading_plans",
+        file: "reading-plans.json",
+        backup_keys: |json| {
+            let plans: Vec<crate::reading_plans::EnrolledReadingPlanJson> = serde_json::from_str(json)?;
+            Ok(plans.into_iter().map(|p| p.plan.uid).collect())
+        },
+        live_sql: "SELECT uid AS key FROM reading_plans",
+    },
+    JsonCategory {
+        name: "saved_searches",
+        file: "saved-searches.json",
+        backup_keys: |json| {
+            let searches: Vec<crate::saved_searches::SavedSearchExport> = serde_json::from_str(json)?;
+            Ok(searches.into_iter().map(|s| s.saved_search.name).collect())
+        },
+        live_sql: "SELECT name AS key FROM saved_searches",
+    },
+];
+
+const HISTORY_CATEGORY: &str = "history";
+
+#[derive(QueryableByName)]
+struct KeyRow {
+    #[diesel(sql_type = Text)]
+    key: String,
+}
+
+fn load_keys(db_conn: &mut SqliteConnection, sql: &str) -> Result<Vec<String>> {
+    let rows: Vec<KeyRow> = sql_query(sql).load(db_conn)?;
+    Ok(rows.into_iter().map(|r| r.key).collect())
+}
+
+fn open_sqlite(path: &Path) -> Result<SqliteConnection> {
+    SqliteConnection::establish(&format!("sqlite://{}", path.display()))
+        .with_context(|| format!("Failed to open {}", path.display()))
+}
+
+/// Keys of the category's rows in an extracted backup, empty when the backup
+/// has no such file.
+fn backup_keys(dir: &Path, cat: &Category) -> Result<Vec<String>> {
+    let path = dir.join(cat.file);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    load_keys(&mut open_sqlite(&path)?, cat.backup_sql)
+        .with_context(|| format!("Failed to read {} from {}", cat.name, cat.file))
+}
+
+fn live_keys(app_data: &AppData, db: Db, live_sql: &str) -> Result<HashSet<String>> {
+    let handle = match db {
+        Db::Appdata => &app_data.dbm.appdata,
+        Db::Dictionaries => &app_data.dbm.dictionaries,
+    };
+    let keys = handle.do_read(|db_conn| {
+        let rows: Vec<KeyRow> = sql_query(live_sql).load(db_conn)?;
+        Ok(rows.into_iter().map(|r| r.key).collect())
+    })?;
+    Ok(keys)
+}
+
+/// Keys of the category's entries in an extracted backup, empty when the
+/// backup has no such file.
+fn json_backup_keys(dir: &Path, cat: &JsonCategory) -> Result<Vec<String>> {
+    let path = dir.join(cat.file);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    (cat.backup_keys)(&fs::read_to_string(&path)?)
+        .with_context(|| format!("Failed to read {} from {}", cat.name, cat.file))
+}
+
+/// History rows are the same when their type, time and data are.
+fn history_key(h: &GlossPromptsHistory) -> String {
+    format!("{}\u{1f}{:?}\u{1f}{}", h.item_type, h.created_at, h.data_json)
+}
+
+fn export_history(app_data: &AppData, dir: &Path) -> Result<()> {
+    use crate::db::appdata_schema::gloss_prompts_history::dsl::*;
+
+    let rows: Vec<GlossPromptsHistory> = app_data.dbm.appdata.do_read(|db_conn| {
+        gloss_prompts_history.order(id.asc()).select(GlossPromptsHistory::as_select()).load(db_conn)
+    })?;
+    fs::write(dir.join(HISTORY_FILE), serde_json::to_string(&rows)?)
+        .context("Failed to write history.json")?;
+    Ok(())
+}
+
+fn read_history(dir: &Path) -> Result<Vec<GlossPromptsHistory>> {
+    let path = dir.join(HISTORY_FILE);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&fs::read_to_string(&path)?).context("Failed to parse history.json")
+}
+
+fn live_history_keys(app_data: &AppData) -> Result<HashSet<String>> {
+    use crate::db::appdata_schema::gloss_prompts_history::dsl::*;
+
+    let rows: Vec<GlossPromptsHistory> = app_data.dbm.appdata.do_read(|db_conn| {
+        gloss_prompts_history.select(GlossPromptsHistory::as_select()).load(db_conn)
+    })?;
+    Ok(rows.iter().map(history_key).collect())
+}
+
+/// Insert the history rows the app doesn't have. Returns how many.
+fn import_history(app_data: &AppData, dir: &Path) -> Result<usize> {
+    use crate::db::appdata_schema::gloss_prompts_history::dsl::*;
+
+    let existing = live_history_keys(app_data)?;
+    let new_rows: Vec<GlossPromptsHistory> = read_history(dir)?
+        .into_iter()
+        .filter(|h| !existing.contains(&history_key(h)))
+        .collect();
+
+    app_data.dbm.appdata.do_write(|db_conn| {
+        for h in &new_rows {
+            diesel::insert_into(gloss_prompts_history)
+                .values(NewGlossPromptsHistory {
+                    item_type: &h.item_type,
+                    data_json: &h.data_json,
+                    created_at: h.created_at,
+                    updated_at: h.updated_at,
+                })
+                .execute(db_conn)?;
+        }
+        Ok(())
+    })?;
+    Ok(new_rows.len())
+}
+
+/// Write the backup files of every category to `dir`.
+fn export_all(app_data: &AppData, dir: &Path) -> Result<()> {
+    let steps: [(&str, &dyn Fn() -> Result<()>); 10] = [
+        ("app_settings", &|| app_data.export_app_settings_json(dir)),
+        ("bookmarks", &|| app_data.export_user_bookmarks(dir)),
+        ("books", &|| app_data.export_user_books(dir)),
+        ("reading_state", &|| app_data.export_user_reading_state(dir)),
+        ("reading_plans", &|| app_data.export_user_reading_plans(dir)),
+        ("saved_searches", &|| app_data.export_user_saved_searches(dir)),
+        ("search_history", &|| app_data.export_user_search_history(dir)),
+        ("chanting", &|| app_data.export_user_chanting_data(dir)),
+        ("user_dictionaries", &|| app_data.export_user_dictionaries(dir)),
+        ("history", &|| export_history(app_data, dir)),
+    ];
+    for (category, step) in steps {
+        step().with_context(|| format!("Failed to back up the {}", category))?;
+    }
+    Ok(())
+}
+
+fn zip_path_of(dir: &Path, path: &Path) -> Result<String> {
+    let rel = path.strip_prefix(dir)?;
+    Ok(rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// The backup's file name for its time, with `-2`, `-3`, ... when there is
+/// already one from the same second.
+fn new_backup_path(backups_dir: &Path, created_at: NaiveDateTime) -> PathBuf {
+    let stem = format!("{}{}", FILE_PREFIX, created_at.format(NAME_TIME_FORMAT));
+    let mut path = backups_dir.join(format!("{}.zip", stem));
+    let mut n = 2;
+    while path.exists() {
+        path = backups_dir.join(format!("{}-{}.zip", stem, n));
+        n += 1;
+    }
+    path
+}
+
+/// Time and same-second number of a backup file name.
+fn parse_backup_name(file_name: &str) -> Option<(NaiveDateTime, u32)> {
+    let stem = file_name.strip_prefix(FILE_PREFIX)?.strip_suffix(".zip")?;
+    let (time, n) = match stem.len() {
+        15 => (stem, 1),
+        _ => (stem.get(..15)?, stem.get(15..)?.strip_prefix('-')?.parse().ok()?),
+    };
+    let created_at = NaiveDateTime::parse_from_str(time, NAME_TIME_FORMAT).ok()?;
+    Some((created_at, n))
+}
+
+/// Write a backup of the user data to `backups_dir`, and verify it.
+pub fn create_backup(app_data: &AppData, backups_dir: &Path) -> Result<BackupInfo> {
+    fs::create_dir_all(backups_dir)
+        .with_context(|| format!("Failed to create {}", backups_dir.display()))?;
+
+    let staging = tempfile::tempdir().context("Failed to create temp directory")?;
+    let dir = staging.path();
+    export_all(app_data, dir)?;
+
+    let mut counts = BTreeMap::new();
+    for cat in CATEGORIES {
+        counts.insert(cat.name.to_string(), backup_keys(dir, cat)?.len());
+    }
+    for cat in JSON_CATEGORIES {
+        counts.insert(cat.name.to_string(), json_backup_keys(dir, cat)?.len());
+    }
+    counts.insert(HISTORY_CATEGORY.to_string(), read_history(dir)?.len());
+
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push((entry.path().to_path_buf(), BackupFileEntry {
+                path: zip_path_of(dir, entry.path())?,
+                size: entry.metadata()?.len(),
+            }));
+        }
+    }
+
+    let created_at = Local::now().naive_local();
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT,
+        created_at,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        counts,
+        files: files.iter().map(|(_, f)| f.clone()).collect(),
+    };
+
+    let path = new_backup_path(backups_dir, created_at);
+    let tmp_path = path.with_extension("zip.tmp");
+    let write = || -> Result<()> {
+        let mut zip_writer = zip::ZipWriter::new(fs::File::create(&tmp_path)?);
+        let options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(true);
+
+        zip_writer.start_file(MANIFEST_FILE, options)?;
+        zip_writer.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+        for (src, entry) in &files {
+            zip_writer.start_file(entry.path.as_str(), options)?;
+            io::copy(&mut fs::File::open(src)?, &mut zip_writer)?;
+        }
+        zip_writer.finish()?;
+        Ok(())
+    };
+    if let Err(e) = write().and_then(|_| verify_backup(&tmp_path).map(|_| ())) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.context(format!("Failed to write the backup {}", path.display())));
+    }
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to rename {} to {}", tmp_path.display(), path.display()))?;
+
+    let info_ = BackupInfo { size_bytes: fs::metadata(&path)?.len(), path, created_at };
+    info(&format!("Created backup {} ({} bytes)", info_.path.display(), info_.size_bytes));
+    Ok(info_)
+}
+
+/// Check that the backup is complete and readable: the manifest, every file
+/// it lists with its size and checksum, and the SQLite files' integrity.
+/// Returns the manifest.
+pub fn verify_backup(path: &Path) -> Result<BackupManifest> {
+    let file = fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .with_context(|| format!("{} is not a zip file", path.display()))?;
+
+    let manifest: BackupManifest = {
+        let mut entry = archive.by_name(MANIFEST_FILE)
+            .map_err(|_| anyhow!("{} has no {}", path.display(), MANIFEST_FILE))?;
+        let mut text = String::new();
+        entry.read_to_string(&mut text)?;
+        serde_json::from_str(&text).context("Failed to parse the backup manifest")?
+    };
+    if manifest.format > BACKUP_FORMAT {
+        bail!("The backup was made by a newer version of the app (format {})", manifest.format);
+    }
+
+    let check_dir = tempfile::tempdir().context("Failed to create temp directory")?;
+    for f in &manifest.files {
+        let mut entry = archive.by_name(&f.path)
+            .map_err(|_| anyhow!("The backup is missing {}", f.path))?;
+
+        // Reading an entry to the end checks its CRC.
+        let size = if f.path.ends_with(".sqlite3") {
+            let dest = check_dir.path().join(f.path.replace('/', "_"));
+            let n = io::copy(&mut entry, &mut fs::File::create(&dest)?)
+                .with_context(|| format!("{} is damaged", f.path))?;
+            check_sqlite(&dest).with_context(|| format!("{} is damaged", f.path))?;
+            n
+        } else {
+            io::copy(&mut entry, &mut io::sink()).with_context(|| format!("{} is damaged", f.path))?
+        };
+        if size != f.size {
+            bail!("{} has {} bytes instead of {}", f.path, size, f.size);
+        }
+    }
+
+    Ok(manifest)
+}
+
+#[derive(QueryableByName)]
+struct IntegrityRow {
+    #[diesel(sql_type = Text)]
+    integrity_check: String,
+}
+
+fn check_sqlite(path: &Path) -> Result<()> {
+    let rows: Vec<IntegrityRow> = sql_query("PRAGMA integrity_check").load(&mut open_sqlite(path)?)?;
+    match rows.first() {
+        Some(r) if r.integrity_check == "ok" => Ok(()),
+        Some(r) => bail!("integrity check: {}", r.integrity_check),
+        None => bail!("integrity check returned nothing"),
+    }
+}
+
+/// The backups in `backups_dir`, newest first.
+pub fn list_backups(backups_dir: &Path) -> Result<Vec<BackupInfo>> {
+    if !backups_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<(NaiveDateTime, u32, BackupInfo)> = Vec::new();
+    for entry in fs::read_dir(backups_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some((created_at, n)) = parse_backup_name(&name) {
+            let info_ = BackupInfo { path: entry.path(), created_at, size_bytes: entry.metadata()?.len() };
+            backups.push((created_at, n, info_));
+        }
+    }
+    backups.sort_by_key(|b| std::cmp::Reverse((b.0, b.1)));
+    Ok(backups.into_iter().map(|(_, _, b)| b).collect())
+}
+
+/// Remove all but the `keep` newest backups (at least one is kept). Returns
+/// the removed files.
+pub fn prune_backups(backups_dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for b in list_backups(backups_dir)?.into_iter().skip(keep.max(1)) {
+        fs::remove_file(&b.path).with_context(|| format!("Failed to remove {}", b.path.display()))?;
+        info(&format!("Removed old backup {}", b.path.display()));
+        removed.push(b.path);
+    }
+    Ok(removed)
+}
+
+fn extract(path: &Path, dest: &Path) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+    archive.extract(dest).with_context(|| format!("Failed to extract {}", path.display()))
+}
+
+/// Compare an extracted backup with the app's data.
+fn plan_restore(app_data: &AppData, dir: &Path, manifest: BackupManifest) -> Result<RestorePlan> {
+    let mut categories = Vec::new();
+    let mut push_diff = |name: &str, keys: Vec<String>, live: HashSet<String>| {
+        let to_add = keys.iter().collect::<HashSet<_>>().into_iter().filter(|k| !live.contains(*k)).count();
+        categories.push(CategoryDiff { category: name.to_string(), in_backup: keys.len(), to_add });
+    };
+    for cat in CATEGORIES {
+        push_diff(cat.name, backup_keys(dir, cat)?, live_keys(app_data, cat.db, cat.live_sql)?);
+    }
+    for cat in JSON_CATEGORIES {
+        push_diff(cat.name, json_backup_keys(dir, cat)?, live_keys(app_data, Db::Appdata, cat.live_sql)?);
+    }
+
+    let history = read_history(dir)?;
+    let live = live_history_keys(app_data)?;
+    categories.push(CategoryDiff {
+        category: HISTORY_CATEGORY.to_string(),
+        in_backup: history.len(),
+        to_add: history.iter().filter(|h| !live.contains(&history_key(h))).count(),
+    });
+
+    let mut settings_changed = Vec::new();
+    let settings_path = dir.join("app_settings.json");
+    if settings_path.is_file() {
+        let backup: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings_path)?)
+            .context("Failed to parse app_settings.json")?;
+        let current = {
+            let settings = app_data.app_settings_cache.read().expect("Failed to read app settings");
+            serde_json::to_value(&*settings)?
+        };
+        if let (Some(backup), Some(current)) = (backup.as_object(), current.as_object()) {
+            settings_changed = backup.iter()
+                .filter(|(k, v)| current.get(*k) != Some(*v))
+                .map(|(k, _)| k.clone())
+                .collect();
+        }
+    }
+
+    Ok(RestorePlan { manifest, categories, settings_changed, applied: false })
+}
+
+/// Leave out of the extracted dictionaries snapshot the dictionaries the
+/// app already has, the importer would add their words a second time.
+fn drop_existing_dictionaries(app_data: &AppData, dir: &Path) -> Result<()> {
+    let path = dir.join("user_dictionaries.sqlite3");
+    if !path.is_file() {
+        return Ok(());
+    }
+    let cat = CATEGORIES.iter().find(|c| c.name == "dictionaries").expect("dictionaries category");
+    let live = live_keys(app_data, cat.db, cat.live_sql)?;
+
+    let mut db_conn = open_sqlite(&path)?;
+    for label in load_keys(&mut db_conn, cat.backup_sql)? {
+        if live.contains(&label) {
+            sql_query("DELETE FROM dict_words WHERE dictionary_id IN (SELECT id FROM dictionaries WHERE label = ?)")
+                .bind::<Text, _>(&label)
+                .execute(&mut db_conn)?;
+            sql_query("DELETE FROM dictionaries WHERE label = ?")
+                .bind::<Text, _>(&label)
+                .execute(&mut db_conn)?;
+        }
+    }
+    Ok(())
+}
+
+/// Restore the backup at `path`, or with `dry_run` only report what the
+/// restore would add and change. Restored dictionaries are indexed by the
+/// dictionary index reconciliation on the next start.
+pub fn restore_backup(app_data: &AppData, path: &Path, dry_run: bool) -> Result<RestorePlan> {
+    let manifest = verify_backup(path)?;
+    let staging = tempfile::tempdir().context("Failed to create temp directory")?;
+    let dir = staging.path();
+    extract(path, dir)?;
+
+    let mut plan = plan_restore(app_data, dir, manifest)?;
+    if dry_run {
+        return Ok(plan);
+    }
+
+    if !plan.settings_changed.is_empty() {
+        app_data.import_app_settings_json(dir).context("Failed to restore the app settings")?;
+    }
+    app_data.import_user_bookmarks(dir).context("Failed to restore the bookmarks")?;
+    app_data.import_user_books(dir).context("Failed to restore the books")?;
+    app_data.import_user_reading_state(dir).context("Failed to restore the reading state")?;
+    app_data.import_user_reading_plans(dir).context("Failed to restore the reading plans")?;
+    app_data.restore_user_saved_searches(dir).context("Failed to restore the saved searches")?;
+    app_data.import_user_search_history(dir).context("Failed to restore the search history")?;
+    app_data.import_user_chanting_data(dir).context("Failed to restore the chanting data")?;
+    drop_existing_dictionaries(app_data, dir)?;
+    app_data.import_user_dictionaries(dir).context("Failed to restore the dictionaries")?;
+    import_history(app_data, dir).context("Failed to restore the history")?;
+
+    plan.applied = true;
+    info(&format!("Restored backup {}", path.display()));
+    Ok(plan)
+}
+
+/// The backup folder of the settings, or `backups/` in the Simsapa folder.
+pub fn backups_dir(app_data: &AppData) -> PathBuf {
+    let settings = app_data.app_settings_cache.read().expect("Failed to read app settings");
+    match settings.backup_folder.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => get_app_globals().paths.simsapa_dir.join("backups"),
+    }
+}
+
+/// Whether a backup is due, `interval_hours` after the newest one. An
+/// interval of 0 turns the automatic backups off.
+pub fn backup_due(newest: Option<NaiveDateTime>, now: NaiveDateTime, interval_hours: u32) -> bool {
+    if interval_hours == 0 {
+        return false;
+    }
+    match newest {
+        Some(t) => now - t >= chrono::Duration::hours(interval_hours as i64),
+        None => true,
+    }
+}
+
+/// Make a backup if one is due by the settings, and remove the old ones.
+pub fn run_scheduled_backup(app_data: &AppData) -> Result<Option<BackupInfo>> {
+    let (interval_hours, keep) = {
+        let settings = app_data.app_settings_cache.read().expect("Failed to read app settings");
+        (settings.backup_interval_hours, settings.backup_keep)
+    };
+    let dir = backups_dir(app_data);
+
+    let newest = list_backups(&dir)?.first().map(|b| b.created_at);
+    if !backup_due(newest, Local::now().naive_local(), interval_hours) {
+        return Ok(None);
+    }
+
+    let backup = create_backup(app_data, &dir)?;
+    if let Err(e) = prune_backups(&dir, keep) {
+        error(&format!("Failed to remove old backups: {:#}", e));
+    }
+    Ok(Some(backup))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_backup_names() {
+        let created = t("2026-10-18 09:05:00");
+        assert_eq!(parse_backup_name("simsapa-backup-20261018-090500.zip"), Some((created, 1)));
+        assert_eq!(parse_backup_name("simsapa-backup-20261018-090500-3.zip"), Some((created, 3)));
+        assert_eq!(parse_backup_name("simsapa-backup-20261018-090500.zip.tmp"), None);
+        assert_eq!(parse_backup_name("notes.zip"), None);
+    }
+
+    #[test]
+    fn test_backup_due() {
+        let now = t("2026-10-18 12:00:00");
+        assert!(backup_due(None, now, 24));
+        assert!(!backup_due(None, now, 0));
+        assert!(!backup_due(Some(t("2026-10-18 00:00:00")), now, 24));
+        assert!(backup_due(Some(t("2026-10-17 12:00:00")), now, 24));
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use diesel::prelude::*;
 use regex::Regex;
 use anyhow::{Context, Result};
@@ -5,7 +7,10 @@ use serde::Serialize;
 
 use crate::get_app_data;
 use crate::db::appdata_models::*;
+use crate::reading_plans::{sutta_ref_uid, ReadingPlanEnrollmentJson, ReadingPlanItemJson, ReadingPlanJson, PLAN_FORMAT, PLAN_FORMAT_VERSION};
 use crate::db::DatabaseHandle;
+use crate::types::SearchResult;
+use crate::pali_sort::pali_list_sorter;
 use crate::app_settings::AppSettings;
 use crate::logger::{info, error};
 
@@ -836,6 +841,43 @@ impl AppdataDbHandle {
         })
     }
 
+    /// The library chapters citing a sutta, in book title and chapter order.
+    /// `sutta_uid_param` may have the /lang/author suffix, e.g. mn10/en/sujato.
+    pub fn get_chapters_citing_sutta(&self, sutta_uid_param: &str) -> Result<Vec<SuttaCitingChapter>> {
+        use crate::db::appdata_schema::{book_spine_items, book_sutta_citations, books};
+
+        let sutta_uid_param = sutta_uid_param.split('/').next().unwrap_or_default().to_lowercase();
+
+        let rows: Vec<SuttaCitingChapterRow> = self.do_read(|db_conn| {
+            book_sutta_citations::table
+                .inner_join(book_spine_items::table.on(book_spine_items::spine_item_uid.eq(book_sutta_citations::spine_item_uid)))
+                .inner_join(books::table.on(books::id.eq(book_sutta_citations::book_id)))
+                .filter(book_sutta_citations::sutta_uid.eq(&sutta_uid_param))
+                .order((books::title.asc(), book_spine_items::spine_index.asc()))
+                .select((
+                    books::uid,
+                    books::title,
+                    book_spine_items::spine_item_uid,
+                    book_spine_items::title,
+                    book_sutta_citations::ref_text,
+                    book_sutta_citations::ref_count,
+                ))
+                .load(db_conn)
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SuttaCitingChapter {
+                book_title: row.book_title.unwrap_or_else(|| row.book_uid.clone()),
+                chapter_title: row.chapter_title.unwrap_or_default(),
+                book_uid: row.book_uid,
+                spine_item_uid: row.spine_item_uid,
+                ref_text: row.ref_text,
+                ref_count: row.ref_count,
+            })
+            .collect())
+    }
+
     pub fn update_book_metadata(&self, book_uid_param: &str, title_param: &str, author_param: &str, language_param: &str, enable_embedded_css_param: bool) -> Result<()> {
         use crate::db::appdata_schema::books::dsl::*;
 
@@ -1267,6 +1309,17 @@ impl AppdataDbHandle {
         })
     }
 
+    pub fn update_chanting_section_metadata(&self, section_uid_param: &str, new_metadata_json: Option<&str>) -> Result<()> {
+        use crate::db::appdata_schema::chanting_sections::dsl::*;
+
+        self.do_write(|db_conn| {
+            diesel::update(chanting_sections.filter(uid.eq(section_uid_param)))
+                .set(metadata_json.eq(new_metadata_json))
+                .execute(db_conn)
+                .map(|_| ())
+        })
+    }
+
     pub fn update_recording_volume(&self, recording_uid_param: &str, new_volume: f32) -> Result<()> {
         use crate::db::appdata_schema::chanting_recordings::dsl::*;
 
@@ -1665,6 +1718,46 @@ impl AppdataDbHandle {
         }
     }
 
+    pub fn get_bookmark_folder(&self, folder_id_param: i32) -> Option<BookmarkFolder> {
+        use crate::db::appdata_schema::bookmark_folders::dsl::*;
+
+        let result = self.do_read(|db_conn| {
+            bookmark_folders
+                .find(folder_id_param)
+                .select(BookmarkFolder::as_select())
+                .first(db_conn)
+                .optional()
+        });
+
+        match result {
+            Ok(folder) => folder,
+            Err(e) => {
+                error(&format!("get_bookmark_folder(): {}", e));
+                None
+            }
+        }
+    }
+
+    pub fn get_bookmark_item(&self, item_id_param: i32) -> Option<BookmarkItem> {
+        use crate::db::appdata_schema::bookmark_items::dsl::*;
+
+        let result = self.do_read(|db_conn| {
+            bookmark_items
+                .find(item_id_param)
+                .select(BookmarkItem::as_select())
+                .first(db_conn)
+                .optional()
+        });
+
+        match result {
+            Ok(item) => item,
+            Err(e) => {
+                error(&format!("get_bookmark_item(): {}", e));
+                None
+            }
+        }
+    }
+
     pub fn create_bookmark_folder(&self, name_param: &str, is_last_session_param: bool) -> Result<i32> {
         use crate::db::appdata_schema::bookmark_folders::dsl::*;
 
@@ -1969,6 +2062,1209 @@ impl AppdataDbHandle {
                 .map(|_| ())
         })
     }
+
+    // === Reading state ===
+
+    pub fn get_reading_state(&self, item_uid_param: &str, table_name_param: &str) -> Result<Option<ReadingState>> {
+        use crate::db::appdata_schema::reading_state::dsl::*;
+
+        self.do_read(|db_conn| {
+            reading_state
+                .filter(item_uid.eq(item_uid_param))
+                .filter(table_name.eq(table_name_param))
+                .select(ReadingState::as_select())
+                .first(db_conn)
+                .optional()
+        })
+    }
+
+    /// Record reading a sutta or a book chapter: its last scroll position,
+    /// the furthest position read and `seconds` more reading time. Reading
+    /// past READ_TO_END_PROGRESS marks the item finished, unless it was
+    /// already read to the end and marked unread since.
+    pub fn record_reading_progress(&self,
+                                   item_uid_param: &str,
+                                   table_name_param: &str,
+                                   scroll_position_param: f32,
+                                   progress_param: f32,
+                                   seconds: i32) -> Result<()> {
+        use crate::db::appdata_schema::reading_state::dsl::*;
+
+        let scroll_position_param = scroll_position_param.clamp(0.0, 1.0);
+        let progress_param = progress_param.clamp(0.0, 1.0).max(scroll_position_param);
+        let seconds = seconds.max(0);
+        let now = chrono::Utc::now().naive_utc();
+
+        self.do_write(|db_conn| {
+            let existing: Option<ReadingState> = reading_state
+                .filter(item_uid.eq(item_uid_param))
+                .filter(table_name.eq(table_name_param))
+                .select(ReadingState::as_select())
+                .first(db_conn)
+                .optional()?;
+
+            match existing {
+                Some(state) => {
+                    let finished_now = !state.is_finished
+                        && state.progress < READ_TO_END_PROGRESS
+                        && progress_param >= READ_TO_END_PROGRESS;
+                    diesel::update(reading_state.find(state.id))
+                        .set((
+                            scroll_position.eq(scroll_position_param),
+                            progress.eq(state.progress.max(progress_param)),
+                            time_spent_secs.eq(state.time_spent_secs.saturating_add(seconds)),
+                            is_finished.eq(state.is_finished || finished_now),
+                            finished_at.eq(if finished_now { Some(now) } else { state.finished_at }),
+                            last_read_at.eq(Some(now)),
+                        ))
+                        .execute(db_conn)?;
+                }
+                None => {
+                    let finished = progress_param >= READ_TO_END_PROGRESS;
+                    let (ref_uid, book) = reading_item_refs(db_conn, item_uid_param, table_name_param)?;
+                    diesel::insert_into(reading_state)
+                        .values(&NewReadingState {
+                            item_uid: item_uid_param,
+                            table_name: table_name_param,
+                            sutta_ref_uid: ref_uid.as_deref(),
+                            book_uid: book.as_deref(),
+                            scroll_position: scroll_position_param,
+                            progress: progress_param,
+                            time_spent_secs: seconds,
+                            is_finished: finished,
+                            finished_at: if finished { Some(now) } else { None },
+                            last_read_at: Some(now),
+                        })
+                        .execute(db_conn)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Mark a sutta or a book chapter read or unread.
+    pub fn set_reading_finished(&self, item_uid_param: &str, table_name_param: &str, finished: bool) -> Result<()> {
+        use crate::db::appdata_schema::reading_state::dsl::*;
+
+        let now = chrono::Utc::now().naive_utc();
+        let finished_at_value = if finished { Some(now) } else { None };
+
+        self.do_write(|db_conn| {
+            let existing_id: Option<i32> = reading_state
+                .filter(item_uid.eq(item_uid_param))
+                .filter(table_name.eq(table_name_param))
+                .select(id)
+                .first(db_conn)
+                .optional()?;
+
+            match existing_id {
+                Some(state_id) => {
+                    diesel::update(reading_state.find(state_id))
+                        .set((is_finished.eq(finished), finished_at.eq(finished_at_value)))
+                        .execute(db_conn)?;
+                }
+                None => {
+                    let (ref_uid, book) = reading_item_refs(db_conn, item_uid_param, table_name_param)?;
+                    diesel::insert_into(reading_state)
+                        .values(&NewReadingState {
+                            item_uid: item_uid_param,
+                            table_name: table_name_param,
+                            sutta_ref_uid: ref_uid.as_deref(),
+                            book_uid: book.as_deref(),
+                            scroll_position: 0.0,
+                            progress: 0.0,
+                            time_spent_secs: 0,
+                            is_finished: finished,
+                            finished_at: finished_at_value,
+                            last_read_at: None,
+                        })
+                        .execute(db_conn)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Reading progress of a book over its chapters (`get_spine_items_for_book`).
+    pub fn get_book_reading_progress(&self, book_uid_param: &str) -> Result<BookReadingProgress> {
+        use crate::db::appdata_schema::{book_spine_items, reading_state};
+
+        let (spine_uids, states) = self.do_read(|db_conn| {
+            let spine_uids: Vec<String> = book_spine_items::table
+                .filter(book_spine_items::book_uid.eq(book_uid_param))
+                .select(book_spine_items::spine_item_uid)
+                .load(db_conn)?;
+            let states: Vec<ReadingState> = reading_state::table
+                .filter(reading_state::table_name.eq("book_spine_items"))
+                .filter(reading_state::item_uid.eq_any(&spine_uids))
+                .select(ReadingState::as_select())
+                .load(db_conn)?;
+            Ok((spine_uids, states))
+        })?;
+
+        let chapters_total = spine_uids.len() as i32;
+        let read_sum: f32 = states.iter()
+            .map(|s| if s.is_finished { 1.0 } else { s.progress })
+            .sum();
+        let last = states.iter()
+            .filter(|s| s.last_read_at.is_some())
+            .max_by_key(|s| s.last_read_at);
+
+        Ok(BookReadingProgress {
+            book_uid: book_uid_param.to_string(),
+            chapters_total,
+            chapters_started: states.iter().filter(|s| s.last_read_at.is_some() || s.is_finished).count() as i32,
+            chapters_finished: states.iter().filter(|s| s.is_finished).count() as i32,
+            percent_read: if chapters_total > 0 { read_sum / chapters_total as f32 * 100.0 } else { 0.0 },
+            time_spent_secs: states.iter().map(|s| s.time_spent_secs as i64).sum(),
+            last_spine_item_uid: last.map(|s| s.item_uid.clone()),
+            last_read_at: last.and_then(|s| s.last_read_at),
+        })
+    }
+
+    /// Canon reading progress per nikāya, e.g. how much of MN or SN was read.
+    /// A sutta counts once across its translations. Without a `nikaya_param`
+    /// only the nikāyas with some reading are returned. `group_path_prefix`
+    /// limits the count to a part of the `suttas.group_path` tree, which is
+    /// set on the CST texts.
+    pub fn get_canon_reading_progress(&self, nikaya_param: Option<&str>, group_path_prefix: Option<&str>) -> Result<Vec<CanonReadingProgress>> {
+        use diesel::sql_types::Text;
+
+        let sql = r#"
+            SELECT s.nikaya AS nikaya,
+                   COUNT(DISTINCT s.ref_uid) AS suttas_total,
+                   COUNT(DISTINCT r.sutta_ref_uid) AS suttas_started,
+                   COUNT(DISTINCT CASE WHEN r.is_finished THEN r.sutta_ref_uid END) AS suttas_finished
+            FROM (
+                SELECT nikaya,
+                       CASE WHEN instr(uid, '/') > 0 THEN substr(uid, 1, instr(uid, '/') - 1) ELSE uid END AS ref_uid
+                FROM suttas
+                WHERE nikaya != ''
+                  AND (?1 = '' OR nikaya = ?1)
+                  AND (?2 = '' OR group_path LIKE ?2 || '%')
+            ) s
+            LEFT JOIN reading_state r
+                ON r.table_name = 'suttas' AND r.sutta_ref_uid = s.ref_uid
+            GROUP BY s.nikaya
+            HAVING ?1 != '' OR COUNT(DISTINCT r.sutta_ref_uid) > 0
+            ORDER BY s.nikaya
+        "#;
+
+        let nikaya_param = nikaya_param.unwrap_or_default().to_lowercase();
+        let group_path_prefix = group_path_prefix.unwrap_or_default().to_string();
+
+        self.do_read(|db_conn| {
+            diesel::sql_query(sql)
+                .bind::<Text, _>(&nikaya_param)
+                .bind::<Text, _>(&group_path_prefix)
+                .load::<CanonReadingProgress>(db_conn)
+        })
+    }
+
+    /// Reading time and counts of started and finished suttas and chapters,
+    /// with the `recent_limit` items read last.
+    pub fn get_reading_statistics(&self, recent_limit: i64) -> Result<ReadingStatistics> {
+        use crate::db::appdata_schema::reading_state::dsl::*;
+
+        let (states, recent) = self.do_read(|db_conn| {
+            let states: Vec<ReadingState> = reading_state
+                .select(ReadingState::as_select())
+                .load(db_conn)?;
+            let recent: Vec<ReadingState> = reading_state
+                .filter(last_read_at.is_not_null())
+                .order(last_read_at.desc())
+                .limit(recent_limit)
+                .select(ReadingState::as_select())
+                .load(db_conn)?;
+            Ok((states, recent))
+        })?;
+
+        let mut suttas_started = HashSet::new();
+        let mut suttas_finished = HashSet::new();
+        let mut chapters_started = 0;
+        let mut chapters_finished = 0;
+        for s in states.iter() {
+            match s.table_name.as_str() {
+                "suttas" => {
+                    let ref_uid = s.sutta_ref_uid.as_deref().unwrap_or(&s.item_uid);
+                    suttas_started.insert(ref_uid);
+                    if s.is_finished {
+                        suttas_finished.insert(ref_uid);
+                    }
+                }
+                "book_spine_items" => {
+                    chapters_started += 1;
+                    if s.is_finished {
+                        chapters_finished += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ReadingStatistics {
+            time_spent_secs: states.iter().map(|s| s.time_spent_secs as i64).sum(),
+            suttas_started: suttas_started.len() as i64,
+            suttas_finished: suttas_finished.len() as i64,
+            chapters_started,
+            chapters_finished,
+            recent,
+        })
+    }
+
+    // === Reading plans ===
+
+    /// The first sutta of a nikāya (e.g. "mn", "sn"), in the given language
+    /// and source where it exists.
+    pub fn get_first_sutta_of_nikaya(&self, nikaya_param: &str, language_param: &str, source_uid_param: Option<&str>) -> Result<Option<Sutta>> {
+        use crate::db::appdata_schema::suttas::dsl::*;
+
+        let nikaya_param = nikaya_param.to_lowercase();
+        let groups = [nikaya_param.clone(), format!("{}1", nikaya_param)];
+
+        let candidates: Vec<Sutta> = self.do_read(|db_conn| {
+            suttas
+                .filter(nikaya.eq(&nikaya_param))
+                .filter(sutta_range_group.eq_any(&groups))
+                .filter(sutta_range_start.is_not_null())
+                .order((sutta_range_group.asc(), sutta_range_start.asc()))
+                .limit(100)
+                .select(Sutta::as_select())
+                .load(db_conn)
+        })?;
+
+        // Only the texts of the first sutta, then prefer the language and source
+        let first_key = candidates.first().map(|s| (s.sutta_range_group.clone(), s.sutta_range_start));
+        let candidates: Vec<Sutta> = candidates
+            .into_iter()
+            .filter(|s| Some((s.sutta_range_group.clone(), s.sutta_range_start)) == first_key)
+            .collect();
+
+        Ok(self.prioritize_sutta_by_language_and_source(
+            candidates,
+            language_param,
+            &source_uid_param.map(|s| s.to_string()),
+        ))
+    }
+
+    /// The suttas under a part of the `group_path` tree, in their order.
+    pub fn get_suttas_by_group_path_prefix(&self, group_path_prefix: &str) -> Result<Vec<Sutta>> {
+        use crate::db::appdata_schema::suttas::dsl::*;
+
+        let pattern = format!("{}%", group_path_prefix);
+        self.do_read(|db_conn| {
+            suttas
+                .filter(group_path.like(&pattern))
+                .order((group_path.asc(), order_index.asc(), uid.asc()))
+                .select(Sutta::as_select())
+                .load(db_conn)
+        })
+    }
+
+    /// Add a reading plan, or replace the plan and its items if a plan with
+    /// the same uid exists. The enrollment in a replaced plan is kept.
+    /// Returns the plan id.
+    pub fn save_reading_plan(&self, plan: &ReadingPlanJson) -> Result<i32> {
+        use crate::db::appdata_schema::{reading_plan_completions, reading_plan_enrollments, reading_plan_items, reading_plans};
+
+        let mut plan = plan.clone();
+        plan.validate()?;
+
+        self.do_write(|db_conn| {
+            db_conn.transaction(|tx| {
+                let existing: Option<i32> = reading_plans::table
+                    .filter(reading_plans::uid.eq(&plan.uid))
+                    .select(reading_plans::id)
+                    .first(tx)
+                    .optional()?;
+
+                let plan_id = match existing {
+                    Some(plan_id) => {
+                        diesel::update(reading_plans::table.find(plan_id))
+                            .set((
+                                reading_plans::title.eq(&plan.title),
+                                reading_plans::description.eq(&plan.description),
+                                reading_plans::author.eq(&plan.author),
+                                reading_plans::schedule.eq(plan.schedule.as_str()),
+                            ))
+                            .execute(tx)?;
+                        diesel::delete(reading_plan_items::table.filter(reading_plan_items::plan_id.eq(plan_id)))
+                            .execute(tx)?;
+                        plan_id
+                    }
+                    None => {
+                        diesel::insert_into(reading_plans::table)
+                            .values(&NewReadingPlan {
+                                uid: &plan.uid,
+                                title: &plan.title,
+                                description: plan.description.as_deref(),
+                                author: plan.author.as_deref(),
+                                schedule: plan.schedule.as_str(),
+                                is_user_added: true,
+                            })
+                            .returning(reading_plans::id)
+                            .get_result::<i32>(tx)?
+                    }
+                };
+
+                let new_items: Vec<NewReadingPlanItem> = plan.items
+                    .iter()
+                    .enumerate()
+                    .map(|(n, item)| NewReadingPlanItem {
+                        plan_id,
+                        item_index: n as i32,
+                        day_number: item.day.unwrap_or(n as i32 + 1),
+                        item_uid: &item.item_uid,
+                        table_name: &item.table_name,
+                        title: item.title.as_deref(),
+                    })
+                    .collect();
+                diesel::insert_into(reading_plan_items::table)
+                    .values(&new_items)
+                    .execute(tx)?;
+
+                // Completions past the end of a shortened plan
+                let enrollment_ids = reading_plan_enrollments::table
+                    .filter(reading_plan_enrollments::plan_id.eq(plan_id))
+                    .select(reading_plan_enrollments::id);
+                diesel::delete(reading_plan_completions::table
+                    .filter(reading_plan_completions::enrollment_id.eq_any(enrollment_ids))
+                    .filter(reading_plan_completions::item_index.ge(new_items.len() as i32)))
+                    .execute(tx)?;
+
+                Ok(plan_id)
+            })
+        })
+    }
+
+    /// A plan in the JSON file format, for sharing it.
+    pub fn get_reading_plan(&self, plan_uid: &str) -> Result<Option<ReadingPlanJson>> {
+        let Some((plan, items)) = self.load_reading_plan(plan_uid)? else {
+            return Ok(None);
+        };
+        Ok(Some(ReadingPlanJson {
+            format: PLAN_FORMAT.to_string(),
+            version: PLAN_FORMAT_VERSION,
+            uid: plan.uid,
+            title: plan.title,
+            description: plan.description,
+            author: plan.author,
+            schedule: plan.schedule.parse()?,
+            items: items
+                .into_iter()
+                .map(|i| ReadingPlanItemJson {
+                    day: Some(i.day_number),
+                    item_uid: i.item_uid,
+                    table_name: i.table_name,
+                    title: i.title,
+                })
+                .collect(),
+        }))
+    }
+
+    fn load_reading_plan(&self, plan_uid: &str) -> Result<Option<(ReadingPlan, Vec<ReadingPlanItem>)>> {
+        use crate::db::appdata_schema::{reading_plan_items, reading_plans};
+
+        self.do_read(|db_conn| {
+            let plan: Option<ReadingPlan> = reading_plans::table
+                .filter(reading_plans::uid.eq(plan_uid))
+                .select(ReadingPlan::as_select())
+                .first(db_conn)
+                .optional()?;
+            let Some(plan) = plan else {
+                return Ok(None);
+            };
+            let items: Vec<ReadingPlanItem> = ReadingPlanItem::belonging_to(&plan)
+                .order(reading_plan_items::item_index.asc())
+                .select(ReadingPlanItem::as_select())
+                .load(db_conn)?;
+            Ok(Some((plan, items)))
+        })
+    }
+
+    pub fn delete_reading_plan(&self, plan_uid: &str) -> Result<()> {
+        use crate::db::appdata_schema::reading_plans;
+
+        self.do_write(|db_conn| {
+            diesel::delete(reading_plans::table.filter(reading_plans::uid.eq(plan_uid)))
+                .execute(db_conn)
+                .map(|_| ())
+        })
+    }
+
+    /// All plans with their enrollment and progress on `date`.
+    pub fn get_reading_plans(&self, date: chrono::NaiveDate) -> Result<Vec<ReadingPlanSummary>> {
+        let mut summaries = Vec::new();
+        for plan_uid in self.get_reading_plan_uids()? {
+            let Some((plan, items)) = self.load_reading_plan(&plan_uid)? else {
+                continue;
+            };
+            let enrollment = self.get_reading_plan_enrollment(plan.id)?;
+            let (items_completed, current_day) = match &enrollment {
+                Some(enrollment) => {
+                    let done = self.do_read(|db_conn| completed_plan_items(db_conn, enrollment, &items))?;
+                    (done.len() as i32, current_plan_day(&plan, enrollment, &items, &done, date))
+                }
+                None => (0, None),
+            };
+            summaries.push(ReadingPlanSummary {
+                items_total: items.len() as i32,
+                days_total: items.iter().map(|i| i.day_number).max().unwrap_or(0),
+                enrollment,
+                items_completed,
+                current_day,
+                plan,
+            });
+        }
+        Ok(summaries)
+    }
+
+    fn get_reading_plan_uids(&self) -> Result<Vec<String>> {
+        use crate::db::appdata_schema::reading_plans;
+
+        self.do_read(|db_conn| {
+            reading_plans::table
+                .order(reading_plans::title.asc())
+                .select(reading_plans::uid)
+                .load(db_conn)
+        })
+    }
+
+    fn get_reading_plan_enrollment(&self, plan_id_param: i32) -> Result<Option<ReadingPlanEnrollment>> {
+        use crate::db::appdata_schema::reading_plan_enrollments::dsl::*;
+
+        self.do_read(|db_conn| {
+            reading_plan_enrollments
+                .filter(plan_id.eq(plan_id_param))
+                .select(ReadingPlanEnrollment::as_select())
+                .first(db_conn)
+                .optional()
+        })
+    }
+
+    /// Start a plan on `start_date`. Enrolling in a plan again restarts it.
+    pub fn enroll_in_reading_plan(&self, plan_uid: &str, start_date: chrono::NaiveDate) -> Result<()> {
+        use crate::db::appdata_schema::{reading_plan_enrollments, reading_plans};
+
+        self.do_write(|db_conn| {
+            db_conn.transaction(|tx| {
+                let plan_id: i32 = reading_plans::table
+                    .filter(reading_plans::uid.eq(plan_uid))
+                    .select(reading_plans::id)
+                    .first(tx)?;
+                diesel::delete(reading_plan_enrollments::table.filter(reading_plan_enrollments::plan_id.eq(plan_id)))
+                    .execute(tx)?;
+                diesel::insert_into(reading_plan_enrollments::table)
+                    .values(&NewReadingPlanEnrollment {
+                        plan_id,
+                        start_date,
+                        is_active: true,
+                    })
+                    .execute(tx)
+                    .map(|_| ())
+            })
+        })
+    }
+
+    /// Leave a plan, forgetting its completions.
+    pub fn unenroll_from_reading_plan(&self, plan_uid: &str) -> Result<()> {
+        use crate::db::appdata_schema::{reading_plan_enrollments, reading_plans};
+
+        self.do_write(|db_conn| {
+            let plan_ids = reading_plans::table
+                .filter(reading_plans::uid.eq(plan_uid))
+                .select(reading_plans::id);
+            diesel::delete(reading_plan_enrollments::table.filter(reading_plan_enrollments::plan_id.eq_any(plan_ids)))
+                .execute(db_conn)
+                .map(|_| ())
+        })
+    }
+
+    /// Pause or resume an enrolled plan. The items of a paused plan are not
+    /// listed for the day.
+    pub fn set_reading_plan_active(&self, plan_uid: &str, active: bool) -> Result<()> {
+        use crate::db::appdata_schema::{reading_plan_enrollments, reading_plans};
+
+        self.do_write(|db_conn| {
+            let plan_ids = reading_plans::table
+                .filter(reading_plans::uid.eq(plan_uid))
+                .select(reading_plans::id);
+            diesel::update(reading_plan_enrollments::table.filter(reading_plan_enrollments::plan_id.eq_any(plan_ids)))
+                .set(reading_plan_enrollments::is_active.eq(active))
+                .execute(db_conn)
+                .map(|_| ())
+        })
+    }
+
+    /// The enrollment in a plan with the item indexes marked completed in it.
+    pub fn get_reading_plan_enrollment_json(&self, plan_uid: &str) -> Result<Option<ReadingPlanEnrollmentJson>> {
+        use crate::db::appdata_schema::reading_plan_completions;
+
+        let Some((plan, _)) = self.load_reading_plan(plan_uid)? else {
+            return Ok(None);
+        };
+        let Some(enrollment) = self.get_reading_plan_enrollment(plan.id)? else {
+            return Ok(None);
+        };
+        let completed_items: Vec<i32> = self.do_read(|db_conn| {
+            reading_plan_completions::table
+                .filter(reading_plan_completions::enrollment_id.eq(enrollment.id))
+                .order(reading_plan_completions::item_index.asc())
+                .select(reading_plan_completions::item_index)
+                .load(db_conn)
+        })?;
+        Ok(Some(ReadingPlanEnrollmentJson {
+            start_date: enrollment.start_date,
+            is_active: enrollment.is_active,
+            completed_items,
+        }))
+    }
+
+    /// Mark an item of an enrolled plan completed or not.
+    pub fn set_reading_plan_item_completed(&self, plan_uid: &str, item_index_param: i32, completed: bool) -> Result<()> {
+        use crate::db::appdata_schema::reading_plan_completions;
+
+        let (plan, _) = self.load_reading_plan(plan_uid)?
+            .ok_or_else(|| anyhow::anyhow!("Reading plan not found: {}", plan_uid))?;
+        let enrollment = self.get_reading_plan_enrollment(plan.id)?
+            .ok_or_else(|| anyhow::anyhow!("Not enrolled in reading plan: {}", plan_uid))?;
+
+        self.do_write(|db_conn| {
+            diesel::delete(reading_plan_completions::table
+                .filter(reading_plan_completions::enrollment_id.eq(enrollment.id))
+                .filter(reading_plan_completions::item_index.eq(item_index_param)))
+                .execute(db_conn)?;
+            if completed {
+                diesel::insert_into(reading_plan_completions::table)
+                    .values(&NewReadingPlanCompletion {
+                        enrollment_id: enrollment.id,
+                        item_index: item_index_param,
+                        completed_at: chrono::Utc::now().naive_utc(),
+                    })
+                    .execute(db_conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// The items of a plan with their completion, on `date`.
+    pub fn get_reading_plan_items(&self, plan_uid: &str, date: chrono::NaiveDate) -> Result<Vec<PlanDayItem>> {
+        let Some((plan, items)) = self.load_reading_plan(plan_uid)? else {
+            return Ok(Vec::new());
+        };
+        let enrollment = self.get_reading_plan_enrollment(plan.id)?;
+        let done = match &enrollment {
+            Some(enrollment) => self.do_read(|db_conn| completed_plan_items(db_conn, enrollment, &items))?,
+            None => HashSet::new(),
+        };
+        let today = enrollment.as_ref().map(|e| days_since_start(e, date));
+        Ok(items
+            .iter()
+            .map(|item| plan_day_item(&plan, item, done.contains(&item.item_index),
+                                      plan.schedule == "daily" && today.is_some_and(|d| item.day_number < d)))
+            .collect())
+    }
+
+    /// The items to read on `date` from all active enrolled plans: the items
+    /// of the day and the overdue ones of daily plans, and the next unread
+    /// items of sequential plans.
+    pub fn get_reading_plan_items_for_day(&self, date: chrono::NaiveDate) -> Result<Vec<PlanDayItem>> {
+        let mut day_items = Vec::new();
+        for plan_uid in self.get_reading_plan_uids()? {
+            let Some((plan, items)) = self.load_reading_plan(&plan_uid)? else {
+                continue;
+            };
+            let Some(enrollment) = self.get_reading_plan_enrollment(plan.id)?.filter(|e| e.is_active) else {
+                continue;
+            };
+            let done = self.do_read(|db_conn| completed_plan_items(db_conn, &enrollment, &items))?;
+            day_items.extend(plan_day_items(&plan, &enrollment, &items, &done, date));
+        }
+        Ok(day_items)
+    }
+
+    // === Saved searches ===
+
+    /// Save a search under its name, replacing the search of the same name.
+    /// When the query, area or params change, the results seen so far are
+    /// dropped, and the next run is a new baseline. Returns the id.
+    pub fn save_search(&self, new_search: &NewSavedSearch) -> Result<i32> {
+        use crate::db::appdata_schema::{saved_search_hits, saved_searches};
+
+        self.do_write(|db_conn| {
+            db_conn.transaction(|tx| {
+                let existing: Option<SavedSearch> = saved_searches::table
+                    .filter(saved_searches::name.eq(new_search.name))
+                    .select(SavedSearch::as_select())
+                    .first(tx)
+                    .optional()?;
+
+                match existing {
+                    Some(existing) => {
+                        let is_changed = existing.query_text != new_search.query_text
+                            || existing.search_area != new_search.search_area
+                            || existing.params_json != new_search.params_json;
+                        if is_changed {
+                            diesel::delete(saved_search_hits::table
+                                .filter(saved_search_hits::saved_search_id.eq(existing.id)))
+                                .execute(tx)?;
+                            diesel::update(saved_searches::table.find(existing.id))
+                                .set((
+                                    saved_searches::query_text.eq(new_search.query_text),
+                                    saved_searches::search_area.eq(new_search.search_area),
+                                    saved_searches::search_mode.eq(new_search.search_mode),
+                                    saved_searches::params_json.eq(new_search.params_json),
+                                    saved_searches::last_run_at.eq(None::<chrono::NaiveDateTime>),
+                                    saved_searches::last_hits_count.eq(0),
+                                    saved_searches::new_hits_count.eq(0),
+                                ))
+                                .execute(tx)?;
+                        }
+                        Ok(existing.id)
+                    }
+                    None => diesel::insert_into(saved_searches::table)
+                        .values(new_search)
+                        .returning(saved_searches::id)
+                        .get_result::<i32>(tx),
+                }
+            })
+        })
+    }
+
+    /// The saved searches, by name.
+    pub fn get_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        use crate::db::appdata_schema::saved_searches;
+
+        self.do_read(|db_conn| {
+            saved_searches::table
+                .order(saved_searches::name.asc())
+                .select(SavedSearch::as_select())
+                .load(db_conn)
+        })
+    }
+
+    pub fn get_saved_search(&self, saved_search_id: i32) -> Result<Option<SavedSearch>> {
+        use crate::db::appdata_schema::saved_searches;
+
+        self.do_read(|db_conn| {
+            saved_searches::table
+                .find(saved_search_id)
+                .select(SavedSearch::as_select())
+                .first(db_conn)
+                .optional()
+        })
+    }
+
+    pub fn rename_saved_search(&self, saved_search_id: i32, new_name: &str) -> Result<()> {
+        use crate::db::appdata_schema::saved_searches;
+
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            anyhow::bail!("The name of a saved search can't be empty");
+        }
+        self.do_write(|db_conn| {
+            diesel::update(saved_searches::table.find(saved_search_id))
+                .set(saved_searches::name.eq(new_name))
+                .execute(db_conn)?;
+            Ok(())
+        })
+    }
+
+    pub fn delete_saved_search(&self, saved_search_id: i32) -> Result<()> {
+        use crate::db::appdata_schema::{saved_search_hits, saved_searches};
+
+        self.do_write(|db_conn| {
+            db_conn.transaction(|tx| {
+                diesel::delete(saved_search_hits::table
+                    .filter(saved_search_hits::saved_search_id.eq(saved_search_id)))
+                    .execute(tx)?;
+                diesel::delete(saved_searches::table.find(saved_search_id))
+                    .execute(tx)?;
+                Ok(())
+            })
+        })
+    }
+
+    /// Record the results of a run of a saved search. The first run is the
+    /// baseline. On later runs, the results not seen before are new, and they
+    /// stay new until `mark_saved_search_seen()`. Returns the new results of
+    /// this run.
+    pub fn record_saved_search_hits(&self,
+                                    saved_search_id: i32,
+                                    results: &[SearchResult],
+                                    hits_count: i32) -> Result<Vec<SavedSearchHit>> {
+        use crate::db::appdata_schema::{saved_search_hits, saved_searches};
+
+        let now = chrono::Utc::now().naive_utc();
+
+        self.do_write(|db_conn| {
+            db_conn.transaction(|tx| {
+                let last_run_at: Option<chrono::NaiveDateTime> = saved_searches::table
+                    .find(saved_search_id)
+                    .select(saved_searches::last_run_at)
+                    .first(tx)?;
+                let is_first_run = last_run_at.is_none();
+
+                let seen: HashSet<(String, String)> = saved_search_hits::table
+                    .filter(saved_search_hits::saved_search_id.eq(saved_search_id))
+                    .select((saved_search_hits::table_name, saved_search_hits::uid))
+                    .load::<(String, String)>(tx)?
+                    .into_iter()
+                    .collect();
+
+                // A record can be in the results once per snippet
+                let mut added: HashSet<(&str, &str)> = HashSet::new();
+                let new_hits: Vec<NewSavedSearchHit> = results
+                    .iter()
+                    .filter(|r| !seen.contains(&(r.table_name.clone(), r.uid.clone())))
+                    .filter(|r| added.insert((r.table_name.as_str(), r.uid.as_str())))
+                    .map(|r| NewSavedSearchHit {
+                        saved_search_id,
+                        table_name: &r.table_name,
+                        uid: &r.uid,
+                        title: Some(r.title.as_str()).filter(|t| !t.is_empty()),
+                        first_seen_at: now,
+                        is_new: !is_first_run,
+                    })
+                    .collect();
+                // Inserted one by one to return them in the order of the results
+                let mut inserted: Vec<SavedSearchHit> = Vec::with_capacity(new_hits.len());
+                for hit in &new_hits {
+                    inserted.push(
+                        diesel::insert_into(saved_search_hits::table)
+                            .values(hit)
+                            .returning(SavedSearchHit::as_returning())
+                            .get_result(tx)?,
+                    );
+                }
+
+                let new_hits_count: i64 = saved_search_hits::table
+                    .filter(saved_search_hits::saved_search_id.eq(saved_search_id))
+                    .filter(saved_search_hits::is_new.eq(true))
+                    .count()
+                    .get_result(tx)?;
+
+                diesel::update(saved_searches::table.find(saved_search_id))
+                    .set((
+                        saved_searches::last_run_at.eq(Some(now)),
+                        saved_searches::last_hits_count.eq(hits_count),
+                        saved_searches::new_hits_count.eq(new_hits_count as i32),
+                    ))
+                    .execute(tx)?;
+
+                Ok(inserted.into_iter().filter(|h| h.is_new).collect())
+            })
+        })
+    }
+
+    /// The results of a saved search seen so far, or only the new ones.
+    pub fn get_saved_search_hits(&self, saved_search_id: i32, only_new: bool) -> Result<Vec<SavedSearchHit>> {
+        use crate::db::appdata_schema::saved_search_hits;
+
+        self.do_read(|db_conn| {
+            let mut query = saved_search_hits::table
+                .filter(saved_search_hits::saved_search_id.eq(saved_search_id))
+                .into_boxed();
+            if only_new {
+                query = query.filter(saved_search_hits::is_new.eq(true));
+            }
+            query
+                .order((saved_search_hits::first_seen_at.desc(), saved_search_hits::id.asc()))
+                .select(SavedSearchHit::as_select())
+                .load(db_conn)
+        })
+    }
+
+    /// The new results of a saved search have been seen.
+    pub fn mark_saved_search_seen(&self, saved_search_id: i32) -> Result<()> {
+        use crate::db::appdata_schema::{saved_search_hits, saved_searches};
+
+        self.do_write(|db_conn| {
+            db_conn.transaction(|tx| {
+                diesel::update(saved_search_hits::table
+                    .filter(saved_search_hits::saved_search_id.eq(saved_search_id))
+                    .filter(saved_search_hits::is_new.eq(true)))
+                    .set(saved_search_hits::is_new.eq(false))
+                    .execute(tx)?;
+                diesel::update(saved_searches::table.find(saved_search_id))
+                    .set(saved_searches::new_hits_count.eq(0))
+                    .execute(tx)?;
+                Ok(())
+            })
+        })
+    }
+
+    /// Restore a saved search with its run state and the results seen so far,
+    /// e.g. from the user data exported for an upgrade. A search with the same
+    /// name is kept as it is. Returns the id of a restored search.
+    pub fn restore_saved_search(&self, search: &SavedSearch, hits: &[SavedSearchHit]) -> Result<Option<i32>> {
+        use crate::db::appdata_schema::{saved_search_hits, saved_searches};
+
+        self.do_write(|db_conn| {
+            db_conn.transaction(|tx| {
+                let exists: i64 = saved_searches::table
+                    .filter(saved_searches::name.eq(&search.name))
+                    .count()
+                    .get_result(tx)?;
+                if exists > 0 {
+                    return Ok(None);
+                }
+
+                let saved_search_id = diesel::insert_into(saved_searches::table)
+                    .values(&NewSavedSearch {
+                        name: &search.name,
+                        query_text: &search.query_text,
+                        search_area: &search.search_area,
+                        search_mode: &search.search_mode,
+                        params_json: &search.params_json,
+                    })
+                    .returning(saved_searches::id)
+                    .get_result::<i32>(tx)?;
+
+                diesel::update(saved_searches::table.find(saved_search_id))
+                    .set((
+                        saved_searches::last_run_at.eq(search.last_run_at),
+                        saved_searches::last_hits_count.eq(search.last_hits_count),
+                        saved_searches::new_hits_count.eq(search.new_hits_count),
+                    ))
+                    .execute(tx)?;
+
+                let new_hits: Vec<NewSavedSearchHit> = hits
+                    .iter()
+                    .map(|h| NewSavedSearchHit {
+                        saved_search_id,
+                        table_name: &h.table_name,
+                        uid: &h.uid,
+                        title: h.title.as_deref(),
+                        first_seen_at: h.first_seen_at,
+                        is_new: h.is_new,
+                    })
+                    .collect();
+                diesel::insert_into(saved_search_hits::table)
+                    .values(&new_hits)
+                    .execute(tx)?;
+
+                Ok(Some(saved_search_id))
+            })
+        })
+    }
+
+    // === Search history ===
+
+    /// Record a search. A search with the same query, area and params is
+    /// counted again and moves to the top of the history. The oldest searches
+    /// over `max_items` are removed. Returns the id of the history item.
+    pub fn record_search_history(&self, item: &NewSearchHistoryItem, max_items: i64) -> Result<i32> {
+        use crate::db::appdata_schema::search_history;
+
+        self.do_write(|db_conn| {
+            db_conn.transaction(|tx| {
+                let existing_id: Option<i32> = search_history::table
+                    .filter(search_history::query_text.eq(item.query_text))
+                    .filter(search_history::search_area.eq(item.search_area))
+                    .filter(search_history::params_json.eq(item.params_json))
+                    .select(search_history::id)
+                    .first(tx)
+                    .optional()?;
+
+                let history_id = match existing_id {
+                    Some(history_id) => {
+                        diesel::update(search_history::table.find(history_id))
+                            .set((
+                                search_history::search_mode.eq(item.search_mode),
+                                search_history::hits_count.eq(item.hits_count),
+                                search_history::search_count.eq(search_history::search_count + 1),
+                                search_history::last_searched_at.eq(item.last_searched_at),
+                            ))
+                            .execute(tx)?;
+                        history_id
+                    }
+                    None => diesel::insert_into(search_history::table)
+                        .values(item)
+                        .returning(search_history::id)
+                        .get_result::<i32>(tx)?,
+                };
+
+                let keep_ids: Vec<i32> = search_history::table
+                    .order((search_history::last_searched_at.desc(), search_history::id.desc()))
+                    .limit(max_items)
+                    .select(search_history::id)
+                    .load(tx)?;
+                diesel::delete(search_history::table.filter(search_history::id.ne_all(keep_ids)))
+                    .execute(tx)?;
+
+                Ok(history_id)
+            })
+        })
+    }
+
+    /// Remember the result the user opened from a search.
+    pub fn set_search_history_opened(&self, history_id: i32, uid: &str, table_name: &str, title: Option<&str>) -> Result<()> {
+        use crate::db::appdata_schema::search_history;
+
+        self.do_write(|db_conn| {
+            diesel::update(search_history::table.find(history_id))
+                .set((
+                    search_history::opened_uid.eq(Some(uid)),
+                    search_history::opened_table_name.eq(Some(table_name)),
+                    search_history::opened_title.eq(title),
+                ))
+                .execute(db_conn)
+                .map(|_| ())
+        })
+    }
+
+    /// The search history, the most recent first.
+    pub fn get_search_history(&self, limit: i64) -> Result<Vec<SearchHistoryItem>> {
+        use crate::db::appdata_schema::search_history;
+
+        self.do_read(|db_conn| {
+            search_history::table
+                .order((search_history::last_searched_at.desc(), search_history::id.desc()))
+                .limit(limit)
+                .select(SearchHistoryItem::as_select())
+                .load(db_conn)
+        })
+    }
+
+    /// The searches in an area with a query starting with `prefix` (not case
+    /// sensitive), the most repeated first.
+    pub fn get_search_history_matching(&self, prefix: &str, search_area: &str, limit: i64) -> Result<Vec<SearchHistoryItem>> {
+        use crate::db::appdata_schema::search_history;
+
+        let prefix_lower = prefix.to_lowercase();
+        let items = self.do_read(|db_conn| {
+            search_history::table
+                .filter(search_history::search_area.eq(search_area))
+                .filter(search_history::query_text.like(format!("{}%", prefix)))
+                .order((search_history::search_count.desc(), search_history::last_searched_at.desc()))
+                .select(SearchHistoryItem::as_select())
+                .load(db_conn)
+        })?;
+
+        // LIKE treats _ and % in the prefix as wildcards
+        Ok(items
+            .into_iter()
+            .filter(|i| i.query_text.to_lowercase().starts_with(&prefix_lower))
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    pub fn delete_search_history_item(&self, history_id: i32) -> Result<()> {
+        use crate::db::appdata_schema::search_history;
+
+        self.do_write(|db_conn| {
+            diesel::delete(search_history::table.find(history_id))
+                .execute(db_conn)
+                .map(|_| ())
+        })
+    }
+
+    pub fn clear_search_history(&self) -> Result<()> {
+        use crate::db::appdata_schema::search_history;
+
+        self.do_write(|db_conn| {
+            diesel::delete(search_history::table)
+                .execute(db_conn)
+                .map(|_| ())
+        })
+    }
+
+    /// Counts over the search history, with the `top_n` most repeated
+    /// searches and the `top_n` most recent searches without hits.
+    pub fn get_search_history_stats(&self, top_n: i64) -> Result<SearchHistoryStats> {
+        use crate::db::appdata_schema::search_history;
+        use diesel::dsl::sum;
+
+        self.do_read(|db_conn| {
+            let queries_count: i64 = search_history::table.count().get_result(db_conn)?;
+            let zero_hits_count: i64 = search_history::table
+                .filter(search_history::hits_count.eq(0))
+                .count()
+                .get_result(db_conn)?;
+
+            let area_counts: Vec<(String, i64)> = search_history::table
+                .group_by(search_history::search_area)
+                .select((search_history::search_area, sum(search_history::search_count)))
+                .load::<(String, Option<i64>)>(db_conn)?
+                .into_iter()
+                .map(|(area, count)| (area, count.unwrap_or(0)))
+                .collect();
+            let searches_count = area_counts.iter().map(|(_, count)| count).sum();
+
+            let top_searches = search_history::table
+                .order((search_history::search_count.desc(), search_history::last_searched_at.desc()))
+                .limit(top_n)
+                .select(SearchHistoryItem::as_select())
+                .load(db_conn)?;
+
+            let zero_hits_searches = search_history::table
+                .filter(search_history::hits_count.eq(0))
+                .order(search_history::last_searched_at.desc())
+                .limit(top_n)
+                .select(SearchHistoryItem::as_select())
+                .load(db_conn)?;
+
+            Ok(SearchHistoryStats {
+                searches_count,
+                queries_count,
+                zero_hits_count,
+                area_counts,
+                top_searches,
+                zero_hits_searches,
+            })
+        })
+    }
+
+    /// The distinct sutta titles, with and without diacritics, in Pāli
+    /// alphabetical order. The flat list for query completion.
+    pub fn sutta_titles_flat_completion_list(&self) -> Result<Vec<String>> {
+        use crate::db::appdata_schema::suttas;
+
+        let titles = self.do_read(|db_conn| {
+            let mut titles: Vec<Option<String>> = suttas::table
+                .select(suttas::title)
+                .distinct()
+                .load(db_conn)?;
+            titles.extend(suttas::table
+                .select(suttas::title_pali)
+                .distinct()
+                .load::<Option<String>>(db_conn)?);
+            Ok(titles)
+        })?;
+
+        let titles: HashSet<String> = titles
+            .into_iter()
+            .flatten()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+        Ok(pali_list_sorter(titles.into_iter().collect()))
+    }
+}
+
+/// Progress ratio from which a read item counts as finished.
+pub const READ_TO_END_PROGRESS: f32 = 0.95;
+
+/// The `sutta_ref_uid` and `book_uid` of a new reading state row.
+fn reading_item_refs(db_conn: &mut SqliteConnection,
+                     item_uid: &str,
+                     table_name: &str) -> Result<(Option<String>, Option<String>), diesel::result::Error> {
+    use crate::db::appdata_schema::book_spine_items;
+
+    match table_name {
+        "suttas" => Ok((item_uid.split('/').next().map(|s| s.to_lowercase()), None)),
+        "book_spine_items" => {
+            let book = book_spine_items::table
+                .filter(book_spine_items::spine_item_uid.eq(item_uid))
+                .select(book_spine_items::book_uid)
+                .first::<String>(db_conn)
+                .optional()?;
+            Ok((None, book))
+        }
+        _ => Ok((None, None)),
+    }
+}
+
+/// The day of a plan on `date`, the start date is day 1.
+fn days_since_start(enrollment: &ReadingPlanEnrollment, date: chrono::NaiveDate) -> i32 {
+    (date - enrollment.start_date).num_days() as i32 + 1
+}
+
+/// The current day of a daily plan, or the day of the next unread items of a
+/// sequential plan.
+fn current_plan_day(plan: &ReadingPlan,
+                    enrollment: &ReadingPlanEnrollment,
+                    items: &[ReadingPlanItem],
+                    done: &HashSet<i32>,
+                    date: chrono::NaiveDate) -> Option<i32> {
+    if plan.schedule == "daily" {
+        Some(days_since_start(enrollment, date)).filter(|day| *day >= 1)
+    } else {
+        items.iter().find(|i| !done.contains(&i.item_index)).map(|i| i.day_number)
+    }
+}
+
+fn plan_day_item(plan: &ReadingPlan, item: &ReadingPlanItem, is_completed: bool, is_past_day: bool) -> PlanDayItem {
+    PlanDayItem {
+        plan_uid: plan.uid.clone(),
+        plan_title: plan.title.clone(),
+        item_index: item.item_index,
+        day_number: item.day_number,
+        item_uid: item.item_uid.clone(),
+        table_name: item.table_name.clone(),
+        title: item.title.clone(),
+        is_completed,
+        is_overdue: is_past_day && !is_completed,
+    }
+}
+
+/// The items of a plan to read on `date`.
+fn plan_day_items(plan: &ReadingPlan,
+                  enrollment: &ReadingPlanEnrollment,
+                  items: &[ReadingPlanItem],
+                  done: &HashSet<i32>,
+                  date: chrono::NaiveDate) -> Vec<PlanDayItem> {
+    if plan.schedule == "daily" {
+        let day = days_since_start(enrollment, date);
+        if day < 1 {
+            return Vec::new();
+        }
+        items.iter()
+            .filter(|i| i.day_number == day || (i.day_number < day && !done.contains(&i.item_index)))
+            .map(|i| plan_day_item(plan, i, done.contains(&i.item_index), i.day_number < day))
+            .collect()
+    } else {
+        // The day group of the first unread item
+        let Some(next_day) = items.iter().find(|i| !done.contains(&i.item_index)).map(|i| i.day_number) else {
+            return Vec::new();
+        };
+        items.iter()
+            .filter(|i| i.day_number == next_day)
+            .map(|i| plan_day_item(plan, i, done.contains(&i.item_index), false))
+            .collect()
+    }
+}
+
+/// The completed items of an enrolled plan: marked completed in the plan, or
+/// marked finished in the reading view since the start of the plan. A sutta
+/// counts as read in any translation.
+fn completed_plan_items(db_conn: &mut SqliteConnection,
+                        enrollment: &ReadingPlanEnrollment,
+                        items: &[ReadingPlanItem]) -> Result<HashSet<i32>, diesel::result::Error> {
+    use crate::db::appdata_schema::{reading_plan_completions, reading_state};
+
+    let mut done: HashSet<i32> = reading_plan_completions::table
+        .filter(reading_plan_completions::enrollment_id.eq(enrollment.id))
+        .select(reading_plan_completions::item_index)
+        .load::<i32>(db_conn)?
+        .into_iter()
+        .collect();
+
+    let start = enrollment.start_date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let finished: HashSet<(String, String)> = reading_state::table
+        .filter(reading_state::is_finished.eq(true))
+        .filter(reading_state::finished_at.ge(start))
+        .select((reading_state::item_uid, reading_state::table_name, reading_state::sutta_ref_uid))
+        .load::<(String, String, Option<String>)>(db_conn)?
+        .into_iter()
+        .map(|(item_uid, table_name, ref_uid)| match ref_uid {
+            Some(ref_uid) if table_name == "suttas" => (ref_uid, table_name),
+            _ => (item_uid, table_name),
+        })
+        .collect();
+
+    for item in items {
+        let key = if item.table_name == "suttas" {
+            sutta_ref_uid(&item.item_uid).to_lowercase()
+        } else {
+            item.item_uid.clone()
+        };
+        if finished.contains(&(key, item.table_name.clone())) {
+            done.insert(item.item_index);
+        }
+    }
+    Ok(done)
 }
 
 #[cfg(test)]
@@ -282,6 +282,55 @@ pub struct NewBookResource<'a> {
     pub content_data: Option<&'a [u8]>,
 }
 
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, PartialEq, Associations)]
+#[diesel(belongs_to(Book, foreign_key = book_id))]
+#[diesel(table_name = book_sutta_citations)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct BookSuttaCitation {
+    pub id: i32,
+    pub book_id: i32,
+    pub book_uid: String,
+    pub spine_item_uid: String,
+    pub sutta_uid: String,
+    pub ref_text: String,
+    pub ref_count: i32,
+    // pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = book_sutta_citations)]
+pub struct NewBookSuttaCitation<'a> {
+    pub book_id: i32,
+    pub book_uid: &'a str,
+    pub spine_item_uid: &'a str,
+    pub sutta_uid: &'a str,
+    pub ref_text: &'a str,
+    pub ref_count: i32,
+}
+
+/// A citation row joined with its book and chapter, as loaded by
+/// `get_chapters_citing_sutta()`.
+#[derive(Debug, Clone, Queryable)]
+pub struct SuttaCitingChapterRow {
+    pub book_uid: String,
+    pub book_title: Option<String>,
+    pub spine_item_uid: String,
+    pub chapter_title: Option<String>,
+    pub ref_text: String,
+    pub ref_count: i32,
+}
+
+/// A library chapter citing a sutta, for the sutta view.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuttaCitingChapter {
+    pub book_uid: String,
+    pub book_title: String,
+    pub spine_item_uid: String,
+    pub chapter_title: String,
+    pub ref_text: String,
+    pub ref_count: i32,
+}
+
 // Chanting models
 
 #[derive(Debug, Clone, Queryable, Selectable, Identifiable, PartialEq)]
@@ -590,6 +639,316 @@ pub struct NewGlossPromptsHistory<'a> {
     pub updated_at: Option<chrono::NaiveDateTime>,
 }
 
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, PartialEq, Serialize, Deserialize)]
+#[diesel(table_name = reading_state)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReadingState {
+    pub id: i32,
+    pub item_uid: String,
+    pub table_name: String,
+    pub sutta_ref_uid: Option<String>,
+    pub book_uid: Option<String>,
+    pub scroll_position: f32,
+    pub progress: f32,
+    pub time_spent_secs: i32,
+    pub is_finished: bool,
+    pub finished_at: Option<chrono::NaiveDateTime>,
+    pub last_read_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = reading_state)]
+pub struct NewReadingState<'a> {
+    pub item_uid: &'a str,
+    pub table_name: &'a str,
+    pub sutta_ref_uid: Option<&'a str>,
+    pub book_uid: Option<&'a str>,
+    pub scroll_position: f32,
+    pub progress: f32,
+    pub time_spent_secs: i32,
+    pub is_finished: bool,
+    pub finished_at: Option<chrono::NaiveDateTime>,
+    pub last_read_at: Option<chrono::NaiveDateTime>,
+}
+
+impl ReadingState {
+    /// A copy of this row for inserting into another database.
+    pub fn as_new(&self) -> NewReadingState<'_> {
+        NewReadingState {
+            item_uid: &self.item_uid,
+            table_name: &self.table_name,
+            sutta_ref_uid: self.sutta_ref_uid.as_deref(),
+            book_uid: self.book_uid.as_deref(),
+            scroll_position: self.scroll_position,
+            progress: self.progress,
+            time_spent_secs: self.time_spent_secs,
+            is_finished: self.is_finished,
+            finished_at: self.finished_at,
+            last_read_at: self.last_read_at,
+        }
+    }
+}
+
+/// Reading progress of a library book over its chapters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookReadingProgress {
+    pub book_uid: String,
+    pub chapters_total: i32,
+    pub chapters_started: i32,
+    pub chapters_finished: i32,
+    /// Percent of the book read, 0-100, a finished chapter counts as read
+    pub percent_read: f32,
+    pub time_spent_secs: i64,
+    /// The chapter read last, to continue from
+    pub last_spine_item_uid: Option<String>,
+    pub last_read_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Reading progress over the suttas of a nikāya, counting each sutta once
+/// across its translations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, QueryableByName)]
+pub struct CanonReadingProgress {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub nikaya: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub suttas_total: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub suttas_started: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub suttas_finished: i64,
+}
+
+/// Totals of the reading state, for the statistics view.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingStatistics {
+    pub time_spent_secs: i64,
+    pub suttas_started: i64,
+    pub suttas_finished: i64,
+    pub chapters_started: i64,
+    pub chapters_finished: i64,
+    /// The items read last, most recent first
+    pub recent: Vec<ReadingState>,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, PartialEq, Serialize, Deserialize)]
+#[diesel(table_name = reading_plans)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReadingPlan {
+    pub id: i32,
+    pub uid: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    /// "daily" or "sequential", see `reading_plans::PlanSchedule`
+    pub schedule: String,
+    pub is_user_added: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = reading_plans)]
+pub struct NewReadingPlan<'a> {
+    pub uid: &'a str,
+    pub title: &'a str,
+    pub description: Option<&'a str>,
+    pub author: Option<&'a str>,
+    pub schedule: &'a str,
+    pub is_user_added: bool,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations, PartialEq, Serialize, Deserialize)]
+#[diesel(belongs_to(ReadingPlan, foreign_key = plan_id))]
+#[diesel(table_name = reading_plan_items)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReadingPlanItem {
+    pub id: i32,
+    pub plan_id: i32,
+    pub item_index: i32,
+    pub day_number: i32,
+    pub item_uid: String,
+    pub table_name: String,
+    pub title: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = reading_plan_items)]
+pub struct NewReadingPlanItem<'a> {
+    pub plan_id: i32,
+    pub item_index: i32,
+    pub day_number: i32,
+    pub item_uid: &'a str,
+    pub table_name: &'a str,
+    pub title: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations, PartialEq, Serialize, Deserialize)]
+#[diesel(belongs_to(ReadingPlan, foreign_key = plan_id))]
+#[diesel(table_name = reading_plan_enrollments)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReadingPlanEnrollment {
+    pub id: i32,
+    pub plan_id: i32,
+    pub start_date: chrono::NaiveDate,
+    pub is_active: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = reading_plan_enrollments)]
+pub struct NewReadingPlanEnrollment {
+    pub plan_id: i32,
+    pub start_date: chrono::NaiveDate,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, PartialEq, Serialize, Deserialize)]
+#[diesel(table_name = reading_plan_completions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReadingPlanCompletion {
+    pub id: i32,
+    pub enrollment_id: i32,
+    pub item_index: i32,
+    pub completed_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = reading_plan_completions)]
+pub struct NewReadingPlanCompletion {
+    pub enrollment_id: i32,
+    pub item_index: i32,
+    pub completed_at: chrono::NaiveDateTime,
+}
+
+/// A reading plan with its enrollment and completion counts, for the plan list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingPlanSummary {
+    pub plan: ReadingPlan,
+    pub items_total: i32,
+    /// The number of days of a daily plan
+    pub days_total: i32,
+    pub enrollment: Option<ReadingPlanEnrollment>,
+    pub items_completed: i32,
+    /// The day of a daily plan on the given date, starting with 1
+    pub current_day: Option<i32>,
+}
+
+/// An item to read on a day, from an enrolled plan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanDayItem {
+    pub plan_uid: String,
+    pub plan_title: String,
+    pub item_index: i32,
+    pub day_number: i32,
+    pub item_uid: String,
+    pub table_name: String,
+    pub title: Option<String>,
+    pub is_completed: bool,
+    /// A daily plan item of an earlier day which is not completed yet
+    pub is_overdue: bool,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, PartialEq, Serialize, Deserialize)]
+#[diesel(table_name = saved_searches)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct SavedSearch {
+    pub id: i32,
+    pub name: String,
+    pub query_text: String,
+    /// "Suttas", "Dictionary" or "Library"
+    pub search_area: String,
+    /// The `SearchMode` name, e.g. "Fulltext Match"
+    pub search_mode: String,
+    /// The `SearchParams` of the search, with the mode and all filters
+    pub params_json: String,
+    pub last_run_at: Option<chrono::NaiveDateTime>,
+    pub last_hits_count: i32,
+    /// Results found since the user last looked at the search
+    pub new_hits_count: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = saved_searches)]
+pub struct NewSavedSearch<'a> {
+    pub name: &'a str,
+    pub query_text: &'a str,
+    pub search_area: &'a str,
+    pub search_mode: &'a str,
+    pub params_json: &'a str,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations, PartialEq, Serialize, Deserialize)]
+#[diesel(belongs_to(SavedSearch, foreign_key = saved_search_id))]
+#[diesel(table_name = saved_search_hits)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct SavedSearchHit {
+    pub id: i32,
+    pub saved_search_id: i32,
+    pub table_name: String,
+    pub uid: String,
+    pub title: Option<String>,
+    pub first_seen_at: chrono::NaiveDateTime,
+    pub is_new: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = saved_search_hits)]
+pub struct NewSavedSearchHit<'a> {
+    pub saved_search_id: i32,
+    pub table_name: &'a str,
+    pub uid: &'a str,
+    pub title: Option<&'a str>,
+    pub first_seen_at: chrono::NaiveDateTime,
+    pub is_new: bool,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, PartialEq, Serialize, Deserialize)]
+#[diesel(table_name = search_history)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct SearchHistoryItem {
+    pub id: i32,
+    pub query_text: String,
+    /// "Suttas", "Dictionary" or "Library"
+    pub search_area: String,
+    /// The `SearchMode` name, e.g. "Fulltext Match"
+    pub search_mode: String,
+    /// The `SearchParams` of the search, with the mode and all filters
+    pub params_json: String,
+    /// The hits of the last run of the search
+    pub hits_count: i32,
+    pub search_count: i32,
+    /// The last result opened from the search
+    pub opened_uid: Option<String>,
+    pub opened_table_name: Option<String>,
+    pub opened_title: Option<String>,
+    pub last_searched_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = search_history)]
+pub struct NewSearchHistoryItem<'a> {
+    pub query_text: &'a str,
+    pub search_area: &'a str,
+    pub search_mode: &'a str,
+    pub params_json: &'a str,
+    pub hits_count: i32,
+    pub last_searched_at: chrono::NaiveDateTime,
+}
+
+/// Counts over the search history, for the history dialog.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHistoryStats {
+    /// All the searches, with the repeated ones
+    pub searches_count: i64,
+    /// The distinct searches (query, area and params)
+    pub queries_count: i64,
+    /// The distinct searches which had no hits on their last run
+    pub zero_hits_count: i64,
+    /// The number of searches in each area, by area name
+    pub area_counts: Vec<(String, i64)>,
+    /// The most repeated searches
+    pub top_searches: Vec<SearchHistoryItem>,
+    /// The most recent searches which had no hits
+    pub zero_hits_searches: Vec<SearchHistoryItem>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BookmarkItemUpdate {
     pub item_uid: Option<String>,
@@ -131,6 +131,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    book_sutta_citations (id) {
+        id -> Integer,
+        book_id -> Integer,
+        book_uid -> Text,
+        spine_item_uid -> Text,
+        sutta_uid -> Text,
+        ref_text -> Text,
+        ref_count -> Integer,
+        // created_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     chanting_collections (id) {
         id -> Integer,
@@ -235,12 +248,122 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    reading_state (id) {
+        id -> Integer,
+        item_uid -> Text,
+        table_name -> Text,
+        sutta_ref_uid -> Nullable<Text>,
+        book_uid -> Nullable<Text>,
+        scroll_position -> Float,
+        progress -> Float,
+        time_spent_secs -> Integer,
+        is_finished -> Bool,
+        finished_at -> Nullable<Timestamp>,
+        last_read_at -> Nullable<Timestamp>,
+        // created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    reading_plans (id) {
+        id -> Integer,
+        uid -> Text,
+        title -> Text,
+        description -> Nullable<Text>,
+        author -> Nullable<Text>,
+        schedule -> Text,
+        is_user_added -> Bool,
+        // created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    reading_plan_items (id) {
+        id -> Integer,
+        plan_id -> Integer,
+        item_index -> Integer,
+        day_number -> Integer,
+        item_uid -> Text,
+        table_name -> Text,
+        title -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    reading_plan_enrollments (id) {
+        id -> Integer,
+        plan_id -> Integer,
+        start_date -> Date,
+        is_active -> Bool,
+        // created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    reading_plan_completions (id) {
+        id -> Integer,
+        enrollment_id -> Integer,
+        item_index -> Integer,
+        completed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    saved_searches (id) {
+        id -> Integer,
+        name -> Text,
+        query_text -> Text,
+        search_area -> Text,
+        search_mode -> Text,
+        params_json -> Text,
+        last_run_at -> Nullable<Timestamp>,
+        last_hits_count -> Integer,
+        new_hits_count -> Integer,
+        // created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    saved_search_hits (id) {
+        id -> Integer,
+        saved_search_id -> Integer,
+        table_name -> Text,
+        uid -> Text,
+        title -> Nullable<Text>,
+        first_seen_at -> Timestamp,
+        is_new -> Bool,
+    }
+}
+
+diesel::table! {
+    search_history (id) {
+        id -> Integer,
+        query_text -> Text,
+        search_area -> Text,
+        search_mode -> Text,
+        params_json -> Text,
+        hits_count -> Integer,
+        search_count -> Integer,
+        opened_uid -> Nullable<Text>,
+        opened_table_name -> Nullable<Text>,
+        opened_title -> Nullable<Text>,
+        // created_at -> Nullable<Timestamp>,
+        last_searched_at -> Timestamp,
+    }
+}
+
 diesel::joinable!(sutta_variants -> suttas (sutta_id));
 diesel::joinable!(sutta_comments -> suttas (sutta_id));
 diesel::joinable!(sutta_glosses -> suttas (sutta_id));
 diesel::joinable!(book_spine_items -> books (book_id));
 diesel::joinable!(book_resources -> books (book_id));
+diesel::joinable!(book_sutta_citations -> books (book_id));
 diesel::joinable!(bookmark_items -> bookmark_folders (folder_id));
+diesel::joinable!(reading_plan_items -> reading_plans (plan_id));
+diesel::joinable!(reading_plan_enrollments -> reading_plans (plan_id));
+diesel::joinable!(reading_plan_completions -> reading_plan_enrollments (enrollment_id));
+diesel::joinable!(saved_search_hits -> saved_searches (saved_search_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     app_settings,
@@ -251,6 +374,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     books,
     book_spine_items,
     book_resources,
+    book_sutta_citations,
     chanting_collections,
     chanting_chants,
     chanting_sections,
@@ -258,4 +382,12 @@ diesel::allow_tables_to_appear_in_same_query!(
     bookmark_folders,
     bookmark_items,
     gloss_prompts_history,
+    reading_state,
+    reading_plans,
+    reading_plan_items,
+    reading_plan_enrollments,
+    reading_plan_completions,
+    saved_searches,
+    saved_search_hits,
+    search_history,
 );
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::fs;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use diesel::prelude::*;
@@ -9,6 +9,8 @@ use diesel::sqlite::SqliteConnection;
 use diesel_migrations::MigrationHarness;
 use zip::write::SimpleFileOptions;
 
+use crate::audio::export::{export_audio, AudioExportItem, AudioExportOptions, AudioExportResult};
+use crate::audio::timing_track::remap_timing_track_recordings;
 use crate::db::appdata::AppdataDbHandle;
 use crate::db::appdata_models::*;
 use crate::db::APPDATA_MIGRATIONS;
@@ -302,6 +304,90 @@ pub fn export_chanting_to_zip(
     Ok(())
 }
 
+/// Gather the recordings of the selected sections for audio export, in
+/// collection → chant → section order. Each section contributes its first
+/// recording of `recording_type` ("reference" or "user"); sections without one
+/// are skipped. Returns a playlist title derived from the selection and the
+/// export items.
+pub fn collect_chanting_audio_items(
+    appdata_db: &AppdataDbHandle,
+    selected_collection_uids: &[String],
+    selected_section_uids: &[String],
+    recording_type: &str,
+) -> Result<(String, Vec<AudioExportItem>)> {
+    let mut sections = appdata_db.get_chanting_sections_by_uids(selected_section_uids)?;
+    let recordings = appdata_db.get_chanting_recordings_for_sections(selected_section_uids)?;
+
+    let chant_uids: Vec<String> = sections.iter().map(|s| s.chant_uid.clone()).collect();
+    let chants = appdata_db.get_chanting_chants_by_uids(&chant_uids)?;
+    let collection_uids: Vec<String> = chants.iter().map(|c| c.collection_uid.clone()).collect();
+    let collections = appdata_db.get_chanting_collections_by_uids(&collection_uids)?;
+
+    let chant_by_uid: HashMap<&str, &ChantingChant> = chants.iter().map(|c| (c.uid.as_str(), c)).collect();
+    let collection_order: HashMap<&str, i32> = collections.iter().map(|c| (c.uid.as_str(), c.sort_index)).collect();
+
+    sections.sort_by_key(|sec| {
+        let chant = chant_by_uid.get(sec.chant_uid.as_str());
+        let col_idx = chant
+            .and_then(|c| collection_order.get(c.collection_uid.as_str()).copied())
+            .unwrap_or(i32::MAX);
+        let chant_idx = chant.map(|c| c.sort_index).unwrap_or(i32::MAX);
+        (col_idx, chant_idx, sec.sort_index)
+    });
+
+    let recordings_dir = get_chanting_recordings_dir();
+    let mut items = Vec::new();
+    for sec in &sections {
+        let Some(rec) = recordings
+            .iter()
+            .find(|r| r.section_uid == sec.uid && r.recording_type == recording_type)
+        else {
+            continue;
+        };
+        let file_path = if Path::new(&rec.file_name).is_absolute() {
+            PathBuf::from(&rec.file_name)
+        } else {
+            recordings_dir.join(&rec.file_name)
+        };
+        let title = match chant_by_uid.get(sec.chant_uid.as_str()) {
+            Some(chant) if chant.title != sec.title => format!("{}: {}", chant.title, sec.title),
+            _ => sec.title.clone(),
+        };
+        items.push(AudioExportItem {
+            title,
+            file_path,
+            markers_json: rec.markers_json.clone(),
+        });
+    }
+
+    let playlist_title = collections
+        .iter()
+        .find(|c| selected_collection_uids.contains(&c.uid))
+        .map(|c| c.title.clone())
+        .or_else(|| chants.first().map(|c| c.title.clone()))
+        .unwrap_or_else(|| "Chanting".to_string());
+
+    Ok((playlist_title, items))
+}
+
+/// Export the audio of the selected sections to `dest_dir` (see
+/// [`crate::audio::export::export_audio`]).
+pub fn export_chanting_audio(
+    appdata_db: &AppdataDbHandle,
+    selected_collection_uids: &[String],
+    selected_section_uids: &[String],
+    recording_type: &str,
+    dest_dir: &Path,
+    options: &AudioExportOptions,
+) -> Result<AudioExportResult> {
+    let (playlist_title, items) = collect_chanting_audio_items(
+        appdata_db, selected_collection_uids, selected_section_uids, recording_type)?;
+
+    info(&format!("Exporting audio of {} sections as {}", items.len(), options.format.extension()));
+
+    export_audio(&items, dest_dir, &playlist_title, options)
+}
+
 /// Generate a new UID with the given prefix, matching the app's UID format:
 /// `prefix-timestamp_base36-random_base36`
 fn generate_uid(prefix: &str) -> String {
@@ -446,12 +532,13 @@ fn remap_uids(
         })
         .collect();
 
-    // Remap sections (uid + chant_uid FK)
+    // Remap sections (uid + chant_uid FK + recording uids of timing tracks)
     let new_sections: Vec<ChantingSection> = sections
         .into_iter()
         .map(|mut sec| {
             sec.uid = uid_map[&sec.uid].clone();
             sec.chant_uid = uid_map[&sec.chant_uid].clone();
+            sec.metadata_json = remap_timing_track_recordings(sec.metadata_json, &uid_map);
             sec.is_user_added = true;
             sec
         })
@@ -1079,6 +1166,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_remap_uids_rewrites_timing_track_keys() {
+        use crate::audio::timing_track::{
+            set_timing_track_in_metadata, timing_track_from_metadata, TimingGranularity, TimingTrack,
+        };
+
+        let (collections, chants, mut sections, recordings) = make_test_data();
+        let track = TimingTrack::generate("Namo tassa", TimingGranularity::Syllable, None, 5000);
+        let meta = set_timing_track_in_metadata(None, "rec-test-1", Some(&track)).unwrap();
+        let meta = set_timing_track_in_metadata(meta.as_deref(), "rec-not-exported", Some(&track)).unwrap();
+        sections[0].metadata_json = meta;
+
+        let (_new_cols, _new_chants, new_secs, new_recs, _) =
+            remap_uids(collections, chants, sections, recordings);
+
+        let meta = new_secs[0].metadata_json.as_deref();
+        assert_eq!(timing_track_from_metadata(meta, &new_recs[0].uid), Some(track));
+        assert!(timing_track_from_metadata(meta, "rec-test-1").is_none());
+        assert!(timing_track_from_metadata(meta, "rec-not-exported").is_none());
+    }
+
     #[test]
     fn test_read_chanting_from_sqlite_roundtrip() {
         let dir = tempdir().unwrap();
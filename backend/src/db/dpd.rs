@@ -566,6 +566,60 @@ impl DpdDbHandle {
         }
     }
 
+    /// The distinct DPD headwords without their homonym numbers, in Pāli
+    /// alphabetical order. The flat list for query completion.
+    pub fn dict_words_flat_completion_list(&self) -> Result<Vec<String>> {
+        use crate::db::dpd_schema::dpd_headwords::dsl::*;
+
+        let mut words: Vec<String> = self.do_read(|db_conn| {
+            dpd_headwords
+                .select(lemma_clean)
+                .distinct()
+                .load::<String>(db_conn)
+        }).context("dict_words_flat_completion_list failed")?;
+
+        words.retain(|w| !w.trim().is_empty());
+        words.sort_by_key(|w| pali_sort_key(w));
+        Ok(words)
+    }
+
+    /// The DPD headwords (`lemma_clean` with its `ebt_count`) and the
+    /// `lookup` keys (inflected forms and compounds, with a count of 0) which
+    /// begin with one of `initials`. The Pāli candidates of the "did you mean"
+    /// suggestions, see `crate::did_you_mean`.
+    pub fn spelling_candidates(&self, initials: &[char]) -> Result<Vec<(String, i32)>> {
+        use crate::db::dpd_schema::dpd_headwords::dsl as hd;
+        use crate::db::dpd_schema::lookup::dsl as lk;
+
+        if initials.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.do_read(|db_conn| {
+            // The ASCII form of the initial is enough for word_ascii
+            let ascii_initials: HashSet<String> = initials
+                .iter()
+                .map(|c| pali_to_ascii(Some(&c.to_string())))
+                .collect();
+            let mut headwords_q = hd::dpd_headwords
+                .select((hd::lemma_clean, hd::ebt_count))
+                .into_boxed();
+            for c in ascii_initials {
+                headwords_q = headwords_q.or_filter(hd::word_ascii.like(format!("{}%", c)));
+            }
+            let mut words: Vec<(String, i32)> = headwords_q.distinct().load(db_conn)?;
+
+            let mut lookup_q = lk::lookup.select(lk::lookup_key).into_boxed();
+            for c in initials {
+                lookup_q = lookup_q.or_filter(lk::lookup_key.like(format!("{}%", c)));
+            }
+            let keys: Vec<String> = lookup_q.load(db_conn)?;
+            words.extend(keys.into_iter().map(|k| (k, 0)));
+
+            Ok(words)
+        }).context("spelling_candidates failed")
+    }
+
     pub fn dpd_lookup_json(&self, query: &str) -> String {
         let list: Vec<LookupResult> = match self.dpd_lookup(query, false, true, None, None) {
             Ok(res) => LookupResult::from_search_results(&res),
@@ -289,6 +289,8 @@ pub fn upgrade_appdata_schema(db_conn: &mut SqliteConnection) {
         include_str!("../../migrations/appdata/2026-04-14-000000_add_is_user_added/up.sql"),
         // 2026-04-14: is_user_added on chanting_recordings
         include_str!("../../migrations/appdata/2026-04-14-000002_add_recordings_is_user_added/up.sql"),
+        // 2026-10-18: sync change log and peers
+        include_str!("../../migrations/appdata/2026-10-18-000000_create_sync_tables/up.sql"),
     ];
 
     for sql in &statements {
@@ -18,6 +18,7 @@ use stardict::{self, Ifo};
 
 use crate::{get_app_data, get_app_globals};
 use crate::logger::{info, error};
+use crate::saved_searches::request_saved_searches_run;
 use crate::stardict_parse::{import_stardict_as_new, ImportOutcome, StardictImportProgress, read_ifo_description};
 
 /// Single global serialisation lock for user-dictionary mutations.
@@ -187,6 +188,10 @@ fn import_located_stardict(
         // of `dict_label` / `dict_words.word` enough to matter for the
         // Headword Match plan. See docs/user-data-and-sqlite-analyze.md.
         get_app_data().dbm.dictionaries.analyze("dictionaries");
+
+        // The new words are indexed by the next startup reconciliation, the
+        // saved searches run after it.
+        request_saved_searches_run(&format!("Dictionary: {}", label));
     }
 
     Ok(outcome)
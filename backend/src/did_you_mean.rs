@@ -0,0 +1,329 @@
+//! "Did you mean" suggestions for searches without results.
+//!
+//! When a search has no hits, each query word is compared with the words the
+//! search could have found: the terms of the fulltext indexes of the search
+//! area (in the Pāli and the translation languages), and for Pāli also the DPD
+//! headwords and the `lookup` table of inflected forms. The alternatives within
+//! a small edit distance are ranked by their distance, then by their corpus
+//! frequency (the number of indexed documents with the word, or the DPD
+//! `ebt_count`), and replace the unknown words in the suggested queries.
+//!
+//! See docs/did-you-mean-suggestions.md.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbManager;
+use crate::helpers::pali_to_ascii;
+use crate::logger::warn;
+use crate::pali_phonetic::{edit_distance, fuzzy_distance_for_key, MAX_FUZZY_DISTANCE};
+use crate::search::types::SearchFilters;
+use crate::types::{SearchArea, SearchParams};
+
+/// The number of suggested queries for a search.
+pub const SUGGESTIONS_LIMIT: usize = 5;
+
+/// The number of alternatives kept for each unknown word.
+const WORD_CANDIDATES_LIMIT: usize = 5;
+
+/// A suggested query for a search without results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpellingSuggestion {
+    /// The query with the unknown words replaced.
+    pub query_text: String,
+    /// The sum of the edit distances of the replaced words.
+    pub distance: usize,
+    /// The lowest corpus frequency of the replaced words.
+    pub frequency: u64,
+}
+
+/// An alternative spelling of one query word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordCandidate {
+    pub word: String,
+    pub distance: usize,
+    pub frequency: u64,
+}
+
+/// The edit distance allowed for the alternatives of a word. Words of three
+/// letters or less have none, they are close to too many other words.
+pub fn max_distance_for_word(word: &str) -> usize {
+    fuzzy_distance_for_key(word, MAX_FUZZY_DISTANCE) as usize
+}
+
+/// The lowercase ASCII form of a word, which the candidates are compared in.
+fn ascii_form(word: &str) -> String {
+    pali_to_ascii(Some(&word.to_lowercase()))
+}
+
+/// The initial letter and the Pāli letters which are typed as it in ASCII,
+/// to select the DPD candidates.
+fn initial_variants(c: char) -> Vec<char> {
+    match c {
+        'a' => vec!['a', 'ā'],
+        'i' => vec!['i', 'ī'],
+        'u' => vec!['u', 'ū'],
+        't' => vec!['t', 'ṭ'],
+        'd' => vec!['d', 'ḍ'],
+        'n' => vec!['n', 'ñ', 'ṇ', 'ṅ'],
+        'l' => vec!['l', 'ḷ'],
+        'm' => vec!['m', 'ṁ', 'ṃ'],
+        _ => vec![c],
+    }
+}
+
+/// Whether the query token is a word to check. Operators, excluded words,
+/// quoted phrases, numbers and references are kept as they are.
+fn is_checked_word(token: &str) -> bool {
+    !matches!(token, "AND" | "OR" | "NOT") && token.chars().all(char::is_alphabetic)
+}
+
+/// Merge the candidates with the same ASCII form (keeping the lowest distance,
+/// the highest frequency and the spelling with diacritics), and sort them by
+/// distance, then by frequency.
+pub fn rank_word_candidates(candidates: Vec<WordCandidate>, limit: usize) -> Vec<WordCandidate> {
+    let mut merged: HashMap<String, WordCandidate> = HashMap::new();
+
+    for c in candidates {
+        let key = ascii_form(&c.word);
+        match merged.get_mut(&key) {
+            Some(m) => {
+                m.distance = m.distance.min(c.distance);
+                m.frequency = m.frequency.max(c.frequency);
+                if m.word == key && c.word != key {
+                    m.word = c.word;
+                }
+            }
+            None => {
+                merged.insert(key, c);
+            }
+        }
+    }
+
+    let mut ranked: Vec<WordCandidate> = merged.into_values().collect();
+    ranked.sort_by(|a, b| {
+        a.distance
+            .cmp(&b.distance)
+            .then(b.frequency.cmp(&a.frequency))
+            .then(a.word.cmp(&b.word))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+/// The suggested queries for `query_text`, with the alternatives of each
+/// word from `word_candidates(word, max_distance)`.
+///
+/// A word with a candidate at distance 0 is known and kept. The first
+/// suggestion replaces each unknown word with its best alternative, the
+/// others change one word to one of its next alternatives.
+pub fn suggest_queries<F>(query_text: &str, limit: usize, mut word_candidates: F) -> Vec<SpellingSuggestion>
+where
+    F: FnMut(&str, usize) -> Vec<WordCandidate>,
+{
+    let tokens: Vec<&str> = query_text.split_whitespace().collect();
+
+    // The alternatives of the unknown words, by token position
+    let mut alternatives: Vec<Vec<WordCandidate>> = vec![Vec::new(); tokens.len()];
+    for (i, token) in tokens.iter().enumerate() {
+        if !is_checked_word(token) {
+            continue;
+        }
+        let max_distance = max_distance_for_word(&ascii_form(token));
+        if max_distance == 0 {
+            continue;
+        }
+        let ranked = rank_word_candidates(word_candidates(token, max_distance), WORD_CANDIDATES_LIMIT);
+        if ranked.first().is_some_and(|c| c.distance == 0) {
+            continue;
+        }
+        alternatives[i] = ranked;
+    }
+
+    let corrected: Vec<usize> = (0..tokens.len()).filter(|i| !alternatives[*i].is_empty()).collect();
+    if corrected.is_empty() {
+        return Vec::new();
+    }
+
+    // The chosen alternative of each token
+    let mut choices: Vec<Vec<usize>> = vec![vec![0; tokens.len()]];
+    for &i in &corrected {
+        for n in 1..alternatives[i].len() {
+            let mut choice = vec![0; tokens.len()];
+            choice[i] = n;
+            choices.push(choice);
+        }
+    }
+
+    let mut suggestions: Vec<SpellingSuggestion> = choices
+        .into_iter()
+        .map(|choice| {
+            let mut words: Vec<&str> = Vec::with_capacity(tokens.len());
+            let mut distance = 0;
+            let mut frequency = u64::MAX;
+            for (i, token) in tokens.iter().enumerate() {
+                match alternatives[i].get(choice[i]) {
+                    Some(c) => {
+                        words.push(&c.word);
+                        distance += c.distance;
+                        frequency = frequency.min(c.frequency);
+                    }
+                    None => words.push(token),
+                }
+            }
+            SpellingSuggestion {
+                query_text: words.join(" "),
+                distance,
+                frequency,
+            }
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| a.distance.cmp(&b.distance).then(b.frequency.cmp(&a.frequency)));
+    suggestions.truncate(limit);
+    suggestions
+}
+
+/// Whether the Pāli DPD words are candidates for the search area and the
+/// language filter.
+fn includes_pali(area: &SearchArea, filters: &SearchFilters) -> bool {
+    if matches!(area, SearchArea::Library) {
+        return false;
+    }
+    match filters.lang {
+        Some(ref lang) if filters.lang_include && !lang.is_empty() && lang != "Language" => lang == "pli",
+        _ => true,
+    }
+}
+
+/// The "did you mean" suggestions for a search without results, from the
+/// fulltext index terms of the area and language of `filters` (when the
+/// fulltext searcher is open) and the DPD words.
+pub fn query_suggestions(
+    dbm: &DbManager,
+    query_text: &str,
+    area: &SearchArea,
+    filters: &SearchFilters,
+    limit: usize,
+) -> Vec<SpellingSuggestion> {
+    let with_pali = includes_pali(area, filters);
+
+    suggest_queries(query_text, limit, |word, max_distance| {
+        let mut candidates: Vec<WordCandidate> = Vec::new();
+
+        match crate::with_fulltext_searcher(|searcher| searcher.similar_terms(area, filters, word, max_distance)) {
+            Some(Ok(terms)) => {
+                candidates.extend(terms.into_iter().map(|(word, distance, frequency)| WordCandidate {
+                    word,
+                    distance,
+                    frequency,
+                }));
+            }
+            Some(Err(e)) => warn(&format!("similar_terms failed: {}", e)),
+            None => {}
+        }
+
+        let word_ascii = ascii_form(word);
+        if with_pali && let Some(initial) = word_ascii.chars().next() {
+            match dbm.dpd.spelling_candidates(&initial_variants(initial)) {
+                Ok(words) => {
+                    let len = word_ascii.chars().count();
+                    for (dpd_word, ebt_count) in words {
+                        let dpd_ascii = ascii_form(&dpd_word);
+                        if dpd_ascii.chars().count().abs_diff(len) > max_distance {
+                            continue;
+                        }
+                        let distance = edit_distance(&word_ascii, &dpd_ascii);
+                        if distance <= max_distance {
+                            candidates.push(WordCandidate {
+                                word: dpd_word,
+                                distance,
+                                frequency: ebt_count.max(0) as u64,
+                            });
+                        }
+                    }
+                }
+                Err(e) => warn(&format!("spelling_candidates failed: {}", e)),
+            }
+        }
+
+        candidates
+    })
+}
+
+/// `query_suggestions` with the language filter of the search params.
+pub fn search_params_suggestions(
+    dbm: &DbManager,
+    query_text: &str,
+    area: &SearchArea,
+    params: &SearchParams,
+    limit: usize,
+) -> Vec<SpellingSuggestion> {
+    let filters = SearchFilters {
+        lang: params.lang.clone(),
+        lang_include: params.lang_include,
+        ..SearchFilters::default()
+    };
+    query_suggestions(dbm, query_text, area, &filters, limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(word: &str, distance: usize, frequency: u64) -> WordCandidate {
+        WordCandidate {
+            word: word.to_string(),
+            distance,
+            frequency,
+        }
+    }
+
+    #[test]
+    fn test_rank_word_candidates() {
+        let ranked = rank_word_candidates(
+            vec![
+                candidate("satipatthana", 1, 40),
+                candidate("satipaṭṭhāna", 1, 12),
+                candidate("satipatthane", 2, 90),
+                candidate("satipaṭṭhānā", 1, 50),
+            ],
+            5,
+        );
+        let words: Vec<&str> = ranked.iter().map(|c| c.word.as_str()).collect();
+        // satipaṭṭhāna and satipaṭṭhānā have the same ASCII form
+        assert_eq!(words, vec!["satipaṭṭhāna", "satipatthane"]);
+        assert_eq!(ranked[0].frequency, 50);
+    }
+
+    #[test]
+    fn test_suggest_queries() {
+        let source = |word: &str, _max_distance: usize| match word {
+            "mindfulnes" => vec![candidate("mindfulness", 1, 300), candidate("mindfulnesses", 2, 1)],
+            "breathng" => vec![candidate("breathing", 1, 80), candidate("breathe", 2, 100)],
+            "of" => vec![candidate("of", 0, 1000)],
+            _ => Vec::new(),
+        };
+
+        let suggestions = suggest_queries("mindfulnes of breathng", 3, source);
+        let queries: Vec<&str> = suggestions.iter().map(|s| s.query_text.as_str()).collect();
+        assert_eq!(
+            queries,
+            vec!["mindfulness of breathing", "mindfulness of breathe", "mindfulnesses of breathing"],
+        );
+        assert_eq!(suggestions[0].distance, 2);
+        assert_eq!(suggestions[0].frequency, 80);
+
+        // Known words, short words and operators have no suggestions
+        assert!(suggest_queries("of", 3, source).is_empty());
+        assert!(suggest_queries("abc", 3, |_, _| vec![candidate("abd", 1, 1)]).is_empty());
+        assert!(suggest_queries("-mindfulnes", 3, source).is_empty());
+    }
+
+    #[test]
+    fn test_initial_variants() {
+        assert_eq!(initial_variants('a'), vec!['a', 'ā']);
+        assert_eq!(initial_variants('s'), vec!['s']);
+    }
+}
@@ -0,0 +1,275 @@
+//! The library import of documents which are converted to HTML: Markdown,
+//! DOCX, ODT and FB2.
+//!
+//! Each format module reads its document into a `ConvertedDocument`: the
+//! metadata, the body as HTML, and the images it links to. The body is split
+//! into chapters on its headings as in the HTML import, each chapter is a
+//! spine item, and the heading levels give the nesting of the table of
+//! contents.
+
+use anyhow::{anyhow, Result};
+use diesel::prelude::*;
+use html5ever::parse_document;
+use html5ever::tendril::TendrilSink;
+use markup5ever_rcdom::RcDom;
+use quick_xml::events::BytesStart;
+use std::io::Read;
+use std::path::Path;
+
+use crate::db::appdata_models::{NavPointJson, NewBook, NewBookResource, NewBookSpineItem};
+use crate::db::appdata_schema::{book_resources, book_spine_items, books};
+use crate::document_metadata::DocumentMetadata;
+use crate::html_import::{guess_mime_type, html_to_plain_text, rewrite_resource_links, split_by_headings, Chapter};
+
+/// A document read into HTML, before it is split into chapters.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertedDocument {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub language: Option<String>,
+    /// The body, with `<h1>`-`<h6>` headings and relative image paths.
+    pub body_html: String,
+    /// The images, by the path the body links to.
+    pub resources: Vec<(String, Vec<u8>)>,
+}
+
+impl ConvertedDocument {
+    pub fn metadata(&self) -> DocumentMetadata {
+        DocumentMetadata {
+            title: self.title.clone().unwrap_or_default(),
+            author: self.author.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Import a converted document into the database
+///
+/// # Arguments
+/// * `db_conn` - Mutable reference to SQLite database connection
+/// * `doc` - The converted document
+/// * `document_type` - The `books.document_type`, e.g. "docx"
+/// * `file_path` - The path of the imported file or folder
+/// * `book_uid` - Unique identifier for this book
+/// * `custom_title` - Optional custom title to override the document metadata
+/// * `custom_author` - Optional custom author to override the document metadata
+/// * `custom_language` - Optional custom language to override the document metadata
+/// * `is_user_added` - Whether the book was added by the user
+///
+/// # Returns
+/// * `Result<()>` - Ok if successful, Err with details if failed
+#[allow(clippy::too_many_arguments)]
+pub fn import_converted_document_to_db(
+    db_conn: &mut SqliteConnection,
+    doc: ConvertedDocument,
+    document_type: &str,
+    file_path: &Path,
+    book_uid: &str,
+    custom_title: Option<&str>,
+    custom_author: Option<&str>,
+    custom_language: Option<&str>,
+    is_user_added: bool,
+) -> Result<()> {
+    let file_stem = file_path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Untitled".to_string());
+
+    // Use custom values if provided and non-empty, otherwise use the document metadata
+    let pick = |custom: Option<&str>, extracted: Option<&String>| -> String {
+        custom
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.trim().to_string())
+            .or_else(|| extracted.filter(|s| !s.trim().is_empty()).map(|s| s.trim().to_string()))
+            .unwrap_or_default()
+    };
+    let mut title = pick(custom_title, doc.title.as_ref());
+    if title.is_empty() {
+        title = file_stem;
+    }
+    let author = pick(custom_author, doc.author.as_ref());
+    let language = pick(custom_language, doc.language.as_ref());
+
+    tracing::info!("{} metadata - Title: {}, Author: {}, Language: {}", document_type, title, author, language);
+
+    let dom = parse_document(RcDom::default(), Default::default())
+        .from_utf8()
+        .read_from(&mut doc.body_html.as_bytes())
+        .map_err(|e| anyhow!("Failed to parse HTML: {}", e))?;
+    let chapters = split_by_headings(&dom);
+    tracing::info!("Found {} chapters", chapters.len());
+
+    let toc = chapters_toc(&chapters);
+    let toc_json = if !toc.is_empty() {
+        Some(serde_json::to_string(&toc)?)
+    } else {
+        None
+    };
+
+    let metadata_json = serde_json::to_string(&serde_json::json!({
+        "title": doc.title,
+        "author": doc.author,
+        "language": doc.language,
+    }))?;
+
+    let file_path_str = file_path.to_string_lossy().to_string();
+    let new_book = NewBook {
+        uid: book_uid,
+        document_type,
+        title: Some(&title),
+        author: if author.is_empty() { None } else { Some(&author) },
+        language: if language.is_empty() { None } else { Some(&language) },
+        file_path: Some(&file_path_str),
+        metadata_json: Some(&metadata_json),
+        enable_embedded_css: true,
+        toc_json: toc_json.as_deref(),
+        is_user_added,
+    };
+
+    diesel::insert_into(books::table)
+        .values(&new_book)
+        .execute(db_conn)?;
+
+    let book_id: i32 = books::table
+        .filter(books::uid.eq(book_uid))
+        .select(books::id)
+        .first(db_conn)?;
+
+    tracing::info!("Inserted book record with id: {}", book_id);
+
+    for (spine_index, chapter) in chapters.iter().enumerate() {
+        let spine_item_uid = format!("{}.{}", book_uid, spine_index);
+        let resource_path = chapter_resource_path(spine_index);
+        let content_html = rewrite_resource_links(&chapter.content_html, book_uid);
+        let content_plain = html_to_plain_text(&content_html);
+
+        diesel::insert_into(book_spine_items::table)
+            .values(&NewBookSpineItem {
+                book_id,
+                book_uid,
+                spine_item_uid: &spine_item_uid,
+                spine_index: spine_index as i32,
+                resource_path: &resource_path,
+                title: Some(&chapter.title),
+                language: if language.is_empty() { None } else { Some(&language) },
+                content_html: Some(&content_html),
+                content_plain: Some(&content_plain),
+            })
+            .execute(db_conn)?;
+    }
+
+    for (resource_path, data) in &doc.resources {
+        let mime_type = guess_mime_type(resource_path);
+        diesel::insert_into(book_resources::table)
+            .values(&NewBookResource {
+                book_id,
+                book_uid,
+                resource_path,
+                mime_type: Some(&mime_type),
+                content_data: Some(data),
+            })
+            .execute(db_conn)?;
+    }
+
+    tracing::info!(
+        "Successfully imported {}: {} spine items, {} resources",
+        document_type,
+        chapters.len(),
+        doc.resources.len()
+    );
+    Ok(())
+}
+
+/// The resource path of a chapter's spine item, which the TOC links to.
+pub fn chapter_resource_path(spine_index: usize) -> String {
+    format!("chapter_{}.html", spine_index)
+}
+
+/// The table of contents of the chapters which start with a heading, nested
+/// by the heading levels.
+fn chapters_toc(chapters: &[Chapter]) -> Vec<NavPointJson> {
+    let entries: Vec<(usize, NavPointJson)> = chapters.iter()
+        .enumerate()
+        .filter(|(_, chapter)| chapter.level > 0)
+        .map(|(i, chapter)| (chapter.level, NavPointJson {
+            label: chapter.title.clone(),
+            content: chapter_resource_path(i),
+            children: Vec::new(),
+        }))
+        .collect();
+    nest_toc(&entries)
+}
+
+/// The entries after an entry which have a deeper level are its children.
+fn nest_toc(entries: &[(usize, NavPointJson)]) -> Vec<NavPointJson> {
+    let mut toc = Vec::new();
+    let mut i = 0;
+    while i < entries.len() {
+        let (level, nav) = &entries[i];
+        let end = entries[i + 1..].iter()
+            .position(|(l, _)| l <= level)
+            .map_or(entries.len(), |p| i + 1 + p);
+        let mut nav = nav.clone();
+        nav.children = nest_toc(&entries[i + 1..end]);
+        toc.push(nav);
+        i = end;
+    }
+    toc
+}
+
+/// The unescaped value of an XML attribute, by its qualified name (e.g. `w:val`).
+pub(crate) fn xml_attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+/// Read a file of a zip archive (DOCX, ODT). None when the archive doesn't have it.
+pub(crate) fn read_zip_entry<R: std::io::Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Result<Option<Vec<u8>>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(anyhow!("Failed to read {} from the archive: {}", name, e)),
+    };
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)
+        .map_err(|e| anyhow!("Failed to read {} from the archive: {}", name, e))?;
+    Ok(Some(data))
+}
+
+/// Open a DOCX or ODT file as a zip archive.
+pub(crate) fn open_zip(path: &Path) -> Result<zip::ZipArchive<std::fs::File>> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+    zip::ZipArchive::new(file)
+        .map_err(|e| anyhow!("Failed to read {} as a zip archive: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(title: &str, level: usize) -> Chapter {
+        Chapter { title: title.to_string(), content_html: String::new(), level }
+    }
+
+    #[test]
+    fn test_chapters_toc_nesting() {
+        let chapters = vec![
+            chapter("Introduction", 0),
+            chapter("Part One", 1),
+            chapter("Talk 1", 2),
+            chapter("Questions", 3),
+            chapter("Talk 2", 2),
+            chapter("Part Two", 1),
+        ];
+        let toc = chapters_toc(&chapters);
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].label, "Part One");
+        assert_eq!(toc[0].content, "chapter_1.html");
+        assert_eq!(toc[0].children.len(), 2);
+        assert_eq!(toc[0].children[0].children[0].label, "Questions");
+        assert_eq!(toc[0].children[1].content, "chapter_4.html");
+        assert_eq!(toc[1].label, "Part Two");
+        assert!(toc[1].children.is_empty());
+    }
+}
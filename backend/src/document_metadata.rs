@@ -7,6 +7,10 @@ use markup5ever_rcdom::{Handle, NodeData, RcDom};
 use std::fs;
 use std::path::Path;
 
+use crate::docx_import::convert_docx;
+use crate::fb2_import::convert_fb2;
+use crate::markdown_import::{convert_markdown, is_markdown_file};
+use crate::odt_import::convert_odt;
 use crate::pdf_import::{extract_pdf_metadata, extract_xmp_author};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -30,6 +34,14 @@ pub fn extract_document_metadata(file_path: &Path) -> Result<DocumentMetadata> {
         add_html_metadata_from_file(file_path, &mut metadata)?;
     } else if lower_path.ends_with(".pdf") {
         add_pdf_metadata_from_file(file_path, &mut metadata)?;
+    } else if lower_path.ends_with(".docx") {
+        metadata = convert_docx(file_path)?.metadata();
+    } else if lower_path.ends_with(".odt") {
+        metadata = convert_odt(file_path)?.metadata();
+    } else if lower_path.ends_with(".fb2") {
+        metadata = convert_fb2(file_path)?.metadata();
+    } else if is_markdown_file(file_path) {
+        metadata = convert_markdown(file_path)?.metadata();
     }
 
     Ok(metadata)
@@ -0,0 +1,401 @@
+use anyhow::{anyhow, Result};
+use diesel::prelude::*;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::document_import::{import_converted_document_to_db, open_zip, read_zip_entry, xml_attr, ConvertedDocument};
+
+/// Import a Word (DOCX) document into the database
+///
+/// The paragraphs with a heading style ("Heading 1" to "Heading 6", or an
+/// outline level) start the chapters. Bold and italic runs, lists, tables,
+/// hyperlinks and images are kept. The title, author and language are read
+/// from `docProps/core.xml`.
+///
+/// # Arguments
+/// * `db_conn` - Mutable reference to SQLite database connection
+/// * `docx_path` - Path to the DOCX file to import
+/// * `book_uid` - Unique identifier for this book
+/// * `custom_title` - Optional custom title to override the document properties
+/// * `custom_author` - Optional custom author to override the document properties
+/// * `custom_language` - Optional custom language to override the document properties
+/// * `is_user_added` - Whether the book was added by the user
+///
+/// # Returns
+/// * `Result<()>` - Ok if successful, Err with details if failed
+#[allow(clippy::too_many_arguments)]
+pub fn import_docx_to_db(
+    db_conn: &mut SqliteConnection,
+    docx_path: &Path,
+    book_uid: &str,
+    custom_title: Option<&str>,
+    custom_author: Option<&str>,
+    custom_language: Option<&str>,
+    is_user_added: bool,
+) -> Result<()> {
+    tracing::info!("Importing DOCX from {:?} with UID: {}", docx_path, book_uid);
+    let doc = convert_docx(docx_path)?;
+    import_converted_document_to_db(db_conn, doc, "docx", docx_path, book_uid, custom_title, custom_author, custom_language, is_user_added)
+}
+
+/// Convert a DOCX file to HTML.
+pub fn convert_docx(docx_path: &Path) -> Result<ConvertedDocument> {
+    let mut archive = open_zip(docx_path)?;
+
+    let document_xml = read_zip_entry(&mut archive, "word/document.xml")?
+        .ok_or_else(|| anyhow!("Not a DOCX document, word/document.xml is missing: {}", docx_path.display()))?;
+    let heading_styles = match read_zip_entry(&mut archive, "word/styles.xml")? {
+        Some(xml) => parse_heading_styles(&String::from_utf8_lossy(&xml))?,
+        None => HashMap::new(),
+    };
+    let relationships = match read_zip_entry(&mut archive, "word/_rels/document.xml.rels")? {
+        Some(xml) => parse_relationships(&String::from_utf8_lossy(&xml))?,
+        None => HashMap::new(),
+    };
+
+    let mut doc = match read_zip_entry(&mut archive, "docProps/core.xml")? {
+        Some(xml) => parse_core_properties(&String::from_utf8_lossy(&xml))?,
+        None => ConvertedDocument::default(),
+    };
+
+    let mut converter = DocxConverter::new(&heading_styles, &relationships);
+    converter.convert(&String::from_utf8_lossy(&document_xml))?;
+    doc.body_html = converter.out;
+
+    // The images are stored under their path in the archive, without the word/ folder
+    for target in converter.images {
+        if let Some(data) = read_zip_entry(&mut archive, &format!("word/{}", target))? {
+            doc.resources.push((target, data));
+        }
+    }
+
+    Ok(doc)
+}
+
+/// The title, author and language of the document properties.
+fn parse_core_properties(xml: &str) -> Result<ConvertedDocument> {
+    let mut doc = ConvertedDocument::default();
+    let mut reader = Reader::from_str(xml);
+    let mut current: Option<Vec<u8>> = None;
+    loop {
+        match reader.read_event().map_err(|e| anyhow!("Failed to parse docProps/core.xml: {}", e))? {
+            Event::Start(e) => current = Some(e.name().as_ref().to_vec()),
+            Event::End(_) => current = None,
+            Event::Text(t) => {
+                let text = t.unescape().map_err(|e| anyhow!("Failed to parse docProps/core.xml: {}", e))?;
+                let text = text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+                match current.as_deref() {
+                    Some(b"dc:title") => doc.title = Some(text.to_string()),
+                    Some(b"dc:creator") => doc.author = Some(text.to_string()),
+                    Some(b"dc:language") => doc.language = Some(text.to_string()),
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(doc)
+}
+
+/// The heading level of the paragraph styles, by style id.
+fn parse_heading_styles(xml: &str) -> Result<HashMap<String, usize>> {
+    let mut styles = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    let mut style_id: Option<String> = None;
+    loop {
+        match reader.read_event().map_err(|e| anyhow!("Failed to parse word/styles.xml: {}", e))? {
+            Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                b"w:style" => {
+                    style_id = if xml_attr(&e, b"w:type").as_deref() == Some("paragraph") {
+                        xml_attr(&e, b"w:styleId")
+                    } else {
+                        None
+                    };
+                }
+                b"w:name" => {
+                    if let (Some(id), Some(name)) = (&style_id, xml_attr(&e, b"w:val"))
+                        && let Some(level) = heading_style_level(&name) {
+                            styles.insert(id.clone(), level);
+                        }
+                }
+                b"w:outlineLvl" => {
+                    if let (Some(id), Some(level)) = (&style_id, xml_attr(&e, b"w:val").and_then(|v| outline_level(&v))) {
+                        styles.entry(id.clone()).or_insert(level);
+                    }
+                }
+                _ => {}
+            },
+            Event::End(e) if e.name().as_ref() == b"w:style" => style_id = None,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(styles)
+}
+
+/// "heading 1" to "heading 6", and "Title" as a first level heading.
+fn heading_style_level(style_name: &str) -> Option<usize> {
+    let name = style_name.trim().to_lowercase();
+    if name == "title" {
+        return Some(1);
+    }
+    let level: usize = name.strip_prefix("heading")?.trim().parse().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+/// The heading level of a `w:outlineLvl` value, which counts from 0. Level 9 is body text.
+fn outline_level(value: &str) -> Option<usize> {
+    let level: usize = value.parse().ok()?;
+    (level < 6).then_some(level + 1)
+}
+
+/// The targets of the relationships (images, hyperlinks), by id.
+fn parse_relationships(xml: &str) -> Result<HashMap<String, String>> {
+    let mut rels = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event().map_err(|e| anyhow!("Failed to parse word/_rels/document.xml.rels: {}", e))? {
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"Relationship" => {
+                if let (Some(id), Some(target)) = (xml_attr(&e, b"Id"), xml_attr(&e, b"Target")) {
+                    rels.insert(id, target);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(rels)
+}
+
+/// Converts `word/document.xml` to HTML, one paragraph at a time.
+struct DocxConverter<'a> {
+    heading_styles: &'a HashMap<String, usize>,
+    relationships: &'a HashMap<String, String>,
+    out: String,
+    /// The image targets, e.g. `media/image1.png`
+    images: Vec<String>,
+
+    para_html: String,
+    para_level: Option<usize>,
+    para_is_list_item: bool,
+    in_list: bool,
+
+    run_html: String,
+    run_bold: bool,
+    run_italic: bool,
+    in_run_props: bool,
+    in_text: bool,
+}
+
+impl<'a> DocxConverter<'a> {
+    fn new(heading_styles: &'a HashMap<String, usize>, relationships: &'a HashMap<String, String>) -> Self {
+        DocxConverter {
+            heading_styles,
+            relationships,
+            out: String::new(),
+            images: Vec::new(),
+            para_html: String::new(),
+            para_level: None,
+            para_is_list_item: false,
+            in_list: false,
+            run_html: String::new(),
+            run_bold: false,
+            run_italic: false,
+            in_run_props: false,
+            in_text: false,
+        }
+    }
+
+    fn convert(&mut self, xml: &str) -> Result<()> {
+        let mut reader = Reader::from_str(xml);
+        loop {
+            match reader.read_event().map_err(|e| anyhow!("Failed to parse word/document.xml: {}", e))? {
+                Event::Start(e) => self.start(&e, false),
+                Event::Empty(e) => self.start(&e, true),
+                Event::End(e) => self.end(e.name().as_ref()),
+                Event::Text(t) if self.in_text => {
+                    let text = t.unescape().map_err(|e| anyhow!("Failed to parse word/document.xml: {}", e))?;
+                    self.run_html.push_str(&html_escape::encode_text(&text));
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        self.close_list();
+        Ok(())
+    }
+
+    fn start(&mut self, e: &BytesStart, is_empty: bool) {
+        match e.name().as_ref() {
+            b"w:p" => {
+                self.para_html.clear();
+                self.para_level = None;
+                self.para_is_list_item = false;
+                if is_empty {
+                    self.end(b"w:p");
+                }
+            }
+            b"w:pStyle" => {
+                if let Some(style) = xml_attr(e, b"w:val") {
+                    self.para_level = self.heading_styles.get(&style).copied();
+                }
+            }
+            b"w:outlineLvl" => {
+                if let Some(level) = xml_attr(e, b"w:val").and_then(|v| outline_level(&v)) {
+                    self.para_level = Some(level);
+                }
+            }
+            b"w:numPr" => self.para_is_list_item = true,
+            b"w:r" => {
+                self.run_html.clear();
+                self.run_bold = false;
+                self.run_italic = false;
+            }
+            b"w:rPr" => self.in_run_props = !is_empty,
+            b"w:b" if self.in_run_props => self.run_bold = is_on(e),
+            b"w:i" if self.in_run_props => self.run_italic = is_on(e),
+            b"w:t" => self.in_text = !is_empty,
+            b"w:tab" => self.run_html.push(' '),
+            b"w:br" | b"w:cr" if xml_attr(e, b"w:type").is_none_or(|t| t == "textWrapping") => {
+                self.run_html.push_str("<br>");
+            }
+            b"w:hyperlink" if !is_empty => {
+                let href = xml_attr(e, b"r:id")
+                    .and_then(|id| self.relationships.get(&id).cloned())
+                    .or_else(|| xml_attr(e, b"w:anchor").map(|anchor| format!("#{}", anchor)));
+                match href {
+                    Some(href) => self.para_html.push_str(&format!(r#"<a href="{}">"#, html_escape::encode_double_quoted_attribute(&href))),
+                    None => self.para_html.push_str("<a>"),
+                }
+            }
+            // DrawingML and the older VML images
+            b"a:blip" | b"v:imagedata" => {
+                let target = xml_attr(e, b"r:embed")
+                    .or_else(|| xml_attr(e, b"r:id"))
+                    .and_then(|id| self.relationships.get(&id).cloned());
+                if let Some(target) = target {
+                    self.para_html.push_str(&format!(r#"<img src="{}">"#, html_escape::encode_double_quoted_attribute(&target)));
+                    if !self.images.contains(&target) {
+                        self.images.push(target);
+                    }
+                }
+            }
+            b"w:tbl" => {
+                self.close_list();
+                self.out.push_str("<table>");
+            }
+            b"w:tr" => self.out.push_str("<tr>"),
+            b"w:tc" => self.out.push_str("<td>"),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, name: &[u8]) {
+        match name {
+            b"w:p" => self.end_paragraph(),
+            b"w:r" if !self.run_html.is_empty() => {
+                let mut html = std::mem::take(&mut self.run_html);
+                if self.run_italic {
+                    html = format!("<em>{}</em>", html);
+                }
+                if self.run_bold {
+                    html = format!("<strong>{}</strong>", html);
+                }
+                self.para_html.push_str(&html);
+            }
+            b"w:rPr" => self.in_run_props = false,
+            b"w:t" => self.in_text = false,
+            b"w:hyperlink" => self.para_html.push_str("</a>"),
+            b"w:tbl" => {
+                self.close_list();
+                self.out.push_str("</table>\n");
+            }
+            b"w:tr" => self.out.push_str("</tr>"),
+            b"w:tc" => {
+                self.close_list();
+                self.out.push_str("</td>");
+            }
+            _ => {}
+        }
+    }
+
+    fn end_paragraph(&mut self) {
+        let html = std::mem::take(&mut self.para_html);
+        if html.trim().is_empty() {
+            return;
+        }
+        match self.para_level {
+            Some(level) => {
+                self.close_list();
+                self.out.push_str(&format!("<h{level}>{html}</h{level}>\n"));
+            }
+            None if self.para_is_list_item => {
+                if !self.in_list {
+                    self.out.push_str("<ul>");
+                    self.in_list = true;
+                }
+                self.out.push_str(&format!("<li>{}</li>\n", html));
+            }
+            None => {
+                self.close_list();
+                self.out.push_str(&format!("<p>{}</p>\n", html));
+            }
+        }
+    }
+
+    fn close_list(&mut self) {
+        if self.in_list {
+            self.out.push_str("</ul>\n");
+            self.in_list = false;
+        }
+    }
+}
+
+/// A toggle property like `<w:b/>` is on, unless its value turns it off.
+fn is_on(e: &BytesStart) -> bool {
+    !matches!(xml_attr(e, b"w:val").as_deref(), Some("0") | Some("false") | Some("none"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heading_style_level() {
+        assert_eq!(heading_style_level("heading 1"), Some(1));
+        assert_eq!(heading_style_level("Heading 3"), Some(3));
+        assert_eq!(heading_style_level("Title"), Some(1));
+        assert_eq!(heading_style_level("heading 9"), None);
+        assert_eq!(heading_style_level("Normal"), None);
+    }
+
+    #[test]
+    fn test_convert_document_xml() {
+        let rels = HashMap::from([
+            ("rId1".to_string(), "media/image1.png".to_string()),
+            ("rId2".to_string(), "https://www.dhammatalks.org".to_string()),
+        ]);
+        let styles = HashMap::from([("Heading1".to_string(), 1)]);
+        let xml = r#"<w:document xmlns:w="w" xmlns:r="r" xmlns:a="a"><w:body>
+<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>First Talk</w:t></w:r></w:p>
+<w:p><w:r><w:rPr><w:b/></w:rPr><w:t>Mettā</w:t></w:r><w:r><w:rPr><w:i w:val="0"/></w:rPr><w:t xml:space="preserve"> is &amp; </w:t></w:r><w:hyperlink r:id="rId2"><w:r><w:t>link</w:t></w:r></w:hyperlink></w:p>
+<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/></w:numPr></w:pPr><w:r><w:t>one</w:t></w:r></w:p>
+<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/></w:numPr></w:pPr><w:r><w:t>two</w:t></w:r></w:p>
+<w:p><w:r><w:drawing><a:blip r:embed="rId1"/></w:drawing></w:r></w:p>
+<w:p/>
+</w:body></w:document>"#;
+        let mut converter = DocxConverter::new(&styles, &rels);
+        converter.convert(xml).unwrap();
+        assert_eq!(converter.out, "<h1>First Talk</h1>\n\
+<p><strong>Mettā</strong> is &amp; <a href=\"https://www.dhammatalks.org\">link</a></p>\n\
+<ul><li>one</li>\n<li>two</li>\n</ul>\n\
+<p><img src=\"media/image1.png\"></p>\n");
+        assert_eq!(converter.images, vec!["media/image1.png".to_string()]);
+    }
+}
@@ -0,0 +1,424 @@
+//! Downloads of the release assets which survive flaky connections.
+//!
+//! - An interrupted download is kept as `<file>.part`, with the URL, size and
+//!   validators of the response in `<file>.part.json`, and continued with a
+//!   `Range` request, by the same call after a retry or by a later one.
+//! - Mirrors serve the same paths as the release hosts under another base
+//!   URL, and are tried in order before the release URL.
+//! - A proxy and a bandwidth limit can be set.
+//!
+//! The settings are in `download-settings.json` in the internal app folder,
+//! next to `storage-path.txt`, because the first downloads run before
+//! `appdata.sqlite3` exists. See `docs/downloads.md`.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderName, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::{Proxy, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::get_create_simsapa_internal_app_root;
+use crate::logger::{info, warn};
+
+pub const DOWNLOAD_SETTINGS_FILE: &str = "download-settings.json";
+
+const PARTIAL_SUFFIX: &str = ".part";
+const PARTIAL_INFO_SUFFIX: &str = ".part.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadSettings {
+    /// Base URLs which serve the paths of the release URLs, e.g. with
+    /// `https://mirror.example.org/gh` the archive
+    /// `https://github.com/simsapa/simsapa-ng-assets/releases/download/v0.1.0/dpd.tar.bz2`
+    /// is tried at `https://mirror.example.org/gh/simsapa/simsapa-ng-assets/releases/download/v0.1.0/dpd.tar.bz2`.
+    pub mirrors: Vec<String>,
+    /// An `http://` or `https://` proxy URL, optionally with `user:password@`.
+    /// `None` uses the proxy of the `HTTP_PROXY` / `HTTPS_PROXY` environment.
+    pub proxy: Option<String>,
+    /// KiB per second, 0 for no limit.
+    pub bandwidth_limit_kib: u32,
+}
+
+impl DownloadSettings {
+    pub fn settings_path() -> Result<PathBuf> {
+        let root = get_create_simsapa_internal_app_root().map_err(|e| anyhow!("{}", e))?;
+        Ok(root.join(DOWNLOAD_SETTINGS_FILE))
+    }
+
+    /// The saved settings, or the defaults when there are none or they can't
+    /// be read.
+    pub fn load() -> Self {
+        match Self::settings_path().and_then(|p| Self::load_from(&p)) {
+            Ok(s) => s,
+            Err(e) => {
+                warn(&format!("Using the default download settings: {:#}", e));
+                Self::default()
+            }
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.try_exists()? {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::settings_path()?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        let settings = self.validated()?;
+        fs::write(path, serde_json::to_string_pretty(&settings)?)?;
+        Ok(())
+    }
+
+    /// Trim the values and check the URLs.
+    pub fn validated(&self) -> Result<Self> {
+        let mut mirrors = Vec::new();
+        for m in self.mirrors.iter().map(|m| m.trim()).filter(|m| !m.is_empty()) {
+            let u = Url::parse(m).with_context(|| format!("Invalid mirror URL: {}", m))?;
+            if !matches!(u.scheme(), "http" | "https") {
+                bail!("The mirror URL must be http:// or https://: {}", m);
+            }
+            mirrors.push(m.trim_end_matches('/').to_string());
+        }
+        let proxy = match self.proxy.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+            Some(p) => {
+                Proxy::all(p).with_context(|| format!("Invalid proxy URL: {}", p))?;
+                Some(p.to_string())
+            }
+            None => None,
+        };
+        Ok(Self { mirrors, proxy, bandwidth_limit_kib: self.bandwidth_limit_kib })
+    }
+}
+
+/// The URLs to try for `url`: the mirrors, then `url`.
+pub fn mirror_urls(url: &str, mirrors: &[String]) -> Vec<String> {
+    let mut urls = Vec::new();
+    if let Ok(u) = Url::parse(url) {
+        let path = match u.query() {
+            Some(q) => format!("{}?{}", u.path(), q),
+            None => u.path().to_string(),
+        };
+        for m in mirrors {
+            let candidate = format!("{}{}", m.trim_end_matches('/'), path);
+            if !urls.contains(&candidate) {
+                urls.push(candidate);
+            }
+        }
+    }
+    if !urls.iter().any(|u| u == url) {
+        urls.push(url.to_string());
+    }
+    urls
+}
+
+/// A blocking client with the proxy of the settings.
+pub fn http_client(settings: &DownloadSettings) -> Result<Client> {
+    // Bound the DNS+TCP+TLS phase so a silent network stall surfaces as a
+    // retryable error. In the blocking client the timeout applies to each
+    // read of the body, not to the whole download, so large archives on slow
+    // connections can finish.
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(30))
+        .timeout(Duration::from_secs(60));
+    if let Some(proxy) = settings.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+        builder = builder.proxy(Proxy::all(proxy.trim())?);
+    }
+    Ok(builder.build()?)
+}
+
+pub fn partial_path(dest: &Path) -> PathBuf {
+    suffixed(dest, PARTIAL_SUFFIX)
+}
+
+fn partial_info_path(dest: &Path) -> PathBuf {
+    suffixed(dest, PARTIAL_INFO_SUFFIX)
+}
+
+fn suffixed(dest: &Path, suffix: &str) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    dest.with_file_name(name)
+}
+
+/// Whether `path` is the partial file of a download, or its info, which a
+/// cleanup should keep for resuming.
+pub fn is_partial_download(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    name.ends_with(PARTIAL_SUFFIX) || name.ends_with(PARTIAL_INFO_SUFFIX)
+}
+
+/// Remove a download's partial file, e.g. when it is not wanted any more.
+pub fn remove_partial(dest: &Path) {
+    let _ = fs::remove_file(partial_path(dest));
+    let _ = fs::remove_file(partial_info_path(dest));
+}
+
+/// What a partial download was fetched from, to check that a resumed
+/// response continues the same file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PartialInfo {
+    url: String,
+    total: Option<u64>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadEvent {
+    Connecting { url: String, attempt: u32, max_attempts: u32 },
+    Resuming { url: String, offset: u64 },
+    Progress { downloaded: u64, total: Option<u64> },
+    Retrying { error: String, wait: Duration, attempt: u32, max_attempts: u32 },
+}
+
+pub struct Downloader {
+    settings: DownloadSettings,
+    client: Client,
+    max_attempts: u32,
+    retry_wait: Duration,
+}
+
+impl Downloader {
+    /// A downloader which tries 5 rounds of the URLs, waiting 2, 4, 8 and 16
+    /// seconds between the rounds.
+    pub fn new(settings: DownloadSettings) -> Result<Self> {
+        let client = http_client(&settings)?;
+        Ok(Self { settings, client, max_attempts: 5, retry_wait: Duration::from_secs(2) })
+    }
+
+    /// Rounds of the URLs to try, and the wait after the first one, which
+    /// doubles after each.
+    pub fn with_retries(mut self, max_attempts: u32, retry_wait: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_wait = retry_wait;
+        self
+    }
+
+    /// Download `url` to `dest`, continuing a partial download of an earlier
+    /// call. A round of the mirrors and the URL which gets more of the file
+    /// doesn't count as a failed attempt, so a connection which keeps
+    /// dropping still finishes the download.
+    pub fn download(&self, url: &str, dest: &Path, on_event: &mut dyn FnMut(DownloadEvent)) -> Result<PathBuf> {
+        if let Some(dir) = dest.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let candidates = mirror_urls(url, &self.settings.mirrors);
+        let mut failures = 0;
+        let mut last_error = anyhow!("No URL to download");
+
+        while failures < self.max_attempts {
+            let before = partial_len(dest);
+            for candidate in &candidates {
+                on_event(DownloadEvent::Connecting {
+                    url: candidate.clone(),
+                    attempt: failures + 1,
+                    max_attempts: self.max_attempts,
+                });
+                match self.fetch(candidate, dest, on_event) {
+                    Ok(()) => {
+                        fs::rename(partial_path(dest), dest)?;
+                        let _ = fs::remove_file(partial_info_path(dest));
+                        info(&format!("Downloaded {}", candidate));
+                        return Ok(dest.to_path_buf());
+                    }
+                    Err(e) => {
+                        warn(&format!("Download from {} failed: {:#}", candidate, e));
+                        last_error = e;
+                    }
+                }
+            }
+
+            if partial_len(dest) > before {
+                continue;
+            }
+            failures += 1;
+            if failures < self.max_attempts {
+                let wait = self.retry_wait * 2_u32.pow(failures - 1);
+                on_event(DownloadEvent::Retrying {
+                    error: format!("{:#}", last_error),
+                    wait,
+                    attempt: failures + 1,
+                    max_attempts: self.max_attempts,
+                });
+                thread::sleep(wait);
+            }
+        }
+
+        Err(last_error.context(format!("Failed to download {} after {} attempts", url, self.max_attempts)))
+    }
+
+    /// One request, appending to the partial file when the server continues it.
+    fn fetch(&self, url: &str, dest: &Path, on_event: &mut dyn FnMut(DownloadEvent)) -> Result<()> {
+        let part = partial_path(dest);
+        let info_path = partial_info_path(dest);
+
+        let prev: Option<PartialInfo> = fs::read_to_string(&info_path).ok()
+            .and_then(|text| serde_json::from_str(&text).ok());
+        let mut offset = match &prev {
+            Some(_) => partial_len(dest),
+            None => 0,
+        };
+        if let Some(PartialInfo { total: Some(total), .. }) = &prev
+            && offset > *total
+        {
+            offset = 0;
+        }
+
+        let mut req = self.client.get(url);
+        if offset > 0 {
+            req = req.header(RANGE, format!("bytes={}-", offset));
+            // The validators are only meaningful to the server which sent them.
+            if let Some(p) = prev.as_ref().filter(|p| p.url == url)
+                && let Some(v) = p.etag.as_ref().or(p.last_modified.as_ref())
+            {
+                req = req.header(IF_RANGE, v.as_str());
+            }
+            on_event(DownloadEvent::Resuming { url: url.to_string(), offset });
+        }
+
+        let resp = req.send()?;
+        let status = resp.status();
+        if status == StatusCode::RANGE_NOT_SATISFIABLE
+            && prev.as_ref().and_then(|p| p.total) == Some(offset)
+        {
+            // The partial file is already complete.
+            return Ok(());
+        }
+        let mut resp = resp.error_for_status()?;
+
+        let header = |name: HeaderName| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from);
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+
+        let (start, total) = if status == StatusCode::PARTIAL_CONTENT {
+            let content_range = header(CONTENT_RANGE).ok_or_else(|| anyhow!("A partial response without Content-Range"))?;
+            let (start, total) = parse_content_range(&content_range)?;
+            if start != offset {
+                bail!("The server continued from byte {}, not {}", start, offset);
+            }
+            let known = prev.as_ref().and_then(|p| p.total);
+            if let (Some(known), Some(total)) = (known, total)
+                && known != total
+            {
+                remove_partial(dest);
+                bail!("The file is {} bytes at {}, the partial download was of {} bytes", total, url, known);
+            }
+            (start, total.or(known))
+        } else {
+            (0, resp.content_length())
+        };
+
+        let info = PartialInfo { url: url.to_string(), total, etag, last_modified };
+        fs::write(&info_path, serde_json::to_string(&info)?)?;
+
+        let mut file = if start > 0 {
+            OpenOptions::new().append(true).open(&part)?
+        } else {
+            File::create(&part)?
+        };
+
+        let limit = u64::from(self.settings.bandwidth_limit_kib) * 1024;
+        let started = Instant::now();
+        let mut received = 0_u64;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = resp.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n])?;
+            received += n as u64;
+            on_event(DownloadEvent::Progress { downloaded: start + received, total });
+
+            if limit > 0 {
+                let due = Duration::from_secs_f64(received as f64 / limit as f64);
+                let elapsed = started.elapsed();
+                if due > elapsed {
+                    thread::sleep(due - elapsed);
+                }
+            }
+        }
+        file.flush()?;
+
+        if let Some(total) = total
+            && start + received != total
+        {
+            bail!("The connection closed after {} of {} bytes", start + received, total);
+        }
+        Ok(())
+    }
+}
+
+fn partial_len(dest: &Path) -> u64 {
+    fs::metadata(partial_path(dest)).map(|m| m.len()).unwrap_or(0)
+}
+
+/// `bytes 100-999/1000` to the first byte and the total size, which may be `*`.
+fn parse_content_range(value: &str) -> Result<(u64, Option<u64>)> {
+    let err = || anyhow!("Invalid Content-Range: {}", value);
+    let range = value.trim().strip_prefix("bytes ").ok_or_else(err)?;
+    let (span, total) = range.split_once('/').ok_or_else(err)?;
+    let (start, _end) = span.split_once('-').ok_or_else(err)?;
+    let start = start.trim().parse().map_err(|_| err())?;
+    let total = match total.trim() {
+        "*" => None,
+        t => Some(t.parse().map_err(|_| err())?),
+    };
+    Ok((start, total))
+}
+
+/// Download with the saved settings, for the backend's own downloads.
+pub fn download_file(url: &str, dest: &Path) -> Result<PathBuf> {
+    Downloader::new(DownloadSettings::load())?.download(url, dest, &mut |_| {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirror_urls() {
+        let url = "https://github.com/simsapa/simsapa-ng-assets/releases/download/v0.1.0/dpd.tar.bz2";
+        let mirrors = vec!["https://mirror.example.org/gh/".to_string()];
+        assert_eq!(mirror_urls(url, &mirrors), vec![
+            "https://mirror.example.org/gh/simsapa/simsapa-ng-assets/releases/download/v0.1.0/dpd.tar.bz2".to_string(),
+            url.to_string(),
+        ]);
+        assert_eq!(mirror_urls(url, &[]), vec![url.to_string()]);
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 100-999/1000").unwrap(), (100, Some(1000)));
+        assert_eq!(parse_content_range("bytes 0-9/*").unwrap(), (0, None));
+        assert!(parse_content_range("items 0-9/10").is_err());
+    }
+
+    #[test]
+    fn test_settings_are_validated() {
+        let s = DownloadSettings {
+            mirrors: vec![" https://mirror.example.org/ ".to_string(), "".to_string()],
+            proxy: Some(" ".to_string()),
+            bandwidth_limit_kib: 100,
+        };
+        let v = s.validated().unwrap();
+        assert_eq!(v.mirrors, vec!["https://mirror.example.org".to_string()]);
+        assert_eq!(v.proxy, None);
+
+        let s = DownloadSettings { mirrors: vec!["ftp://mirror.example.org".to_string()], ..Default::default() };
+        assert!(s.validated().is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use anyhow::{anyhow, bail, Result};
+use diesel::prelude::*;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::fs;
+use std::path::Path;
+
+use crate::document_import::{import_converted_document_to_db, xml_attr, ConvertedDocument};
+
+/// Import a FictionBook (FB2) document into the database
+///
+/// The section titles are the headings, nested by the sections, so each
+/// section is a chapter. The notes body is kept at the end, without
+/// splitting it into a chapter per note. The images are the `<binary>`
+/// elements, stored as resources under `images/`.
+///
+/// # Arguments
+/// * `db_conn` - Mutable reference to SQLite database connection
+/// * `fb2_path` - Path to the FB2 file to import
+/// * `book_uid` - Unique identifier for this book
+/// * `custom_title` - Optional custom title to override the title-info
+/// * `custom_author` - Optional custom author to override the title-info
+/// * `custom_language` - Optional custom language to override the title-info
+/// * `is_user_added` - Whether the book was added by the user
+///
+/// # Returns
+/// * `Result<()>` - Ok if successful, Err with details if failed
+#[allow(clippy::too_many_arguments)]
+pub fn import_fb2_to_db(
+    db_conn: &mut SqliteConnection,
+    fb2_path: &Path,
+    book_uid: &str,
+    custom_title: Option<&str>,
+    custom_author: Option<&str>,
+    custom_language: Option<&str>,
+    is_user_added: bool,
+) -> Result<()> {
+    tracing::info!("Importing FB2 from {:?} with UID: {}", fb2_path, book_uid);
+    let doc = convert_fb2(fb2_path)?;
+    import_converted_document_to_db(db_conn, doc, "fb2", fb2_path, book_uid, custom_title, custom_author, custom_language, is_user_added)
+}
+
+/// Convert an FB2 file to HTML.
+pub fn convert_fb2(fb2_path: &Path) -> Result<ConvertedDocument> {
+    let data = fs::read(fb2_path)
+        .map_err(|e| anyhow!("Failed to read FB2 file: {}", e))?;
+    let Ok(xml) = String::from_utf8(data) else {
+        bail!("Only UTF-8 encoded FB2 files can be imported: {}", fb2_path.display());
+    };
+    let mut converter = Fb2Converter::default();
+    converter.convert(&xml)?;
+    Ok(converter.doc)
+}
+
+/// Converts the FB2 XML to HTML. Each element pushes its closing tag, which
+/// is written at its end.
+#[derive(Debug, Default)]
+struct Fb2Converter {
+    doc: ConvertedDocument,
+    /// The element names from the root
+    path: Vec<Vec<u8>>,
+    closing_tags: Vec<String>,
+    section_depth: usize,
+    in_notes: bool,
+    /// The `<binary>` being read: its resource path
+    binary: Option<String>,
+    authors: Vec<String>,
+    author_names: Vec<String>,
+}
+
+impl Fb2Converter {
+    fn convert(&mut self, xml: &str) -> Result<()> {
+        let mut reader = Reader::from_str(xml);
+        loop {
+            match reader.read_event().map_err(|e| anyhow!("Failed to parse FB2: {}", e))? {
+                Event::Start(e) => {
+                    let (open, close) = self.element_tags(&e);
+                    self.doc.body_html.push_str(&open);
+                    self.closing_tags.push(close);
+                    self.path.push(e.name().as_ref().to_vec());
+                }
+                Event::Empty(e) => {
+                    let (open, close) = self.element_tags(&e);
+                    self.doc.body_html.push_str(&open);
+                    self.doc.body_html.push_str(&close);
+                    // The element ends as well
+                    self.path.push(e.name().as_ref().to_vec());
+                    self.end_element();
+                    self.path.pop();
+                }
+                Event::End(_) => {
+                    self.end_element();
+                    self.path.pop();
+                    if let Some(close) = self.closing_tags.pop() {
+                        self.doc.body_html.push_str(&close);
+                    }
+                }
+                Event::Text(t) => {
+                    let text = t.unescape().map_err(|e| anyhow!("Failed to parse FB2: {}", e))?;
+                    self.text(&text)?;
+                }
+                Event::CData(t) => {
+                    let text = String::from_utf8_lossy(&t).to_string();
+                    self.text(&text)?;
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        if !self.authors.is_empty() {
+            self.doc.author = Some(self.authors.join(", "));
+        }
+        Ok(())
+    }
+
+    fn in_element(&self, name: &[u8]) -> bool {
+        self.path.iter().any(|n| n.as_slice() == name)
+    }
+
+    fn parent_is(&self, name: &[u8]) -> bool {
+        self.path.last().is_some_and(|n| n.as_slice() == name)
+    }
+
+    fn in_body(&self) -> bool {
+        self.in_element(b"body")
+    }
+
+    fn text(&mut self, text: &str) -> Result<()> {
+        if let Some(resource_path) = &self.binary {
+            use base64::Engine;
+            let data: String = text.split_whitespace().collect();
+            match base64::engine::general_purpose::STANDARD.decode(data) {
+                Ok(data) => self.doc.resources.push((resource_path.clone(), data)),
+                Err(e) => tracing::warn!("Failed to decode FB2 image {}: {}", resource_path, e),
+            }
+            return Ok(());
+        }
+
+        if self.in_element(b"title-info") {
+            let text = text.trim();
+            if text.is_empty() || self.in_element(b"annotation") {
+                return Ok(());
+            }
+            if self.parent_is(b"book-title") {
+                self.doc.title = Some(text.to_string());
+            } else if self.parent_is(b"lang") {
+                self.doc.language = Some(text.to_string());
+            } else if self.in_element(b"author") && !self.parent_is(b"email") && !self.parent_is(b"id") && !self.parent_is(b"home-page") {
+                self.author_names.push(text.to_string());
+            }
+            return Ok(());
+        }
+
+        if self.in_body() {
+            self.doc.body_html.push_str(&html_escape::encode_text(text));
+        }
+        Ok(())
+    }
+
+    fn end_element(&mut self) {
+        match self.path.last().map(|n| n.as_slice()) {
+            Some(b"section") if self.in_body() => self.section_depth = self.section_depth.saturating_sub(1),
+            Some(b"body") => self.in_notes = false,
+            Some(b"binary") => self.binary = None,
+            Some(b"author") if self.in_element(b"title-info") => {
+                let name = std::mem::take(&mut self.author_names);
+                if !name.is_empty() {
+                    self.authors.push(name.join(" "));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The HTML opening and closing tags of an element, empty for the ones
+    /// which are not written.
+    fn element_tags(&mut self, e: &BytesStart) -> (String, String) {
+        let tags = |open: &str, close: &str| (open.to_string(), close.to_string());
+        let name = e.name().as_ref().to_vec();
+
+        if name == b"binary" {
+            if let Some(id) = xml_attr(e, b"id") {
+                self.binary = Some(format!("images/{}", id));
+            }
+            return tags("", "");
+        }
+        if name == b"body" {
+            self.in_notes = xml_attr(e, b"name").is_some_and(|n| n == "notes" || n == "comments");
+            return tags("", "");
+        }
+        if !self.in_body() {
+            return tags("", "");
+        }
+
+        match name.as_slice() {
+            b"section" => {
+                self.section_depth += 1;
+                match xml_attr(e, b"id") {
+                    Some(id) if self.in_notes => (format!(r#"<div class="note" id="{}">"#, html_escape::encode_double_quoted_attribute(&id)), "</div>\n".to_string()),
+                    _ => tags("", ""),
+                }
+            }
+            b"title" => {
+                if self.in_notes && self.section_depth > 0 {
+                    tags(r#"<div class="note-title">"#, "</div>\n")
+                } else if self.section_depth == 0 {
+                    // The title of a body: the book title, or the heading of the notes
+                    if self.in_notes {
+                        tags("<h1>", "</h1>\n")
+                    } else {
+                        tags(r#"<div class="book-title">"#, "</div>\n")
+                    }
+                } else {
+                    let level = self.section_depth.min(6);
+                    (format!("<h{}>", level), format!("</h{}>\n", level))
+                }
+            }
+            // The lines of a heading are joined with a space
+            b"p" if self.parent_is(b"title") && !self.in_notes && self.section_depth > 0 => tags("", " "),
+            b"p" => tags("<p>", "</p>\n"),
+            b"v" => tags(r#"<p class="verse">"#, "</p>\n"),
+            b"subtitle" => tags(r#"<p class="subtitle">"#, "</p>\n"),
+            b"text-author" => tags(r#"<p class="text-author">"#, "</p>\n"),
+            b"empty-line" => tags("<br>", ""),
+            b"emphasis" => tags("<em>", "</em>"),
+            b"strong" => tags("<strong>", "</strong>"),
+            b"strikethrough" => tags("<s>", "</s>"),
+            b"sub" => tags("<sub>", "</sub>"),
+            b"sup" => tags("<sup>", "</sup>"),
+            b"code" => tags("<code>", "</code>"),
+            b"epigraph" => tags(r#"<blockquote class="epigraph">"#, "</blockquote>\n"),
+            b"cite" => tags("<blockquote>", "</blockquote>\n"),
+            b"poem" => tags(r#"<div class="poem">"#, "</div>\n"),
+            b"stanza" => tags(r#"<div class="stanza">"#, "</div>\n"),
+            b"table" => tags("<table>", "</table>\n"),
+            b"tr" => tags("<tr>", "</tr>"),
+            b"td" => tags("<td>", "</td>"),
+            b"th" => tags("<th>", "</th>"),
+            b"a" => match href_attr(e) {
+                Some(href) => (format!(r#"<a href="{}">"#, html_escape::encode_double_quoted_attribute(&href)), "</a>".to_string()),
+                None => tags("<a>", "</a>"),
+            },
+            b"image" => match href_attr(e).as_deref().and_then(|href| href.strip_prefix('#')) {
+                Some(id) => (format!(r#"<img src="images/{}">"#, html_escape::encode_double_quoted_attribute(id)), String::new()),
+                None => tags("", ""),
+            },
+            _ => tags("", ""),
+        }
+    }
+}
+
+/// The `l:href` or `xlink:href` of a link or image, by any namespace prefix.
+fn href_attr(e: &BytesStart) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == b"href")
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_fb2() {
+        let xml = r##"<?xml version="1.0" encoding="utf-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0" xmlns:l="http://www.w3.org/1999/xlink">
+<description><title-info>
+<author><first-name>Ajahn</first-name><last-name>Test</last-name></author>
+<book-title>Talks on Mettā</book-title><lang>en</lang>
+<annotation><p>Not the body</p></annotation>
+</title-info></description>
+<body><title><p>Talks on Mettā</p></title>
+<section><title><p>First</p><p>Talk</p></title><p>Mettā <emphasis>is</emphasis> kindness<a l:href="#n1">1</a>.</p>
+<image l:href="#pic.png"/>
+<section><title><p>Questions</p></title><p>Q &amp; A</p></section>
+</section>
+</body>
+<body name="notes"><title><p>Notes</p></title><section id="n1"><title><p>1</p></title><p>A note.</p></section></body>
+<binary id="pic.png" content-type="image/png">aGVsbG8=</binary>
+</FictionBook>"##;
+        let mut converter = Fb2Converter::default();
+        converter.convert(xml).unwrap();
+        let doc = converter.doc;
+        assert_eq!(doc.title.as_deref(), Some("Talks on Mettā"));
+        assert_eq!(doc.author.as_deref(), Some("Ajahn Test"));
+        assert_eq!(doc.language.as_deref(), Some("en"));
+
+        let body = &doc.body_html;
+        assert!(body.contains(r#"<div class="book-title"><p>Talks on Mettā</p>"#), "{}", body);
+        assert!(body.contains("<h1>First Talk </h1>"), "{}", body);
+        assert!(body.contains(r##"<p>Mettā <em>is</em> kindness<a href="#n1">1</a>.</p>"##), "{}", body);
+        assert!(body.contains(r#"<img src="images/pic.png">"#), "{}", body);
+        assert!(body.contains("<h2>Questions </h2>"), "{}", body);
+        assert!(body.contains("<p>Q &amp; A</p>"), "{}", body);
+        assert!(body.contains("<h1><p>Notes</p>"), "{}", body);
+        assert!(body.contains(r#"<div class="note" id="n1"><div class="note-title"><p>1</p>"#), "{}", body);
+        assert!(!body.contains("Not the body"), "{}", body);
+        assert_eq!(doc.resources, vec![("images/pic.png".to_string(), b"hello".to_vec())]);
+    }
+}
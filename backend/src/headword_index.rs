@@ -0,0 +1,175 @@
+//! Flat completion lists of the sutta titles and the dictionary headwords.
+//!
+//! The lists complete a typed prefix (the query suggestions of the search
+//! bar, see `search_history`), and match a word by its Pāli phonetic key for
+//! the fuzzy dictionary search (`query_task`). They are loaded from the
+//! databases on first use and kept in memory.
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::Result;
+
+use crate::db::DbManager;
+use crate::helpers::pali_to_ascii;
+use crate::pali_phonetic::{edit_distance, fuzzy_distance_for_key, pali_phonetic_key};
+
+/// A flat completion list with the keys to match a query against.
+pub struct CompletionList {
+    words: Vec<String>,
+    keys: Vec<String>,
+    /// The Pāli phonetic keys of the words, made on the first fuzzy lookup.
+    phonetic_keys: OnceLock<Vec<String>>,
+}
+
+impl CompletionList {
+    pub fn new(words: Vec<String>) -> Self {
+        let keys = words.iter().map(|w| completion_key(w)).collect();
+        CompletionList { words, keys, phonetic_keys: OnceLock::new() }
+    }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// The words starting with the query, then the words with a word starting
+    /// with it, shorter words first. Diacritics and case are ignored.
+    pub fn complete(&self, query: &str, limit: usize) -> Vec<String> {
+        let key = completion_key(query);
+        if key.is_empty() {
+            return Vec::new();
+        }
+        let word_start = format!(" {}", key);
+
+        let mut matches: Vec<(bool, usize, usize)> = self.keys
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, k)| {
+                if k.starts_with(&key) {
+                    Some((false, self.words[idx].chars().count(), idx))
+                } else if k.contains(&word_start) {
+                    Some((true, self.words[idx].chars().count(), idx))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        matches.sort();
+
+        matches
+            .into_iter()
+            .map(|(_, _, idx)| self.words[idx].clone())
+            .filter(|w| w != query)
+            .take(limit)
+            .collect()
+    }
+
+    /// The words with a Pāli phonetic key within `fuzzy_distance` of the key
+    /// of the query, its words written together, so `sati patthana` and
+    /// `satipatana` find `satipaṭṭhāna`. Closest first, then shorter words,
+    /// with their edit distance. See docs/fuzzy-phonetic-search.md.
+    pub fn phonetic_matches(&self, query: &str, fuzzy_distance: u8, limit: usize) -> Vec<(String, usize)> {
+        let key = pali_phonetic_key(&query.split_whitespace().collect::<String>());
+        if key.is_empty() {
+            return Vec::new();
+        }
+        let max_distance = fuzzy_distance_for_key(&key, fuzzy_distance) as usize;
+        let len = key.chars().count();
+
+        let phonetic_keys = self.phonetic_keys.get_or_init(|| {
+            self.words.iter().map(|w| pali_phonetic_key(&w.replace(' ', ""))).collect()
+        });
+
+        let mut matches: Vec<(usize, usize, usize)> = phonetic_keys
+            .iter()
+            .enumerate()
+            .filter(|(_, k)| k.chars().count().abs_diff(len) <= max_distance)
+            .filter_map(|(idx, k)| {
+                let distance = edit_distance(&key, k);
+                (distance <= max_distance).then(|| (distance, self.words[idx].chars().count(), idx))
+            })
+            .collect();
+        matches.sort();
+
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(distance, _, idx)| (self.words[idx].clone(), distance))
+            .collect()
+    }
+}
+
+static SUTTA_TITLES: Mutex<Option<Arc<CompletionList>>> = Mutex::new(None);
+static DICT_WORDS: Mutex<Option<Arc<CompletionList>>> = Mutex::new(None);
+
+/// Lowercase, without diacritics: `Satipaṭṭhāna` → `satipatthana`.
+pub fn completion_key(text: &str) -> String {
+    pali_to_ascii(Some(&text.to_lowercase()))
+}
+
+/// The list is loaded on first use, and kept unless it is empty (e.g. the
+/// database was not ready yet).
+fn cached_list(cache: &Mutex<Option<Arc<CompletionList>>>,
+               load: impl FnOnce() -> Result<Vec<String>>) -> Result<Arc<CompletionList>> {
+    let mut cached = cache.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(list) = cached.as_ref() {
+        return Ok(list.clone());
+    }
+    let list = Arc::new(CompletionList::new(load()?));
+    if !list.words.is_empty() {
+        *cached = Some(list.clone());
+    }
+    Ok(list)
+}
+
+pub fn sutta_titles_completion_list(dbm: &DbManager) -> Result<Arc<CompletionList>> {
+    cached_list(&SUTTA_TITLES, || dbm.appdata.sutta_titles_flat_completion_list())
+}
+
+pub fn dict_words_completion_list(dbm: &DbManager) -> Result<Arc<CompletionList>> {
+    cached_list(&DICT_WORDS, || dbm.dpd.dict_words_flat_completion_list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete() {
+        let list = CompletionList::new(vec![
+            "Satipaṭṭhāna Sutta".to_string(),
+            "Mahāsatipaṭṭhāna Sutta".to_string(),
+            "sati".to_string(),
+            "satipaṭṭhāna".to_string(),
+            "The Establishing of Mindfulness".to_string(),
+        ]);
+
+        assert_eq!(list.complete("satip", 10), vec!["satipaṭṭhāna", "Satipaṭṭhāna Sutta"]);
+        assert_eq!(list.complete("sati", 10), vec!["satipaṭṭhāna", "Satipaṭṭhāna Sutta"]);
+        assert_eq!(list.complete("mind", 10), vec!["The Establishing of Mindfulness"]);
+        assert_eq!(list.complete("satip", 1), vec!["satipaṭṭhāna"]);
+        assert!(list.complete("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_phonetic_matches() {
+        let list = CompletionList::new(vec![
+            "sati".to_string(),
+            "satipaṭṭhāna".to_string(),
+            "satipaṭṭhānasutta".to_string(),
+            "sammāsati".to_string(),
+        ]);
+
+        let words = |query: &str, fuzzy_distance: u8| -> Vec<String> {
+            list.phonetic_matches(query, fuzzy_distance, 10).into_iter().map(|(w, _)| w).collect()
+        };
+
+        assert_eq!(list.phonetic_matches("satipatana", 1, 10), vec![("satipaṭṭhāna".to_string(), 0)]);
+        assert_eq!(words("sati patthana", 1), vec!["satipaṭṭhāna"]);
+        // One edit away
+        assert_eq!(list.phonetic_matches("satipatane", 1, 10), vec![("satipaṭṭhāna".to_string(), 1)]);
+        // The same key without the aspirate, and a short key is matched within
+        // one edit at most
+        assert_eq!(words("sathi", 2), vec!["sati"]);
+        assert!(words("", 2).is_empty());
+    }
+}
@@ -2594,6 +2594,15 @@ pub fn analyze_sqlite_db_via_cli(db_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// `words` random 64-bit words as lowercase hex (16 chars each), from the OS
+/// random source, for the API token, the sync secrets and the ids which have
+/// to be unguessable.
+pub fn random_hex(words: usize) -> String {
+    let mut bytes = vec![0u8; words * 8];
+    getrandom::fill(&mut bytes).expect("the OS random source is available");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -14,11 +14,10 @@ use crate::helpers::{compact_rich_text, strip_html};
 
 /// Chapter extracted from HTML document
 #[derive(Debug, Clone)]
-struct Chapter {
-    title: String,
-    content_html: String,
-    #[allow(dead_code)]
-    level: usize, // Heading level (1-6) - reserved for future use
+pub(crate) struct Chapter {
+    pub title: String,
+    pub content_html: String,
+    pub level: usize, // Heading level (1-6), 0 before the first heading
 }
 
 /// Import an HTML file into the database
@@ -295,7 +294,7 @@ fn get_element_text(handle: &Handle) -> Option<String> {
 }
 
 /// Split HTML document into chapters based on heading tags (h1-h6)
-fn split_by_headings(dom: &RcDom) -> Vec<Chapter> {
+pub(crate) fn split_by_headings(dom: &RcDom) -> Vec<Chapter> {
     let mut chapters = Vec::new();
     let mut current_content = String::new();
     let mut current_title = String::from("Introduction");
@@ -599,7 +598,7 @@ fn mime_to_extension(mime: &str) -> &str {
 }
 
 /// Guess MIME type from file extension
-fn guess_mime_type(path: &str) -> String {
+pub(crate) fn guess_mime_type(path: &str) -> String {
     let extension = Path::new(path)
         .extension()
         .and_then(|e| e.to_str())
@@ -622,7 +621,7 @@ fn guess_mime_type(path: &str) -> String {
 }
 
 /// Rewrite resource links in HTML to use the API endpoint format
-fn rewrite_resource_links(html: &str, book_uid: &str) -> String {
+pub(crate) fn rewrite_resource_links(html: &str, book_uid: &str) -> String {
     lazy_static::lazy_static! {
         static ref RE_SRC: Regex = Regex::new(r#"(?i)(src|href)=["']([^"']+)["']"#).unwrap();
     }
@@ -651,7 +650,7 @@ fn rewrite_resource_links(html: &str, book_uid: &str) -> String {
 }
 
 /// Convert HTML content to plain text for FTS5 indexing
-fn html_to_plain_text(html: &str) -> String {
+pub(crate) fn html_to_plain_text(html: &str) -> String {
     let stripped = strip_html(html);
     compact_rich_text(&stripped)
 }
@@ -0,0 +1,264 @@
+use anyhow::{anyhow, bail, Result};
+use diesel::prelude::*;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::db::appdata_models::{NewBook, NewBookResource, NewBookSpineItem};
+use crate::db::appdata_schema::{book_resources, book_spine_items, books};
+use crate::helpers::compact_rich_text;
+use crate::ocr::Ocr;
+
+/// The page images of a scanned book, in a folder.
+pub const PAGE_IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "tif", "tiff"];
+
+/// Import a folder of page images (PNG, TIFF, JPEG scans) as a book
+///
+/// Each image is a page: a spine item which shows the image, with the text
+/// recognized by OCR (when `ocr` is given) for searching and below the image.
+/// The images are stored as resources under `images/`.
+///
+/// # Arguments
+/// * `db_conn` - Mutable reference to SQLite database connection
+/// * `folder` - The folder of the page images, in file name order
+/// * `book_uid` - Unique identifier for this book
+/// * `custom_title` - Optional title, the folder name when None
+/// * `custom_author` - Optional author
+/// * `custom_language` - Optional language
+/// * `is_user_added` - Whether the book was added by the user
+/// * `ocr` - Optional OCR engine to recognize the text of the pages
+///
+/// # Returns
+/// * `Result<()>` - Ok if successful, Err with details if failed
+#[allow(clippy::too_many_arguments)]
+pub fn import_images_to_db(
+    db_conn: &mut SqliteConnection,
+    folder: &Path,
+    book_uid: &str,
+    custom_title: Option<&str>,
+    custom_author: Option<&str>,
+    custom_language: Option<&str>,
+    is_user_added: bool,
+    ocr: Option<&Ocr>,
+) -> Result<()> {
+    tracing::info!("Importing page images from {:?} with UID: {}", folder, book_uid);
+
+    let images = list_page_images(folder)?;
+    if images.is_empty() {
+        bail!("No page images (PNG, TIFF, JPEG) in {}", folder.display());
+    }
+
+    let folder_name = folder.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Untitled".to_string());
+    let title = custom_title
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| s.to_string())
+        .unwrap_or(folder_name);
+    let author = custom_author.map(|s| s.trim().to_string()).unwrap_or_default();
+    let language = custom_language.map(|s| s.trim().to_string()).unwrap_or_default();
+
+    // Recognize the pages before writing, OCR is the slow part
+    let mut pages = Vec::with_capacity(images.len());
+    let mut ocr_pages = Vec::new();
+    for (i, image) in images.iter().enumerate() {
+        let page = i as u32 + 1;
+        let text = match ocr {
+            Some(ocr) => match ocr.recognize(image, page) {
+                Ok((text, info)) => {
+                    tracing::debug!("OCR page {}: {} words, confidence {:?}", page, info.words, info.confidence);
+                    ocr_pages.push(info);
+                    text
+                }
+                Err(e) => {
+                    tracing::warn!("OCR failed on {:?}: {}", image, e);
+                    String::new()
+                }
+            },
+            None => String::new(),
+        };
+        pages.push((image, text));
+    }
+
+    let metadata_json = match ocr {
+        Some(ocr) => Some(ocr.metadata(ocr_pages).to_metadata_json()?),
+        None => None,
+    };
+
+    let folder_str = folder.to_string_lossy().to_string();
+    let new_book = NewBook {
+        uid: book_uid,
+        document_type: "images",
+        title: Some(&title),
+        author: if author.is_empty() { None } else { Some(&author) },
+        language: if language.is_empty() { None } else { Some(&language) },
+        file_path: Some(&folder_str),
+        metadata_json: metadata_json.as_deref(),
+        enable_embedded_css: true,
+        toc_json: None,
+        is_user_added,
+    };
+
+    diesel::insert_into(books::table)
+        .values(&new_book)
+        .execute(db_conn)?;
+
+    let book_id: i32 = books::table
+        .filter(books::uid.eq(book_uid))
+        .select(books::id)
+        .first(db_conn)?;
+
+    for (i, (image, text)) in pages.iter().enumerate() {
+        let page = i + 1;
+        let file_name = image.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("Invalid image path: {:?}", image))?;
+        let resource_path = format!("images/{}", file_name);
+        let data = fs::read(image)
+            .map_err(|e| anyhow!("Failed to read {:?}: {}", image, e))?;
+
+        diesel::insert_into(book_resources::table)
+            .values(&NewBookResource {
+                book_id,
+                book_uid,
+                resource_path: &resource_path,
+                mime_type: Some(image_mime_type(&file_name)),
+                content_data: Some(&data),
+            })
+            .execute(db_conn)?;
+
+        let spine_item_uid = format!("{}.{}", book_uid, i);
+        let page_title = format!("p. {}", page);
+        let content_html = page_html(book_uid, &resource_path, &page_title, text);
+        let content_plain = compact_rich_text(text);
+
+        diesel::insert_into(book_spine_items::table)
+            .values(&NewBookSpineItem {
+                book_id,
+                book_uid,
+                spine_item_uid: &spine_item_uid,
+                spine_index: i as i32,
+                resource_path: &resource_path,
+                title: Some(&page_title),
+                language: if language.is_empty() { None } else { Some(&language) },
+                content_html: Some(&content_html),
+                content_plain: Some(&content_plain),
+            })
+            .execute(db_conn)?;
+    }
+
+    tracing::info!("Successfully imported page images: {} pages", pages.len());
+    Ok(())
+}
+
+/// The page images of a folder, in natural file name order (`page-2.png`
+/// before `page-10.png`).
+pub fn list_page_images(folder: &Path) -> Result<Vec<PathBuf>> {
+    let mut images: Vec<PathBuf> = fs::read_dir(folder)
+        .map_err(|e| anyhow!("Failed to read folder {}: {}", folder.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && is_page_image(path))
+        .collect();
+    images.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    Ok(images)
+}
+
+pub fn is_page_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| PAGE_IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+fn image_mime_type(file_name: &str) -> &'static str {
+    match Path::new(file_name).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("tif") | Some("tiff") => "image/tiff",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The page image with the recognized text below it. The web view doesn't
+/// show TIFF images, so for those the text is expanded.
+fn page_html(book_uid: &str, resource_path: &str, page_title: &str, text: &str) -> String {
+    let src = format!("/book_resources/{}/{}", book_uid, resource_path);
+    let mut html = format!(
+        r#"<div class="page-image"><img src="{}" alt="{}"></div>"#,
+        html_escape::encode_double_quoted_attribute(&src),
+        html_escape::encode_double_quoted_attribute(page_title),
+    );
+    if !text.trim().is_empty() {
+        let is_tiff = image_mime_type(resource_path) == "image/tiff";
+        let paragraphs: Vec<String> = text
+            .split("\n\n")
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| format!("<p>{}</p>", html_escape::encode_text(p.trim()).replace('\n', "<br>")))
+            .collect();
+        html.push_str(&format!(
+            r#"<details class="ocr-text"{}><summary>Recognized text</summary>{}</details>"#,
+            if is_tiff { " open" } else { "" },
+            paragraphs.join(""),
+        ));
+    }
+    html
+}
+
+/// Compare file names with their numbers by value.
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |it: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = it.peek().copied().filter(char::is_ascii_digit) {
+                        digits.push(c);
+                        it.next();
+                    }
+                    digits
+                };
+                let (x, y) = (take_number(&mut a), take_number(&mut b));
+                let (x_trimmed, y_trimmed) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let ord = x_trimmed.len().cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed))
+                    .then_with(|| x.len().cmp(&y.len()));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["page-10.png", "page-2.png", "page-1.png", "page-02a.png", "cover.png"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["cover.png", "page-1.png", "page-2.png", "page-02a.png", "page-10.png"]);
+    }
+
+    #[test]
+    fn test_page_html() {
+        let html = page_html("scan", "images/p1.png", "p. 1", "Evaṃ me <sutaṃ>\nekaṃ\n\nsamayaṃ");
+        assert!(html.contains(r#"<img src="/book_resources/scan/images/p1.png" alt="p. 1">"#), "{}", html);
+        assert!(html.contains("<p>Evaṃ me &lt;sutaṃ&gt;<br>ekaṃ</p><p>samayaṃ</p>"), "{}", html);
+        assert!(html.contains(r#"<details class="ocr-text"><summary>"#), "{}", html);
+        assert!(page_html("scan", "images/p1.tif", "p. 1", "text").contains("<details class=\"ocr-text\" open>"));
+        assert!(!page_html("scan", "images/p1.png", "p. 1", "").contains("details"));
+    }
+}
@@ -0,0 +1,714 @@
+//! Integrity checks of the installed databases and fulltext indexes, and
+//! targeted repairs.
+//!
+//! A corrupt or half-extracted asset otherwise shows up as a confusing search
+//! failure. `verify_installation()` checks:
+//!
+//! - the databases: `PRAGMA quick_check` (or the slower `integrity_check`),
+//!   the applied migrations against the ones of this app, the DPD tables, and
+//!   the `db_version` against the app version;
+//! - the fulltext indexes: every language of the databases has one, its
+//!   segments can be read (and their checksums match, in a full check), and
+//!   its document count matches the database rows;
+//! - the release archives in a folder, e.g. an offline copy on a USB stick,
+//!   against the SHA-256 checksums of the release manifest which the
+//!   bootstrap writes next to them (`write_release_manifest()`).
+//!
+//! A failed check suggests a `Repair`: rebuilding one language index, or
+//! extracting one release archive again. An archive repair replaces database
+//! files, so the app applies it on the next start before opening the
+//! databases (`stage_archive_repair()`, `apply_pending_repairs()`), while
+//! the CLI, with the app closed, applies it right away. See
+//! `docs/integrity.md`.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use bzip2::read::BzDecoder;
+use diesel::migration::MigrationSource;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Text};
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_delta::{read_db_version, sha256_file};
+use crate::downloader::{self, DownloadSettings};
+use crate::db::{DatabaseHandle, APPDATA_MIGRATIONS, DICTIONARIES_MIGRATIONS};
+use crate::logger::{error, info, warn};
+use crate::search::indexer;
+use crate::update_checker::{get_app_version, is_local_db_obsolete};
+use crate::move_folder_contents;
+
+/// Written by the bootstrap next to the release archives, and kept in the app
+/// assets from the last download.
+pub const RELEASE_MANIFEST_FILE: &str = "release-manifest.json";
+
+/// Folder of the app assets where the archives of a repair wait for the next start.
+pub const PENDING_REPAIRS_DIR: &str = "repair-pending";
+
+/// The tables the DPD lookups need.
+const DPD_TABLES: [&str; 5] = ["dpd_headwords", "dpd_roots", "lookup", "inflection_templates", "db_info"];
+
+/// Databases of the app assets, with the release archive they come from.
+const DATABASES: [(&str, &str); 3] = [
+    ("appdata.sqlite3", "appdata.tar.bz2"),
+    ("dictionaries.sqlite3", "dictionaries.tar.bz2"),
+    ("dpd.sqlite3", "dpd.tar.bz2"),
+];
+
+/// Archives which hold the user's own rows along with the release data.
+const USER_DATA_ARCHIVES: [&str; 2] = ["appdata.tar.bz2", "dictionaries.tar.bz2"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveChecksum {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseManifest {
+    pub db_version: String,
+    pub archives: Vec<ArchiveChecksum>,
+}
+
+impl ReleaseManifest {
+    pub fn get(&self, name: &str) -> Option<&ArchiveChecksum> {
+        self.archives.iter().find(|a| a.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexKind {
+    Suttas,
+    DictWords,
+    Library,
+}
+
+impl IndexKind {
+    pub const ALL: [IndexKind; 3] = [IndexKind::Suttas, IndexKind::DictWords, IndexKind::Library];
+
+    /// Folder of the index in `index/`.
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            IndexKind::Suttas => "suttas",
+            IndexKind::DictWords => "dict_words",
+            IndexKind::Library => "library",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Repair {
+    /// Extract a release archive again, replacing the files it holds.
+    ExtractArchive { archive: String },
+    /// Rebuild the fulltext index of one language.
+    RebuildIndex { index: IndexKind, lang: String },
+}
+
+impl Repair {
+    pub fn describe(&self) -> String {
+        match self {
+            Repair::ExtractArchive { archive } => format!("extract {} again", archive),
+            Repair::RebuildIndex { index, lang } => format!("rebuild the {} index for '{}'", index.dir_name(), lang),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityCheck {
+    /// What was checked, e.g. `dpd.sqlite3` or `index/suttas/pli`.
+    pub target: String,
+    pub status: CheckStatus,
+    pub message: String,
+    pub repair: Option<Repair>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub checks: Vec<IntegrityCheck>,
+}
+
+impl IntegrityReport {
+    fn push(&mut self, target: &str, status: CheckStatus, message: impl Into<String>, repair: Option<Repair>) {
+        self.checks.push(IntegrityCheck { target: target.to_string(), status, message: message.into(), repair });
+    }
+
+    fn ok(&mut self, target: &str, message: impl Into<String>) {
+        self.push(target, CheckStatus::Ok, message, None);
+    }
+
+    /// No check failed. Warnings don't count.
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Failed)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &IntegrityCheck> {
+        self.checks.iter().filter(|c| c.status == CheckStatus::Failed)
+    }
+
+    /// The repairs the failed checks suggest, without duplicates, the archives first.
+    pub fn repairs(&self) -> Vec<Repair> {
+        let set: BTreeSet<Repair> = self.failed().filter_map(|c| c.repair.clone()).collect();
+        set.into_iter().collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    /// `PRAGMA integrity_check` and the index checksums, instead of the quick checks.
+    pub full: bool,
+    /// A folder of release archives with their manifest to check as well.
+    pub archives_dir: Option<PathBuf>,
+}
+
+#[derive(QueryableByName)]
+struct TextRow {
+    #[diesel(sql_type = Text)]
+    r: String,
+}
+
+fn sqlite_url(path: &Path) -> String {
+    format!("sqlite://{}", path.display())
+}
+
+/// Write the release manifest with the checksums of the `.tar.bz2` archives in
+/// `release_databases_dir`.
+pub fn write_release_manifest(release_databases_dir: &Path, db_version: &str) -> Result<ReleaseManifest> {
+    let mut archives = Vec::new();
+    for entry in fs::read_dir(release_databases_dir)
+        .with_context(|| format!("Failed to read {}", release_databases_dir.display()))?
+    {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+        if path.is_file() && name.ends_with(".tar.bz2") {
+            archives.push(ArchiveChecksum {
+                name: name.to_string(),
+                size: fs::metadata(&path)?.len(),
+                sha256: sha256_file(&path)?,
+            });
+        }
+    }
+    archives.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let manifest = ReleaseManifest { db_version: db_version.to_string(), archives };
+    let path = release_databases_dir.join(RELEASE_MANIFEST_FILE);
+    fs::write(&path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(manifest)
+}
+
+pub fn read_release_manifest(path: &Path) -> Result<ReleaseManifest> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Download the release manifest from the folder URL of the release
+/// archives, or from one of the download mirrors.
+pub fn fetch_release_manifest(base_url: &str) -> Result<ReleaseManifest> {
+    let url = format!("{}/{}", base_url.trim_end_matches('/'), RELEASE_MANIFEST_FILE);
+    let settings = DownloadSettings::load();
+    let client = downloader::http_client(&settings)?;
+    let mut last_error = anyhow!("No URL for {}", url);
+    for candidate in downloader::mirror_urls(&url, &settings.mirrors) {
+        match client.get(&candidate).send().and_then(|r| r.error_for_status()).and_then(|r| r.text()) {
+            Ok(text) => return serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", candidate)),
+            Err(e) => last_error = anyhow!(e).context(format!("Failed to download {}", candidate)),
+        }
+    }
+    Err(last_error)
+}
+
+/// Check the size and the SHA-256 checksum of an archive against the manifest.
+pub fn verify_archive(manifest: &ReleaseManifest, path: &Path) -> Result<()> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let expected = manifest.get(name)
+        .ok_or_else(|| anyhow!("{} is not in the release manifest", name))?;
+    let size = fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?.len();
+    if size != expected.size {
+        bail!("{} is {} bytes instead of {}, the file is incomplete or damaged", name, size, expected.size);
+    }
+    if !sha256_file(path)?.eq_ignore_ascii_case(&expected.sha256) {
+        bail!("The checksum of {} doesn't match, the file is damaged", name);
+    }
+    Ok(())
+}
+
+/// Check the databases, the indexes and optionally a folder of release
+/// archives in `assets_dir`. The databases are only read.
+pub fn verify_installation(assets_dir: &Path, options: &VerifyOptions) -> IntegrityReport {
+    let mut report = IntegrityReport::default();
+
+    let mut readable = HashSet::new();
+    for (file, archive) in DATABASES {
+        if check_database(&mut report, assets_dir, file, archive, options.full) {
+            readable.insert(file);
+        }
+    }
+
+    if readable.contains("appdata.sqlite3") {
+        match read_db_version(assets_dir) {
+            Ok(Some(db_version)) => {
+                let app_version = get_app_version();
+                if is_local_db_obsolete(&app_version, Some(&db_version)).is_some() {
+                    report.push("db_version", CheckStatus::Failed, format!(
+                        "The databases ({}) are not compatible with this version of the app ({}), download the current release",
+                        db_version, app_version
+                    ), None);
+                } else {
+                    report.ok("db_version", db_version);
+                }
+            }
+            Ok(None) => report.push("db_version", CheckStatus::Warning, "No db_version in appdata.sqlite3", None),
+            Err(e) => report.push("db_version", CheckStatus::Failed, format!("{:#}", e), None),
+        }
+    }
+
+    // The indexes are compared with the rows of the databases they are built from.
+    let source_db = |kind: IndexKind| match kind {
+        IndexKind::Suttas | IndexKind::Library => "appdata.sqlite3",
+        IndexKind::DictWords => "dictionaries.sqlite3",
+    };
+    for kind in IndexKind::ALL {
+        let db_file = source_db(kind);
+        if !readable.contains(db_file) {
+            continue;
+        }
+        if let Err(e) = check_index(&mut report, assets_dir, kind, &assets_dir.join(db_file), options.full) {
+            report.push(&format!("index/{}", kind.dir_name()), CheckStatus::Failed, format!("{:#}", e), None);
+        }
+    }
+
+    if let Some(dir) = &options.archives_dir {
+        check_archives(&mut report, dir);
+    }
+
+    report
+}
+
+/// Check one database, returns whether it can be read.
+fn check_database(report: &mut IntegrityReport, assets_dir: &Path, file: &str, archive: &str, full: bool) -> bool {
+    let path = assets_dir.join(file);
+    let repair = Some(Repair::ExtractArchive { archive: archive.to_string() });
+    match fs::metadata(&path) {
+        Ok(m) if m.len() == 0 => {
+            report.push(file, CheckStatus::Failed, "The database file is empty", repair);
+            return false;
+        }
+        Ok(_) => {}
+        Err(_) => {
+            report.push(file, CheckStatus::Failed, "The database file is missing", repair);
+            return false;
+        }
+    }
+
+    let mut db_conn = match SqliteConnection::establish(&sqlite_url(&path)) {
+        Ok(c) => c,
+        Err(e) => {
+            report.push(file, CheckStatus::Failed, format!("Can't open the database: {}", e), repair);
+            return false;
+        }
+    };
+
+    let pragma = if full { "integrity_check" } else { "quick_check" };
+    match sql_query(format!("SELECT {pragma} AS r FROM pragma_{pragma}()")).load::<TextRow>(&mut db_conn) {
+        Ok(rows) if rows.len() == 1 && rows[0].r == "ok" => report.ok(file, format!("{} ok", pragma)),
+        Ok(rows) => {
+            let problems: Vec<String> = rows.into_iter().take(5).map(|r| r.r).collect();
+            report.push(file, CheckStatus::Failed, format!("{}: {}", pragma, problems.join("; ")), repair);
+            return false;
+        }
+        Err(e) => {
+            report.push(file, CheckStatus::Failed, format!("Can't read the database: {}", e), repair);
+            return false;
+        }
+    }
+
+    let schema = match file {
+        "appdata.sqlite3" => check_migrations(&mut db_conn, &APPDATA_MIGRATIONS),
+        "dictionaries.sqlite3" => check_migrations(&mut db_conn, &DICTIONARIES_MIGRATIONS),
+        _ => check_tables(&mut db_conn, &DPD_TABLES),
+    };
+    let target = format!("{} schema", file);
+    match schema {
+        Ok(None) => report.ok(&target, "current"),
+        Ok(Some(warning)) => report.push(&target, CheckStatus::Warning, warning, None),
+        Err(e) => report.push(&target, CheckStatus::Failed, format!("{:#}", e), repair),
+    }
+    true
+}
+
+/// Compare the applied migrations with the ones of this app. Pending ones
+/// are a warning, they are run when the app starts.
+fn check_migrations<S: MigrationSource<Sqlite>>(db_conn: &mut SqliteConnection, source: &S) -> Result<Option<String>> {
+    let known: HashSet<String> = source.migrations()
+        .map_err(|e| anyhow!("{}", e))?
+        .iter()
+        .map(|m| m.name().version().to_string())
+        .collect();
+    let applied: HashSet<String> = sql_query("SELECT version AS r FROM __diesel_schema_migrations")
+        .load::<TextRow>(db_conn)
+        .context("The migrations table is missing")?
+        .into_iter()
+        .map(|r| r.r)
+        .collect();
+
+    let unknown = applied.difference(&known).count();
+    if unknown > 0 {
+        bail!("{} migrations are newer than this version of the app", unknown);
+    }
+    let pending = known.difference(&applied).count();
+    Ok((pending > 0).then(|| format!("{} migrations are applied when the app starts", pending)))
+}
+
+fn check_tables(db_conn: &mut SqliteConnection, tables: &[&str]) -> Result<Option<String>> {
+    let present: HashSet<String> = sql_query("SELECT name AS r FROM sqlite_master WHERE type = 'table'")
+        .load::<TextRow>(db_conn)?
+        .into_iter()
+        .map(|r| r.r)
+        .collect();
+    let missing: Vec<&str> = tables.iter().copied().filter(|t| !present.contains(*t)).collect();
+    if !missing.is_empty() {
+        bail!("Missing tables: {}", missing.join(", "));
+    }
+    Ok(None)
+}
+
+/// Languages of the database which `kind` indexes, with the number of rows
+/// to index.
+fn indexed_languages(db_conn: &mut SqliteConnection, kind: IndexKind) -> Result<Vec<(String, usize)>> {
+    #[derive(QueryableByName)]
+    struct LangCount {
+        #[diesel(sql_type = Text)]
+        lang: String,
+        #[diesel(sql_type = BigInt)]
+        n: i64,
+    }
+
+    let sql = match kind {
+        IndexKind::Suttas => "SELECT lower(language) AS lang, COUNT(*) AS n FROM suttas
+            WHERE language != '' AND content_plain IS NOT NULL AND content_plain != '' GROUP BY lower(language)",
+        IndexKind::DictWords => "SELECT lower(language) AS lang, COUNT(*) AS n FROM dict_words
+            WHERE language IS NOT NULL AND language != '' AND definition_plain IS NOT NULL AND definition_plain != ''
+            GROUP BY lower(language)",
+        IndexKind::Library => "SELECT lower(COALESCE(NULLIF(s.language, ''), NULLIF(b.language, ''), 'en')) AS lang, COUNT(*) AS n
+            FROM book_spine_items s JOIN books b ON b.id = s.book_id GROUP BY 1",
+    };
+    let rows: Vec<LangCount> = sql_query(sql).load(db_conn)?;
+    Ok(rows.into_iter().map(|r| (r.lang, r.n as usize)).collect())
+}
+
+/// Number of documents in a Tantivy index, after reading every segment's
+/// document store. A full check validates the file checksums too.
+fn read_index(dir: &Path, full: bool) -> Result<usize> {
+    let index = tantivy::Index::open_in_dir(dir)?;
+    if full {
+        let damaged = index.validate_checksum()?;
+        if !damaged.is_empty() {
+            let files: Vec<String> = damaged.iter().map(|p| p.display().to_string()).collect();
+            bail!("Damaged files: {}", files.join(", "));
+        }
+    }
+    let reader = index.reader_builder().reload_policy(tantivy::ReloadPolicy::Manual).try_into()?;
+    let searcher = reader.searcher();
+    for segment in searcher.segment_readers() {
+        if segment.max_doc() > 0 {
+            let store = segment.get_store_reader(1)?;
+            store.get::<tantivy::TantivyDocument>(0)?;
+            store.get::<tantivy::TantivyDocument>(segment.max_doc() - 1)?;
+        }
+    }
+    Ok(searcher.num_docs() as usize)
+}
+
+fn check_index(report: &mut IntegrityReport, assets_dir: &Path, kind: IndexKind, db_path: &Path, full: bool) -> Result<()> {
+    let mut db_conn = SqliteConnection::establish(&sqlite_url(db_path))?;
+    let kind_dir = assets_dir.join("index").join(kind.dir_name());
+
+    for (lang, rows) in indexed_languages(&mut db_conn, kind)? {
+        let target = format!("index/{}/{}", kind.dir_name(), lang);
+        let repair = Some(Repair::RebuildIndex { index: kind, lang: lang.clone() });
+        let dir = kind_dir.join(&lang);
+        if !dir.join("meta.json").is_file() {
+            report.push(&target, CheckStatus::Failed, "The index is missing", repair);
+            continue;
+        }
+        // The dict index has the DPD bold definitions as well, and the library
+        // index skips the empty chapters.
+        match read_index(&dir, full) {
+            Ok(n) => {
+                let matches = match kind {
+                    IndexKind::Suttas => n == rows,
+                    IndexKind::DictWords => n >= rows,
+                    IndexKind::Library => n > 0 || rows == 0,
+                };
+                if matches {
+                    report.ok(&target, format!("{} documents", n));
+                } else {
+                    report.push(&target, CheckStatus::Failed, format!(
+                        "The index has {} documents for {} rows in the database", n, rows
+                    ), repair);
+                }
+            }
+            Err(e) => report.push(&target, CheckStatus::Failed, format!("Can't read the index: {:#}", e), repair),
+        }
+    }
+    Ok(())
+}
+
+fn check_archives(report: &mut IntegrityReport, dir: &Path) {
+    let manifest = match read_release_manifest(&dir.join(RELEASE_MANIFEST_FILE)) {
+        Ok(m) => m,
+        Err(e) => {
+            report.push(RELEASE_MANIFEST_FILE, CheckStatus::Failed, format!("{:#}", e), None);
+            return;
+        }
+    };
+    for archive in &manifest.archives {
+        let path = dir.join(&archive.name);
+        if !path.is_file() {
+            report.push(&archive.name, CheckStatus::Warning, "Not in the folder", None);
+            continue;
+        }
+        match verify_archive(&manifest, &path) {
+            Ok(()) => report.ok(&archive.name, "checksum ok"),
+            Err(e) => report.push(&archive.name, CheckStatus::Failed, format!("{:#}", e), None),
+        }
+    }
+}
+
+/// Extract a `.tar.bz2` archive into `dest`.
+pub fn extract_archive(archive: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest)?;
+    let file = fs::File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    tar::Archive::new(BzDecoder::new(io::BufReader::new(file)))
+        .unpack(dest)
+        .with_context(|| format!("Failed to extract {}", archive.display()))
+}
+
+/// Where the archives for a repair come from.
+#[derive(Debug, Clone)]
+pub enum ArchiveSource {
+    /// A folder with the archives and the release manifest.
+    Dir(PathBuf),
+    /// The folder URL of a release, e.g. `https://github.com/<repo>/releases/download/<tag>`.
+    Url(String),
+}
+
+/// Find or download an archive of the release for a repair, and check it
+/// against the release manifest. Downloads go to `download_dir`.
+pub fn fetch_archive(source: &ArchiveSource, archive: &str, download_dir: &Path) -> Result<(PathBuf, ReleaseManifest)> {
+    let (path, manifest) = match source {
+        ArchiveSource::Dir(dir) => (dir.join(archive), read_release_manifest(&dir.join(RELEASE_MANIFEST_FILE))?),
+        ArchiveSource::Url(base_url) => {
+            let manifest = fetch_release_manifest(base_url)?;
+            fs::create_dir_all(download_dir)?;
+            let dest = download_dir.join(archive);
+            let url = format!("{}/{}", base_url.trim_end_matches('/'), archive);
+            info(&format!("Downloading {} for a repair", url));
+            downloader::download_file(&url, &dest)?;
+            (dest, manifest)
+        }
+    };
+    verify_archive(&manifest, &path)?;
+    Ok((path, manifest))
+}
+
+/// An archive must be from the release of the installed databases, when
+/// their version can be read.
+fn check_release_version(manifest: &ReleaseManifest, name: &str, assets_dir: &Path) -> Result<()> {
+    if let Some(installed) = read_db_version(assets_dir).ok().flatten()
+        && installed != manifest.db_version
+    {
+        bail!("{} is from the release {}, but the installed databases are {}", name, manifest.db_version, installed);
+    }
+    Ok(())
+}
+
+/// Extract a checked archive into `assets_dir`. The database files it
+/// replaces are kept with a `.damaged` suffix. Language archives are not
+/// repaired this way, they are downloaded again from the app.
+pub fn install_archive(archive: &Path, manifest: &ReleaseManifest, assets_dir: &Path) -> Result<()> {
+    let name = archive.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if name.starts_with("suttas_lang_") {
+        bail!("{} is imported into appdata.sqlite3, download the language again instead", name);
+    }
+    check_release_version(manifest, name, assets_dir)?;
+
+    let staging = tempfile::tempdir_in(assets_dir).context("Failed to create temp directory")?;
+    extract_archive(archive, staging.path())?;
+
+    for entry in fs::read_dir(staging.path())? {
+        let path = entry?.path();
+        let Some(file) = path.file_name().and_then(|n| n.to_str()) else { continue };
+        let target = assets_dir.join(file);
+        if path.is_file() && file.ends_with(".sqlite3") && target.exists() {
+            let damaged = assets_dir.join(format!("{}.damaged", file));
+            let _ = fs::remove_file(&damaged);
+            fs::rename(&target, &damaged)
+                .with_context(|| format!("Failed to move {} aside", target.display()))?;
+            warn(&format!("Kept the replaced {} as {}", file, damaged.display()));
+        } else if path.is_dir() && file == "index" && target.is_dir() {
+            fs::remove_dir_all(&target).with_context(|| format!("Failed to remove {}", target.display()))?;
+        }
+    }
+    move_folder_contents(staging.path(), assets_dir)
+        .with_context(|| format!("Failed to move the files of {} into the app assets", name))?;
+
+    if USER_DATA_ARCHIVES.contains(&name) {
+        warn(&format!("{} replaced a database with user data, restore it from a backup", name));
+    }
+    info(&format!("Installed {} from the release {}", name, manifest.db_version));
+    Ok(())
+}
+
+/// Rebuild the index of one language in `assets_dir`.
+pub fn rebuild_index(assets_dir: &Path, kind: IndexKind, lang: &str) -> Result<()> {
+    let db = |file: &str| DatabaseHandle::new(&sqlite_url(&assets_dir.join(file)));
+    let index_dir = assets_dir.join("index").join(kind.dir_name());
+    match kind {
+        IndexKind::Suttas => indexer::build_sutta_index(&db("appdata.sqlite3")?, &index_dir, lang),
+        IndexKind::Library => indexer::build_library_index(&db("appdata.sqlite3")?, &index_dir, lang),
+        IndexKind::DictWords => {
+            indexer::build_dict_index(&db("dictionaries.sqlite3")?, &index_dir, lang)?;
+            if lang == "pli" && assets_dir.join("dpd.sqlite3").is_file() {
+                indexer::append_bold_definitions_to_dict_index(&db("dpd.sqlite3")?, &index_dir, lang)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Apply a repair with the app closed: archives are fetched from `source`,
+/// downloads go to `download_dir`.
+pub fn apply_repair(assets_dir: &Path, repair: &Repair, source: Option<&ArchiveSource>, download_dir: &Path) -> Result<()> {
+    match repair {
+        Repair::RebuildIndex { index, lang } => rebuild_index(assets_dir, *index, lang),
+        Repair::ExtractArchive { archive } => {
+            let source = source.ok_or_else(|| anyhow!("{} is needed, give a folder or URL of the release", archive))?;
+            let (path, manifest) = fetch_archive(source, archive, download_dir)?;
+            let result = install_archive(&path, &manifest, assets_dir);
+            if matches!(source, ArchiveSource::Url(_)) {
+                let _ = fs::remove_file(&path);
+            }
+            result
+        }
+    }
+}
+
+/// Verify, apply the suggested repairs, and verify again. The archives go
+/// first, since they can leave indexes to rebuild. Returns the final report
+/// and the repairs which failed.
+pub fn repair_installation(
+    assets_dir: &Path,
+    options: &VerifyOptions,
+    source: Option<&ArchiveSource>,
+    download_dir: &Path,
+) -> (IntegrityReport, Vec<(Repair, String)>) {
+    let mut failed = Vec::new();
+    let mut attempted = HashSet::new();
+    for _ in 0..2 {
+        let report = verify_installation(assets_dir, options);
+        let repairs: Vec<Repair> = report.repairs().into_iter().filter(|r| !attempted.contains(r)).collect();
+        if repairs.is_empty() {
+            return (report, failed);
+        }
+        for repair in repairs {
+            info(&format!("Repair: {}", repair.describe()));
+            if let Err(e) = apply_repair(assets_dir, &repair, source, download_dir) {
+                error(&format!("Repair failed, {}: {:#}", repair.describe(), e));
+                failed.push((repair.clone(), format!("{:#}", e)));
+            }
+            attempted.insert(repair);
+        }
+    }
+    (verify_installation(assets_dir, options), failed)
+}
+
+/// Put a checked archive into the pending repairs of `assets_dir`, to be
+/// installed on the next start, with its manifest.
+pub fn stage_archive_repair(archive: &Path, manifest: &ReleaseManifest, assets_dir: &Path) -> Result<()> {
+    verify_archive(manifest, archive)?;
+    let name = archive.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    check_release_version(manifest, name, assets_dir)?;
+    let pending = assets_dir.join(PENDING_REPAIRS_DIR);
+    fs::create_dir_all(&pending)?;
+    fs::copy(archive, pending.join(name))
+        .with_context(|| format!("Failed to copy {} to {}", archive.display(), pending.display()))?;
+    fs::write(pending.join(RELEASE_MANIFEST_FILE), serde_json::to_string_pretty(manifest)?)?;
+    info(&format!("Staged {} to be installed on the next start", archive.display()));
+    Ok(())
+}
+
+/// Install the archives staged by `stage_archive_repair()`, before the
+/// databases are opened. Returns the installed archives.
+pub fn apply_pending_repairs(assets_dir: &Path) -> Vec<String> {
+    let pending = assets_dir.join(PENDING_REPAIRS_DIR);
+    let mut installed = Vec::new();
+    if !pending.is_dir() {
+        return installed;
+    }
+
+    match read_release_manifest(&pending.join(RELEASE_MANIFEST_FILE)) {
+        Ok(manifest) => {
+            for archive in &manifest.archives {
+                let path = pending.join(&archive.name);
+                if !path.is_file() {
+                    continue;
+                }
+                match verify_archive(&manifest, &path).and_then(|_| install_archive(&path, &manifest, assets_dir)) {
+                    Ok(()) => installed.push(archive.name.clone()),
+                    Err(e) => error(&format!("Failed to install {}: {:#}", archive.name, e)),
+                }
+            }
+        }
+        Err(e) => error(&format!("Failed to read the pending repairs: {:#}", e)),
+    }
+
+    if let Err(e) = fs::remove_dir_all(&pending) {
+        warn(&format!("Failed to remove {}: {}", pending.display(), e));
+    }
+    installed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repairs_are_deduplicated_archives_first() {
+        let mut report = IntegrityReport::default();
+        let rebuild = Repair::RebuildIndex { index: IndexKind::Suttas, lang: "pli".to_string() };
+        let extract = Repair::ExtractArchive { archive: "dpd.tar.bz2".to_string() };
+        report.push("index/suttas/pli", CheckStatus::Failed, "missing", Some(rebuild.clone()));
+        report.push("dpd.sqlite3", CheckStatus::Failed, "damaged", Some(extract.clone()));
+        report.push("dpd.sqlite3 schema", CheckStatus::Failed, "missing tables", Some(extract.clone()));
+        report.push("index/library/en", CheckStatus::Warning, "", Some(Repair::RebuildIndex {
+            index: IndexKind::Library, lang: "en".to_string(),
+        }));
+        assert!(!report.is_ok());
+        assert_eq!(report.repairs(), vec![extract, rebuild]);
+    }
+
+    #[test]
+    fn test_repair_json() {
+        let repair = Repair::RebuildIndex { index: IndexKind::DictWords, lang: "pli".to_string() };
+        let json = serde_json::to_string(&repair).unwrap();
+        assert_eq!(json, r#"{"kind":"rebuild_index","index":"dict_words","lang":"pli"}"#);
+        assert_eq!(serde_json::from_str::<Repair>(&json).unwrap(), repair);
+    }
+}
@@ -14,6 +14,8 @@ pub mod dictionary_manager_core;
 pub mod dict_index_reconcile;
 pub mod pali_stemmer;
 pub mod pali_sort;
+pub mod pali_phonetic;
+pub mod headword_index;
 pub mod logger;
 pub mod theme_colors;
 pub mod app_settings;
@@ -24,7 +26,19 @@ pub mod anki_sample_data;
 pub mod anki_export;
 pub mod epub_import;
 pub mod pdf_import;
+pub mod ocr;
+pub mod image_import;
 pub mod html_import;
+pub mod document_import;
+pub mod markdown_import;
+pub mod docx_import;
+pub mod odt_import;
+pub mod fb2_import;
+pub mod sutta_ref_links;
+pub mod reading_plans;
+pub mod saved_searches;
+pub mod search_history;
+pub mod did_you_mean;
 pub mod document_metadata;
 pub mod pts_reference_search;
 pub mod update_checker;
@@ -33,7 +47,15 @@ pub mod snowball;
 pub mod search;
 pub mod waveform;
 pub mod audio;
+pub mod prosody;
 pub mod global_hotkeys;
+pub mod sync;
+pub mod backup;
+pub mod asset_delta;
+pub mod integrity;
+pub mod downloader;
+#[cfg(feature = "api-server")]
+pub mod api_server;
 
 use std::env;
 use std::io::{self, Read, Write};
@@ -401,6 +423,8 @@ pub struct AppGlobals {
 pub struct AppGlobalPaths {
     pub simsapa_dir: PathBuf,
     pub simsapa_api_port_path: PathBuf,
+    /// Token for the API's user data routes, see `api_server::auth`.
+    pub simsapa_api_token_path: PathBuf,
     pub download_temp_folder: PathBuf,
     pub extract_temp_folder: PathBuf,
     pub app_assets_dir: PathBuf,
@@ -428,6 +452,8 @@ pub struct AppGlobalPaths {
     pub auto_start_download_marker: PathBuf,
     pub delete_files_for_upgrade_marker: PathBuf,
     pub download_select_sanskrit_bundle_marker: PathBuf,
+    // Marker file for running the saved searches after new content is imported
+    pub run_saved_searches_marker: PathBuf,
 }
 
 impl AppGlobals {
@@ -444,7 +470,11 @@ impl AppGlobals {
             4848
         };
 
-        let api_url = format!("http://localhost:{}", api_port);
+        // API_URL overrides the base url written into pages, for a headless
+        // server reached through a public host name (simsapa_cli serve --public-url).
+        let api_url = env::var("API_URL")
+            .map(|s| s.trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| format!("http://localhost:{}", api_port));
 
         save_to_file(format!("{}", api_port).as_bytes(), paths.simsapa_api_port_path.to_str().expect("Path error"));
 
@@ -539,6 +569,7 @@ impl AppGlobalPaths {
         };
 
         let simsapa_api_port_path = simsapa_dir.join("api-port.txt");
+        let simsapa_api_token_path = simsapa_dir.join("api-token.txt");
 
         let download_temp_folder = simsapa_dir.join("temp-download");
         let extract_temp_folder = download_temp_folder.join("temp-extract");
@@ -568,10 +599,12 @@ impl AppGlobalPaths {
         let auto_start_download_marker = app_assets_dir.join("auto_start_download.txt");
         let delete_files_for_upgrade_marker = app_assets_dir.join("delete_files_for_upgrade.txt");
         let download_select_sanskrit_bundle_marker = app_assets_dir.join("download_select_sanskrit_bundle.txt");
+        let run_saved_searches_marker = app_assets_dir.join("run_saved_searches.txt");
 
         AppGlobalPaths {
             simsapa_dir,
             simsapa_api_port_path,
+            simsapa_api_token_path,
             download_temp_folder,
             extract_temp_folder,
             app_assets_dir,
@@ -597,6 +630,7 @@ impl AppGlobalPaths {
             auto_start_download_marker,
             delete_files_for_upgrade_marker,
             download_select_sanskrit_bundle_marker,
+            run_saved_searches_marker,
         }
     }
 }
@@ -869,6 +903,26 @@ pub extern "C" fn ensure_no_empty_db_files() {
     }
 }
 
+/// Install the release archives staged by an integrity repair, before the
+/// databases are opened. See `integrity`.
+#[unsafe(no_mangle)]
+pub extern "C" fn apply_pending_asset_repairs() {
+    let g = get_app_globals();
+    for archive in integrity::apply_pending_repairs(&g.paths.app_assets_dir) {
+        info(&format!("Repaired the app assets from {}", archive));
+    }
+}
+
+/// Apply the delta updates waiting in the app assets, before the databases are
+/// opened. See `asset_delta`.
+#[unsafe(no_mangle)]
+pub extern "C" fn apply_pending_delta_updates() {
+    let g = get_app_globals();
+    for report in asset_delta::apply_pending_deltas(&g.paths.app_assets_dir) {
+        info(&format!("Updated the databases from {} to {}", report.from_version, report.to_version));
+    }
+}
+
 /// Check for the delete_files_for_upgrade.txt marker file and delete database files if found.
 ///
 /// This is called during app startup. If the marker file exists, it deletes:
@@ -1068,6 +1122,41 @@ pub extern "C" fn import_user_data_after_upgrade() {
     }
 }
 
+/// Sync the user data through the shared folder of the settings, if one is
+/// set. Called on startup and on exit.
+#[unsafe(no_mangle)]
+pub extern "C" fn sync_user_data_folder() {
+    let Some(app_data) = try_get_app_data() else {
+        error("sync_user_data_folder: APP_DATA is not initialized");
+        return;
+    };
+    let Some(folder) = app_data.get_sync_folder() else {
+        return;
+    };
+    if let Err(e) = crate::sync::sync_folder(&app_data.dbm, Path::new(&folder)) {
+        error(&format!("Failed to sync the user data folder: {:#}", e));
+    }
+}
+
+/// Start the thread of the automatic backups, which checks every hour
+/// whether a backup is due by the settings.
+#[unsafe(no_mangle)]
+pub extern "C" fn start_backup_scheduler() {
+    std::thread::spawn(|| {
+        loop {
+            match try_get_app_data() {
+                Some(app_data) => {
+                    if let Err(e) = crate::backup::run_scheduled_backup(app_data) {
+                        error(&format!("Automatic backup failed: {:#}", e));
+                    }
+                }
+                None => error("start_backup_scheduler: APP_DATA is not initialized"),
+            }
+            std::thread::sleep(std::time::Duration::from_secs(60 * 60));
+        }
+    });
+}
+
 pub fn move_folder_contents<P: AsRef<Path>>(src: P, dest: P) -> io::Result<()> {
     let src_path = src.as_ref();
     let dest_path = dest.as_ref();
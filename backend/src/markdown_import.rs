@@ -0,0 +1,210 @@
+use anyhow::{anyhow, bail, Result};
+use diesel::prelude::*;
+use markdown::{to_html_with_options, Options};
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::document_import::{import_converted_document_to_db, ConvertedDocument};
+use crate::helpers::strip_html;
+use crate::image_import::natural_cmp;
+
+pub const MARKDOWN_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+
+/// Import a Markdown file, or a folder of Markdown files, into the database
+///
+/// A folder is a book with a file per chapter, in natural file name order
+/// (sub-folders included). The title, author and language are read from the
+/// YAML front matter of the (first) file, and the images the files link to
+/// are stored as resources.
+///
+/// # Arguments
+/// * `db_conn` - Mutable reference to SQLite database connection
+/// * `path` - Path to the Markdown file or folder to import
+/// * `book_uid` - Unique identifier for this book
+/// * `custom_title` - Optional custom title to override the front matter
+/// * `custom_author` - Optional custom author to override the front matter
+/// * `custom_language` - Optional custom language to override the front matter
+/// * `is_user_added` - Whether the book was added by the user
+///
+/// # Returns
+/// * `Result<()>` - Ok if successful, Err with details if failed
+#[allow(clippy::too_many_arguments)]
+pub fn import_markdown_to_db(
+    db_conn: &mut SqliteConnection,
+    path: &Path,
+    book_uid: &str,
+    custom_title: Option<&str>,
+    custom_author: Option<&str>,
+    custom_language: Option<&str>,
+    is_user_added: bool,
+) -> Result<()> {
+    tracing::info!("Importing Markdown from {:?} with UID: {}", path, book_uid);
+    let doc = convert_markdown(path)?;
+    import_converted_document_to_db(db_conn, doc, "markdown", path, book_uid, custom_title, custom_author, custom_language, is_user_added)
+}
+
+/// Convert a Markdown file or folder to HTML.
+pub fn convert_markdown(path: &Path) -> Result<ConvertedDocument> {
+    let is_folder = path.is_dir();
+    let (root, files) = if is_folder {
+        (path.to_path_buf(), list_markdown_files(path)?)
+    } else {
+        (path.parent().map(Path::to_path_buf).unwrap_or_default(), vec![path.to_path_buf()])
+    };
+    if files.is_empty() {
+        bail!("No Markdown files in {}", path.display());
+    }
+
+    lazy_static::lazy_static! {
+        static ref RE_HEADING: Regex = Regex::new(r"<h[1-6][ >]").unwrap();
+        static ref RE_H1: Regex = Regex::new(r"(?s)<h1>(.*?)</h1>").unwrap();
+    }
+
+    let mut doc = ConvertedDocument::default();
+    let mut seen_resources = HashSet::new();
+    for file in &files {
+        let text = fs::read_to_string(file)
+            .map_err(|e| anyhow!("Failed to read {}: {}", file.display(), e))?;
+        let (front_matter, body) = split_front_matter(&text);
+        for (key, value) in front_matter {
+            match key.as_str() {
+                "title" => { doc.title.get_or_insert(value); }
+                "author" | "authors" | "speaker" => { doc.author.get_or_insert(value); }
+                "lang" | "language" => { doc.language.get_or_insert(value); }
+                _ => {}
+            }
+        }
+
+        let mut html = to_html_with_options(body, &Options::gfm())
+            .map_err(|e| anyhow!("Failed to parse Markdown {}: {}", file.display(), e))?;
+
+        // A chapter file without headings starts with its file name as the heading
+        if is_folder && !RE_HEADING.is_match(&html) {
+            let stem = file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            html = format!("<h1>{}</h1>\n{}", html_escape::encode_text(&stem), html);
+        }
+
+        if doc.title.is_none() && !is_folder
+            && let Some(caps) = RE_H1.captures(&html) {
+                let heading = strip_html(&caps[1]);
+                if !heading.trim().is_empty() {
+                    doc.title = Some(heading.trim().to_string());
+                }
+            }
+
+        let base_dir = file.parent().unwrap_or(&root);
+        let html = collect_images(&html, base_dir, &root, &mut doc.resources, &mut seen_resources);
+        doc.body_html.push_str(&html);
+        doc.body_html.push('\n');
+    }
+
+    Ok(doc)
+}
+
+/// The Markdown files of a folder and its sub-folders, in natural path order.
+pub fn list_markdown_files(folder: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(folder)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_markdown_file(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect();
+    files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    Ok(files)
+}
+
+pub fn is_markdown_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| MARKDOWN_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Split the YAML front matter (`---` fenced, at the start) from the text.
+/// Only the `key: value` lines are read.
+fn split_front_matter(text: &str) -> (Vec<(String, String)>, &str) {
+    let text = text.trim_start_matches('\u{feff}');
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (Vec::new(), text);
+    };
+    let Some(end) = rest.find("\n---") else {
+        return (Vec::new(), text);
+    };
+    let fields = rest[..end]
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let value = value.trim().trim_matches('"').trim_matches('\'').trim();
+            if value.is_empty() {
+                None
+            } else {
+                Some((key.trim().to_lowercase(), value.to_string()))
+            }
+        })
+        .collect();
+    let body = rest[end + 4..].split_once('\n').map_or("", |(_, body)| body);
+    (fields, body)
+}
+
+/// Read the local images of the HTML into `resources`, and link them by
+/// their path in the book folder.
+fn collect_images(
+    html: &str,
+    base_dir: &Path,
+    root: &Path,
+    resources: &mut Vec<(String, Vec<u8>)>,
+    seen_resources: &mut HashSet<String>,
+) -> String {
+    lazy_static::lazy_static! {
+        static ref RE_IMG_SRC: Regex = Regex::new(r#"(<img [^>]*?src=")([^"]+)(")"#).unwrap();
+    }
+
+    RE_IMG_SRC
+        .replace_all(html, |caps: &regex::Captures| {
+            let src = html_escape::decode_html_entities(&caps[2]).replace("%20", " ");
+            if src.contains("://") || src.starts_with('/') || src.starts_with("data:") {
+                return caps[0].to_string();
+            }
+            let image_path = base_dir.join(&src);
+            let Ok(relative) = image_path.strip_prefix(root) else {
+                return caps[0].to_string();
+            };
+            let resource_path = relative.to_string_lossy().replace('\\', "/");
+            if !seen_resources.contains(&resource_path) {
+                match fs::read(&image_path) {
+                    Ok(data) => {
+                        resources.push((resource_path.clone(), data));
+                        seen_resources.insert(resource_path.clone());
+                    }
+                    Err(e) => {
+                        tracing::warn!("Missing image {:?}: {}", image_path, e);
+                        return caps[0].to_string();
+                    }
+                }
+            }
+            format!("{}{}{}", &caps[1], html_escape::encode_double_quoted_attribute(&resource_path), &caps[3])
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_front_matter() {
+        let text = "---\ntitle: \"Kindness\"\nauthor: Ajahn Test\ntags:\n---\n# Talk\n\nText";
+        let (fields, body) = split_front_matter(text);
+        assert_eq!(fields, vec![
+            ("title".to_string(), "Kindness".to_string()),
+            ("author".to_string(), "Ajahn Test".to_string()),
+        ]);
+        assert_eq!(body, "# Talk\n\nText");
+
+        let (fields, body) = split_front_matter("# No front matter\n---\n");
+        assert!(fields.is_empty());
+        assert_eq!(body, "# No front matter\n---\n");
+    }
+}
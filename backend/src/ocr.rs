@@ -0,0 +1,445 @@
+//! Offline OCR of scanned pages for the library imports.
+//!
+//! Many old Pāli-text and commentary PDFs are image scans without a text
+//! layer. The recognition runs the Tesseract command of the system
+//! (`tesseract`, and `pdftoppm` of Poppler to render a PDF page as an image),
+//! so nothing is bundled and the import works as before when they are not
+//! installed. Tesseract's TSV output gives the confidence of each word.
+//!
+//! Tesseract has no Pāli model, and with the English or Latin models the
+//! diacritics come out wrong (`ä`, `à`, or dropped). `PaliSpellingCorrector`
+//! repairs a word when, with the diacritics folded, it matches exactly one
+//! word form of the DPD `lookup` table. See `docs/ocr.md`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Context, Result};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_OCR_LANGUAGES: &str = "eng";
+
+/// A PDF page with less extracted text than this is treated as a scan.
+pub const MIN_PAGE_TEXT_CHARS: usize = 20;
+
+/// The key of the OCR results in `books.metadata_json`.
+pub const OCR_METADATA_KEY: &str = "ocr";
+
+/// Words below this confidence are checked against the Pāli word list even
+/// when they have no diacritics.
+const LOW_WORD_CONFIDENCE: f32 = 85.0;
+
+/// Shorter words are not corrected, too many of them are ambiguous.
+const MIN_CORRECTED_WORD_CHARS: usize = 3;
+
+/// The system OCR commands.
+#[derive(Debug, Clone)]
+pub struct OcrEngine {
+    tesseract: PathBuf,
+    pdftoppm: PathBuf,
+    languages: String,
+}
+
+impl OcrEngine {
+    /// `tesseract` and `pdftoppm` from the PATH, with Tesseract language
+    /// codes like `eng` or `eng+lat`.
+    pub fn new(languages: &str) -> Self {
+        Self::with_commands(Path::new("tesseract"), Path::new("pdftoppm"), languages)
+    }
+
+    pub fn with_commands(tesseract: &Path, pdftoppm: &Path, languages: &str) -> Self {
+        let languages = languages.trim();
+        OcrEngine {
+            tesseract: tesseract.to_path_buf(),
+            pdftoppm: pdftoppm.to_path_buf(),
+            languages: if languages.is_empty() { DEFAULT_OCR_LANGUAGES.to_string() } else { languages.to_string() },
+        }
+    }
+
+    pub fn languages(&self) -> &str {
+        &self.languages
+    }
+
+    /// Whether the tesseract command runs.
+    pub fn is_available(&self) -> bool {
+        Command::new(&self.tesseract)
+            .arg("--version")
+            .output()
+            .map(|out| out.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Recognize the text of an image file (PNG, TIFF, JPEG).
+    pub fn recognize_image(&self, image: &Path) -> Result<OcrPage> {
+        let out = Command::new(&self.tesseract)
+            .arg(image)
+            .arg("stdout")
+            .args(["-l", &self.languages])
+            .arg("tsv")
+            .output()
+            .with_context(|| format!("Failed to run {}", self.tesseract.display()))?;
+        if !out.status.success() {
+            bail!("tesseract failed on {}: {}", image.display(), String::from_utf8_lossy(&out.stderr).trim());
+        }
+        Ok(OcrPage::from_tsv(&String::from_utf8_lossy(&out.stdout)))
+    }
+
+    /// Render one page of a PDF (numbered from 1) as a grayscale PNG in `out_dir`.
+    pub fn render_pdf_page(&self, pdf_path: &Path, page: u32, out_dir: &Path) -> Result<PathBuf> {
+        let base = out_dir.join(format!("page-{}", page));
+        let out = Command::new(&self.pdftoppm)
+            .args(["-f", &page.to_string(), "-l", &page.to_string()])
+            .args(["-r", "300", "-gray", "-png", "-singlefile"])
+            .arg(pdf_path)
+            .arg(&base)
+            .output()
+            .with_context(|| format!("Failed to run {}", self.pdftoppm.display()))?;
+        let image = base.with_extension("png");
+        if !out.status.success() || !image.exists() {
+            bail!("pdftoppm failed on page {}: {}", page, String::from_utf8_lossy(&out.stderr).trim());
+        }
+        Ok(image)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrWord {
+    pub text: String,
+    /// 0-100, as given by Tesseract.
+    pub confidence: f32,
+}
+
+/// The recognized words of a page, by line. An empty line separates paragraphs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OcrPage {
+    pub lines: Vec<Vec<OcrWord>>,
+}
+
+impl OcrPage {
+    /// Parse the TSV output of Tesseract. The word rows (level 5) are grouped
+    /// into lines by their block, paragraph and line numbers.
+    pub fn from_tsv(tsv: &str) -> OcrPage {
+        let mut page = OcrPage::default();
+        let mut current_line: Option<(u32, u32, u32)> = None;
+        let mut current_par: Option<(u32, u32)> = None;
+
+        for row in tsv.lines().skip(1) {
+            let cols: Vec<&str> = row.split('\t').collect();
+            if cols.len() < 12 || cols[0] != "5" {
+                continue;
+            }
+            let text = cols[11].trim();
+            let confidence = cols[10].trim().parse::<f32>().unwrap_or(-1.0);
+            if text.is_empty() || confidence < 0.0 {
+                continue;
+            }
+            let num = |i: usize| cols[i].trim().parse::<u32>().unwrap_or(0);
+            let (block, par, line) = (num(2), num(3), num(4));
+
+            if current_line != Some((block, par, line)) {
+                if current_par.is_some() && current_par != Some((block, par)) {
+                    page.lines.push(Vec::new());
+                }
+                page.lines.push(Vec::new());
+                current_line = Some((block, par, line));
+                current_par = Some((block, par));
+            }
+            if let Some(words) = page.lines.last_mut() {
+                words.push(OcrWord { text: text.to_string(), confidence });
+            }
+        }
+        page
+    }
+
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|words| words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.lines.iter().map(|words| words.len()).sum()
+    }
+
+    /// The mean word confidence, None without words.
+    pub fn confidence(&self) -> Option<f32> {
+        let count = self.word_count();
+        if count == 0 {
+            return None;
+        }
+        let sum: f32 = self.lines.iter().flatten().map(|w| w.confidence).sum();
+        Some(sum / count as f32)
+    }
+}
+
+/// Fold a lowercase word for matching OCR output against the Pāli word list:
+/// the Pāli letters and the accented letters which OCR gives for them become
+/// their base letter.
+pub fn ocr_fold(word: &str) -> String {
+    word.chars()
+        .map(|c| match c {
+            'ā' | 'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+            'ī' | 'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ū' | 'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ē' | 'é' | 'è' | 'ê' | 'ë' => 'e',
+            'ō' | 'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+            'ṃ' | 'ṁ' | 'ŋ' => 'm',
+            'ṅ' | 'ñ' | 'ṇ' | 'ń' => 'n',
+            'ṭ' => 't',
+            'ḍ' => 'd',
+            'ḷ' | 'ḹ' => 'l',
+            'ḥ' => 'h',
+            'ṛ' => 'r',
+            'ṣ' | 'ś' => 's',
+            c => c,
+        })
+        .collect()
+}
+
+/// Repairs the diacritics of OCR words with a list of Pāli word forms.
+#[derive(Debug, Default)]
+pub struct PaliSpellingCorrector {
+    /// Folded form to the word form, or None when several forms fold the same.
+    forms: HashMap<String, Option<String>>,
+}
+
+impl PaliSpellingCorrector {
+    pub fn from_words<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut forms: HashMap<String, Option<String>> = HashMap::new();
+        for word in words {
+            let word = word.as_ref().trim().to_lowercase();
+            if word.is_empty() || word.contains(' ') {
+                continue;
+            }
+            forms
+                .entry(ocr_fold(&word))
+                .and_modify(|form| {
+                    if form.as_deref() != Some(word.as_str()) {
+                        *form = None;
+                    }
+                })
+                .or_insert(Some(word));
+        }
+        PaliSpellingCorrector { forms }
+    }
+
+    /// The inflected forms and compounds of the DPD `lookup` table. The
+    /// English (`epd`) and misspelling (`spelling`, `variant`) keys are left out.
+    pub fn from_dpd(db_conn: &mut SqliteConnection) -> Result<Self> {
+        use crate::db::dpd_schema::lookup::dsl::*;
+
+        let keys: Vec<String> = lookup
+            .filter(headwords.ne("").or(deconstructor.ne("")))
+            .select(lookup_key)
+            .load(db_conn)
+            .map_err(|e| anyhow!("Failed to load the DPD word forms: {}", e))?;
+        Ok(Self::from_words(keys))
+    }
+
+    pub fn len(&self) -> usize {
+        self.forms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.forms.is_empty()
+    }
+
+    /// The corrected word, with the punctuation and the capital of the OCR
+    /// word. None when the word is kept: it is a known form, it matches no
+    /// form or several, or it has no diacritics and a high confidence (e.g.
+    /// an English word, or Pāli written without diacritics on purpose).
+    pub fn correct_word(&self, word: &str, confidence: f32) -> Option<String> {
+        let start = word.find(|c: char| c.is_alphabetic())?;
+        let end = word.rfind(|c: char| c.is_alphabetic()).map(|i| i + word[i..].chars().next().map_or(1, char::len_utf8))?;
+        let core = &word[start..end];
+        if core.chars().count() < MIN_CORRECTED_WORD_CHARS {
+            return None;
+        }
+
+        let lower = core.to_lowercase();
+        if lower.is_ascii() && confidence >= LOW_WORD_CONFIDENCE {
+            return None;
+        }
+        let form = self.forms.get(&ocr_fold(&lower))?.as_deref()?;
+        if form == lower {
+            return None;
+        }
+
+        let mut corrected = String::with_capacity(word.len() + 4);
+        corrected.push_str(&word[..start]);
+        if core.chars().next().is_some_and(char::is_uppercase) {
+            let mut chars = form.chars();
+            if let Some(first) = chars.next() {
+                corrected.extend(first.to_uppercase());
+                corrected.push_str(chars.as_str());
+            }
+        } else {
+            corrected.push_str(form);
+        }
+        corrected.push_str(&word[end..]);
+        Some(corrected)
+    }
+
+    /// Correct the words of a page, returns the number of corrections.
+    pub fn correct_page(&self, page: &mut OcrPage) -> usize {
+        let mut count = 0;
+        for word in page.lines.iter_mut().flatten() {
+            if let Some(corrected) = self.correct_word(&word.text, word.confidence) {
+                word.text = corrected;
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+/// The OCR engine with the optional spelling repair, as used by the imports.
+#[derive(Debug)]
+pub struct Ocr {
+    pub engine: OcrEngine,
+    pub corrector: Option<PaliSpellingCorrector>,
+}
+
+impl Ocr {
+    pub fn new(engine: OcrEngine, corrector: Option<PaliSpellingCorrector>) -> Self {
+        Ocr { engine, corrector }
+    }
+
+    /// Recognize an image, and repair the Pāli words. Returns the text and
+    /// the record of the page for the book metadata.
+    pub fn recognize(&self, image: &Path, page: u32) -> Result<(String, OcrPageInfo)> {
+        let mut result = self.engine.recognize_image(image)?;
+        let corrections = match &self.corrector {
+            Some(corrector) => corrector.correct_page(&mut result),
+            None => 0,
+        };
+        let info = OcrPageInfo {
+            page,
+            confidence: result.confidence().map(|c| (c * 10.0).round() / 10.0),
+            words: result.word_count(),
+            corrections,
+        };
+        Ok((result.text(), info))
+    }
+
+    pub fn metadata(&self, pages: Vec<OcrPageInfo>) -> OcrMetadata {
+        OcrMetadata {
+            engine: "tesseract".to_string(),
+            languages: self.engine.languages().to_string(),
+            spelling_repair: self.corrector.is_some(),
+            pages,
+        }
+    }
+}
+
+/// The OCR result of one page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcrPageInfo {
+    /// Numbered from 1, as the spine item pages.
+    pub page: u32,
+    /// The mean word confidence, 0-100.
+    pub confidence: Option<f32>,
+    pub words: usize,
+    /// Words repaired with the Pāli word list.
+    pub corrections: usize,
+}
+
+/// Stored in `books.metadata_json` under the `ocr` key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcrMetadata {
+    pub engine: String,
+    pub languages: String,
+    pub spelling_repair: bool,
+    pub pages: Vec<OcrPageInfo>,
+}
+
+impl OcrMetadata {
+    pub fn to_metadata_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&serde_json::json!({ OCR_METADATA_KEY: self }))?)
+    }
+
+    /// The OCR results of a book, None when it was not OCR'd.
+    pub fn from_metadata_json(metadata_json: Option<&str>) -> Option<OcrMetadata> {
+        let value: serde_json::Value = serde_json::from_str(metadata_json?).ok()?;
+        serde_json::from_value(value.get(OCR_METADATA_KEY)?.clone()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t2480\t3508\t-1\t
+4\t1\t1\t1\t1\t0\t100\t100\t800\t40\t-1\t
+5\t1\t1\t1\t1\t1\t100\t100\t200\t40\t96.5\tEvaṃ
+5\t1\t1\t1\t1\t2\t320\t100\t100\t40\t91.0\tme
+5\t1\t1\t1\t2\t1\t100\t150\t200\t40\t60.0\tsutam.
+5\t1\t1\t2\t1\t1\t100\t250\t200\t40\t70.0\tEkam
+5\t1\t1\t2\t1\t2\t320\t250\t200\t40\t-1\t
+";
+
+    #[test]
+    fn test_parse_tsv() {
+        let page = OcrPage::from_tsv(TSV);
+        assert_eq!(page.text(), "Evaṃ me\nsutam.\n\nEkam");
+        assert_eq!(page.word_count(), 4);
+        assert_eq!(page.confidence(), Some((96.5 + 91.0 + 60.0 + 70.0) / 4.0));
+        assert_eq!(OcrPage::from_tsv("").confidence(), None);
+    }
+
+    #[test]
+    fn test_ocr_fold() {
+        assert_eq!(ocr_fold("saṅkhārā"), "sankhara");
+        assert_eq!(ocr_fold("sankhärä"), "sankhara");
+        assert_eq!(ocr_fold("ñāṇa"), "nana");
+    }
+
+    #[test]
+    fn test_correct_word() {
+        let c = PaliSpellingCorrector::from_words(["sutaṃ", "saṅkhārā", "sati", "satī", "dhamma", "ekaṃ"]);
+        // A misread diacritic
+        assert_eq!(c.correct_word("sankhärä,", 95.0).as_deref(), Some("saṅkhārā,"));
+        // A dropped diacritic, with a low confidence
+        assert_eq!(c.correct_word("sutam.", 60.0).as_deref(), Some("sutaṃ."));
+        assert_eq!(c.correct_word("Ekam", 70.0).as_deref(), Some("Ekaṃ"));
+        // Kept: no diacritics and a high confidence
+        assert_eq!(c.correct_word("sutam", 95.0), None);
+        // Kept: a known form, an ambiguous one, an unknown one, a short one
+        assert_eq!(c.correct_word("dhamma", 50.0), None);
+        assert_eq!(c.correct_word("sati", 50.0), None);
+        assert_eq!(c.correct_word("the", 50.0), None);
+        assert_eq!(c.correct_word("...", 50.0), None);
+    }
+
+    #[test]
+    fn test_correct_page() {
+        let c = PaliSpellingCorrector::from_words(["evaṃ", "sutaṃ", "ekaṃ"]);
+        let mut page = OcrPage::from_tsv(TSV);
+        assert_eq!(c.correct_page(&mut page), 2);
+        assert_eq!(page.text(), "Evaṃ me\nsutaṃ.\n\nEkaṃ");
+    }
+
+    #[test]
+    fn test_metadata_json() {
+        let metadata = OcrMetadata {
+            engine: "tesseract".to_string(),
+            languages: "eng".to_string(),
+            spelling_repair: true,
+            pages: vec![OcrPageInfo { page: 2, confidence: Some(87.5), words: 120, corrections: 3 }],
+        };
+        let json = metadata.to_metadata_json().unwrap();
+        assert!(json.starts_with(r#"{"ocr":"#), "{}", json);
+        assert_eq!(OcrMetadata::from_metadata_json(Some(&json)), Some(metadata));
+        assert_eq!(OcrMetadata::from_metadata_json(Some(r#"{"title":"x"}"#)), None);
+        assert_eq!(OcrMetadata::from_metadata_json(None), None);
+    }
+}
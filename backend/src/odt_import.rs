@@ -0,0 +1,321 @@
+use anyhow::{anyhow, Result};
+use diesel::prelude::*;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::document_import::{import_converted_document_to_db, open_zip, read_zip_entry, xml_attr, ConvertedDocument};
+
+/// Import an OpenDocument text (ODT) document into the database
+///
+/// The headings (`text:h`, and paragraphs with a "Heading N" style) start
+/// the chapters. Bold and italic spans, lists, tables, links and images are
+/// kept, the footnotes and comments are left out. The title, author and
+/// language are read from `meta.xml`.
+///
+/// # Arguments
+/// * `db_conn` - Mutable reference to SQLite database connection
+/// * `odt_path` - Path to the ODT file to import
+/// * `book_uid` - Unique identifier for this book
+/// * `custom_title` - Optional custom title to override the document metadata
+/// * `custom_author` - Optional custom author to override the document metadata
+/// * `custom_language` - Optional custom language to override the document metadata
+/// * `is_user_added` - Whether the book was added by the user
+///
+/// # Returns
+/// * `Result<()>` - Ok if successful, Err with details if failed
+#[allow(clippy::too_many_arguments)]
+pub fn import_odt_to_db(
+    db_conn: &mut SqliteConnection,
+    odt_path: &Path,
+    book_uid: &str,
+    custom_title: Option<&str>,
+    custom_author: Option<&str>,
+    custom_language: Option<&str>,
+    is_user_added: bool,
+) -> Result<()> {
+    tracing::info!("Importing ODT from {:?} with UID: {}", odt_path, book_uid);
+    let doc = convert_odt(odt_path)?;
+    import_converted_document_to_db(db_conn, doc, "odt", odt_path, book_uid, custom_title, custom_author, custom_language, is_user_added)
+}
+
+/// Convert an ODT file to HTML.
+pub fn convert_odt(odt_path: &Path) -> Result<ConvertedDocument> {
+    let mut archive = open_zip(odt_path)?;
+
+    let content_xml = read_zip_entry(&mut archive, "content.xml")?
+        .ok_or_else(|| anyhow!("Not an ODT document, content.xml is missing: {}", odt_path.display()))?;
+
+    let mut doc = match read_zip_entry(&mut archive, "meta.xml")? {
+        Some(xml) => parse_meta(&String::from_utf8_lossy(&xml))?,
+        None => ConvertedDocument::default(),
+    };
+
+    let mut converter = OdtConverter::default();
+    converter.convert(&String::from_utf8_lossy(&content_xml))?;
+    doc.body_html = converter.out;
+
+    for href in converter.images {
+        if let Some(data) = read_zip_entry(&mut archive, &href)? {
+            doc.resources.push((href, data));
+        }
+    }
+
+    Ok(doc)
+}
+
+/// The title, author and language of `meta.xml`.
+fn parse_meta(xml: &str) -> Result<ConvertedDocument> {
+    let mut doc = ConvertedDocument::default();
+    let mut reader = Reader::from_str(xml);
+    let mut current: Option<Vec<u8>> = None;
+    loop {
+        match reader.read_event().map_err(|e| anyhow!("Failed to parse meta.xml: {}", e))? {
+            Event::Start(e) => current = Some(e.name().as_ref().to_vec()),
+            Event::End(_) => current = None,
+            Event::Text(t) => {
+                let text = t.unescape().map_err(|e| anyhow!("Failed to parse meta.xml: {}", e))?;
+                let text = text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+                match current.as_deref() {
+                    Some(b"dc:title") => doc.title = Some(text.to_string()),
+                    // The last editor, when there is no initial creator
+                    Some(b"meta:initial-creator") => doc.author = Some(text.to_string()),
+                    Some(b"dc:creator") => { doc.author.get_or_insert_with(|| text.to_string()); }
+                    Some(b"dc:language") => doc.language = Some(text.to_string()),
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(doc)
+}
+
+/// The formatting of an automatic style.
+#[derive(Debug, Default, Clone)]
+struct OdtStyle {
+    bold: bool,
+    italic: bool,
+    heading_level: Option<usize>,
+}
+
+/// Converts `content.xml` to HTML. Each element pushes its closing tag, which
+/// is written at its end.
+#[derive(Debug, Default)]
+struct OdtConverter {
+    out: String,
+    /// The image paths in the archive, e.g. `Pictures/1000.png`
+    images: Vec<String>,
+    styles: HashMap<String, OdtStyle>,
+    current_style: Option<String>,
+    closing_tags: Vec<String>,
+    /// Inside a footnote or comment, which are left out
+    skip_depth: usize,
+    /// Inside a paragraph or heading, where the text is
+    text_depth: usize,
+}
+
+impl OdtConverter {
+    fn convert(&mut self, xml: &str) -> Result<()> {
+        let mut reader = Reader::from_str(xml);
+        loop {
+            match reader.read_event().map_err(|e| anyhow!("Failed to parse content.xml: {}", e))? {
+                Event::Start(e) => {
+                    if self.skip_depth > 0 || is_skipped(e.name().as_ref()) {
+                        self.skip_depth += 1;
+                        continue;
+                    }
+                    let (open, close) = self.element_tags(&e);
+                    self.out.push_str(&open);
+                    self.closing_tags.push(close);
+                    if matches!(e.name().as_ref(), b"text:p" | b"text:h") {
+                        self.text_depth += 1;
+                    }
+                }
+                Event::Empty(e) => {
+                    if self.skip_depth > 0 || is_skipped(e.name().as_ref()) {
+                        continue;
+                    }
+                    let (open, close) = self.element_tags(&e);
+                    self.out.push_str(&open);
+                    self.out.push_str(&close);
+                    if e.name().as_ref() == b"style:style" {
+                        self.current_style = None;
+                    }
+                }
+                Event::End(e) => {
+                    if self.skip_depth > 0 {
+                        self.skip_depth -= 1;
+                        continue;
+                    }
+                    match e.name().as_ref() {
+                        b"style:style" => self.current_style = None,
+                        b"text:p" | b"text:h" => self.text_depth = self.text_depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    if let Some(close) = self.closing_tags.pop() {
+                        self.out.push_str(&close);
+                    }
+                }
+                Event::Text(t) if self.skip_depth == 0 && self.text_depth > 0 => {
+                    let text = t.unescape().map_err(|e| anyhow!("Failed to parse content.xml: {}", e))?;
+                    self.out.push_str(&html_escape::encode_text(&text));
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The HTML opening and closing tags of an element, empty for the ones
+    /// which are not written.
+    fn element_tags(&mut self, e: &BytesStart) -> (String, String) {
+        let tags = |open: &str, close: &str| (open.to_string(), close.to_string());
+        match e.name().as_ref() {
+            // The automatic styles come before the body
+            b"style:style" => {
+                if let Some(name) = xml_attr(e, b"style:name") {
+                    let mut style = OdtStyle {
+                        heading_level: xml_attr(e, b"style:parent-style-name").and_then(|p| heading_style_level(&p)),
+                        ..Default::default()
+                    };
+                    if let Some(level) = xml_attr(e, b"style:default-outline-level").and_then(|l| l.parse().ok()) {
+                        style.heading_level = Some(level);
+                    }
+                    self.styles.insert(name.clone(), style);
+                    self.current_style = Some(name);
+                }
+                tags("", "")
+            }
+            b"style:text-properties" => {
+                if let Some(style) = self.current_style.as_ref().and_then(|name| self.styles.get_mut(name)) {
+                    style.bold = xml_attr(e, b"fo:font-weight").is_some_and(|w| w == "bold" || w.parse::<u32>().is_ok_and(|w| w >= 600));
+                    style.italic = xml_attr(e, b"fo:font-style").is_some_and(|s| s == "italic" || s == "oblique");
+                }
+                tags("", "")
+            }
+            b"text:h" => {
+                let level = xml_attr(e, b"text:outline-level")
+                    .and_then(|l| l.parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .clamp(1, 6);
+                (format!("<h{}>", level), format!("</h{}>\n", level))
+            }
+            b"text:p" => {
+                let level = xml_attr(e, b"text:style-name").and_then(|name| self.style_heading_level(&name));
+                match level {
+                    Some(level) => (format!("<h{}>", level), format!("</h{}>\n", level)),
+                    None => tags("<p>", "</p>\n"),
+                }
+            }
+            b"text:span" => {
+                let style = xml_attr(e, b"text:style-name")
+                    .and_then(|name| self.styles.get(&name).cloned())
+                    .unwrap_or_default();
+                match (style.bold, style.italic) {
+                    (true, true) => tags("<strong><em>", "</em></strong>"),
+                    (true, false) => tags("<strong>", "</strong>"),
+                    (false, true) => tags("<em>", "</em>"),
+                    (false, false) => tags("", ""),
+                }
+            }
+            b"text:a" => match xml_attr(e, b"xlink:href") {
+                Some(href) => (format!(r#"<a href="{}">"#, html_escape::encode_double_quoted_attribute(&href)), "</a>".to_string()),
+                None => tags("<a>", "</a>"),
+            },
+            b"text:s" => {
+                let count = xml_attr(e, b"text:c").and_then(|c| c.parse().ok()).unwrap_or(1);
+                (" ".repeat(count), String::new())
+            }
+            b"text:tab" => tags(" ", ""),
+            b"text:line-break" => tags("<br>", ""),
+            b"text:list" => tags("<ul>", "</ul>\n"),
+            b"text:list-item" | b"text:list-header" => tags("<li>", "</li>\n"),
+            b"table:table" => tags("<table>", "</table>\n"),
+            b"table:table-row" => tags("<tr>", "</tr>"),
+            b"table:table-cell" => tags("<td>", "</td>"),
+            b"draw:image" => {
+                // Images linked outside of the document are left out
+                match xml_attr(e, b"xlink:href").filter(|href| !href.contains("://") && !href.starts_with("..")) {
+                    Some(href) => {
+                        let href = href.trim_start_matches("./").to_string();
+                        let img = format!(r#"<img src="{}">"#, html_escape::encode_double_quoted_attribute(&href));
+                        if !self.images.contains(&href) {
+                            self.images.push(href);
+                        }
+                        (img, String::new())
+                    }
+                    None => tags("", ""),
+                }
+            }
+            _ => tags("", ""),
+        }
+    }
+
+    fn style_heading_level(&self, style_name: &str) -> Option<usize> {
+        self.styles.get(style_name)
+            .and_then(|style| style.heading_level)
+            .or_else(|| heading_style_level(style_name))
+            .map(|level| level.clamp(1, 6))
+    }
+}
+
+/// The footnotes, comments and tracked changes are left out.
+fn is_skipped(name: &[u8]) -> bool {
+    matches!(name, b"text:note" | b"office:annotation" | b"text:tracked-changes" | b"text:sequence-decls")
+}
+
+/// The level of the "Heading N" style names, as `Heading_20_2` in the XML.
+fn heading_style_level(style_name: &str) -> Option<usize> {
+    lazy_static::lazy_static! {
+        static ref RE_HEADING_STYLE: Regex = Regex::new(r"^Heading(?:_20_| )([1-6])$").unwrap();
+    }
+    RE_HEADING_STYLE.captures(style_name)?[1].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heading_style_level() {
+        assert_eq!(heading_style_level("Heading_20_2"), Some(2));
+        assert_eq!(heading_style_level("Heading 1"), Some(1));
+        assert_eq!(heading_style_level("Heading"), None);
+        assert_eq!(heading_style_level("Text_20_body"), None);
+    }
+
+    #[test]
+    fn test_convert_content_xml() {
+        let xml = r#"<office:document-content xmlns:office="o" xmlns:style="s" xmlns:text="t" xmlns:fo="f" xmlns:draw="d" xmlns:xlink="x">
+<office:automatic-styles>
+<style:style style:name="T1" style:family="text"><style:text-properties fo:font-weight="bold"/></style:style>
+<style:style style:name="P1" style:family="paragraph" style:parent-style-name="Heading_20_2"/>
+</office:automatic-styles>
+<office:body><office:text>
+<text:sequence-decls><text:sequence-decl text:name="Table"/></text:sequence-decls>
+<text:h text:outline-level="1">Talk</text:h>
+<text:p text:style-name="P1">Part</text:p>
+<text:p>The <text:span text:style-name="T1">Dhamma</text:span>,<text:s text:c="2"/>here<text:note><text:note-body><text:p>A note</text:p></text:note-body></text:note>.</text:p>
+<text:list><text:list-item><text:p>one</text:p></text:list-item></text:list>
+<text:p><draw:frame><draw:image xlink:href="Pictures/1.png"/></draw:frame></text:p>
+</office:text></office:body></office:document-content>"#;
+        let mut converter = OdtConverter::default();
+        converter.convert(xml).unwrap();
+        let body = converter.out.trim();
+        assert!(body.contains("<h1>Talk</h1>"), "{}", body);
+        assert!(body.contains("<h2>Part</h2>"), "{}", body);
+        assert!(body.contains("<p>The <strong>Dhamma</strong>,  here.</p>"), "{}", body);
+        assert!(body.contains("<ul><li><p>one</p>\n</li>\n</ul>"), "{}", body);
+        assert!(body.contains(r#"<p><img src="Pictures/1.png"></p>"#), "{}", body);
+        assert!(!body.contains("A note"), "{}", body);
+        assert_eq!(converter.images, vec!["Pictures/1.png".to_string()]);
+    }
+}
@@ -0,0 +1,163 @@
+//! Phonetic keys for matching Pāli words typed in ASCII.
+//!
+//! Readers type `satipatana`, `sati patthana` or `satipaṭṭhāna` for the same
+//! word. The key removes the differences which ASCII typing loses or varies:
+//!
+//! - diacritics: long and short vowels (ā → a), retroflex and dental
+//!   consonants (ṭ → t), the nasals (ñ, ṇ, ṅ → n)
+//! - aspirates: the h after a stop (th → t, bh → b)
+//! - geminates: doubled letters (tt → t, also typed long vowels: aa → a)
+//! - niggahita: ṁ, ṃ, ŋ, and a nasal before a consonant are all m
+//!   (saṅgha, saṁgha, sangha → samga)
+//!
+//! So `satipaṭṭhāna` and `satipatana` both have the key `satipatana`.
+//!
+//! The key is indexed in the `content_phonetic` field of the sutta and dict
+//! fulltext indexes (the `pali_phonetic` tokenizer), and queried when the
+//! search has a fuzzy distance. See docs/fuzzy-phonetic-search.md.
+
+/// The highest edit distance for the fuzzy queries, as tantivy's Levenshtein
+/// automatons support.
+pub const MAX_FUZZY_DISTANCE: u8 = 2;
+
+fn fold_char(c: char) -> char {
+    match c {
+        'ā' => 'a',
+        'ī' => 'i',
+        'ū' => 'u',
+        'ṭ' => 't',
+        'ḍ' => 'd',
+        'ṇ' | 'ñ' | 'ṅ' => 'n',
+        'ḷ' => 'l',
+        'ṁ' | 'ṃ' | 'ŋ' => 'm',
+        'ḥ' => 'h',
+        'ṛ' => 'r',
+        'ṣ' | 'ś' => 's',
+        _ => c,
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+fn is_stop(c: char) -> bool {
+    matches!(c, 'k' | 'g' | 'c' | 'j' | 't' | 'd' | 'p' | 'b')
+}
+
+/// The phonetic key of a single word. See the module docs.
+pub fn pali_phonetic_key(word: &str) -> String {
+    let folded: Vec<char> = word
+        .chars()
+        .flat_map(|c| c.to_lowercase())
+        .map(fold_char)
+        .collect();
+
+    // Aspirates: drop the h after a stop
+    let mut chars: Vec<char> = Vec::with_capacity(folded.len());
+    for c in folded {
+        if c == 'h' && chars.last().is_some_and(|p| is_stop(*p)) {
+            continue;
+        }
+        chars.push(c);
+    }
+
+    // Geminates
+    chars.dedup();
+
+    // Niggahita: a nasal before a consonant is m
+    for i in 0..chars.len().saturating_sub(1) {
+        let next = chars[i + 1];
+        if matches!(chars[i], 'n' | 'm') && next.is_alphabetic() && !is_vowel(next) {
+            chars[i] = 'm';
+        }
+    }
+
+    chars.dedup();
+
+    chars.into_iter().collect()
+}
+
+/// The fuzzy distance to use for a key: short keys are matched exactly or
+/// with one edit, so that they don't match most of the short words.
+pub fn fuzzy_distance_for_key(key: &str, requested: u8) -> u8 {
+    let requested = requested.min(MAX_FUZZY_DISTANCE);
+    match key.chars().count() {
+        0..=3 => 0,
+        4..=6 => requested.min(1),
+        _ => requested,
+    }
+}
+
+/// The edit distance of two strings in chars, counting a swap of two
+/// neighbour chars as one edit, as the fuzzy term queries do.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() {
+        return b.len();
+    }
+    if b.is_empty() {
+        return a.len();
+    }
+
+    // Three rows of the optimal string alignment table
+    let mut prev_prev: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        cur[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            cur[j] = (prev[j] + 1)
+                .min(cur[j - 1] + 1)
+                .min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cur[j] = cur[j].min(prev_prev[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut prev_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pali_phonetic_key() {
+        assert_eq!(pali_phonetic_key("satipaṭṭhāna"), "satipatana");
+        assert_eq!(pali_phonetic_key("satipatana"), "satipatana");
+        assert_eq!(pali_phonetic_key("Satipatthana"), "satipatana");
+        assert_eq!(pali_phonetic_key("saṅgha"), "samga");
+        assert_eq!(pali_phonetic_key("saṁgha"), "samga");
+        assert_eq!(pali_phonetic_key("sangha"), "samga");
+        assert_eq!(pali_phonetic_key("dhammaṁ"), "damam");
+        assert_eq!(pali_phonetic_key("dhammaṃ"), "damam");
+        assert_eq!(pali_phonetic_key("viññāṇa"), "vinana");
+        assert_eq!(pali_phonetic_key("vinnana"), "vinana");
+        assert_eq!(pali_phonetic_key("nibbaana"), "nibana");
+        assert_eq!(pali_phonetic_key("bhikkhu"), "biku");
+    }
+
+    #[test]
+    fn test_fuzzy_distance_for_key() {
+        assert_eq!(fuzzy_distance_for_key("sati", 2), 1);
+        assert_eq!(fuzzy_distance_for_key("kama", 0), 0);
+        assert_eq!(fuzzy_distance_for_key("ca", 2), 0);
+        assert_eq!(fuzzy_distance_for_key("satipatana", 5), 2);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("satipatana", "satipatana"), 0);
+        assert_eq!(edit_distance("satipatana", "satipatna"), 1);
+        assert_eq!(edit_distance("satipatana", "satiptaana"), 1);
+        assert_eq!(edit_distance("dhamma", "kamma"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
@@ -1,12 +1,15 @@
 use anyhow::{anyhow, Result};
 use diesel::prelude::*;
 use lopdf::Document;
+use lopdf::{Dictionary, Object, ObjectId};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
-use crate::db::appdata_models::{NewBook, NewBookResource, NewBookSpineItem};
+use crate::db::appdata_models::{NavPointJson, NewBook, NewBookResource, NewBookSpineItem};
 use crate::db::appdata_schema::{book_resources, book_spine_items, books};
 use crate::helpers::compact_rich_text;
+use crate::ocr::{Ocr, OcrPageInfo, MIN_PAGE_TEXT_CHARS};
 
 /// Import a PDF file into the database
 ///
@@ -31,6 +34,26 @@ pub fn import_pdf_to_db(
     custom_language: Option<&str>,
     custom_enable_embedded_css: Option<bool>,
     is_user_added: bool,
+) -> Result<()> {
+    import_pdf_to_db_with_ocr(db_conn, pdf_path, book_uid, custom_title, custom_author, custom_language, custom_enable_embedded_css, is_user_added, None)
+}
+
+/// Import a PDF file into the database, recognizing the text of scanned pages
+///
+/// As `import_pdf_to_db()`. With `ocr`, the pages with (almost) no text are
+/// rendered and recognized, and the confidence of each of them is stored in
+/// the book's `metadata_json`, see `ocr::OcrMetadata`.
+#[allow(clippy::too_many_arguments)]
+pub fn import_pdf_to_db_with_ocr(
+    db_conn: &mut SqliteConnection,
+    pdf_path: &Path,
+    book_uid: &str,
+    custom_title: Option<&str>,
+    custom_author: Option<&str>,
+    custom_language: Option<&str>,
+    custom_enable_embedded_css: Option<bool>,
+    is_user_added: bool,
+    ocr: Option<&Ocr>,
 ) -> Result<()> {
     tracing::info!("Importing PDF from {:?} with UID: {}", pdf_path, book_uid);
 
@@ -76,16 +99,28 @@ pub fn import_pdf_to_db(
 
     tracing::info!("PDF metadata - Title: {}, Author: {}, Language: {}", title, author, language);
 
-    // Extract plain text from PDF using pdf-extract
-    let content_plain = match pdf_extract::extract_text(pdf_path) {
-        Ok(text) => {
-            tracing::debug!("Extracted {} characters of plain text", text.len());
-            compact_rich_text(&text)
-        }
-        Err(e) => {
-            tracing::warn!("Failed to extract text from PDF: {}. Using empty text.", e);
-            String::new()
+    // Extract plain text per page, so that a search result can point to the page
+    let mut page_texts = extract_page_texts(pdf_path, &doc);
+    tracing::debug!("Extracted text of {} pages", page_texts.len());
+
+    let metadata_json = match ocr {
+        Some(ocr) => {
+            let ocr_pages = ocr_scanned_pages(ocr, pdf_path, &mut page_texts)?;
+            if ocr_pages.is_empty() {
+                None
+            } else {
+                Some(ocr.metadata(ocr_pages).to_metadata_json()?)
+            }
         }
+        None => None,
+    };
+
+    let toc = extract_pdf_outline(&doc);
+    tracing::info!("PDF has {} outline entries", toc.len());
+    let toc_json = if !toc.is_empty() {
+        Some(serde_json::to_string(&toc)?)
+    } else {
+        None
     };
 
     // Read PDF file into memory as binary data
@@ -103,9 +138,9 @@ pub fn import_pdf_to_db(
         author: if author.is_empty() { None } else { Some(&author) },
         language: if language.is_empty() { None } else { Some(&language) },
         file_path: Some(&file_path_str),
-        metadata_json: None, // PDFs don't have structured metadata like EPUBs
+        metadata_json: metadata_json.as_deref(), // The OCR results, PDFs don't have structured metadata like EPUBs
         enable_embedded_css,
-        toc_json: None, // PDFs don't have TOC support yet
+        toc_json: toc_json.as_deref(),
         is_user_added,
     };
 
@@ -120,27 +155,46 @@ pub fn import_pdf_to_db(
 
     tracing::info!("Inserted book record with id: {}", book_id);
 
-    // Insert single spine item (PDFs are treated as single documents)
+    // Insert one spine item per page.
     // content_html is None for PDFs - the API will serve the PDF viewer template
-    // For PDFs, the spine_item should use the book's title and language, not the extracted metadata
-    let spine_item_uid = format!("{}.0", book_uid);
-    let new_spine_item = NewBookSpineItem {
-        book_id,
-        book_uid,
-        spine_item_uid: &spine_item_uid,
-        spine_index: 0,
-        resource_path: "document.pdf",
-        title: Some(&title), // Use book's title (which includes custom or extracted)
-        language: if language.is_empty() { None } else { Some(&language) }, // Use book's language (which includes custom or extracted)
-        content_html: None,
-        content_plain: Some(&content_plain),
+    // The resource_path is document.pdf#page=N, which the viewer opens at the page.
+    let sections = page_sections(&toc);
+    let pages: Vec<(String, String, String)> = if page_texts.is_empty() {
+        // A PDF without pages, kept as one item of the whole document
+        vec![(format!("{}.0", book_uid), "document.pdf".to_string(), title.clone())]
+    } else {
+        (1..=page_texts.len() as u32)
+            .map(|page| {
+                let page_title = match section_of_page(&sections, page) {
+                    Some(section) => format!("{}, p. {}", section, page),
+                    None => format!("p. {}", page),
+                };
+                (format!("{}.{}", book_uid, page - 1), pdf_page_resource_path(page), page_title)
+            })
+            .collect()
     };
 
+    let new_spine_items: Vec<NewBookSpineItem> = pages
+        .iter()
+        .enumerate()
+        .map(|(i, (spine_item_uid, resource_path, page_title))| NewBookSpineItem {
+            book_id,
+            book_uid,
+            spine_item_uid,
+            spine_index: i as i32,
+            resource_path,
+            title: Some(page_title),
+            language: if language.is_empty() { None } else { Some(&language) }, // Use book's language (which includes custom or extracted)
+            content_html: None,
+            content_plain: Some(page_texts.get(i).map(|s| s.as_str()).unwrap_or("")),
+        })
+        .collect();
+
     diesel::insert_into(book_spine_items::table)
-        .values(&new_spine_item)
+        .values(&new_spine_items)
         .execute(db_conn)?;
 
-    tracing::info!("Inserted spine item: {}", spine_item_uid);
+    tracing::info!("Inserted {} spine items", new_spine_items.len());
 
     // Insert PDF as a resource
     let new_resource = NewBookResource {
@@ -155,10 +209,253 @@ pub fn import_pdf_to_db(
         .values(&new_resource)
         .execute(db_conn)?;
 
-    tracing::info!("Successfully imported PDF: 1 spine item, 1 resource ({} bytes)", pdf_bytes.len());
+    tracing::info!("Successfully imported PDF: {} pages, 1 resource ({} bytes)", pages.len(), pdf_bytes.len());
     Ok(())
 }
 
+/// The resource path of a PDF page spine item, e.g. `document.pdf#page=12`
+pub fn pdf_page_resource_path(page: u32) -> String {
+    format!("document.pdf#page={}", page)
+}
+
+/// The page number of a PDF page spine item from its resource path, None for
+/// other spine items, and for PDFs imported as one item.
+pub fn pdf_page_number(resource_path: &str) -> Option<i32> {
+    resource_path
+        .strip_prefix("document.pdf#page=")
+        .and_then(|page| page.parse::<i32>().ok())
+        .filter(|page| *page > 0)
+}
+
+/// Extract the plain text of each page. Uses pdf-extract, and lopdf for the
+/// pages when pdf-extract fails on the document.
+fn extract_page_texts(pdf_path: &Path, doc: &Document) -> Vec<String> {
+    let page_count = doc.get_pages().len();
+    let texts = match pdf_extract::extract_text_by_pages(pdf_path) {
+        Ok(texts) => texts,
+        Err(e) => {
+            tracing::warn!("Failed to extract text from PDF: {}. Trying per page with lopdf.", e);
+            doc.get_pages()
+                .keys()
+                .map(|page| doc.extract_text(&[*page]).unwrap_or_default())
+                .collect()
+        }
+    };
+
+    let mut texts: Vec<String> = texts.iter().map(|text| compact_rich_text(text)).collect();
+    texts.resize(page_count.max(texts.len()), String::new());
+    texts
+}
+
+/// Recognize the text of the pages which have (almost) none, e.g. image scans.
+/// Returns the OCR results of the pages.
+fn ocr_scanned_pages(ocr: &Ocr, pdf_path: &Path, page_texts: &mut [String]) -> Result<Vec<OcrPageInfo>> {
+    let scanned: Vec<usize> = page_texts.iter()
+        .enumerate()
+        .filter(|(_, text)| text.trim().chars().count() < MIN_PAGE_TEXT_CHARS)
+        .map(|(i, _)| i)
+        .collect();
+    if scanned.is_empty() {
+        return Ok(Vec::new());
+    }
+    tracing::info!("OCR of {} scanned pages", scanned.len());
+
+    let tmp_dir = tempfile::tempdir()
+        .map_err(|e| anyhow!("Failed to create a temp folder for OCR: {}", e))?;
+    let mut ocr_pages = Vec::new();
+    for i in scanned {
+        let page = i as u32 + 1;
+        let result = ocr.engine.render_pdf_page(pdf_path, page, tmp_dir.path())
+            .and_then(|image| {
+                let result = ocr.recognize(&image, page);
+                let _ = fs::remove_file(&image);
+                result
+            });
+        match result {
+            Ok((text, info)) => {
+                tracing::debug!("OCR page {}: {} words, confidence {:?}", page, info.words, info.confidence);
+                page_texts[i] = compact_rich_text(&text);
+                ocr_pages.push(info);
+            }
+            Err(e) => tracing::warn!("OCR failed on page {}: {}", page, e),
+        }
+    }
+    Ok(ocr_pages)
+}
+
+/// Read the PDF outline (`/Outlines`) as a table of contents. Each entry links
+/// to the resource path of its page. Entries without a destination in the
+/// document link to their first child, or are left out.
+pub fn extract_pdf_outline(doc: &Document) -> Vec<NavPointJson> {
+    let page_numbers: HashMap<ObjectId, u32> = doc.get_pages()
+        .into_iter()
+        .map(|(page, id)| (id, page))
+        .collect();
+
+    let Ok(catalog) = doc.catalog() else {
+        return Vec::new();
+    };
+    let Some(first) = catalog.get(b"Outlines").ok()
+        .and_then(|outlines| resolve_dict(doc, outlines))
+        .and_then(|outlines| outlines.get(b"First").ok()) else {
+        return Vec::new();
+    };
+
+    let mut seen = HashSet::new();
+    outline_items(doc, first, &page_numbers, &mut seen)
+}
+
+/// The items of one outline level, following /Next from the first item.
+fn outline_items(
+    doc: &Document,
+    first: &Object,
+    page_numbers: &HashMap<ObjectId, u32>,
+    seen: &mut HashSet<ObjectId>,
+) -> Vec<NavPointJson> {
+    let mut items = Vec::new();
+    let mut next = Some(first);
+
+    while let Some(obj) = next {
+        // Guard against a malformed outline which links back to an item
+        if let Object::Reference(id) = obj
+            && !seen.insert(*id) {
+                break;
+            }
+        let Some(node) = resolve_dict(doc, obj) else {
+            break;
+        };
+
+        let label = node.get(b"Title").ok()
+            .and_then(|t| resolve_object(doc, t).as_str().ok())
+            .map(|bytes| trim_pdf_string(&decode_pdf_text_string(bytes)))
+            .unwrap_or_default();
+
+        let children = match node.get(b"First") {
+            Ok(child) => outline_items(doc, child, page_numbers, seen),
+            Err(_) => Vec::new(),
+        };
+
+        let content = outline_item_page(doc, node, page_numbers)
+            .map(pdf_page_resource_path)
+            .or_else(|| children.first().map(|c| c.content.clone()));
+
+        if let Some(content) = content {
+            items.push(NavPointJson { label, content, children });
+        }
+
+        next = node.get(b"Next").ok();
+    }
+
+    items
+}
+
+/// The page of an outline item, from its /Dest or its GoTo action.
+fn outline_item_page(doc: &Document, node: &Dictionary, page_numbers: &HashMap<ObjectId, u32>) -> Option<u32> {
+    let dest = match node.get(b"Dest") {
+        Ok(dest) => dest,
+        Err(_) => {
+            let action = resolve_dict(doc, node.get(b"A").ok()?)?;
+            if action.get(b"S").ok()?.as_name().ok()? != b"GoTo" {
+                return None;
+            }
+            action.get(b"D").ok()?
+        }
+    };
+    destination_page(doc, dest, page_numbers, 0)
+}
+
+/// The page of a destination: an array starting with the page object, a
+/// dictionary with /D, or the name of a named destination.
+fn destination_page(doc: &Document, dest: &Object, page_numbers: &HashMap<ObjectId, u32>, depth: u8) -> Option<u32> {
+    if depth > 4 {
+        return None;
+    }
+    match resolve_object(doc, dest) {
+        Object::Array(dest) => match dest.first()? {
+            Object::Reference(page_id) => page_numbers.get(page_id).copied(),
+            _ => None,
+        },
+        Object::Dictionary(dict) => destination_page(doc, dict.get(b"D").ok()?, page_numbers, depth + 1),
+        Object::Name(name) | Object::String(name, _) => {
+            destination_page(doc, named_destination(doc, name)?, page_numbers, depth + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Look up a named destination in the catalog /Dests dictionary (PDF 1.1), or
+/// in the /Names /Dests name tree.
+fn named_destination<'a>(doc: &'a Document, name: &[u8]) -> Option<&'a Object> {
+    let catalog = doc.catalog().ok()?;
+    if let Some(dests) = catalog.get(b"Dests").ok().and_then(|d| resolve_dict(doc, d))
+        && let Ok(dest) = dests.get(name) {
+            return Some(dest);
+        }
+    let names = resolve_dict(doc, catalog.get(b"Names").ok()?)?;
+    let tree = resolve_dict(doc, names.get(b"Dests").ok()?)?;
+    name_tree_lookup(doc, tree, name, 0)
+}
+
+fn name_tree_lookup<'a>(doc: &'a Document, node: &'a Dictionary, name: &[u8], depth: u8) -> Option<&'a Object> {
+    if depth > 32 {
+        return None;
+    }
+    if let Ok(Object::Array(pairs)) = node.get(b"Names").map(|n| resolve_object(doc, n)) {
+        for pair in pairs.chunks(2) {
+            if let [key, value] = pair
+                && resolve_object(doc, key).as_str().ok() == Some(name) {
+                    return Some(value);
+                }
+        }
+    }
+    if let Ok(Object::Array(kids)) = node.get(b"Kids").map(|k| resolve_object(doc, k)) {
+        for kid in kids {
+            if let Some(kid) = resolve_dict(doc, kid)
+                && let Some(value) = name_tree_lookup(doc, kid, name, depth + 1) {
+                    return Some(value);
+                }
+        }
+    }
+    None
+}
+
+fn resolve_object<'a>(doc: &'a Document, obj: &'a Object) -> &'a Object {
+    match obj {
+        Object::Reference(id) => doc.get_object(*id).unwrap_or(obj),
+        _ => obj,
+    }
+}
+
+fn resolve_dict<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Dictionary> {
+    resolve_object(doc, obj).as_dict().ok()
+}
+
+/// The outline entries as (page, label), in page order. Of entries on the same
+/// page, the last one in the outline (the deepest) comes last.
+fn page_sections(toc: &[NavPointJson]) -> Vec<(u32, String)> {
+    fn walk(items: &[NavPointJson], sections: &mut Vec<(u32, String)>) {
+        for item in items {
+            if let Some(page) = pdf_page_number(&item.content)
+                && !item.label.is_empty() {
+                    sections.push((page as u32, item.label.clone()));
+                }
+            walk(&item.children, sections);
+        }
+    }
+    let mut sections = Vec::new();
+    walk(toc, &mut sections);
+    sections.sort_by_key(|(page, _)| *page);
+    sections
+}
+
+/// The label of the outline section which a page is in.
+fn section_of_page(sections: &[(u32, String)], page: u32) -> Option<&str> {
+    sections.iter()
+        .rev()
+        .find(|(start, _)| *start <= page)
+        .map(|(_, label)| label.as_str())
+}
+
 /// Extract metadata field from PDF document
 pub fn extract_pdf_metadata(doc: &Document, key: &[u8]) -> Option<String> {
     // Get the Info dictionary reference - convert Result to Option
@@ -281,3 +578,34 @@ pub fn extract_xmp_author(doc: &Document) -> Option<String> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nav(label: &str, page: u32, children: Vec<NavPointJson>) -> NavPointJson {
+        NavPointJson { label: label.to_string(), content: pdf_page_resource_path(page), children }
+    }
+
+    #[test]
+    fn test_pdf_page_number() {
+        assert_eq!(pdf_page_number("document.pdf#page=12"), Some(12));
+        assert_eq!(pdf_page_number(&pdf_page_resource_path(3)), Some(3));
+        assert_eq!(pdf_page_number("document.pdf"), None);
+        assert_eq!(pdf_page_number("document.pdf#page=0"), None);
+        assert_eq!(pdf_page_number("OEBPS/ch1.xhtml"), None);
+    }
+
+    #[test]
+    fn test_section_of_page() {
+        let toc = vec![
+            nav("Introduction", 2, vec![]),
+            nav("Part One", 5, vec![nav("Chapter 1", 5, vec![]), nav("Chapter 2", 9, vec![])]),
+        ];
+        let sections = page_sections(&toc);
+        assert_eq!(section_of_page(&sections, 1), None);
+        assert_eq!(section_of_page(&sections, 3), Some("Introduction"));
+        assert_eq!(section_of_page(&sections, 5), Some("Chapter 1"));
+        assert_eq!(section_of_page(&sections, 12), Some("Chapter 2"));
+    }
+}
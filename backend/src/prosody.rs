@@ -0,0 +1,629 @@
+//! Pāli prosody: syllabification, syllable weight (garu / lahu) and metre.
+//!
+//! A syllable is garu (heavy) when its vowel is long (ā ī ū e o), when it
+//! ends in niggahīta (ṁ / ṃ), or when a consonant cluster follows it; all
+//! other syllables are lahu (light). Aspirated stops (kh, gh, ch, ...) count
+//! as a single consonant. As in recitation, weight is computed across word
+//! boundaries within a line: a final short open syllable becomes garu when
+//! the next word begins with a cluster.
+//!
+//! [`scan_verses`] splits verse text into pādas, identifies the metre
+//! (siloka, tuṭṭhubha, jagatī, vetālīya, opacchandasaka) from the cadences and
+//! mattā counts, and flags the pādas that do not fit it.
+//! [`render_scansion_html`] renders the result as an overlay of the text.
+
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::consistent_niggahita;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyllableWeight {
+    Garu,
+    Lahu,
+}
+
+impl SyllableWeight {
+    /// Length in mattā (morae): garu counts two, lahu one.
+    pub fn morae(self) -> u32 {
+        match self {
+            SyllableWeight::Garu => 2,
+            SyllableWeight::Lahu => 1,
+        }
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            SyllableWeight::Garu => '–',
+            SyllableWeight::Lahu => '⏑',
+        }
+    }
+}
+
+/// One syllable of a line. `text` is the syllable as written, including any
+/// punctuation attached to the word, so that joining the syllables of a word
+/// reproduces the word. Tokens without a vowel (dashes, numbers) become a
+/// single syllable with no weight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Syllable {
+    pub text: String,
+    pub word_index: usize,
+    pub weight: Option<SyllableWeight>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phone {
+    Vowel { long: bool },
+    Consonant,
+    Niggahita,
+    Other,
+}
+
+/// A sound unit of a word with its byte offset in the word.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    phone: Phone,
+    start: usize,
+}
+
+fn classify(c: char) -> Phone {
+    match c {
+        'a' | 'i' | 'u' => Phone::Vowel { long: false },
+        'ā' | 'ī' | 'ū' | 'e' | 'o' => Phone::Vowel { long: true },
+        'ṁ' | 'ṃ' | 'ŋ' => Phone::Niggahita,
+        c if c.is_alphabetic() => Phone::Consonant,
+        _ => Phone::Other,
+    }
+}
+
+fn takes_aspiration(c: char) -> bool {
+    matches!(c, 'k' | 'g' | 'c' | 'j' | 'ṭ' | 'ḍ' | 't' | 'd' | 'p' | 'b' | 'ḷ')
+}
+
+fn segments(word: &str) -> Vec<Segment> {
+    let lower: Vec<(usize, char)> = word
+        .char_indices()
+        .map(|(i, c)| (i, c.to_lowercase().next().unwrap_or(c)))
+        .collect();
+
+    let mut out = Vec::with_capacity(lower.len());
+    let mut i = 0;
+    while i < lower.len() {
+        let (start, c) = lower[i];
+        let mut next = i + 1;
+        let phone = classify(c);
+        if phone == Phone::Consonant && takes_aspiration(c) && lower.get(next).is_some_and(|&(_, h)| h == 'h') {
+            next += 1;
+        }
+        out.push(Segment { phone, start });
+        i = next;
+    }
+    out
+}
+
+/// Number of consonants at the start of a word, ignoring leading punctuation.
+fn onset_consonants(segs: &[Segment]) -> usize {
+    segs.iter()
+        .filter(|s| s.phone != Phone::Other)
+        .take_while(|s| s.phone == Phone::Consonant)
+        .count()
+}
+
+struct WordSyllables {
+    /// (byte start, byte end, vowel is long, closed by a coda) per syllable.
+    spans: Vec<(usize, usize, bool, bool)>,
+}
+
+fn split_word(word: &str, segs: &[Segment]) -> WordSyllables {
+    let letters: Vec<usize> = (0..segs.len()).filter(|&i| segs[i].phone != Phone::Other).collect();
+    let vowels: Vec<usize> = letters
+        .iter()
+        .copied()
+        .filter(|&i| matches!(segs[i].phone, Phone::Vowel { .. }))
+        .collect();
+
+    if vowels.is_empty() {
+        return WordSyllables { spans: vec![(0, word.len(), false, false)] };
+    }
+
+    // Segment index at which each syllable after the first begins.
+    let mut starts: Vec<usize> = Vec::with_capacity(vowels.len());
+    let mut closed: Vec<bool> = Vec::with_capacity(vowels.len());
+    for pair in vowels.windows(2) {
+        let between: Vec<usize> = letters.iter().copied().filter(|&i| i > pair[0] && i < pair[1]).collect();
+        let coda_niggahita = between.first().is_some_and(|&i| segs[i].phone == Phone::Niggahita);
+        let consonants = between.iter().filter(|&&i| segs[i].phone == Phone::Consonant).count();
+        let (start, is_closed) = if coda_niggahita {
+            (between.get(1).copied().unwrap_or(pair[1]), true)
+        } else if consonants >= 2 {
+            (between[1], true)
+        } else {
+            (between.first().copied().unwrap_or(pair[1]), false)
+        };
+        starts.push(start);
+        closed.push(is_closed);
+    }
+    let last_vowel = *vowels.last().unwrap_or(&0);
+    closed.push(letters.iter().any(|&i| i > last_vowel));
+
+    let mut spans = Vec::with_capacity(vowels.len());
+    let mut byte_start = 0;
+    for (n, &v) in vowels.iter().enumerate() {
+        let long = matches!(segs[v].phone, Phone::Vowel { long: true });
+        let byte_end = starts.get(n).map(|&s| segs[s].start).unwrap_or(word.len());
+        spans.push((byte_start, byte_end, long, closed[n]));
+        byte_start = byte_end;
+    }
+    WordSyllables { spans }
+}
+
+/// Split a line of Pāli into syllables with their metrical weight.
+pub fn syllabify_line(line: &str) -> Vec<Syllable> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let segs: Vec<Vec<Segment>> = words.iter().map(|w| segments(w)).collect();
+
+    let mut out = Vec::new();
+    for (word_index, word) in words.iter().enumerate() {
+        let split = split_word(word, &segs[word_index]);
+        let has_vowel = segs[word_index].iter().any(|s| matches!(s.phone, Phone::Vowel { .. }));
+        let next_onset = segs.get(word_index + 1).map(|s| onset_consonants(s)).unwrap_or(0);
+        let n = split.spans.len();
+
+        for (k, &(start, end, long, closed)) in split.spans.iter().enumerate() {
+            let weight = has_vowel.then(|| {
+                let cluster_follows = k + 1 == n && next_onset >= 2;
+                if long || closed || cluster_follows {
+                    SyllableWeight::Garu
+                } else {
+                    SyllableWeight::Lahu
+                }
+            });
+            out.push(Syllable {
+                text: word[start..end].to_string(),
+                word_index,
+                weight,
+            });
+        }
+    }
+    out
+}
+
+/// Split a word into syllables (weights as if the word stood alone).
+pub fn syllabify_word(word: &str) -> Vec<Syllable> {
+    syllabify_line(word)
+}
+
+/// The weight pattern of a line as a string of `–` (garu) and `⏑` (lahu).
+pub fn weight_pattern(syllables: &[Syllable]) -> String {
+    syllables.iter().filter_map(|s| s.weight).map(|w| w.symbol()).collect()
+}
+
+// === Metre ===
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metre {
+    Siloka,
+    Tutthubha,
+    Jagati,
+    Vetaliya,
+    Opacchandasaka,
+}
+
+/// Candidate metres, in order of preference when two fit equally well.
+const METRES: [Metre; 5] = [
+    Metre::Siloka,
+    Metre::Tutthubha,
+    Metre::Jagati,
+    Metre::Vetaliya,
+    Metre::Opacchandasaka,
+];
+
+/// How a pāda fits a metre: regular (with an optional note such as the
+/// vipulā variant) or irregular with the reason.
+enum PadaFit {
+    Regular(Option<String>),
+    Irregular(String),
+}
+
+/// Whether `weights` match `pattern`, written with `–` (garu), `⏑` (lahu)
+/// and `x` (either).
+fn matches_pattern(weights: &[SyllableWeight], pattern: &str) -> bool {
+    weights.len() == pattern.chars().count()
+        && weights.iter().zip(pattern.chars()).all(|(w, p)| match p {
+            '–' => *w == SyllableWeight::Garu,
+            '⏑' => *w == SyllableWeight::Lahu,
+            _ => true,
+        })
+}
+
+/// Mattā count of a pāda, the final (anceps) syllable counted as garu.
+fn pada_morae(weights: &[SyllableWeight]) -> u32 {
+    match weights.split_last() {
+        Some((_, rest)) => rest.iter().map(|w| w.morae()).sum::<u32>() + 2,
+        None => 0,
+    }
+}
+
+fn pattern_string(weights: &[SyllableWeight]) -> String {
+    weights.iter().map(|w| w.symbol()).collect()
+}
+
+impl Metre {
+    pub fn name(self) -> &'static str {
+        match self {
+            Metre::Siloka => "Siloka (anuṭṭhubha)",
+            Metre::Tutthubha => "Tuṭṭhubha",
+            Metre::Jagati => "Jagatī",
+            Metre::Vetaliya => "Vetālīya",
+            Metre::Opacchandasaka => "Opacchandasaka",
+        }
+    }
+
+    /// Check one pāda; `index` counts from 0 within the verse, so even
+    /// indexes are the odd (first, third) pādas.
+    fn check_pada(self, weights: &[SyllableWeight], index: usize) -> PadaFit {
+        let n = weights.len();
+        let is_odd_pada = index.is_multiple_of(2);
+        match self {
+            Metre::Siloka => {
+                if n != 8 {
+                    return PadaFit::Irregular(format!("{} syllables, a siloka pāda has 8", n));
+                }
+                let cadence = &weights[4..7];
+                if !is_odd_pada {
+                    return if matches_pattern(cadence, "⏑–⏑") {
+                        PadaFit::Regular(None)
+                    } else {
+                        PadaFit::Irregular("syllables 5–7 of an even pāda should be ⏑–⏑".to_string())
+                    };
+                }
+                let vipula = [("⏑⏑⏑", "na-vipulā"), ("–⏑⏑", "bha-vipulā"), ("–––", "ma-vipulā"), ("–⏑–", "ra-vipulā")];
+                if matches_pattern(cadence, "⏑––") {
+                    PadaFit::Regular(None)
+                } else if let Some((_, name)) = vipula.iter().find(|(p, _)| matches_pattern(cadence, p)) {
+                    PadaFit::Regular(Some(name.to_string()))
+                } else {
+                    PadaFit::Irregular("syllables 5–7 of an odd pāda should be ⏑––".to_string())
+                }
+            }
+            Metre::Tutthubha | Metre::Jagati => {
+                let own = if self == Metre::Tutthubha { 11 } else { 12 };
+                if n != 11 && n != 12 {
+                    return PadaFit::Irregular(format!("{} syllables, a {} pāda has {}", n, self.name().to_lowercase(), own));
+                }
+                // Both end in –⏑–x; mixed tuṭṭhubha–jagatī verses are common.
+                if !matches_pattern(&weights[n - 4..n - 1], "–⏑–") {
+                    return PadaFit::Irregular("the cadence should be –⏑–x".to_string());
+                }
+                if n == own {
+                    PadaFit::Regular(None)
+                } else if n == 11 {
+                    PadaFit::Regular(Some("tuṭṭhubha pāda".to_string()))
+                } else {
+                    PadaFit::Regular(Some("jagatī pāda".to_string()))
+                }
+            }
+            Metre::Vetaliya | Metre::Opacchandasaka => {
+                let (cadence, odd_morae) = if self == Metre::Vetaliya { ("–⏑–⏑x", 14) } else { ("–⏑–⏑–x", 16) };
+                let expected = if is_odd_pada { odd_morae } else { odd_morae + 2 };
+                let len = cadence.chars().count();
+                if n < len || !matches_pattern(&weights[n - len..], cadence) {
+                    return PadaFit::Irregular(format!("the cadence should be {}", cadence));
+                }
+                let morae = pada_morae(weights);
+                if morae == expected {
+                    PadaFit::Regular(None)
+                } else {
+                    PadaFit::Irregular(format!("{} mattā, expected {}", morae, expected))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PadaScan {
+    pub text: String,
+    pub syllables: Vec<Syllable>,
+    /// Weight pattern, e.g. `⏑–––⏑–––`.
+    pub pattern: String,
+    /// Mattā count with the final syllable counted as garu.
+    pub morae: u32,
+    /// False when the verse's metre was identified and this pāda breaks it.
+    pub regular: bool,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerseScan {
+    pub metre: Option<Metre>,
+    pub padas: Vec<PadaScan>,
+}
+
+impl VerseScan {
+    pub fn irregular_padas(&self) -> usize {
+        self.padas.iter().filter(|p| !p.regular).count()
+    }
+}
+
+fn weights_of(syllables: &[Syllable]) -> Vec<SyllableWeight> {
+    syllables.iter().filter_map(|s| s.weight).collect()
+}
+
+/// Split a verse line into pādas at punctuation. A line without punctuation
+/// of 16, 22 or 24 syllables (two siloka, tuṭṭhubha or jagatī pādas) is
+/// halved at the word boundary in the middle, if there is one.
+fn split_padas(line: &str) -> Vec<String> {
+    let parts: Vec<String> = line
+        .split([',', ';', ':', '|', '।', '.', '?', '!'])
+        .map(str::trim)
+        .filter(|p| syllabify_line(p).iter().any(|s| s.weight.is_some()))
+        .map(String::from)
+        .collect();
+
+    if parts.len() != 1 {
+        return parts;
+    }
+
+    let syllables = syllabify_line(&parts[0]);
+    let count = syllables.iter().filter(|s| s.weight.is_some()).count();
+    if ![16, 22, 24].contains(&count) {
+        return parts;
+    }
+
+    let words: Vec<&str> = parts[0].split_whitespace().collect();
+    let mut seen = 0;
+    for word_index in 0..words.len() {
+        seen += syllables.iter().filter(|s| s.word_index == word_index && s.weight.is_some()).count();
+        if seen == count / 2 {
+            return vec![words[..=word_index].join(" "), words[word_index + 1..].join(" ")];
+        }
+        if seen > count / 2 {
+            break;
+        }
+    }
+    parts
+}
+
+/// Scan one verse: one or more lines, each holding one or two pādas.
+pub fn scan_verse(text: &str) -> VerseScan {
+    let text = consistent_niggahita(Some(text.to_string()));
+    let pada_texts: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .flat_map(split_padas)
+        .collect();
+
+    let scanned: Vec<(String, Vec<Syllable>, Vec<SyllableWeight>)> = pada_texts
+        .into_iter()
+        .map(|t| {
+            let syllables = syllabify_line(&t);
+            let weights = weights_of(&syllables);
+            (t, syllables, weights)
+        })
+        .collect();
+
+    // The metre with the most regular pādas, then the fewest noted variants.
+    let mut best: Option<(Metre, Vec<PadaFit>, usize, usize)> = None;
+    for metre in METRES {
+        let fits: Vec<PadaFit> = scanned.iter().enumerate().map(|(i, (_, _, w))| metre.check_pada(w, i)).collect();
+        let regular = fits.iter().filter(|f| matches!(f, PadaFit::Regular(_))).count();
+        let plain = fits.iter().filter(|f| matches!(f, PadaFit::Regular(None))).count();
+        if best.as_ref().is_none_or(|(_, _, r, p)| (regular, plain) > (*r, *p)) {
+            best = Some((metre, fits, regular, plain));
+        }
+    }
+    let best = best.filter(|(_, _, regular, _)| *regular > 0 && regular * 2 >= scanned.len());
+
+    let (metre, fits) = match best {
+        Some((metre, fits, _, _)) => (Some(metre), fits),
+        None => (None, scanned.iter().map(|_| PadaFit::Regular(None)).collect()),
+    };
+
+    let padas = scanned
+        .into_iter()
+        .zip(fits)
+        .map(|((text, syllables, weights), fit)| {
+            let (regular, note) = match fit {
+                PadaFit::Regular(note) => (true, note),
+                PadaFit::Irregular(reason) => (false, Some(reason)),
+            };
+            PadaScan {
+                text,
+                pattern: pattern_string(&weights),
+                morae: pada_morae(&weights),
+                syllables,
+                regular,
+                note,
+            }
+        })
+        .collect();
+
+    VerseScan { metre, padas }
+}
+
+/// Scan verse text such as `ChantingSection::content_pali` or a verse
+/// passage of `Sutta::content_plain`. Verses are separated by blank lines.
+pub fn scan_verses(text: &str) -> Vec<VerseScan> {
+    let mut verses = Vec::new();
+    let mut block: Vec<&str> = Vec::new();
+    for line in text.lines().chain(std::iter::once("")) {
+        if line.trim().is_empty() {
+            if !block.is_empty() {
+                let verse = scan_verse(&block.join("\n"));
+                if !verse.padas.is_empty() {
+                    verses.push(verse);
+                }
+                block.clear();
+            }
+        } else {
+            block.push(line);
+        }
+    }
+    verses
+}
+
+/// Style for [`render_scansion_html`]: garu syllables are underlined with a
+/// bar, lahu ones with a dotted line, irregular pādas are marked red.
+pub const SCANSION_CSS: &str = "\
+.scansion .verse { margin-bottom: 1em; }
+.scansion .metre { font-size: small; font-weight: bold; color: #5c6bc0; }
+.scansion .pada { margin-left: 1em; }
+.scansion .pada.irregular { color: #c62828; }
+.scansion .garu { text-decoration: underline; }
+.scansion .lahu { text-decoration: none; }
+.scansion .pattern { font-family: monospace; color: #757575; margin-left: 1em; }
+.scansion .note { font-size: small; font-style: italic; color: #757575; margin-left: 0.5em; }
+";
+
+/// Render scanned verses as HTML: each syllable in a `garu` / `lahu` span,
+/// each pāda followed by its weight pattern and any note.
+pub fn render_scansion_html(verses: &[VerseScan]) -> String {
+    let mut html = format!("<style>{}</style><div class=\"scansion\">", SCANSION_CSS);
+    for verse in verses {
+        html.push_str("<div class=\"verse\">");
+        let metre = verse.metre.map(|m| m.name()).unwrap_or("Metre not recognised");
+        html.push_str(&format!("<div class=\"metre\">{}</div>", html_escape::encode_text(metre)));
+
+        for pada in &verse.padas {
+            let class = if pada.regular { "pada" } else { "pada irregular" };
+            html.push_str(&format!("<div class=\"{}\">", class));
+            let mut prev_word = None;
+            for syllable in &pada.syllables {
+                if prev_word.is_some_and(|w| w != syllable.word_index) {
+                    html.push(' ');
+                }
+                prev_word = Some(syllable.word_index);
+                let text = html_escape::encode_text(&syllable.text);
+                match syllable.weight {
+                    Some(SyllableWeight::Garu) => html.push_str(&format!("<span class=\"garu\">{}</span>", text)),
+                    Some(SyllableWeight::Lahu) => html.push_str(&format!("<span class=\"lahu\">{}</span>", text)),
+                    None => html.push_str(&text),
+                }
+            }
+            html.push_str(&format!("<span class=\"pattern\">{}</span>", pada.pattern));
+            if let Some(note) = &pada.note {
+                html.push_str(&format!("<span class=\"note\">{}</span>", html_escape::encode_text(note)));
+            }
+            html.push_str("</div>");
+        }
+        html.push_str("</div>");
+    }
+    html.push_str("</div>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(syllables: &[Syllable]) -> Vec<&str> {
+        syllables.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn splits_between_vowels_and_clusters() {
+        assert_eq!(texts(&syllabify_word("bhagavato")), vec!["bha", "ga", "va", "to"]);
+        assert_eq!(texts(&syllabify_word("sammāsambuddhassa")), vec!["sam", "mā", "sam", "bud", "dhas", "sa"]);
+        assert_eq!(texts(&syllabify_word("saṅgham")), vec!["saṅ", "gham"]);
+        assert_eq!(texts(&syllabify_word("buddhaṁ")), vec!["bud", "dhaṁ"]);
+    }
+
+    #[test]
+    fn weights_follow_length_and_position() {
+        assert_eq!(weight_pattern(&syllabify_line("Namo tassa bhagavato")), "⏑––⏑⏑⏑⏑–");
+        assert_eq!(weight_pattern(&syllabify_line("arahato sammāsambuddhassa")), "⏑⏑⏑––––––⏑");
+        // Niggahīta closes the syllable.
+        assert_eq!(weight_pattern(&syllabify_line("saraṇaṁ gacchāmi")), "⏑⏑–––⏑");
+    }
+
+    #[test]
+    fn cluster_at_next_word_makes_final_syllable_heavy() {
+        let syllables = syllabify_line("ca brahma");
+        assert_eq!(syllables[0].weight, Some(SyllableWeight::Garu));
+        let alone = syllabify_line("ca");
+        assert_eq!(alone[0].weight, Some(SyllableWeight::Lahu));
+    }
+
+    #[test]
+    fn punctuation_stays_attached() {
+        let syllables = syllabify_line("“Evaṁ me sutaṁ –");
+        let rebuilt: String = syllables.iter().filter(|s| s.word_index == 0).map(|s| s.text.as_str()).collect();
+        assert_eq!(rebuilt, "“Evaṁ");
+        assert_eq!(syllables.last().unwrap().text, "–");
+        assert_eq!(syllables.last().unwrap().weight, None);
+    }
+
+    const DHP_1: &str = "Manopubbaṅgamā dhammā, manoseṭṭhā manomayā;
+Manasā ce paduṭṭhena, bhāsati vā karoti vā;
+Tato naṁ dukkhamanveti, cakkaṁva vahato padaṁ.";
+
+    #[test]
+    fn dhammapada_1_is_siloka() {
+        let verse = scan_verse(DHP_1);
+        assert_eq!(verse.metre, Some(Metre::Siloka));
+        assert_eq!(verse.padas.len(), 6);
+        assert_eq!(verse.padas[0].text, "Manopubbaṅgamā dhammā");
+        assert_eq!(verse.padas[0].pattern, "⏑–––⏑–––");
+        assert_eq!(verse.irregular_padas(), 0);
+    }
+
+    #[test]
+    fn unpunctuated_siloka_lines_are_halved() {
+        let verse = scan_verse("Manopubbaṅgamā dhammā manoseṭṭhā manomayā");
+        let texts: Vec<&str> = verse.padas.iter().map(|p| p.text.as_str()).collect();
+        assert_eq!(texts, vec!["Manopubbaṅgamā dhammā", "manoseṭṭhā manomayā"]);
+    }
+
+    #[test]
+    fn irregular_pada_is_flagged() {
+        // Even pāda with a long 7th syllable: manomāyā.
+        let verse = scan_verse("Manopubbaṅgamā dhammā, manoseṭṭhā manomāyā;\nManasā ce paduṭṭhena, bhāsati vā karoti vā;");
+        assert_eq!(verse.metre, Some(Metre::Siloka));
+        assert!(!verse.padas[1].regular);
+        assert!(verse.padas.iter().enumerate().all(|(i, p)| p.regular || i == 1));
+    }
+
+    #[test]
+    fn ratana_sutta_is_tutthubha() {
+        let verse = scan_verse("Yaṁ kiñci vittaṁ idha vā huraṁ vā
+Saggesu vā yaṁ ratanaṁ paṇītaṁ
+Na no samaṁ atthi tathāgatena
+Idampi buddhe ratanaṁ paṇītaṁ
+Etena saccena suvatthi hotu");
+        assert_eq!(verse.metre, Some(Metre::Tutthubha));
+        assert_eq!(verse.irregular_padas(), 0);
+    }
+
+    #[test]
+    fn uraga_sutta_is_opacchandasaka() {
+        let verse = scan_verse("Yo uppatitaṁ vineti kodhaṁ, visaṭaṁ sappavisaṁva osadhehi;
+So bhikkhu jahāti orapāraṁ, urago jiṇṇamivattacaṁ purāṇaṁ.");
+        assert_eq!(verse.metre, Some(Metre::Opacchandasaka));
+        let morae: Vec<u32> = verse.padas.iter().map(|p| p.morae).collect();
+        assert_eq!(morae, vec![16, 18, 16, 18]);
+        assert_eq!(verse.irregular_padas(), 0);
+    }
+
+    #[test]
+    fn prose_has_no_metre() {
+        let verse = scan_verse("Evaṁ me sutaṁ. Ekaṁ samayaṁ bhagavā sāvatthiyaṁ viharati jetavane anāthapiṇḍikassa ārāme.");
+        assert_eq!(verse.metre, None);
+        assert_eq!(verse.irregular_padas(), 0);
+    }
+
+    #[test]
+    fn verses_split_on_blank_lines_and_render() {
+        let text = format!("{}\n\nYaṁ kiñci vittaṁ idha vā huraṁ vā\nSaggesu vā yaṁ ratanaṁ paṇītaṁ", DHP_1);
+        let verses = scan_verses(&text);
+        assert_eq!(verses.len(), 2);
+
+        let html = render_scansion_html(&verses);
+        assert!(html.contains("Siloka (anuṭṭhubha)"));
+        assert!(html.contains("<span class=\"lahu\">Ma</span><span class=\"garu\">no</span>"));
+        assert!(html.contains("<span class=\"garu\">dham</span>"));
+        assert!(!html.contains("class=\"pada irregular\""));
+    }
+}
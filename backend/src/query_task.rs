@@ -1,5 +1,5 @@
 // use std::any::Any;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::time::Instant;
 
@@ -10,12 +10,14 @@ use diesel::sql_types::{Text, BigInt};
 
 use crate::helpers::{normalize_plain_text, normalize_query_text, pali_to_ascii, remove_inter_word_hyphens, strip_html, sutta_range_from_ref};
 use crate::highlight::{literal_ranges, wrap_ranges};
+use crate::pali_phonetic::MAX_FUZZY_DISTANCE;
 use crate::{get_app_data, get_app_globals};
 use crate::types::{SearchArea, SearchMode, SearchParams, SearchResult};
 use crate::db::appdata_models::{Sutta, BookSpineItem};
 use crate::db::dictionaries_models::DictWord;
 use crate::db::DbManager;
 use crate::logger::{debug, info, warn, error};
+use crate::headword_index::dict_words_completion_list;
 
 /// Defense-in-depth ceiling on SQL `LIMIT` for unbounded multi-phase fetches
 /// (e.g. dict_words_contains_match_fts5's per-phase intermediate fetch). Real
@@ -24,6 +26,14 @@ use crate::logger::{debug, info, warn, error};
 /// yet expressed as a single paged SQL query.
 pub(crate) const SAFETY_LIMIT_SQL: i64 = 50_000;
 
+/// The most DPD headwords a fuzzy Headword Match adds by their phonetic key.
+const PHONETIC_HEADWORDS_LIMIT: usize = 20;
+
+/// A DPD `lemma_1` without its homonym number: `dhamma 1.01` → `dhamma`.
+fn headword_without_number(word: &str) -> &str {
+    word.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.').trim_end()
+}
+
 /// Sanitize a user-supplied prefix for direct embedding in a SQL LIKE pattern.
 /// Returns `Some(prefix_lowercase)` if the input is non-empty and contains only
 /// safe characters (alphanumeric, dot, hyphen, underscore, slash); otherwise `None`.
@@ -105,6 +115,7 @@ pub struct SearchQueryTask<'a> {
     pub include_comm_bold_definitions: bool,
     pub dict_source_uids: Option<Vec<String>>,
     pub show_all_snippets: bool,
+    pub fuzzy_distance: u8,
     pub snippet_exclude: Option<Vec<String>>,
     pub snippet_chars_before: usize,
     pub snippet_chars_after: usize,
@@ -166,6 +177,7 @@ impl<'a> SearchQueryTask<'a> {
             include_comm_bold_definitions: params.include_comm_bold_definitions,
             dict_source_uids: params.dict_source_uids.clone(),
             show_all_snippets: params.show_all_snippets,
+            fuzzy_distance: params.fuzzy_distance.clamp(0, MAX_FUZZY_DISTANCE as i32) as u8,
             snippet_exclude: params.snippet_exclude.clone(),
             snippet_chars_before: get_app_data().get_snippet_chars_before(),
             snippet_chars_after: get_app_data().get_snippet_chars_after(),
@@ -2020,6 +2032,11 @@ impl<'a> SearchQueryTask<'a> {
     /// materialised result length is the authoritative `total`
     /// (materialise-then-slice; PRD §2.5 contract preserved).
     ///
+    /// With a fuzzy distance, the DPD headwords with a Pāli phonetic key close
+    /// to the query's (`CompletionList::phonetic_matches()`) are matched too,
+    /// as `word` or `word <homonym number>`, and ranked after the substring
+    /// tiers by their edit distance. See docs/fuzzy-phonetic-search.md.
+    ///
     /// `dict_source_uids` contract:
     ///   - None             → search every dict (DPD + user).
     ///   - Some(non-empty)  → restrict via `dict_label IN (set)`.
@@ -2041,6 +2058,14 @@ impl<'a> SearchQueryTask<'a> {
         // FTS query.
         let like_pattern = format!("%{}%", self.query_text);
 
+        // The DPD headwords within the fuzzy distance of the query's phonetic key.
+        let phonetic_matches: Vec<(String, usize)> = if self.fuzzy_distance > 0 {
+            dict_words_completion_list(&app_data.dbm)?
+                .phonetic_matches(&self.query_text, self.fuzzy_distance, PHONETIC_HEADWORDS_LIMIT)
+        } else {
+            Vec::new()
+        };
+
         let apply_lang_filter = !self.lang.is_empty() && self.lang != "Language";
 
         // Inclusion-set contract:
@@ -2065,8 +2090,12 @@ impl<'a> SearchQueryTask<'a> {
         let mut sql = String::from(
             "SELECT dw.* FROM dict_words dw \
              JOIN dict_words_fts f ON f.rowid = dw.id \
-             WHERE f.word LIKE ?"
+             WHERE (f.word LIKE ?"
         );
+        for _ in &phonetic_matches {
+            sql.push_str(" OR f.word LIKE ? OR f.word LIKE ?");
+        }
+        sql.push(')');
 
         if self.source.is_some() {
             if self.source_include {
@@ -2092,6 +2121,12 @@ impl<'a> SearchQueryTask<'a> {
             .into_boxed::<diesel::sqlite::Sqlite>()
             .bind::<Text, _>(like_pattern.clone());
 
+        for (word, _) in &phonetic_matches {
+            q = q
+                .bind::<Text, _>(word.clone())
+                .bind::<Text, _>(format!("{} %", word));
+        }
+
         if let Some(ref source_val) = self.source {
             q = q.bind::<Text, _>(source_val.clone());
         }
@@ -2121,15 +2156,33 @@ impl<'a> SearchQueryTask<'a> {
         }
 
         // Merge ordering tiers: exact `word == query_text` first, then
-        // starts-with, then contains; tie-break by dict_label then id.
+        // starts-with, then contains, then the phonetic matches by their
+        // distance; tie-break by dict_label then id.
         // The starts-with tier is what makes "gacch" surface `gacchati`,
         // `gacchanti`, ... before inflected forms like `abhigacchati`.
         let qt = self.query_text.as_str();
+        let qt_lower = qt.to_lowercase();
+        let phonetic_distances: HashMap<&str, usize> = phonetic_matches
+            .iter()
+            .map(|(word, distance)| (word.as_str(), *distance))
+            .collect();
+        let phonetic_distance = |w: &str| -> usize {
+            phonetic_distances.get(headword_without_number(w)).copied().unwrap_or(usize::MAX)
+        };
         let tier = |w: &str| -> u8 {
-            if w == qt { 3 } else if w.starts_with(qt) { 2 } else { 1 }
+            if w == qt {
+                3
+            } else if w.starts_with(qt) {
+                2
+            } else if phonetic_distance(w) != usize::MAX && !w.to_lowercase().contains(&qt_lower) {
+                0
+            } else {
+                1
+            }
         };
         all_rows.sort_by(|a, b| {
             tier(&b.word).cmp(&tier(&a.word))
+                .then_with(|| phonetic_distance(&a.word).cmp(&phonetic_distance(&b.word)))
                 .then_with(|| a.dict_label.cmp(&b.dict_label))
                 .then_with(|| a.id.cmp(&b.id))
         });
@@ -2169,6 +2222,7 @@ impl<'a> SearchQueryTask<'a> {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: self.show_all_snippets,
+            fuzzy_distance: self.fuzzy_distance,
         };
 
         let query_text = self.query_text.clone();
@@ -2221,6 +2275,7 @@ impl<'a> SearchQueryTask<'a> {
             include_bold_definitions: self.include_comm_bold_definitions,
             dict_source_uids: self.dict_source_uids.clone(),
             show_all_snippets: false,
+            fuzzy_distance: self.fuzzy_distance,
         };
 
         let query_text = self.query_text.clone();
@@ -2266,11 +2321,12 @@ impl<'a> SearchQueryTask<'a> {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: self.show_all_snippets,
+            fuzzy_distance: self.fuzzy_distance,
         };
 
         let query_text = self.query_text.clone();
 
-        let (total, results) = match with_fulltext_searcher(|searcher| {
+        let (total, mut results) = match with_fulltext_searcher(|searcher| {
             if !searcher.has_library_indexes() {
                 warn("No library fulltext indexes available.");
                 return Ok((0usize, Vec::new()));
@@ -2285,9 +2341,41 @@ impl<'a> SearchQueryTask<'a> {
             }
         };
 
+        Self::set_pdf_page_numbers(&mut results)?;
+
         Ok((results, total))
     }
 
+    /// The library index doesn't store the resource path, so the page of a
+    /// PDF page hit is looked up in the database.
+    fn set_pdf_page_numbers(results: &mut [SearchResult]) -> Result<(), Box<dyn Error>> {
+        use crate::db::appdata_schema::book_spine_items::dsl::*;
+        use crate::pdf_import::pdf_page_number;
+        use std::collections::HashMap;
+
+        if results.is_empty() {
+            return Ok(());
+        }
+        let uids: Vec<&str> = results.iter().map(|r| r.uid.as_str()).collect();
+        let app_data = get_app_data();
+        let db_conn = &mut app_data.dbm.appdata.get_conn()?;
+        let pages: HashMap<String, i32> = book_spine_items
+            .filter(spine_item_uid.eq_any(&uids))
+            .filter(resource_path.like("document.pdf#page=%"))
+            .select((spine_item_uid, resource_path))
+            .load::<(String, String)>(db_conn)?
+            .into_iter()
+            .filter_map(|(uid, path)| pdf_page_number(&path).map(|page| (uid, page)))
+            .collect();
+
+        for r in results.iter_mut() {
+            if let Some(page) = pages.get(&r.uid) {
+                r.page_number = Some(*page);
+            }
+        }
+        Ok(())
+    }
+
     /// Boundary-aware page splitter for two concatenated streams: regular
     /// rows first (count `regular_total`), then bold-definition rows (count
     /// `bold_total`). For the requested page `[page_num*page_len .. +page_len)`
@@ -2648,7 +2736,7 @@ impl<'a> SearchQueryTask<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{normalize_for_exclude, snippet_is_excluded};
+    use super::{headword_without_number, normalize_for_exclude, snippet_is_excluded};
 
     fn excludes(terms: &[&str]) -> Vec<String> {
         terms.iter().map(|t| normalize_for_exclude(t)).collect()
@@ -2696,4 +2784,11 @@ mod tests {
     fn empty_exclude_list_is_noop() {
         assert!(!snippet_is_excluded("anything at all", &[]));
     }
+
+    #[test]
+    fn headword_homonym_number_is_removed() {
+        assert_eq!(headword_without_number("dhamma 1.01"), "dhamma");
+        assert_eq!(headword_without_number("satipaṭṭhāna 1"), "satipaṭṭhāna");
+        assert_eq!(headword_without_number("satipaṭṭhāna"), "satipaṭṭhāna");
+    }
 }
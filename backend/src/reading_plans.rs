@@ -0,0 +1,431 @@
+//! Reading plans: ordered lists of suttas and book chapters, read one day at
+//! a time ("a sutta a day", "the Majjhima in a year").
+//!
+//! Plans are shared as JSON files in the format of `ReadingPlanJson`, so that
+//! teachers can distribute them. The generators build the item list of a plan
+//! from a sutta range walked with `get_next_sutta()`, from a part of the
+//! `suttas.group_path` tree, or from the chapters of a library book.
+//! The plans, enrollments and completions are stored in appdata, see
+//! `AppdataDbHandle::save_reading_plan()`.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::db::appdata::AppdataDbHandle;
+use crate::db::appdata_models::Sutta;
+
+/// The `format` field of a plan JSON file.
+pub const PLAN_FORMAT: &str = "simsapa-reading-plan";
+pub const PLAN_FORMAT_VERSION: u32 = 1;
+
+/// An upper limit on the length of a generated plan, in case the sutta
+/// navigation goes around in a loop.
+pub const MAX_GENERATED_ITEMS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanSchedule {
+    /// Each item has a day, counted from the reader's start date.
+    Daily,
+    /// The next unread item, at the reader's own pace.
+    Sequential,
+}
+
+impl PlanSchedule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanSchedule::Daily => "daily",
+            PlanSchedule::Sequential => "sequential",
+        }
+    }
+}
+
+impl std::str::FromStr for PlanSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "daily" => Ok(PlanSchedule::Daily),
+            "sequential" => Ok(PlanSchedule::Sequential),
+            _ => Err(anyhow!("Unknown reading plan schedule: {}", s)),
+        }
+    }
+}
+
+/// A reading plan as a JSON file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingPlanJson {
+    #[serde(default = "default_format")]
+    pub format: String,
+    #[serde(default = "default_version")]
+    pub version: u32,
+    pub uid: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub schedule: PlanSchedule,
+    pub items: Vec<ReadingPlanItemJson>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingPlanItemJson {
+    /// The day of the item, starting with 1. Items without a day are read on
+    /// the day after the previous item.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day: Option<i32>,
+    /// A sutta uid (mn10/en/sujato) or a book spine item uid
+    pub item_uid: String,
+    /// "suttas" or "book_spine_items"
+    #[serde(default = "default_table_name")]
+    pub table_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// A plan with the reader's enrollment in it, as kept in the import-me folder
+/// through database upgrades.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnrolledReadingPlanJson {
+    pub plan: ReadingPlanJson,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enrollment: Option<ReadingPlanEnrollmentJson>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingPlanEnrollmentJson {
+    pub start_date: NaiveDate,
+    pub is_active: bool,
+    /// The item indexes marked completed
+    pub completed_items: Vec<i32>,
+}
+
+fn default_format() -> String {
+    PLAN_FORMAT.to_string()
+}
+
+fn default_version() -> u32 {
+    PLAN_FORMAT_VERSION
+}
+
+fn default_table_name() -> String {
+    "suttas".to_string()
+}
+
+impl ReadingPlanJson {
+    /// A new plan of `items`. With `days`, the items are spread evenly over
+    /// that many days, otherwise there is one item a day.
+    pub fn new(uid: &str, title: &str, schedule: PlanSchedule, mut items: Vec<ReadingPlanItemJson>, days: Option<u32>) -> Self {
+        let day_numbers = spread_over_days(items.len(), days.unwrap_or(0));
+        for (item, day) in items.iter_mut().zip(day_numbers) {
+            item.day = Some(day);
+        }
+        ReadingPlanJson {
+            format: default_format(),
+            version: default_version(),
+            uid: uid.to_string(),
+            title: title.to_string(),
+            description: None,
+            author: None,
+            schedule,
+            items,
+        }
+    }
+
+    /// Check the plan and number the items without a day.
+    pub fn validate(&mut self) -> Result<()> {
+        if self.format != PLAN_FORMAT {
+            bail!("Not a reading plan file: format is '{}'", self.format);
+        }
+        if self.version > PLAN_FORMAT_VERSION {
+            bail!("Reading plan format version {} is newer than supported ({})", self.version, PLAN_FORMAT_VERSION);
+        }
+        self.uid = self.uid.trim().to_string();
+        if self.uid.is_empty() || !self.uid.chars().all(|c| c.is_alphanumeric() || "-_.".contains(c)) {
+            bail!("Invalid reading plan uid: '{}'", self.uid);
+        }
+        if self.title.trim().is_empty() {
+            bail!("Reading plan {} has no title", self.uid);
+        }
+        if self.items.is_empty() {
+            bail!("Reading plan {} has no items", self.uid);
+        }
+
+        let mut prev_day = 0;
+        for (n, item) in self.items.iter_mut().enumerate() {
+            if item.item_uid.trim().is_empty() {
+                bail!("Item {} of reading plan {} has no uid", n + 1, self.uid);
+            }
+            if item.table_name != "suttas" && item.table_name != "book_spine_items" {
+                bail!("Item {} of reading plan {} has an unknown table: {}", n + 1, self.uid, item.table_name);
+            }
+            let day = item.day.unwrap_or(prev_day + 1);
+            if day < 1 || day < prev_day {
+                bail!("Item {} of reading plan {} is out of order: day {}", n + 1, self.uid, day);
+            }
+            item.day = Some(day);
+            prev_day = day;
+        }
+        Ok(())
+    }
+
+    /// The number of days of the plan.
+    pub fn days_total(&self) -> i32 {
+        self.items.iter().filter_map(|i| i.day).max().unwrap_or(0)
+    }
+}
+
+/// Parse and validate a plan JSON file's contents.
+pub fn parse_plan_json(json: &str) -> Result<ReadingPlanJson> {
+    let mut plan: ReadingPlanJson = serde_json::from_str(json)
+        .map_err(|e| anyhow!("Failed to parse the reading plan: {}", e))?;
+    plan.validate()?;
+    Ok(plan)
+}
+
+pub fn plan_to_json(plan: &ReadingPlanJson) -> Result<String> {
+    Ok(serde_json::to_string_pretty(plan)?)
+}
+
+/// The day numbers, starting with 1, of `count` items spread evenly over
+/// `days` days. With 0 days, one item a day.
+pub fn spread_over_days(count: usize, days: u32) -> Vec<i32> {
+    let days = days as usize;
+    (0..count)
+        .map(|n| {
+            if days == 0 {
+                n as i32 + 1
+            } else {
+                (n * days / count) as i32 + 1
+            }
+        })
+        .collect()
+}
+
+/// The suttas from `first_uid` to `last_uid`, following `get_next_sutta()`.
+/// Without a `last_uid`, to the end of the nikāya of the first sutta. The
+/// texts stay in the language and source of the first sutta where they exist.
+pub fn sutta_range_plan_items(appdata: &AppdataDbHandle, first_uid: &str, last_uid: Option<&str>) -> Result<Vec<ReadingPlanItemJson>> {
+    let first = appdata.get_sutta(first_uid)
+        .ok_or_else(|| anyhow!("Sutta not found: {}", first_uid))?;
+    let last_ref = last_uid.map(|u| sutta_ref_uid(u).to_lowercase());
+
+    let mut items = Vec::new();
+    let mut seen = HashSet::new();
+    let mut current = Some(first.clone());
+    while let Some(sutta) = current {
+        let ref_uid = sutta_ref_uid(&sutta.uid).to_string();
+        if last_uid.is_none() && sutta.nikaya != first.nikaya {
+            break;
+        }
+        if !seen.insert(ref_uid.clone()) || items.len() >= MAX_GENERATED_ITEMS {
+            break;
+        }
+        items.push(ReadingPlanItemJson {
+            day: None,
+            item_uid: sutta.uid.clone(),
+            table_name: "suttas".to_string(),
+            title: sutta.title.clone(),
+        });
+        if last_ref.as_deref() == Some(ref_uid.as_str()) {
+            break;
+        }
+        current = appdata.get_next_sutta(&sutta.uid)?
+            .map(|next| in_language_of(appdata, &first, next));
+    }
+    Ok(items)
+}
+
+/// The text of `sutta` in the language and source of `first`, if it exists.
+/// `get_next_sutta()` follows the previous text, which may be a fallback.
+fn in_language_of(appdata: &AppdataDbHandle, first: &Sutta, sutta: Sutta) -> Sutta {
+    if sutta.language == first.language && sutta.source_uid == first.source_uid {
+        return sutta;
+    }
+    match &first.source_uid {
+        Some(source) => appdata
+            .get_sutta(&format!("{}/{}/{}", sutta_ref_uid(&sutta.uid), first.language, source))
+            .unwrap_or(sutta),
+        None => sutta,
+    }
+}
+
+/// All the suttas of a nikāya (e.g. "mn"), in the given language and source
+/// (e.g. "en", "sujato") where a text exists.
+pub fn nikaya_plan_items(appdata: &AppdataDbHandle, nikaya: &str, language: &str, source_uid: Option<&str>) -> Result<Vec<ReadingPlanItemJson>> {
+    let first = appdata.get_first_sutta_of_nikaya(nikaya, language, source_uid)?
+        .ok_or_else(|| anyhow!("No suttas found for nikāya: {}", nikaya))?;
+    sutta_range_plan_items(appdata, &first.uid, None)
+}
+
+/// The suttas under a part of the `group_path` tree of the CST texts, in their
+/// order.
+pub fn group_path_plan_items(appdata: &AppdataDbHandle, group_path_prefix: &str) -> Result<Vec<ReadingPlanItemJson>> {
+    let suttas = appdata.get_suttas_by_group_path_prefix(group_path_prefix)?;
+    if suttas.is_empty() {
+        bail!("No suttas found under: {}", group_path_prefix);
+    }
+    Ok(suttas
+        .into_iter()
+        .take(MAX_GENERATED_ITEMS)
+        .map(|s| ReadingPlanItemJson {
+            day: None,
+            item_uid: s.uid,
+            table_name: "suttas".to_string(),
+            title: s.title,
+        })
+        .collect())
+}
+
+/// The chapters of a library book, in spine order.
+pub fn book_plan_items(appdata: &AppdataDbHandle, book_uid: &str) -> Result<Vec<ReadingPlanItemJson>> {
+    let spine_items = appdata.get_spine_items_for_book(book_uid)?;
+    if spine_items.is_empty() {
+        bail!("No chapters found for book: {}", book_uid);
+    }
+    Ok(spine_items
+        .into_iter()
+        .map(|s| ReadingPlanItemJson {
+            day: None,
+            item_uid: s.spine_item_uid,
+            table_name: "book_spine_items".to_string(),
+            title: s.title,
+        })
+        .collect())
+}
+
+/// Read, check and save a plan JSON file.
+pub fn import_plan_file(appdata: &AppdataDbHandle, path: &Path) -> Result<ReadingPlanJson> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let plan = parse_plan_json(&json)?;
+    appdata.save_reading_plan(&plan)?;
+    Ok(plan)
+}
+
+/// Write a saved plan to a JSON file.
+pub fn export_plan_file(appdata: &AppdataDbHandle, plan_uid: &str, path: &Path) -> Result<()> {
+    let plan = appdata.get_reading_plan(plan_uid)?
+        .ok_or_else(|| anyhow!("Reading plan not found: {}", plan_uid))?;
+    fs::write(path, plan_to_json(&plan)?)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Today in the local time zone, the day of the plans.
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+/// Parse a YYYY-MM-DD date, an empty string is today.
+pub fn parse_plan_date(date: &str) -> Result<NaiveDate> {
+    let date = date.trim();
+    if date.is_empty() {
+        return Ok(today());
+    }
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| anyhow!("Invalid date '{}': {}", date, e))
+}
+
+/// Where the items of a generated plan come from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum PlanItemsSource {
+    /// All suttas of a nikāya, e.g. "mn"
+    Nikaya { nikaya: String, language: String, source_uid: Option<String> },
+    /// From a sutta to another, e.g. "sn56.1/en/sujato" to "sn56.20/en/sujato"
+    SuttaRange { first_uid: String, last_uid: Option<String> },
+    /// The suttas under a part of the `group_path` tree
+    GroupPath { group_path: String },
+    /// The chapters of a library book
+    Book { book_uid: String },
+}
+
+/// The options of `generate_plan()`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanGeneratorOptions {
+    pub uid: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    pub schedule: PlanSchedule,
+    /// Spread the items over this many days, otherwise one item a day
+    #[serde(default)]
+    pub days: Option<u32>,
+    pub items: PlanItemsSource,
+}
+
+/// Generate a plan from the options, e.g. "the Majjhima in a year".
+pub fn generate_plan(appdata: &AppdataDbHandle, options: &PlanGeneratorOptions) -> Result<ReadingPlanJson> {
+    let items = match &options.items {
+        PlanItemsSource::Nikaya { nikaya, language, source_uid } =>
+            nikaya_plan_items(appdata, nikaya, language, source_uid.as_deref())?,
+        PlanItemsSource::SuttaRange { first_uid, last_uid } =>
+            sutta_range_plan_items(appdata, first_uid, last_uid.as_deref())?,
+        PlanItemsSource::GroupPath { group_path } =>
+            group_path_plan_items(appdata, group_path)?,
+        PlanItemsSource::Book { book_uid } =>
+            book_plan_items(appdata, book_uid)?,
+    };
+    let mut plan = ReadingPlanJson::new(&options.uid, &options.title, options.schedule, items, options.days);
+    plan.description = options.description.clone();
+    plan.author = options.author.clone();
+    plan.validate()?;
+    Ok(plan)
+}
+
+/// The sutta uid without the /lang/author suffix, mn10/en/sujato is mn10.
+pub fn sutta_ref_uid(uid: &str) -> &str {
+    uid.split('/').next().unwrap_or(uid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spread_over_days() {
+        assert_eq!(spread_over_days(3, 0), vec![1, 2, 3]);
+        assert_eq!(spread_over_days(3, 10), vec![1, 4, 7]);
+        assert_eq!(spread_over_days(6, 3), vec![1, 1, 2, 2, 3, 3]);
+        assert_eq!(spread_over_days(152, 365).last(), Some(&363));
+        assert_eq!(spread_over_days(152, 50).last(), Some(&50));
+    }
+
+    #[test]
+    fn test_parse_plan_json() {
+        let json = r#"{
+            "uid": "sutta-a-day",
+            "title": "A sutta a day",
+            "schedule": "daily",
+            "items": [
+                { "item_uid": "mn1/en/sujato" },
+                { "item_uid": "mn2/en/sujato", "day": 3 },
+                { "item_uid": "mn3/en/sujato" }
+            ]
+        }"#;
+        let plan = parse_plan_json(json).unwrap();
+        assert_eq!(plan.format, PLAN_FORMAT);
+        let days: Vec<i32> = plan.items.iter().filter_map(|i| i.day).collect();
+        assert_eq!(days, vec![1, 3, 4]);
+        assert_eq!(plan.days_total(), 4);
+        assert_eq!(plan.items[0].table_name, "suttas");
+
+        let again = parse_plan_json(&plan_to_json(&plan).unwrap()).unwrap();
+        assert_eq!(again, plan);
+
+        assert!(parse_plan_json(r#"{"uid": "x", "title": "X", "schedule": "daily", "items": []}"#).is_err());
+        assert!(parse_plan_json(r#"{"uid": "a b", "title": "X", "schedule": "daily", "items": [{"item_uid": "mn1"}]}"#).is_err());
+        assert!(parse_plan_json(r#"{"uid": "x", "title": "X", "schedule": "weekly", "items": [{"item_uid": "mn1"}]}"#).is_err());
+        assert!(parse_plan_json(r#"{"uid": "x", "title": "X", "schedule": "daily",
+            "items": [{"item_uid": "mn1", "day": 2}, {"item_uid": "mn2", "day": 1}]}"#).is_err());
+    }
+}
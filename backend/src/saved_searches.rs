@@ -0,0 +1,236 @@
+//! Saved searches: a query with its search area, mode and filters, kept
+//! under a name.
+//!
+//! A saved search can be run again, and when new content is imported (a
+//! language pack, a user dictionary or a library book), all the saved searches
+//! are run to report the results which are new since their last run.
+//!
+//! See docs/saved-searches.md.
+
+use std::collections::HashSet;
+use std::fs;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbManager;
+use crate::db::appdata_models::{NewSavedSearch, SavedSearch, SavedSearchHit};
+use crate::get_app_globals;
+use crate::logger::{error, info};
+use crate::query_task::SearchQueryTask;
+use crate::types::{SearchArea, SearchMode, SearchParams, SearchResult};
+
+/// The most results of a saved search compared between runs.
+pub const MAX_SAVED_SEARCH_HITS: usize = 1000;
+
+const SAVED_SEARCH_PAGE_LEN: usize = 100;
+
+/// Only one run of all the saved searches at a time, e.g. from two windows.
+static RUN_ALL_LOCK: Mutex<()> = Mutex::new(());
+
+/// A run of a saved search, with the results not seen on earlier runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearchRun {
+    pub saved_search: SavedSearch,
+    pub hits_count: i32,
+    pub new_hits: Vec<SavedSearchHit>,
+}
+
+/// The saved searches with new results after new content was imported.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewContentReport {
+    /// What was imported, e.g. "Language pack: de", "Book: The Island"
+    pub reasons: Vec<String>,
+    pub runs: Vec<SavedSearchRun>,
+}
+
+/// A saved search with its run state and the results seen so far, as kept in
+/// the user data export for upgrades.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearchExport {
+    pub saved_search: SavedSearch,
+    pub hits: Vec<SavedSearchHit>,
+}
+
+pub fn parse_search_area(search_area: &str) -> Result<SearchArea> {
+    match search_area {
+        "Suttas" => Ok(SearchArea::Suttas),
+        "Dictionary" => Ok(SearchArea::Dictionary),
+        "Library" => Ok(SearchArea::Library),
+        _ => bail!("Unknown search area: {}", search_area),
+    }
+}
+
+/// Save a search from the search window: the query as it was run, the search
+/// area, and the `SearchParams` JSON with the mode and the filters.
+pub fn save_search(dbm: &DbManager, name: &str, query_text: &str, search_area: &str, params_json: &str) -> Result<i32> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("The name of a saved search can't be empty");
+    }
+    if query_text.trim().is_empty() {
+        bail!("The query of a saved search can't be empty");
+    }
+    parse_search_area(search_area)?;
+    let search_mode = params_search_mode(params_json)?;
+
+    dbm.appdata.save_search(&NewSavedSearch {
+        name,
+        query_text,
+        search_area,
+        search_mode: &search_mode,
+        params_json,
+    })
+}
+
+/// Check the `SearchParams` JSON of a search, and return its mode name as the
+/// search window sent it, e.g. "Fulltext Match".
+pub fn params_search_mode(params_json: &str) -> Result<String> {
+    let params_value: serde_json::Value = serde_json::from_str(params_json)
+        .map_err(|e| anyhow!("Invalid search params: {}", e))?;
+    serde_json::from_value::<SearchParams>(params_value.clone())
+        .map_err(|e| anyhow!("Invalid search params: {}", e))?;
+    params_value.get("mode")
+        .and_then(|m| m.as_str())
+        .map(|m| m.to_string())
+        .ok_or_else(|| anyhow!("The search params have no mode"))
+}
+
+/// The results of a search, up to `MAX_SAVED_SEARCH_HITS`, and the number of
+/// hits.
+pub fn search_results(dbm: &DbManager, query_text: &str, area: SearchArea, params: SearchParams) -> Result<(Vec<SearchResult>, usize)> {
+    if matches!(params.mode, SearchMode::FulltextMatch | SearchMode::Combined) {
+        crate::init_fulltext_searcher();
+    }
+
+    // Combined dictionary search is orchestrated by the bridge as DPD Lookup
+    // and Fulltext Match. Here the results of the two are simply joined.
+    if params.mode == SearchMode::Combined && area == SearchArea::Dictionary {
+        let (mut results, dpd_total) = search_results(
+            dbm, query_text, area.clone(), SearchParams { mode: SearchMode::DpdLookup, ..params.clone() })?;
+        let (ft_results, ft_total) = search_results(
+            dbm, query_text, area, SearchParams { mode: SearchMode::FulltextMatch, ..params })?;
+        results.extend(ft_results);
+        results.truncate(MAX_SAVED_SEARCH_HITS);
+        return Ok((results, dpd_total + ft_total));
+    }
+
+    let params = SearchParams { page_len: Some(SAVED_SEARCH_PAGE_LEN), ..params };
+    let mut task = SearchQueryTask::new(dbm, query_text.to_string(), params, area);
+
+    let mut results = Vec::new();
+    let mut page_num = 0;
+    loop {
+        let page = task.results_page(page_num).map_err(|e| anyhow!("{}", e))?;
+        let total = task.total_hits().max(0) as usize;
+        let is_last_page = page.is_empty() || (page_num + 1) * task.page_len >= total;
+        results.extend(page);
+        if is_last_page || results.len() >= MAX_SAVED_SEARCH_HITS {
+            results.truncate(MAX_SAVED_SEARCH_HITS);
+            return Ok((results, total));
+        }
+        page_num += 1;
+    }
+}
+
+/// Run a saved search and record its results. The results not seen on the
+/// earlier runs are returned as new.
+pub fn run_saved_search(dbm: &DbManager, saved_search_id: i32) -> Result<SavedSearchRun> {
+    let saved_search = dbm.appdata.get_saved_search(saved_search_id)?
+        .ok_or_else(|| anyhow!("Saved search not found: {}", saved_search_id))?;
+
+    let area = parse_search_area(&saved_search.search_area)?;
+    let params: SearchParams = serde_json::from_str(&saved_search.params_json)
+        .map_err(|e| anyhow!("Invalid search params of '{}': {}", saved_search.name, e))?;
+
+    let (results, total) = search_results(dbm, &saved_search.query_text, area, params)?;
+    let hits_count = total.min(i32::MAX as usize) as i32;
+    let new_hits = dbm.appdata.record_saved_search_hits(saved_search_id, &results, hits_count)?;
+
+    let saved_search = dbm.appdata.get_saved_search(saved_search_id)?.unwrap_or(saved_search);
+    Ok(SavedSearchRun { saved_search, hits_count, new_hits })
+}
+
+/// Run all the saved searches, returning the ones with new results. A search
+/// which fails is logged and skipped.
+pub fn run_all_saved_searches(dbm: &DbManager) -> Result<Vec<SavedSearchRun>> {
+    let _lock = RUN_ALL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    run_all(dbm)
+}
+
+fn run_all(dbm: &DbManager) -> Result<Vec<SavedSearchRun>> {
+    let mut runs = Vec::new();
+    for saved_search in dbm.appdata.get_saved_searches()? {
+        match run_saved_search(dbm, saved_search.id) {
+            Ok(run) if !run.new_hits.is_empty() => runs.push(run),
+            Ok(_) => {}
+            Err(e) => error(&format!("Failed to run saved search '{}': {}", saved_search.name, e)),
+        }
+    }
+    Ok(runs)
+}
+
+/// Note that new content was imported, so that the saved searches are run
+/// when the new content is searchable, which may be after a restart (e.g. the
+/// dictionary index is built at startup). `reason` is shown to the user.
+pub fn request_saved_searches_run(reason: &str) {
+    let marker = &get_app_globals().paths.run_saved_searches_marker;
+    let mut reasons = fs::read_to_string(marker).unwrap_or_default();
+    if reasons.lines().any(|line| line == reason) {
+        return;
+    }
+    reasons.push_str(reason);
+    reasons.push('\n');
+    if let Err(e) = fs::write(marker, reasons) {
+        error(&format!("Failed to write {}: {}", marker.display(), e));
+    }
+}
+
+/// If new content was imported since the last check, run all the saved
+/// searches and report the ones with new results.
+pub fn run_saved_searches_if_requested(dbm: &DbManager) -> Result<Option<NewContentReport>> {
+    let _lock = RUN_ALL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let marker = &get_app_globals().paths.run_saved_searches_marker;
+    if !marker.try_exists().unwrap_or(false) {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(marker).unwrap_or_default();
+    fs::remove_file(marker)?;
+
+    let mut seen = HashSet::new();
+    let reasons: Vec<String> = text
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty() && seen.insert(line.clone()))
+        .collect();
+
+    info(&format!("Running the saved searches after new content: {}", reasons.join(", ")));
+    let runs = run_all(dbm)?;
+    Ok(Some(NewContentReport { reasons, runs }))
+}
+
+/// All the saved searches with their results seen so far.
+pub fn export_saved_searches(dbm: &DbManager) -> Result<Vec<SavedSearchExport>> {
+    dbm.appdata.get_saved_searches()?
+        .into_iter()
+        .map(|saved_search| {
+            let hits = dbm.appdata.get_saved_search_hits(saved_search.id, false)?;
+            Ok(SavedSearchExport { saved_search, hits })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search_area() {
+        assert_eq!(parse_search_area("Suttas").unwrap(), SearchArea::Suttas);
+        assert_eq!(parse_search_area("Dictionary").unwrap(), SearchArea::Dictionary);
+        assert_eq!(parse_search_area("Library").unwrap(), SearchArea::Library);
+        assert!(parse_search_area("suttas").is_err());
+    }
+}
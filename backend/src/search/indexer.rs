@@ -22,6 +22,35 @@ fn reversed_lowercased(s: &str) -> String {
     s.to_lowercase().chars().rev().collect()
 }
 
+/// Open an existing index with the schema it was built with, or create it.
+///
+/// An index of an earlier `INDEX_VERSION` may lack a field of the current
+/// schema (e.g. `content_phonetic`). `Index::open_or_create` would refuse it
+/// as a schema mismatch, and it couldn't be searched until it is rebuilt.
+/// The optional fields are looked up with `schema.get_field(..).ok()` by the
+/// writers and the searcher.
+pub fn open_with_own_schema(mmap_dir: tantivy::directory::MmapDirectory, schema: tantivy::schema::Schema) -> Result<Index> {
+    if Index::exists(&mmap_dir)? {
+        Ok(Index::open(mmap_dir)?)
+    } else {
+        Ok(Index::create(mmap_dir, schema, tantivy::IndexSettings::default())?)
+    }
+}
+
+/// Remove the index at `dir` when it was built with a different schema, so
+/// that a full build writes the current one.
+fn remove_index_with_other_schema(dir: &Path, schema: &tantivy::schema::Schema) -> Result<()> {
+    if !matches!(dir.try_exists(), Ok(true)) {
+        return Ok(());
+    }
+    let mmap_dir = tantivy::directory::MmapDirectory::open(dir)?;
+    if Index::exists(&mmap_dir)? && Index::open(mmap_dir)?.schema() != *schema {
+        info(&format!("Removing the index with an earlier schema: {}", dir.display()));
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
 /// Open or create a Tantivy index at the given directory path.
 pub fn open_or_create_index(dir: &Path, schema: tantivy::schema::Schema, lang: &str) -> Result<Index> {
     match dir.try_exists() {
@@ -32,7 +61,7 @@ pub fn open_or_create_index(dir: &Path, schema: tantivy::schema::Schema, lang: &
     }
 
     let mmap_dir = tantivy::directory::MmapDirectory::open(dir)?;
-    let index = Index::open_or_create(mmap_dir, schema)?;
+    let index = open_with_own_schema(mmap_dir, schema)?;
 
     register_tokenizers(&index, lang);
 
@@ -47,6 +76,7 @@ pub fn build_sutta_index(appdata_db: &DatabaseHandle, index_dir: &Path, lang: &s
 
     let lang_index_dir = index_dir.join(lang);
     let schema = build_sutta_schema(lang);
+    remove_index_with_other_schema(&lang_index_dir, &schema)?;
     let index = open_or_create_index(&lang_index_dir, schema, lang)?;
 
     let mut writer: IndexWriter = index.writer(50_000_000)?;
@@ -65,6 +95,7 @@ pub fn build_sutta_index(appdata_db: &DatabaseHandle, index_dir: &Path, lang: &s
     let nikaya_field = schema.get_field("nikaya").unwrap();
     let content_field = schema.get_field("content").unwrap();
     let content_exact_field = schema.get_field("content_exact").unwrap();
+    let content_phonetic_field = schema.get_field("content_phonetic").ok();
     let is_mula_field = schema.get_field("is_mula").unwrap();
     let is_commentary_field = schema.get_field("is_commentary").unwrap();
 
@@ -99,7 +130,7 @@ pub fn build_sutta_index(appdata_db: &DatabaseHandle, index_dir: &Path, lang: &s
 
         let uid_rev = reversed_lowercased(&sutta.uid);
 
-        writer.add_document(doc!(
+        let mut document = doc!(
             uid_field => sutta.uid.as_str(),
             uid_rev_field => uid_rev.as_str(),
             title_field => t,
@@ -111,7 +142,11 @@ pub fn build_sutta_index(appdata_db: &DatabaseHandle, index_dir: &Path, lang: &s
             content_exact_field => content_text.as_str(),
             is_mula_field => is_mula,
             is_commentary_field => is_commentary,
-        ))?;
+        );
+        if let Some(field) = content_phonetic_field {
+            document.add_text(field, content_text.as_str());
+        }
+        writer.add_document(document)?;
 
         indexed_count += 1;
     }
@@ -139,6 +174,7 @@ pub fn build_dict_index(dict_db: &DatabaseHandle, index_dir: &Path, lang: &str)
 
     let lang_index_dir = index_dir.join(lang);
     let schema = build_dict_schema(lang);
+    remove_index_with_other_schema(&lang_index_dir, &schema)?;
     let index = open_or_create_index(&lang_index_dir, schema, lang)?;
 
     let mut writer: IndexWriter = index.writer(50_000_000)?;
@@ -156,6 +192,7 @@ pub fn build_dict_index(dict_db: &DatabaseHandle, index_dir: &Path, lang: &str)
     let source_uid_field = schema.get_field("source_uid").unwrap();
     let content_field = schema.get_field("content").unwrap();
     let content_exact_field = schema.get_field("content_exact").unwrap();
+    let content_phonetic_field = schema.get_field("content_phonetic").ok();
     let is_bold_definition_field = schema.get_field("is_bold_definition").unwrap();
 
     let lang_clone = lang.to_string();
@@ -184,7 +221,7 @@ pub fn build_dict_index(dict_db: &DatabaseHandle, index_dir: &Path, lang: &str)
 
         let uid_rev = reversed_lowercased(&dw.uid);
 
-        writer.add_document(doc!(
+        let mut document = doc!(
             uid_field => dw.uid.as_str(),
             uid_rev_field => uid_rev.as_str(),
             word_field => w.as_str(),
@@ -194,7 +231,11 @@ pub fn build_dict_index(dict_db: &DatabaseHandle, index_dir: &Path, lang: &str)
             content_field => content_text.as_str(),
             content_exact_field => content_text.as_str(),
             is_bold_definition_field => false,
-        ))?;
+        );
+        if let Some(field) = content_phonetic_field {
+            document.add_text(field, content_text.as_str());
+        }
+        writer.add_document(document)?;
 
         indexed_count += 1;
     }
@@ -247,6 +288,7 @@ pub fn append_dict_label_to_dict_index(
     let source_uid_field = schema.get_field("source_uid").unwrap();
     let content_field = schema.get_field("content").unwrap();
     let content_exact_field = schema.get_field("content_exact").unwrap();
+    let content_phonetic_field = schema.get_field("content_phonetic").ok();
     let is_bold_definition_field = schema.get_field("is_bold_definition").unwrap();
 
     // Drop any prior docs for this label so a re-import doesn't duplicate.
@@ -284,7 +326,7 @@ pub fn append_dict_label_to_dict_index(
 
         let uid_rev = reversed_lowercased(&dw.uid);
 
-        writer.add_document(doc!(
+        let mut document = doc!(
             uid_field => dw.uid.as_str(),
             uid_rev_field => uid_rev.as_str(),
             word_field => w.as_str(),
@@ -294,7 +336,11 @@ pub fn append_dict_label_to_dict_index(
             content_field => content_text.as_str(),
             content_exact_field => content_text.as_str(),
             is_bold_definition_field => false,
-        ))?;
+        );
+        if let Some(field) = content_phonetic_field {
+            document.add_text(field, content_text.as_str());
+        }
+        writer.add_document(document)?;
 
         indexed_count += 1;
     }
@@ -349,6 +395,7 @@ pub fn append_bold_definitions_to_dict_index(
     let nikaya_group_path_field = schema.get_field("nikaya_group_path").unwrap();
     let content_field = schema.get_field("content").unwrap();
     let content_exact_field = schema.get_field("content_exact").unwrap();
+    let content_phonetic_field = schema.get_field("content_phonetic").ok();
     let is_bold_definition_field = schema.get_field("is_bold_definition").unwrap();
 
     let rows: Vec<BoldDefinition> = dpd_db.do_read(|db_conn| {
@@ -380,7 +427,7 @@ pub fn append_bold_definitions_to_dict_index(
 
         let uid_rev = reversed_lowercased(&row.uid);
 
-        writer.add_document(doc!(
+        let mut document = doc!(
             uid_field => row.uid.as_str(),
             uid_rev_field => uid_rev.as_str(),
             word_field => row.bold.as_str(),
@@ -391,7 +438,11 @@ pub fn append_bold_definitions_to_dict_index(
             content_field => plain,
             content_exact_field => plain,
             is_bold_definition_field => true,
-        ))?;
+        );
+        if let Some(field) = content_phonetic_field {
+            document.add_text(field, plain);
+        }
+        writer.add_document(document)?;
 
         indexed_count += 1;
     }
@@ -416,6 +467,7 @@ pub fn build_library_index(appdata_db: &DatabaseHandle, index_dir: &Path, lang:
 
     let lang_index_dir = index_dir.join(lang);
     let schema = build_library_schema(lang);
+    remove_index_with_other_schema(&lang_index_dir, &schema)?;
     let index = open_or_create_index(&lang_index_dir, schema, lang)?;
 
     let mut writer: IndexWriter = index.writer(50_000_000)?;
@@ -606,7 +658,7 @@ pub fn build_all_indexes(
 // Index versioning
 // ---------------------------------------------------------------------------
 
-pub const INDEX_VERSION: &str = "1.0";
+pub const INDEX_VERSION: &str = "1.1";
 
 /// Write a VERSION file to the index directory.
 pub fn write_version_file(index_dir: &Path) -> Result<()> {
@@ -689,6 +741,7 @@ where
     let source_uid_field = schema.get_field("source_uid").unwrap();
     let content_field = schema.get_field("content").unwrap();
     let content_exact_field = schema.get_field("content_exact").unwrap();
+    let content_phonetic_field = schema.get_field("content_phonetic").ok();
     let is_bold_definition_field = schema.get_field("is_bold_definition").unwrap();
 
     let total = words.len();
@@ -706,7 +759,7 @@ where
         let lang_val = dw.language.as_deref().unwrap_or(lang);
         let uid_rev = reversed_lowercased(&dw.uid);
 
-        writer.add_document(doc!(
+        let mut document = doc!(
             uid_field => dw.uid.as_str(),
             uid_rev_field => uid_rev.as_str(),
             word_field => w.as_str(),
@@ -716,7 +769,11 @@ where
             content_field => content_text.as_str(),
             content_exact_field => content_text.as_str(),
             is_bold_definition_field => false,
-        ))?;
+        );
+        if let Some(field) = content_phonetic_field {
+            document.add_text(field, content_text.as_str());
+        }
+        writer.add_document(document)?;
         indexed += 1;
 
         if (i + 1) % chunk == 0 {
@@ -768,7 +825,7 @@ pub fn delete_from_dict_index_by_source_uid(index_dir: &Path, label: &str) -> Re
                 continue;
             }
         };
-        let index = match Index::open_or_create(mmap_dir, schema) {
+        let index = match open_with_own_schema(mmap_dir, schema) {
             Ok(i) => i,
             Err(e) => {
                 warn(&format!("delete_from_dict_index: open index {}: {}", path.display(), e));
@@ -818,7 +875,7 @@ pub fn list_indexed_source_uids_in_dict_index(index_dir: &Path) -> Result<HashSe
                 continue;
             }
         };
-        let index = match Index::open_or_create(mmap_dir, schema) {
+        let index = match open_with_own_schema(mmap_dir, schema) {
             Ok(i) => i,
             Err(e) => {
                 warn(&format!("list_indexed_source_uids: open index {}: {}", path.display(), e));
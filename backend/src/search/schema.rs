@@ -1,5 +1,18 @@
 use tantivy::schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions, INDEXED, STORED};
 
+use super::tokenizer::PALI_PHONETIC_TOKENIZER;
+
+/// Pāli phonetic keys of the content, for fuzzy matching of ASCII-typed Pāli.
+/// Not stored, and without positions: it is only matched and scored.
+fn phonetic_opts() -> TextOptions {
+    TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(PALI_PHONETIC_TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqs),
+        )
+}
+
 /// Build the Tantivy schema for sutta indexing with the given language.
 pub fn build_sutta_schema(lang: &str) -> Schema {
     let mut builder = Schema::builder();
@@ -46,6 +59,7 @@ pub fn build_sutta_schema(lang: &str) -> Schema {
     builder.add_text_field("nikaya", raw_opts);
     builder.add_text_field("content", lang_stem_opts);
     builder.add_text_field("content_exact", lang_normalize_opts);
+    builder.add_text_field("content_phonetic", phonetic_opts());
     builder.add_bool_field("is_mula", INDEXED | STORED);
     builder.add_bool_field("is_commentary", INDEXED | STORED);
 
@@ -151,6 +165,7 @@ pub fn build_dict_schema(lang: &str) -> Schema {
     builder.add_text_field("nikaya_group_path", raw_opts);
     builder.add_text_field("content", lang_stem_opts);
     builder.add_text_field("content_exact", lang_normalize_opts);
+    builder.add_text_field("content_phonetic", phonetic_opts());
     builder.add_bool_field("is_bold_definition", INDEXED | STORED);
 
     builder.build()
@@ -4,19 +4,21 @@ use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use tantivy::collector::{Count, TopDocs};
-use tantivy::query::{BooleanQuery, Occur, QueryParser, RegexQuery, TermQuery};
+use tantivy::query::{BooleanQuery, BoostQuery, FuzzyTermQuery, Occur, Query, QueryParser, RegexQuery, TermQuery};
 use tantivy::schema::{IndexRecordOption, Value};
 use tantivy::{Index, IndexReader, Term};
 
 use crate::logger::{info, warn};
-use crate::types::SearchResult;
+use crate::types::{SearchArea, SearchResult};
 use crate::highlight::{literal_ranges, wrap_ranges};
 use crate::helpers::normalize_plain_text;
+use crate::pali_phonetic::{edit_distance, fuzzy_distance_for_key, pali_phonetic_key};
 use crate::query_task::SearchQueryTask;
 use crate::AppGlobalPaths;
 
+use super::indexer::open_with_own_schema;
 use super::schema::{build_dict_schema, build_library_schema, build_sutta_schema};
-use super::tokenizer::register_tokenizers;
+use super::tokenizer::{pali_phonetic_analyzer, register_tokenizers};
 pub use super::types::SearchFilters;
 
 /// Identifies the type of index for schema selection.
@@ -134,7 +136,7 @@ impl FulltextSearcher {
         };
 
         let mmap_dir = tantivy::directory::MmapDirectory::open(dir)?;
-        let index = Index::open_or_create(mmap_dir, schema)?;
+        let index = open_with_own_schema(mmap_dir, schema)?;
         register_tokenizers(&index, lang);
 
         let reader = index.reader()?;
@@ -329,17 +331,7 @@ impl FulltextSearcher {
             return Ok((0, Vec::new()));
         }
 
-        // Determine which languages to search
-        let langs_to_search: Vec<&String> = if let Some(ref lang) = filters.lang {
-            if filters.lang_include && !lang.is_empty() && lang != "Language" {
-                // Only search the specified language
-                indexes.keys().filter(|k| *k == lang).collect()
-            } else {
-                indexes.keys().collect()
-            }
-        } else {
-            indexes.keys().collect()
-        };
+        let langs_to_search = Self::langs_to_search(indexes, filters);
 
         // Fetch enough results from each index to cover all pages up to the requested one
         let limit = (page_num + 1) * page_len;
@@ -412,6 +404,94 @@ impl FulltextSearcher {
         Ok((total_hits, results))
     }
 
+    /// The languages of `indexes` to search with `filters`.
+    fn langs_to_search<'a>(indexes: &'a HashMap<String, (Index, IndexReader)>, filters: &SearchFilters) -> Vec<&'a String> {
+        if let Some(ref lang) = filters.lang {
+            if filters.lang_include && !lang.is_empty() && lang != "Language" {
+                // Only search the specified language
+                indexes.keys().filter(|k| *k == lang).collect()
+            } else {
+                indexes.keys().collect()
+            }
+        } else {
+            indexes.keys().collect()
+        }
+    }
+
+    /// The indexed terms within `max_distance` edits of `word`, with their
+    /// distance and their document frequency summed over the segments and
+    /// languages. The candidates of the "did you mean" suggestions, see
+    /// `crate::did_you_mean`.
+    ///
+    /// The word is normalized with each field's own analyzer, so it is compared
+    /// with the terms as they are stored: lowercase and ASCII-folded. The Dict
+    /// area also reads the headword field (`word`). When the word itself is
+    /// indexed it is returned at distance 0.
+    pub fn similar_terms(
+        &self,
+        area: &SearchArea,
+        filters: &SearchFilters,
+        word: &str,
+        max_distance: usize,
+    ) -> Result<Vec<(String, usize, u64)>> {
+        let (indexes, field_names): (&HashMap<String, (Index, IndexReader)>, &[&str]) = match area {
+            SearchArea::Suttas => (&self.sutta_indexes, &["content_exact"]),
+            SearchArea::Dictionary => (&self.dict_indexes, &["word", "content_exact"]),
+            SearchArea::Library => (&self.library_indexes, &["content_exact"]),
+        };
+
+        let mut terms_map: HashMap<String, (usize, u64)> = HashMap::new();
+
+        for lang in Self::langs_to_search(indexes, filters) {
+            let Some((index, reader)) = indexes.get(lang) else {
+                continue;
+            };
+            let schema = index.schema();
+            let searcher = reader.searcher();
+
+            for field_name in field_names {
+                let Ok(field) = schema.get_field(field_name) else {
+                    continue;
+                };
+
+                let mut analyzer = index.tokenizer_for_field(field)?;
+                let mut stream = analyzer.token_stream(word);
+                if !stream.advance() {
+                    continue;
+                }
+                let normalized = stream.token().text.clone();
+                let normalized_len = normalized.chars().count();
+
+                for segment_reader in searcher.segment_readers() {
+                    let inverted_index = segment_reader.inverted_index(field)?;
+                    let mut term_stream = inverted_index.terms().stream()?;
+                    while term_stream.advance() {
+                        let Ok(term) = std::str::from_utf8(term_stream.key()) else {
+                            continue;
+                        };
+                        // The length difference is a lower bound of the distance
+                        if term.chars().count().abs_diff(normalized_len) > max_distance
+                            || !term.chars().all(char::is_alphabetic)
+                        {
+                            continue;
+                        }
+                        let distance = edit_distance(&normalized, term);
+                        if distance > max_distance {
+                            continue;
+                        }
+                        let entry = terms_map.entry(term.to_string()).or_insert((distance, 0));
+                        entry.1 += term_stream.value().doc_freq as u64;
+                    }
+                }
+            }
+        }
+
+        Ok(terms_map
+            .into_iter()
+            .map(|(term, (distance, frequency))| (term, distance, frequency))
+            .collect())
+    }
+
     /// Enumerate every match byte range of the query terms in `content` by
     /// re-tokenizing it with the index's `{lang}_stem` analyzer and keeping
     /// each token whose stem equals a query term's stem. This is what surfaces
@@ -521,15 +601,38 @@ impl FulltextSearcher {
         let content_query = content_parser.parse_query(query_text)?;
         let content_exact_query = content_exact_parser.parse_query(query_text)?;
 
-        let boosted_exact = tantivy::query::BoostQuery::new(
+        let boosted_exact = BoostQuery::new(
             Box::new(content_exact_query),
             2.0,
         );
 
-        let mut subqueries: Vec<(Occur, Box<dyn tantivy::query::Query>)> = vec![
-            (Occur::Must, Box::new(content_query)),
-            (Occur::Should, Box::new(boosted_exact)),
-        ];
+        // With a fuzzy distance, a document matches on the stemmed content or
+        // on the phonetic keys, so ASCII-typed Pāli (`satipatana`) finds
+        // `satipaṭṭhāna`. The exact matches score on all the clauses and
+        // rank first.
+        let phonetic_field = schema
+            .get_field("content_phonetic")
+            .ok()
+            .filter(|_| filters.fuzzy_distance > 0);
+        let phonetic_keys = match phonetic_field {
+            Some(_) => Self::phonetic_query_keys(query_text),
+            None => Vec::new(),
+        };
+
+        let mut subqueries: Vec<(Occur, Box<dyn tantivy::query::Query>)> = match phonetic_field {
+            Some(field) if !phonetic_keys.is_empty() => {
+                let phonetic_query = Self::phonetic_query(field, &phonetic_keys, filters.fuzzy_distance);
+                vec![(Occur::Must, Box::new(BooleanQuery::new(vec![
+                    (Occur::Should, Box::new(content_query) as Box<dyn Query>),
+                    (Occur::Should, Box::new(boosted_exact)),
+                    (Occur::Should, phonetic_query),
+                ])))]
+            }
+            _ => vec![
+                (Occur::Must, Box::new(content_query)),
+                (Occur::Should, Box::new(boosted_exact)),
+            ],
+        };
 
         // Add filter term queries
         match index_type {
@@ -586,12 +689,113 @@ impl FulltextSearcher {
                 IndexType::Library => self.library_doc_to_result(&doc, &schema, score, &snippet_gen, query_text)?,
             };
 
+            // A phonetic match has no stemmed term for tantivy to highlight
+            let result = if !phonetic_keys.is_empty() && !result.snippet.contains("<span class='match'>") {
+                let content = Self::get_text_field(&doc, &schema, "content");
+                SearchResult {
+                    snippet: Self::phonetic_snippet(&content, &phonetic_keys, filters.fuzzy_distance),
+                    ..result
+                }
+            } else {
+                result
+            };
+
             results.push((score, doc_address, result));
         }
 
         Ok((count, results))
     }
 
+    /// The phonetic keys of the query words, for the `content_phonetic` field.
+    /// The excluded words (`-word`) and the AND / OR / NOT operators are left
+    /// out. For several words, the last key is the key of the words written
+    /// together, so `sati patthana` also matches `satipaṭṭhāna`.
+    fn phonetic_query_keys(query_text: &str) -> Vec<String> {
+        let words: Vec<&str> = query_text
+            .split_whitespace()
+            .filter(|w| !w.starts_with('-') && !matches!(*w, "AND" | "OR" | "NOT"))
+            .collect();
+
+        let mut analyzer = pali_phonetic_analyzer();
+        let mut keys: Vec<String> = Vec::new();
+        let mut raw_words: Vec<String> = Vec::new();
+        for word in words {
+            let mut stream = analyzer.token_stream(word);
+            while stream.advance() {
+                let token = stream.token();
+                keys.push(token.text.clone());
+                raw_words.push(word[token.offset_from..token.offset_to].to_string());
+            }
+        }
+
+        if raw_words.len() > 1 {
+            keys.push(pali_phonetic_key(&raw_words.concat()));
+        }
+        keys
+    }
+
+    /// One key: the exact key (boosted), and the keys within 1, then 2 edits.
+    /// A closer key also matches the wider automatons, so it scores higher.
+    fn phonetic_key_query(field: tantivy::schema::Field, key: &str, fuzzy_distance: u8) -> Box<dyn Query> {
+        let term = Term::from_field_text(field, key);
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(
+            Occur::Should,
+            Box::new(BoostQuery::new(Box::new(TermQuery::new(term.clone(), IndexRecordOption::WithFreqs)), 2.0)),
+        )];
+        for distance in 1..=fuzzy_distance_for_key(key, fuzzy_distance) {
+            clauses.push((Occur::Should, Box::new(FuzzyTermQuery::new(term.clone(), distance, true))));
+        }
+        Box::new(BooleanQuery::new(clauses))
+    }
+
+    /// All the word keys must match, or the key of the words written together.
+    fn phonetic_query(field: tantivy::schema::Field, keys: &[String], fuzzy_distance: u8) -> Box<dyn Query> {
+        if keys.len() == 1 {
+            return Self::phonetic_key_query(field, &keys[0], fuzzy_distance);
+        }
+        let (joined_key, word_keys) = keys.split_last().expect("several keys");
+        let all_words = BooleanQuery::new(
+            word_keys
+                .iter()
+                .map(|k| (Occur::Must, Self::phonetic_key_query(field, k, fuzzy_distance)))
+                .collect(),
+        );
+        Box::new(BooleanQuery::new(vec![
+            (Occur::Should, Box::new(all_words) as Box<dyn Query>),
+            (Occur::Should, Self::phonetic_key_query(field, joined_key, fuzzy_distance)),
+        ]))
+    }
+
+    /// A snippet around the content word with the closest phonetic key.
+    fn phonetic_snippet(content: &str, keys: &[String], fuzzy_distance: u8) -> String {
+        let mut analyzer = pali_phonetic_analyzer();
+        let mut best: Option<(usize, std::ops::Range<usize>)> = None;
+        let mut stream = analyzer.token_stream(content);
+        while stream.advance() {
+            let token = stream.token();
+            for key in keys {
+                let distance = edit_distance(&token.text, key);
+                let closer = best.as_ref().is_none_or(|(d, _)| distance < *d);
+                if distance <= fuzzy_distance_for_key(key, fuzzy_distance) as usize && closer {
+                    best = Some((distance, token.offset_from..token.offset_to));
+                }
+            }
+            if best.as_ref().is_some_and(|(d, _)| *d == 0) {
+                break;
+            }
+        }
+
+        let Some((_, range)) = best else {
+            return String::new();
+        };
+        let (chars_before, chars_after) = match crate::try_get_app_data() {
+            Some(app_data) => (app_data.get_snippet_chars_before(), app_data.get_snippet_chars_after()),
+            None => (30, 200),
+        };
+        let (window, focal) = SearchQueryTask::fragment_around_offset(content, range.start, range.end - range.start, chars_before, chars_after);
+        wrap_ranges(&window, &[focal])
+    }
+
     /// Push down uid prefix and suffix filters as exact regex queries against
     /// the `raw`-tokenized uid + reversed-uid fields. Both reduce to anchored
     /// prefix-on-some-field, so the term dictionary's btree handles them in
@@ -1234,6 +1438,7 @@ mod tests {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         };
 
         let result = searcher.debug_query("bhikkhave", &filters).unwrap();
@@ -1274,6 +1479,7 @@ mod tests {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         };
 
         // Unbalanced quotes should cause a parse error but still return partial results
@@ -1313,6 +1519,7 @@ mod tests {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         };
 
         // "bhikkhūnaṁ" should stem differently than normalize
@@ -1348,6 +1555,7 @@ mod tests {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         };
 
         // "sattanam" is the ASCII-folded form of "sattānaṁ" in the test document.
@@ -1387,6 +1595,7 @@ mod tests {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         };
 
         let (count, results) = searcher.search_suttas_with_count("jaramaranam", &filters, 10, 0).unwrap();
@@ -1454,6 +1663,7 @@ mod tests {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         };
 
         let (count, results) = searcher.search_suttas_with_count("vinnanam", &filters, 10, 0).unwrap();
@@ -1484,6 +1694,7 @@ mod tests {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         };
 
         let result = searcher.debug_query("test", &filters).unwrap();
@@ -1491,6 +1702,148 @@ mod tests {
         assert!(result.parse_error.is_none());
     }
 
+    /// Build an in-memory sutta index with one doc per (uid, content), with
+    /// the phonetic keys of the content indexed.
+    fn create_phonetic_test_index(docs: &[(&str, &str)]) -> (Index, IndexReader) {
+        let schema = build_sutta_schema("pli");
+        let index = Index::create_in_ram(schema.clone());
+        register_tokenizers(&index, "pli");
+
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        let uid_field = schema.get_field("uid").unwrap();
+        let language_field = schema.get_field("language").unwrap();
+        let content_field = schema.get_field("content").unwrap();
+        let content_exact_field = schema.get_field("content_exact").unwrap();
+        let content_phonetic_field = schema.get_field("content_phonetic").unwrap();
+
+        for (uid, text) in docs {
+            writer
+                .add_document(doc!(
+                    uid_field => *uid,
+                    language_field => "pli",
+                    content_field => *text,
+                    content_exact_field => *text,
+                    content_phonetic_field => *text,
+                ))
+                .unwrap();
+        }
+        writer.commit().unwrap();
+        let reader = index.reader().unwrap();
+        (index, reader)
+    }
+
+    #[test]
+    fn test_fuzzy_phonetic_match() {
+        let (index, reader) = create_phonetic_test_index(&[
+            ("mn10/pli/ms", "ekāyano ayaṁ bhikkhave maggo yadidaṁ cattāro satipaṭṭhānā"),
+            ("sn47.2/pli/ms", "cattāro satipaṭṭhānaṁ bhāvetabbaṁ"),
+            ("dn1/pli/ms", "evaṁ me sutaṁ brahmajāla"),
+        ]);
+        let mut sutta_indexes = HashMap::new();
+        sutta_indexes.insert("pli".to_string(), (index, reader));
+
+        let searcher = FulltextSearcher {
+            sutta_indexes,
+            dict_indexes: HashMap::new(),
+            library_indexes: HashMap::new(),
+        };
+
+        let exact = SearchFilters::default();
+        let (count, _) = searcher.search_suttas_with_count("satipatana", &exact, 10, 0).unwrap();
+        assert_eq!(count, 0, "without a fuzzy distance, only the stemmed content matches");
+
+        let fuzzy = SearchFilters { fuzzy_distance: 1, ..SearchFilters::default() };
+        let (count, results) = searcher.search_suttas_with_count("satipatana", &fuzzy, 10, 0).unwrap();
+        assert_eq!(count, 2);
+        // The same phonetic key ranks before the key one edit away
+        assert_eq!(results[0].uid, "mn10/pli/ms");
+        assert_eq!(results[1].uid, "sn47.2/pli/ms");
+        assert!(results[0].snippet.contains("<span class='match'>satipaṭṭhānā</span>"),
+            "snippet: {}", results[0].snippet);
+
+        let (_, results) = searcher.search_suttas_with_count("sati patthana", &fuzzy, 10, 0).unwrap();
+        assert_eq!(results.first().map(|r| r.uid.as_str()), Some("mn10/pli/ms"));
+        assert!(results.iter().all(|r| r.uid != "dn1/pli/ms"));
+    }
+
+    #[test]
+    fn test_fuzzy_search_without_phonetic_field() {
+        // An index built before the content_phonetic field was added to the
+        // sutta schema
+        let mut builder = tantivy::schema::Schema::builder();
+        for (_, entry) in build_sutta_schema("pli").fields() {
+            if entry.name() != "content_phonetic" {
+                builder.add_field(entry.clone());
+            }
+        }
+        let old_schema = builder.build();
+
+        let tmp = tempfile::tempdir().unwrap();
+        {
+            let index = Index::create_in_dir(tmp.path(), old_schema.clone()).unwrap();
+            register_tokenizers(&index, "pli");
+            let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+            let text = "cattāro satipaṭṭhānā";
+            writer
+                .add_document(doc!(
+                    old_schema.get_field("uid").unwrap() => "mn10/pli/ms",
+                    old_schema.get_field("language").unwrap() => "pli",
+                    old_schema.get_field("content").unwrap() => text,
+                    old_schema.get_field("content_exact").unwrap() => text,
+                ))
+                .unwrap();
+            writer.commit().unwrap();
+        }
+
+        let (index, reader) = FulltextSearcher::open_single_index(tmp.path(), "pli", IndexType::Sutta).unwrap();
+        assert!(index.schema().get_field("content_phonetic").is_err());
+
+        let mut sutta_indexes = HashMap::new();
+        sutta_indexes.insert("pli".to_string(), (index, reader));
+        let searcher = FulltextSearcher {
+            sutta_indexes,
+            dict_indexes: HashMap::new(),
+            library_indexes: HashMap::new(),
+        };
+
+        // Without the phonetic keys, the fuzzy search falls back to the
+        // stemmed content
+        let fuzzy = SearchFilters { fuzzy_distance: 1, ..SearchFilters::default() };
+        let (count, results) = searcher.search_suttas_with_count("satipatthana", &fuzzy, 10, 0).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(results[0].uid, "mn10/pli/ms");
+
+        let (count, _) = searcher.search_suttas_with_count("satipatana", &fuzzy, 10, 0).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_similar_terms() {
+        let (index, reader) = create_phonetic_test_index(&[
+            ("mn10/pli/ms", "cattāro satipaṭṭhānā"),
+            ("sn47.2/pli/ms", "cattāro satipaṭṭhānā bhāvetabbā"),
+            ("dn1/pli/ms", "evaṁ me sutaṁ"),
+        ]);
+        let mut sutta_indexes = HashMap::new();
+        sutta_indexes.insert("pli".to_string(), (index, reader));
+
+        let searcher = FulltextSearcher {
+            sutta_indexes,
+            dict_indexes: HashMap::new(),
+            library_indexes: HashMap::new(),
+        };
+
+        let filters = SearchFilters::default();
+        let terms = searcher.similar_terms(&SearchArea::Suttas, &filters, "satipatthna", 2).unwrap();
+        assert_eq!(terms, vec![("satipatthana".to_string(), 1, 2)]);
+
+        // The word itself, normalized, at distance 0
+        let terms = searcher.similar_terms(&SearchArea::Suttas, &filters, "Cattāro", 1).unwrap();
+        assert_eq!(terms, vec![("cattaro".to_string(), 0, 2)]);
+
+        assert!(searcher.similar_terms(&SearchArea::Dictionary, &filters, "cattaro", 1).unwrap().is_empty());
+    }
+
     /// Build an in-memory sutta index containing one doc per uid in `uids`.
     /// Each doc carries the same content so a content match doesn't filter
     /// any out — the only differentiator is `uid` / `uid_rev`.
@@ -1565,6 +1918,7 @@ mod tests {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         };
 
         // suffix "1.1" matches uids ending in "1.1" — only "an1.1/en/sujato" if we
@@ -1617,6 +1971,7 @@ mod tests {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         };
 
         let mut subqueries: Vec<(Occur, Box<dyn tantivy::query::Query>)> = Vec::new();
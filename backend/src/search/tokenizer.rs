@@ -1,9 +1,11 @@
 //! Custom Tantivy tokenizer filters for Pāli text.
 //!
-//! Provides two filters and a registration function:
+//! Provides three filters and a registration function:
 //! - `NiggahitaNormalizer`: normalizes ṃ→ṁ, strips √
 //! - `PaliStemmerFilter`: applies the Snowball Pāli stemmer
-//! - `register_tokenizers()`: registers `pali_stem` and `simple_fold` analyzers
+//! - `PhoneticKeyFilter`: replaces each token with its Pāli phonetic key
+//! - `register_tokenizers()`: registers `pali_stem`, `simple_fold` and
+//!   `pali_phonetic` analyzers
 
 use std::mem;
 
@@ -12,6 +14,7 @@ use tantivy::tokenizer::{
     TokenFilter, TokenStream, Tokenizer,
 };
 
+use crate::pali_phonetic::pali_phonetic_key;
 use crate::snowball::{self, Algorithm, Stemmer};
 
 // ---------------------------------------------------------------------------
@@ -173,16 +176,82 @@ impl<T: TokenStream> TokenStream for StemmerStream<'_, T> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// PhoneticKeyFilter
+// ---------------------------------------------------------------------------
+
+/// Replaces each token with its Pāli phonetic key (`pali_phonetic_key()`), so
+/// that `satipaṭṭhāna` and `satipatana` are the same term.
+#[derive(Clone)]
+pub struct PhoneticKeyFilter;
+
+impl TokenFilter for PhoneticKeyFilter {
+    type Tokenizer<T: Tokenizer> = PhoneticKeyWrapper<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> Self::Tokenizer<T> {
+        PhoneticKeyWrapper {
+            inner: tokenizer,
+            buffer: String::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PhoneticKeyWrapper<T> {
+    inner: T,
+    buffer: String,
+}
+
+impl<T: Tokenizer> Tokenizer for PhoneticKeyWrapper<T> {
+    type TokenStream<'a> = PhoneticKeyStream<'a, T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        self.buffer.clear();
+        PhoneticKeyStream {
+            tail: self.inner.token_stream(text),
+            buffer: &mut self.buffer,
+        }
+    }
+}
+
+pub struct PhoneticKeyStream<'a, T> {
+    tail: T,
+    buffer: &'a mut String,
+}
+
+impl<T: TokenStream> TokenStream for PhoneticKeyStream<'_, T> {
+    fn advance(&mut self) -> bool {
+        if !self.tail.advance() {
+            return false;
+        }
+        let key = pali_phonetic_key(&self.tail.token().text);
+        self.buffer.clear();
+        self.buffer.push_str(&key);
+        mem::swap(&mut self.tail.token_mut().text, self.buffer);
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Registration
 // ---------------------------------------------------------------------------
 
 /// Register custom tokenizers with a Tantivy index for the given language.
 ///
-/// Registers three analyzers:
+/// Registers four analyzers:
 /// - `{lang}_stem`: full stemming pipeline
 /// - `simple_fold`: lowercase + ASCII folding (no stemming)
 /// - `{lang}_normalize`: normalization without stemming
+/// - `pali_phonetic`: Pāli phonetic keys, for fuzzy matching of ASCII-typed
+///   Pāli (see `crate::pali_phonetic`)
 pub fn register_tokenizers(index: &tantivy::Index, lang: &str) {
     let algorithm = snowball::lang_to_algorithm(lang);
 
@@ -215,6 +284,20 @@ pub fn register_tokenizers(index: &tantivy::Index, lang: &str) {
     index
         .tokenizers()
         .register(&format!("{lang}_normalize"), lang_normalize);
+
+    index.tokenizers().register(PALI_PHONETIC_TOKENIZER, pali_phonetic_analyzer());
+}
+
+/// The name of the phonetic key analyzer, for the `content_phonetic` fields.
+pub const PALI_PHONETIC_TOKENIZER: &str = "pali_phonetic";
+
+/// The phonetic key analyzer. The key does its own lowercasing and Pāli
+/// folding; the token offsets are kept for the snippets.
+pub fn pali_phonetic_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(50))
+        .filter(PhoneticKeyFilter)
+        .build()
 }
 
 // ---------------------------------------------------------------------------
@@ -321,6 +404,15 @@ mod tests {
         assert_eq!(tokens[0].text, "dhammam");
     }
 
+    #[test]
+    fn test_pali_phonetic_analyzer() {
+        let mut a = pali_phonetic_analyzer();
+        let tokens = tokenize(&mut a, "Satipaṭṭhāna sati patthana");
+        let keys: Vec<&str> = tokens.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(keys, vec!["satipatana", "sati", "patana"]);
+        assert_eq!(tokens[0].offset_to, "Satipaṭṭhāna".len());
+    }
+
     #[test]
     fn test_offsets_preserved() {
         let mut a = pali_stem_analyzer();
@@ -34,6 +34,12 @@ pub struct SearchFilters {
     /// single-snippet. See docs/search-snippet-highlight-pipeline.md.
     #[serde(default)]
     pub show_all_snippets: bool,
+    /// When above 0, the query also matches the Pāli phonetic keys of the
+    /// content (`content_phonetic`), exactly and within this edit distance
+    /// (at most 2). Only the Suttas and Dict indexes have the phonetic field.
+    /// See docs/fuzzy-phonetic-search.md.
+    #[serde(default)]
+    pub fuzzy_distance: u8,
 }
 
 fn default_true() -> bool { true }
@@ -55,6 +61,7 @@ impl Default for SearchFilters {
             include_bold_definitions: true,
             dict_source_uids: None,
             show_all_snippets: false,
+            fuzzy_distance: 0,
         }
     }
 }
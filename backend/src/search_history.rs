@@ -0,0 +1,137 @@
+//! Search history: the searches run in the search window, with their area,
+//! mode, filters, hit count and the result opened from them.
+//!
+//! The history provides query suggestions together with the flat completion
+//! lists of sutta titles and dictionary words (`headword_index`). Recording the history can be
+//! turned off in the settings (`search_history_enabled`).
+//!
+//! See docs/search-history.md.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbManager;
+use crate::db::appdata_models::NewSearchHistoryItem;
+use crate::headword_index::{completion_key, dict_words_completion_list, sutta_titles_completion_list};
+use crate::saved_searches::{params_search_mode, parse_search_area};
+
+/// The most searches kept in the history, the oldest are removed.
+pub const MAX_SEARCH_HISTORY: i64 = 2000;
+
+/// Queries shorter than this get no suggestions.
+const MIN_SUGGESTION_QUERY_LEN: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionSource {
+    History,
+    SuttaTitle,
+    DictWord,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuerySuggestion {
+    pub text: String,
+    pub source: SuggestionSource,
+    /// A search from the history: the hits of its last run
+    pub hits_count: Option<i32>,
+    /// A search from the history: its `SearchParams` JSON, to run it again
+    pub params_json: Option<String>,
+}
+
+/// Collapse the whitespace of a query, so that the same query is recorded once.
+pub fn normalize_history_query(query_text: &str) -> String {
+    query_text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Record a search in the history. Returns the id of the history item, or
+/// None for an empty query.
+pub fn record_search(dbm: &DbManager,
+                     query_text: &str,
+                     search_area: &str,
+                     params_json: &str,
+                     hits_count: i32) -> Result<Option<i32>> {
+    let query_text = normalize_history_query(query_text);
+    if query_text.is_empty() {
+        return Ok(None);
+    }
+    parse_search_area(search_area)?;
+    let search_mode = params_search_mode(params_json)?;
+
+    let history_id = dbm.appdata.record_search_history(&NewSearchHistoryItem {
+        query_text: &query_text,
+        search_area,
+        search_mode: &search_mode,
+        params_json,
+        hits_count,
+        last_searched_at: chrono::Utc::now().naive_utc(),
+    }, MAX_SEARCH_HISTORY)?;
+    Ok(Some(history_id))
+}
+
+/// Suggestions for a query being typed: the earlier searches in the area
+/// starting with it, then the sutta titles (Suttas area) or the dictionary
+/// words (Dictionary area) matching it.
+pub fn query_suggestions(dbm: &DbManager,
+                         query_text: &str,
+                         search_area: &str,
+                         include_history: bool,
+                         limit: usize) -> Result<Vec<QuerySuggestion>> {
+    let query_text = normalize_history_query(query_text);
+    if query_text.chars().count() < MIN_SUGGESTION_QUERY_LEN {
+        return Ok(Vec::new());
+    }
+
+    let mut suggestions: Vec<QuerySuggestion> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    if include_history {
+        for item in dbm.appdata.get_search_history_matching(&query_text, search_area, limit as i64)? {
+            if item.query_text == query_text || !seen.insert(completion_key(&item.query_text)) {
+                continue;
+            }
+            suggestions.push(QuerySuggestion {
+                text: item.query_text,
+                source: SuggestionSource::History,
+                hits_count: Some(item.hits_count),
+                params_json: Some(item.params_json),
+            });
+        }
+    }
+
+    let (list, source) = match search_area {
+        "Suttas" => (sutta_titles_completion_list(dbm)?, SuggestionSource::SuttaTitle),
+        "Dictionary" => (dict_words_completion_list(dbm)?, SuggestionSource::DictWord),
+        _ => return Ok(suggestions),
+    };
+
+    let remaining = limit.saturating_sub(suggestions.len());
+    for text in list.complete(&query_text, remaining + seen.len()) {
+        if suggestions.len() >= limit {
+            break;
+        }
+        if !seen.insert(completion_key(&text)) {
+            continue;
+        }
+        suggestions.push(QuerySuggestion {
+            text,
+            source,
+            hits_count: None,
+            params_json: None,
+        });
+    }
+
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_history_query() {
+        assert_eq!(normalize_history_query("  sati   patthana "), "sati patthana");
+    }
+}
@@ -0,0 +1,388 @@
+//! Link the sutta references cited in the text of library books.
+//!
+//! Book chapters cite suttas in plain text ("MN 10", "SN 56.11", "Dhp 183",
+//! "D ii 290"). After a book is imported, the references are wrapped in
+//! `ssp://suttas/` links, and every cited sutta is recorded in
+//! `book_sutta_citations`, so that the sutta view can list the library
+//! chapters citing it.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use diesel::prelude::*;
+use regex::Regex;
+
+use crate::db::appdata_models::{BookSpineItem, NewBookSuttaCitation};
+use crate::helpers::{sutta_range_from_ref, verse_sutta_ref_to_uid, RE_ALL_BOOK_SUTTA_REF, RE_ALL_PTS_VOL_SUTTA_REF};
+use crate::pts_reference_search::search_by_pts_reference;
+
+/// A sutta reference found in a chapter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuttaRefLink {
+    /// The sutta uid without the /lang/author suffix, e.g. mn10, dhp179-196
+    pub sutta_uid: String,
+    /// The reference as written in the text, e.g. "MN 10"
+    pub ref_text: String,
+}
+
+lazy_static::lazy_static! {
+    static ref RE_TAG: Regex = Regex::new(r"(?s)<!--.*?-->|<[^>]*>").unwrap();
+    static ref RE_TAG_NAME: Regex = Regex::new(r"^<\s*(/?)\s*([a-zA-Z][a-zA-Z0-9]*)").unwrap();
+    static ref RE_SSP_SUTTA_HREF: Regex = Regex::new(r#"href\s*=\s*["']ssp://suttas/([^/"'#?]+)"#).unwrap();
+}
+
+/// Wrap the sutta references in the text of `html` in `ssp://suttas/` links.
+///
+/// `resolve_uid` is given a candidate uid (e.g. "mn10", "an1.5") and returns
+/// the uid of the sutta in the database which it refers to (e.g. "mn10",
+/// "an1.1-10"), or None to leave the reference as plain text.
+///
+/// Text inside existing links, `<script>` and `<style>` is not changed, but
+/// the existing `ssp://suttas/` links are returned with the new ones, so that
+/// linking a chapter again gives the same citations.
+pub fn link_sutta_refs_in_html<F>(html: &str, mut resolve_uid: F) -> (String, Vec<SuttaRefLink>)
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(html.len());
+    let mut links = Vec::new();
+    let mut link_depth = 0_usize;
+    let mut raw_text_tag: Option<String> = None;
+    // The uid and text of the ssp:// sutta link being read
+    let mut current_link: Option<(String, String)> = None;
+    let mut last_end = 0;
+
+    for tag in RE_TAG.find_iter(html) {
+        let text = &html[last_end..tag.start()];
+        if link_depth > 0 || raw_text_tag.is_some() {
+            out.push_str(text);
+            if let Some((_, link_text)) = current_link.as_mut() {
+                link_text.push_str(text);
+            }
+        } else {
+            out.push_str(&link_sutta_refs_in_text(text, &mut resolve_uid, &mut links));
+        }
+        out.push_str(tag.as_str());
+        last_end = tag.end();
+
+        let Some(caps) = RE_TAG_NAME.captures(tag.as_str()) else {
+            continue;
+        };
+        let is_closing = !caps[1].is_empty();
+        let name = caps[2].to_lowercase();
+
+        if let Some(raw_tag) = &raw_text_tag {
+            if is_closing && name == *raw_tag {
+                raw_text_tag = None;
+            }
+            continue;
+        }
+
+        match (name.as_str(), is_closing) {
+            ("script" | "style", false) => raw_text_tag = Some(name.clone()),
+            ("a", false) => {
+                if tag.as_str().ends_with("/>") {
+                    continue;
+                }
+                link_depth += 1;
+                if let Some(href) = RE_SSP_SUTTA_HREF.captures(tag.as_str()) {
+                    current_link = Some((href[1].to_lowercase(), String::new()));
+                }
+            }
+            ("a", true) => {
+                link_depth = link_depth.saturating_sub(1);
+                if let Some((sutta_uid, link_text)) = current_link.take() {
+                    let ref_text = crate::helpers::strip_html(&link_text).trim().to_string();
+                    links.push(SuttaRefLink {
+                        ref_text: if ref_text.is_empty() { sutta_uid.clone() } else { ref_text },
+                        sutta_uid,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let text = &html[last_end..];
+    if link_depth > 0 || raw_text_tag.is_some() {
+        out.push_str(text);
+    } else {
+        out.push_str(&link_sutta_refs_in_text(text, &mut resolve_uid, &mut links));
+    }
+
+    (out, links)
+}
+
+/// Link the references in a text segment between tags.
+fn link_sutta_refs_in_text<F>(text: &str, resolve_uid: &mut F, links: &mut Vec<SuttaRefLink>) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    // (start, end, candidate uids), PTS references first, as "MN iii 203"
+    // must not be read as a book reference.
+    let mut matches: Vec<(usize, usize, Vec<String>)> = Vec::new();
+
+    for caps in RE_ALL_PTS_VOL_SUTTA_REF.captures_iter(text) {
+        let m = caps.get(0).unwrap();
+        if !starts_uppercase(m.as_str()) {
+            continue;
+        }
+        let candidates = pts_ref_candidate_uids(&caps[1], &caps[2], &caps[3]);
+        if !candidates.is_empty() {
+            matches.push((m.start(), m.end(), candidates));
+        }
+    }
+
+    for caps in RE_ALL_BOOK_SUTTA_REF.captures_iter(text) {
+        let m = caps.get(0).unwrap();
+        if !starts_uppercase(m.as_str())
+            || matches.iter().any(|(start, end, _)| m.start() < *end && *start < m.end()) {
+            continue;
+        }
+        let candidates = book_ref_candidate_uids(&caps[1], &caps[2]);
+        if !candidates.is_empty() {
+            matches.push((m.start(), m.end(), candidates));
+        }
+    }
+
+    if matches.is_empty() {
+        return text.to_string();
+    }
+    matches.sort_by_key(|(start, _, _)| *start);
+
+    let mut out = String::with_capacity(text.len() + matches.len() * 64);
+    let mut last_end = 0;
+    for (start, end, candidates) in matches {
+        let Some(sutta_uid) = candidates.iter().find_map(|uid| resolve_uid(uid)) else {
+            continue;
+        };
+        let ref_text = &text[start..end];
+        out.push_str(&text[last_end..start]);
+        out.push_str(&format!(
+            r#"<a href="ssp://suttas/{}/pli/ms" class="sutta-link">{}</a>"#,
+            sutta_uid, ref_text
+        ));
+        links.push(SuttaRefLink {
+            sutta_uid,
+            ref_text: html_escape::decode_html_entities(ref_text).to_string(),
+        });
+        last_end = end;
+    }
+    out.push_str(&text[last_end..]);
+    out
+}
+
+/// References are written with a capital, which keeps the English words
+/// ("an", "th") from matching.
+fn starts_uppercase(s: &str) -> bool {
+    s.chars().next().is_some_and(|c| c.is_uppercase())
+}
+
+/// The uids a book reference may refer to, in order of preference.
+///
+/// "MN 10" -> mn10, "Dhp 183" -> dhp179-196, "Sn 4:2" -> snp4.2. A paragraph
+/// number is dropped as a fallback: "DN 22.3" -> dn22, "SN 56.11.5" -> sn56.11.
+pub fn book_ref_candidate_uids(nikaya: &str, number: &str) -> Vec<String> {
+    let number = number.replace(':', ".");
+    let number = number.trim_end_matches('.');
+    let nikaya = match nikaya.to_lowercase().as_str() {
+        // "Sn" is the Suttanipāta, "SN" the Saṁyutta Nikāya
+        "sn" if nikaya == "Sn" => "snp".to_string(),
+        "khp" => "kp".to_string(),
+        "uda" => "ud".to_string(),
+        "th" => "thag".to_string(),
+        // The Visuddhimagga is not in the suttas
+        "vism" => return Vec::new(),
+        other => other.to_string(),
+    };
+
+    // A single number of the verse collections is a verse number
+    if matches!(nikaya.as_str(), "dhp" | "snp" | "thag" | "thig")
+        && !number.contains('.')
+        && let Some(uid) = verse_sutta_ref_to_uid(&format!("{}{}", nikaya, number)) {
+            return vec![uid];
+        }
+
+    let mut candidates = vec![format!("{}{}", nikaya, number)];
+    let parts: Vec<&str> = number.split('.').collect();
+    let sutta_parts = if matches!(nikaya.as_str(), "dn" | "mn") { 1 } else { 2 };
+    if parts.len() > sutta_parts {
+        candidates.push(format!("{}{}", nikaya, parts[..sutta_parts].join(".")));
+    }
+    candidates
+}
+
+/// The uids a PTS volume and page reference may refer to, from the sutta
+/// reference table. "D ii 290" -> dn22.
+pub fn pts_ref_candidate_uids(nikaya: &str, volume: &str, page: &str) -> Vec<String> {
+    if crate::try_get_sutta_references().is_none() {
+        return Vec::new();
+    }
+    let nikaya = match nikaya.to_lowercase().as_str() {
+        "dn" => "d".to_string(),
+        "mn" => "m".to_string(),
+        "sn" => "s".to_string(),
+        "an" => "a".to_string(),
+        other => other.to_string(),
+    };
+    let query = format!("{} {} {}", nikaya, volume, page.trim_end_matches('.'));
+    search_by_pts_reference(&query)
+        .into_iter()
+        .filter_map(|r| r.url.rsplit('/').next().map(|uid| uid.to_lowercase()))
+        .filter(|uid| !uid.is_empty())
+        .collect()
+}
+
+/// The uid (without the /lang/author suffix) of the sutta `candidate` refers
+/// to: a sutta with that uid, or the sutta whose range includes it, e.g.
+/// an1.5 -> an1.1-10.
+pub fn resolve_sutta_uid(db_conn: &mut SqliteConnection, candidate: &str) -> Option<String> {
+    use crate::db::appdata_schema::suttas::dsl::*;
+
+    // The uids from "mn10/" up to "mn100" are those starting with "mn10/",
+    // as '0' follows '/'.
+    let found = suttas
+        .filter(uid.gt(format!("{}/", candidate)))
+        .filter(uid.lt(format!("{}0", candidate)))
+        .select(uid)
+        .first::<String>(db_conn)
+        .optional()
+        .ok()?;
+    if found.is_some() {
+        return Some(candidate.to_string());
+    }
+
+    let range = sutta_range_from_ref(candidate)?;
+    let range_start = range.start? as i32;
+    let found = suttas
+        .filter(sutta_range_group.eq(&range.group))
+        .filter(sutta_range_start.le(range_start))
+        .filter(sutta_range_end.ge(range_start))
+        .order(uid.asc())
+        .select(uid)
+        .first::<String>(db_conn)
+        .optional()
+        .ok()??;
+    found.split('/').next().map(|s| s.to_string())
+}
+
+/// Link the sutta references in the chapters of a book, and replace its rows
+/// in `book_sutta_citations`.
+///
+/// # Returns
+/// * `Result<usize>` - The number of (chapter, sutta) citations
+pub fn link_sutta_refs_in_book(db_conn: &mut SqliteConnection, book_uid_param: &str) -> Result<usize> {
+    use crate::db::appdata_schema::{book_spine_items, book_sutta_citations};
+
+    crate::init_sutta_references();
+
+    db_conn.transaction::<_, anyhow::Error, _>(|db_conn| {
+        let spine_items: Vec<BookSpineItem> = book_spine_items::table
+            .filter(book_spine_items::book_uid.eq(book_uid_param))
+            .order(book_spine_items::spine_index.asc())
+            .select(BookSpineItem::as_select())
+            .load(db_conn)
+            .context("Failed to load spine items")?;
+
+        diesel::delete(book_sutta_citations::table.filter(book_sutta_citations::book_uid.eq(book_uid_param)))
+            .execute(db_conn)
+            .context("Failed to delete old citations")?;
+
+        let mut resolved: HashMap<String, Option<String>> = HashMap::new();
+        let mut count = 0;
+
+        for item in &spine_items {
+            let Some(html) = &item.content_html else {
+                continue;
+            };
+            let (linked_html, links) = link_sutta_refs_in_html(html, |candidate| {
+                resolved
+                    .entry(candidate.to_string())
+                    .or_insert_with(|| resolve_sutta_uid(db_conn, candidate))
+                    .clone()
+            });
+
+            if &linked_html != html {
+                diesel::update(book_spine_items::table.filter(book_spine_items::id.eq(item.id)))
+                    .set(book_spine_items::content_html.eq(Some(linked_html.as_str())))
+                    .execute(db_conn)
+                    .with_context(|| format!("Failed to update spine item: {}", item.spine_item_uid))?;
+            }
+
+            // One row per sutta, with the first reference text
+            let mut citations: Vec<(SuttaRefLink, i32)> = Vec::new();
+            for link in links {
+                match citations.iter_mut().find(|(c, _)| c.sutta_uid == link.sutta_uid) {
+                    Some((_, n)) => *n += 1,
+                    None => citations.push((link, 1)),
+                }
+            }
+
+            for (link, n) in &citations {
+                diesel::insert_into(book_sutta_citations::table)
+                    .values(&NewBookSuttaCitation {
+                        book_id: item.book_id,
+                        book_uid: &item.book_uid,
+                        spine_item_uid: &item.spine_item_uid,
+                        sutta_uid: &link.sutta_uid,
+                        ref_text: &link.ref_text,
+                        ref_count: *n,
+                    })
+                    .execute(db_conn)
+                    .with_context(|| format!("Failed to insert citation: {}", link.sutta_uid))?;
+            }
+            count += citations.len();
+        }
+
+        Ok(count)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(candidate: &str) -> Option<String> {
+        match candidate {
+            "mn10" | "sn56.11" | "dn22" | "dhp179-196" | "snp4.2" => Some(candidate.to_string()),
+            "an1.5" => Some("an1.1-10".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_link_sutta_refs_in_html() {
+        let html = r#"<p>See MN 10, SN 56.11 and Dhp 183.</p><p><a href="x.html">MN 10</a> AN 4 Vism 152 <b>AN 1.5</b></p>"#;
+        let (linked, links) = link_sutta_refs_in_html(html, resolve);
+        assert_eq!(
+            linked,
+            r#"<p>See <a href="ssp://suttas/mn10/pli/ms" class="sutta-link">MN 10</a>, <a href="ssp://suttas/sn56.11/pli/ms" class="sutta-link">SN 56.11</a> and <a href="ssp://suttas/dhp179-196/pli/ms" class="sutta-link">Dhp 183</a>.</p><p><a href="x.html">MN 10</a> AN 4 Vism 152 <b><a href="ssp://suttas/an1.1-10/pli/ms" class="sutta-link">AN 1.5</a></b></p>"#
+        );
+        let uids: Vec<&str> = links.iter().map(|l| l.sutta_uid.as_str()).collect();
+        assert_eq!(uids, vec!["mn10", "sn56.11", "dhp179-196", "an1.1-10"]);
+
+        // Linking again keeps the html and gives the same citations
+        let (linked_again, links_again) = link_sutta_refs_in_html(&linked, resolve);
+        assert_eq!(linked_again, linked);
+        assert_eq!(links_again, links);
+    }
+
+    #[test]
+    fn test_skips_lowercase_and_raw_text() {
+        let html = "<style>.mn 10 {}</style><p>an 8 in 5, Sn 4:2</p>";
+        let (linked, links) = link_sutta_refs_in_html(html, resolve);
+        assert_eq!(linked, r#"<style>.mn 10 {}</style><p>an 8 in 5, <a href="ssp://suttas/snp4.2/pli/ms" class="sutta-link">Sn 4:2</a></p>"#);
+        assert_eq!(links.len(), 1);
+    }
+
+    #[test]
+    fn test_book_ref_candidate_uids() {
+        assert_eq!(book_ref_candidate_uids("MN", "10"), vec!["mn10"]);
+        assert_eq!(book_ref_candidate_uids("DN", "22.3"), vec!["dn22.3", "dn22"]);
+        assert_eq!(book_ref_candidate_uids("SN", "56.11.5"), vec!["sn56.11.5", "sn56.11"]);
+        assert_eq!(book_ref_candidate_uids("Sn", "4:2"), vec!["snp4.2"]);
+        assert_eq!(book_ref_candidate_uids("SN", "22"), vec!["sn22"]);
+        assert_eq!(book_ref_candidate_uids("Dhp", "183"), vec!["dhp179-196"]);
+        assert!(book_ref_candidate_uids("Vism", "152").is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+//! The device which starts a sync: pairing with a peer's one-time code, and
+//! exchanging changes with it over its `/v1/sync` routes.
+//!
+//! The HTTP calls are behind `SyncTransport`, so the tests can run both
+//! devices in one process against Rocket's local client.
+
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use crate::db::DbManager;
+use crate::logger::info;
+
+use super::{store, with_sync_conns, PairRequest, PairResponse, SyncPeer, SyncReport, SyncRequest, SyncResponse};
+use super::entities::{apply_remote, record_local_changes};
+
+pub trait SyncTransport {
+    /// `POST <url>/v1/sync/pair`
+    fn pair(&self, url: &str, req: &PairRequest) -> Result<PairResponse>;
+    /// `POST <url>/v1/sync/exchange` with the peer's secret.
+    fn exchange(&self, url: &str, secret: &str, req: &SyncRequest) -> Result<SyncResponse>;
+}
+
+/// `SyncTransport` over HTTP. `url` is the peer's API server, e.g.
+/// `http://192.168.1.20:4848`.
+pub struct HttpTransport {
+    client: reqwest::blocking::Client,
+}
+
+impl HttpTransport {
+    pub fn new() -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .context("Failed to build the HTTP client")?;
+        Ok(Self { client })
+    }
+
+    fn post<Req: serde::Serialize, Resp: serde::de::DeserializeOwned>(&self, url: &str, path: &str, secret: Option<&str>, body: &Req) -> Result<Resp> {
+        let endpoint = format!("{}{}", url.trim_end_matches('/'), path);
+        let mut req = self.client.post(&endpoint).json(body);
+        if let Some(secret) = secret {
+            req = req.bearer_auth(secret);
+        }
+
+        let resp = req.send().with_context(|| format!("Can't reach {}", endpoint))?;
+        let status = resp.status();
+        if !status.is_success() {
+            // The /v1 error body: {"error": {"code": ..., "message": ...}}
+            let message = resp.json::<serde_json::Value>().ok()
+                .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+                .unwrap_or_default();
+            bail!("{} returned {}: {}", endpoint, status, message);
+        }
+
+        resp.json::<Resp>().with_context(|| format!("Bad response from {}", endpoint))
+    }
+}
+
+impl SyncTransport for HttpTransport {
+    fn pair(&self, url: &str, req: &PairRequest) -> Result<PairResponse> {
+        self.post(url, "/v1/sync/pair", None, req)
+    }
+
+    fn exchange(&self, url: &str, secret: &str, req: &SyncRequest) -> Result<SyncResponse> {
+        self.post(url, "/v1/sync/exchange", Some(secret), req)
+    }
+}
+
+/// Pair with the device at `url` using the code it shows, and remember it
+/// as a peer.
+pub fn pair_with(dbm: &DbManager, transport: &dyn SyncTransport, url: &str, code: &str) -> Result<SyncPeer> {
+    let (device_id, device_name) = super::device_identity(dbm)?;
+
+    let resp = transport.pair(url, &PairRequest {
+        code: code.trim().to_string(),
+        device_id,
+        device_name,
+    })?;
+    info(&format!("sync: paired with {} ({})", resp.device_name, resp.device_id));
+
+    dbm.appdata.do_write(|db_conn| {
+        store::upsert_peer(db_conn, &resp.device_id, &resp.device_name, Some(url), &resp.secret)
+    })?;
+
+    super::find_peer(dbm, &resp.device_id)
+        .context("The new peer was not saved")
+}
+
+/// Send the local changes the peer hasn't seen and merge the ones it sends
+/// back. The database is not locked while waiting for the peer.
+pub fn sync_with(dbm: &DbManager, transport: &dyn SyncTransport, peer: &SyncPeer) -> Result<SyncReport> {
+    let Some(url) = peer.url.as_deref() else {
+        bail!("{} syncs with this device, not the other way around", peer.name);
+    };
+
+    let (request, pushed_seq) = with_sync_conns(dbm, |conns, w| {
+        record_local_changes(conns, w)?;
+        let records = store::records_since(conns.appdata, peer.pushed_seq, &peer.device_id)?;
+        let req = SyncRequest { device_id: w.device_id.clone(), since: peer.pulled_seq, records };
+        Ok((req, w.seq))
+    })?;
+    let sent = request.records.len();
+
+    let resp = transport.exchange(url, &peer.secret, &request)?;
+    if resp.device_id != peer.device_id {
+        bail!("{} answered as another device ({})", url, resp.device_id);
+    }
+    let received = resp.records.len();
+
+    let applied = with_sync_conns(dbm, |conns, w| {
+        // Edits made while waiting for the peer, so they aren't overwritten.
+        record_local_changes(conns, w)?;
+        let n = apply_remote(conns, w, resp.records)?;
+        store::update_peer_cursors(conns.appdata, &peer.device_id, resp.cursor, pushed_seq)?;
+        Ok(n)
+    })?;
+
+    info(&format!("sync: {} sent {}, received {}, applied {}", peer.name, sent, received, applied));
+    Ok(SyncReport { sent, received, applied })
+}
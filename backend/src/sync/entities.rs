@@ -0,0 +1,698 @@
+//! The synced tables, read into the change log as field maps and written
+//! back from it.
+//!
+//! Each table is described by an `EntitySpec`; the rows are read with
+//! `json_object()` and written with `json_extract()`, so one code path
+//! serves all of them. The app settings are the one entity which is not a
+//! table: a single row whose fields are the top-level `AppSettings` keys.
+
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{Integer, Nullable, Text};
+use serde_json::{Map, Value};
+
+use crate::app_settings::AppSettings;
+use crate::helpers::random_hex;
+use crate::logger::warn;
+
+use super::merge::{self, RowState, DELETED_FIELD};
+use super::store;
+use super::SyncRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDb {
+    Appdata,
+    Dictionaries,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum RowKey {
+    /// A unique text column with the same value on every device.
+    Column(&'static str),
+    /// Only the local integer id, the uid is kept in `sync_ids`.
+    Mapped,
+}
+
+#[derive(Debug)]
+pub struct EntitySpec {
+    pub entity: &'static str,
+    pub db: SyncDb,
+    table: &'static str,
+    key: RowKey,
+    /// Synced columns, the field names are the column names.
+    columns: &'static [&'static str],
+    /// `(column, entity)`: a column with the local id of a row of another
+    /// `Mapped` entity, synced as that row's uid.
+    parent: Option<(&'static str, &'static str)>,
+    /// Which rows are synced, on the table alias `t`.
+    filter: &'static str,
+    /// Extra `(column, SQL value)` pairs of a row inserted by a sync.
+    insert_defaults: &'static [(&'static str, &'static str)],
+    /// Rows are created and deleted by syncs. Otherwise a sync only updates
+    /// rows which exist on both devices, e.g. the metadata of a dictionary
+    /// which was imported on both, or of a recording whose audio file is
+    /// not synced.
+    pub create_delete: bool,
+}
+
+/// Parents before their children.
+pub const ENTITIES: &[EntitySpec] = &[
+    EntitySpec {
+        entity: "bookmark_folder",
+        db: SyncDb::Appdata,
+        table: "bookmark_folders",
+        key: RowKey::Mapped,
+        columns: &["name", "sort_order"],
+        parent: None,
+        // The last session folders hold each device's own open tabs.
+        filter: "t.is_last_session = 0",
+        insert_defaults: &[("is_last_session", "0"), ("is_user_added", "1")],
+        create_delete: true,
+    },
+    EntitySpec {
+        entity: "bookmark_item",
+        db: SyncDb::Appdata,
+        table: "bookmark_items",
+        key: RowKey::Mapped,
+        columns: &["item_uid", "table_name", "title", "tab_group", "scroll_position", "find_query", "find_match_index", "sort_order"],
+        parent: Some(("folder_id", "bookmark_folder")),
+        filter: "t.folder_id IN (SELECT id FROM bookmark_folders WHERE is_last_session = 0)",
+        insert_defaults: &[("is_user_added", "1")],
+        create_delete: true,
+    },
+    EntitySpec {
+        entity: "history",
+        db: SyncDb::Appdata,
+        table: "gloss_prompts_history",
+        key: RowKey::Mapped,
+        columns: &["item_type", "data_json"],
+        parent: None,
+        filter: "1 = 1",
+        insert_defaults: &[("updated_at", "CURRENT_TIMESTAMP")],
+        create_delete: true,
+    },
+    EntitySpec {
+        entity: "chanting_collection",
+        db: SyncDb::Appdata,
+        table: "chanting_collections",
+        key: RowKey::Column("uid"),
+        columns: &["title", "description", "language", "sort_index", "metadata_json"],
+        parent: None,
+        filter: "t.is_user_added = 1",
+        insert_defaults: &[("is_user_added", "1")],
+        create_delete: true,
+    },
+    EntitySpec {
+        entity: "chanting_chant",
+        db: SyncDb::Appdata,
+        table: "chanting_chants",
+        key: RowKey::Column("uid"),
+        columns: &["collection_uid", "title", "description", "sort_index", "metadata_json"],
+        parent: None,
+        filter: "t.is_user_added = 1",
+        insert_defaults: &[("is_user_added", "1")],
+        create_delete: true,
+    },
+    EntitySpec {
+        entity: "chanting_section",
+        db: SyncDb::Appdata,
+        table: "chanting_sections",
+        key: RowKey::Column("uid"),
+        columns: &["chant_uid", "title", "content_pali", "sort_index", "metadata_json"],
+        parent: None,
+        filter: "t.is_user_added = 1",
+        insert_defaults: &[("is_user_added", "1")],
+        create_delete: true,
+    },
+    EntitySpec {
+        entity: "chanting_recording",
+        db: SyncDb::Appdata,
+        table: "chanting_recordings",
+        key: RowKey::Column("uid"),
+        columns: &["label", "markers_json", "volume"],
+        parent: None,
+        filter: "t.is_user_added = 1",
+        insert_defaults: &[],
+        create_delete: false,
+    },
+    EntitySpec {
+        entity: "dictionary",
+        db: SyncDb::Dictionaries,
+        table: "dictionaries",
+        key: RowKey::Column("label"),
+        columns: &["title", "description", "creator", "feedback_email", "feedback_url", "version", "language"],
+        parent: None,
+        filter: "t.is_user_imported = 1",
+        insert_defaults: &[],
+        create_delete: false,
+    },
+];
+
+pub const SETTINGS_ENTITY: &str = "setting";
+pub const SETTINGS_UID: &str = "app_settings";
+
+/// `AppSettings` keys which stay on the device: platform and window state,
+/// shortcuts, caches, and the AI provider keys.
+const LOCAL_SETTINGS: &[&str] = &[
+    "api_keys",
+    "providers",
+    "first_time_start",
+    "mobile_top_bar_margin",
+    "app_keybindings",
+    "global_hotkeys",
+    "cached_shipped_source_uids",
+    "cached_commentary_definitions_source_uids",
+    "cached_sutta_languages",
+    "cached_dict_languages",
+    "cached_library_languages",
+    "render_use_flat_results_background",
+    "render_disable_results_clip",
+    "render_loop_basic",
+    "item_height_use_default",
+    "item_height_fixed",
+    "sync_folder",
+    "sync_lan_enabled",
+    "sync_lan_port",
+    "backup_interval_hours",
+    "backup_keep",
+    "backup_folder",
+];
+
+pub fn entity_spec(entity: &str) -> Option<&'static EntitySpec> {
+    ENTITIES.iter().find(|s| s.entity == entity)
+}
+
+impl EntitySpec {
+    fn has_column(&self, field: &str) -> bool {
+        self.columns.contains(&field) || self.parent.is_some_and(|(col, _)| col == field)
+    }
+
+    /// SQL reading `local_id`, `uid` (NULL for a row without one yet),
+    /// `row_created_at` and `fields_json` of the synced rows.
+    fn select_sql(&self) -> String {
+        let mut pairs: Vec<String> = self.columns.iter()
+            .map(|c| format!("'{c}', t.{c}"))
+            .collect();
+        if let Some((col, parent)) = self.parent {
+            pairs.push(format!("'{col}', (SELECT p.uid FROM sync_ids p WHERE p.entity = '{parent}' AND p.local_id = t.{col})"));
+        }
+
+        let (uid, created) = match self.key {
+            RowKey::Column(c) => (format!("t.{c}"), "NULL".to_string()),
+            RowKey::Mapped => (
+                format!(
+                    "(SELECT s.uid FROM sync_ids s WHERE s.entity = '{}' AND s.local_id = t.id AND s.row_created_at IS CAST(t.created_at AS TEXT))",
+                    self.entity
+                ),
+                "CAST(t.created_at AS TEXT)".to_string(),
+            ),
+        };
+
+        format!(
+            "SELECT t.id AS local_id, {uid} AS uid, {created} AS row_created_at, json_object({}) AS fields_json FROM {} t WHERE {} ORDER BY t.id",
+            pairs.join(", "), self.table, self.filter
+        )
+    }
+
+    /// Matches the row with the uid bound at `param`.
+    fn key_match(&self, param: &str) -> String {
+        match self.key {
+            RowKey::Column(c) => format!("{c} = {param}"),
+            RowKey::Mapped => format!("id = (SELECT local_id FROM sync_ids WHERE entity = '{}' AND uid = {param})", self.entity),
+        }
+    }
+
+    /// The value of `column` from the field map bound as `?1`.
+    fn value_expr(&self, column: &str) -> String {
+        match self.parent {
+            Some((col, parent)) if col == column => format!(
+                "(SELECT local_id FROM sync_ids WHERE entity = '{parent}' AND uid = json_extract(?1, '$.{col}'))"
+            ),
+            _ => format!("json_extract(?1, '$.{column}')"),
+        }
+    }
+
+    fn all_columns(&self) -> Vec<&'static str> {
+        let mut cols = self.columns.to_vec();
+        if let Some((col, _)) = self.parent {
+            cols.push(col);
+        }
+        cols
+    }
+}
+
+/// The appdata and the dictionaries connections of one sync.
+pub struct SyncConns<'a> {
+    pub appdata: &'a mut SqliteConnection,
+    pub dictionaries: &'a mut SqliteConnection,
+}
+
+impl SyncConns<'_> {
+    fn get(&mut self, db: SyncDb) -> &mut SqliteConnection {
+        match db {
+            SyncDb::Appdata => self.appdata,
+            SyncDb::Dictionaries => self.dictionaries,
+        }
+    }
+}
+
+/// Writes to the change log, numbering the records with the local sequence.
+pub struct Writer {
+    pub device_id: String,
+    pub now_ms: i64,
+    pub seq: i64,
+    pub settings_changed: bool,
+}
+
+impl Writer {
+    fn local_change(&mut self, db_conn: &mut SqliteConnection, entity: &str, uid: &str, field: &str, value: Value, previous: Option<&SyncRecord>) -> QueryResult<()> {
+        let record = SyncRecord {
+            entity: entity.to_string(),
+            uid: uid.to_string(),
+            field: field.to_string(),
+            value,
+            modified_at: merge::next_stamp(self.now_ms, previous.map(|p| p.modified_at)),
+            device_id: self.device_id.clone(),
+        };
+        self.store(db_conn, &record)
+    }
+
+    /// Record a value nobody has changed yet, e.g. a default setting: it
+    /// loses to an edit on any device.
+    fn baseline(&mut self, db_conn: &mut SqliteConnection, entity: &str, uid: &str, field: &str, value: Value) -> QueryResult<()> {
+        let record = SyncRecord {
+            entity: entity.to_string(),
+            uid: uid.to_string(),
+            field: field.to_string(),
+            value,
+            modified_at: 0,
+            device_id: self.device_id.clone(),
+        };
+        self.store(db_conn, &record)
+    }
+
+    fn store(&mut self, db_conn: &mut SqliteConnection, record: &SyncRecord) -> QueryResult<()> {
+        self.seq += 1;
+        store::write_record(db_conn, record, self.seq).map(|_| ())
+    }
+}
+
+#[derive(QueryableByName)]
+struct LocalRow {
+    #[diesel(sql_type = Integer)]
+    local_id: i32,
+    #[diesel(sql_type = Nullable<Text>)]
+    uid: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    row_created_at: Option<String>,
+    #[diesel(sql_type = Text)]
+    fields_json: String,
+}
+
+/// Compare the synced rows with the change log and record what changed
+/// since the last scan. Returns the number of changed fields.
+pub fn record_local_changes(conns: &mut SyncConns, w: &mut Writer) -> QueryResult<usize> {
+    let mut n = 0;
+    for spec in ENTITIES {
+        n += scan_entity(conns, w, spec)?;
+    }
+    n += scan_settings(conns.appdata, w)?;
+    Ok(n)
+}
+
+fn scan_entity(conns: &mut SyncConns, w: &mut Writer, spec: &EntitySpec) -> QueryResult<usize> {
+    let rows: Vec<LocalRow> = sql_query(spec.select_sql()).load(conns.get(spec.db))?;
+    let mut stored = store::load_stored(conns.appdata, spec.entity)?;
+    let mut n = 0;
+
+    for row in rows {
+        let uid = match row.uid {
+            Some(uid) => uid,
+            None => {
+                let uid = random_hex(2);
+                store::map_local_id(conns.appdata, spec.entity, row.local_id, &uid, row.row_created_at.as_deref())?;
+                uid
+            }
+        };
+
+        let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(&row.fields_json) else {
+            warn(&format!("sync: can't read {} {}", spec.entity, uid));
+            continue;
+        };
+
+        let prev = stored.remove(&uid).unwrap_or_default();
+        if merge::row_state(prev.values()) == RowState::Deleted {
+            // Created again under the same uid.
+            w.local_change(conns.appdata, spec.entity, &uid, DELETED_FIELD, Value::Bool(false), prev.get(DELETED_FIELD))?;
+            n += 1;
+        }
+
+        for (field, value) in fields {
+            let old = prev.get(&field);
+            if old.is_none_or(|o| o.value != value) {
+                w.local_change(conns.appdata, spec.entity, &uid, &field, value, old)?;
+                n += 1;
+            }
+        }
+    }
+
+    // What is left was deleted since the last scan.
+    if spec.create_delete {
+        for (uid, prev) in stored {
+            if merge::row_state(prev.values()) == RowState::Live {
+                w.local_change(conns.appdata, spec.entity, &uid, DELETED_FIELD, Value::Bool(true), prev.get(DELETED_FIELD))?;
+                n += 1;
+            }
+        }
+    }
+
+    Ok(n)
+}
+
+/// Restore the change log of a replaced appdata database (an upgrade
+/// through the import-me folder). The re-imported rows have new local ids, so
+/// each one without a uid is given the uid of a live logged row with the same
+/// values, and the logged rows which weren't re-imported (e.g. history) are
+/// created again. Otherwise the next scan would send them as new rows, and
+/// the old ones as deleted. Returns the number of re-created rows.
+pub fn restore_change_log(conns: &mut SyncConns, w: &mut Writer, records: Vec<SyncRecord>) -> QueryResult<usize> {
+    for r in &records {
+        w.store(conns.appdata, r)?;
+    }
+
+    let mut n = 0;
+    for spec in ENTITIES {
+        let rows: Vec<LocalRow> = sql_query(spec.select_sql()).load(conns.get(spec.db))?;
+        let mut stored = store::load_stored(conns.appdata, spec.entity)?;
+        stored.retain(|_, fields| merge::row_state(fields.values()) == RowState::Live);
+
+        let mut unmapped = Vec::new();
+        for row in rows {
+            match &row.uid {
+                Some(uid) => {
+                    stored.remove(uid);
+                }
+                None => unmapped.push(row),
+            }
+        }
+
+        for row in unmapped {
+            let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(&row.fields_json) else {
+                continue;
+            };
+            let same = stored.iter()
+                .find(|(_, prev)| fields.iter().all(|(k, v)| prev.get(k).is_some_and(|r| &r.value == v)))
+                .map(|(uid, _)| uid.clone());
+            if let Some(uid) = same {
+                store::map_local_id(conns.appdata, spec.entity, row.local_id, &uid, row.row_created_at.as_deref())?;
+                stored.remove(&uid);
+            }
+        }
+
+        for (uid, fields) in stored {
+            match apply_row(conns, spec, &uid, &fields) {
+                Ok(true) => n += 1,
+                Ok(false) => {}
+                Err(e) => warn(&format!("sync: can't restore {} {}: {}", spec.entity, uid, e)),
+            }
+        }
+    }
+
+    Ok(n)
+}
+
+#[derive(QueryableByName)]
+struct SettingsRow {
+    #[diesel(sql_type = Nullable<Text>)]
+    value: Option<String>,
+}
+
+/// The stored settings as a JSON object with every key, defaults filled in.
+fn read_settings(db_conn: &mut SqliteConnection) -> QueryResult<Map<String, Value>> {
+    let row: Option<SettingsRow> = sql_query(r#"SELECT value FROM app_settings WHERE "key" = 'app_settings'"#)
+        .get_result(db_conn)
+        .optional()?;
+
+    let settings: AppSettings = row.and_then(|r| r.value)
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default();
+
+    match serde_json::to_value(settings) {
+        Ok(Value::Object(m)) => Ok(m),
+        _ => Ok(Map::new()),
+    }
+}
+
+fn scan_settings(db_conn: &mut SqliteConnection, w: &mut Writer) -> QueryResult<usize> {
+    let fields = read_settings(db_conn)?;
+    let stored = store::load_stored(db_conn, SETTINGS_ENTITY)?;
+    let prev = stored.get(SETTINGS_UID).cloned().unwrap_or_default();
+    let defaults = serde_json::to_value(AppSettings::default()).unwrap_or_default();
+    let mut n = 0;
+
+    for (field, value) in fields {
+        if LOCAL_SETTINGS.contains(&field.as_str()) {
+            continue;
+        }
+        match prev.get(&field) {
+            // Not to override the other device's choice on the first sync.
+            None if defaults.get(&field) == Some(&value) => {
+                w.baseline(db_conn, SETTINGS_ENTITY, SETTINGS_UID, &field, value)?;
+            }
+            old if old.is_none_or(|o| o.value != value) => {
+                w.local_change(db_conn, SETTINGS_ENTITY, SETTINGS_UID, &field, value, old)?;
+                n += 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(n)
+}
+
+/// Merge records from a peer into the change log and the synced tables.
+/// Returns the number of rows changed on this device.
+///
+/// A row which can't be written (e.g. a bookmark whose folder was deleted
+/// here) is logged and skipped, and its records are not stored, so the next
+/// scan doesn't mistake its absence for a local delete.
+pub fn apply_remote(conns: &mut SyncConns, w: &mut Writer, records: Vec<SyncRecord>) -> QueryResult<usize> {
+    let mut by_entity: HashMap<String, HashMap<String, Vec<SyncRecord>>> = HashMap::new();
+    for r in records {
+        by_entity.entry(r.entity.clone()).or_default()
+            .entry(r.uid.clone()).or_default()
+            .push(r);
+    }
+
+    let mut n = 0;
+
+    for spec in ENTITIES {
+        let Some(rows) = by_entity.remove(spec.entity) else {
+            continue;
+        };
+        let mut stored = store::load_stored(conns.appdata, spec.entity)?;
+
+        for (uid, incoming) in rows {
+            let current = stored.remove(&uid).unwrap_or_default();
+            let winners = merge::winning_records(&current, incoming);
+            if winners.is_empty() {
+                continue;
+            }
+
+            let mut merged = current;
+            for r in &winners {
+                merged.insert(r.field.clone(), r.clone());
+            }
+
+            match apply_row(conns, spec, &uid, &merged) {
+                Ok(true) => {
+                    for r in &winners {
+                        w.store(conns.appdata, r)?;
+                    }
+                    n += 1;
+                }
+                Ok(false) => {}
+                Err(e) => warn(&format!("sync: can't apply {} {}: {}", spec.entity, uid, e)),
+            }
+        }
+    }
+
+    if let Some(incoming) = by_entity.remove(SETTINGS_ENTITY).and_then(|mut rows| rows.remove(SETTINGS_UID)) {
+        let stored = store::load_stored(conns.appdata, SETTINGS_ENTITY)?;
+        let current = stored.get(SETTINGS_UID).cloned().unwrap_or_default();
+        let winners = merge::winning_records(&current, incoming);
+        if !winners.is_empty() {
+            // Stored also when the values were the same already.
+            let changed = apply_settings(conns.appdata, &winners)?;
+            for r in &winners {
+                w.store(conns.appdata, r)?;
+            }
+            if changed {
+                w.settings_changed = true;
+                n += 1;
+            }
+        }
+    }
+
+    for entity in by_entity.keys() {
+        warn(&format!("sync: skipping records of unknown entity '{}'", entity));
+    }
+
+    Ok(n)
+}
+
+/// Write the merged state of one row. Returns false when there was nothing
+/// to write on this device.
+fn apply_row(conns: &mut SyncConns, spec: &EntitySpec, uid: &str, merged: &HashMap<String, SyncRecord>) -> QueryResult<bool> {
+    if merge::row_state(merged.values()) == RowState::Deleted {
+        if !spec.create_delete {
+            return Ok(false);
+        }
+        sql_query(format!("DELETE FROM {} WHERE {}", spec.table, spec.key_match("?1")))
+            .bind::<Text, _>(uid)
+            .execute(conns.get(spec.db))?;
+        if let RowKey::Mapped = spec.key {
+            store::unmap_uid(conns.appdata, spec.entity, uid)?;
+        }
+        return Ok(true);
+    }
+
+    // Only known column names reach the SQL.
+    let fields: Map<String, Value> = merged.values()
+        .filter(|r| spec.has_column(&r.field))
+        .map(|r| (r.field.clone(), r.value.clone()))
+        .collect();
+    if fields.is_empty() {
+        return Ok(false);
+    }
+
+    let set: Vec<String> = fields.keys()
+        .map(|c| format!("{} = {}", c, spec.value_expr(c)))
+        .collect();
+    let fields_json = Value::Object(fields).to_string();
+
+    let updated = sql_query(format!("UPDATE {} SET {} WHERE {}", spec.table, set.join(", "), spec.key_match("?2")))
+        .bind::<Text, _>(&fields_json)
+        .bind::<Text, _>(uid)
+        .execute(conns.get(spec.db))?;
+    if updated > 0 {
+        return Ok(true);
+    }
+    if !spec.create_delete {
+        return Ok(false);
+    }
+
+    let mut cols: Vec<String> = Vec::new();
+    let mut values: Vec<String> = Vec::new();
+    if let RowKey::Column(c) = spec.key {
+        cols.push(c.to_string());
+        values.push("?2".to_string());
+    }
+    for c in spec.all_columns() {
+        cols.push(c.to_string());
+        values.push(spec.value_expr(c));
+    }
+    for (c, v) in spec.insert_defaults {
+        cols.push(c.to_string());
+        values.push(v.to_string());
+    }
+
+    let insert = sql_query(format!("INSERT INTO {} ({}) VALUES ({})", spec.table, cols.join(", "), values.join(", ")))
+        .bind::<Text, _>(&fields_json);
+    // A mapped row has no uid column, ?2 is not in the statement.
+    match spec.key {
+        RowKey::Column(_) => insert.bind::<Text, _>(uid).execute(conns.get(spec.db))?,
+        RowKey::Mapped => insert.execute(conns.get(spec.db))?,
+    };
+
+    if let RowKey::Mapped = spec.key {
+        sql_query(format!(
+            "INSERT OR REPLACE INTO sync_ids (entity, local_id, uid, row_created_at) SELECT ?, id, ?, CAST(created_at AS TEXT) FROM {} WHERE id = last_insert_rowid()",
+            spec.table
+        ))
+            .bind::<Text, _>(spec.entity)
+            .bind::<Text, _>(uid)
+            .execute(conns.appdata)?;
+    }
+
+    Ok(true)
+}
+
+/// Overlay the winning settings fields on the stored settings. Fields this
+/// version doesn't know, or which stay on the device, are left out. Returns
+/// whether a value changed.
+fn apply_settings(db_conn: &mut SqliteConnection, winners: &[SyncRecord]) -> QueryResult<bool> {
+    let mut obj = read_settings(db_conn)?;
+    let mut changed = false;
+    for r in winners {
+        let known = !LOCAL_SETTINGS.contains(&r.field.as_str()) && obj.contains_key(&r.field);
+        if known && obj.get(&r.field) != Some(&r.value) {
+            obj.insert(r.field.clone(), r.value.clone());
+            changed = true;
+        }
+    }
+    if !changed {
+        return Ok(false);
+    }
+
+    let settings_json = match serde_json::from_value::<AppSettings>(Value::Object(obj)).map(|s| serde_json::to_string(&s)) {
+        Ok(Ok(json)) => json,
+        Ok(Err(e)) | Err(e) => {
+            warn(&format!("sync: can't apply the app settings: {}", e));
+            return Ok(false);
+        }
+    };
+
+    let updated = sql_query(r#"UPDATE app_settings SET value = ? WHERE "key" = 'app_settings'"#)
+        .bind::<Text, _>(&settings_json)
+        .execute(db_conn)?;
+    if updated == 0 {
+        sql_query(r#"INSERT INTO app_settings ("key", value) VALUES ('app_settings', ?)"#)
+            .bind::<Text, _>(&settings_json)
+            .execute(db_conn)?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_sql() {
+        let items = entity_spec("bookmark_item").unwrap();
+        let sql = items.select_sql();
+        assert!(sql.contains("'folder_id', (SELECT p.uid FROM sync_ids p WHERE p.entity = 'bookmark_folder' AND p.local_id = t.folder_id)"));
+        assert!(sql.contains("s.entity = 'bookmark_item' AND s.local_id = t.id"));
+        assert!(sql.ends_with("FROM bookmark_items t WHERE t.folder_id IN (SELECT id FROM bookmark_folders WHERE is_last_session = 0) ORDER BY t.id"));
+
+        let sections = entity_spec("chanting_section").unwrap();
+        assert!(sections.select_sql().starts_with("SELECT t.id AS local_id, t.uid AS uid, NULL AS row_created_at, json_object('chant_uid', t.chant_uid,"));
+    }
+
+    #[test]
+    fn test_write_exprs() {
+        let items = entity_spec("bookmark_item").unwrap();
+        assert_eq!(items.value_expr("title"), "json_extract(?1, '$.title')");
+        assert_eq!(
+            items.value_expr("folder_id"),
+            "(SELECT local_id FROM sync_ids WHERE entity = 'bookmark_folder' AND uid = json_extract(?1, '$.folder_id'))"
+        );
+        assert_eq!(items.key_match("?2"), "id = (SELECT local_id FROM sync_ids WHERE entity = 'bookmark_item' AND uid = ?2)");
+        assert!(items.has_column("folder_id"));
+        assert!(!items.has_column("id; DROP TABLE bookmark_items"));
+
+        let dict = entity_spec("dictionary").unwrap();
+        assert_eq!(dict.key_match("?1"), "label = ?1");
+        assert!(!dict.create_delete);
+    }
+
+    #[test]
+    fn test_local_settings_are_settings() {
+        let defaults = serde_json::to_value(AppSettings::default()).unwrap();
+        for key in LOCAL_SETTINGS {
+            assert!(defaults.get(key).is_some(), "not an AppSettings key: {}", key);
+        }
+    }
+}
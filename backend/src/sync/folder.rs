@@ -0,0 +1,338 @@
+//! Sync through a folder which another program keeps the same on every
+//! device, e.g. Syncthing or Nextcloud, for when the devices can't reach
+//! each other's API server.
+//!
+//! Every device writes only its own sub-folder, so the sync program never
+//! sees two devices editing the same file:
+//!
+//! ```text
+//! <folder>/<device_id>/device.json              FolderManifest
+//! <folder>/<device_id>/bookmark_folder.ndjson   one FolderRow per line
+//! <folder>/<device_id>/chanting_chant.ndjson
+//! ...
+//! ```
+//!
+//! A device's files are a snapshot of everything it has stored in
+//! `sync_fields`, its own changes and those it got from others, so a device
+//! which is never online at the same time as another still gets its data
+//! through a third one. Reading the other devices' snapshots merges them with
+//! the same rules as the LAN sync (see `merge`), and a snapshot is only read
+//! again after its device has written a new one.
+//!
+//! The snapshot is not built with the SQLite exporters of the import-me
+//! folder (`db::chanting_export::create_chanting_sqlite()`, the
+//! `export_user_*()` functions of `AppData`), for two reasons:
+//!
+//! - The merge needs the stamp of every field (its `modified_at` and
+//!   `device_id`) and the deleted rows, which `sync_fields` has and the
+//!   exported tables don't. Importing their rows would let the last device to
+//!   write overwrite whole rows, and bring deleted rows back.
+//! - The sync programs copy a file while it may still be written, and keep
+//!   conflict copies of the files they can't merge. A text file written to a
+//!   temporary name and renamed (`write_atomic()`) is whole when it arrives,
+//!   where an SQLite file copied during a write is corrupt.
+//!
+//! So the rows are the `SyncRecord`s of `/v1/sync/exchange`, grouped by row,
+//! one JSON object per line. The upgrade export (`export_for_upgrade()`) uses
+//! the same format next to the import-me SQLite files: those still carry the
+//! user data, the snapshot only adds the sync state.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::db::DbManager;
+use crate::logger::{error, info, warn};
+
+use super::entities::{apply_remote, record_local_changes, restore_change_log, ENTITIES, SETTINGS_ENTITY};
+use super::{now_ms, store, with_sync_conns, SyncRecord};
+
+/// Version of the folder format. Snapshots in a newer format are skipped.
+pub const FOLDER_FORMAT: u32 = 1;
+
+const MANIFEST_FILE: &str = "device.json";
+const ROWS_EXT: &str = "ndjson";
+/// The paired devices, only in the import-me snapshot.
+const PEERS_FILE: &str = "peers.json";
+
+/// `device.json`, written after the entity files, so that it describes a
+/// complete snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderManifest {
+    pub format: u32,
+    pub device_id: String,
+    pub device_name: String,
+    /// Unix time in milliseconds.
+    pub written_at: i64,
+    /// The device's sequence number the snapshot has the records up to.
+    pub seq: i64,
+}
+
+/// One synced row, a line of `<entity>.ndjson`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderRow {
+    pub uid: String,
+    /// Field name (or `_deleted`) -> value and stamp.
+    pub fields: BTreeMap<String, FolderField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderField {
+    pub value: Value,
+    /// Unix time in milliseconds on the device which wrote it.
+    pub modified_at: i64,
+    pub device_id: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FolderSyncReport {
+    /// Snapshots of other devices read, those unchanged since the last
+    /// sync are not counted.
+    pub devices_read: usize,
+    pub received: usize,
+    /// Rows changed on this device.
+    pub applied: usize,
+    /// Whether this device's snapshot was written.
+    pub written: bool,
+}
+
+/// A paired device in the import-me snapshot, with its secret.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedPeer {
+    device_id: String,
+    name: String,
+    url: Option<String>,
+    secret: String,
+    pulled_seq: i64,
+}
+
+/// Sync key of the last snapshot read from a device.
+fn read_marker_key(device_id: &str) -> String {
+    format!("folder_read:{}", device_id)
+}
+
+fn manifest_marker(m: &FolderManifest) -> String {
+    format!("{}:{}", m.written_at, m.seq)
+}
+
+fn read_manifest(device_dir: &Path) -> Result<FolderManifest> {
+    let path = device_dir.join(MANIFEST_FILE);
+    let text = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// The records of one device's snapshot. The entity is the file name.
+fn read_snapshot(device_dir: &Path) -> Result<Vec<SyncRecord>> {
+    let mut records = Vec::new();
+
+    for entry in fs::read_dir(device_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(ROWS_EXT) {
+            continue;
+        }
+        let Some(entity) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+
+        let text = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let row: FolderRow = serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: invalid row", path.display(), i + 1))?;
+            for (field, f) in row.fields {
+                records.push(SyncRecord {
+                    entity: entity.to_string(),
+                    uid: row.uid.clone(),
+                    field,
+                    value: f.value,
+                    modified_at: f.modified_at,
+                    device_id: f.device_id,
+                });
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+/// Write to a hidden file next to `path` and rename it, so the sync program
+/// doesn't pick up a half written file.
+fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.tmp", file_name));
+    fs::write(&tmp, contents).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to rename {} to {}", tmp.display(), path.display()))?;
+    Ok(())
+}
+
+/// Write every entity file, also the empty ones, then the manifest.
+fn write_snapshot(device_dir: &Path, manifest: &FolderManifest, records: Vec<SyncRecord>) -> Result<()> {
+    fs::create_dir_all(device_dir).with_context(|| format!("Failed to create {}", device_dir.display()))?;
+
+    let mut by_entity: HashMap<String, BTreeMap<String, FolderRow>> = HashMap::new();
+    for r in records {
+        let rows = by_entity.entry(r.entity).or_default();
+        let row = rows.entry(r.uid.clone()).or_insert_with(|| FolderRow { uid: r.uid, fields: BTreeMap::new() });
+        row.fields.insert(r.field, FolderField { value: r.value, modified_at: r.modified_at, device_id: r.device_id });
+    }
+
+    let entities = ENTITIES.iter().map(|s| s.entity).chain([SETTINGS_ENTITY]);
+    for entity in entities {
+        let mut text = String::new();
+        for row in by_entity.remove(entity).unwrap_or_default().into_values() {
+            text.push_str(&serde_json::to_string(&row)?);
+            text.push('\n');
+        }
+        write_atomic(&device_dir.join(format!("{}.{}", entity, ROWS_EXT)), &text)?;
+    }
+
+    write_atomic(&device_dir.join(MANIFEST_FILE), &serde_json::to_string_pretty(manifest)?)
+}
+
+/// The other devices' snapshots changed since they were last read, with
+/// their manifests. A snapshot which can't be read is logged and skipped,
+/// it is tried again on the next sync.
+fn changed_snapshots(dbm: &DbManager, folder: &Path, own_device_id: &str) -> Result<Vec<(FolderManifest, Vec<SyncRecord>)>> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(folder)
+        .with_context(|| format!("Failed to read {}", folder.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.join(MANIFEST_FILE).is_file())
+        .collect();
+    dirs.sort();
+
+    let mut res = Vec::new();
+    for dir in dirs {
+        let manifest = match read_manifest(&dir) {
+            Ok(m) => m,
+            Err(e) => {
+                error(&format!("sync folder: {:#}", e));
+                continue;
+            }
+        };
+        if manifest.device_id == own_device_id {
+            continue;
+        }
+        if manifest.format > FOLDER_FORMAT {
+            warn(&format!("sync folder: {} is written in a newer format ({}), update the app to read it", dir.display(), manifest.format));
+            continue;
+        }
+
+        let key = read_marker_key(&manifest.device_id);
+        let last_read = dbm.appdata.do_read(|db_conn| store::get_state(db_conn, &key))?;
+        if last_read.as_deref() == Some(manifest_marker(&manifest).as_str()) {
+            continue;
+        }
+
+        match read_snapshot(&dir) {
+            Ok(records) => res.push((manifest, records)),
+            Err(e) => error(&format!("sync folder: {:#}", e)),
+        }
+    }
+
+    Ok(res)
+}
+
+/// Merge the other devices' snapshots in `folder` and write this device's.
+/// The folder has to exist, so that an unmounted drive isn't mistaken for
+/// an empty sync folder.
+pub fn sync_folder(dbm: &DbManager, folder: &Path) -> Result<FolderSyncReport> {
+    if !folder.is_dir() {
+        bail!("The sync folder {} does not exist", folder.display());
+    }
+
+    let (own_device_id, device_name) = super::device_identity(dbm)?;
+    let snapshots = changed_snapshots(dbm, folder, &own_device_id)?;
+    let devices_read = snapshots.len();
+    let received: usize = snapshots.iter().map(|(_, r)| r.len()).sum();
+
+    let (applied, seq, records) = with_sync_conns(dbm, |conns, w| {
+        record_local_changes(conns, w)?;
+        let mut applied = 0;
+        for (manifest, records) in snapshots {
+            applied += apply_remote(conns, w, records)?;
+            store::set_state(conns.appdata, &read_marker_key(&manifest.device_id), &manifest_marker(&manifest))?;
+        }
+        let records = store::records_since(conns.appdata, 0, "")?;
+        Ok((applied, w.seq, records))
+    })?;
+
+    // Nothing new since this device's last snapshot.
+    let device_dir = folder.join(&own_device_id);
+    let written = match read_manifest(&device_dir) {
+        Ok(m) if m.seq == seq && m.format == FOLDER_FORMAT => false,
+        _ => {
+            let manifest = FolderManifest {
+                format: FOLDER_FORMAT,
+                device_id: own_device_id,
+                device_name,
+                written_at: now_ms(),
+                seq,
+            };
+            write_snapshot(&device_dir, &manifest, records)?;
+            true
+        }
+    };
+
+    info(&format!("sync folder: read {} devices, received {}, applied {}, written: {}", devices_read, received, applied, written));
+    Ok(FolderSyncReport { devices_read, received, applied, written })
+}
+
+/// Save this device's sync state to `dir` in the import-me folder before a
+/// database upgrade: its identity, change log and paired devices, so that
+/// the other devices still know it afterwards.
+pub fn export_for_upgrade(dbm: &DbManager, dir: &Path) -> Result<()> {
+    let (manifest, records, peers) = with_sync_conns(dbm, |conns, w| {
+        record_local_changes(conns, w)?;
+        let (device_id, device_name) = store::device_identity(conns.appdata)?;
+        let records = store::records_since(conns.appdata, 0, "")?;
+        let peers = store::load_peers(conns.appdata)?;
+        let manifest = FolderManifest { format: FOLDER_FORMAT, device_id, device_name, written_at: now_ms(), seq: w.seq };
+        Ok((manifest, records, peers))
+    })?;
+
+    let peers: Vec<SavedPeer> = peers.into_iter()
+        .map(|p| SavedPeer { device_id: p.device_id, name: p.name, url: p.url, secret: p.secret, pulled_seq: p.pulled_seq })
+        .collect();
+
+    write_snapshot(dir, &manifest, records)?;
+    write_atomic(&dir.join(PEERS_FILE), &serde_json::to_string_pretty(&peers)?)?;
+    info(&format!("sync: saved the sync state to {}", dir.display()));
+    Ok(())
+}
+
+/// Restore the sync state saved by `export_for_upgrade`, after the user
+/// data was imported into the new database.
+pub fn import_after_upgrade(dbm: &DbManager, dir: &Path) -> Result<()> {
+    if !dir.join(MANIFEST_FILE).is_file() {
+        return Ok(());
+    }
+    let manifest = read_manifest(dir)?;
+    let records = read_snapshot(dir)?;
+    let peers: Vec<SavedPeer> = match fs::read_to_string(dir.join(PEERS_FILE)) {
+        Ok(text) => serde_json::from_str(&text).context("Failed to parse the saved peers")?,
+        Err(_) => Vec::new(),
+    };
+
+    let restored = with_sync_conns(dbm, |conns, w| {
+        store::set_state(conns.appdata, "device_id", &manifest.device_id)?;
+        store::set_state(conns.appdata, "device_name", &manifest.device_name)?;
+        w.device_id = manifest.device_id.clone();
+
+        // The local sequence starts again, so every record is sent again.
+        for p in &peers {
+            store::upsert_peer(conns.appdata, &p.device_id, &p.name, p.url.as_deref(), &p.secret)?;
+            store::update_peer_cursors(conns.appdata, &p.device_id, p.pulled_seq, 0)?;
+        }
+
+        Ok(restore_change_log(conns, w, records)?)
+    })?;
+
+    info(&format!("sync: restored the sync state of {}, re-created {} rows", manifest.device_id, restored));
+    Ok(())
+}
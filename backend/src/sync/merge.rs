@@ -0,0 +1,171 @@
+//! The conflict resolution policy, without database access.
+//!
+//! Every synced value is a `SyncRecord` of one field of one row. Two devices
+//! may both change a row between syncs: each field keeps the value with the
+//! later stamp (last writer wins per field), so a rename on the phone and a
+//! re-order on the desktop both survive. A deleted row is the `_deleted`
+//! field set to `true` (a tombstone); it wins over the other fields unless
+//! one of them was written after it, in which case the row comes back.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::SyncRecord;
+
+/// The tombstone field of a deleted row.
+pub const DELETED_FIELD: &str = "_deleted";
+
+/// Whether `incoming` replaces `current`. The later `modified_at` wins; on
+/// the same millisecond the larger device id does, so that every device
+/// resolves the tie the same way.
+pub fn wins(incoming: &SyncRecord, current: &SyncRecord) -> bool {
+    (incoming.modified_at, &incoming.device_id) > (current.modified_at, &current.device_id)
+}
+
+/// The stamp for a local change: the clock, but always after the field's
+/// previous stamp, so a local edit is never older than what it replaces
+/// when the clocks of two devices disagree a little.
+pub fn next_stamp(now_ms: i64, previous: Option<i64>) -> i64 {
+    match previous {
+        Some(prev) if prev >= now_ms => prev + 1,
+        _ => now_ms,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowState {
+    Live,
+    Deleted,
+}
+
+/// A row is deleted when its tombstone is set and no other field was
+/// written after it.
+pub fn row_state<'a>(fields: impl IntoIterator<Item = &'a SyncRecord>) -> RowState {
+    let mut tombstone: Option<&SyncRecord> = None;
+    let mut newest_field: Option<&SyncRecord> = None;
+
+    for r in fields {
+        if r.field == DELETED_FIELD {
+            tombstone = Some(r);
+        } else if newest_field.is_none_or(|n| wins(r, n)) {
+            newest_field = Some(r);
+        }
+    }
+
+    match tombstone {
+        Some(t) if t.value == Value::Bool(true) => match newest_field {
+            Some(f) if wins(f, t) => RowState::Live,
+            _ => RowState::Deleted,
+        },
+        _ => RowState::Live,
+    }
+}
+
+/// The incoming records which win over the stored fields of one row (keyed
+/// by field name), at most one per field.
+pub fn winning_records(stored: &HashMap<String, SyncRecord>, incoming: Vec<SyncRecord>) -> Vec<SyncRecord> {
+    let mut best: HashMap<String, SyncRecord> = HashMap::new();
+
+    for r in incoming {
+        let beats_stored = stored.get(&r.field).is_none_or(|s| wins(&r, s));
+        let beats_best = best.get(&r.field).is_none_or(|b| wins(&r, b));
+        if beats_stored && beats_best {
+            best.insert(r.field.clone(), r);
+        }
+    }
+
+    let mut res: Vec<SyncRecord> = best.into_values().collect();
+    res.sort_by(|a, b| a.field.cmp(&b.field));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rec(field: &str, value: Value, modified_at: i64, device_id: &str) -> SyncRecord {
+        SyncRecord {
+            entity: "bookmark_folder".to_string(),
+            uid: "f1".to_string(),
+            field: field.to_string(),
+            value,
+            modified_at,
+            device_id: device_id.to_string(),
+        }
+    }
+
+    fn stored(records: &[SyncRecord]) -> HashMap<String, SyncRecord> {
+        records.iter().map(|r| (r.field.clone(), r.clone())).collect()
+    }
+
+    #[test]
+    fn test_last_writer_wins_per_field() {
+        let current = stored(&[
+            rec("name", json!("Reading"), 100, "desktop"),
+            rec("sort_order", json!(0), 100, "desktop"),
+        ]);
+
+        // The phone renamed the folder later, but re-ordered it earlier.
+        let incoming = vec![
+            rec("name", json!("To read"), 200, "phone"),
+            rec("sort_order", json!(3), 50, "phone"),
+        ];
+
+        let res = winning_records(&current, incoming);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].field, "name");
+        assert_eq!(res[0].value, json!("To read"));
+    }
+
+    #[test]
+    fn test_same_stamp_tie_break() {
+        let a = rec("name", json!("A"), 100, "aaaa");
+        let b = rec("name", json!("B"), 100, "bbbb");
+        assert!(wins(&b, &a));
+        assert!(!wins(&a, &b));
+        // Re-sending the stored record is not a change.
+        assert!(!wins(&a, &a));
+    }
+
+    #[test]
+    fn test_newest_incoming_per_field() {
+        let incoming = vec![
+            rec("name", json!("First"), 100, "phone"),
+            rec("name", json!("Third"), 300, "phone"),
+            rec("name", json!("Second"), 200, "phone"),
+        ];
+        let res = winning_records(&HashMap::new(), incoming);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].value, json!("Third"));
+    }
+
+    #[test]
+    fn test_tombstones() {
+        let name = rec("name", json!("Reading"), 100, "desktop");
+        let deleted = rec(DELETED_FIELD, json!(true), 200, "phone");
+        assert_eq!(row_state([&name]), RowState::Live);
+        assert_eq!(row_state([&name, &deleted]), RowState::Deleted);
+
+        // Renamed on another device after the delete: the row comes back.
+        let renamed = rec("name", json!("Renamed"), 300, "desktop");
+        assert_eq!(row_state([&renamed, &deleted]), RowState::Live);
+
+        // Re-created locally.
+        let undeleted = rec(DELETED_FIELD, json!(false), 400, "phone");
+        assert_eq!(row_state([&name, &undeleted]), RowState::Live);
+
+        // An edit at the same stamp as the delete loses to it.
+        let same = rec("name", json!("Same"), 200, "phone");
+        assert_eq!(row_state([&same, &deleted]), RowState::Deleted);
+    }
+
+    #[test]
+    fn test_next_stamp() {
+        assert_eq!(next_stamp(1000, None), 1000);
+        assert_eq!(next_stamp(1000, Some(500)), 1000);
+        assert_eq!(next_stamp(1000, Some(1000)), 1001);
+        assert_eq!(next_stamp(1000, Some(5000)), 5001);
+    }
+}
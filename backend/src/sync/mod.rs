@@ -0,0 +1,263 @@
+//! Peer-to-peer sync of the user data between devices on the LAN, e.g. the
+//! desktop and the phone.
+//!
+//! Synced: bookmark folders and items (not the last session), gloss and
+//! prompts history, the user's chanting collections, chants and sections,
+//! the label / volume / markers of recordings which both devices have, the
+//! metadata of dictionaries imported on both, and the app settings other
+//! than the device's own (see `entities::LOCAL_SETTINGS`). Audio files and
+//! dictionary words are not sent.
+//!
+//! - Change log: `sync_fields` holds the last known value of every synced
+//!   field with its stamp (unix ms and device id) and a local sequence
+//!   number. Before every sync the tables are compared with it and the
+//!   differences recorded as this device's changes, so the app's own write
+//!   paths don't have to know about sync.
+//! - Conflicts: last writer wins per field, deletes are tombstones, see
+//!   `merge`.
+//! - Pairing: the serving device shows a one-time code
+//!   (`create_pairing_code`), the other device sends it with its id to
+//!   `POST /v1/sync/pair` and gets back a secret for this pair of devices.
+//! - Sync: `POST /v1/sync/exchange` with that secret sends the changes since
+//!   the last push and gets back the server's changes since the last pull.
+//!   Each side keeps its cursors, so a sync after a failed one resends
+//!   rather than loses changes.
+//! - Folder sync: without a connection between the devices, the same
+//!   records are exchanged as files in a folder shared by e.g. Syncthing,
+//!   see `folder`.
+
+pub mod merge;
+pub mod store;
+pub mod entities;
+pub mod client;
+pub mod folder;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use diesel::{Connection, SqliteConnection};
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbManager;
+use crate::helpers::random_hex;
+use crate::logger::{error, info};
+
+use entities::{apply_remote, record_local_changes, SyncConns, Writer};
+
+pub use client::{pair_with, sync_with, HttpTransport, SyncTransport};
+pub use folder::{sync_folder, FolderSyncReport};
+
+/// How long a pairing code can be used.
+pub const PAIRING_CODE_TTL_MS: i64 = 10 * 60 * 1000;
+
+/// The value of one field of one synced row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    /// e.g. `bookmark_folder`, see `entities::ENTITIES`
+    pub entity: String,
+    /// The row's uid, the same on every device.
+    pub uid: String,
+    /// A column name, or `_deleted` for the tombstone.
+    pub field: String,
+    pub value: serde_json::Value,
+    /// Unix time in milliseconds on the device which wrote it.
+    pub modified_at: i64,
+    pub device_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPeer {
+    pub id: i32,
+    pub device_id: String,
+    pub name: String,
+    /// The peer's API server, on the device which starts the syncs.
+    pub url: Option<String>,
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    /// The peer's sequence number this device has received up to.
+    pub pulled_seq: i64,
+    /// This device's sequence number the peer has been sent up to.
+    pub pushed_seq: i64,
+    pub last_synced_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairingCode {
+    pub code: String,
+    /// Unix time in milliseconds.
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairRequest {
+    pub code: String,
+    pub device_id: String,
+    pub device_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairResponse {
+    pub device_id: String,
+    pub device_name: String,
+    /// Sent as `Authorization: Bearer <secret>` to `/v1/sync/exchange`.
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRequest {
+    pub device_id: String,
+    /// The server's sequence number the client has received up to.
+    pub since: i64,
+    pub records: Vec<SyncRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub device_id: String,
+    /// The `since` of the next request.
+    pub cursor: i64,
+    pub records: Vec<SyncRecord>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub sent: usize,
+    pub received: usize,
+    /// Rows changed on this device.
+    pub applied: usize,
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Run `f` in an appdata transaction with both write locks held, then
+/// reload the in-memory settings of the app if a sync changed them.
+fn with_sync_conns<T>(dbm: &DbManager, f: impl FnOnce(&mut SyncConns, &mut Writer) -> Result<T>) -> Result<T> {
+    let (res, settings_changed) = {
+        let _appdata_lock = dbm.appdata.write_lock.lock();
+        let _dict_lock = dbm.dictionaries.write_lock.lock();
+        let mut appdata_conn = dbm.appdata.get_conn().context("Failed to get appdata connection")?;
+        let mut dict_conn = dbm.dictionaries.get_conn().context("Failed to get dictionaries connection")?;
+        let appdata_conn: &mut SqliteConnection = &mut appdata_conn;
+        let dict_conn: &mut SqliteConnection = &mut dict_conn;
+
+        appdata_conn.transaction::<_, anyhow::Error, _>(|appdata| {
+            let (device_id, _) = store::device_identity(appdata)?;
+            let seq = store::max_seq(appdata)?;
+            let mut w = Writer { device_id, now_ms: now_ms(), seq, settings_changed: false };
+            let mut conns = SyncConns { appdata, dictionaries: dict_conn };
+            let res = f(&mut conns, &mut w)?;
+            Ok((res, w.settings_changed))
+        })?
+    };
+
+    if let Some(app_data) = crate::try_get_app_data().filter(|_| settings_changed) {
+        let settings = app_data.dbm.appdata.get_app_settings();
+        *app_data.app_settings_cache.write().expect("Failed to write app settings") = settings;
+    }
+
+    Ok(res)
+}
+
+/// This device's id and name.
+pub fn device_identity(dbm: &DbManager) -> Result<(String, String)> {
+    dbm.appdata.do_write(store::device_identity)
+}
+
+pub fn set_device_name(dbm: &DbManager, name: &str) -> Result<()> {
+    dbm.appdata.do_write(|db_conn| store::set_state(db_conn, "device_name", name.trim()))?;
+    Ok(())
+}
+
+/// Record the changes made since the last sync. Syncs do this themselves,
+/// calling it e.g. on shutdown only makes the stamps closer to the edits.
+pub fn record_changes(dbm: &DbManager) -> Result<usize> {
+    with_sync_conns(dbm, |conns, w| Ok(record_local_changes(conns, w)?))
+}
+
+/// A new six digit code for a device to pair with this one.
+pub fn create_pairing_code(dbm: &DbManager) -> Result<PairingCode> {
+    let n = u64::from_str_radix(&random_hex(1), 16).context("random_hex")?;
+    let code = PairingCode {
+        code: format!("{:06}", n % 1_000_000),
+        expires_at: now_ms() + PAIRING_CODE_TTL_MS,
+    };
+    dbm.appdata.do_write(|db_conn| store::insert_pairing_code(db_conn, &code.code, code.expires_at))?;
+    info("sync: created a pairing code");
+    Ok(code)
+}
+
+/// Pair with the device in `req` if its code is valid. A wrong code also
+/// cancels the other codes, so they can't be guessed one after the other.
+pub fn accept_pairing(dbm: &DbManager, req: &PairRequest) -> Result<Option<PairResponse>> {
+    let res = dbm.appdata.do_write(|db_conn| {
+        if !store::take_pairing_code(db_conn, req.code.trim(), now_ms())? {
+            store::clear_pairing_codes(db_conn)?;
+            return Ok(None);
+        }
+
+        let (device_id, device_name) = store::device_identity(db_conn)?;
+        let secret = random_hex(4);
+        store::upsert_peer(db_conn, &req.device_id, &req.device_name, None, &secret)?;
+        Ok(Some(PairResponse { device_id, device_name, secret }))
+    })?;
+
+    match &res {
+        Some(_) => info(&format!("sync: paired with {} ({})", req.device_name, req.device_id)),
+        None => info("sync: rejected a wrong or expired pairing code"),
+    }
+    Ok(res)
+}
+
+/// Serve a sync request from a paired device.
+pub fn handle_sync_request(dbm: &DbManager, peer: &SyncPeer, req: SyncRequest) -> Result<SyncResponse> {
+    let received = req.records.len();
+
+    let resp = with_sync_conns(dbm, |conns, w| {
+        record_local_changes(conns, w)?;
+        let applied = apply_remote(conns, w, req.records)?;
+        let records = store::records_since(conns.appdata, req.since, &peer.device_id)?;
+        store::touch_peer(conns.appdata, &peer.device_id)?;
+        info(&format!("sync: {} sent {}, applied {}, sending {}", peer.name, received, applied, records.len()));
+        Ok(SyncResponse { device_id: w.device_id.clone(), cursor: w.seq, records })
+    })?;
+
+    Ok(resp)
+}
+
+pub fn list_peers(dbm: &DbManager) -> Vec<SyncPeer> {
+    match dbm.appdata.do_read(store::load_peers) {
+        Ok(peers) => peers,
+        Err(e) => {
+            error(&format!("list_peers(): {}", e));
+            Vec::new()
+        }
+    }
+}
+
+pub fn find_peer(dbm: &DbManager, device_id: &str) -> Option<SyncPeer> {
+    list_peers(dbm).into_iter().find(|p| p.device_id == device_id)
+}
+
+/// The URL of this device's LAN sync listener on `port`, with the address of
+/// the network interface of the default route. `None` without a network.
+pub fn lan_url(port: u16) -> Option<String> {
+    // Connecting a UDP socket sends nothing, it only selects the interface.
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:9").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    if ip.is_loopback() || ip.is_unspecified() {
+        return None;
+    }
+    Some(format!("http://{}:{}", ip, port))
+}
+
+/// Forget a paired device. Returns false if there was none.
+pub fn remove_peer(dbm: &DbManager, device_id: &str) -> Result<bool> {
+    let n = dbm.appdata.do_write(|db_conn| store::delete_peer(db_conn, device_id))?;
+    Ok(n > 0)
+}
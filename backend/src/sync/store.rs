@@ -0,0 +1,284 @@
+//! The sync tables of the appdata database: the per-field change log
+//! (`sync_fields`), the uids of rows which only have a local id
+//! (`sync_ids`), the paired devices and this device's identity.
+
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text, Timestamp};
+use serde_json::Value;
+
+use crate::helpers::random_hex;
+
+use super::{SyncPeer, SyncRecord};
+
+/// Stored fields of one entity: uid -> field -> record.
+pub type StoredRows = HashMap<String, HashMap<String, SyncRecord>>;
+
+#[derive(QueryableByName)]
+struct FieldRow {
+    #[diesel(sql_type = Text)]
+    entity: String,
+    #[diesel(sql_type = Text)]
+    uid: String,
+    #[diesel(sql_type = Text)]
+    field: String,
+    #[diesel(sql_type = Text)]
+    value_json: String,
+    #[diesel(sql_type = BigInt)]
+    modified_at: i64,
+    #[diesel(sql_type = Text)]
+    device_id: String,
+}
+
+impl FieldRow {
+    fn into_record(self) -> SyncRecord {
+        SyncRecord {
+            entity: self.entity,
+            uid: self.uid,
+            field: self.field,
+            // Only ever written from a serde_json::Value.
+            value: serde_json::from_str(&self.value_json).unwrap_or(Value::Null),
+            modified_at: self.modified_at,
+            device_id: self.device_id,
+        }
+    }
+}
+
+pub fn load_stored(db_conn: &mut SqliteConnection, entity: &str) -> QueryResult<StoredRows> {
+    let rows: Vec<FieldRow> = sql_query(
+        "SELECT entity, uid, field, value_json, modified_at, device_id FROM sync_fields WHERE entity = ?"
+    )
+        .bind::<Text, _>(entity)
+        .load(db_conn)?;
+
+    let mut stored = StoredRows::new();
+    for row in rows {
+        let r = row.into_record();
+        stored.entry(r.uid.clone()).or_default().insert(r.field.clone(), r);
+    }
+    Ok(stored)
+}
+
+/// Insert or replace the stored value of the record's field.
+pub fn write_record(db_conn: &mut SqliteConnection, r: &SyncRecord, seq: i64) -> QueryResult<usize> {
+    sql_query(
+        r#"
+        INSERT INTO sync_fields (entity, uid, field, value_json, modified_at, device_id, seq)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (entity, uid, field) DO UPDATE SET
+            value_json = excluded.value_json,
+            modified_at = excluded.modified_at,
+            device_id = excluded.device_id,
+            seq = excluded.seq
+        "#
+    )
+        .bind::<Text, _>(&r.entity)
+        .bind::<Text, _>(&r.uid)
+        .bind::<Text, _>(&r.field)
+        .bind::<Text, _>(r.value.to_string())
+        .bind::<BigInt, _>(r.modified_at)
+        .bind::<Text, _>(&r.device_id)
+        .bind::<BigInt, _>(seq)
+        .execute(db_conn)
+}
+
+#[derive(QueryableByName)]
+struct SeqRow {
+    #[diesel(sql_type = BigInt)]
+    seq: i64,
+}
+
+/// The newest sequence number, which is the cursor a peer is given.
+pub fn max_seq(db_conn: &mut SqliteConnection) -> QueryResult<i64> {
+    sql_query("SELECT COALESCE(MAX(seq), 0) AS seq FROM sync_fields")
+        .get_result::<SeqRow>(db_conn)
+        .map(|r| r.seq)
+}
+
+/// Records stored after `since`, leaving out those written by
+/// `exclude_device`, which already has them or newer ones.
+pub fn records_since(db_conn: &mut SqliteConnection, since: i64, exclude_device: &str) -> QueryResult<Vec<SyncRecord>> {
+    let rows: Vec<FieldRow> = sql_query(
+        "SELECT entity, uid, field, value_json, modified_at, device_id FROM sync_fields WHERE seq > ? AND device_id != ? ORDER BY seq"
+    )
+        .bind::<BigInt, _>(since)
+        .bind::<Text, _>(exclude_device)
+        .load(db_conn)?;
+
+    Ok(rows.into_iter().map(FieldRow::into_record).collect())
+}
+
+/// Give a locally created row its sync uid.
+pub fn map_local_id(db_conn: &mut SqliteConnection, entity: &str, local_id: i32, uid: &str, row_created_at: Option<&str>) -> QueryResult<usize> {
+    sql_query("INSERT OR REPLACE INTO sync_ids (entity, local_id, uid, row_created_at) VALUES (?, ?, ?, ?)")
+        .bind::<Text, _>(entity)
+        .bind::<Integer, _>(local_id)
+        .bind::<Text, _>(uid)
+        .bind::<Nullable<Text>, _>(row_created_at)
+        .execute(db_conn)
+}
+
+pub fn unmap_uid(db_conn: &mut SqliteConnection, entity: &str, uid: &str) -> QueryResult<usize> {
+    sql_query("DELETE FROM sync_ids WHERE entity = ? AND uid = ?")
+        .bind::<Text, _>(entity)
+        .bind::<Text, _>(uid)
+        .execute(db_conn)
+}
+
+// --- This device ---
+
+#[derive(QueryableByName)]
+struct StateRow {
+    #[diesel(sql_type = Text)]
+    value: String,
+}
+
+pub fn get_state(db_conn: &mut SqliteConnection, key: &str) -> QueryResult<Option<String>> {
+    sql_query(r#"SELECT value FROM sync_state WHERE "key" = ?"#)
+        .bind::<Text, _>(key)
+        .get_result::<StateRow>(db_conn)
+        .optional()
+        .map(|r| r.map(|r| r.value))
+}
+
+pub fn set_state(db_conn: &mut SqliteConnection, key: &str, value: &str) -> QueryResult<usize> {
+    sql_query(r#"INSERT OR REPLACE INTO sync_state ("key", value) VALUES (?, ?)"#)
+        .bind::<Text, _>(key)
+        .bind::<Text, _>(value)
+        .execute(db_conn)
+}
+
+/// This device's id and name, created on first use. The name defaults to
+/// `SIMSAPA_DEVICE_NAME` or the host name.
+pub fn device_identity(db_conn: &mut SqliteConnection) -> QueryResult<(String, String)> {
+    let device_id = match get_state(db_conn, "device_id")? {
+        Some(id) => id,
+        None => {
+            let id = random_hex(2);
+            set_state(db_conn, "device_id", &id)?;
+            id
+        }
+    };
+
+    let device_name = match get_state(db_conn, "device_name")? {
+        Some(name) => name,
+        None => ["SIMSAPA_DEVICE_NAME", "HOSTNAME", "COMPUTERNAME"].iter()
+            .find_map(|v| std::env::var(v).ok().filter(|s| !s.trim().is_empty()))
+            .unwrap_or_else(|| "Simsapa".to_string()),
+    };
+
+    Ok((device_id, device_name))
+}
+
+// --- Peers ---
+
+#[derive(QueryableByName)]
+struct PeerRow {
+    #[diesel(sql_type = Integer)]
+    id: i32,
+    #[diesel(sql_type = Text)]
+    device_id: String,
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    url: Option<String>,
+    #[diesel(sql_type = Text)]
+    secret: String,
+    #[diesel(sql_type = BigInt)]
+    pulled_seq: i64,
+    #[diesel(sql_type = BigInt)]
+    pushed_seq: i64,
+    #[diesel(sql_type = Nullable<Timestamp>)]
+    last_synced_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<PeerRow> for SyncPeer {
+    fn from(r: PeerRow) -> Self {
+        SyncPeer {
+            id: r.id,
+            device_id: r.device_id,
+            name: r.name,
+            url: r.url,
+            secret: r.secret,
+            pulled_seq: r.pulled_seq,
+            pushed_seq: r.pushed_seq,
+            last_synced_at: r.last_synced_at,
+        }
+    }
+}
+
+pub fn load_peers(db_conn: &mut SqliteConnection) -> QueryResult<Vec<SyncPeer>> {
+    let rows: Vec<PeerRow> = sql_query(
+        "SELECT id, device_id, name, url, secret, pulled_seq, pushed_seq, last_synced_at FROM sync_peers ORDER BY id"
+    )
+        .load(db_conn)?;
+    Ok(rows.into_iter().map(SyncPeer::from).collect())
+}
+
+/// Insert the peer, or replace its name, url and secret when it was paired
+/// before. The cursors start over, the next sync sends everything.
+pub fn upsert_peer(db_conn: &mut SqliteConnection, device_id: &str, name: &str, url: Option<&str>, secret: &str) -> QueryResult<usize> {
+    sql_query(
+        r#"
+        INSERT INTO sync_peers (device_id, name, url, secret) VALUES (?, ?, ?, ?)
+        ON CONFLICT (device_id) DO UPDATE SET
+            name = excluded.name,
+            url = excluded.url,
+            secret = excluded.secret,
+            pulled_seq = 0,
+            pushed_seq = 0
+        "#
+    )
+        .bind::<Text, _>(device_id)
+        .bind::<Text, _>(name)
+        .bind::<Nullable<Text>, _>(url)
+        .bind::<Text, _>(secret)
+        .execute(db_conn)
+}
+
+pub fn update_peer_cursors(db_conn: &mut SqliteConnection, device_id: &str, pulled_seq: i64, pushed_seq: i64) -> QueryResult<usize> {
+    sql_query("UPDATE sync_peers SET pulled_seq = ?, pushed_seq = ?, last_synced_at = CURRENT_TIMESTAMP WHERE device_id = ?")
+        .bind::<BigInt, _>(pulled_seq)
+        .bind::<BigInt, _>(pushed_seq)
+        .bind::<Text, _>(device_id)
+        .execute(db_conn)
+}
+
+pub fn touch_peer(db_conn: &mut SqliteConnection, device_id: &str) -> QueryResult<usize> {
+    sql_query("UPDATE sync_peers SET last_synced_at = CURRENT_TIMESTAMP WHERE device_id = ?")
+        .bind::<Text, _>(device_id)
+        .execute(db_conn)
+}
+
+pub fn delete_peer(db_conn: &mut SqliteConnection, device_id: &str) -> QueryResult<usize> {
+    sql_query("DELETE FROM sync_peers WHERE device_id = ?")
+        .bind::<Text, _>(device_id)
+        .execute(db_conn)
+}
+
+// --- Pairing codes ---
+
+pub fn insert_pairing_code(db_conn: &mut SqliteConnection, code: &str, expires_at: i64) -> QueryResult<usize> {
+    sql_query("INSERT OR REPLACE INTO sync_pairing_codes (code, expires_at) VALUES (?, ?)")
+        .bind::<Text, _>(code)
+        .bind::<BigInt, _>(expires_at)
+        .execute(db_conn)
+}
+
+/// Use up a pairing code. Returns whether it was valid.
+pub fn take_pairing_code(db_conn: &mut SqliteConnection, code: &str, now_ms: i64) -> QueryResult<bool> {
+    sql_query("DELETE FROM sync_pairing_codes WHERE expires_at <= ?")
+        .bind::<BigInt, _>(now_ms)
+        .execute(db_conn)?;
+
+    let n = sql_query("DELETE FROM sync_pairing_codes WHERE code = ?")
+        .bind::<Text, _>(code)
+        .execute(db_conn)?;
+    Ok(n > 0)
+}
+
+pub fn clear_pairing_codes(db_conn: &mut SqliteConnection) -> QueryResult<usize> {
+    sql_query("DELETE FROM sync_pairing_codes").execute(db_conn)
+}
@@ -9,6 +9,7 @@ use thiserror::Error;
 use crate::db::appdata_models::{Sutta, BookSpineItem};
 use crate::db::dictionaries_models::DictWord;
 use crate::db::dpd_models::{DpdHeadword, DpdRoot};
+use crate::did_you_mean::SpellingSuggestion;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QueryType {
@@ -329,7 +330,7 @@ impl SearchResult {
             author: None,
             lang: spine_item.language.clone(),
             snippet,
-            page_number: None,
+            page_number: crate::pdf_import::pdf_page_number(&spine_item.resource_path),
             score: None,
             rank: None,
             is_section_header: false,
@@ -345,6 +346,10 @@ pub struct SearchResultPage {
     pub page_len: usize,
     pub page_num: usize,
     pub results: Vec<SearchResult>,
+    /// "Did you mean" queries for the first page of a search without
+    /// results. See docs/did-you-mean-suggestions.md.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<SpellingSuggestion>,
 }
 
 /// Options for word processing in gloss operations
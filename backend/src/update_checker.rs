@@ -418,6 +418,23 @@ pub struct ReleaseEntry {
     /// Release description/notes
     #[serde(default)]
     pub description: Option<String>,
+    /// Delta updates to this release from earlier ones
+    #[serde(default)]
+    pub deltas: Vec<DeltaRelease>,
+}
+
+/// A delta update of the databases, see `asset_delta`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaRelease {
+    /// Database version the delta applies to (e.g., "0.1.0")
+    pub from_version: String,
+    /// Download URL of the delta zip
+    pub url: String,
+    /// Size in bytes
+    #[serde(default)]
+    pub size: u64,
+    /// SHA-256 checksum of the delta zip, in hex
+    pub sha256: String,
 }
 
 /// A section of releases (either application or assets).
@@ -453,6 +470,9 @@ pub struct UpdateInfo {
     /// Languages available in this release (for database updates)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub languages: Option<Vec<String>>,
+    /// Delta update from the current database version, if the release has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<DeltaRelease>,
 }
 
 /// Collect system information for the API request.
@@ -617,6 +637,7 @@ pub fn has_app_update(info: &ReleasesInfo, current_version: &str) -> Option<Upda
             current_version: current_version.to_string(),
             release_notes,
             languages: None,
+            delta: None,
         })
     } else {
         None
@@ -699,12 +720,19 @@ pub fn has_db_update(
             current_version: current_db_version.unwrap_or("none").to_string(),
             release_notes: latest_compatible.description.clone(),
             languages: Some(latest_compatible.suttas_lang.clone()),
+            delta: current_db_version.and_then(|v| find_db_delta(latest_compatible, v)).cloned(),
         })
     } else {
         None
     }
 }
 
+/// Find the delta update of a release from the current database version.
+pub fn find_db_delta<'a>(release: &'a ReleaseEntry, current_db_version: &str) -> Option<&'a DeltaRelease> {
+    let current = to_version(current_db_version).ok()?;
+    release.deltas.iter().find(|d| to_version(&d.from_version).is_ok_and(|v| v == current))
+}
+
 /// Check if the local database is obsolete compared to the app version.
 ///
 /// This is used to warn users when their database is incompatible with the app.
@@ -734,6 +762,7 @@ pub fn is_local_db_obsolete(app_version: &str, db_version: Option<&str>) -> Opti
             current_version: db_ver_str.to_string(),
             release_notes: None,
             languages: None,
+            delta: None,
         })
     } else {
         None
@@ -835,6 +864,7 @@ mod tests {
                         date: Some("2024-01-15".to_string()),
                         title: Some("Version 0.2.0".to_string()),
                         description: Some("New features and improvements".to_string()),
+                        deltas: vec![],
                     },
                     ReleaseEntry {
                         version_tag: "v0.1.0".to_string(),
@@ -843,6 +873,7 @@ mod tests {
                         date: Some("2024-01-01".to_string()),
                         title: Some("Version 0.1.0".to_string()),
                         description: Some("Initial release".to_string()),
+                        deltas: vec![],
                     },
                 ],
             },
@@ -855,6 +886,7 @@ mod tests {
                         date: Some("2024-01-15".to_string()),
                         title: Some("Assets 0.2.0".to_string()),
                         description: Some("Database updates".to_string()),
+                        deltas: vec![],
                     },
                     ReleaseEntry {
                         version_tag: "v0.1.5".to_string(),
@@ -863,6 +895,12 @@ mod tests {
                         date: Some("2024-01-10".to_string()),
                         title: Some("Assets 0.1.5".to_string()),
                         description: Some("Patch release".to_string()),
+                        deltas: vec![DeltaRelease {
+                            from_version: "0.1.0".to_string(),
+                            url: "https://example.com/0.1.0-to-0.1.5.zip".to_string(),
+                            size: 1000,
+                            sha256: "ab".repeat(32),
+                        }],
                     },
                     ReleaseEntry {
                         version_tag: "v0.1.0".to_string(),
@@ -871,6 +909,7 @@ mod tests {
                         date: Some("2024-01-01".to_string()),
                         title: Some("Assets 0.1.0".to_string()),
                         description: Some("Initial release".to_string()),
+                        deltas: vec![],
                     },
                 ],
             },
@@ -944,6 +983,20 @@ mod tests {
         assert!(update.languages.is_some());
     }
 
+    #[test]
+    fn test_has_db_update_with_delta() {
+        let info = create_mock_releases_info();
+
+        let update = has_db_update(&info, "0.1.0", Some("0.1.0")).unwrap();
+        assert_eq!(update.delta.unwrap().from_version, "0.1.0");
+
+        // No delta from other versions, nor without a database.
+        let update = has_db_update(&info, "0.1.0", Some("0.1.2")).unwrap();
+        assert!(update.delta.is_none());
+        let update = has_db_update(&info, "0.1.0", None).unwrap();
+        assert!(update.delta.is_none());
+    }
+
     #[test]
     fn test_has_db_update_none_when_current() {
         let info = create_mock_releases_info();
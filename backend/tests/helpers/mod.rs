@@ -82,6 +82,7 @@ pub fn handle_timing(test_name: &str, key: &str, dt: Duration) {
     );
 }
 
+#[allow(dead_code)]
 pub fn app_data_setup() {
     // unsafe { env::set_var("SIMSAPA_DIR", "../../assets-testing/"); }
     dotenv().ok();
@@ -223,3 +224,86 @@ pub fn create_test_task(query_text: &str, search_mode: SearchMode) -> SearchQuer
         SearchArea::Suttas,
     )
 }
+
+// ---------------------------------------------------------------------------
+// OpenAPI contract — shared by test_api_contract and test_api_v1.
+//
+// The `/v1` schemas are written by hand (`ApiSchema` impls), so the tests
+// validate the real request and response bodies against the document served
+// at `/v1/openapi.json`. Object schemas with `properties` are checked as
+// closed, so a field missing from a schema fails as well as a wrong type.
+// ---------------------------------------------------------------------------
+
+/// The `/v1` routes which need the content databases, checked in test_api_v1.
+/// The others are checked on empty databases in test_api_contract.
+#[allow(dead_code)]
+pub const OPENAPI_CONTENT_ROUTES: &[(&str, &str)] = &[
+    ("post", "/v1/search"),
+    ("get", "/v1/search/options"),
+    ("get", "/v1/suttas/{uid}"),
+    ("get", "/v1/words/{uid}"),
+    ("get", "/v1/health"),
+];
+
+/// The schema of a JSON body, with the components for its `$ref`s.
+fn openapi_body_schema(openapi: &serde_json::Value, content: &serde_json::Value) -> Option<serde_json::Value> {
+    let schema = content.get("content")?.get("application/json")?.get("schema")?;
+    let mut root = schema.clone();
+    root["components"] = openapi["components"].clone();
+    close_object_schemas(&mut root);
+    Some(root)
+}
+
+fn close_object_schemas(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::Object(map) => {
+            if map.contains_key("properties") && !map.contains_key("additionalProperties") {
+                map.insert("additionalProperties".to_string(), serde_json::Value::Bool(false));
+            }
+            map.values_mut().for_each(close_object_schemas);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(close_object_schemas),
+        _ => {}
+    }
+}
+
+fn assert_valid(schema: &serde_json::Value, body: &serde_json::Value, what: &str) {
+    let validator = jsonschema::draft202012::new(schema).unwrap_or_else(|e| panic!("{}: invalid schema: {}", what, e));
+    let errors: Vec<String> = validator.iter_errors(body)
+        .map(|e| format!("  {} at '{}'", e, e.instance_path))
+        .collect();
+    assert!(errors.is_empty(), "{} doesn't match the schema:\n{}\n{}", what, errors.join("\n"), body);
+}
+
+/// The body of a request to `method path` (an OpenAPI path template) is valid
+/// for the documented `requestBody`.
+#[allow(dead_code)]
+pub fn assert_request_matches_openapi(openapi: &serde_json::Value, method: &str, path: &str, body: &serde_json::Value) {
+    let op = &openapi["paths"][path][method];
+    assert!(op.is_object(), "{} {} is not in openapi.json", method, path);
+    let schema = op.get("requestBody")
+        .and_then(|request| openapi_body_schema(openapi, request))
+        .unwrap_or_else(|| panic!("{} {} documents no request body", method, path));
+    assert_valid(&schema, body, &format!("the request body of {} {}", method, path));
+}
+
+/// The response of `method path` is documented for its status, and its body
+/// (`None` for no body) is valid for the schema of that status. An error
+/// status which isn't listed falls back to the `default` response.
+#[allow(dead_code)]
+pub fn assert_response_matches_openapi(openapi: &serde_json::Value, method: &str, path: &str, status: u16, body: Option<&serde_json::Value>) {
+    let responses = &openapi["paths"][path][method]["responses"];
+    assert!(responses.is_object(), "{} {} is not in openapi.json", method, path);
+    let response = match responses.get(status.to_string()) {
+        Some(response) => response,
+        None if status >= 400 => &responses["default"],
+        None => panic!("{} {} returned {}, which is not documented", method, path, status),
+    };
+    let what = format!("the {} response of {} {}", status, method, path);
+    match (openapi_body_schema(openapi, response), body) {
+        (Some(schema), Some(body)) => assert_valid(&schema, body, &what),
+        (None, None) => {}
+        (Some(_), None) => panic!("{} has no body", what),
+        (None, Some(body)) => panic!("{} has an undocumented body: {}", what, body),
+    }
+}
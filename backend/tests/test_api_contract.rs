@@ -0,0 +1,204 @@
+// Contract tests of the /v1 API: the bodies of the user data and sync routes,
+// the error responses included, match the schemas served at /v1/openapi.json.
+// Runs on empty databases; test_api_v1.rs checks the content routes
+// (`OPENAPI_CONTENT_ROUTES`) against the test content databases.
+#![cfg(feature = "api-server")]
+
+mod helpers;
+use helpers as h;
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+use rocket::http::{ContentType, Header, Method, Status};
+use rocket::local::blocking::Client;
+use serde_json::{json, Value};
+
+use simsapa_backend::api_server::v1::openapi::ENDPOINTS;
+use simsapa_backend::api_server::{build_rocket, ApiServerConfig};
+use simsapa_backend::db::{DatabaseHandle, DbManager, APPDATA_MIGRATIONS, DICTIONARIES_MIGRATIONS};
+use simsapa_backend::sync;
+
+const TOKEN: &str = "test-token-0123456789";
+
+/// A device with its own empty databases, as in test_sync.rs.
+fn device(dir: &Path, name: &str) -> Arc<DbManager> {
+    let url = |db: &str| dir.join(format!("{}-{}.sqlite3", name, db)).to_string_lossy().to_string();
+
+    let mut db_conn = SqliteConnection::establish(&url("appdata")).expect("appdata");
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).expect("appdata migrations");
+    let mut db_conn = SqliteConnection::establish(&url("dictionaries")).expect("dictionaries");
+    db_conn.run_pending_migrations(DICTIONARIES_MIGRATIONS).expect("dictionaries migrations");
+
+    let dbm = DbManager {
+        appdata: DatabaseHandle::new(&url("appdata")).expect("appdata handle"),
+        dictionaries: DatabaseHandle::new(&url("dictionaries")).expect("dictionaries handle"),
+        dpd: DatabaseHandle::new(&url("dpd")).expect("dpd handle"),
+    };
+    sync::set_device_name(&dbm, name).expect("device name");
+    Arc::new(dbm)
+}
+
+/// A client which checks each call against the served OpenAPI document, and
+/// keeps the routes it called.
+struct Contract {
+    client: Client,
+    openapi: Value,
+    called: RefCell<BTreeSet<(String, String)>>,
+}
+
+impl Contract {
+    fn new(dbm: Arc<DbManager>) -> Self {
+        let config = ApiServerConfig { api_token: Some(TOKEN.to_string()), ..Default::default() };
+        let client = Client::tracked(build_rocket(config, dbm).expect("build rocket")).expect("valid rocket");
+        let openapi: Value = client.get("/v1/openapi.json").dispatch().into_json().expect("openapi json");
+        Contract { client, openapi, called: RefCell::new(BTreeSet::new()) }
+    }
+
+    /// Call `uri`, the route `path` of the OpenAPI document, with the API
+    /// token or a peer secret as `bearer`.
+    fn call(&self, method: Method, path: &str, uri: &str, body: Option<Value>, bearer: Option<&str>) -> (Status, Value) {
+        if let Some(body) = &body {
+            h::assert_request_matches_openapi(&self.openapi, &method.as_str().to_lowercase(), path, body);
+        }
+        self.send(method, path, uri, body, bearer)
+    }
+
+    fn token(&self, method: Method, path: &str, uri: &str, body: Option<Value>) -> (Status, Value) {
+        self.call(method, path, uri, body, Some(TOKEN))
+    }
+
+    /// Send a body which the schema doesn't allow, with the API token.
+    fn invalid(&self, method: Method, path: &str, uri: &str, body: Value) -> Status {
+        self.send(method, path, uri, Some(body), Some(TOKEN)).0
+    }
+
+    fn send(&self, method: Method, path: &str, uri: &str, body: Option<Value>, bearer: Option<&str>) -> (Status, Value) {
+        let method_name = method.as_str().to_lowercase();
+        let mut req = self.client.req(method, uri.to_string());
+        if let Some(body) = body {
+            req = req.header(ContentType::JSON).body(body.to_string());
+        }
+        if let Some(bearer) = bearer {
+            req = req.header(Header::new("Authorization", format!("Bearer {}", bearer)));
+        }
+        let resp = req.dispatch();
+        let status = resp.status();
+        let doc: Option<Value> = resp.into_string()
+            .filter(|s| !s.is_empty())
+            .map(|s| serde_json::from_str(&s).unwrap_or_else(|e| panic!("{} {}: not JSON: {}", method_name, uri, e)));
+
+        h::assert_response_matches_openapi(&self.openapi, &method_name, path, status.code, doc.as_ref());
+        self.called.borrow_mut().insert((method_name, path.to_string()));
+        (status, doc.unwrap_or(Value::Null))
+    }
+}
+
+#[test]
+fn every_route_matches_the_openapi_document() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let desktop = device(dir.path(), "desktop");
+    let phone = device(dir.path(), "phone");
+    desktop.appdata.get_conn().expect("conn").batch_execute(
+        "INSERT INTO gloss_prompts_history (id, item_type, data_json, updated_at) VALUES
+             (1, 'gloss', '{}', CURRENT_TIMESTAMP), (2, 'gloss', '{}', NULL);"
+    ).expect("history rows");
+    let api = Contract::new(desktop);
+
+    assert_eq!(api.call(Method::Get, "/v1/openapi.json", "/v1/openapi.json", None, None).0, Status::Ok);
+
+    // Bookmarks
+
+    const FOLDERS: &str = "/v1/bookmarks/folders";
+    const FOLDER: &str = "/v1/bookmarks/folders/{folder_id}";
+    const ITEMS: &str = "/v1/bookmarks/folders/{folder_id}/items";
+    const ITEM: &str = "/v1/bookmarks/items/{item_id}";
+
+    assert_eq!(api.call(Method::Get, FOLDERS, FOLDERS, None, None).0, Status::Unauthorized);
+    assert_eq!(api.token(Method::Get, FOLDERS, FOLDERS, None).0, Status::Ok);
+
+    let (status, a) = api.token(Method::Post, FOLDERS, FOLDERS, Some(json!({ "name": "Study" })));
+    assert_eq!(status, Status::Created);
+    let (_, b) = api.token(Method::Post, FOLDERS, FOLDERS, Some(json!({ "name": "Later" })));
+    let (a, b) = (a["id"].as_i64().unwrap(), b["id"].as_i64().unwrap());
+
+    let (status, folders) = api.token(Method::Post, "/v1/bookmarks/folders/reorder", "/v1/bookmarks/folders/reorder",
+                                      Some(json!({ "ids": [b, a] })));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(folders[0]["id"], json!(b));
+    let (status, _) = api.token(Method::Patch, FOLDER, &format!("{}/{}", FOLDERS, a), Some(json!({ "name": "Suttas" })));
+    assert_eq!(status, Status::Ok);
+    let (status, _) = api.token(Method::Patch, FOLDER, &format!("{}/9999", FOLDERS), Some(json!({ "name": "None" })));
+    assert_eq!(status, Status::NotFound);
+
+    let items_uri = format!("{}/{}/items", FOLDERS, a);
+    let (status, first) = api.token(Method::Post, ITEMS, &items_uri, Some(json!({
+        "item_uid": "mn1/pli/ms", "table_name": "suttas", "title": "Mūlapariyāya Sutta",
+    })));
+    assert_eq!(status, Status::Created);
+    let (_, second) = api.token(Method::Post, ITEMS, &items_uri, Some(json!({ "item_uid": "dhamma 1.01", "table_name": "dpd_headwords" })));
+    let (first, second) = (first["id"].as_i64().unwrap(), second["id"].as_i64().unwrap());
+    assert_eq!(api.invalid(Method::Post, ITEMS, &items_uri, json!({ "item_uid": "mn2/pli/ms" })), Status::BadRequest);
+
+    assert_eq!(api.token(Method::Get, ITEMS, &items_uri, None).0, Status::Ok);
+    let (status, _) = api.token(Method::Post, "/v1/bookmarks/folders/{folder_id}/items/reorder", &format!("{}/reorder", items_uri),
+                                Some(json!({ "ids": [second, first] })));
+    assert_eq!(status, Status::Ok);
+    let (status, _) = api.token(Method::Patch, ITEM, &format!("/v1/bookmarks/items/{}", first),
+                                Some(json!({ "title": null, "find_query": "sabbadhamma", "find_match_index": 2 })));
+    assert_eq!(status, Status::Ok);
+    let (status, moved) = api.token(Method::Post, "/v1/bookmarks/items/move", "/v1/bookmarks/items/move",
+                                    Some(json!({ "item_ids": [first], "folder_id": b })));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(moved[0]["id"], json!(first));
+
+    assert_eq!(api.token(Method::Delete, ITEM, &format!("/v1/bookmarks/items/{}", first), None).0, Status::NoContent);
+    assert_eq!(api.token(Method::Delete, ITEM, &format!("/v1/bookmarks/items/{}", first), None).0, Status::NotFound);
+    assert_eq!(api.token(Method::Delete, FOLDER, &format!("{}/{}", FOLDERS, a), None).0, Status::NoContent);
+
+    // History
+
+    let (status, history) = api.token(Method::Get, "/v1/history/{item_type}", "/v1/history/gloss", None);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(history.as_array().unwrap().len(), 2);
+    assert_eq!(api.token(Method::Get, "/v1/history/{item_type}", "/v1/history/notes", None).0, Status::BadRequest);
+    assert_eq!(api.token(Method::Delete, "/v1/history/{item_type}/{id}", "/v1/history/gloss/1", None).0, Status::NoContent);
+    assert_eq!(api.token(Method::Delete, "/v1/history/{item_type}/{id}", "/v1/history/gloss/1", None).0, Status::NotFound);
+    assert_eq!(api.token(Method::Delete, "/v1/history/{item_type}", "/v1/history/gloss", None).0, Status::NoContent);
+
+    // Sync
+
+    let (status, code) = api.token(Method::Post, "/v1/sync/pairing-code", "/v1/sync/pairing-code", None);
+    assert_eq!(status, Status::Created);
+    let (phone_id, _) = sync::device_identity(&phone).expect("identity");
+    let pair = |code: &str| api.call(Method::Post, "/v1/sync/pair", "/v1/sync/pair", Some(json!({
+        "code": code, "device_id": phone_id, "device_name": "phone",
+    })), None);
+    let (status, paired) = pair(code["code"].as_str().unwrap());
+    assert_eq!(status, Status::Ok);
+    assert_eq!(pair("000000x").0, Status::Unauthorized);
+
+    let exchange = |bearer: &str| api.call(Method::Post, "/v1/sync/exchange", "/v1/sync/exchange", Some(json!({
+        "device_id": phone_id, "since": 0, "records": [],
+    })), Some(bearer));
+    assert_eq!(exchange(paired["secret"].as_str().unwrap()).0, Status::Ok);
+    assert_eq!(exchange("wrong").0, Status::Unauthorized);
+
+    assert_eq!(api.token(Method::Get, "/v1/sync/peers", "/v1/sync/peers", None).0, Status::Ok);
+    let peer_uri = format!("/v1/sync/peers/{}", phone_id);
+    assert_eq!(api.token(Method::Delete, "/v1/sync/peers/{device_id}", &peer_uri, None).0, Status::NoContent);
+    assert_eq!(api.token(Method::Delete, "/v1/sync/peers/{device_id}", &peer_uri, None).0, Status::NotFound);
+
+    // A route added to the document is added here too, or to the content
+    // routes of test_api_v1.rs.
+    let documented: BTreeSet<(String, String)> = ENDPOINTS.iter()
+        .map(|ep| (ep.method.to_string(), ep.path.to_string()))
+        .filter(|route| !h::OPENAPI_CONTENT_ROUTES.contains(&(route.0.as_str(), route.1.as_str())))
+        .collect();
+    assert_eq!(*api.called.borrow(), documented);
+}
@@ -0,0 +1,107 @@
+// The shared localhost API routes as `simsapa_cli serve` mounts them: the
+// database-backed routes answer, the app-window routes answer 501, and
+// read-only mode refuses /shutdown, and off loopback /shutdown and /logger
+// need the API token.
+#![cfg(feature = "api-server")]
+
+mod helpers;
+use helpers as h;
+
+use std::sync::Arc;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::blocking::Client;
+use serial_test::serial;
+
+use simsapa_backend::api_server::{build_rocket, headless, ApiServerConfig};
+use simsapa_backend::db::DbManager;
+
+fn headless_client(read_only: bool) -> Client {
+    headless_client_with(ApiServerConfig { read_only, ..Default::default() })
+}
+
+fn headless_client_with(config: ApiServerConfig) -> Client {
+    h::app_data_setup();
+    let dbm = Arc::new(DbManager::new().expect("DbManager"));
+    let rocket = build_rocket(config, dbm).expect("build rocket").mount("/", headless::routes());
+    Client::tracked(rocket).expect("valid rocket")
+}
+
+#[test]
+#[serial]
+fn health_reports_read_only() {
+    let client = headless_client(true);
+    let resp = client.get("/health").dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+
+    let doc: serde_json::Value = resp.into_json().expect("health json");
+    assert_eq!(doc["read_only"], serde_json::json!(true));
+    assert_eq!(doc["api_port"], serde_json::json!(4848));
+    assert!(doc["counts"]["suttas"].as_i64().unwrap_or(0) > 0);
+}
+
+#[test]
+#[serial]
+fn search_runs_without_the_app() {
+    let client = headless_client(false);
+    let resp = client.post("/search")
+        .header(ContentType::JSON)
+        .body(r#"{"query_text": "sn56.11", "search_area": "Suttas"}"#)
+        .dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+
+    let doc: serde_json::Value = resp.into_json().expect("search json");
+    assert!(doc["hits"].as_i64().unwrap_or(0) > 0);
+}
+
+#[test]
+#[serial]
+fn app_window_routes_are_not_implemented() {
+    let client = headless_client(false);
+
+    assert_eq!(client.get("/open_sutta_tab/win-1/mn1/pli/ms").dispatch().status(), Status::NotImplemented);
+    assert_eq!(client.get("/suttas/mn1/pli/ms").dispatch().status(), Status::NotImplemented);
+    assert_eq!(client.get("/lookup_window_query/dhamma").dispatch().status(), Status::NotImplemented);
+
+    let resp = client.post("/lookup_window_query")
+        .header(ContentType::JSON)
+        .body(r#"{"query_text": "dhamma"}"#)
+        .dispatch();
+    assert_eq!(resp.status(), Status::NotImplemented);
+}
+
+#[test]
+#[serial]
+fn read_only_refuses_shutdown() {
+    let client = headless_client(true);
+    assert_eq!(client.post("/shutdown").dispatch().status(), Status::Forbidden);
+}
+
+#[test]
+#[serial]
+fn network_bind_needs_the_token_for_server_control() {
+    let log = r#"{"log_level": "info", "msg": "test"}"#;
+
+    let client = headless_client(false);
+    let resp = client.post("/logger").header(ContentType::JSON).body(log).dispatch();
+    assert_eq!(resp.status(), Status::Ok, "no token on loopback");
+
+    let client = headless_client_with(ApiServerConfig {
+        bind: "0.0.0.0".to_string(),
+        api_token: Some("secret".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(client.get("/shutdown").dispatch().status(), Status::NotFound, "not a GET route");
+    assert_eq!(client.post("/shutdown").dispatch().status(), Status::Unauthorized);
+    let resp = client.post("/logger").header(ContentType::JSON).body(log).dispatch();
+    assert_eq!(resp.status(), Status::Unauthorized);
+    let resp = client.post("/logger")
+        .header(ContentType::JSON)
+        .header(rocket::http::Header::new("Authorization", "Bearer secret"))
+        .body(log)
+        .dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+
+    let client = headless_client_with(ApiServerConfig { bind: "0.0.0.0".to_string(), ..Default::default() });
+    assert_eq!(client.post("/shutdown").dispatch().status(), Status::Forbidden, "no token configured");
+}
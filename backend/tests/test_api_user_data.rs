@@ -0,0 +1,270 @@
+// The token-guarded /v1 bookmark and history routes: auth, read-only mode,
+// and CRUD through the same AppdataDbHandle functions as the app.
+#![cfg(feature = "api-server")]
+
+use diesel::prelude::*;
+use serial_test::serial;
+
+mod helpers;
+use helpers as h;
+
+use std::sync::Arc;
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::{Client, LocalResponse};
+use serde_json::{json, Value};
+
+use simsapa_backend::api_server::{build_rocket, ApiServerConfig};
+use simsapa_backend::db::appdata_models::HistoryItemType;
+use simsapa_backend::db::DbManager;
+use simsapa_backend::search_history;
+
+const TOKEN: &str = "test-token-0123456789";
+
+/// The test appdata DB is built outside Diesel's migrations, so create the
+/// bookmark and history tables the way test_bookmark_crud.rs does.
+fn ensure_user_data_tables(dbm: &DbManager) {
+    let migrations = [
+        include_str!("../migrations/appdata/2026-04-02-120000_create_bookmarks/up.sql"),
+        include_str!("../migrations/appdata/2026-04-14-000000_add_is_user_added/up.sql"),
+        include_str!("../migrations/appdata/2026-06-27-131935_create_gloss_prompts_history/up.sql"),
+        include_str!("../migrations/appdata/2026-10-18-230000_create_search_history/up.sql"),
+    ];
+
+    let mut db_conn = dbm.appdata.get_conn().expect("get conn");
+    for up_sql in migrations {
+        for statement in up_sql.split(';') {
+            let trimmed = statement.trim();
+            if !trimmed.is_empty() && !trimmed.lines().all(|l| l.trim_start().starts_with("--")) {
+                // "already exists" / "duplicate column" when the DB has them.
+                let _ = diesel::sql_query(trimmed).execute(&mut db_conn);
+            }
+        }
+    }
+
+    let _ = dbm.appdata.do_write(|db_conn| {
+        diesel::sql_query("DELETE FROM bookmark_items").execute(db_conn)?;
+        diesel::sql_query("DELETE FROM bookmark_folders").execute(db_conn)?;
+        diesel::sql_query("DELETE FROM gloss_prompts_history").execute(db_conn)?;
+        diesel::sql_query("DELETE FROM search_history").execute(db_conn)?;
+        Ok(())
+    });
+}
+
+fn client_with(read_only: bool, api_token: Option<&str>) -> (Client, Arc<DbManager>) {
+    h::app_data_setup();
+    let dbm = Arc::new(DbManager::new().expect("DbManager"));
+    ensure_user_data_tables(&dbm);
+
+    let config = ApiServerConfig {
+        read_only,
+        api_token: api_token.map(str::to_string),
+        ..Default::default()
+    };
+    let rocket = build_rocket(config, dbm.clone()).expect("build rocket");
+    (Client::tracked(rocket).expect("valid rocket"), dbm)
+}
+
+fn auth() -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {}", TOKEN))
+}
+
+fn json_of(resp: LocalResponse<'_>) -> Value {
+    resp.into_json().expect("json body")
+}
+
+fn post(client: &Client, path: &str, body: Value) -> (Status, Value) {
+    let resp = client.post(path.to_string()).header(auth()).header(ContentType::JSON).body(body.to_string()).dispatch();
+    (resp.status(), json_of(resp))
+}
+
+fn create_folder(client: &Client, name: &str) -> i64 {
+    let (status, folder) = post(client, "/v1/bookmarks/folders", json!({ "name": name }));
+    assert_eq!(status, Status::Created);
+    folder["id"].as_i64().unwrap()
+}
+
+fn create_item(client: &Client, folder_id: i64, item_uid: &str) -> i64 {
+    let (status, item) = post(client, &format!("/v1/bookmarks/folders/{}/items", folder_id),
+                              json!({ "item_uid": item_uid, "table_name": "suttas" }));
+    assert_eq!(status, Status::Created);
+    assert_eq!(item["folder_id"].as_i64(), Some(folder_id));
+    item["id"].as_i64().unwrap()
+}
+
+fn item_uids(client: &Client, folder_id: i64) -> Vec<String> {
+    let resp = client.get(format!("/v1/bookmarks/folders/{}/items", folder_id)).header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    json_of(resp).as_array().unwrap().iter()
+        .map(|i| i["item_uid"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+#[serial]
+fn requires_the_token() {
+    let (client, _) = client_with(false, Some(TOKEN));
+
+    let resp = client.get("/v1/bookmarks/folders").dispatch();
+    assert_eq!(resp.status(), Status::Unauthorized);
+    assert_eq!(json_of(resp)["error"]["code"], json!("unauthorized"));
+
+    let resp = client.get("/v1/bookmarks/folders").header(Header::new("Authorization", "Bearer wrong")).dispatch();
+    assert_eq!(resp.status(), Status::Unauthorized);
+
+    let resp = client.get("/v1/bookmarks/folders").header(Header::new("X-Simsapa-Token", TOKEN)).dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+
+    // Search stays open.
+    let resp = client.post("/v1/search").header(ContentType::JSON).body(r#"{"query": "sn56.11"}"#).dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+}
+
+#[test]
+#[serial]
+fn search_suggestions_history_requires_the_token() {
+    let (client, dbm) = client_with(false, Some(TOKEN));
+    simsapa_backend::get_app_data().set_search_history_enabled(true);
+    search_history::record_search(&dbm, "satipatthana vibhanga", "Suttas", r#"{"mode":"Fulltext Match"}"#, 3).unwrap();
+
+    let history_texts = |resp: LocalResponse<'_>| -> Vec<Value> {
+        json_of(resp).as_array().unwrap().iter()
+            .filter(|s| s["source"] == json!("history"))
+            .map(|s| s["text"].clone())
+            .collect()
+    };
+
+    // Without the token, the earlier searches are left out.
+    let resp = client.get("/search_suggestions?q=satipatthana&area=Suttas").dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    assert!(history_texts(resp).is_empty());
+
+    let resp = client.get("/search_suggestions?q=satipatthana&area=Suttas").header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    assert_eq!(history_texts(resp), vec![json!("satipatthana vibhanga")]);
+}
+
+#[test]
+#[serial]
+fn disabled_without_a_token() {
+    let (client, _) = client_with(false, None);
+    let resp = client.get("/v1/bookmarks/folders").header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::Forbidden);
+}
+
+#[test]
+#[serial]
+fn read_only_allows_reads_only() {
+    let (client, _) = client_with(true, Some(TOKEN));
+
+    assert_eq!(client.get("/v1/bookmarks/folders").header(auth()).dispatch().status(), Status::Ok);
+
+    let (status, doc) = post(&client, "/v1/bookmarks/folders", json!({ "name": "Reading" }));
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(doc["error"]["message"], json!("The server is read-only."));
+}
+
+#[test]
+#[serial]
+fn folder_crud_and_reorder() {
+    let (client, dbm) = client_with(false, Some(TOKEN));
+
+    let a = create_folder(&client, "First");
+    let b = create_folder(&client, "Second");
+
+    let resp = client.patch(format!("/v1/bookmarks/folders/{}", a))
+        .header(auth()).header(ContentType::JSON)
+        .body(r#"{"name": "Renamed"}"#)
+        .dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    assert_eq!(json_of(resp)["name"], json!("Renamed"));
+
+    let (status, folders) = post(&client, "/v1/bookmarks/folders/reorder", json!({ "ids": [b, a] }));
+    assert_eq!(status, Status::Ok);
+    let names: Vec<&str> = folders.as_array().unwrap().iter().map(|f| f["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["Second", "Renamed"]);
+
+    // Same rows as the bridges see.
+    assert_eq!(dbm.appdata.get_all_bookmark_folders()[0].name, "Second");
+
+    let resp = client.delete(format!("/v1/bookmarks/folders/{}", a)).header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::NoContent);
+    let resp = client.delete(format!("/v1/bookmarks/folders/{}", a)).header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::NotFound);
+    assert_eq!(json_of(resp)["error"]["code"], json!("not_found"));
+
+    let (status, _) = post(&client, "/v1/bookmarks/folders", json!({ "name": "  " }));
+    assert_eq!(status, Status::BadRequest);
+}
+
+#[test]
+#[serial]
+fn item_crud_reorder_and_move() {
+    let (client, _) = client_with(false, Some(TOKEN));
+
+    let folder = create_folder(&client, "Reading");
+    let other = create_folder(&client, "Later");
+    let mn1 = create_item(&client, folder, "mn1/pli/ms");
+    let mn2 = create_item(&client, folder, "mn2/pli/ms");
+    let mn3 = create_item(&client, folder, "mn3/pli/ms");
+    assert_eq!(item_uids(&client, folder), vec!["mn1/pli/ms", "mn2/pli/ms", "mn3/pli/ms"]);
+
+    let (status, _) = post(&client, &format!("/v1/bookmarks/folders/{}/items/reorder", folder), json!({ "ids": [mn3, mn1, mn2] }));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(item_uids(&client, folder), vec!["mn3/pli/ms", "mn1/pli/ms", "mn2/pli/ms"]);
+
+    let resp = client.patch(format!("/v1/bookmarks/items/{}", mn1))
+        .header(auth()).header(ContentType::JSON)
+        .body(r#"{"title": "The Root of All Things"}"#)
+        .dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    let item = json_of(resp);
+    assert_eq!(item["title"], json!("The Root of All Things"));
+    assert_eq!(item["item_uid"], json!("mn1/pli/ms"));
+
+    create_item(&client, other, "sn56.11/pli/ms");
+    let (status, moved) = post(&client, "/v1/bookmarks/items/move", json!({ "item_ids": [mn2, mn3], "folder_id": other }));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(moved.as_array().unwrap().len(), 3);
+    assert_eq!(item_uids(&client, other), vec!["sn56.11/pli/ms", "mn2/pli/ms", "mn3/pli/ms"]);
+    assert_eq!(item_uids(&client, folder), vec!["mn1/pli/ms"]);
+
+    let resp = client.delete(format!("/v1/bookmarks/items/{}", mn1)).header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::NoContent);
+    assert!(item_uids(&client, folder).is_empty());
+
+    let (status, _) = post(&client, "/v1/bookmarks/folders/999999/items", json!({ "item_uid": "mn1/pli/ms", "table_name": "suttas" }));
+    assert_eq!(status, Status::NotFound);
+}
+
+#[test]
+#[serial]
+fn history_read_and_delete() {
+    let (client, dbm) = client_with(false, Some(TOKEN));
+
+    let first = dbm.appdata.save_new_history(HistoryItemType::Gloss, r#"{"text": "first"}"#).expect("save");
+    dbm.appdata.save_new_history(HistoryItemType::Gloss, r#"{"text": "second"}"#).expect("save");
+    dbm.appdata.save_new_history(HistoryItemType::Prompts, r#"{"text": "prompt"}"#).expect("save");
+
+    let resp = client.get("/v1/history/gloss").header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    let items = json_of(resp);
+    assert_eq!(items.as_array().unwrap().len(), 2);
+    assert_eq!(items[0]["item_type"], json!("gloss"));
+
+    let resp = client.get("/v1/history/notes").header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::BadRequest);
+
+    // An id of another type is not found under this one.
+    let resp = client.delete(format!("/v1/history/prompts/{}", first)).header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::NotFound);
+
+    let resp = client.delete(format!("/v1/history/gloss/{}", first)).header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::NoContent);
+    assert_eq!(dbm.appdata.get_history_for_type(HistoryItemType::Gloss).len(), 1);
+
+    let resp = client.delete("/v1/history/gloss").header(auth()).dispatch();
+    assert_eq!(resp.status(), Status::NoContent);
+    assert!(dbm.appdata.get_history_for_type(HistoryItemType::Gloss).is_empty());
+    assert_eq!(dbm.appdata.get_history_for_type(HistoryItemType::Prompts).len(), 1);
+}
@@ -0,0 +1,247 @@
+// Contract tests of the versioned /v1 API: response shapes, the error body,
+// pagination metadata, that /v1/openapi.json documents every mounted /v1
+// route, and that the content routes return what it documents (the other
+// routes are checked in test_api_contract.rs).
+#![cfg(feature = "api-server")]
+
+mod helpers;
+use helpers as h;
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use rocket::http::{ContentType, Method, Status};
+use rocket::local::blocking::Client;
+use serde_json::{json, Value};
+use serial_test::serial;
+
+use simsapa_backend::api_server::v1::openapi::ENDPOINTS;
+use simsapa_backend::api_server::{build_rocket, ApiServerConfig};
+use simsapa_backend::db::DbManager;
+
+fn client() -> Client {
+    h::app_data_setup();
+    let dbm = Arc::new(DbManager::new().expect("DbManager"));
+    let rocket = build_rocket(ApiServerConfig::default(), dbm).expect("build rocket");
+    Client::tracked(rocket).expect("valid rocket")
+}
+
+fn post_search(client: &Client, body: Value) -> (Status, Value) {
+    let resp = client.post("/v1/search")
+        .header(ContentType::JSON)
+        .body(body.to_string())
+        .dispatch();
+    let status = resp.status();
+    (status, resp.into_json().expect("json body"))
+}
+
+fn assert_error_body(doc: &Value, status: Status, code: &str) {
+    assert_eq!(doc["error"]["status"], json!(status.code), "{}", doc);
+    assert_eq!(doc["error"]["code"], json!(code), "{}", doc);
+    assert!(doc["error"]["message"].is_string(), "{}", doc);
+}
+
+/// `/v1/suttas/<uid..>` → `/v1/suttas/{uid}`
+fn openapi_path(rocket_path: &str) -> String {
+    rocket_path.split('/')
+        .map(|seg| match seg.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+            Some(name) => format!("{{{}}}", name.trim_end_matches("..")),
+            None => seg.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[test]
+#[serial]
+fn openapi_documents_every_v1_route() {
+    let client = client();
+
+    let mounted: BTreeSet<(String, String)> = client.rocket().routes()
+        .filter(|r| r.uri.path().starts_with("/v1/"))
+        .map(|r| (r.method.as_str().to_lowercase(), openapi_path(r.uri.path())))
+        .collect();
+    let documented: BTreeSet<(String, String)> = ENDPOINTS.iter()
+        .map(|ep| (ep.method.to_string(), ep.path.to_string()))
+        .collect();
+    assert_eq!(mounted, documented);
+
+    let resp = client.get("/v1/openapi.json").dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    let doc: Value = resp.into_json().expect("openapi json");
+    assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+    for (method, path) in &documented {
+        assert!(doc["paths"][path][method].is_object(), "{} {} not in openapi.json", method, path);
+    }
+}
+
+#[test]
+#[serial]
+fn content_routes_match_the_openapi_document() {
+    let client = client();
+    let openapi: Value = client.get("/v1/openapi.json").dispatch().into_json().expect("openapi json");
+    let mut called: BTreeSet<(String, String)> = BTreeSet::new();
+
+    // A request which the server accepts must be valid for the schema too.
+    let mut call = |method: Method, path: &str, uri: &str, body: Option<Value>| -> Status {
+        let method_name = method.as_str().to_lowercase();
+        let mut req = client.req(method, uri.to_string());
+        if let Some(body) = &body {
+            req = req.header(ContentType::JSON).body(body.to_string());
+        }
+        let resp = req.dispatch();
+        let status = resp.status();
+        let doc: Option<Value> = resp.into_json();
+        if let (Some(body), true) = (&body, status.class().is_success()) {
+            h::assert_request_matches_openapi(&openapi, &method_name, path, body);
+        }
+        h::assert_response_matches_openapi(&openapi, &method_name, path, status.code, doc.as_ref());
+        called.insert((method_name, path.to_string()));
+        status
+    };
+
+    assert_eq!(call(Method::Get, "/v1/health", "/v1/health", None), Status::Ok);
+    assert_eq!(call(Method::Get, "/v1/search/options", "/v1/search/options", None), Status::Ok);
+
+    for body in [
+        json!({ "query": "dhamma", "page_len": 5 }),
+        json!({ "query": "dhamma", "mode": "contains", "page_len": 5 }),
+        json!({ "query": "sn56.11", "mode": "uid" }),
+        json!({ "query": "dhamma", "area": "library" }),
+        json!({ "query": "dhamma", "area": "dictionary" }),
+        json!({ "query": "dhamma", "area": "dictionary", "mode": "fulltext", "page_len": 5 }),
+    ] {
+        assert_eq!(call(Method::Post, "/v1/search", "/v1/search", Some(body)), Status::Ok);
+    }
+    let invalid = json!({ "query": "dhamma", "mode": "Fulltext Match" });
+    assert_eq!(call(Method::Post, "/v1/search", "/v1/search", Some(invalid)), Status::BadRequest);
+
+    assert_eq!(call(Method::Get, "/v1/suttas/{uid}", "/v1/suttas/sn56.11/pli/ms", None), Status::Ok);
+    assert_eq!(call(Method::Get, "/v1/suttas/{uid}", "/v1/suttas/no-such-sutta/pli/ms", None), Status::NotFound);
+    assert_eq!(call(Method::Get, "/v1/words/{uid}", "/v1/words/dhamma%201.01", None), Status::Ok);
+    assert_eq!(call(Method::Get, "/v1/words/{uid}", "/v1/words/no-such-word/dpd", None), Status::NotFound);
+
+    let content_routes: BTreeSet<(String, String)> = h::OPENAPI_CONTENT_ROUTES.iter()
+        .map(|(method, path)| (method.to_string(), path.to_string()))
+        .collect();
+    assert_eq!(called, content_routes);
+}
+
+#[test]
+#[serial]
+fn search_has_pagination_metadata() {
+    let client = client();
+    let (status, doc) = post_search(&client, json!({ "query": "dhamma", "mode": "contains", "page_len": 5 }));
+    assert_eq!(status, Status::Ok);
+
+    assert_eq!(doc["area"], json!("suttas"));
+    assert_eq!(doc["mode"], json!("contains"));
+    assert_eq!(doc["page"], json!(0));
+    assert_eq!(doc["page_len"], json!(5));
+
+    let total_hits = doc["total_hits"].as_u64().unwrap();
+    let results = doc["results"].as_array().unwrap();
+    assert!(total_hits > 5);
+    assert_eq!(results.len(), 5);
+    assert_eq!(doc["has_more"], json!(true));
+
+    let hit = &results[0];
+    for key in ["uid", "kind", "title", "sutta_ref", "lang", "source_uid", "snippet", "page_number", "score"] {
+        assert!(hit.get(key).is_some(), "hit is missing '{}'", key);
+    }
+    assert_eq!(hit["kind"], json!("suttas"));
+    assert!(hit.get("schema_name").is_none());
+}
+
+#[test]
+#[serial]
+fn search_last_page_has_no_more() {
+    let client = client();
+    let (_, first) = post_search(&client, json!({ "query": "sn56.11" }));
+    let total_hits = first["total_hits"].as_u64().unwrap();
+    assert!(total_hits > 0);
+
+    let last_page = (total_hits - 1) / 20;
+    let (status, last) = post_search(&client, json!({ "query": "sn56.11", "page": last_page }));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(last["has_more"], json!(false));
+}
+
+#[test]
+#[serial]
+fn dictionary_search_defaults_to_combined() {
+    let client = client();
+    let (status, doc) = post_search(&client, json!({ "query": "dhamma", "area": "dictionary" }));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(doc["mode"], json!("combined"));
+    assert!(doc["deconstructor"].is_array());
+    assert!(doc["total_hits"].as_u64().unwrap() > 0);
+}
+
+#[test]
+#[serial]
+fn invalid_requests_get_the_error_body() {
+    let client = client();
+
+    let (status, doc) = post_search(&client, json!({ "query": "dhamma", "mode": "Fulltext Match" }));
+    assert_eq!(status, Status::BadRequest);
+    assert_error_body(&doc, status, "bad_request");
+
+    let (status, doc) = post_search(&client, json!({ "query_text": "dhamma" }));
+    assert_eq!(status, Status::BadRequest);
+    assert_error_body(&doc, status, "bad_request");
+
+    let (status, doc) = post_search(&client, json!({ "query": "dhamma", "page_len": 1000 }));
+    assert_eq!(status, Status::BadRequest);
+    assert_error_body(&doc, status, "bad_request");
+
+    let resp = client.post("/v1/search").header(ContentType::JSON).body("{not json").dispatch();
+    assert_eq!(resp.status(), Status::BadRequest);
+    assert_error_body(&resp.into_json().unwrap(), Status::BadRequest, "bad_request");
+
+    let resp = client.get("/v1/no_such_route").dispatch();
+    assert_eq!(resp.status(), Status::NotFound);
+    assert_error_body(&resp.into_json().unwrap(), Status::NotFound, "not_found");
+}
+
+#[test]
+#[serial]
+fn get_sutta_and_word() {
+    let client = client();
+
+    let resp = client.get("/v1/suttas/sn56.11/pli/ms").dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    let sutta: Value = resp.into_json().unwrap();
+    assert_eq!(sutta["uid"], json!("sn56.11/pli/ms"));
+    assert!(!sutta["segments"].as_array().unwrap().is_empty());
+    assert!(sutta["text"].is_null());
+
+    let resp = client.get("/v1/suttas/no-such-sutta/pli/ms").dispatch();
+    assert_eq!(resp.status(), Status::NotFound);
+    assert_error_body(&resp.into_json().unwrap(), Status::NotFound, "not_found");
+
+    let resp = client.get("/v1/words/dhamma%201.01").dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    let word: Value = resp.into_json().unwrap();
+    assert_eq!(word["query_uid"], json!("dhamma 1.01"));
+    assert_eq!(word["kind"], json!("dict_word"));
+    assert!(word["data"].is_object());
+
+    let resp = client.get("/v1/words/no-such-word/dpd").dispatch();
+    assert_eq!(resp.status(), Status::NotFound);
+    assert_error_body(&resp.into_json().unwrap(), Status::NotFound, "not_found");
+}
+
+#[test]
+#[serial]
+fn legacy_routes_are_unchanged() {
+    let client = client();
+    let resp = client.post("/search")
+        .header(ContentType::JSON)
+        .body(r#"{"query_text": "sn56.11", "search_area": "Suttas"}"#)
+        .dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    let doc: Value = resp.into_json().unwrap();
+    assert!(doc["hits"].as_i64().unwrap() > 0);
+    assert!(doc["results"][0].get("schema_name").is_some());
+}
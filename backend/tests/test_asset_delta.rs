@@ -0,0 +1,271 @@
+// Delta updates of the shipped databases: create a delta between two
+// releases, apply it to a client with its own rows, and roll back a delta
+// which doesn't fit, keeping it to try again.
+
+use std::path::Path;
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::asset_delta::{self, TableSpec};
+use simsapa_backend::db::{APPDATA_MIGRATIONS, DICTIONARIES_MIGRATIONS};
+
+fn url(dir: &Path, db: &str) -> String {
+    dir.join(format!("{}.sqlite3", db)).to_string_lossy().to_string()
+}
+
+/// Migrated appdata and dictionaries databases in `dir` at `version`.
+fn release(dir: &Path, version: &str, appdata_sql: &str, dictionaries_sql: &str) {
+    std::fs::create_dir_all(dir).unwrap();
+    let mut db_conn = SqliteConnection::establish(&url(dir, "appdata")).expect("appdata");
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).expect("appdata migrations");
+    db_conn.batch_execute(appdata_sql).expect("appdata rows");
+    db_conn.batch_execute(&format!(
+        "DELETE FROM app_settings WHERE key = 'db_version';
+         INSERT INTO app_settings (key, value) VALUES ('db_version', '{}');", version
+    )).expect("db_version");
+
+    let mut db_conn = SqliteConnection::establish(&url(dir, "dictionaries")).expect("dictionaries");
+    db_conn.run_pending_migrations(DICTIONARIES_MIGRATIONS).expect("dictionaries migrations");
+    db_conn.batch_execute(dictionaries_sql).expect("dictionaries rows");
+}
+
+fn old_release(dir: &Path) {
+    release(dir, "0.1.0", "
+        INSERT INTO suttas (id, uid, sutta_ref, nikaya, language, title, created_at) VALUES
+            (1, 'mn1/pli/ms', 'MN 1', 'mn', 'pli', 'Mūlapariyāya (old)', '2025-01-01'),
+            (2, 'mn2/pli/ms', 'MN 2', 'mn', 'pli', 'Sabbāsava', '2025-01-01'),
+            (3, 'mn3/pli/ms', 'MN 3', 'mn', 'pli', 'Dhammadāyāda', '2025-01-01');
+        INSERT INTO sutta_variants (sutta_id, sutta_uid, content_json) VALUES
+            (1, 'mn1/pli/ms', 'v1'), (1, 'mn1/pli/ms', 'v2'), (2, 'mn2/pli/ms', 'v3'), (3, 'mn3/pli/ms', 'v4');
+    ", "
+        INSERT INTO dictionaries (id, label, title, dict_type, created_at) VALUES (1, 'pts', 'PTS', 'stardict', '2025-01-01');
+        INSERT INTO dict_words (dictionary_id, dict_label, uid, word, word_ascii) VALUES
+            (1, 'pts', 'a/pts', 'a', 'a'), (1, 'pts', 'b/pts', 'b', 'b');
+    ");
+}
+
+/// The next release, bootstrapped again: other ids and timestamps.
+fn new_release(dir: &Path) {
+    release(dir, "0.2.0", "
+        INSERT INTO suttas (id, uid, sutta_ref, nikaya, language, title, created_at) VALUES
+            (1, 'mn2/pli/ms', 'MN 2', 'mn', 'pli', 'Sabbāsava', '2026-01-01'),
+            (2, 'mn1/pli/ms', 'MN 1', 'mn', 'pli', 'Mūlapariyāya', '2026-01-01'),
+            (3, 'mn4/pli/ms', 'MN 4', 'mn', 'pli', 'Bhayabherava', '2026-01-01');
+        INSERT INTO sutta_variants (sutta_id, sutta_uid, content_json) VALUES
+            (1, 'mn2/pli/ms', 'v3'), (2, 'mn1/pli/ms', 'v1 fixed'), (3, 'mn4/pli/ms', 'v5');
+    ", "
+        INSERT INTO dictionaries (id, label, title, dict_type, created_at) VALUES
+            (1, 'cpd', 'CPD', 'stardict', '2026-01-01'), (2, 'pts', 'PTS', 'stardict', '2026-01-01');
+        INSERT INTO dict_words (dictionary_id, dict_label, uid, word, word_ascii) VALUES
+            (2, 'pts', 'a/pts', 'ā', 'a'), (2, 'pts', 'c/pts', 'c', 'c'), (1, 'cpd', 'a/cpd', 'a', 'a');
+    ");
+}
+
+fn specs() -> Vec<(&'static str, Vec<TableSpec>)> {
+    let ts = ["created_at", "updated_at"];
+    vec![
+        ("appdata.sqlite3", vec![
+            TableSpec::keyed("suttas", &["uid"]).ignoring(&ts),
+            TableSpec::children("sutta_variants", "sutta_id", "suttas").ignoring(&ts),
+        ]),
+        ("dictionaries.sqlite3", vec![
+            TableSpec::keyed("dictionaries", &["label"]).ignoring(&ts),
+            TableSpec::keyed_child("dict_words", &["uid"], "dictionary_id", "dictionaries").ignoring(&ts),
+        ]),
+    ]
+}
+
+fn strings(dir: &Path, db: &str, sql: &str) -> Vec<String> {
+    #[derive(QueryableByName)]
+    struct Row {
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        s: String,
+    }
+    let mut db_conn = SqliteConnection::establish(&url(dir, db)).expect("db");
+    diesel::sql_query(sql).load::<Row>(&mut db_conn).expect(sql).into_iter().map(|r| r.s).collect()
+}
+
+/// A client at the old release with its own rows in the shared tables.
+fn client(dir: &Path) {
+    old_release(dir);
+    let mut db_conn = SqliteConnection::establish(&url(dir, "appdata")).unwrap();
+    db_conn.batch_execute("
+        INSERT INTO suttas (id, uid, sutta_ref, nikaya, language, title) VALUES (10, 'mn1/de/user', 'MN 1', 'mn', 'de', 'Die Wurzel');
+        INSERT INTO sutta_variants (sutta_id, sutta_uid, content_json) VALUES (10, 'mn1/de/user', 'de');
+    ").unwrap();
+    let mut db_conn = SqliteConnection::establish(&url(dir, "dictionaries")).unwrap();
+    db_conn.batch_execute("
+        INSERT INTO dictionaries (id, label, title, dict_type, is_user_imported) VALUES (2, 'mydict', 'My Dictionary', 'stardict', 1);
+        INSERT INTO dict_words (dictionary_id, dict_label, uid, word, word_ascii) VALUES (2, 'mydict', 'a/mydict', 'a', 'a');
+    ").unwrap();
+}
+
+#[test]
+fn test_create_and_apply_delta() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (old, new, app) = (tmp.path().join("old"), tmp.path().join("new"), tmp.path().join("app"));
+    old_release(&old);
+    new_release(&new);
+    client(&app);
+    std::fs::create_dir_all(app.join("index")).unwrap();
+    std::fs::write(app.join("index").join("VERSION"), "1").unwrap();
+
+    let delta = tmp.path().join("delta.zip");
+    let manifest = asset_delta::create_delta(&old, &new, &specs(), "0.1.0", "0.2.0", &delta).expect("create delta");
+    let tables: Vec<(&str, usize, usize)> = manifest.databases.iter()
+        .flat_map(|d| &d.tables)
+        .map(|t| (t.spec.table.as_str(), t.rows, t.deletes))
+        .collect();
+    // The timestamps and the new ids are not changes.
+    assert_eq!(tables, vec![
+        ("suttas", 2, 1),
+        ("sutta_variants", 2, 3),
+        ("dictionaries", 1, 0),
+        ("dict_words", 3, 1),
+    ]);
+    assert_eq!(asset_delta::verify_delta(&delta).expect("verify").to_version, "0.2.0");
+
+    let report = asset_delta::apply_delta(&delta, &app).expect("apply delta");
+    assert_eq!(report.changes["appdata.sqlite3/suttas"], 3);
+    assert_eq!(asset_delta::read_db_version(&app).unwrap().as_deref(), Some("0.2.0"));
+    assert!(!app.join("index").join("VERSION").exists());
+
+    assert_eq!(
+        strings(&app, "appdata", "SELECT uid || ':' || title AS s FROM suttas ORDER BY uid"),
+        vec!["mn1/de/user:Die Wurzel", "mn1/pli/ms:Mūlapariyāya", "mn2/pli/ms:Sabbāsava", "mn4/pli/ms:Bhayabherava"]
+    );
+    // The updated sutta keeps its id, the children follow their parent.
+    assert_eq!(
+        strings(&app, "appdata", "SELECT s.uid || ':' || s.id || ':' || v.content_json AS s
+            FROM sutta_variants v JOIN suttas s ON s.id = v.sutta_id ORDER BY s.uid, v.content_json"),
+        vec!["mn1/de/user:10:de", "mn1/pli/ms:1:v1 fixed", "mn2/pli/ms:2:v3", "mn4/pli/ms:11:v5"]
+    );
+    assert_eq!(
+        strings(&app, "dictionaries", "SELECT w.uid || ':' || d.label || ':' || w.word AS s
+            FROM dict_words w JOIN dictionaries d ON d.id = w.dictionary_id ORDER BY w.uid"),
+        vec!["a/cpd:cpd:a", "a/mydict:mydict:a", "a/pts:pts:ā", "c/pts:pts:c"]
+    );
+
+    // The delta is for 0.1.0 only.
+    let err = asset_delta::apply_delta(&delta, &app).expect_err("applied twice");
+    assert!(format!("{:#}", err).contains("0.2.0"), "{:#}", err);
+}
+
+#[test]
+fn test_failed_delta_rolls_back() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (old, new, app) = (tmp.path().join("old"), tmp.path().join("new"), tmp.path().join("app"));
+    old_release(&old);
+    new_release(&new);
+    client(&app);
+    let delta = tmp.path().join("delta.zip");
+    asset_delta::create_delta(&old, &new, &specs(), "0.1.0", "0.2.0", &delta).expect("create delta");
+
+    // The delta's new words of 'pts' have no dictionary to go to.
+    let mut db_conn = SqliteConnection::establish(&url(&app, "dictionaries")).unwrap();
+    db_conn.batch_execute("DELETE FROM dict_words WHERE dict_label = 'pts'; DELETE FROM dictionaries WHERE label = 'pts';").unwrap();
+
+    let err = asset_delta::apply_delta(&delta, &app).expect_err("no parent");
+    assert!(format!("{:#}", err).contains("no parent"), "{:#}", err);
+
+    // The suttas, updated before the failure, are as they were.
+    assert_eq!(asset_delta::read_db_version(&app).unwrap().as_deref(), Some("0.1.0"));
+    assert_eq!(
+        strings(&app, "appdata", "SELECT uid AS s FROM suttas ORDER BY uid"),
+        vec!["mn1/de/user", "mn1/pli/ms", "mn2/pli/ms", "mn3/pli/ms"]
+    );
+    assert_eq!(strings(&app, "appdata", "SELECT content_json AS s FROM sutta_variants WHERE sutta_id = 1 ORDER BY content_json"), vec!["v1", "v2"]);
+    assert_eq!(strings(&app, "dictionaries", "SELECT label AS s FROM dictionaries"), vec!["mydict"]);
+}
+
+#[test]
+fn test_staged_deltas_apply_in_order() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (old, new, app) = (tmp.path().join("old"), tmp.path().join("new"), tmp.path().join("app"));
+    old_release(&old);
+    new_release(&new);
+    client(&app);
+
+    // 0.1.0 -> 0.2.0, and 0.2.0 -> 0.2.1 without changes.
+    let first = tmp.path().join("first.zip");
+    asset_delta::create_delta(&old, &new, &specs(), "0.1.0", "0.2.0", &first).unwrap();
+    let second = tmp.path().join("second.zip");
+    let manifest = asset_delta::create_delta(&new, &new, &specs(), "0.2.0", "0.2.1", &second).unwrap();
+    assert_eq!(manifest.total_changes(), 0);
+    let unrelated = tmp.path().join("unrelated.zip");
+    asset_delta::create_delta(&new, &new, &specs(), "0.0.9", "0.1.0", &unrelated).unwrap();
+
+    for delta in [&second, &first, &unrelated] {
+        asset_delta::stage_delta(delta, &app).expect("stage");
+    }
+    std::fs::write(tmp.path().join("junk.zip"), b"not a zip").unwrap();
+    assert!(asset_delta::stage_delta(&tmp.path().join("junk.zip"), &app).is_err());
+
+    let reports = asset_delta::apply_pending_deltas(&app);
+    let versions: Vec<&str> = reports.iter().map(|r| r.to_version.as_str()).collect();
+    assert_eq!(versions, vec!["0.2.0", "0.2.1"]);
+    assert_eq!(asset_delta::read_db_version(&app).unwrap().as_deref(), Some("0.2.1"));
+    assert!(!app.join(asset_delta::PENDING_DELTAS_DIR).exists());
+}
+
+#[test]
+fn test_failed_pending_delta_is_kept() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (old, new, app) = (tmp.path().join("old"), tmp.path().join("new"), tmp.path().join("app"));
+    old_release(&old);
+    new_release(&new);
+    client(&app);
+
+    let first = tmp.path().join("first.zip");
+    asset_delta::create_delta(&old, &new, &specs(), "0.1.0", "0.2.0", &first).unwrap();
+    let second = tmp.path().join("second.zip");
+    asset_delta::create_delta(&new, &new, &specs(), "0.2.0", "0.2.1", &second).unwrap();
+    for delta in [&first, &second] {
+        asset_delta::stage_delta(delta, &app).expect("stage");
+    }
+    let pending = app.join(asset_delta::PENDING_DELTAS_DIR);
+    let staged = || -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(&pending).into_iter().flatten().flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    };
+
+    // As in test_failed_delta_rolls_back, the first delta fails. It is kept
+    // with the one after it.
+    let mut db_conn = SqliteConnection::establish(&url(&app, "dictionaries")).unwrap();
+    db_conn.batch_execute("DELETE FROM dict_words WHERE dict_label = 'pts'; DELETE FROM dictionaries WHERE label = 'pts';").unwrap();
+
+    assert!(asset_delta::apply_pending_deltas(&app).is_empty());
+    assert_eq!(asset_delta::read_db_version(&app).unwrap().as_deref(), Some("0.1.0"));
+    assert_eq!(staged(), vec!["0.1.0-to-0.2.0.zip", "0.2.0-to-0.2.1.zip"]);
+
+    // Once it can be applied, the next start applies both.
+    db_conn.batch_execute("
+        INSERT INTO dictionaries (id, label, title, dict_type, created_at) VALUES (1, 'pts', 'PTS', 'stardict', '2025-01-01');
+        INSERT INTO dict_words (dictionary_id, dict_label, uid, word, word_ascii) VALUES (1, 'pts', 'a/pts', 'a', 'a'), (1, 'pts', 'b/pts', 'b', 'b');
+    ").unwrap();
+    let reports = asset_delta::apply_pending_deltas(&app);
+    let versions: Vec<&str> = reports.iter().map(|r| r.to_version.as_str()).collect();
+    assert_eq!(versions, vec!["0.2.0", "0.2.1"]);
+    assert!(!pending.exists());
+}
+
+#[test]
+fn test_release_tables_match_the_schema() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (old, new) = (tmp.path().join("old"), tmp.path().join("new"));
+    release(&old, "0.1.0", "", "");
+    release(&new, "0.2.0", "", "");
+
+    // The DPD database is not created by the migrations here.
+    let tables: Vec<_> = asset_delta::release_delta_tables().into_iter()
+        .filter(|(file, _)| *file != "dpd.sqlite3")
+        .collect();
+    let manifest = asset_delta::create_delta(&old, &new, &tables, "0.1.0", "0.2.0", &tmp.path().join("delta.zip"))
+        .expect("create delta");
+    assert_eq!(manifest.total_changes(), 0);
+}
@@ -0,0 +1,96 @@
+//! Audio export of chanting recordings against real `.flac` inputs produced
+//! by the recorder's encode path.
+//!
+//! Covers one-file-per-recording WAV export, trimming to range markers, and
+//! concatenation into a single file with a CUE sheet.
+
+use simsapa_backend::audio::export::{export_audio, AudioExportFormat, AudioExportItem, AudioExportOptions};
+use simsapa_backend::audio::format::CANONICAL_SAMPLE_RATE;
+use simsapa_backend::audio::loudness::integrated_loudness;
+use simsapa_backend::audio::player::decode_to_mono;
+use simsapa_backend::audio::recorder::encode_canonical_pcm_to_flac;
+use simsapa_backend::waveform::get_audio_duration_ms;
+
+/// Generate `seconds` of a mono sine wave at `freq` Hz and `amplitude` as
+/// canonical 16-bit PCM.
+fn sine_pcm(freq: f32, amplitude: f32, seconds: f32) -> Vec<i16> {
+    let n = (CANONICAL_SAMPLE_RATE as f32 * seconds) as usize;
+    (0..n)
+        .map(|i| {
+            let t = i as f32 / CANONICAL_SAMPLE_RATE as f32;
+            let v = (2.0 * std::f32::consts::PI * freq * t).sin() * amplitude;
+            (v * i16::MAX as f32) as i16
+        })
+        .collect()
+}
+
+fn fixture(dir: &std::path::Path, name: &str, amplitude: f32, seconds: f32) -> std::path::PathBuf {
+    let path = dir.join(name);
+    encode_canonical_pcm_to_flac(&sine_pcm(220.0, amplitude, seconds), &path).expect("encode flac");
+    path
+}
+
+#[test]
+fn separate_wav_files_are_normalized() {
+    let src = tempfile::tempdir().expect("tempdir");
+    let dest = tempfile::tempdir().expect("tempdir");
+
+    let items = vec![
+        AudioExportItem { title: "Quiet".to_string(), file_path: fixture(src.path(), "a.flac", 0.05, 2.0), markers_json: None },
+        AudioExportItem { title: "Loud".to_string(), file_path: fixture(src.path(), "b.flac", 0.6, 2.0), markers_json: None },
+    ];
+
+    let options = AudioExportOptions { format: AudioExportFormat::Wav, ..Default::default() };
+    let result = export_audio(&items, dest.path(), "Evening", &options).expect("export");
+
+    assert_eq!(result.audio_files.len(), 2);
+    assert!(result.cue_file.is_none());
+    assert!(result.audio_files[0].file_name().unwrap().to_str().unwrap().starts_with("01_Quiet"));
+
+    for path in &result.audio_files {
+        let (samples, rate) = decode_to_mono(path).expect("decode wav");
+        assert_eq!(rate, CANONICAL_SAMPLE_RATE);
+        let lufs = integrated_loudness(&samples).expect("measurable");
+        assert!((lufs - options.target_lufs).abs() < 0.5, "{} at {lufs} LUFS", path.display());
+    }
+}
+
+#[test]
+fn concatenated_export_trims_to_markers_and_writes_cue() {
+    let src = tempfile::tempdir().expect("tempdir");
+    let dest = tempfile::tempdir().expect("tempdir");
+
+    let markers = r#"[
+        {"id": "r1", "type": "range", "label": "Line 1", "start_ms": 0, "end_ms": 1000},
+        {"id": "p1", "type": "position", "label": "Mark", "position_ms": 1500},
+        {"id": "r2", "type": "range", "label": "Line 2", "start_ms": 2000, "end_ms": 2500}
+    ]"#;
+    let items = vec![
+        AudioExportItem { title: "Namo".to_string(), file_path: fixture(src.path(), "a.flac", 0.3, 3.0), markers_json: Some(markers.to_string()) },
+        AudioExportItem { title: "Tisaraṇa".to_string(), file_path: fixture(src.path(), "b.flac", 0.3, 1.0), markers_json: None },
+    ];
+
+    let options = AudioExportOptions {
+        format: AudioExportFormat::Wav,
+        trim_to_markers: true,
+        normalize_loudness: false,
+        concatenate: true,
+        gap_ms: 500,
+        ..Default::default()
+    };
+    let result = export_audio(&items, dest.path(), "Evening Chanting", &options).expect("export");
+
+    assert_eq!(result.audio_files.len(), 1);
+    let titles: Vec<&str> = result.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, vec!["Namo – Line 1", "Namo – Line 2", "Tisaraṇa"]);
+
+    // 1000 + 500 gap + 500 + 500 gap + 1000
+    let duration = get_audio_duration_ms(result.audio_files[0].to_str().unwrap());
+    assert!((3450..=3550).contains(&duration), "duration {duration}");
+    assert_eq!(result.chapters[1].start_ms, 1500);
+    assert_eq!(result.chapters[2].start_ms, 2500);
+
+    let cue = std::fs::read_to_string(result.cue_file.expect("cue sheet")).expect("read cue");
+    assert!(cue.contains("FILE \"Evening_Chanting.wav\" WAVE"));
+    assert!(cue.contains("TRACK 03 AUDIO\n    TITLE \"Tisaraṇa\"\n    INDEX 01 00:02:37"));
+}
@@ -0,0 +1,234 @@
+// Backups of the user data: create, verify, retention, and a restore with
+// its dry-run diff.
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::RwLock;
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::app_data::AppData;
+use simsapa_backend::backup::{self, RestorePlan};
+use simsapa_backend::db::{DatabaseHandle, DbManager, APPDATA_MIGRATIONS, DICTIONARIES_MIGRATIONS};
+use simsapa_backend::reading_plans::parse_plan_json;
+
+/// An `AppData` on empty databases in `dir`, with the Simsapa folder there too.
+fn app_data(dir: &Path) -> AppData {
+    unsafe { std::env::set_var("SIMSAPA_DIR", dir.join("simsapa")) };
+    let url = |db: &str| dir.join(format!("{}.sqlite3", db)).to_string_lossy().to_string();
+
+    let mut db_conn = SqliteConnection::establish(&url("appdata")).expect("appdata");
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).expect("appdata migrations");
+    let mut db_conn = SqliteConnection::establish(&url("dictionaries")).expect("dictionaries");
+    db_conn.run_pending_migrations(DICTIONARIES_MIGRATIONS).expect("dictionaries migrations");
+
+    let dbm = DbManager {
+        appdata: DatabaseHandle::new(&url("appdata")).expect("appdata handle"),
+        dictionaries: DatabaseHandle::new(&url("dictionaries")).expect("dictionaries handle"),
+        dpd: DatabaseHandle::new(&url("dpd")).expect("dpd handle"),
+    };
+    dbm.appdata.get_conn().expect("conn")
+        .batch_execute(r#"INSERT INTO app_settings (key, value) VALUES ('app_settings', '{"sutta_font_size": 26}');"#)
+        .expect("settings");
+    let app_settings_cache = RwLock::new(dbm.appdata.get_app_settings());
+
+    AppData { dbm, app_settings_cache, api_url: String::new() }
+}
+
+fn exec(app_data: &AppData, sql: &str) {
+    app_data.dbm.appdata.get_conn().expect("conn").batch_execute(sql).expect("sql");
+}
+
+fn exec_dict(app_data: &AppData, sql: &str) {
+    app_data.dbm.dictionaries.get_conn().expect("conn").batch_execute(sql).expect("sql");
+}
+
+#[derive(QueryableByName)]
+struct Count {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    n: i64,
+}
+
+fn count(app_data: &AppData, table: &str) -> i64 {
+    let sql = format!("SELECT COUNT(*) AS n FROM {}", table);
+    let handle = if table.starts_with("dict") { &app_data.dbm.dictionaries } else { &app_data.dbm.appdata };
+    handle.do_read(|db_conn| diesel::sql_query(&sql).get_result::<Count>(db_conn)).expect("count").n
+}
+
+fn to_add(plan: &RestorePlan, category: &str) -> usize {
+    plan.categories.iter().find(|c| c.category == category).expect(category).to_add
+}
+
+fn add_user_data(app_data: &AppData) {
+    exec(app_data, "
+        INSERT INTO bookmark_folders (name, sort_order) VALUES ('Reading', 0);
+        INSERT INTO bookmark_folders (name, sort_order, is_last_session) VALUES ('Last session', 1, 1);
+        INSERT INTO bookmark_items (folder_id, item_uid, table_name, tab_group, sort_order)
+            VALUES (1, 'mn1/pli/ms', 'suttas', '', 0), (1, 'sn56.11/pli/ms', 'suttas', '', 1), (2, 'dn1/pli/ms', 'suttas', '', 0);
+        INSERT INTO chanting_collections (uid, title) VALUES ('my-coll', 'Morning');
+        INSERT INTO chanting_chants (uid, collection_uid, title) VALUES ('my-chant', 'my-coll', 'Refuges');
+        INSERT INTO chanting_sections (uid, chant_uid, title, content_pali) VALUES ('my-sec', 'my-chant', 'One', 'Buddhaṃ saraṇaṃ gacchāmi');
+        INSERT INTO gloss_prompts_history (item_type, data_json, created_at) VALUES ('gloss', '{\"text\": \"a\"}', '2026-01-01 10:00:00');
+        INSERT INTO reading_state (item_uid, table_name, scroll_position, progress, time_spent_secs, is_finished)
+            VALUES ('mn1/pli/ms', 'suttas', 0.5, 0.5, 120, 0), ('mn2/pli/ms', 'suttas', 1.0, 1.0, 300, 1);
+        INSERT INTO saved_searches (name, query_text, search_area, search_mode, params_json, last_hits_count)
+            VALUES ('Mindfulness', 'satipatthana', 'Suttas', 'Fulltext Match', '{}', 1);
+        INSERT INTO saved_search_hits (saved_search_id, table_name, uid, first_seen_at)
+            VALUES (1, 'suttas', 'mn10/pli/ms', '2026-01-01 10:00:00');
+        INSERT INTO search_history (query_text, search_area, search_mode, params_json, hits_count, search_count, opened_uid, last_searched_at)
+            VALUES ('sati', 'Suttas', 'Fulltext Match', '{}', 12, 3, 'mn10/pli/ms', '2026-01-01 10:00:00'),
+                   ('dhamma', 'Dictionary', 'Fulltext Match', '{}', 4, 1, NULL, '2026-01-02 10:00:00');
+    ");
+    let plan = parse_plan_json(r#"{"uid": "my-plan", "title": "Majjhima", "schedule": "sequential",
+        "items": [{"item_uid": "mn1/pli/ms"}, {"item_uid": "mn2/pli/ms"}]}"#).expect("plan");
+    app_data.dbm.appdata.save_reading_plan(&plan).expect("save plan");
+    app_data.dbm.appdata.enroll_in_reading_plan("my-plan", chrono::NaiveDate::from_ymd_opt(2026, 10, 1).unwrap()).expect("enroll");
+    app_data.dbm.appdata.set_reading_plan_item_completed("my-plan", 0, true).expect("complete");
+    exec_dict(app_data, "
+        INSERT INTO dictionaries (id, label, title, dict_type, is_user_imported) VALUES (1, 'mydict', 'My Dictionary', 'stardict', 1);
+        INSERT INTO dict_words (dictionary_id, dict_label, uid, word, word_ascii) VALUES (1, 'mydict', 'dhamma/mydict', 'dhamma', 'dhamma');
+    ");
+}
+
+#[test]
+fn test_backup_and_restore() {
+    let dir = tempfile::tempdir().unwrap();
+    let app_data = app_data(dir.path());
+    add_user_data(&app_data);
+    let backups = dir.path().join("backups");
+
+    let created = backup::create_backup(&app_data, &backups).expect("create backup");
+    let manifest = backup::verify_backup(&created.path).expect("verify backup");
+    assert_eq!(manifest.format, backup::BACKUP_FORMAT);
+    assert_eq!(manifest.counts["bookmark_folders"], 1);
+    assert_eq!(manifest.counts["bookmark_items"], 2);
+    assert_eq!(manifest.counts["chanting_sections"], 1);
+    assert_eq!(manifest.counts["dictionaries"], 1);
+    assert_eq!(manifest.counts["history"], 1);
+    assert_eq!(manifest.counts["reading_state"], 2);
+    assert_eq!(manifest.counts["reading_plans"], 1);
+    assert_eq!(manifest.counts["saved_searches"], 1);
+    assert_eq!(manifest.counts["search_history"], 2);
+    assert_eq!(backup::list_backups(&backups).unwrap().len(), 1);
+
+    // Nothing is missing right after the backup.
+    let plan = backup::restore_backup(&app_data, &created.path, true).expect("dry run");
+    assert!(plan.categories.iter().all(|c| c.to_add == 0), "{:?}", plan.categories);
+    assert!(plan.settings_changed.is_empty(), "{:?}", plan.settings_changed);
+
+    exec(&app_data, "
+        DELETE FROM bookmark_items WHERE item_uid = 'sn56.11/pli/ms';
+        DELETE FROM chanting_sections; DELETE FROM chanting_chants; DELETE FROM chanting_collections;
+        DELETE FROM gloss_prompts_history;
+        DELETE FROM reading_state WHERE item_uid = 'mn2/pli/ms';
+        DELETE FROM saved_search_hits; DELETE FROM saved_searches;
+        DELETE FROM search_history WHERE query_text = 'sati';
+    ");
+    exec_dict(&app_data, "DELETE FROM dict_words; DELETE FROM dictionaries;");
+    app_data.dbm.appdata.delete_reading_plan("my-plan").expect("delete plan");
+    app_data.set_sync_folder(Some("/tmp/sync".to_string()));
+
+    // The dry run reports the difference and changes nothing.
+    let plan = backup::restore_backup(&app_data, &created.path, true).expect("dry run");
+    assert!(!plan.applied);
+    assert_eq!(to_add(&plan, "bookmark_folders"), 0);
+    assert_eq!(to_add(&plan, "bookmark_items"), 1);
+    assert_eq!(to_add(&plan, "chanting_collections"), 1);
+    assert_eq!(to_add(&plan, "chanting_sections"), 1);
+    assert_eq!(to_add(&plan, "dictionaries"), 1);
+    assert_eq!(to_add(&plan, "history"), 1);
+    assert_eq!(to_add(&plan, "reading_state"), 1);
+    assert_eq!(to_add(&plan, "reading_plans"), 1);
+    assert_eq!(to_add(&plan, "saved_searches"), 1);
+    assert_eq!(to_add(&plan, "search_history"), 1);
+    assert_eq!(plan.settings_changed, vec!["sync_folder".to_string()]);
+    assert_eq!(count(&app_data, "bookmark_items"), 2);
+    assert_eq!(count(&app_data, "chanting_sections"), 0);
+
+    let plan = backup::restore_backup(&app_data, &created.path, false).expect("restore");
+    assert!(plan.applied);
+    assert_eq!(count(&app_data, "bookmark_folders"), 2);
+    assert_eq!(count(&app_data, "bookmark_items"), 3);
+    assert_eq!(count(&app_data, "chanting_sections"), 1);
+    assert_eq!(count(&app_data, "gloss_prompts_history"), 1);
+    assert_eq!(count(&app_data, "reading_state"), 2);
+    let enrollment = app_data.dbm.appdata.get_reading_plan_enrollment_json("my-plan").expect("enrollment");
+    assert_eq!(enrollment.map(|e| e.completed_items), Some(vec![0]));
+    assert_eq!(count(&app_data, "saved_searches"), 1);
+    assert_eq!(count(&app_data, "saved_search_hits"), 1);
+    assert_eq!(count(&app_data, "search_history"), 2);
+    let history = app_data.dbm.appdata.get_search_history(10).expect("search history");
+    let sati = history.iter().find(|h| h.query_text == "sati").expect("sati");
+    assert_eq!((sati.search_count, sati.opened_uid.as_deref()), (3, Some("mn10/pli/ms")));
+    assert_eq!(count(&app_data, "dictionaries"), 1);
+    assert_eq!(count(&app_data, "dict_words"), 1);
+    assert_eq!(app_data.get_sync_folder(), None);
+
+    // Restoring again adds nothing.
+    backup::restore_backup(&app_data, &created.path, false).expect("restore again");
+    assert_eq!(count(&app_data, "bookmark_items"), 3);
+    assert_eq!(count(&app_data, "gloss_prompts_history"), 1);
+    assert_eq!(count(&app_data, "reading_plans"), 1);
+    assert_eq!(count(&app_data, "saved_searches"), 1);
+    assert_eq!(count(&app_data, "search_history"), 2);
+    assert_eq!(count(&app_data, "dict_words"), 1);
+    let plan = backup::restore_backup(&app_data, &created.path, true).expect("dry run");
+    assert!(plan.categories.iter().all(|c| c.to_add == 0), "{:?}", plan.categories);
+}
+
+#[test]
+fn test_verify_rejects_damaged_backup() {
+    let dir = tempfile::tempdir().unwrap();
+    let app_data = app_data(dir.path());
+    add_user_data(&app_data);
+    let created = backup::create_backup(&app_data, &dir.path().join("backups")).expect("create backup");
+
+    // Copy the backup without the bookmarks file.
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&created.path).unwrap()).unwrap();
+    let damaged = dir.path().join("damaged.zip");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&damaged).unwrap());
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).unwrap();
+        if entry.name() == "appdata-bookmarks.sqlite3" {
+            continue;
+        }
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).unwrap();
+        writer.start_file(entry.name(), zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(&data).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let err = backup::verify_backup(&damaged).expect_err("missing file");
+    assert!(format!("{:#}", err).contains("appdata-bookmarks.sqlite3"), "{:#}", err);
+    assert!(backup::restore_backup(&app_data, &damaged, true).is_err());
+
+    std::fs::write(dir.path().join("junk.zip"), b"not a zip").unwrap();
+    assert!(backup::verify_backup(&dir.path().join("junk.zip")).is_err());
+}
+
+#[test]
+fn test_prune_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let app_data = app_data(dir.path());
+    let backups = dir.path().join("backups");
+
+    for _ in 0..4 {
+        backup::create_backup(&app_data, &backups).expect("create backup");
+    }
+    std::fs::write(backups.join("notes.txt"), "kept").unwrap();
+    let all = backup::list_backups(&backups).unwrap();
+    assert_eq!(all.len(), 4);
+
+    let removed = backup::prune_backups(&backups, 2).unwrap();
+    assert_eq!(removed.len(), 2);
+    let kept: Vec<_> = backup::list_backups(&backups).unwrap().into_iter().map(|b| b.path).collect();
+    assert_eq!(kept, vec![all[0].path.clone(), all[1].path.clone()]);
+    assert!(backups.join("notes.txt").exists());
+
+    // At least one backup is kept.
+    backup::prune_backups(&backups, 0).unwrap();
+    assert_eq!(backup::list_backups(&backups).unwrap().len(), 1);
+}
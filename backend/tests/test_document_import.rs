@@ -0,0 +1,225 @@
+// Markdown, DOCX, ODT and FB2 imports into a fresh appdata database.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::db::appdata_models::{BookSpineItem, NavPointJson};
+use simsapa_backend::db::appdata_schema::{book_resources, book_spine_items, books};
+use simsapa_backend::db::APPDATA_MIGRATIONS;
+use simsapa_backend::docx_import::import_docx_to_db;
+use simsapa_backend::document_metadata::extract_document_metadata;
+use simsapa_backend::fb2_import::import_fb2_to_db;
+use simsapa_backend::markdown_import::import_markdown_to_db;
+use simsapa_backend::odt_import::import_odt_to_db;
+
+fn appdata_conn(dir: &Path) -> SqliteConnection {
+    let mut db_conn = SqliteConnection::establish(&dir.join("appdata.sqlite3").to_string_lossy()).unwrap();
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).unwrap();
+    db_conn
+}
+
+fn spine_items(db_conn: &mut SqliteConnection, book_uid: &str) -> Vec<BookSpineItem> {
+    book_spine_items::table
+        .filter(book_spine_items::book_uid.eq(book_uid))
+        .order(book_spine_items::spine_index)
+        .select(BookSpineItem::as_select())
+        .load(db_conn)
+        .unwrap()
+}
+
+fn book_toc(db_conn: &mut SqliteConnection, book_uid: &str) -> Vec<NavPointJson> {
+    let toc_json: Option<String> = books::table
+        .filter(books::uid.eq(book_uid))
+        .select(books::toc_json)
+        .first(db_conn)
+        .unwrap();
+    serde_json::from_str(&toc_json.expect("toc_json")).unwrap()
+}
+
+fn resource_paths(db_conn: &mut SqliteConnection, book_uid: &str) -> Vec<String> {
+    book_resources::table
+        .filter(book_resources::book_uid.eq(book_uid))
+        .select(book_resources::resource_path)
+        .order(book_resources::resource_path)
+        .load(db_conn)
+        .unwrap()
+}
+
+fn write_zip(path: &Path, files: &[(&str, &str)]) {
+    let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+    for (name, data) in files {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(data.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn test_import_markdown_folder() {
+    let tmp = tempfile::tempdir().unwrap();
+    let folder = tmp.path().join("Retreat Talks");
+    fs::create_dir_all(folder.join("img")).unwrap();
+    fs::write(folder.join("img/tree.png"), b"png").unwrap();
+    fs::write(folder.join("01 Opening.md"), "---\ntitle: Retreat Talks\nauthor: Ajahn Test\n---\n\nWelcome to the **retreat**.\n\n![A tree](img/tree.png)\n").unwrap();
+    fs::write(folder.join("02 Day.md"), "# Day Two\n\nOn mettā.\n\n## Questions\n\nQ and A.\n").unwrap();
+    fs::write(folder.join("notes.txt"), "not a chapter").unwrap();
+
+    let mut db_conn = appdata_conn(tmp.path());
+    import_markdown_to_db(&mut db_conn, &folder, "retreat", None, None, None, true).unwrap();
+
+    let (title, author, document_type): (Option<String>, Option<String>, String) = books::table
+        .filter(books::uid.eq("retreat"))
+        .select((books::title, books::author, books::document_type))
+        .first(&mut db_conn)
+        .unwrap();
+    assert_eq!(title.as_deref(), Some("Retreat Talks"));
+    assert_eq!(author.as_deref(), Some("Ajahn Test"));
+    assert_eq!(document_type, "markdown");
+
+    let items = spine_items(&mut db_conn, "retreat");
+    let titles: Vec<_> = items.iter().map(|i| i.title.clone().unwrap_or_default()).collect();
+    assert_eq!(titles, vec!["01 Opening", "Day Two", "Questions"]);
+    let opening = items[0].content_html.as_deref().unwrap();
+    assert!(opening.contains(r#"src="/book_resources/retreat/img/tree.png""#), "{}", opening);
+    // content_plain is normalized to lowercase
+    let day_two = items[1].content_plain.as_deref().unwrap();
+    assert!(day_two.contains("on mettā"), "{}", day_two);
+
+    let toc = book_toc(&mut db_conn, "retreat");
+    assert_eq!(toc.len(), 2);
+    assert_eq!(toc[1].label, "Day Two");
+    assert_eq!(toc[1].children[0].label, "Questions");
+    assert_eq!(toc[1].children[0].content, items[2].resource_path);
+
+    assert_eq!(resource_paths(&mut db_conn, "retreat"), vec!["img/tree.png"]);
+}
+
+#[test]
+fn test_import_docx() {
+    let tmp = tempfile::tempdir().unwrap();
+    let docx_path = tmp.path().join("talk.docx");
+    write_zip(&docx_path, &[
+        ("word/document.xml", r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><w:body>
+<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Right Effort</w:t></w:r></w:p>
+<w:p><w:r><w:t>The four right efforts.</w:t></w:r></w:p>
+<w:p><w:r><w:drawing><a:blip r:embed="rId7"/></w:drawing></w:r></w:p>
+<w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Restraint</w:t></w:r></w:p>
+<w:p><w:r><w:t>Guarding the senses.</w:t></w:r></w:p>
+</w:body></w:document>"#),
+        ("word/styles.xml", r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:pPr><w:outlineLvl w:val="1"/></w:pPr></w:style>
+</w:styles>"#),
+        ("word/_rels/document.xml.rels", r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId7" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/image1.png"/>
+</Relationships>"#),
+        ("word/media/image1.png", "png"),
+        ("docProps/core.xml", r#"<?xml version="1.0" encoding="UTF-8"?>
+<cp:coreProperties xmlns:cp="cp" xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>Right Effort</dc:title><dc:creator>Ajahn Test</dc:creator><dc:language>en-GB</dc:language></cp:coreProperties>"#),
+    ]);
+
+    let metadata = extract_document_metadata(&docx_path).unwrap();
+    assert_eq!(metadata.title, "Right Effort");
+    assert_eq!(metadata.author, "Ajahn Test");
+
+    let mut db_conn = appdata_conn(tmp.path());
+    import_docx_to_db(&mut db_conn, &docx_path, "effort", None, Some("Custom Author"), None, true).unwrap();
+
+    let author: Option<String> = books::table
+        .filter(books::uid.eq("effort"))
+        .select(books::author)
+        .first(&mut db_conn)
+        .unwrap();
+    assert_eq!(author.as_deref(), Some("Custom Author"));
+
+    let items = spine_items(&mut db_conn, "effort");
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].language.as_deref(), Some("en-GB"));
+    let first = items[0].content_html.as_deref().unwrap();
+    assert!(first.contains(r#"<img src="/book_resources/effort/media/image1.png">"#), "{}", first);
+    // content_plain is normalized to lowercase
+    let second = items[1].content_plain.as_deref().unwrap();
+    assert!(second.contains("guarding the senses"), "{}", second);
+
+    let toc = book_toc(&mut db_conn, "effort");
+    assert_eq!(toc.len(), 1);
+    assert_eq!(toc[0].children[0].label, "Restraint");
+    assert_eq!(resource_paths(&mut db_conn, "effort"), vec!["media/image1.png"]);
+}
+
+#[test]
+fn test_import_odt() {
+    let tmp = tempfile::tempdir().unwrap();
+    let odt_path = tmp.path().join("talk.odt");
+    write_zip(&odt_path, &[
+        ("mimetype", "application/vnd.oasis.opendocument.text"),
+        ("content.xml", r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:draw="urn:oasis:names:tc:opendocument:xmlns:drawing:1.0" xmlns:xlink="http://www.w3.org/1999/xlink"><office:body><office:text>
+<text:h text:outline-level="1">Sīla</text:h>
+<text:p>Virtue first.</text:p>
+<text:h text:outline-level="1">Samādhi</text:h>
+<text:p>Then <text:a xlink:href="https://example.org">stillness</text:a>.<draw:frame><draw:image xlink:href="Pictures/lotus.jpg"/></draw:frame></text:p>
+</office:text></office:body></office:document-content>"#),
+        ("meta.xml", r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-meta xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0" xmlns:dc="http://purl.org/dc/elements/1.1/"><office:meta><dc:title>The Training</dc:title><meta:initial-creator>Ajahn Test</meta:initial-creator><dc:creator>Editor</dc:creator></office:meta></office:document-meta>"#),
+        ("Pictures/lotus.jpg", "jpg"),
+    ]);
+
+    let mut db_conn = appdata_conn(tmp.path());
+    import_odt_to_db(&mut db_conn, &odt_path, "training", None, None, None, true).unwrap();
+
+    let (title, author): (Option<String>, Option<String>) = books::table
+        .filter(books::uid.eq("training"))
+        .select((books::title, books::author))
+        .first(&mut db_conn)
+        .unwrap();
+    assert_eq!(title.as_deref(), Some("The Training"));
+    assert_eq!(author.as_deref(), Some("Ajahn Test"));
+
+    let items = spine_items(&mut db_conn, "training");
+    let titles: Vec<_> = items.iter().map(|i| i.title.clone().unwrap_or_default()).collect();
+    assert_eq!(titles, vec!["Sīla", "Samādhi"]);
+    let second = items[1].content_html.as_deref().unwrap();
+    assert!(second.contains(r#"<a href="https://example.org">stillness</a>"#), "{}", second);
+    assert!(second.contains(r#"<img src="/book_resources/training/Pictures/lotus.jpg">"#), "{}", second);
+
+    let toc = book_toc(&mut db_conn, "training");
+    assert_eq!(toc.iter().map(|n| n.label.as_str()).collect::<Vec<_>>(), vec!["Sīla", "Samādhi"]);
+    assert_eq!(resource_paths(&mut db_conn, "training"), vec!["Pictures/lotus.jpg"]);
+}
+
+#[test]
+fn test_import_fb2() {
+    let tmp = tempfile::tempdir().unwrap();
+    let fb2_path = tmp.path().join("book.fb2");
+    fs::write(&fb2_path, r##"<?xml version="1.0" encoding="utf-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0" xmlns:l="http://www.w3.org/1999/xlink">
+<description><title-info><author><first-name>Ajahn</first-name><last-name>Test</last-name></author><book-title>Forest Talks</book-title><lang>en</lang></title-info></description>
+<body>
+<section><title><p>The Forest</p></title><p>Trees.</p><image l:href="#tree.png"/></section>
+<section><title><p>The Monastery</p></title><p>Walls.</p></section>
+</body>
+<binary id="tree.png" content-type="image/png">cG5n</binary>
+</FictionBook>"##).unwrap();
+
+    let metadata = extract_document_metadata(&fb2_path).unwrap();
+    assert_eq!(metadata.title, "Forest Talks");
+
+    let mut db_conn = appdata_conn(tmp.path());
+    import_fb2_to_db(&mut db_conn, &fb2_path, "forest", None, None, None, true).unwrap();
+
+    let items = spine_items(&mut db_conn, "forest");
+    let titles: Vec<_> = items.iter().map(|i| i.title.clone().unwrap_or_default()).collect();
+    assert_eq!(titles, vec!["The Forest", "The Monastery"]);
+    let first = items[0].content_html.as_deref().unwrap();
+    assert!(first.contains(r#"<img src="/book_resources/forest/images/tree.png">"#), "{}", first);
+    assert_eq!(book_toc(&mut db_conn, "forest").len(), 2);
+    assert_eq!(resource_paths(&mut db_conn, "forest"), vec!["images/tree.png"]);
+}
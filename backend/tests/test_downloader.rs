@@ -0,0 +1,226 @@
+// Downloads against a local HTTP server which can drop the connection part
+// way, ignore Range requests, or act as a proxy.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use simsapa_backend::downloader::{self, DownloadEvent, DownloadSettings, Downloader};
+
+#[derive(Default)]
+struct ServerOptions {
+    /// The first this many responses stop after `drop_after` bytes of the body.
+    drop_count: usize,
+    drop_after: usize,
+    /// Answer Range requests with the whole file.
+    ignore_range: bool,
+}
+
+struct TestServer {
+    base_url: String,
+    /// The request line and the Range and If-Range headers of each request.
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+fn body(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn serve(stream: TcpStream, file: &[u8], options: &ServerOptions, served: &AtomicUsize, requests: &Mutex<Vec<String>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut range_start = None;
+    let mut log = request_line.trim().to_string();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap() == 0 || line.trim().is_empty() {
+            break;
+        }
+        let lower = line.to_lowercase();
+        if let Some(v) = lower.strip_prefix("range: bytes=") {
+            range_start = v.trim().trim_end_matches('-').parse::<usize>().ok();
+            log.push_str(&format!(" | {}", lower.trim()));
+        } else if lower.starts_with("if-range:") {
+            log.push_str(&format!(" | {}", lower.trim()));
+        }
+    }
+    requests.lock().unwrap().push(log);
+
+    let mut stream = stream;
+    if !request_line.contains("/releases/download/") {
+        let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        return;
+    }
+
+    let start = if options.ignore_range { 0 } else { range_start.unwrap_or(0) };
+    let part = &file[start..];
+    let head = if start > 0 {
+        format!(
+            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+            part.len(), start, file.len() - 1, file.len()
+        )
+    } else {
+        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"v1\"\r\nAccept-Ranges: bytes\r\nConnection: close\r\n\r\n", part.len())
+    };
+    stream.write_all(head.as_bytes()).unwrap();
+
+    if served.fetch_add(1, Ordering::SeqCst) < options.drop_count {
+        let _ = stream.write_all(&part[..options.drop_after.min(part.len())]);
+        let _ = stream.flush();
+        let _ = stream.shutdown(Shutdown::Both);
+        return;
+    }
+    let _ = stream.write_all(part);
+}
+
+fn start_server(file: Vec<u8>, options: ServerOptions) -> TestServer {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let log = requests.clone();
+    thread::spawn(move || {
+        let served = AtomicUsize::new(0);
+        for stream in listener.incoming() {
+            match stream {
+                Ok(s) => serve(s, &file, &options, &served, &log),
+                Err(_) => break,
+            }
+        }
+    });
+    TestServer { base_url, requests }
+}
+
+const PATH: &str = "/simsapa/assets/releases/download/v0.1.0/dpd.tar.bz2";
+
+fn quick(settings: DownloadSettings, attempts: u32) -> Downloader {
+    Downloader::new(settings).unwrap().with_retries(attempts, Duration::from_millis(10))
+}
+
+#[test]
+fn test_interrupted_download_is_resumed() {
+    let file = body(300_000);
+    let server = start_server(file.clone(), ServerOptions { drop_count: 1, drop_after: 100_000, ..Default::default() });
+    let tmp = tempfile::tempdir().unwrap();
+    let dest = tmp.path().join("dpd.tar.bz2");
+
+    let mut events = Vec::new();
+    let path = quick(DownloadSettings::default(), 3)
+        .download(&format!("{}{}", server.base_url, PATH), &dest, &mut |e| events.push(e))
+        .unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), file);
+    assert!(!downloader::partial_path(&dest).exists());
+    let requests = server.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2, "{:?}", requests);
+    assert!(requests[1].contains("range: bytes=100000-"), "{:?}", requests);
+    assert!(requests[1].contains("if-range: \"v1\""), "{:?}", requests);
+    assert!(events.iter().any(|e| matches!(e, DownloadEvent::Resuming { offset: 100_000, .. })));
+    assert!(events.contains(&DownloadEvent::Progress { downloaded: 300_000, total: Some(300_000) }));
+}
+
+#[test]
+fn test_partial_download_is_kept_for_a_later_call() {
+    let file = body(200_000);
+    // Always drops, and starts over, so the call gives up.
+    let flaky = start_server(file.clone(), ServerOptions { drop_count: usize::MAX, drop_after: 50_000, ignore_range: true });
+    let tmp = tempfile::tempdir().unwrap();
+    let dest = tmp.path().join("dpd.tar.bz2");
+
+    assert!(quick(DownloadSettings::default(), 1).download(&format!("{}{}", flaky.base_url, PATH), &dest, &mut |_| {}).is_err());
+    let part = downloader::partial_path(&dest);
+    assert_eq!(fs::metadata(&part).unwrap().len(), 50_000);
+    assert!(downloader::is_partial_download(&part));
+
+    // Continued from another server, without the validators of the first one.
+    let server = start_server(file.clone(), ServerOptions::default());
+    quick(DownloadSettings::default(), 1).download(&format!("{}{}", server.base_url, PATH), &dest, &mut |_| {}).unwrap();
+    assert_eq!(fs::read(&dest).unwrap(), file);
+    let requests = server.requests.lock().unwrap();
+    assert!(requests[0].contains("range: bytes=50000-"), "{:?}", requests);
+    assert!(!requests[0].contains("if-range"), "{:?}", requests);
+}
+
+#[test]
+fn test_server_without_range_support_restarts() {
+    let file = body(100_000);
+    let server = start_server(file.clone(), ServerOptions { drop_count: 1, drop_after: 30_000, ignore_range: true });
+    let tmp = tempfile::tempdir().unwrap();
+    let dest = tmp.path().join("dpd.tar.bz2");
+
+    quick(DownloadSettings::default(), 3)
+        .download(&format!("{}{}", server.base_url, PATH), &dest, &mut |_| {})
+        .unwrap();
+    assert_eq!(fs::read(&dest).unwrap(), file);
+}
+
+#[test]
+fn test_mirrors_fail_over() {
+    let file = body(50_000);
+    let mirror = start_server(file.clone(), ServerOptions::default());
+    // Nothing listens on a port of a dropped listener.
+    let closed = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let settings = DownloadSettings {
+        mirrors: vec![format!("http://{}/", closed), mirror.base_url.clone()],
+        ..Default::default()
+    };
+    let tmp = tempfile::tempdir().unwrap();
+    let dest = tmp.path().join("dpd.tar.bz2");
+
+    let mut connecting = Vec::new();
+    quick(settings, 1)
+        .download(&format!("http://simsapa.invalid{}", PATH), &dest, &mut |e| {
+            if let DownloadEvent::Connecting { url, .. } = e {
+                connecting.push(url);
+            }
+        })
+        .unwrap();
+    assert_eq!(fs::read(&dest).unwrap(), file);
+    assert_eq!(connecting, vec![format!("http://{}{}", closed, PATH), format!("{}{}", mirror.base_url, PATH)]);
+}
+
+#[test]
+fn test_download_through_proxy() {
+    let file = body(20_000);
+    let proxy = start_server(file.clone(), ServerOptions::default());
+    let settings = DownloadSettings { proxy: Some(proxy.base_url.clone()), ..Default::default() };
+    let tmp = tempfile::tempdir().unwrap();
+    let dest = tmp.path().join("dpd.tar.bz2");
+
+    quick(settings, 1)
+        .download(&format!("http://simsapa.invalid{}", PATH), &dest, &mut |_| {})
+        .unwrap();
+    assert_eq!(fs::read(&dest).unwrap(), file);
+    // A proxy receives the absolute URL.
+    assert!(proxy.requests.lock().unwrap()[0].starts_with(&format!("GET http://simsapa.invalid{}", PATH)));
+}
+
+#[test]
+fn test_bandwidth_limit() {
+    let file = body(96 * 1024);
+    let server = start_server(file, ServerOptions::default());
+    let settings = DownloadSettings { bandwidth_limit_kib: 192, ..Default::default() };
+    let tmp = tempfile::tempdir().unwrap();
+
+    let started = Instant::now();
+    quick(settings, 1)
+        .download(&format!("{}{}", server.base_url, PATH), &tmp.path().join("dpd.tar.bz2"), &mut |_| {})
+        .unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(450), "{:?}", started.elapsed());
+}
+
+#[test]
+fn test_missing_file_fails() {
+    let server = start_server(body(10), ServerOptions::default());
+    let tmp = tempfile::tempdir().unwrap();
+    let dest = tmp.path().join("dpd.tar.bz2");
+    let err = quick(DownloadSettings::default(), 2)
+        .download(&format!("{}/missing.tar.bz2", server.base_url), &dest, &mut |_| {})
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("after 2 attempts"), "{:#}", err);
+    assert!(!dest.exists());
+}
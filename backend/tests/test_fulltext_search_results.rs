@@ -50,6 +50,7 @@ fn generate_fulltext_fixture() {
         include_bold_definitions: true,
         dict_source_uids: None,
         show_all_snippets: false,
+        fuzzy_distance: 0,
     };
 
     let query = r#""so ce" evaṁ vadeyya"#;
@@ -104,6 +105,7 @@ fn test_fulltext_search_so_ce_evam_vadeyya() {
         include_bold_definitions: true,
         dict_source_uids: None,
         show_all_snippets: false,
+        fuzzy_distance: 0,
     };
 
     let query = r#""so ce" evaṁ vadeyya"#;
@@ -1,7 +1,7 @@
 // Smoke test for the row-count helpers backing GET /health (Task 5.2).
-// The /health handler itself lives in the bridges crate (cxx-qt, not
-// unit-testable), so this pins that the count queries run against the real
-// schema and return the populated shipped DBs' non-zero counts.
+// The /health handler itself is covered in test_api_server.rs (api-server
+// feature); this pins that the count queries run against the real schema and
+// return the populated shipped DBs' non-zero counts.
 
 mod helpers;
 use helpers as h;
@@ -0,0 +1,172 @@
+// Integrity checks of the installed databases and indexes, and their repairs:
+// rebuilding a missing index, and extracting a damaged database again from
+// the release archive.
+
+use std::fs;
+use std::path::Path;
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::db::{APPDATA_MIGRATIONS, DICTIONARIES_MIGRATIONS};
+use simsapa_backend::integrity::{self, ArchiveSource, CheckStatus, IndexKind, IntegrityReport, Repair, VerifyOptions};
+use simsapa_backend::update_checker::get_app_version;
+
+fn db(dir: &Path, file: &str) -> SqliteConnection {
+    SqliteConnection::establish(&dir.join(file).to_string_lossy()).expect(file)
+}
+
+/// Databases in `dir` at a db_version compatible with the app, without indexes.
+fn assets(dir: &Path) {
+    fs::create_dir_all(dir).unwrap();
+    let app_version = get_app_version();
+    let mut parts = app_version.split('.');
+    let db_version = format!("{}.{}.0", parts.next().unwrap(), parts.next().unwrap());
+
+    let mut conn = db(dir, "appdata.sqlite3");
+    conn.run_pending_migrations(APPDATA_MIGRATIONS).unwrap();
+    conn.batch_execute(&format!("
+        INSERT INTO app_settings (key, value) VALUES ('db_version', '{}');
+        INSERT INTO suttas (uid, sutta_ref, nikaya, language, title, content_plain) VALUES
+            ('mn1/pli/ms', 'MN 1', 'mn', 'pli', 'Mūlapariyāya', 'evaṃ me sutaṃ'),
+            ('mn2/pli/ms', 'MN 2', 'mn', 'pli', 'Sabbāsava', 'evaṃ me sutaṃ ekaṃ samayaṃ'),
+            ('mn3/pli/ms', 'MN 3', 'mn', 'pli', 'Dhammadāyāda', '');
+    ", db_version)).unwrap();
+
+    let mut conn = db(dir, "dictionaries.sqlite3");
+    conn.run_pending_migrations(DICTIONARIES_MIGRATIONS).unwrap();
+    conn.batch_execute("
+        INSERT INTO dictionaries (id, label, title, dict_type) VALUES (1, 'pts', 'PTS', 'stardict');
+        INSERT INTO dict_words (dictionary_id, dict_label, uid, word, word_ascii, language, definition_plain) VALUES
+            (1, 'pts', 'dhamma/pts', 'dhamma', 'dhamma', 'en', 'nature; truth');
+    ").unwrap();
+
+    db(dir, "dpd.sqlite3").batch_execute("
+        CREATE TABLE dpd_headwords (id INTEGER PRIMARY KEY, lemma_1 TEXT);
+        CREATE TABLE dpd_roots (root TEXT PRIMARY KEY);
+        CREATE TABLE lookup (lookup_key TEXT PRIMARY KEY, headwords TEXT);
+        CREATE TABLE inflection_templates (pattern TEXT PRIMARY KEY);
+        CREATE TABLE db_info (id INTEGER PRIMARY KEY, key TEXT, value TEXT);
+        CREATE TABLE bold_definitions (id INTEGER PRIMARY KEY, bold TEXT, commentary TEXT);
+        INSERT INTO dpd_headwords (lemma_1) VALUES ('dhamma 1');
+    ").unwrap();
+}
+
+fn status(report: &IntegrityReport, target: &str) -> CheckStatus {
+    report.checks.iter().find(|c| c.target == target)
+        .unwrap_or_else(|| panic!("no check for {}: {:?}", target, report.checks))
+        .status
+}
+
+/// A release folder with a `.tar.bz2` of `file` and the release manifest.
+fn release(assets_dir: &Path, release_dir: &Path, file: &str, db_version: &str) {
+    fs::create_dir_all(release_dir).unwrap();
+    let archive = release_dir.join(file.replace(".sqlite3", ".tar.bz2"));
+    let encoder = bzip2::write::BzEncoder::new(fs::File::create(&archive).unwrap(), bzip2::Compression::fast());
+    let mut builder = tar::Builder::new(encoder);
+    builder.append_path_with_name(assets_dir.join(file), file).unwrap();
+    builder.into_inner().unwrap().finish().unwrap();
+    integrity::write_release_manifest(release_dir, db_version).unwrap();
+}
+
+#[test]
+fn test_missing_index_is_rebuilt() {
+    let tmp = tempfile::tempdir().unwrap();
+    let assets_dir = tmp.path().join("app-assets");
+    assets(&assets_dir);
+
+    let report = integrity::verify_installation(&assets_dir, &VerifyOptions::default());
+    assert_eq!(status(&report, "appdata.sqlite3"), CheckStatus::Ok);
+    assert_eq!(status(&report, "dpd.sqlite3 schema"), CheckStatus::Ok);
+    assert_eq!(status(&report, "db_version"), CheckStatus::Ok);
+    assert_eq!(status(&report, "index/suttas/pli"), CheckStatus::Failed);
+    assert_eq!(report.repairs(), vec![
+        Repair::RebuildIndex { index: IndexKind::Suttas, lang: "pli".to_string() },
+        Repair::RebuildIndex { index: IndexKind::DictWords, lang: "en".to_string() },
+    ]);
+
+    let (report, failed) = integrity::repair_installation(&assets_dir, &VerifyOptions::default(), None, tmp.path());
+    assert!(failed.is_empty(), "{:?}", failed);
+    assert!(report.is_ok(), "{:?}", report.checks);
+    let suttas = report.checks.iter().find(|c| c.target == "index/suttas/pli").unwrap();
+    assert_eq!(suttas.message, "2 documents");
+
+    // A sutta added to the database without indexing it.
+    db(&assets_dir, "appdata.sqlite3")
+        .batch_execute("INSERT INTO suttas (uid, sutta_ref, nikaya, language, content_plain) VALUES ('mn4/pli/ms', 'MN 4', 'mn', 'pli', 'bhaya');")
+        .unwrap();
+    let full = VerifyOptions { full: true, archives_dir: None };
+    let report = integrity::verify_installation(&assets_dir, &full);
+    assert_eq!(status(&report, "index/suttas/pli"), CheckStatus::Failed);
+    assert_eq!(status(&report, "index/dict_words/en"), CheckStatus::Ok);
+}
+
+#[test]
+fn test_damaged_database_is_extracted_again() {
+    let tmp = tempfile::tempdir().unwrap();
+    let assets_dir = tmp.path().join("app-assets");
+    let release_dir = tmp.path().join("release");
+    assets(&assets_dir);
+    let db_version = simsapa_backend::asset_delta::read_db_version(&assets_dir).unwrap().unwrap();
+    release(&assets_dir, &release_dir, "dpd.sqlite3", &db_version);
+
+    fs::write(assets_dir.join("dpd.sqlite3"), vec![7u8; 8192]).unwrap();
+    let options = VerifyOptions { full: false, archives_dir: Some(release_dir.clone()) };
+    let report = integrity::verify_installation(&assets_dir, &options);
+    assert_eq!(status(&report, "dpd.sqlite3"), CheckStatus::Failed);
+    assert_eq!(status(&report, "dpd.tar.bz2"), CheckStatus::Ok);
+    assert!(report.repairs().contains(&Repair::ExtractArchive { archive: "dpd.tar.bz2".to_string() }));
+
+    // Without a source of the archives only the indexes are repaired.
+    let (_, failed) = integrity::repair_installation(&assets_dir, &VerifyOptions::default(), None, tmp.path());
+    assert_eq!(failed.len(), 1, "{:?}", failed);
+
+    let source = ArchiveSource::Dir(release_dir.clone());
+    let (report, failed) = integrity::repair_installation(&assets_dir, &VerifyOptions::default(), Some(&source), tmp.path());
+    assert!(failed.is_empty(), "{:?}", failed);
+    assert!(report.is_ok(), "{:?}", report.checks);
+    assert!(assets_dir.join("dpd.sqlite3.damaged").is_file());
+}
+
+#[test]
+fn test_damaged_archive_is_rejected() {
+    let tmp = tempfile::tempdir().unwrap();
+    let assets_dir = tmp.path().join("app-assets");
+    let release_dir = tmp.path().join("release");
+    assets(&assets_dir);
+    release(&assets_dir, &release_dir, "dpd.sqlite3", "0.0.1");
+
+    let archive = release_dir.join("dpd.tar.bz2");
+    let mut bytes = fs::read(&archive).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    fs::write(&archive, &bytes).unwrap();
+
+    let options = VerifyOptions { full: false, archives_dir: Some(release_dir.clone()) };
+    let report = integrity::verify_installation(&assets_dir, &options);
+    assert_eq!(status(&report, "dpd.tar.bz2"), CheckStatus::Failed);
+
+    let manifest = integrity::read_release_manifest(&release_dir.join(integrity::RELEASE_MANIFEST_FILE)).unwrap();
+    assert!(integrity::stage_archive_repair(&archive, &manifest, &assets_dir).is_err());
+    assert!(integrity::verify_archive(&manifest, &archive).is_err());
+}
+
+#[test]
+fn test_pending_repair_is_installed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let assets_dir = tmp.path().join("app-assets");
+    let release_dir = tmp.path().join("release");
+    assets(&assets_dir);
+    let db_version = simsapa_backend::asset_delta::read_db_version(&assets_dir).unwrap().unwrap();
+    release(&assets_dir, &release_dir, "dpd.sqlite3", &db_version);
+    let manifest = integrity::read_release_manifest(&release_dir.join(integrity::RELEASE_MANIFEST_FILE)).unwrap();
+
+    fs::write(assets_dir.join("dpd.sqlite3"), b"").unwrap();
+    integrity::stage_archive_repair(&release_dir.join("dpd.tar.bz2"), &manifest, &assets_dir).unwrap();
+    assert_eq!(integrity::apply_pending_repairs(&assets_dir), vec!["dpd.tar.bz2".to_string()]);
+    assert!(!assets_dir.join(integrity::PENDING_REPAIRS_DIR).exists());
+
+    let report = integrity::verify_installation(&assets_dir, &VerifyOptions::default());
+    assert_eq!(status(&report, "dpd.sqlite3"), CheckStatus::Ok);
+}
@@ -0,0 +1,205 @@
+// OCR imports with stand-in tesseract and pdftoppm scripts, which give a
+// fixed recognition result with the name of the image.
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::db::appdata_models::BookSpineItem;
+use simsapa_backend::db::appdata_schema::{book_resources, book_spine_items, books};
+use simsapa_backend::db::APPDATA_MIGRATIONS;
+use simsapa_backend::image_import::import_images_to_db;
+use simsapa_backend::ocr::{Ocr, OcrEngine, OcrMetadata, PaliSpellingCorrector};
+use simsapa_backend::pdf_import::import_pdf_to_db_with_ocr;
+
+fn write_script(path: &Path, body: &str) {
+    fs::write(path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// The stand-in OCR commands in `dir`.
+fn fake_engine(dir: &Path) -> OcrEngine {
+    let tesseract = dir.join("tesseract");
+    write_script(&tesseract, r#"name=$(basename "$1")
+printf 'level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n'
+printf '5\t1\t1\t1\t1\t1\t0\t0\t10\t10\t95.0\tSankhärä\n'
+printf '5\t1\t1\t1\t1\t2\t0\t0\t10\t10\t80.0\tof\n'
+printf '5\t1\t1\t1\t2\t1\t0\t0\t10\t10\t65.0\t%s\n' "$name""#);
+    let pdftoppm = dir.join("pdftoppm");
+    // The last argument is the output file name without the extension.
+    write_script(&pdftoppm, r#"for last; do :; done
+touch "$last.png""#);
+    OcrEngine::with_commands(&tesseract, &pdftoppm, "eng")
+}
+
+fn appdata_conn(dir: &Path) -> SqliteConnection {
+    let mut db_conn = SqliteConnection::establish(&dir.join("appdata.sqlite3").to_string_lossy()).unwrap();
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).unwrap();
+    db_conn
+}
+
+fn spine_items(db_conn: &mut SqliteConnection, book_uid: &str) -> Vec<BookSpineItem> {
+    book_spine_items::table
+        .filter(book_spine_items::book_uid.eq(book_uid))
+        .order(book_spine_items::spine_index)
+        .select(BookSpineItem::as_select())
+        .load(db_conn)
+        .unwrap()
+}
+
+fn ocr_metadata(db_conn: &mut SqliteConnection, book_uid: &str) -> Option<OcrMetadata> {
+    let metadata_json: Option<String> = books::table
+        .filter(books::uid.eq(book_uid))
+        .select(books::metadata_json)
+        .first(db_conn)
+        .unwrap();
+    OcrMetadata::from_metadata_json(metadata_json.as_deref())
+}
+
+#[test]
+fn test_engine_availability() {
+    let tmp = tempfile::tempdir().unwrap();
+    assert!(fake_engine(tmp.path()).is_available());
+    assert!(!OcrEngine::with_commands(Path::new("/nonexistent/tesseract"), Path::new("pdftoppm"), "").is_available());
+    assert_eq!(OcrEngine::new(" ").languages(), "eng");
+}
+
+#[test]
+fn test_import_image_folder_with_ocr() {
+    let tmp = tempfile::tempdir().unwrap();
+    let folder = tmp.path().join("Visuddhimagga scan");
+    fs::create_dir(&folder).unwrap();
+    for name in ["page-10.png", "page-2.jpg", "page-1.tif", "notes.txt"] {
+        fs::write(folder.join(name), name.as_bytes()).unwrap();
+    }
+
+    let ocr = Ocr::new(fake_engine(tmp.path()), Some(PaliSpellingCorrector::from_words(["saṅkhārā", "dhamma"])));
+    let mut db_conn = appdata_conn(tmp.path());
+    import_images_to_db(&mut db_conn, &folder, "vism-scan", None, None, Some("pli"), true, Some(&ocr)).unwrap();
+
+    let (title, document_type): (Option<String>, String) = books::table
+        .filter(books::uid.eq("vism-scan"))
+        .select((books::title, books::document_type))
+        .first(&mut db_conn)
+        .unwrap();
+    assert_eq!(title.as_deref(), Some("Visuddhimagga scan"));
+    assert_eq!(document_type, "images");
+
+    let items = spine_items(&mut db_conn, "vism-scan");
+    let paths: Vec<&str> = items.iter().map(|i| i.resource_path.as_str()).collect();
+    assert_eq!(paths, vec!["images/page-1.tif", "images/page-2.jpg", "images/page-10.png"]);
+    assert_eq!(items[1].title.as_deref(), Some("p. 2"));
+    // Recognized, with the diacritics repaired
+    let plain = items[2].content_plain.as_deref().unwrap();
+    assert!(plain.contains("saṅkhārā"), "{}", plain);
+    assert!(plain.contains("page10 png"), "{}", plain);
+    let html = items[2].content_html.as_deref().unwrap();
+    assert!(html.contains(r#"src="/book_resources/vism-scan/images/page-10.png""#), "{}", html);
+    assert!(html.contains("Saṅkhārā of"), "{}", html);
+
+    let resources: Vec<(String, Option<String>)> = book_resources::table
+        .filter(book_resources::book_uid.eq("vism-scan"))
+        .select((book_resources::resource_path, book_resources::mime_type))
+        .order(book_resources::resource_path)
+        .load(&mut db_conn)
+        .unwrap();
+    assert_eq!(resources.len(), 3);
+    assert_eq!(resources[0], ("images/page-1.tif".to_string(), Some("image/tiff".to_string())));
+
+    let metadata = ocr_metadata(&mut db_conn, "vism-scan").expect("OCR metadata");
+    assert_eq!(metadata.pages.len(), 3);
+    assert_eq!(metadata.pages[0].page, 1);
+    assert_eq!(metadata.pages[0].words, 3);
+    assert_eq!(metadata.pages[0].corrections, 1);
+    assert_eq!(metadata.pages[0].confidence, Some(80.0));
+}
+
+#[test]
+fn test_import_image_folder_without_images_fails() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut db_conn = appdata_conn(tmp.path());
+    let err = import_images_to_db(&mut db_conn, tmp.path(), "empty", None, None, None, true, None).unwrap_err();
+    assert!(err.to_string().contains("No page images"), "{}", err);
+}
+
+/// A PDF with a text page and a page without text, as a scan would be.
+fn write_half_scanned_pdf(path: &Path) {
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Document, Object, Stream};
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+    let text = Content {
+        operations: vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 24.into()]),
+            Operation::new("Td", vec![100.into(), 600.into()]),
+            Operation::new("Tj", vec![Object::string_literal("A page with a text layer")]),
+            Operation::new("ET", vec![]),
+        ],
+    };
+    let mut page_ids: Vec<Object> = Vec::new();
+    for content in [text.encode().unwrap(), Vec::new()] {
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+        page_ids.push(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        }).into());
+    }
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => page_ids,
+        "Count" => 2,
+        "Resources" => resources_id,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+    }));
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).unwrap();
+}
+
+#[test]
+fn test_pdf_ocr_of_scanned_pages() {
+    let tmp = tempfile::tempdir().unwrap();
+    let pdf_path: PathBuf = tmp.path().join("scan.pdf");
+    write_half_scanned_pdf(&pdf_path);
+
+    let ocr = Ocr::new(fake_engine(tmp.path()), None);
+    let mut db_conn = appdata_conn(tmp.path());
+    import_pdf_to_db_with_ocr(&mut db_conn, &pdf_path, "scan", None, None, None, None, true, Some(&ocr)).unwrap();
+
+    let items = spine_items(&mut db_conn, "scan");
+    assert_eq!(items.len(), 2);
+    let page_1 = items[0].content_plain.as_deref().unwrap();
+    assert!(page_1.contains("text layer"), "{}", page_1);
+    // Only the page without text is recognized
+    let page_2 = items[1].content_plain.as_deref().unwrap();
+    assert!(page_2.contains("page2 png"), "{}", page_2);
+
+    let metadata = ocr_metadata(&mut db_conn, "scan").expect("OCR metadata");
+    assert!(!metadata.spelling_repair);
+    assert_eq!(metadata.pages.len(), 1);
+    assert_eq!(metadata.pages[0].page, 2);
+
+    // Without OCR the page stays empty, and there is no OCR metadata
+    import_pdf_to_db_with_ocr(&mut db_conn, &pdf_path, "scan-no-ocr", None, None, None, None, true, None).unwrap();
+    let items = spine_items(&mut db_conn, "scan-no-ocr");
+    assert_eq!(items[1].content_plain.as_deref(), Some(""));
+    assert_eq!(ocr_metadata(&mut db_conn, "scan-no-ocr"), None);
+}
@@ -43,7 +43,7 @@ fn test_pdf_import_with_utf16_bom_title() {
     assert_eq!(book.1, Some("Pali Lessons".to_string()), 
         "Title should be 'Pali Lessons' without BOM or control characters");
     
-    // Verify spine item was created
+    // Verify the spine item of the first page was created
     let spine_item_uid = format!("{}.0", book_uid);
     let spine_title = app_data.dbm.appdata.do_read(|db_conn| {
         book_spine_items::table
@@ -52,7 +52,7 @@ fn test_pdf_import_with_utf16_bom_title() {
             .first::<Option<String>>(db_conn)
     }).expect("Query failed");
     
-    assert_eq!(spine_title, Some("Pali Lessons".to_string()));
+    assert!(spine_title.unwrap_or_default().ends_with("p. 1"), "First page title should end with its page number");
     
     // Clean up
     let _ = app_data.dbm.appdata.do_write(|db_conn| {
@@ -100,18 +100,19 @@ fn test_pdf_import_general() {
             .get_result::<i64>(db_conn)
     }).expect("Query failed");
     
-    assert_eq!(spine_count, 1, "PDFs should have exactly one spine item");
+    let page_count = lopdf::Document::load(&pdf_path).unwrap().get_pages().len() as i64;
+    assert_eq!(spine_count, page_count, "PDFs should have one spine item per page");
     
-    // Verify the spine item has content_plain (for FTS5 search)
+    // Verify the spine items have content_plain (for FTS5 search)
     let content_plain = app_data.dbm.appdata.do_read(|db_conn| {
         book_spine_items::table
             .filter(book_spine_items::book_uid.eq(book_uid))
             .select(book_spine_items::content_plain)
-            .first::<Option<String>>(db_conn)
+            .load::<Option<String>>(db_conn)
     }).expect("Query failed");
     
-    assert!(content_plain.is_some(), "content_plain should be extracted");
-    assert!(!content_plain.unwrap().is_empty(), "content_plain should not be empty");
+    assert!(content_plain.iter().all(|c| c.is_some()), "content_plain should be extracted");
+    assert!(content_plain.iter().any(|c| !c.as_deref().unwrap_or_default().is_empty()), "content_plain should not be empty");
     
     // Clean up
     let _ = app_data.dbm.appdata.do_write(|db_conn| {
@@ -156,3 +157,154 @@ fn test_decode_utf16_be_with_bom() {
     let decoded = String::from_utf16_lossy(&utf16_chars);
     assert_eq!(decoded, "Pali Lessons");
 }
+
+/// Write a three page PDF with an outline: "Part One" on page 1 (a /Dest
+/// array), "Chapter A" under it on page 2 (a GoTo action), and "Chapter B" on
+/// page 3 (a named destination in the /Names tree).
+fn write_outline_pdf(path: &std::path::Path) {
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Document, Object, Stream};
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    let texts = ["Vitakka first page", "Vicara second page", "Piti third page"];
+    let mut page_ids = Vec::new();
+    for text in texts {
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 24.into()]),
+                Operation::new("Td", vec![100.into(), 600.into()]),
+                Operation::new("Tj", vec![Object::string_literal(text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        page_ids.push(doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        }));
+    }
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => page_ids.iter().map(|id| Object::Reference(*id)).collect::<Vec<_>>(),
+        "Count" => 3,
+        "Resources" => resources_id,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+    }));
+
+    let outlines_id = doc.new_object_id();
+    let part_id = doc.new_object_id();
+    let chapter_a_id = doc.new_object_id();
+    let chapter_b_id = doc.new_object_id();
+    doc.objects.insert(chapter_a_id, Object::Dictionary(dictionary! {
+        "Title" => Object::string_literal("Chapter A"),
+        "Parent" => part_id,
+        "A" => dictionary! {
+            "S" => "GoTo",
+            "D" => vec![page_ids[1].into(), "Fit".into()],
+        },
+    }));
+    doc.objects.insert(part_id, Object::Dictionary(dictionary! {
+        "Title" => Object::string_literal("Part One"),
+        "Parent" => outlines_id,
+        "Dest" => vec![page_ids[0].into(), "Fit".into()],
+        "First" => chapter_a_id,
+        "Last" => chapter_a_id,
+        "Next" => chapter_b_id,
+        "Count" => 1,
+    }));
+    doc.objects.insert(chapter_b_id, Object::Dictionary(dictionary! {
+        "Title" => Object::string_literal("Chapter B"),
+        "Parent" => outlines_id,
+        "Prev" => part_id,
+        "Dest" => Object::string_literal("chapter-b"),
+    }));
+    doc.objects.insert(outlines_id, Object::Dictionary(dictionary! {
+        "Type" => "Outlines",
+        "First" => part_id,
+        "Last" => chapter_b_id,
+        "Count" => 2,
+    }));
+
+    let dests_id = doc.add_object(dictionary! {
+        "Names" => vec![Object::string_literal("chapter-b"), vec![page_ids[2].into(), "Fit".into()].into()],
+    });
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+        "Outlines" => outlines_id,
+        "Names" => dictionary! { "Dests" => dests_id },
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).unwrap();
+}
+
+#[test]
+fn test_pdf_import_pages_and_outline() {
+    use diesel_migrations::MigrationHarness;
+    use simsapa_backend::db::APPDATA_MIGRATIONS;
+    use simsapa_backend::db::appdata_models::{BookSpineItem, NavPointJson};
+    use simsapa_backend::pdf_import::import_pdf_to_db;
+    use simsapa_backend::types::SearchResult;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let pdf_path = tmp.path().join("outline.pdf");
+    write_outline_pdf(&pdf_path);
+    let mut db_conn = SqliteConnection::establish(&tmp.path().join("appdata.sqlite3").to_string_lossy()).unwrap();
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).unwrap();
+
+    let book_uid = "test-pdf-pages";
+    let result = import_pdf_to_db(&mut db_conn, &pdf_path, book_uid, Some("Jhana Notes"), None, None, None, true);
+    assert!(result.is_ok(), "PDF import failed: {:?}", result.err());
+
+    // One spine item per page
+    let items: Vec<BookSpineItem> = book_spine_items::table
+        .filter(book_spine_items::book_uid.eq(book_uid))
+        .order(book_spine_items::spine_index)
+        .select(BookSpineItem::as_select())
+        .load(&mut db_conn)
+        .unwrap();
+
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].spine_item_uid, "test-pdf-pages.0");
+    assert_eq!(items[1].resource_path, "document.pdf#page=2");
+    assert_eq!(items[0].title.as_deref(), Some("Part One, p. 1"));
+    assert_eq!(items[1].title.as_deref(), Some("Chapter A, p. 2"));
+    assert_eq!(items[2].title.as_deref(), Some("Chapter B, p. 3"));
+    let page_2 = items[1].content_plain.as_deref().unwrap_or_default();
+    assert!(page_2.contains("vicara second page"), "{:?}", page_2);
+    assert!(!page_2.contains("vitakka"), "{:?}", page_2);
+
+    // The outline as the table of contents
+    let toc_json = books::table
+        .filter(books::uid.eq(book_uid))
+        .select(books::toc_json)
+        .first::<Option<String>>(&mut db_conn)
+        .unwrap()
+        .expect("toc_json should be set");
+    let toc: Vec<NavPointJson> = serde_json::from_str(&toc_json).unwrap();
+    assert_eq!(toc.len(), 2);
+    assert_eq!(toc[0].label, "Part One");
+    assert_eq!(toc[0].content, "document.pdf#page=1");
+    assert_eq!(toc[0].children[0].label, "Chapter A");
+    assert_eq!(toc[0].children[0].content, "document.pdf#page=2");
+    assert_eq!(toc[1].label, "Chapter B");
+    assert_eq!(toc[1].content, "document.pdf#page=3");
+
+    // Search results of the pages carry the page number
+    let pages: Vec<Option<i32>> = items.iter()
+        .map(|item| SearchResult::from_book_spine_item(item, String::new()).page_number)
+        .collect();
+    assert_eq!(pages, vec![Some(1), Some(2), Some(3)]);
+}
@@ -0,0 +1,66 @@
+//! Automatic phrase markers against a real `.flac` produced by the recorder's
+//! encode path.
+//!
+//! Builds a canonical recording of three "lines" of tone separated by pauses
+//! and checks that `detect_phrase_markers` proposes one range marker per line,
+//! labelled with the chant text, and that re-running detection replaces only
+//! the automatic markers.
+
+use simsapa_backend::audio::format::CANONICAL_SAMPLE_RATE;
+use simsapa_backend::audio::phrase_markers::{
+    detect_phrase_markers, merge_with_existing_markers, PhraseDetectionParams,
+};
+use simsapa_backend::audio::recorder::encode_canonical_pcm_to_flac;
+
+/// Canonical 16-bit PCM of alternating tone (`true`) / silence (`false`)
+/// blocks, durations in ms.
+fn blocks_pcm(spec: &[(bool, u32)]) -> Vec<i16> {
+    let mut out = Vec::new();
+    for &(voiced, ms) in spec {
+        let n = (CANONICAL_SAMPLE_RATE as u64 * ms as u64 / 1000) as usize;
+        for i in 0..n {
+            let t = i as f32 / CANONICAL_SAMPLE_RATE as f32;
+            let v = if voiced {
+                (2.0 * std::f32::consts::PI * 196.0 * t).sin() * 0.4
+            } else {
+                ((i * 7919 % 97) as f32 / 97.0 - 0.5) * 0.002
+            };
+            out.push((v * i16::MAX as f32) as i16);
+        }
+    }
+    out
+}
+
+#[test]
+fn phrase_markers_follow_text_lines() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let flac_path = dir.path().join("chant.flac");
+
+    // The second line contains a short breath (150 ms) which must not split it.
+    let pcm = blocks_pcm(&[
+        (false, 400), (true, 1500), (false, 700),
+        (true, 900), (false, 150), (true, 900), (false, 700),
+        (true, 1200), (false, 400),
+    ]);
+    encode_canonical_pcm_to_flac(&pcm, &flac_path).expect("encode flac");
+
+    let content = "Namo tassa bhagavato\narahato\nsammāsambuddhassa";
+    let markers = detect_phrase_markers(&flac_path, Some(content), &PhraseDetectionParams::default())
+        .expect("detect markers");
+
+    assert_eq!(markers.len(), 3, "{:?}", markers);
+    assert_eq!(markers[0].label, "Namo tassa bhagavato");
+    assert_eq!(markers[2].label, "sammāsambuddhassa");
+    assert!(markers.iter().all(|m| m.marker_type == "range" && m.start_ms < m.end_ms));
+
+    // Second line spans both halves around the breath: ~2600 ms to ~4550 ms.
+    assert!((2400..=2700).contains(&markers[1].start_ms), "{:?}", markers[1]);
+    assert!((4500..=4800).contains(&markers[1].end_ms), "{:?}", markers[1]);
+
+    let user_markers = r#"[{"id": "pos_1", "type": "position", "label": "Mark", "comment": "", "position_ms": 1000}]"#;
+    let first = merge_with_existing_markers(Some(user_markers), &markers).expect("merge");
+    let second = merge_with_existing_markers(Some(&first), &markers).expect("merge again");
+    let parsed: Vec<serde_json::Value> = serde_json::from_str(&second).expect("json");
+    assert_eq!(parsed.len(), 4, "re-running detection must not duplicate markers");
+    assert_eq!(parsed[0]["id"], "pos_1");
+}
@@ -0,0 +1,191 @@
+// Reading plans: generators, enrollment, the items of the day and completion.
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::db::appdata_models::NewSutta;
+use simsapa_backend::db::appdata_schema::suttas;
+use simsapa_backend::db::{DatabaseHandle, APPDATA_MIGRATIONS};
+use simsapa_backend::reading_plans::{generate_plan, parse_plan_json, plan_to_json, PlanGeneratorOptions, PlanItemsSource, PlanSchedule};
+
+fn new_sutta<'a>(uid: &'a str, nikaya: &'a str, language: &'a str, range: (&'a str, i32)) -> NewSutta<'a> {
+    NewSutta {
+        uid,
+        sutta_ref: uid,
+        nikaya,
+        language,
+        group_path: None,
+        group_index: None,
+        order_index: None,
+        sutta_range_group: Some(range.0),
+        sutta_range_start: Some(range.1),
+        sutta_range_end: Some(range.1),
+        title: Some(uid),
+        title_ascii: Some(uid),
+        title_pali: None,
+        title_trans: None,
+        description: None,
+        content_plain: None,
+        content_html: None,
+        content_json: None,
+        content_json_tmpl: None,
+        source_uid: Some(uid.rsplit('/').next().unwrap_or_default()),
+        source_info: None,
+        source_language: None,
+        message: None,
+        copyright: None,
+        license: None,
+    }
+}
+
+fn appdata_handle(dir: &std::path::Path) -> DatabaseHandle {
+    let handle = DatabaseHandle::new(&dir.join("appdata.sqlite3").to_string_lossy()).unwrap();
+    let mut db_conn = handle.get_conn().unwrap();
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).unwrap();
+    diesel::insert_into(suttas::table)
+        .values(&vec![
+            new_sutta("mn1/pli/ms", "mn", "pli", ("mn", 1)),
+            new_sutta("mn1/en/sujato", "mn", "en", ("mn", 1)),
+            new_sutta("mn2/pli/ms", "mn", "pli", ("mn", 2)),
+            new_sutta("mn2/en/sujato", "mn", "en", ("mn", 2)),
+            new_sutta("mn3/pli/ms", "mn", "pli", ("mn", 3)),
+            new_sutta("mn4/pli/ms", "mn", "pli", ("mn", 4)),
+            new_sutta("mn4/en/sujato", "mn", "en", ("mn", 4)),
+            new_sutta("sn1.1/en/sujato", "sn", "en", ("sn1", 1)),
+        ])
+        .execute(&mut db_conn)
+        .unwrap();
+    handle
+}
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn nikaya_options(uid: &str, schedule: PlanSchedule, days: Option<u32>) -> PlanGeneratorOptions {
+    PlanGeneratorOptions {
+        uid: uid.to_string(),
+        title: "The Majjhima".to_string(),
+        description: None,
+        author: Some("Teacher".to_string()),
+        schedule,
+        days,
+        items: PlanItemsSource::Nikaya { nikaya: "mn".to_string(), language: "en".to_string(), source_uid: Some("sujato".to_string()) },
+    }
+}
+
+#[test]
+fn test_generate_plans() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+
+    // The whole nikāya in English where it exists, stopping before SN
+    let plan = generate_plan(&handle, &nikaya_options("mn", PlanSchedule::Daily, Some(2))).unwrap();
+    let uids: Vec<&str> = plan.items.iter().map(|i| i.item_uid.as_str()).collect();
+    assert_eq!(uids, vec!["mn1/en/sujato", "mn2/en/sujato", "mn3/pli/ms", "mn4/en/sujato"]);
+    let days: Vec<i32> = plan.items.iter().filter_map(|i| i.day).collect();
+    assert_eq!(days, vec![1, 1, 2, 2]);
+
+    let range = generate_plan(&handle, &PlanGeneratorOptions {
+        items: PlanItemsSource::SuttaRange { first_uid: "mn2/pli/ms".to_string(), last_uid: Some("mn3".to_string()) },
+        ..nikaya_options("mn-range", PlanSchedule::Sequential, None)
+    }).unwrap();
+    let uids: Vec<&str> = range.items.iter().map(|i| i.item_uid.as_str()).collect();
+    assert_eq!(uids, vec!["mn2/pli/ms", "mn3/pli/ms"]);
+
+    // Saved and exported, the plan is the same
+    handle.save_reading_plan(&plan).unwrap();
+    let saved = handle.get_reading_plan("mn").unwrap().unwrap();
+    assert_eq!(saved, plan);
+    assert_eq!(parse_plan_json(&plan_to_json(&saved).unwrap()).unwrap(), plan);
+}
+
+#[test]
+fn test_daily_plan_enrollment() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+
+    let plan = generate_plan(&handle, &nikaya_options("mn", PlanSchedule::Daily, None)).unwrap();
+    handle.save_reading_plan(&plan).unwrap();
+
+    assert!(handle.get_reading_plan_items_for_day(date("2026-01-01")).unwrap().is_empty());
+
+    handle.enroll_in_reading_plan("mn", date("2026-01-01")).unwrap();
+
+    let day_items = handle.get_reading_plan_items_for_day(date("2025-12-31")).unwrap();
+    assert!(day_items.is_empty());
+
+    let day_items = handle.get_reading_plan_items_for_day(date("2026-01-01")).unwrap();
+    assert_eq!(day_items.len(), 1);
+    assert_eq!(day_items[0].item_uid, "mn1/en/sujato");
+    assert!(!day_items[0].is_overdue);
+
+    // Day 3: mn1 and mn2 are overdue
+    let day_items = handle.get_reading_plan_items_for_day(date("2026-01-03")).unwrap();
+    let uids: Vec<&str> = day_items.iter().map(|i| i.item_uid.as_str()).collect();
+    assert_eq!(uids, vec!["mn1/en/sujato", "mn2/en/sujato", "mn3/pli/ms"]);
+    assert!(day_items[0].is_overdue);
+
+    // Marked completed in the plan, or read to the end in any translation
+    handle.set_reading_plan_item_completed("mn", 0, true).unwrap();
+    handle.set_reading_finished("mn2/pli/ms", "suttas", true).unwrap();
+
+    let day_items = handle.get_reading_plan_items_for_day(date("2026-01-03")).unwrap();
+    let uids: Vec<&str> = day_items.iter().map(|i| i.item_uid.as_str()).collect();
+    assert_eq!(uids, vec!["mn3/pli/ms"]);
+
+    let plans = handle.get_reading_plans(date("2026-01-03")).unwrap();
+    assert_eq!(plans.len(), 1);
+    assert_eq!(plans[0].items_total, 4);
+    assert_eq!(plans[0].days_total, 4);
+    assert_eq!(plans[0].items_completed, 2);
+    assert_eq!(plans[0].current_day, Some(3));
+
+    // Paused plans are not listed for the day
+    handle.set_reading_plan_active("mn", false).unwrap();
+    assert!(handle.get_reading_plan_items_for_day(date("2026-01-03")).unwrap().is_empty());
+    handle.set_reading_plan_active("mn", true).unwrap();
+
+    // Saving the plan again keeps the enrollment
+    handle.save_reading_plan(&plan).unwrap();
+    let enrollment = handle.get_reading_plan_enrollment_json("mn").unwrap().unwrap();
+    assert_eq!(enrollment.start_date, date("2026-01-01"));
+    assert_eq!(enrollment.completed_items, vec![0]);
+
+    // Enrolling again restarts the plan
+    handle.enroll_in_reading_plan("mn", date("2026-02-01")).unwrap();
+    let enrollment = handle.get_reading_plan_enrollment_json("mn").unwrap().unwrap();
+    assert!(enrollment.completed_items.is_empty());
+
+    handle.delete_reading_plan("mn").unwrap();
+    assert!(handle.get_reading_plans(date("2026-02-01")).unwrap().is_empty());
+}
+
+#[test]
+fn test_sequential_plan() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+
+    let plan = generate_plan(&handle, &nikaya_options("mn-seq", PlanSchedule::Sequential, Some(2))).unwrap();
+    handle.save_reading_plan(&plan).unwrap();
+    handle.enroll_in_reading_plan("mn-seq", date("2026-01-01")).unwrap();
+
+    // The next unread day group, whatever the date
+    let day_items = handle.get_reading_plan_items_for_day(date("2026-03-01")).unwrap();
+    let uids: Vec<&str> = day_items.iter().map(|i| i.item_uid.as_str()).collect();
+    assert_eq!(uids, vec!["mn1/en/sujato", "mn2/en/sujato"]);
+
+    handle.set_reading_plan_item_completed("mn-seq", 0, true).unwrap();
+    let day_items = handle.get_reading_plan_items_for_day(date("2026-03-01")).unwrap();
+    assert_eq!(day_items.len(), 2);
+    assert!(day_items[0].is_completed);
+
+    handle.set_reading_plan_item_completed("mn-seq", 1, true).unwrap();
+    let day_items = handle.get_reading_plan_items_for_day(date("2026-03-01")).unwrap();
+    let uids: Vec<&str> = day_items.iter().map(|i| i.item_uid.as_str()).collect();
+    assert_eq!(uids, vec!["mn3/pli/ms", "mn4/en/sujato"]);
+
+    let plans = handle.get_reading_plans(date("2026-03-01")).unwrap();
+    assert_eq!(plans[0].current_day, Some(2));
+}
@@ -0,0 +1,150 @@
+// Automatic reading state: positions, progress, finished marks and statistics.
+
+use std::fs;
+
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::db::appdata_models::NewSutta;
+use simsapa_backend::db::appdata_schema::suttas;
+use simsapa_backend::db::{DatabaseHandle, APPDATA_MIGRATIONS};
+use simsapa_backend::markdown_import::import_markdown_to_db;
+
+fn new_sutta<'a>(uid: &'a str, nikaya: &'a str) -> NewSutta<'a> {
+    NewSutta {
+        uid,
+        sutta_ref: uid,
+        nikaya,
+        language: "pli",
+        group_path: None,
+        group_index: None,
+        order_index: None,
+        sutta_range_group: None,
+        sutta_range_start: None,
+        sutta_range_end: None,
+        title: Some(uid),
+        title_ascii: Some(uid),
+        title_pali: None,
+        title_trans: None,
+        description: None,
+        content_plain: None,
+        content_html: None,
+        content_json: None,
+        content_json_tmpl: None,
+        source_uid: Some("ms"),
+        source_info: None,
+        source_language: None,
+        message: None,
+        copyright: None,
+        license: None,
+    }
+}
+
+fn appdata_handle(dir: &std::path::Path) -> DatabaseHandle {
+    let handle = DatabaseHandle::new(&dir.join("appdata.sqlite3").to_string_lossy()).unwrap();
+    let mut db_conn = handle.get_conn().unwrap();
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).unwrap();
+    diesel::insert_into(suttas::table)
+        .values(&vec![
+            new_sutta("mn1/pli/ms", "mn"),
+            new_sutta("mn1/en/sujato", "mn"),
+            new_sutta("mn2/pli/ms", "mn"),
+            new_sutta("mn3/pli/ms", "mn"),
+            new_sutta("mn4/pli/ms", "mn"),
+            new_sutta("sn1.1/pli/ms", "sn"),
+        ])
+        .execute(&mut db_conn)
+        .unwrap();
+    handle
+}
+
+#[test]
+fn test_sutta_reading_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+
+    assert!(handle.get_reading_state("mn1/en/sujato", "suttas").unwrap().is_none());
+
+    handle.record_reading_progress("mn1/en/sujato", "suttas", 0.4, 0.6, 30).unwrap();
+    // Scrolling back keeps the furthest point read
+    handle.record_reading_progress("mn1/en/sujato", "suttas", 0.1, 0.3, 30).unwrap();
+
+    let state = handle.get_reading_state("mn1/en/sujato", "suttas").unwrap().unwrap();
+    assert_eq!(state.sutta_ref_uid.as_deref(), Some("mn1"));
+    assert_eq!(state.scroll_position, 0.1);
+    assert_eq!(state.progress, 0.6);
+    assert_eq!(state.time_spent_secs, 60);
+    assert!(!state.is_finished);
+    assert!(state.last_read_at.is_some());
+
+    // Reading to the end marks it finished
+    handle.record_reading_progress("mn1/en/sujato", "suttas", 0.9, 1.0, 30).unwrap();
+    let state = handle.get_reading_state("mn1/en/sujato", "suttas").unwrap().unwrap();
+    assert!(state.is_finished);
+    assert!(state.finished_at.is_some());
+
+    // Marked unread, it stays unread while reading on
+    handle.set_reading_finished("mn1/en/sujato", "suttas", false).unwrap();
+    handle.record_reading_progress("mn1/en/sujato", "suttas", 0.9, 1.0, 30).unwrap();
+    let state = handle.get_reading_state("mn1/en/sujato", "suttas").unwrap().unwrap();
+    assert!(!state.is_finished);
+    assert!(state.finished_at.is_none());
+
+    // The Pāli text of the same sutta counts as the same sutta
+    handle.set_reading_finished("mn1/pli/ms", "suttas", true).unwrap();
+    handle.record_reading_progress("mn2/pli/ms", "suttas", 0.2, 0.3, 10).unwrap();
+    handle.record_reading_progress("sn1.1/pli/ms", "suttas", 0.0, 0.2, 10).unwrap();
+
+    let canon = handle.get_canon_reading_progress(None, None).unwrap();
+    assert_eq!(canon.len(), 2);
+    assert_eq!(canon[0].nikaya, "mn");
+    assert_eq!(canon[0].suttas_total, 4);
+    assert_eq!(canon[0].suttas_started, 2);
+    assert_eq!(canon[0].suttas_finished, 1);
+    assert_eq!(canon[1].nikaya, "sn");
+    assert_eq!(canon[1].suttas_started, 1);
+
+    let mn = handle.get_canon_reading_progress(Some("MN"), None).unwrap();
+    assert_eq!(mn.len(), 1);
+    assert_eq!(mn[0].suttas_total, 4);
+
+    let stats = handle.get_reading_statistics(2).unwrap();
+    assert_eq!(stats.time_spent_secs, 140);
+    assert_eq!(stats.suttas_started, 3);
+    assert_eq!(stats.suttas_finished, 1);
+    assert_eq!(stats.chapters_started, 0);
+    assert_eq!(stats.recent.len(), 2);
+}
+
+#[test]
+fn test_book_reading_progress() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+    let mut db_conn = handle.get_conn().unwrap();
+
+    let md_path = dir.path().join("talks.md");
+    fs::write(&md_path, "# One\n\nFirst talk.\n\n# Two\n\nSecond talk.\n\n# Three\n\nThird talk.\n\n# Four\n\nFourth talk.\n").unwrap();
+    import_markdown_to_db(&mut db_conn, &md_path, "talks", None, None, None, true).unwrap();
+    drop(db_conn);
+
+    let spine_items = handle.get_spine_items_for_book("talks").unwrap();
+    assert_eq!(spine_items.len(), 4);
+
+    let progress = handle.get_book_reading_progress("talks").unwrap();
+    assert_eq!(progress.chapters_total, 4);
+    assert_eq!(progress.chapters_started, 0);
+    assert_eq!(progress.percent_read, 0.0);
+
+    handle.record_reading_progress(&spine_items[0].spine_item_uid, "book_spine_items", 0.5, 1.0, 20).unwrap();
+    handle.record_reading_progress(&spine_items[1].spine_item_uid, "book_spine_items", 0.2, 0.5, 20).unwrap();
+
+    let state = handle.get_reading_state(&spine_items[1].spine_item_uid, "book_spine_items").unwrap().unwrap();
+    assert_eq!(state.book_uid.as_deref(), Some("talks"));
+
+    let progress = handle.get_book_reading_progress("talks").unwrap();
+    assert_eq!(progress.chapters_started, 2);
+    assert_eq!(progress.chapters_finished, 1);
+    assert_eq!(progress.percent_read, 37.5);
+    assert_eq!(progress.time_spent_secs, 40);
+    assert!(progress.last_spine_item_uid.is_some());
+}
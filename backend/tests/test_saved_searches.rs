@@ -0,0 +1,119 @@
+// Saved searches: saving, the baseline run, new results on later runs, and
+// restoring from the user data export.
+
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::db::appdata_models::NewSavedSearch;
+use simsapa_backend::db::{DatabaseHandle, APPDATA_MIGRATIONS};
+use simsapa_backend::types::SearchResult;
+
+fn appdata_handle(dir: &std::path::Path) -> DatabaseHandle {
+    let handle = DatabaseHandle::new(&dir.join("appdata.sqlite3").to_string_lossy()).unwrap();
+    let mut db_conn = handle.get_conn().unwrap();
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).unwrap();
+    handle
+}
+
+fn result(table_name: &str, uid: &str) -> SearchResult {
+    SearchResult {
+        table_name: table_name.to_string(),
+        ..SearchResult::from_title_str(uid)
+    }
+}
+
+fn new_search<'a>(name: &'a str, query_text: &'a str, params_json: &'a str) -> NewSavedSearch<'a> {
+    NewSavedSearch {
+        name,
+        query_text,
+        search_area: "Suttas",
+        search_mode: "Fulltext Match",
+        params_json,
+    }
+}
+
+const PARAMS: &str = r#"{"mode":"Fulltext Match","lang":"en","lang_include":true}"#;
+
+#[test]
+fn test_saved_search_new_hits() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+
+    let id = handle.save_search(&new_search("Mindfulness", "satipatthana", PARAMS)).unwrap();
+    assert_eq!(handle.get_saved_searches().unwrap().len(), 1);
+
+    // The first run is the baseline, nothing is new
+    let new_hits = handle.record_saved_search_hits(id, &[
+        result("suttas", "mn10/en/sujato"),
+        result("suttas", "mn10/en/sujato"),
+        result("suttas", "dn22/en/sujato"),
+    ], 3).unwrap();
+    assert!(new_hits.is_empty());
+    let saved = handle.get_saved_search(id).unwrap().unwrap();
+    assert!(saved.last_run_at.is_some());
+    assert_eq!(saved.last_hits_count, 3);
+    assert_eq!(saved.new_hits_count, 0);
+    assert_eq!(handle.get_saved_search_hits(id, false).unwrap().len(), 2);
+
+    // A result of new content is reported once
+    let new_hits = handle.record_saved_search_hits(id, &[
+        result("suttas", "mn10/en/sujato"),
+        result("suttas", "mn10/de/sabbamitta"),
+        result("book_spine_items", "the-island.12"),
+    ], 3).unwrap();
+    let uids: Vec<&str> = new_hits.iter().map(|h| h.uid.as_str()).collect();
+    assert_eq!(uids, vec!["mn10/de/sabbamitta", "the-island.12"]);
+    assert_eq!(handle.get_saved_search(id).unwrap().unwrap().new_hits_count, 2);
+
+    let new_hits = handle.record_saved_search_hits(id, &[result("suttas", "mn10/de/sabbamitta")], 1).unwrap();
+    assert!(new_hits.is_empty());
+    assert_eq!(handle.get_saved_search_hits(id, true).unwrap().len(), 2);
+
+    handle.mark_saved_search_seen(id).unwrap();
+    assert!(handle.get_saved_search_hits(id, true).unwrap().is_empty());
+    assert_eq!(handle.get_saved_search(id).unwrap().unwrap().new_hits_count, 0);
+
+    // Saving again under the same name keeps the results seen, unless the
+    // search changed
+    assert_eq!(handle.save_search(&new_search("Mindfulness", "satipatthana", PARAMS)).unwrap(), id);
+    assert_eq!(handle.get_saved_search_hits(id, false).unwrap().len(), 4);
+
+    assert_eq!(handle.save_search(&new_search("Mindfulness", "sati", PARAMS)).unwrap(), id);
+    assert!(handle.get_saved_search_hits(id, false).unwrap().is_empty());
+    assert!(handle.get_saved_search(id).unwrap().unwrap().last_run_at.is_none());
+
+    handle.rename_saved_search(id, "Sati").unwrap();
+    assert!(handle.rename_saved_search(id, " ").is_err());
+    assert_eq!(handle.get_saved_search(id).unwrap().unwrap().name, "Sati");
+
+    handle.delete_saved_search(id).unwrap();
+    assert!(handle.get_saved_search(id).unwrap().is_none());
+}
+
+#[test]
+fn test_restore_saved_search() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+
+    let id = handle.save_search(&new_search("Mindfulness", "satipatthana", PARAMS)).unwrap();
+    handle.record_saved_search_hits(id, &[result("suttas", "mn10/en/sujato")], 1).unwrap();
+    handle.record_saved_search_hits(id, &[result("suttas", "dn22/en/sujato")], 1).unwrap();
+
+    let saved = handle.get_saved_search(id).unwrap().unwrap();
+    let hits = handle.get_saved_search_hits(id, false).unwrap();
+
+    // Into a new database, as after an upgrade
+    let upgraded_dir = tempfile::tempdir().unwrap();
+    let upgraded = appdata_handle(upgraded_dir.path());
+    let restored_id = upgraded.restore_saved_search(&saved, &hits).unwrap().unwrap();
+
+    let restored = upgraded.get_saved_search(restored_id).unwrap().unwrap();
+    assert_eq!(restored.query_text, "satipatthana");
+    assert_eq!(restored.last_run_at, saved.last_run_at);
+    assert_eq!(restored.new_hits_count, 1);
+    let new_hits = upgraded.get_saved_search_hits(restored_id, true).unwrap();
+    assert_eq!(new_hits.len(), 1);
+    assert_eq!(new_hits[0].uid, "dn22/en/sujato");
+
+    // A search with the same name is not replaced
+    assert!(upgraded.restore_saved_search(&saved, &hits).unwrap().is_none());
+}
@@ -317,6 +317,7 @@ fn create_test_indexes(base_name: &str) -> (PathBuf, AppGlobalPaths) {
     let paths = AppGlobalPaths {
         simsapa_dir: base.clone(),
         simsapa_api_port_path: base.join("api-port.txt"),
+        simsapa_api_token_path: base.join("api-token.txt"),
         download_temp_folder: base.join("temp-download"),
         extract_temp_folder: base.join("temp-extract"),
         app_assets_dir: base.join("app-assets"),
@@ -337,6 +338,7 @@ fn create_test_indexes(base_name: &str) -> (PathBuf, AppGlobalPaths) {
         auto_start_download_marker: base.join("auto_start_download.txt"),
         delete_files_for_upgrade_marker: base.join("delete_files_for_upgrade.txt"),
         download_select_sanskrit_bundle_marker: base.join("download_select_sanskrit_bundle.txt"),
+        run_saved_searches_marker: base.join("run_saved_searches.txt"),
     };
 
     (base, paths)
@@ -451,6 +453,7 @@ fn test_fulltext_searcher_empty_indexes() {
     let paths = AppGlobalPaths {
         simsapa_dir: base.clone(),
         simsapa_api_port_path: base.join("api-port.txt"),
+        simsapa_api_token_path: base.join("api-token.txt"),
         download_temp_folder: base.join("temp-download"),
         extract_temp_folder: base.join("temp-extract"),
         app_assets_dir: base.join("app-assets"),
@@ -471,6 +474,7 @@ fn test_fulltext_searcher_empty_indexes() {
         auto_start_download_marker: base.join("auto_start_download.txt"),
         delete_files_for_upgrade_marker: base.join("delete_files_for_upgrade.txt"),
         download_select_sanskrit_bundle_marker: base.join("download_select_sanskrit_bundle.txt"),
+        run_saved_searches_marker: base.join("run_saved_searches.txt"),
     };
 
     let searcher = FulltextSearcher::open(&paths).expect("Should handle missing dirs gracefully");
@@ -0,0 +1,151 @@
+// Search history: dedup, the opened result, pruning, prefix matching for
+// suggestions, the counts, and the sutta titles completion list.
+
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::db::appdata_models::{NewSearchHistoryItem, NewSutta};
+use simsapa_backend::db::appdata_schema::suttas;
+use simsapa_backend::db::{DatabaseHandle, APPDATA_MIGRATIONS};
+
+fn appdata_handle(dir: &std::path::Path) -> DatabaseHandle {
+    let handle = DatabaseHandle::new(&dir.join("appdata.sqlite3").to_string_lossy()).unwrap();
+    let mut db_conn = handle.get_conn().unwrap();
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).unwrap();
+    handle
+}
+
+fn item<'a>(query_text: &'a str, search_area: &'a str, params_json: &'a str, hits_count: i32, seconds: i64) -> NewSearchHistoryItem<'a> {
+    NewSearchHistoryItem {
+        query_text,
+        search_area,
+        search_mode: "Fulltext Match",
+        params_json,
+        hits_count,
+        last_searched_at: chrono::DateTime::from_timestamp(1_790_000_000 + seconds, 0).unwrap().naive_utc(),
+    }
+}
+
+const EN: &str = r#"{"mode":"Fulltext Match","lang":"en"}"#;
+const PLI: &str = r#"{"mode":"Fulltext Match","lang":"pli"}"#;
+
+#[test]
+fn test_search_history_dedup_and_prune() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+
+    let id1 = handle.record_search_history(&item("satipatthana", "Suttas", EN, 10, 1), 100).unwrap();
+    let id2 = handle.record_search_history(&item("satipatthana", "Suttas", PLI, 3, 2), 100).unwrap();
+    assert_ne!(id1, id2);
+
+    // The same query, area and params is counted again
+    let id3 = handle.record_search_history(&item("satipatthana", "Suttas", EN, 12, 3), 100).unwrap();
+    assert_eq!(id3, id1);
+
+    let history = handle.get_search_history(10).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].id, id1);
+    assert_eq!(history[0].search_count, 2);
+    assert_eq!(history[0].hits_count, 12);
+
+    handle.set_search_history_opened(id1, "mn10/en/sujato", "suttas", Some("Mindfulness Meditation")).unwrap();
+    let opened = &handle.get_search_history(1).unwrap()[0];
+    assert_eq!(opened.opened_uid.as_deref(), Some("mn10/en/sujato"));
+    assert_eq!(opened.opened_title.as_deref(), Some("Mindfulness Meditation"));
+
+    // The oldest searches over the limit are removed
+    handle.record_search_history(&item("sati", "Suttas", EN, 0, 4), 2).unwrap();
+    let history = handle.get_search_history(10).unwrap();
+    let ids: Vec<i32> = history.iter().map(|i| i.id).collect();
+    assert_eq!(history.len(), 2);
+    assert!(ids.contains(&id1));
+    assert!(!ids.contains(&id2));
+
+    handle.delete_search_history_item(id1).unwrap();
+    assert_eq!(handle.get_search_history(10).unwrap().len(), 1);
+    handle.clear_search_history().unwrap();
+    assert!(handle.get_search_history(10).unwrap().is_empty());
+}
+
+#[test]
+fn test_search_history_matching_and_stats() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+
+    handle.record_search_history(&item("satipatthana", "Suttas", EN, 10, 1), 100).unwrap();
+    handle.record_search_history(&item("satipatthana", "Suttas", EN, 10, 2), 100).unwrap();
+    handle.record_search_history(&item("Sati sampajanna", "Suttas", EN, 0, 3), 100).unwrap();
+    handle.record_search_history(&item("sati", "Dictionary", EN, 5, 4), 100).unwrap();
+    handle.record_search_history(&item("s_ti", "Suttas", EN, 0, 5), 100).unwrap();
+
+    // Not case sensitive, in the area, the most repeated first
+    let matching = handle.get_search_history_matching("sati", "Suttas", 10).unwrap();
+    let queries: Vec<&str> = matching.iter().map(|i| i.query_text.as_str()).collect();
+    assert_eq!(queries, vec!["satipatthana", "Sati sampajanna"]);
+
+    // _ is not a wildcard
+    let matching = handle.get_search_history_matching("s_", "Suttas", 10).unwrap();
+    assert_eq!(matching.len(), 1);
+    assert_eq!(matching[0].query_text, "s_ti");
+
+    let stats = handle.get_search_history_stats(2).unwrap();
+    assert_eq!(stats.searches_count, 5);
+    assert_eq!(stats.queries_count, 4);
+    assert_eq!(stats.zero_hits_count, 2);
+    assert!(stats.area_counts.contains(&("Suttas".to_string(), 4)));
+    assert!(stats.area_counts.contains(&("Dictionary".to_string(), 1)));
+    assert_eq!(stats.top_searches[0].query_text, "satipatthana");
+    assert_eq!(stats.zero_hits_searches[0].query_text, "s_ti");
+}
+
+#[test]
+fn test_sutta_titles_flat_completion_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+
+    let new_sutta = |uid: &'static str, title: &'static str, title_pali: &'static str| NewSutta {
+        uid,
+        sutta_ref: uid,
+        nikaya: "mn",
+        language: "en",
+        group_path: None,
+        group_index: None,
+        order_index: None,
+        sutta_range_group: None,
+        sutta_range_start: None,
+        sutta_range_end: None,
+        title: Some(title),
+        title_ascii: None,
+        title_pali: Some(title_pali),
+        title_trans: None,
+        description: None,
+        content_plain: None,
+        content_html: None,
+        content_json: None,
+        content_json_tmpl: None,
+        source_uid: None,
+        source_info: None,
+        source_language: None,
+        message: None,
+        copyright: None,
+        license: None,
+    };
+
+    let mut db_conn = handle.get_conn().unwrap();
+    diesel::insert_into(suttas::table)
+        .values(&vec![
+            new_sutta("mn10/en/sujato", "Mindfulness Meditation", "Satipaṭṭhānasutta"),
+            new_sutta("mn10/en/bodhi", "The Foundations of Mindfulness", "Satipaṭṭhānasutta"),
+            new_sutta("mn1/en/sujato", "The Root of All Things", "Mūlapariyāyasutta"),
+        ])
+        .execute(&mut db_conn)
+        .unwrap();
+
+    let titles = handle.sutta_titles_flat_completion_list().unwrap();
+    assert_eq!(titles.len(), 5);
+    assert_eq!(titles.iter().filter(|t| *t == "Satipaṭṭhānasutta").count(), 1);
+    // Pāli alphabetical order: m before s
+    let m = titles.iter().position(|t| t == "Mūlapariyāyasutta").unwrap();
+    let s = titles.iter().position(|t| t == "Satipaṭṭhānasutta").unwrap();
+    assert!(m < s);
+}
@@ -0,0 +1,110 @@
+// Sutta reference linking in library books and the citations index.
+
+use std::fs;
+
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use simsapa_backend::db::appdata_models::{BookSpineItem, NewSutta};
+use simsapa_backend::db::appdata_schema::{book_spine_items, book_sutta_citations, suttas};
+use simsapa_backend::db::{DatabaseHandle, APPDATA_MIGRATIONS};
+use simsapa_backend::markdown_import::import_markdown_to_db;
+use simsapa_backend::sutta_ref_links::link_sutta_refs_in_book;
+
+fn new_sutta<'a>(uid: &'a str, range: Option<(&'a str, i32, i32)>) -> NewSutta<'a> {
+    NewSutta {
+        uid,
+        sutta_ref: uid,
+        nikaya: "",
+        language: "pli",
+        group_path: None,
+        group_index: None,
+        order_index: None,
+        sutta_range_group: range.map(|r| r.0),
+        sutta_range_start: range.map(|r| r.1),
+        sutta_range_end: range.map(|r| r.2),
+        title: Some(uid),
+        title_ascii: Some(uid),
+        title_pali: None,
+        title_trans: None,
+        description: None,
+        content_plain: None,
+        content_html: None,
+        content_json: None,
+        content_json_tmpl: None,
+        source_uid: Some("ms"),
+        source_info: None,
+        source_language: None,
+        message: None,
+        copyright: None,
+        license: None,
+    }
+}
+
+fn appdata_handle(dir: &std::path::Path) -> DatabaseHandle {
+    let handle = DatabaseHandle::new(&dir.join("appdata.sqlite3").to_string_lossy()).unwrap();
+    let mut db_conn = handle.get_conn().unwrap();
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).unwrap();
+    diesel::insert_into(suttas::table)
+        .values(&vec![
+            new_sutta("mn10/pli/ms", None),
+            new_sutta("mn10/en/sujato", None),
+            new_sutta("dn22/pli/ms", None),
+            new_sutta("an1.1-10/pli/ms", Some(("an1", 1, 10))),
+            new_sutta("dhp179-196/pli/ms", Some(("dhp", 179, 196))),
+        ])
+        .execute(&mut db_conn)
+        .unwrap();
+    handle
+}
+
+#[test]
+fn test_link_sutta_refs_in_book() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = appdata_handle(dir.path());
+    let mut db_conn = handle.get_conn().unwrap();
+
+    let md_path = dir.path().join("talk.md");
+    fs::write(&md_path, "# Mindfulness\n\nSee MN 10 (D ii 290) and AN 1.5, Dhp 183, MN 999.\n\nAgain MN 10.\n").unwrap();
+    import_markdown_to_db(&mut db_conn, &md_path, "talk", None, None, None, true).unwrap();
+
+    assert_eq!(link_sutta_refs_in_book(&mut db_conn, "talk").unwrap(), 4);
+
+    let items: Vec<BookSpineItem> = book_spine_items::table
+        .filter(book_spine_items::book_uid.eq("talk"))
+        .select(BookSpineItem::as_select())
+        .load(&mut db_conn)
+        .unwrap();
+    let html = items.iter().filter_map(|i| i.content_html.clone()).collect::<String>();
+    assert!(html.contains(r#"<a href="ssp://suttas/mn10/pli/ms" class="sutta-link">MN 10</a> (<a href="ssp://suttas/dn22/pli/ms" class="sutta-link">D ii 290</a>)"#));
+    assert!(html.contains(r#"<a href="ssp://suttas/an1.1-10/pli/ms" class="sutta-link">AN 1.5</a>"#));
+    assert!(html.contains(r#"<a href="ssp://suttas/dhp179-196/pli/ms" class="sutta-link">Dhp 183</a>"#));
+    assert!(html.contains("MN 999."));
+
+    // Linking again does not nest the links or change the citations
+    assert_eq!(link_sutta_refs_in_book(&mut db_conn, "talk").unwrap(), 4);
+    let html_again = book_spine_items::table
+        .filter(book_spine_items::book_uid.eq("talk"))
+        .select(book_spine_items::content_html)
+        .load::<Option<String>>(&mut db_conn)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<String>();
+    assert_eq!(html_again, html);
+    drop(db_conn);
+
+    let chapters = handle.get_chapters_citing_sutta("mn10/en/sujato").unwrap();
+    assert_eq!(chapters.len(), 1);
+    assert_eq!(chapters[0].book_uid, "talk");
+    assert_eq!(chapters[0].chapter_title, "Mindfulness");
+    assert_eq!(chapters[0].ref_text, "MN 10");
+    assert_eq!(chapters[0].ref_count, 2);
+    assert!(handle.get_chapters_citing_sutta("mn999").unwrap().is_empty());
+
+    // The citations are deleted with the book
+    handle.delete_book_by_uid("talk").unwrap();
+    let mut db_conn = handle.get_conn().unwrap();
+    let count: i64 = book_sutta_citations::table.count().get_result(&mut db_conn).unwrap();
+    assert_eq!(count, 0);
+}
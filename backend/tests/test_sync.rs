@@ -0,0 +1,361 @@
+// Sync between devices in one process: pairing, the /v1/sync routes, merging
+// bookmarks, history, chanting and settings both ways, and the shared folder.
+#![cfg(feature = "api-server")]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::Client;
+use serde_json::{json, Value};
+
+use simsapa_backend::api_server::{build_rocket, build_sync_rocket, ApiServerConfig};
+use simsapa_backend::db::{DatabaseHandle, DbManager, APPDATA_MIGRATIONS, DICTIONARIES_MIGRATIONS};
+use simsapa_backend::sync::{self, PairRequest, PairResponse, SyncReport, SyncRequest, SyncResponse, SyncTransport};
+
+const TOKEN: &str = "test-token-0123456789";
+
+/// A device with its own empty databases.
+fn device(dir: &Path, name: &str) -> Arc<DbManager> {
+    let url = |db: &str| dir.join(format!("{}-{}.sqlite3", name, db)).to_string_lossy().to_string();
+
+    let mut db_conn = SqliteConnection::establish(&url("appdata")).expect("appdata");
+    db_conn.run_pending_migrations(APPDATA_MIGRATIONS).expect("appdata migrations");
+    let mut db_conn = SqliteConnection::establish(&url("dictionaries")).expect("dictionaries");
+    db_conn.run_pending_migrations(DICTIONARIES_MIGRATIONS).expect("dictionaries migrations");
+
+    let dbm = DbManager {
+        appdata: DatabaseHandle::new(&url("appdata")).expect("appdata handle"),
+        dictionaries: DatabaseHandle::new(&url("dictionaries")).expect("dictionaries handle"),
+        dpd: DatabaseHandle::new(&url("dpd")).expect("dpd handle"),
+    };
+    sync::set_device_name(&dbm, name).expect("device name");
+    Arc::new(dbm)
+}
+
+fn server(dbm: Arc<DbManager>) -> Client {
+    let config = ApiServerConfig { api_token: Some(TOKEN.to_string()), ..Default::default() };
+    Client::tracked(build_rocket(config, dbm).expect("build rocket")).expect("valid rocket")
+}
+
+/// `SyncTransport` over Rocket's local client, in place of `HttpTransport`.
+struct LocalTransport<'a>(&'a Client);
+
+impl LocalTransport<'_> {
+    fn post<T: serde::de::DeserializeOwned + Send + 'static>(&self, path: &str, secret: Option<&str>, body: String) -> Result<T> {
+        let mut req = self.0.post(path.to_string()).header(ContentType::JSON).body(body);
+        if let Some(secret) = secret {
+            req = req.header(Header::new("Authorization", format!("Bearer {}", secret)));
+        }
+        let resp = req.dispatch();
+        if resp.status() != Status::Ok {
+            bail!("{} returned {}", path, resp.status());
+        }
+        Ok(resp.into_json().expect("json body"))
+    }
+}
+
+impl SyncTransport for LocalTransport<'_> {
+    fn pair(&self, _url: &str, req: &PairRequest) -> Result<PairResponse> {
+        self.post("/v1/sync/pair", None, serde_json::to_string(req)?)
+    }
+
+    fn exchange(&self, _url: &str, secret: &str, req: &SyncRequest) -> Result<SyncResponse> {
+        self.post("/v1/sync/exchange", Some(secret), serde_json::to_string(req)?)
+    }
+}
+
+fn exec(dbm: &DbManager, sql: &str) {
+    dbm.appdata.get_conn().expect("conn").batch_execute(sql).expect("sql");
+}
+
+#[derive(QueryableByName)]
+struct Row {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    s: String,
+}
+
+fn strings(dbm: &DbManager, sql: &str) -> Vec<String> {
+    let rows: Vec<Row> = diesel::sql_query(sql).load(&mut dbm.appdata.get_conn().expect("conn")).expect("query");
+    rows.into_iter().map(|r| r.s).collect()
+}
+
+fn folder_names(dbm: &DbManager) -> Vec<String> {
+    strings(dbm, "SELECT name AS s FROM bookmark_folders WHERE is_last_session = 0 ORDER BY id")
+}
+
+fn new_code(client: &Client) -> String {
+    let resp = client.post("/v1/sync/pairing-code")
+        .header(Header::new("Authorization", format!("Bearer {}", TOKEN)))
+        .dispatch();
+    assert_eq!(resp.status(), Status::Created);
+    let code: Value = resp.into_json().expect("json body");
+    code["code"].as_str().expect("code").to_string()
+}
+
+fn sync_now(phone: &DbManager, transport: &LocalTransport<'_>, device_id: &str) -> SyncReport {
+    let peer = sync::find_peer(phone, device_id).expect("peer");
+    sync::sync_with(phone, transport, &peer).expect("sync")
+}
+
+#[test]
+fn pairing_and_auth() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let desktop = device(dir.path(), "desktop");
+    let phone = device(dir.path(), "phone");
+    let client = server(desktop.clone());
+    let transport = LocalTransport(&client);
+
+    // Only the local user can create codes.
+    assert_eq!(client.post("/v1/sync/pairing-code").dispatch().status(), Status::Unauthorized);
+
+    // A wrong code is rejected and cancels the open one.
+    let code = new_code(&client);
+    assert!(sync::pair_with(&phone, &transport, "http://desktop", "000000x").is_err());
+    assert!(sync::pair_with(&phone, &transport, "http://desktop", &code).is_err());
+
+    let code = new_code(&client);
+    let peer = sync::pair_with(&phone, &transport, "http://desktop", &code).expect("pair");
+    assert_eq!(peer.name, "desktop");
+    assert!(sync::pair_with(&phone, &transport, "http://desktop", &code).is_err(), "codes are one-time");
+
+    // The peer list has the phone, without its secret.
+    let resp = client.get("/v1/sync/peers")
+        .header(Header::new("Authorization", format!("Bearer {}", TOKEN)))
+        .dispatch();
+    assert_eq!(resp.status(), Status::Ok);
+    let peers: Value = resp.into_json().expect("json body");
+    assert_eq!(peers[0]["name"], json!("phone"));
+    assert!(peers[0].get("secret").is_none());
+
+    // The exchange needs the pair's secret, and the id it was given to.
+    let (phone_id, _) = sync::device_identity(&phone).expect("identity");
+    let body = json!({ "device_id": phone_id, "since": 0, "records": [] }).to_string();
+    let resp = client.post("/v1/sync/exchange").header(ContentType::JSON).body(body)
+        .header(Header::new("Authorization", "Bearer wrong"))
+        .dispatch();
+    assert_eq!(resp.status(), Status::Unauthorized);
+
+    let resp = client.post("/v1/sync/exchange").header(ContentType::JSON)
+        .body(json!({ "device_id": "someone-else", "since": 0, "records": [] }).to_string())
+        .header(Header::new("Authorization", format!("Bearer {}", peer.secret)))
+        .dispatch();
+    assert_eq!(resp.status(), Status::BadRequest);
+
+    // The first sync sends the settings, after that there is nothing to send.
+    sync_now(&phone, &transport, &peer.device_id);
+    assert_eq!(sync_now(&phone, &transport, &peer.device_id), SyncReport::default());
+
+    // Unpaired, the secret stops working.
+    let (desktop_id, _) = sync::device_identity(&desktop).expect("identity");
+    let resp = client.delete(format!("/v1/sync/peers/{}", phone_id))
+        .header(Header::new("Authorization", format!("Bearer {}", TOKEN)))
+        .dispatch();
+    assert_eq!(resp.status(), Status::NoContent);
+    let peer = sync::find_peer(&phone, &desktop_id).expect("peer");
+    assert!(sync::sync_with(&phone, &transport, &peer).is_err());
+}
+
+#[test]
+fn lan_listener_serves_only_the_peer_routes() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let desktop = device(dir.path(), "desktop");
+    let phone = device(dir.path(), "phone");
+    let config = ApiServerConfig { bind: "0.0.0.0".to_string(), api_token: Some(TOKEN.to_string()), ..Default::default() };
+    let lan = Client::tracked(build_sync_rocket(config, desktop.clone()).expect("build rocket")).expect("valid rocket");
+    let transport = LocalTransport(&lan);
+
+    // The code is created by the local user, then used from the LAN.
+    let code = sync::create_pairing_code(&desktop).expect("code").code;
+    let peer = sync::pair_with(&phone, &transport, "http://desktop", &code).expect("pair");
+    sync_now(&phone, &transport, &peer.device_id);
+
+    // Not the routes of the local user, even with the token.
+    let auth = || Header::new("Authorization", format!("Bearer {}", TOKEN));
+    assert_eq!(lan.post("/v1/sync/pairing-code").header(auth()).dispatch().status(), Status::NotFound);
+    assert_eq!(lan.get("/v1/sync/peers").header(auth()).dispatch().status(), Status::NotFound);
+    assert_eq!(lan.get("/v1/bookmarks/folders").header(auth()).dispatch().status(), Status::NotFound);
+    assert_eq!(lan.get("/search_suggestions?q=sati").dispatch().status(), Status::NotFound);
+}
+
+#[test]
+fn sync_both_ways() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let desktop = device(dir.path(), "desktop");
+    let phone = device(dir.path(), "phone");
+    let client = server(desktop.clone());
+    let transport = LocalTransport(&client);
+
+    let code = new_code(&client);
+    let desktop_id = sync::pair_with(&phone, &transport, "http://desktop", &code).expect("pair").device_id;
+
+    exec(&desktop, r#"
+        INSERT INTO bookmark_folders (name, sort_order) VALUES ('Reading', 0);
+        INSERT INTO bookmark_folders (name, sort_order, is_last_session) VALUES ('Last session', 1, 1);
+        INSERT INTO bookmark_items (folder_id, item_uid, table_name, tab_group, sort_order)
+            VALUES (1, 'mn1/pli/ms', 'suttas', '', 0), (1, 'mn2/pli/ms', 'suttas', '', 1), (2, 'sn1.1/pli/ms', 'suttas', '', 0);
+        INSERT INTO chanting_collections (uid, title, is_user_added) VALUES ('my-coll', 'Morning', 1), ('shipped', 'Shipped', 0);
+        INSERT INTO chanting_chants (uid, collection_uid, title) VALUES ('my-chant', 'my-coll', 'Refuges');
+        INSERT INTO chanting_sections (uid, chant_uid, title, content_pali) VALUES ('my-sec', 'my-chant', 'One', 'Buddhaṃ saraṇaṃ gacchāmi');
+        INSERT INTO app_settings (key, value) VALUES ('app_settings', '{"sutta_font_size": 26, "first_time_start": false}');
+    "#);
+    // The phone's own choice, which the desktop's default must not undo.
+    exec(&phone, r#"INSERT INTO app_settings (key, value) VALUES ('app_settings', '{"show_glosses": true}');"#);
+
+    sync_now(&phone, &transport, &desktop_id);
+
+    // The last session folder and the shipped collection stay on the desktop.
+    assert_eq!(folder_names(&phone), vec!["Reading"]);
+    assert_eq!(strings(&phone, "SELECT item_uid AS s FROM bookmark_items ORDER BY sort_order"), vec!["mn1/pli/ms", "mn2/pli/ms"]);
+    assert_eq!(strings(&phone, "SELECT title AS s FROM chanting_collections"), vec!["Morning"]);
+    assert_eq!(strings(&phone, "SELECT content_pali AS s FROM chanting_sections"), vec!["Buddhaṃ saraṇaṃ gacchāmi"]);
+
+    let settings = |dbm: &DbManager| -> Value {
+        serde_json::from_str(&strings(dbm, "SELECT value AS s FROM app_settings WHERE key = 'app_settings'")[0]).expect("settings")
+    };
+    assert_eq!(settings(&phone)["sutta_font_size"], json!(26));
+    assert_eq!(settings(&phone)["show_glosses"], json!(true));
+    assert_eq!(settings(&phone)["first_time_start"], json!(true), "a device's own settings are not synced");
+    assert_eq!(settings(&desktop)["show_glosses"], json!(true));
+
+    assert_eq!(sync_now(&phone, &transport, &desktop_id), SyncReport::default());
+
+    // Edits on the phone.
+    exec(&phone, r#"
+        UPDATE bookmark_folders SET name = 'To read';
+        DELETE FROM bookmark_items WHERE item_uid = 'mn2/pli/ms';
+        INSERT INTO gloss_prompts_history (item_type, data_json, created_at) VALUES ('gloss', '{}', CURRENT_TIMESTAMP);
+    "#);
+    sync_now(&phone, &transport, &desktop_id);
+
+    assert_eq!(folder_names(&desktop), vec!["To read"]);
+    assert_eq!(strings(&desktop, "SELECT item_uid AS s FROM bookmark_items ORDER BY id"), vec!["mn1/pli/ms", "sn1.1/pli/ms"]);
+    assert_eq!(strings(&desktop, "SELECT item_type AS s FROM gloss_prompts_history"), vec!["gloss"]);
+
+    // Both rename the folder, the phone later; the desktop also re-orders
+    // it after that. Each field keeps its last write.
+    exec(&desktop, "UPDATE bookmark_folders SET name = 'Desktop name' WHERE is_last_session = 0;");
+    sync::record_changes(&desktop).expect("record");
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    exec(&phone, "UPDATE bookmark_folders SET name = 'Phone name';");
+    sync::record_changes(&phone).expect("record");
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    exec(&desktop, "UPDATE bookmark_folders SET sort_order = 5 WHERE is_last_session = 0;");
+    sync::record_changes(&desktop).expect("record");
+
+    sync_now(&phone, &transport, &desktop_id);
+    for dbm in [&desktop, &phone] {
+        assert_eq!(
+            strings(dbm, "SELECT name || ':' || sort_order AS s FROM bookmark_folders WHERE is_last_session = 0"),
+            vec!["Phone name:5"]
+        );
+    }
+
+    // A delete on the desktop removes the folder and its items on the phone.
+    exec(&desktop, "DELETE FROM bookmark_folders WHERE is_last_session = 0;");
+    sync_now(&phone, &transport, &desktop_id);
+    assert!(folder_names(&phone).is_empty());
+    assert!(strings(&phone, "SELECT item_uid AS s FROM bookmark_items").is_empty());
+
+    // A new folder may get the deleted one's id, it is still a new row.
+    exec(&phone, "INSERT INTO bookmark_folders (name, sort_order, created_at) VALUES ('New', 0, '2030-01-01 00:00:00');");
+    sync_now(&phone, &transport, &desktop_id);
+    assert_eq!(folder_names(&desktop), vec!["New"]);
+    assert_eq!(sync_now(&phone, &transport, &desktop_id), SyncReport::default());
+}
+
+#[test]
+fn sync_through_a_shared_folder() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let shared = dir.path().join("Sync");
+    let desktop = device(dir.path(), "desktop");
+    let phone = device(dir.path(), "phone");
+    let laptop = device(dir.path(), "laptop");
+
+    assert!(sync::sync_folder(&desktop, &shared).is_err(), "a missing folder is not created");
+    std::fs::create_dir(&shared).expect("mkdir");
+
+    exec(&desktop, r#"
+        INSERT INTO bookmark_folders (name, sort_order) VALUES ('Reading', 0);
+        INSERT INTO bookmark_items (folder_id, item_uid, table_name, tab_group, sort_order) VALUES (1, 'mn1/pli/ms', 'suttas', '', 0);
+    "#);
+    let report = sync::sync_folder(&desktop, &shared).expect("sync");
+    assert_eq!((report.devices_read, report.written), (0, true));
+
+    // Each device writes only its own sub-folder, one file per entity.
+    let (desktop_id, _) = sync::device_identity(&desktop).expect("identity");
+    let rows = std::fs::read_to_string(shared.join(&desktop_id).join("bookmark_folder.ndjson")).expect("rows");
+    let row: Value = serde_json::from_str(rows.lines().next().expect("a row")).expect("json");
+    assert_eq!(row["fields"]["name"]["value"], json!("Reading"));
+    assert_eq!(row["fields"]["name"]["device_id"], json!(desktop_id));
+
+    let report = sync::sync_folder(&phone, &shared).expect("sync");
+    assert_eq!((report.devices_read, report.applied), (1, 2));
+    assert_eq!(folder_names(&phone), vec!["Reading"]);
+
+    // Nothing changed: the desktop skips the phone's snapshot of the same
+    // data once it has read it, and doesn't rewrite its own.
+    sync::sync_folder(&desktop, &shared).expect("sync");
+    assert_eq!(sync::sync_folder(&desktop, &shared).expect("sync"), sync::FolderSyncReport::default());
+
+    // The desktop is gone; the laptop still gets the phone's rename and the
+    // desktop's item through the phone's snapshot.
+    std::fs::remove_dir_all(shared.join(&desktop_id)).expect("rm");
+    exec(&phone, "UPDATE bookmark_folders SET name = 'To read';");
+    sync::sync_folder(&phone, &shared).expect("sync");
+    sync::sync_folder(&laptop, &shared).expect("sync");
+    assert_eq!(folder_names(&laptop), vec!["To read"]);
+    assert_eq!(strings(&laptop, "SELECT item_uid AS s FROM bookmark_items"), vec!["mn1/pli/ms"]);
+
+    // A snapshot in a newer format is left alone.
+    let (laptop_id, _) = sync::device_identity(&laptop).expect("identity");
+    let manifest_path = shared.join(&laptop_id).join("device.json");
+    let mut manifest: Value = serde_json::from_str(&std::fs::read_to_string(&manifest_path).expect("manifest")).expect("json");
+    manifest["format"] = json!(sync::folder::FOLDER_FORMAT + 1);
+    manifest["seq"] = json!(1_000_000);
+    std::fs::write(&manifest_path, manifest.to_string()).expect("write");
+    assert_eq!(sync::sync_folder(&phone, &shared).expect("sync").devices_read, 0);
+}
+
+#[test]
+fn sync_state_survives_a_database_upgrade() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let shared = dir.path().join("Sync");
+    std::fs::create_dir(&shared).expect("mkdir");
+    let desktop = device(dir.path(), "desktop");
+    let phone = device(dir.path(), "phone");
+
+    exec(&desktop, r#"
+        INSERT INTO bookmark_folders (name, sort_order) VALUES ('Reading', 0);
+        INSERT INTO bookmark_items (folder_id, item_uid, table_name, tab_group, sort_order) VALUES (1, 'mn1/pli/ms', 'suttas', '', 0);
+        INSERT INTO gloss_prompts_history (item_type, data_json, created_at) VALUES ('gloss', '{}', CURRENT_TIMESTAMP);
+    "#);
+    sync::sync_folder(&desktop, &shared).expect("sync");
+    sync::sync_folder(&phone, &shared).expect("sync");
+
+    let saved = dir.path().join("import-me").join("sync");
+    sync::folder::export_for_upgrade(&desktop, &saved).expect("export");
+
+    // The new database, with the bookmarks imported under other ids and
+    // without the history.
+    let upgraded = device(dir.path(), "upgraded");
+    exec(&upgraded, r#"
+        INSERT INTO bookmark_folders (name, sort_order) VALUES ('Old', 0), ('Reading', 0);
+        DELETE FROM bookmark_folders WHERE name = 'Old';
+        INSERT INTO bookmark_items (folder_id, item_uid, table_name, tab_group, sort_order) VALUES (2, 'mn1/pli/ms', 'suttas', '', 0);
+    "#);
+    sync::folder::import_after_upgrade(&upgraded, &saved).expect("import");
+
+    assert_eq!(sync::device_identity(&upgraded).expect("identity"), sync::device_identity(&desktop).expect("identity"));
+    assert_eq!(strings(&upgraded, "SELECT item_type AS s FROM gloss_prompts_history"), vec!["gloss"]);
+
+    // Nothing looks new or deleted to the phone.
+    assert_eq!(sync::record_changes(&upgraded).expect("record"), 0);
+    sync::sync_folder(&upgraded, &shared).expect("sync");
+    assert_eq!(sync::sync_folder(&phone, &shared).expect("sync").applied, 0);
+    assert_eq!(folder_names(&phone), vec!["Reading"]);
+}
@@ -53,6 +53,7 @@ fn main() {
         "../assets/qml/ListBackground.qml",
         "../assets/qml/WordSummary.qml",
         "../assets/qml/StorageDialog.qml",
+        "../assets/qml/DownloadSettingsDialog.qml",
         "../assets/qml/GlossTab.qml",
         "../assets/qml/PromptsTab.qml",
         "../assets/qml/AssistantResponses.qml",
@@ -69,6 +70,7 @@ fn main() {
         "../assets/qml/ChantingTreeList.qml",
         "../assets/qml/RecordingPlaybackItem.qml",
         "../assets/qml/WaveformView.qml",
+        "../assets/qml/ChantingKaraokeText.qml",
         "../assets/qml/BookmarksTab.qml",
         "../assets/qml/BookmarkFolderItem.qml",
         "../assets/qml/BookmarkListItem.qml",
@@ -76,6 +78,10 @@ fn main() {
         "../assets/qml/HistoryUtils.qml",
         "../assets/qml/BookmarkEditDialog.qml",
         "../assets/qml/BookmarkFolderDialog.qml",
+        "../assets/qml/ReadingPlansDialog.qml",
+        "../assets/qml/SavedSearchesDialog.qml",
+        "../assets/qml/SearchHistoryDialog.qml",
+        "../assets/qml/SyncDialog.qml",
         "../assets/qml/DictionaryIndexProgressWindow.qml",
         "../assets/qml/DictionariesWindow.qml",
         "../assets/qml/DictionaryListItem.qml",
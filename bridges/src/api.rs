@@ -1,79 +1,29 @@
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::io::{Read, Write};
 use std::net::TcpStream;
 use std::time::Duration;
 use std::sync::{OnceLock, Arc};
 
-use rocket::serde::{Deserialize, Serialize};
+use rocket::serde::Deserialize;
 use rocket::serde::json::Json;
 
 use http;
 use ureq;
-use rocket::{get, post, routes, State, Shutdown};
+use rocket::{get, post, routes, State};
 use rocket::response::content::RawHtml;
-use rocket::http::{ContentType, Status};
-use rocket_cors::CorsOptions;
+use rocket::http::Status;
 
 use simsapa_backend::{AppGlobals, get_app_data, get_create_simsapa_dir, get_create_simsapa_appdata_db_path, save_to_file, create_parent_directory};
 use simsapa_backend::html_content::sutta_html_page;
 use simsapa_backend::dir_list::generate_html_directory_listing;
 use simsapa_backend::db::DbManager;
-use simsapa_backend::db::appdata_models::Sutta;
-use simsapa_backend::helpers::{create_or_update_linux_desktop_icon_file, query_text_to_uid_field_query, verse_sutta_ref_to_uid, normalize_human_word_uid};
-use simsapa_backend::logger::{info, warn, error, profile};
-use simsapa_backend::types::{SearchResult, SearchParams, SearchMode, SearchArea};
-use simsapa_backend::query_task::SearchQueryTask;
-
-// ============================================================================
-// Browser Extension API Data Structures
-// ============================================================================
-
-/// Response structure for search endpoints (suttas and dictionary)
-/// Matches the Python `ApiSearchResult` TypedDict for browser extension compatibility
-#[derive(Debug, Clone, Serialize)]
-pub struct ApiSearchResult {
-    pub hits: i32,
-    pub results: Vec<SearchResult>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub deconstructor: Option<Vec<String>>,
-}
+use simsapa_backend::helpers::create_or_update_linux_desktop_icon_file;
+use simsapa_backend::logger::{info, error};
+use simsapa_backend::api_server::{build_rocket, build_sync_rocket, load_or_create_api_token, ApiServerConfig};
+use simsapa_backend::api_server::routes::{pathbuf_to_forward_slash_string, convert_verse_ref_to_sutta_uid, lookup_sutta_with_fallback};
 
-/// Request body for POST search endpoints
-///
-/// All fields beyond `query_text` are optional; serde deserializes missing
-/// `Option` fields to `None`, so existing clients that omit `mode`,
-/// `search_area`, `page_len`, `show_all_snippets`, or `snippet_exclude` keep
-/// working unchanged. See docs/simsapa-localhost-api-search-endpoints.md.
-#[derive(Debug, Clone, Deserialize)]
-pub struct ApiSearchRequest {
-    pub query_text: String,
-    pub page_num: Option<i32>,
-    pub suttas_lang: Option<String>,
-    pub suttas_lang_include: Option<bool>,
-    pub dict_lang: Option<String>,
-    pub dict_lang_include: Option<bool>,
-    pub dict_dict: Option<String>,
-    pub dict_dict_include: Option<bool>,
-    /// Exact `SearchMode` serde label, e.g. "Fulltext Match", "Contains Match",
-    /// "Combined", "DPD Lookup". Parsed via `parse_search_mode`.
-    pub mode: Option<String>,
-    /// Exact `SearchArea` serde label: "Suttas", "Library", "Dictionary".
-    /// Parsed via `parse_search_area`.
-    pub search_area: Option<String>,
-    pub page_len: Option<i32>,
-    pub show_all_snippets: Option<bool>,
-    /// Already-split list of exclusion strings (the API client sends an array,
-    /// not a CSV string; CSV-splitting is a QML/UI concern).
-    pub snippet_exclude: Option<Vec<String>>,
-}
-
-/// Response structure for /sutta_and_dict_search_options endpoint
-#[derive(Debug, Clone, Serialize)]
-pub struct SearchOptions {
-    pub sutta_languages: Vec<String>,
-    pub dict_languages: Vec<String>,
-    pub dict_sources: Vec<String>,
-}
+// The routes which don't call back into the Qt app live in
+// simsapa_backend::api_server::routes, shared with `simsapa_cli serve`.
 
 /// Request body for POST /lookup_window_query endpoint
 #[derive(Debug, Clone, Deserialize)]
@@ -81,34 +31,6 @@ pub struct LookupWindowRequest {
     pub query_text: String,
 }
 
-/// Map a request `mode` string (exact `SearchMode` serde label) to the enum.
-/// Returns `None` for an unrecognized value (caller returns HTTP 400).
-fn parse_search_mode(s: &str) -> Option<SearchMode> {
-    match s {
-        "Combined" => Some(SearchMode::Combined),
-        "Fulltext Match" => Some(SearchMode::FulltextMatch),
-        "Contains Match" => Some(SearchMode::ContainsMatch),
-        "Headword Match" => Some(SearchMode::HeadwordMatch),
-        "Title Match" => Some(SearchMode::TitleMatch),
-        "DPD ID Match" => Some(SearchMode::DpdIdMatch),
-        "DPD Lookup" => Some(SearchMode::DpdLookup),
-        "Uid Match" => Some(SearchMode::UidMatch),
-        "RegEx Match" => Some(SearchMode::RegExMatch),
-        _ => None,
-    }
-}
-
-/// Map a request `search_area` string (exact `SearchArea` serde label) to the
-/// enum. Returns `None` for an unrecognized value (caller returns HTTP 400).
-fn parse_search_area(s: &str) -> Option<SearchArea> {
-    match s {
-        "Suttas" => Some(SearchArea::Suttas),
-        "Library" => Some(SearchArea::Library),
-        "Dictionary" => Some(SearchArea::Dictionary),
-        _ => None,
-    }
-}
-
 pub static APP_GLOBALS_API: OnceLock<AppGlobals> = OnceLock::new();
 
 pub fn init_app_globals_api() {
@@ -122,88 +44,6 @@ pub fn get_app_globals_api() -> &'static AppGlobals {
     APP_GLOBALS_API.get().expect("AppGlobals (in API) is not initialized")
 }
 
-/// Convert a Path to a string using forward slashes as separators.
-/// On Windows, paths use backslashes, but URLs and database paths use forward slashes.
-/// This ensures consistent path handling across all platforms.
-///
-/// NOTE: this joins path *components*, so an absolute path gains a doubled
-/// leading slash (`/home/...` → `//home/...`) — `Path::iter()` yields the root
-/// `/` as its first component and the join adds another. That is harmless for
-/// the **relative** `<uid..>` segments this is used to normalize (they never
-/// start at the root), but for a real absolute file path use
-/// `fs_path_to_forward_slash` instead.
-fn pathbuf_to_forward_slash_string(path: &Path) -> String {
-    path.iter()
-        .map(|s| s.to_str().unwrap_or(""))
-        .collect::<Vec<_>>()
-        .join("/")
-}
-
-/// Convert an absolute filesystem path to a forward-slash string, preserving a
-/// single leading slash (no doubling). Use this for real file paths (e.g. the
-/// `/health` `db_paths`); on Windows it also maps `\` → `/` and keeps a UNC
-/// `\\server` as `//server`.
-fn fs_path_to_forward_slash(path: &Path) -> String {
-    path.to_string_lossy().replace('\\', "/")
-}
-
-/// Convert verse references to actual sutta UIDs.
-/// E.g., "thag179/pli/ms" -> "thag2.30/pli/ms", "dhp34/pli/ms" -> "dhp33-43/pli/ms"
-/// Returns the original UID if no conversion is needed.
-fn convert_verse_ref_to_sutta_uid(uid_str: &str) -> String {
-    // Extract the sutta code without language/author (before first '/')
-    let code = uid_str.split('/').next().unwrap_or(uid_str);
-
-    // Try to convert verse reference to sutta UID (e.g., "thag179" -> "thag2.30")
-    if let Some(converted_uid) = verse_sutta_ref_to_uid(code) {
-        // Preserve the language/author part if present (e.g., keep "/pli/ms")
-        if uid_str.contains('/') {
-            let parts: Vec<&str> = uid_str.splitn(2, '/').collect();
-            if parts.len() == 2 {
-                format!("{}/{}", converted_uid, parts[1])
-            } else {
-                format!("{}/pli/ms", converted_uid)
-            }
-        } else {
-            format!("{}/pli/ms", converted_uid)
-        }
-    } else {
-        uid_str.to_string()
-    }
-}
-
-/// Look up a sutta by UID with fallback to /pli/ms version.
-/// Returns the sutta if found, or None if not found.
-fn lookup_sutta_with_fallback(dbm: &DbManager, uid_str: &str) -> Option<Sutta> {
-    // Try to get sutta with the given UID
-    let sutta_option = dbm.appdata.get_sutta(uid_str);
-
-    // If not found and not already pli/ms, try fallback
-    if sutta_option.is_none() && !uid_str.ends_with("/pli/ms") {
-        // Extract code (e.g., "sn47.8" from "sn47.8/en/thanissaro")
-        let code = uid_str.split('/').next().unwrap_or(uid_str);
-        let fallback_uid = format!("{}/pli/ms", code);
-
-        // Try to get fallback sutta
-        if let Some(fallback_sutta) = dbm.appdata.get_sutta(&fallback_uid) {
-            info(&format!("Using fallback UID: {}", fallback_uid));
-            return Some(fallback_sutta);
-        }
-    }
-
-    // Still not found: the uid may be a single reference (e.g. "sn45.92/pli/ms")
-    // that falls within a stored range (e.g. "sn45.92-95/pli/ms"). This mirrors
-    // the range lookup done for the search input box.
-    if sutta_option.is_none() {
-        if let Some(range_sutta) = dbm.appdata.get_sutta_by_range(uid_str) {
-            info(&format!("Using range UID: {} for {}", range_sutta.uid, uid_str));
-            return Some(range_sutta);
-        }
-    }
-
-    sutta_option
-}
-
 #[cxx_qt::bridge]
 pub mod ffi {
     unsafe extern "C++" {
@@ -236,95 +76,6 @@ pub mod ffi {
     }
 }
 
-static APP_ASSETS: include_dir::Dir<'_> = include_dir::include_dir!("$CARGO_MANIFEST_DIR/../assets/");
-
-#[derive(Debug)]
-pub struct AssetsHandler {
-    files: &'static include_dir::Dir<'static>,
-}
-
-impl Default for AssetsHandler {
-    fn default() -> Self {
-        let files = &APP_ASSETS;
-        Self { files }
-    }
-}
-
-#[get("/assets/<path..>")]
-fn serve_assets(path: PathBuf, assets: &State<AssetsHandler>) -> (Status, (ContentType, Vec<u8>)) {
-    // Convert path to forward slashes for cross-platform consistency
-    let path_str = pathbuf_to_forward_slash_string(&path);
-    // Also log the raw PathBuf for debugging Windows path issues
-    // info(&format!("serve_assets: path_str='{}', raw_path='{:?}'", path_str, path));
-
-    let some_entry = assets.files.get_entry(&path_str);
-
-    if let Some(entry) = some_entry {
-        if let Some(entry_file) = entry.as_file() {
-
-            let p = PathBuf::from(&path_str);
-            let path_ext = match p.extension() {
-                Some(s) => s.to_str().unwrap_or("txt"),
-                None => "txt",
-            };
-
-            let content_type = match path_ext {
-                "css" => ContentType::CSS,
-                "js" | "mjs" => ContentType::JavaScript,
-                "json" => ContentType::JSON,
-                "svg" => ContentType::SVG,
-                "png" => ContentType::PNG,
-                "jpg" | "jpeg" => ContentType::JPEG,
-                "gif" => ContentType::GIF,
-                "woff" | "woff2" => ContentType::WOFF,
-                "ttf" => ContentType::TTF,
-                "otf" => ContentType::OTF,
-                "html" | "htm" => ContentType::HTML,
-                "wasm" => ContentType::WASM,
-                "pdf" => ContentType::PDF,
-                "map" => ContentType::JSON, // Source maps
-                "ico" => ContentType::Icon,
-                _ => ContentType::from_extension(path_ext).unwrap_or(ContentType::Plain),
-            };
-
-            let body = Vec::from(entry_file.contents());
-
-            (Status::Ok, (content_type, body))
-
-        } else {
-            let s = format!{"404 Not Found: {}", path_str};
-            let ret = Vec::from(s.as_bytes());
-            (Status::NotFound, (ContentType::Plain, ret))
-        }
-
-    } else {
-        let s = format!{"404 Not Found: {}", path_str};
-        let ret = Vec::from(s.as_bytes());
-        (Status::NotFound, (ContentType::Plain, ret))
-    }
-}
-
-#[get("/favicon.ico")]
-fn serve_favicon(assets: &State<AssetsHandler>) -> (Status, (ContentType, Vec<u8>)) {
-    let path_str = "icons/appicons/simsapa.ico";
-    let some_entry = assets.files.get_entry(path_str);
-
-    if let Some(entry) = some_entry {
-        if let Some(entry_file) = entry.as_file() {
-            let body = Vec::from(entry_file.contents());
-            (Status::Ok, (ContentType::Icon, body))
-        } else {
-            let s = "404 Not Found: favicon.ico".to_string();
-            let ret = Vec::from(s.as_bytes());
-            (Status::NotFound, (ContentType::Plain, ret))
-        }
-    } else {
-        let s = "404 Not Found: favicon.ico".to_string();
-        let ret = Vec::from(s.as_bytes());
-        (Status::NotFound, (ContentType::Plain, ret))
-    }
-}
-
 #[get("/lookup_window_query/<text>")]
 fn lookup_window_query(text: &str) -> Status {
     ffi::callback_run_lookup_query(ffi::QString::from(text));
@@ -582,24 +333,6 @@ fn next_sutta(window_id: &str, current_sutta_uid: PathBuf, dbm: &State<Arc<DbMan
     Status::Ok
 }
 
-#[derive(Deserialize)]
-struct LoggerRequest {
-    log_level: String,
-    msg: String,
-}
-
-#[post("/logger", data = "<req>")]
-fn logger_route(req: Json<LoggerRequest>) -> Status {
-    match req.log_level.as_str() {
-        "info" => info(&req.msg),
-        "warn" => warn(&req.msg),
-        "error" => error(&req.msg),
-        "profile" => profile(&req.msg),
-        _ => {},
-    }
-    Status::Ok
-}
-
 #[derive(Deserialize)]
 struct CopyToClipboardRequest {
     text: String,
@@ -653,159 +386,6 @@ fn app_assets_list() -> RawHtml<String> {
     RawHtml(sutta_html_page(&html, None, None, None, None))
 }
 
-#[get("/")]
-fn index() -> RawHtml<String> {
-    let html = r#"
-<!doctype html>
-<html lang="en">
-<head>
-  <meta charset="utf-8">
-  <title>Simsapa Dhamma Reader</title>
-</head>
-<body>
-  <h1>Simsapa Dhamma Reader</h1>
-</body>
-</html>
-"#.to_string();
-
-    RawHtml(html)
-}
-
-#[get("/shutdown")]
-fn shutdown(shutdown: Shutdown) {
-    shutdown.notify();
-    info("Webserver shutting down...")
-}
-
-
-/// Shared body for the sutta-HTML routes. Normalizes verse refs and resolves
-/// `/pli/ms` / range fallbacks via `lookup_sutta_with_fallback` (Finding 3),
-/// then renders. Returns `200` when the sutta exists, `404` (with the prior
-/// blank-page body) on a genuine miss — the success body is unchanged.
-fn sutta_html_response(window_id: &str, uid: &str, anchor: Option<&str>, dbm: &DbManager) -> (Status, RawHtml<String>) {
-    // Show reference anchors only when navigating to a specific anchor
-    let show_references = anchor.is_some();
-    let app_data = get_app_data();
-    let processed_uid = convert_verse_ref_to_sutta_uid(uid);
-
-    match lookup_sutta_with_fallback(dbm, &processed_uid) {
-        Some(sutta) => {
-            let html = app_data.render_sutta_html_by_uid(window_id, &sutta.uid, show_references);
-            (Status::Ok, RawHtml(html))
-        }
-        None => {
-            // Keep the prior blank-page body; add the 404 status signal.
-            let html = app_data.render_sutta_html_by_uid(window_id, &processed_uid, show_references);
-            (Status::NotFound, RawHtml(html))
-        }
-    }
-}
-
-/// Shared body for the word-HTML routes. Renders via the resolver-backed
-/// renderer and returns `200` when the word resolves, `404` (with the prior
-/// blank-page body) on a miss — the success body is unchanged.
-fn word_html_response(window_id: &str, uid: &str) -> (Status, RawHtml<String>) {
-    let app_data = get_app_data();
-    let html = app_data.render_word_html_by_uid(window_id, uid);
-    let status = if app_data.resolve_word_uid(uid).is_some() {
-        Status::Ok
-    } else {
-        Status::NotFound
-    };
-    (status, RawHtml(html))
-}
-
-#[get("/get_sutta_html_by_uid/<window_id>/<uid..>?<anchor>")]
-fn get_sutta_html_by_uid(window_id: &str, uid: PathBuf, anchor: Option<&str>, dbm: &State<Arc<DbManager>>) -> (Status, RawHtml<String>) {
-    // Convert path to forward slashes for cross-platform consistency
-    let uid_str = pathbuf_to_forward_slash_string(&uid);
-
-    let log_msg = if let Some(a) = anchor {
-        format!("get_sutta_html_by_uid(): window_id: {}, uid: {}, anchor: {}", window_id, uid_str, a)
-    } else {
-        format!("get_sutta_html_by_uid(): window_id: {}, uid: {}", window_id, uid_str)
-    };
-    info(&log_msg);
-
-    sutta_html_response(window_id, &uid_str, anchor, dbm)
-}
-
-#[get("/get_word_html_by_uid/<window_id>/<uid..>")]
-fn get_word_html_by_uid(window_id: &str, uid: PathBuf, _dbm: &State<Arc<DbManager>>) -> (Status, RawHtml<String>) {
-    // Convert path to forward slashes for cross-platform consistency
-    let uid_str = pathbuf_to_forward_slash_string(&uid);
-    info(&format!("get_word_html_by_uid(): window_id: {}, uid: {}", window_id, uid_str));
-
-    word_html_response(window_id, &uid_str)
-}
-
-#[get("/get_book_spine_item_html_by_uid/<window_id>/<spine_item_uid..>")]
-fn get_book_spine_item_html_by_uid(window_id: &str, spine_item_uid: PathBuf, _dbm: &State<Arc<DbManager>>) -> RawHtml<String> {
-    // Convert path to forward slashes for cross-platform consistency
-    let uid_str = pathbuf_to_forward_slash_string(&spine_item_uid);
-    info(&format!("get_book_spine_item_html_by_uid(): {}", uid_str));
-
-    let app_data = get_app_data();
-    let html = app_data.render_book_spine_html_by_uid(window_id, &uid_str);
-
-    RawHtml(html)
-}
-
-/// Serve PDF viewer page for a PDF book - for browser testing
-/// URL: /get_pdf_viewer/<book_uid>
-/// This generates the same URL that the QML view would load
-#[get("/get_pdf_viewer/<book_uid>")]
-fn get_pdf_viewer(book_uid: &str) -> RawHtml<String> {
-    let g = get_app_globals_api();
-    let api_url = format!("http://localhost:{}", g.api_port);
-    let pdf_url = format!("{}/book_resources/{}/document.pdf", api_url, book_uid);
-    // URL encode the pdf_url for use as query parameter
-    let encoded_pdf_url = pdf_url.replace(":", "%3A").replace("/", "%2F");
-    let viewer_url = format!("{}/assets/pdf-viewer/web/viewer.html?file={}", api_url, encoded_pdf_url);
-
-    // Return a simple redirect page
-    let html = format!(r#"<!DOCTYPE html>
-<html>
-<head>
-    <meta charset="utf-8">
-    <title>PDF Viewer - {}</title>
-    <meta http-equiv="refresh" content="0; url={}">
-</head>
-<body>
-    <p>Loading PDF viewer...</p>
-    <p>If not redirected, <a href="{}">click here</a></p>
-    <p>Debug info:</p>
-    <ul>
-        <li>Book UID: {}</li>
-        <li>PDF URL: {}</li>
-        <li>Viewer URL: {}</li>
-    </ul>
-</body>
-</html>"#, book_uid, viewer_url, viewer_url, book_uid, pdf_url, viewer_url);
-
-    RawHtml(html)
-}
-
-#[get("/book_pages/<book_uid>/<resource_path..>")]
-fn get_book_page_by_path(book_uid: &str, resource_path: PathBuf, dbm: &State<Arc<DbManager>>) -> Result<RawHtml<String>, (Status, String)> {
-    // Convert path to forward slashes for cross-platform consistency
-    let resource_path_str = pathbuf_to_forward_slash_string(&resource_path);
-    info(&format!("get_book_page_by_path(): {}/{}", book_uid, resource_path_str));
-
-    let item = match dbm.appdata.get_book_spine_item_by_path(book_uid, &resource_path_str) {
-        Ok(Some(item)) => item,
-        Ok(None) => return Err((Status::NotFound, format!("BookSpineItem Not Found for path: {}", resource_path_str))),
-        Err(e) => return Err((Status::InternalServerError, format!("Database error: {}", e))),
-    };
-
-    let app_data = get_app_data();
-    if let Ok(html) = app_data.render_book_spine_item_html(&item, None, None) {
-        Ok(RawHtml(html))
-    } else {
-        Err((Status::InternalServerError, "HTML rendering error".to_string()))
-    }
-}
-
 #[get("/open_sutta_window/<uid..>")]
 fn open_sutta_window(uid: PathBuf, dbm: &State<Arc<DbManager>>) -> Status {
     // Convert path to forward slashes for cross-platform consistency
@@ -884,458 +464,6 @@ fn open_sutta_tab(window_id: &str, uid: PathBuf, anchor: Option<&str>, dbm: &Sta
     }
 }
 
-/// Serve book resources (images, CSS, PDFs, etc.) from the database
-#[get("/book_resources/<book_uid>/<path..>")]
-fn serve_book_resources(book_uid: &str, path: PathBuf, db_manager: &State<Arc<DbManager>>) -> (Status, (ContentType, Vec<u8>)) {
-    // Convert path to forward slashes for cross-platform consistency
-    let path_str = pathbuf_to_forward_slash_string(&path);
-    info(&format!("serve_book_resources: book_uid={}, path={}", book_uid, path_str));
-
-    // Query the database for the resource
-    match db_manager.appdata.get_book_resource(book_uid, &path_str) {
-        Ok(Some(resource)) => {
-            // Determine ContentType from MIME type
-            let content_type = if let Some(ref mime) = resource.mime_type {
-                match mime.as_str() {
-                    "image/png" => ContentType::PNG,
-                    "image/jpeg" | "image/jpg" => ContentType::JPEG,
-                    "image/gif" => ContentType::GIF,
-                    "image/svg+xml" => ContentType::SVG,
-                    "image/webp" => ContentType::WEBP,
-                    "text/css" => ContentType::CSS,
-                    "application/javascript" | "text/javascript" => ContentType::JavaScript,
-                    "application/pdf" => ContentType::PDF,
-                    "font/woff" | "font/woff2" => ContentType::WOFF,
-                    "font/ttf" => ContentType::TTF,
-                    "font/otf" => ContentType::OTF,
-                    _ => ContentType::Binary,
-                }
-            } else {
-                ContentType::Binary
-            };
-
-            // Return the resource data
-            let data = resource.content_data.unwrap_or_default();
-            (Status::Ok, (content_type, data))
-        }
-        Ok(None) => {
-            // Resource not found
-            let msg = format!("404 Not Found: /book_resources/{}/{}", book_uid, path_str);
-            warn(&msg);
-            let ret = Vec::from(msg.as_bytes());
-            (Status::NotFound, (ContentType::Plain, ret))
-        }
-        Err(e) => {
-            // Database error
-            let msg = format!("500 Internal Server Error: {}", e);
-            error(&msg);
-            let ret = Vec::from(msg.as_bytes());
-            (Status::InternalServerError, (ContentType::Plain, ret))
-        }
-    }
-}
-
-/// Serve user-imported StarDict resources (images, fonts, etc.) from the
-/// dictionaries database. Keyed by the dictionary `id` (stable across rename),
-/// modeled on `serve_book_resources`. CSS/JS are injected inline at render time
-/// rather than served here, but are also reachable through this route.
-#[get("/dict_resources/<dict_id>/<path..>")]
-fn serve_dict_resources(dict_id: i32, path: PathBuf, db_manager: &State<Arc<DbManager>>) -> (Status, (ContentType, Vec<u8>)) {
-    let path_str = pathbuf_to_forward_slash_string(&path);
-    info(&format!("serve_dict_resources: dict_id={}, path={}", dict_id, path_str));
-
-    match db_manager.dictionaries.get_dict_resource(dict_id, &path_str) {
-        Ok(Some(resource)) => {
-            let content_type = if let Some(ref mime) = resource.mime_type {
-                match mime.as_str() {
-                    "image/png" => ContentType::PNG,
-                    "image/jpeg" | "image/jpg" => ContentType::JPEG,
-                    "image/gif" => ContentType::GIF,
-                    "image/svg+xml" => ContentType::SVG,
-                    "image/webp" => ContentType::WEBP,
-                    "text/css" => ContentType::CSS,
-                    "application/javascript" | "text/javascript" => ContentType::JavaScript,
-                    "application/pdf" => ContentType::PDF,
-                    "font/woff" | "font/woff2" => ContentType::WOFF,
-                    "font/ttf" => ContentType::TTF,
-                    "font/otf" => ContentType::OTF,
-                    _ => ContentType::Binary,
-                }
-            } else {
-                ContentType::Binary
-            };
-
-            let data = resource.content_data.unwrap_or_default();
-            (Status::Ok, (content_type, data))
-        }
-        Ok(None) => {
-            let msg = format!("404 Not Found: /dict_resources/{}/{}", dict_id, path_str);
-            warn(&msg);
-            let ret = Vec::from(msg.as_bytes());
-            (Status::NotFound, (ContentType::Plain, ret))
-        }
-        Err(e) => {
-            let msg = format!("500 Internal Server Error: {}", e);
-            error(&msg);
-            let ret = Vec::from(msg.as_bytes());
-            (Status::InternalServerError, (ContentType::Plain, ret))
-        }
-    }
-}
-
-// ============================================================================
-// Browser Extension API Routes
-// ============================================================================
-
-/// GET /sutta_and_dict_search_options
-/// Returns available filter options for sutta and dictionary searches
-#[get("/sutta_and_dict_search_options")]
-fn get_search_options(dbm: &State<Arc<DbManager>>) -> Json<SearchOptions> {
-    let sutta_languages = dbm.appdata.get_sutta_languages();
-    let dict_languages = dbm.dictionaries.get_distinct_languages();
-    let dict_sources = dbm.dictionaries.get_distinct_sources();
-
-    Json(SearchOptions {
-        sutta_languages,
-        dict_languages,
-        dict_sources,
-    })
-}
-
-/// Build a `SearchParams` from the request, the resolved `mode`, and the search
-/// `area`. For Suttas/Library this applies the suttas language filter; for
-/// Dictionary it applies the dict language + source filters. `page_len`
-/// defaults to 20 (the browser-extension default). `show_all_snippets` /
-/// `snippet_exclude` are read straight from the request (the backend only
-/// applies them for Suttas/Library). All other fields keep their defaults.
-/// See docs/simsapa-localhost-api-search-endpoints.md.
-fn build_search_params(request: &ApiSearchRequest, mode: SearchMode, area: &SearchArea) -> SearchParams {
-    // "Languages"/"Language" (and empty) are the no-filter placeholders; same
-    // for "Dictionaries"/"Dictionary" on the source filter.
-    let (lang, lang_include, source, source_include) = match area {
-        SearchArea::Dictionary => {
-            let lang = match &request.dict_lang {
-                Some(lang) if lang != "Languages" && lang != "Language" && !lang.is_empty() => Some(lang.clone()),
-                _ => None,
-            };
-            let source = match &request.dict_dict {
-                Some(source) if source != "Dictionaries" && source != "Dictionary" && !source.is_empty() => Some(source.clone()),
-                _ => None,
-            };
-            (lang, request.dict_lang_include.unwrap_or(true), source, request.dict_dict_include.unwrap_or(true))
-        }
-        _ => {
-            let lang = match &request.suttas_lang {
-                Some(lang) if lang != "Languages" && lang != "Language" && !lang.is_empty() => Some(lang.clone()),
-                _ => None,
-            };
-            (lang, request.suttas_lang_include.unwrap_or(true), None, true)
-        }
-    };
-
-    SearchParams {
-        mode,
-        page_len: Some(request.page_len.unwrap_or(20) as usize),
-        lang,
-        lang_include,
-        source,
-        source_include,
-        enable_regex: false,
-        fuzzy_distance: 0,
-        include_cst_mula: true,
-        include_cst_commentary: true,
-        nikaya_prefix: None,
-        uid_prefix: None,
-        uid_suffix: None,
-        include_ms_mula: true,
-        include_comm_bold_definitions: true,
-        dict_source_uids: None,
-        show_all_snippets: request.show_all_snippets.unwrap_or(false),
-        snippet_exclude: request.snippet_exclude.clone(),
-    }
-}
-
-/// Construct and run a `SearchQueryTask`, returning the API JSON. On error this
-/// logs and returns an empty result set (preserving the prior route behaviour).
-///
-/// Lazily initializes the process-global fulltext searcher (idempotent,
-/// mode-gated) immediately before running FulltextMatch / Combined queries: the
-/// webserver shares the GUI's `FULLTEXT_SEARCHER`, and the query path returns
-/// silent-empty if it was never initialized (e.g. a curl request before QML
-/// `load_searcher` ran). Non-fulltext modes never touch the index.
-/// See docs/simsapa-localhost-api-search-endpoints.md.
-fn run_search(
-    dbm: &Arc<DbManager>,
-    query_text: String,
-    params: SearchParams,
-    area: SearchArea,
-    page_num: usize,
-    deconstructor: Option<Vec<String>>,
-) -> Json<ApiSearchResult> {
-    if matches!(params.mode, SearchMode::FulltextMatch | SearchMode::Combined) {
-        simsapa_backend::init_fulltext_searcher();
-    }
-
-    let mut search_task = SearchQueryTask::new(dbm, query_text, params, area);
-
-    match search_task.results_page(page_num) {
-        Ok(results) => {
-            let hits = search_task.total_hits() as i32;
-            Json(ApiSearchResult { hits, results, deconstructor })
-        }
-        Err(e) => {
-            error(&format!("run_search error: {}", e));
-            Json(ApiSearchResult { hits: 0, results: Vec::new(), deconstructor })
-        }
-    }
-}
-
-/// Run a search whose mode may have been chosen by the reference/uid
-/// auto-detect (`query_text_to_uid_field_query`), with a self-correcting
-/// fallback: when the **auto-detected** `UidMatch` run returns 0 hits (because
-/// the human form differs from the stored uid, e.g. `dhamma 1.01` vs
-/// `dhamma-1-01/dpd`), transparently re-run the *original* query under
-/// `fallback_mode` (`DpdLookup` for dictionary, `FulltextMatch`/`ContainsMatch`
-/// for suttas) before returning.
-///
-/// Back-compat: the fallback only fires on `was_uid_auto && hits == 0`, so any
-/// query that returns ≥1 hit today — and any *explicitly* requested mode
-/// (`was_uid_auto = false`) — is untouched. `run_search` consumes its
-/// `query_text`/`params` by value, so the original query and a freshly built
-/// params set are passed for the re-run (Finding 2). See
-/// docs/simsapa-localhost-api-search-endpoints.md.
-#[allow(clippy::too_many_arguments)]
-fn run_search_with_uid_fallback(
-    dbm: &Arc<DbManager>,
-    request: &ApiSearchRequest,
-    area: SearchArea,
-    page_num: usize,
-    was_uid_auto: bool,
-    primary_query: String,
-    primary_mode: SearchMode,
-    query_text_orig: String,
-    fallback_mode: SearchMode,
-    deconstructor: Option<Vec<String>>,
-) -> Json<ApiSearchResult> {
-    let params = build_search_params(request, primary_mode, &area);
-    let result = run_search(dbm, primary_query, params, area.clone(), page_num, deconstructor.clone());
-
-    if was_uid_auto && result.0.hits == 0 {
-        info(&format!(
-            "auto-detected UidMatch returned 0 hits; re-running '{}' as {:?}",
-            query_text_orig, fallback_mode
-        ));
-        let params = build_search_params(request, fallback_mode, &area);
-        return run_search(dbm, query_text_orig, params, area, page_num, deconstructor);
-    }
-
-    result
-}
-
-/// Dictionary combined search with the self-correcting UID auto-detect (P4).
-///
-/// The primary run is `primary_mode` on `primary_query`. When an
-/// **auto-detected** `UidMatch` returns 0 hits (the human form differs from the
-/// stored uid), fall back in order to: (1) `UidMatch` on the *normalized* uid
-/// (Task 1.3 — e.g. `dhamma 1.01` → `uid:dhamma-1-01/dpd`, the exact headword;
-/// a raw `DpdLookup` of `dhamma 1.01` finds nothing because of the number), then
-/// (2) `DpdLookup` on the original query as a last resort. So a uid-like query
-/// never silently returns 0. Only fires on `was_uid_auto && hits == 0`; any
-/// query that returns ≥1 hit, and any explicitly requested mode, is untouched.
-/// `run_search` consumes its args by value, so each attempt rebuilds params and
-/// passes a fresh query string (Finding 2). See
-/// docs/simsapa-localhost-api-search-endpoints.md.
-fn run_dict_combined_with_fallback(
-    dbm: &Arc<DbManager>,
-    request: &ApiSearchRequest,
-    page_num: usize,
-    was_uid_auto: bool,
-    primary_query: String,
-    primary_mode: SearchMode,
-    query_text_orig: String,
-    deconstructor: Option<Vec<String>>,
-) -> Json<ApiSearchResult> {
-    let area = SearchArea::Dictionary;
-    let params = build_search_params(request, primary_mode, &area);
-    let result = run_search(dbm, primary_query, params, area.clone(), page_num, deconstructor.clone());
-
-    if !(was_uid_auto && result.0.hits == 0) {
-        return result;
-    }
-
-    // Fallback 1: normalized UidMatch — the exact entry for a numbered display form.
-    let normalized = normalize_human_word_uid(&query_text_orig);
-    if !normalized.is_empty() {
-        let params = build_search_params(request, SearchMode::UidMatch, &area);
-        let r2 = run_search(dbm, format!("uid:{}", normalized), params, area.clone(), page_num, deconstructor.clone());
-        if r2.0.hits > 0 {
-            info(&format!("auto UidMatch 0-hit; normalized UidMatch 'uid:{}' -> {} hits", normalized, r2.0.hits));
-            return r2;
-        }
-    }
-
-    // Fallback 2: DpdLookup on the original query.
-    info(&format!("auto UidMatch 0-hit; re-running '{}' as DpdLookup", query_text_orig));
-    let params = build_search_params(request, SearchMode::DpdLookup, &area);
-    run_search(dbm, query_text_orig, params, area, page_num, deconstructor)
-}
-
-/// Shared body for the named Suttas search routes. Runs the sutta-reference →
-/// `UidMatch` auto-detect (`query_text_to_uid_field_query`); for ordinary
-/// queries it uses `fallback_mode` (FulltextMatch for `/suttas_fulltext_search`,
-/// ContainsMatch for `/suttas_contains_search`). Builds params via
-/// `build_search_params` and executes via `run_search` (deconstructor `None`).
-/// See docs/simsapa-localhost-api-search-endpoints.md.
-fn run_suttas_search(request: &ApiSearchRequest, dbm: &Arc<DbManager>, fallback_mode: SearchMode) -> Json<ApiSearchResult> {
-    let query_text_orig = request.query_text.clone();
-    let page_num = request.page_num.unwrap_or(0) as usize;
-
-    // Reference auto-detect: query_text_to_uid_field_query returns "uid:..." for
-    // reference-like queries (e.g. "sn56.11", "MN 44", "dhp182"). When that auto
-    // UidMatch finds nothing, run_search_with_uid_fallback re-runs the original
-    // query under fallback_mode (self-correcting, no silent 0-hit).
-    let uid_query = query_text_to_uid_field_query(&query_text_orig);
-    let was_uid_auto = uid_query.starts_with("uid:");
-    let (query_text, mode) = if was_uid_auto {
-        (uid_query, SearchMode::UidMatch)
-    } else {
-        (query_text_orig.clone(), fallback_mode.clone())
-    };
-
-    info(&format!("run_suttas_search(): query='{}', page={}, mode={:?}", query_text, page_num, mode));
-
-    run_search_with_uid_fallback(
-        dbm, request, SearchArea::Suttas, page_num,
-        was_uid_auto, query_text, mode, query_text_orig, fallback_mode, None,
-    )
-}
-
-/// POST /suttas_fulltext_search
-/// Search suttas using FulltextMatch (tantivy), or UidMatch for reference-like
-/// queries. See docs/simsapa-localhost-api-search-endpoints.md.
-#[post("/suttas_fulltext_search", data = "<request>")]
-fn suttas_fulltext_search(request: Json<ApiSearchRequest>, dbm: &State<Arc<DbManager>>) -> Json<ApiSearchResult> {
-    run_suttas_search(&request, dbm.inner(), SearchMode::FulltextMatch)
-}
-
-/// POST /suttas_contains_search
-/// Search suttas using ContainsMatch (literal substring), or UidMatch for
-/// reference-like queries. See docs/simsapa-localhost-api-search-endpoints.md.
-#[post("/suttas_contains_search", data = "<request>")]
-fn suttas_contains_search(request: Json<ApiSearchRequest>, dbm: &State<Arc<DbManager>>) -> Json<ApiSearchResult> {
-    run_suttas_search(&request, dbm.inner(), SearchMode::ContainsMatch)
-}
-
-/// POST /search
-/// General search route: runs any `mode` in any `search_area`. `search_area`
-/// defaults to "Suttas"; `mode` defaults per area (Suttas/Library →
-/// "Fulltext Match", Dictionary → "Combined"). An unrecognized `mode` /
-/// `search_area` returns HTTP 400. Unlike the named convenience routes, `/search`
-/// honors the requested mode strictly (no reference → UidMatch override).
-/// See docs/simsapa-localhost-api-search-endpoints.md.
-#[post("/search", data = "<request>")]
-fn search(request: Json<ApiSearchRequest>, dbm: &State<Arc<DbManager>>) -> Result<Json<ApiSearchResult>, (Status, String)> {
-    let query_text_orig = request.query_text.clone();
-    let page_num = request.page_num.unwrap_or(0) as usize;
-
-    // Resolve search area: default Suttas, unknown → 400.
-    let area = match &request.search_area {
-        Some(s) => parse_search_area(s)
-            .ok_or_else(|| (Status::BadRequest, format!("Unknown search_area: '{}'", s)))?,
-        None => SearchArea::Suttas,
-    };
-
-    // Resolve mode: parse if present (unknown → 400), else area-specific default
-    // (Suttas/Library → FulltextMatch, Dictionary → Combined).
-    let mode = match &request.mode {
-        Some(m) => parse_search_mode(m)
-            .ok_or_else(|| (Status::BadRequest, format!("Unknown mode: '{}'", m)))?,
-        None => match area {
-            SearchArea::Dictionary => SearchMode::Combined,
-            _ => SearchMode::FulltextMatch,
-        },
-    };
-
-    // Dictionary "Combined" is bridge-orchestrated and must NOT reach
-    // SearchQueryTask as Combined (it errors there). Map it to the
-    // /dict_combined_search behaviour: UID pattern → UidMatch (self-correcting
-    // to normalized-UidMatch / DpdLookup on a 0-hit), else DpdLookup directly.
-    // For Suttas/Library, Combined is handled inside results_page (→
-    // FulltextMatch). Only this auto-resolved UidMatch falls back; an explicitly
-    // requested mode stays strict.
-    let dict_combined = area == SearchArea::Dictionary && mode == SearchMode::Combined;
-
-    // Dictionary returns the deconstructor (same as /dict_combined_search),
-    // computed on the original query; for Suttas/Library it is None.
-    let deconstructor = if area == SearchArea::Dictionary {
-        let deconstructor_results = dbm.dpd.dpd_deconstructor_list(&query_text_orig);
-        if deconstructor_results.is_empty() {
-            None
-        } else {
-            Some(deconstructor_results)
-        }
-    } else {
-        None
-    };
-
-    if dict_combined {
-        let uid_query = query_text_to_uid_field_query(&query_text_orig);
-        let was_uid_auto = uid_query.starts_with("uid:");
-        let (query_text, mode) = if was_uid_auto {
-            (uid_query, SearchMode::UidMatch)
-        } else {
-            (query_text_orig.clone(), SearchMode::DpdLookup)
-        };
-        info(&format!("search(): query='{}', page={}, area=Dictionary(Combined), mode={:?}", query_text, page_num, mode));
-        return Ok(run_dict_combined_with_fallback(
-            dbm.inner(), &request, page_num,
-            was_uid_auto, query_text, mode, query_text_orig, deconstructor,
-        ));
-    }
-
-    // Explicit mode / non-dictionary area: strict, single run (no fallback).
-    info(&format!("search(): query='{}', page={}, area={:?}, mode={:?}", query_text_orig, page_num, area, mode));
-    let params = build_search_params(&request, mode, &area);
-    Ok(run_search(dbm.inner(), query_text_orig, params, area, page_num, deconstructor))
-}
-
-/// POST /dict_combined_search
-/// Search dictionary words with language and source filtering, includes deconstructor results
-#[post("/dict_combined_search", data = "<request>")]
-fn dict_combined_search(request: Json<ApiSearchRequest>, dbm: &State<Arc<DbManager>>) -> Json<ApiSearchResult> {
-    let query_text_orig = request.query_text.clone();
-    let page_num = request.page_num.unwrap_or(0) as usize;
-
-    // Auto-detect: query_text_to_uid_field_query returns "uid:..." for uid-like
-    // queries, routed to UidMatch; otherwise DpdLookup is the default (same as
-    // SuttaSearchWindow QML) — searches DPD headwords by lemma. The auto UidMatch
-    // is self-correcting: a human form (e.g. "dhamma 1.01") that doesn't match a
-    // stored uid yields 0 hits, so run_search_with_uid_fallback transparently
-    // re-runs the original query as DpdLookup (no silent 0-hit).
-    let uid_query = query_text_to_uid_field_query(&query_text_orig);
-    let was_uid_auto = uid_query.starts_with("uid:");
-    let (query_text, mode) = if was_uid_auto {
-        (uid_query, SearchMode::UidMatch)
-    } else {
-        (query_text_orig.clone(), SearchMode::DpdLookup)
-    };
-
-    // Deconstructor results for the original query (not the uid: prefixed version).
-    let deconstructor_results = dbm.dpd.dpd_deconstructor_list(&query_text_orig);
-    let deconstructor = if deconstructor_results.is_empty() {
-        None
-    } else {
-        Some(deconstructor_results)
-    };
-
-    info(&format!("dict_combined_search(): query='{}', page={}, mode={:?}", query_text, page_num, mode));
-
-    run_dict_combined_with_fallback(
-        dbm.inner(), &request, page_num,
-        was_uid_auto, query_text, mode, query_text_orig, deconstructor,
-    )
-}
-
 /// GET /suttas/<uid>
 /// Open a sutta in the Simsapa application window (browser extension route)
 /// Returns plain text message for the browser tab
@@ -1434,235 +562,60 @@ fn lookup_window_query_post(request: Json<LookupWindowRequest>, dbm: &State<Arc<
     Status::Ok
 }
 
-/// Whether an opt-in `?verbose=` flag is truthy (`1` / `true`).
-fn is_verbose_flag(verbose: Option<&str>) -> bool {
-    matches!(verbose, Some(v) if v == "1" || v == "true")
-}
-
-/// Verbose envelope for a resolved word (opt-in `?verbose=1`). Pure (no DB).
-fn word_hit_verbose_envelope(uid: &str, canonical_uid: &str, result: &serde_json::Value) -> serde_json::Value {
-    serde_json::json!({
-        "found": true,
-        "query_uid": uid,
-        "canonical_uid": canonical_uid,
-        "result": result,
-    })
-}
-
-/// Verbose envelope for a missing word (opt-in `?verbose=1`). Pure (no DB): the
-/// hint lists the tried normalized form and points at `/health`.
-fn word_miss_verbose_envelope(uid: &str) -> serde_json::Value {
-    let normalized = normalize_human_word_uid(uid);
-    let tried = if normalized.is_empty() || normalized == uid {
-        uid.to_string()
-    } else {
-        format!("{}, {}", uid, normalized)
-    };
-    serde_json::json!({
-        "found": false,
-        "query_uid": uid,
-        "canonical_uid": serde_json::Value::Null,
-        "hint": format!("no word for this uid; tried {}. Is the source dict installed? See /health.", tried),
-    })
-}
-
-/// Shared body for the word-JSON routes: resolve `uid`, then return either the
-/// default bare array or (when `verbose`) a self-describing envelope.
-///
-/// Back-compat (Hard rule 2): the **default** (non-verbose) response is
-/// byte-identical to before — a one-element array on hit, the empty array `[]`
-/// on miss — so a body-reading client (e.g. an installed browser extension) is
-/// unaffected. The only added signal is the **status**: `200` on hit, `404` on
-/// miss (the `[]` body is still present). `?verbose=1` is a separate opt-in
-/// shape. See docs/simsapa-localhost-api-search-endpoints.md.
-fn word_json_response(uid: &str, verbose: Option<&str>) -> (Status, Json<serde_json::Value>) {
-    let resolved = get_app_data().resolve_word_uid(uid);
-    let verbose = is_verbose_flag(verbose);
-
-    match resolved {
-        Some(rw) => {
-            if verbose {
-                (Status::Ok, Json(word_hit_verbose_envelope(uid, rw.canonical_uid(), rw.as_json())))
-            } else {
-                (Status::Ok, Json(serde_json::Value::Array(vec![rw.as_json().clone()])))
-            }
-        }
-        None => {
-            if verbose {
-                (Status::NotFound, Json(word_miss_verbose_envelope(uid)))
-            } else {
-                // Hard rule 2: keep the prior empty-array body on a miss.
-                (Status::NotFound, Json(serde_json::Value::Array(Vec::new())))
-            }
-        }
-    }
-}
-
-#[get("/words/<uid_with_ext..>?<verbose>")]
-fn get_word_json(uid_with_ext: PathBuf, verbose: Option<&str>) -> (Status, Json<serde_json::Value>) {
-    // Convert path to forward slashes and remove .json extension
-    let uid_str = pathbuf_to_forward_slash_string(&uid_with_ext);
-    let uid = uid_str.trim_end_matches(".json");
-
-    info(&format!("get_word_json(): uid={}", uid));
-
-    // Delegate to the shared resolver so the JSON route tolerates the same uid
-    // forms as the HTML route (numeric "<id>/dpd", human "dhamma 1.01", etc.).
-    // The two-lane invariant holds ("<id>/dpd" -> dpd_headwords row, human/lemma
-    // forms -> dict_words row). See AppData::resolve_word_uid.
-    word_json_response(uid, verbose)
-}
-
-/// GET /word.json?<uid>
-/// Encoding-agnostic variant of `/words/<uid..>.json`. Rocket decodes query
-/// strings fully (including `%2F` / `%20`), unlike the `<uid..>` path segments,
-/// so a caller can pass the uid exactly as it appears in a `SearchResult.uid`,
-/// with or without percent-encoding. Delegates to the same resolver as the path
-/// route; the existing `/words/<uid..>` route is unchanged. See
-/// docs/simsapa-localhost-api-search-endpoints.md.
-#[get("/word.json?<uid>&<verbose>")]
-fn get_word_json_q(uid: &str, verbose: Option<&str>) -> (Status, Json<serde_json::Value>) {
-    let uid = uid.trim_end_matches(".json");
-    info(&format!("get_word_json_q(): uid={}", uid));
-
-    word_json_response(uid, verbose)
-}
-
-/// GET /word_html?<window_id>&<uid>
-/// Encoding-agnostic variant of `/get_word_html_by_uid/<window_id>/<uid..>`,
-/// delegating to the same resolver-backed renderer. The path route is unchanged.
-#[get("/word_html?<window_id>&<uid>")]
-fn get_word_html_q(window_id: &str, uid: &str) -> (Status, RawHtml<String>) {
-    info(&format!("get_word_html_q(): window_id: {}, uid: {}", window_id, uid));
-
-    word_html_response(window_id, uid)
-}
-
-/// GET /sutta_html?<window_id>&<uid>&<anchor>
-/// Encoding-agnostic variant of `/get_sutta_html_by_uid/<window_id>/<uid..>`,
-/// so a `%2F` / `%20` sutta uid has a tolerant query-param form. Sutta
-/// existence/normalization reuses `convert_verse_ref_to_sutta_uid` +
-/// `lookup_sutta_with_fallback` (not the word resolver), so verse refs,
-/// `/pli/ms` fallback and ranges resolve to the canonical uid before rendering.
-#[get("/sutta_html?<window_id>&<uid>&<anchor>")]
-fn get_sutta_html_q(window_id: &str, uid: &str, anchor: Option<&str>, dbm: &State<Arc<DbManager>>) -> (Status, RawHtml<String>) {
-    info(&format!("get_sutta_html_q(): window_id: {}, uid: {}", window_id, uid));
-
-    sutta_html_response(window_id, uid, anchor, dbm)
-}
-
-/// GET /sutta_titles_flat_completion_list
-/// Returns list of sutta titles for autocomplete (placeholder - returns empty array)
-/// TODO: Future implementation should query sutta titles from database with Pali sort order
-#[get("/sutta_titles_flat_completion_list")]
-fn sutta_titles_completion() -> Json<Vec<String>> {
-    // Placeholder: return empty array
-    // Future implementation could query sutta titles sorted by Pali order
-    Json(Vec::new())
-}
-
-/// GET /dict_words_flat_completion_list
-/// Returns list of dictionary words for autocomplete (placeholder - returns empty array)
-/// TODO: Future implementation could query DPD lemmas and roots
-/// Note: The browser extension currently loads this from a bundled JSON file
-#[get("/dict_words_flat_completion_list")]
-fn dict_words_completion() -> Json<Vec<String>> {
-    // Placeholder: return empty array
-    // The browser extension has a fallback bundled word list
-    Json(Vec::new())
-}
-
-/// Row counts for `/health`. Each is `Option`: `null` means the count query
-/// errored (Finding 5), a real `0` means the DB is loaded but empty / not
-/// installed (consistent with `fulltext_searcher_ready: false`).
-#[derive(Debug, Clone, Serialize)]
-pub struct HealthCounts {
-    pub suttas: Option<i64>,
-    pub dict_words: Option<i64>,
-    pub dpd_headwords: Option<i64>,
-}
-
-/// Absolute on-disk DB paths (forward-slash normalized) for `/health`.
-#[derive(Debug, Clone, Serialize)]
-pub struct HealthDbPaths {
-    pub appdata: String,
-    pub dictionaries: String,
-    pub dpd: String,
-}
-
-/// The `/health` document: a single read-once snapshot of the running instance.
-#[derive(Debug, Clone, Serialize)]
-pub struct HealthInfo {
-    pub app_version: String,
-    pub api_port: i32,
-    pub db_paths: HealthDbPaths,
-    pub fulltext_searcher_ready: bool,
-    pub counts: HealthCounts,
-    pub sutta_languages: Vec<String>,
-    pub dict_sources: Vec<String>,
-}
-
-/// GET /health
-/// Environment / readiness snapshot so a headless caller can learn version,
-/// live port, DB paths, fulltext-searcher readiness, row counts, languages and
-/// dictionary sources in one call (instead of probing several endpoints).
-/// `GET /` stays the landing page. See docs/simsapa-localhost-api-search-endpoints.md.
-#[get("/health")]
-fn health(dbm: &State<Arc<DbManager>>) -> Json<HealthInfo> {
-    let g = get_app_globals_api();
-
-    let info_doc = HealthInfo {
-        app_version: simsapa_backend::update_checker::get_app_version(),
-        api_port: g.api_port,
-        db_paths: HealthDbPaths {
-            appdata: fs_path_to_forward_slash(&g.paths.appdata_abs_path),
-            dictionaries: fs_path_to_forward_slash(&g.paths.dict_abs_path),
-            dpd: fs_path_to_forward_slash(&g.paths.dpd_abs_path),
-        },
-        fulltext_searcher_ready: simsapa_backend::is_fulltext_searcher_ready(),
-        // A count error -> None -> null (Finding 5); a real empty DB -> Some(0).
-        counts: HealthCounts {
-            suttas: dbm.appdata.count_suttas().ok(),
-            dict_words: dbm.dictionaries.count_dict_words().ok(),
-            dpd_headwords: dbm.dpd.count_dpd_headwords().ok(),
-        },
-        sutta_languages: dbm.appdata.get_sutta_languages(),
-        dict_sources: dbm.dictionaries.get_distinct_sources(),
-    };
-
-    Json(info_doc)
-}
-
 #[rocket::main]
 #[unsafe(no_mangle)]
 pub async extern "C" fn start_webserver() {
     info("start_webserver()");
     init_app_globals_api();
-    let assets_files: AssetsHandler = AssetsHandler::default();
 
     let dbm = DbManager::new().expect("Api: Can't create DbManager");
     let db_manager = Arc::new(dbm);
     let g = get_app_globals_api();
 
-    let cors = CorsOptions::default().to_cors().expect("Cors options error");
+    // The bookmark and history routes need the token from api-token.txt, so
+    // that other web pages in the browser can't reach the user's data.
+    let api_token = match load_or_create_api_token(&g.paths.simsapa_api_token_path) {
+        Ok(token) => Some(token),
+        Err(e) => {
+            error(&format!("Api: user data routes disabled: {}", e));
+            None
+        }
+    };
 
-    let config = rocket::Config::figment()
-        .merge(("log_level", rocket::config::LogLevel::Off))
-        .merge(("address", "127.0.0.1"))
-        .merge(("port", g.api_port));
+    let config = ApiServerConfig {
+        port: g.api_port as u16,
+        api_token,
+        ..Default::default()
+    };
 
-    let _ = rocket::build()
-        .configure(config)
-        .attach(cors)
+    // With LAN sync turned on in the settings, a second listener serves only
+    // the pairing and exchange routes to the other devices. The app server
+    // stays on localhost.
+    let app_data = get_app_data();
+    let lan_server = if app_data.get_sync_lan_enabled() {
+        let lan_config = ApiServerConfig {
+            bind: "0.0.0.0".to_string(),
+            port: app_data.get_sync_lan_port(),
+            ..Default::default()
+        };
+        info(&format!("Api: serving the LAN sync on port {}", lan_config.port));
+        match build_sync_rocket(lan_config, db_manager.clone()) {
+            Ok(r) => r.ignite().await.map_err(|e| error(&format!("Api: LAN sync disabled: {}", e))).ok(),
+            Err(e) => {
+                error(&format!("Api: LAN sync disabled: {}", e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let lan_shutdown = lan_server.as_ref().map(|r| r.shutdown());
+    let lan_task = lan_server.map(|r| rocket::tokio::spawn(r.launch()));
+
+    let _ = build_rocket(config, db_manager)
+        .expect("Api: Can't build webserver")
         .mount("/", routes![
-            index,
-            shutdown,
             app_assets_list,
-            serve_assets,
-            serve_favicon,
-            serve_book_resources,
-            serve_dict_resources,
-            logger_route,
             copy_to_clipboard,
             open_external_url,
             lookup_window_query,
@@ -1670,11 +623,6 @@ pub async extern "C" fn start_webserver() {
             toggle_reading_mode,
             sutta_menu_action,
             dppn_lookup,
-            get_sutta_html_by_uid,
-            get_word_html_by_uid,
-            get_book_spine_item_html_by_uid,
-            get_pdf_viewer,
-            get_book_page_by_path,
             open_sutta_window,
             open_sutta_tab,
             open_book_page_tab,
@@ -1683,30 +631,25 @@ pub async extern "C" fn start_webserver() {
             prev_sutta,
             next_sutta,
             // Browser Extension API routes
-            get_search_options,
-            search,
-            suttas_fulltext_search,
-            suttas_contains_search,
-            dict_combined_search,
             open_sutta_by_uid,
             lookup_window_query_post,
-            get_word_json,
-            get_word_json_q,
-            get_word_html_q,
-            get_sutta_html_q,
-            sutta_titles_completion,
-            dict_words_completion,
-            health,
         ])
-        .manage(assets_files)
-        .manage(db_manager)
         .launch().await;
+
+    // The app server was shut down on exit, stop the LAN listener with it.
+    if let Some(shutdown) = lan_shutdown {
+        shutdown.notify();
+    }
+    if let Some(task) = lan_task
+        && let Ok(Err(e)) = task.await {
+        error(&format!("Api: LAN sync listener: {}", e));
+    }
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn shutdown_webserver() {
     let g = get_app_globals_api();
-    match ureq::get(format!("{}/shutdown", g.api_url.clone())).call() {
+    match ureq::post(format!("{}/shutdown", g.api_url.clone())).send_empty() {
         Ok(mut resp) => {
             match resp.body_mut().read_to_string() {
                 Ok(body) => { info(&body); }
@@ -1774,8 +717,8 @@ pub extern "C" fn shutdown_webserver_tcp() {
                 error(&format!("Error setting timeout: {}", e));
             }
 
-            // Construct and send the HTTP GET request
-            let request = "GET /shutdown HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n".to_string();
+            // Construct and send the HTTP POST request
+            let request = "POST /shutdown HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string();
             if let Err(e) = connection.write_all(request.as_bytes()) {
                 error(&format!("Error sending request: {}", e));
             }
@@ -1793,58 +736,3 @@ pub extern "C" fn shutdown_webserver_tcp() {
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_parse_search_mode() {
-        assert_eq!(parse_search_mode("Fulltext Match"), Some(SearchMode::FulltextMatch));
-        assert_eq!(parse_search_mode("Contains Match"), Some(SearchMode::ContainsMatch));
-        assert_eq!(parse_search_mode("Combined"), Some(SearchMode::Combined));
-        assert_eq!(parse_search_mode("DPD Lookup"), Some(SearchMode::DpdLookup));
-        assert_eq!(parse_search_mode("Uid Match"), Some(SearchMode::UidMatch));
-        assert_eq!(parse_search_mode("nonsense"), None);
-    }
-
-    #[test]
-    fn test_parse_search_area() {
-        assert_eq!(parse_search_area("Suttas"), Some(SearchArea::Suttas));
-        assert_eq!(parse_search_area("Library"), Some(SearchArea::Library));
-        assert_eq!(parse_search_area("Dictionary"), Some(SearchArea::Dictionary));
-        assert_eq!(parse_search_area("nonsense"), None);
-    }
-
-    #[test]
-    fn test_is_verbose_flag() {
-        assert!(is_verbose_flag(Some("1")));
-        assert!(is_verbose_flag(Some("true")));
-        assert!(!is_verbose_flag(Some("0")));
-        assert!(!is_verbose_flag(Some("")));
-        assert!(!is_verbose_flag(None));
-    }
-
-    #[test]
-    fn test_word_miss_verbose_envelope_shape() {
-        // Unknown uid -> { found:false, query_uid, canonical_uid:null, hint }.
-        let env = word_miss_verbose_envelope("dhamma 1.01");
-        assert_eq!(env["found"], serde_json::json!(false));
-        assert_eq!(env["query_uid"], serde_json::json!("dhamma 1.01"));
-        assert_eq!(env["canonical_uid"], serde_json::Value::Null);
-        let hint = env["hint"].as_str().expect("hint should be a string");
-        // The hint lists the tried normalized form and points at /health.
-        assert!(hint.contains("dhamma-1-01/dpd"), "hint should mention the normalized form: {hint}");
-        assert!(hint.contains("/health"), "hint should point at /health: {hint}");
-    }
-
-    #[test]
-    fn test_word_hit_verbose_envelope_shape() {
-        let result = serde_json::json!({ "uid": "dhamma-1-01/dpd", "word": "dhamma 1.01" });
-        let env = word_hit_verbose_envelope("dhamma 1.01", "dhamma-1-01/dpd", &result);
-        assert_eq!(env["found"], serde_json::json!(true));
-        assert_eq!(env["query_uid"], serde_json::json!("dhamma 1.01"));
-        assert_eq!(env["canonical_uid"], serde_json::json!("dhamma-1-01/dpd"));
-        assert_eq!(env["result"], result);
-    }
-}
@@ -1,19 +1,18 @@
 use std::thread;
-use std::fs::{File, create_dir_all, remove_file, remove_dir_all};
-use std::io::{self, Read, Write, BufReader};
+use std::fs::{File, create_dir_all, read_dir, remove_file, remove_dir_all};
+use std::io::{self, Read, BufReader};
 use std::path::Path;
 use std::error::Error;
-use std::time::Duration;
 
 use core::pin::Pin;
 use cxx_qt_lib::{QString, QStringList, QUrl};
 use cxx_qt::{CxxQtThread, Threading};
-use reqwest::blocking::Client;
 use bzip2::read::BzDecoder;
 use tar::Archive;
 
 use simsapa_backend::{move_folder_contents, AppGlobalPaths};
 use simsapa_backend::asset_helpers::import_suttas_lang_to_appdata;
+use simsapa_backend::downloader::{self, DownloadEvent, DownloadSettings, Downloader};
 use simsapa_backend::logger::{info, error};
 use simsapa_backend::app_settings::LANGUAGES_JSON;
 
@@ -79,6 +78,15 @@ pub mod qobject {
         #[qinvokable]
         fn remove_sutta_languages(self: Pin<&mut AssetManager>, language_codes: QStringList);
 
+        #[qinvokable]
+        fn stage_delta_update(self: Pin<&mut AssetManager>, source: QString, sha256: QString);
+
+        #[qinvokable]
+        fn get_download_settings_json(self: &AssetManager) -> QString;
+
+        #[qinvokable]
+        fn save_download_settings_json(self: &AssetManager, settings_json: &QString) -> QString;
+
         #[qsignal]
         #[cxx_name = "downloadProgressChanged"]
         fn download_progress_changed(self: Pin<&mut AssetManager>,
@@ -127,13 +135,20 @@ fn cleanup_on_failure(download_temp_folder: &Path, extract_temp_folder: &Path, a
         qo.as_mut().download_show_msg(cleanup_msg);
     }).unwrap();
 
-    // Remove download temp folder
-    if download_temp_folder.exists() {
-        if let Err(e) = remove_dir_all(download_temp_folder) {
-            error(&format!("Failed to remove download temp folder: {}", e));
-        } else {
-            info("Removed download temp folder");
+    // Remove the download temp folder, except the partial downloads, which
+    // the next attempt continues.
+    if let Ok(entries) = read_dir(download_temp_folder) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if downloader::is_partial_download(&path) {
+                continue;
+            }
+            let removed = if path.is_dir() { remove_dir_all(&path) } else { remove_file(&path) };
+            if let Err(e) = removed {
+                error(&format!("Failed to remove {}: {}", path.display(), e));
+            }
         }
+        info("Removed the download temp folder, kept the partial downloads");
     }
 
     // Remove extract temp folder
@@ -181,6 +196,26 @@ impl qobject::AssetManager {
         qobject::request_microphone_permission_impl();
     }
 
+    /// The mirrors, proxy and bandwidth limit of the downloads as JSON.
+    fn get_download_settings_json(&self) -> QString {
+        QString::from(serde_json::to_string(&DownloadSettings::load()).unwrap_or_default())
+    }
+
+    /// Save the download settings from JSON. Returns an error message, or
+    /// an empty string when they were saved.
+    fn save_download_settings_json(&self, settings_json: &QString) -> QString {
+        let result = serde_json::from_str::<DownloadSettings>(&settings_json.to_string())
+            .map_err(|e| e.to_string())
+            .and_then(|s| s.save().map_err(|e| format!("{:#}", e)));
+        match result {
+            Ok(()) => QString::from(""),
+            Err(e) => {
+                error(&format!("save_download_settings_json(): {}", e));
+                QString::from(&e)
+            }
+        }
+    }
+
     /// Get list of available language codes that can be downloaded
     /// Returns format: "code1|Name1|Count1,code2|Name2|Count2,..."
     fn get_available_languages(self: Pin<&mut Self>) -> QStringList {
@@ -327,6 +362,57 @@ impl qobject::AssetManager {
         });
     }
 
+    /// Download (when `source` is a URL) or copy a delta update of the
+    /// databases and stage it, to be applied on the next start. The `sha256`
+    /// of a download is from the release info, and may be empty for a file.
+    fn stage_delta_update(self: Pin<&mut Self>, source: QString, sha256: QString) {
+        use simsapa_backend::asset_delta;
+
+        let source = source.to_string();
+        let sha256 = sha256.to_string();
+        info(&format!("stage_delta_update(): {}", source));
+
+        let paths = AppGlobalPaths::new();
+        let qt_thread = self.qt_thread();
+
+        thread::spawn(move || {
+            let show_msg = |msg: String| {
+                qt_thread.queue(move |mut qo| {
+                    qo.as_mut().download_show_msg(QString::from(&msg));
+                }).unwrap();
+            };
+
+            let result = if source.starts_with("http://") || source.starts_with("https://") {
+                show_msg("Downloading the database update...".to_string());
+                asset_delta::download_delta(&source, &sha256, &paths.download_temp_folder)
+                    .and_then(|path| {
+                        let staged = asset_delta::stage_delta(&path, &paths.app_assets_dir);
+                        let _ = remove_file(&path);
+                        staged
+                    })
+            } else {
+                asset_delta::stage_delta(Path::new(&source), &paths.app_assets_dir)
+            };
+
+            let success = match result {
+                Ok(manifest) => {
+                    show_msg(format!(
+                        "The database update to {} will be applied when the app restarts.", manifest.to_version
+                    ));
+                    true
+                }
+                Err(e) => {
+                    error(&format!("stage_delta_update(): {:#}", e));
+                    show_msg(format!("The database update failed: {:#}", e));
+                    false
+                }
+            };
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().downloads_completed(success);
+            }).unwrap();
+        });
+    }
+
     fn download_urls_and_extract(self: Pin<&mut Self>, urls: QStringList, is_initial_setup: bool) {
         info(&format!("download_urls_and_extract(): {} urls", urls.len()));
 
@@ -375,18 +461,14 @@ impl qobject::AssetManager {
                     }
                 }
 
-                // Bound the DNS+TCP+TLS phase so a silent network stall surfaces
-                // as a retryable error instead of freezing the UI indefinitely.
-                // No overall timeout — database archives can be large and slow
-                // connections must be allowed to finish the body download.
-                let client = match Client::builder()
-                    .connect_timeout(Duration::from_secs(30))
-                    .build()
-                {
-                    Ok(c) => c,
+                // Mirrors, proxy and bandwidth limit from the download settings.
+                // An interrupted download continues from its partial file, in
+                // this call after a retry, or in the next one.
+                let dl = match Downloader::new(DownloadSettings::load()) {
+                    Ok(d) => d,
                     Err(e) => {
-                        error(&format!("Failed to build HTTP client: {}", e));
-                        let msg = QString::from(&format!("Failed to build HTTP client: {}", e));
+                        error(&format!("Failed to build HTTP client: {:#}", e));
+                        let msg = QString::from(&format!("Failed to build HTTP client: {:#}", e));
                         qt_thread.queue(move |mut qo| {
                             qo.as_mut().download_show_msg(msg);
                         }).unwrap();
@@ -395,113 +477,55 @@ impl qobject::AssetManager {
                     }
                 };
 
-                // Retry logic: try up to 5 times with exponential backoff
-                const MAX_RETRIES: u32 = 5;
-                let mut retry_count = 0;
-                let mut resp = None;
-
-                while retry_count < MAX_RETRIES {
-                    let connecting_msg = QString::from(&format!(
-                        "Connecting to download {}... (attempt {}/{})",
-                        &download_file_name, retry_count + 1, MAX_RETRIES
-                    ));
-                    qt_thread.queue(move |mut qo| {
-                        qo.as_mut().download_show_msg(connecting_msg);
-                    }).unwrap();
-
-                    match client.get(&url_str).send() {
-                        Ok(r) => {
-                            resp = Some(r);
-                            break;
+                let op_msg = format!("Downloading {}", &download_file_name);
+                let result = dl.download(&url_str, &download_temp_file_path, &mut |event| {
+                    match event {
+                        DownloadEvent::Connecting { url, attempt, max_attempts } => {
+                            let host = reqwest::Url::parse(&url).ok()
+                                .and_then(|u| u.host_str().map(String::from))
+                                .unwrap_or(url);
+                            let msg = QString::from(&format!(
+                                "Connecting to {} to download {}... (attempt {}/{})",
+                                host, &download_file_name, attempt, max_attempts
+                            ));
+                            qt_thread.queue(move |mut qo| {
+                                qo.as_mut().download_show_msg(msg);
+                            }).unwrap();
                         }
-                        Err(e) => {
-                            retry_count += 1;
-                            if retry_count < MAX_RETRIES {
-                                // Calculate wait time: 2^retry_count seconds (2, 4, 8, 16, 32)
-                                let wait_seconds = 2_u64.pow(retry_count);
-                                let retry_msg = QString::from(&format!(
-                                    "Download failed for {}: {}. Retrying in {} seconds... (Attempt {}/{})",
-                                    &download_file_name, e, wait_seconds, retry_count + 1, MAX_RETRIES
-                                ));
-                                qt_thread.queue(move |mut qo| {
-                                    qo.as_mut().download_show_msg(retry_msg);
-                                }).unwrap();
-                                error(&format!("Download attempt {} failed: {}", retry_count, e));
-                                thread::sleep(Duration::from_secs(wait_seconds));
-                            } else {
-                                // Max retries reached
-                                error(&format!("Failed to download {} after {} attempts: {}", &download_file_name, MAX_RETRIES, e));
-                                let fail_msg = QString::from(&format!(
-                                    "Network error: Failed to download {} after {} attempts. Please check your internet connection and try again later.",
-                                    &download_file_name, MAX_RETRIES
-                                ));
-                                qt_thread.queue(move |mut qo| {
-                                    qo.as_mut().download_show_msg(fail_msg);
-                                }).unwrap();
-                                cleanup_on_failure(&download_temp_folder, &extract_temp_folder, &app_assets_folder, is_initial_setup, &qt_thread);
-                                return;
-                            }
+                        DownloadEvent::Resuming { offset, .. } => {
+                            info(&format!("Resuming {} from {} bytes", &download_file_name, offset));
+                        }
+                        DownloadEvent::Progress { downloaded, total } => {
+                            let msg = QString::from(&op_msg);
+                            let (downloaded, total) = (downloaded as usize, total.unwrap_or(0) as usize);
+                            qt_thread.queue(move |mut qo| {
+                                qo.as_mut().download_progress_changed(msg, downloaded, total);
+                            }).unwrap();
+                        }
+                        DownloadEvent::Retrying { error: e, wait, attempt, max_attempts } => {
+                            error(&format!("Download of {} failed: {}", &download_file_name, e));
+                            let msg = QString::from(&format!(
+                                "Download failed for {}: {}. Retrying in {} seconds... (Attempt {}/{})",
+                                &download_file_name, e, wait.as_secs(), attempt, max_attempts
+                            ));
+                            qt_thread.queue(move |mut qo| {
+                                qo.as_mut().download_show_msg(msg);
+                            }).unwrap();
                         }
                     }
-                }
-
-                let resp = match resp {
-                    Some(r) => r,
-                    None => {
-                        // This shouldn't happen, but handle it just in case
-                        let msg = QString::from("Unexpected error: failed to initiate download.");
-                        qt_thread.queue(move |mut qo| {
-                            qo.as_mut().download_show_msg(msg);
-                        }).unwrap();
-                        cleanup_on_failure(&download_temp_folder, &extract_temp_folder, &app_assets_folder, is_initial_setup, &qt_thread);
-                        return;
-                    }
-                };
-
-                let mut file = match File::create(&download_temp_file_path) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        qt_thread.queue(move |mut qo| {
-                            let msg = QString::from(&format!("Error creating the file: {}", e));
-                            qo.as_mut().download_show_msg(msg);
-                        }).unwrap();
-                        return;
-                    }
-                };
-
-                let total = match resp.content_length() {
-                    Some(n) => n as usize,
-                    None => {
-                        let error_msg = QString::from("Error: can't read download content length.");
-                        let url_qstr_clone = url_qstr.clone();
-                        qt_thread.queue(move |mut qo| {
-                            qo.as_mut().download_needs_retry(url_qstr_clone, error_msg);
-                        }).unwrap();
-                        // The download file may have already been created with 0 length.
-                        let _ = remove_file(download_temp_file_path);
-                        // Stop processing the remaining URLs in the download loop and exit the thread.
-                        // The QML layer receives the downloadNeedsRetry signal and will:
-                        // 1. Display the error message to the user
-                        // 2. Show a "Retry" button in the UI
-                        // 3. When clicked, call download_urls_and_extract() again with just the failed URL
-                        // 4. After successful retry, continue downloading any remaining URLs from the original list
-                        return;
-                    }
-                };
+                });
 
-                let mut reader = resp;
-                let mut buf = [0u8; 8192]; // 8 KB buffer
-                let mut downloaded = 0_usize;
-
-                loop {
-                    let n = reader.read(&mut buf).unwrap(); // read up to buf.len()
-                    if n == 0 { break; } // EOF
-                    file.write_all(&buf[..n]).unwrap();
-                    downloaded += n;
-                    let op_msg = QString::from(format!("Downloading {}", &download_file_name));
+                if let Err(e) = result {
+                    error(&format!("{:#}", e));
+                    let fail_msg = QString::from(&format!(
+                        "Network error: Failed to download {}. Please check your internet connection and try again later, the download will continue where it stopped.",
+                        &download_file_name
+                    ));
                     qt_thread.queue(move |mut qo| {
-                        qo.as_mut().download_progress_changed(op_msg, downloaded, total);
+                        qo.as_mut().download_show_msg(fail_msg);
                     }).unwrap();
+                    cleanup_on_failure(&download_temp_folder, &extract_temp_folder, &app_assets_folder, is_initial_setup, &qt_thread);
+                    return;
                 }
 
                 let op_msg = QString::from(format!("Extracting {}", &download_file_name));
@@ -19,7 +19,12 @@ use simsapa_backend::helpers::{extract_words, normalize_query_text, query_text_t
 use simsapa_backend::prompt_utils::markdown_to_html;
 use simsapa_backend::logger::{info, warn, error, debug, get_log_level_str, set_log_level_str};
 use simsapa_backend::topic_index;
+use simsapa_backend::saved_searches::{self, NewContentReport};
+use simsapa_backend::search_history;
+use simsapa_backend::sync::{self, HttpTransport};
+use simsapa_backend::did_you_mean::{self, SpellingSuggestion, SUGGESTIONS_LIMIT};
 use simsapa_backend::update_checker;
+use simsapa_backend::{asset_delta, integrity};
 use simsapa_backend::types::SearchResult;
 use simsapa_backend::db::appdata_models::HistoryItemType;
 
@@ -68,6 +73,49 @@ static COMBINED_CACHE: Mutex<Option<CombinedCache>> = Mutex::new(None);
 /// log which errors the user chose to bypass. See PRD §11.6.
 static LAST_EXPORT_FAILURE: Mutex<Option<String>> = Mutex::new(None);
 
+/// Timing track of the recording shown in karaoke mode. The player polls
+/// `get_timing_position()` on every position update, so the parsed track is
+/// kept here rather than read from the section's `metadata_json` each time.
+/// Replaced by `get_timing_track()` and cleared by `save_timing_track()`.
+struct TimingTrackCache {
+    section_uid: String,
+    recording_uid: String,
+    track: simsapa_backend::audio::timing_track::TimingTrack,
+}
+
+static TIMING_TRACK_CACHE: Mutex<Option<TimingTrackCache>> = Mutex::new(None);
+
+/// The saved timing track of a recording, or a generated one when none is
+/// saved with the requested granularity. Returns `(is_saved, track)`.
+fn load_timing_track(
+    section_uid: &str,
+    recording_uid: &str,
+    requested: Option<simsapa_backend::audio::timing_track::TimingGranularity>,
+) -> Result<(bool, simsapa_backend::audio::timing_track::TimingTrack), String> {
+    use simsapa_backend::audio::timing_track::{timing_track_from_metadata, TimingTrack};
+
+    let section = get_app_data().dbm.appdata.get_chanting_section_detail(section_uid)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Section not found: {}", section_uid))?;
+
+    if let Some(track) = timing_track_from_metadata(section.metadata_json.as_deref(), recording_uid)
+        .filter(|t| requested.is_none_or(|g| g == t.granularity))
+    {
+        return Ok((true, track));
+    }
+
+    let recording = section.recordings.iter()
+        .find(|r| r.uid == recording_uid)
+        .ok_or_else(|| format!("Recording not found: {}", recording_uid))?;
+    let track = TimingTrack::generate(
+        &section.content_pali,
+        requested.unwrap_or_default(),
+        recording.markers_json.as_deref(),
+        recording.duration_ms as i64,
+    );
+    Ok((false, track))
+}
+
 /// Fetch, highlight, and cache a single page of search results.
 /// Returns (results, total_hits, page_len) on success.
 /// If the cache key has changed (new search started), returns None to signal abort.
@@ -144,6 +192,39 @@ fn compatible_assets_release() -> Option<update_checker::ReleaseEntry> {
     update_checker::get_latest_app_compatible_assets_release(&releases_info, &app_version).cloned()
 }
 
+/// A repair of `SuttaBridge::repair_installation()`, returns the message for the user.
+fn apply_integrity_repair(repair_json: &str) -> Result<String, String> {
+    let repair: integrity::Repair = serde_json::from_str(repair_json).map_err(|e| e.to_string())?;
+    let paths = &get_app_globals().paths;
+    match &repair {
+        integrity::Repair::RebuildIndex { index, lang } => {
+            integrity::rebuild_index(&paths.app_assets_dir, *index, lang).map_err(|e| format!("{:#}", e))?;
+            simsapa_backend::reinit_fulltext_searcher();
+            Ok(format!("Done: {}", repair.describe()))
+        }
+        integrity::Repair::ExtractArchive { archive } => {
+            let repo = compatible_assets_release()
+                .map(|r| r.github_repo)
+                .ok_or("The assets release is not known, check for updates first")?;
+            let db_version = asset_delta::read_db_version(&paths.app_assets_dir)
+                .map_err(|e| format!("{:#}", e))?
+                .ok_or("The installed db_version is not known")?;
+            let base_url = format!("https://github.com/{}/releases/download/v{}", repo, db_version);
+            let source = integrity::ArchiveSource::Url(base_url);
+            let (path, manifest) = integrity::fetch_archive(&source, archive, &paths.download_temp_folder)
+                .map_err(|e| format!("{:#}", e))?;
+            let staged = integrity::stage_archive_repair(&path, &manifest, &paths.app_assets_dir);
+            let _ = fs::remove_file(&path);
+            staged.map_err(|e| format!("{:#}", e))?;
+            let mut message = format!("{} is downloaded, restart the app to install it.", archive);
+            if archive.starts_with("appdata") || archive.starts_with("dictionaries") {
+                message.push_str(" The notes and imports in the damaged database are not kept, restore them from a backup afterwards.");
+            }
+            Ok(message)
+        }
+    }
+}
+
 fn fetch_and_cache_page(
     cache_key: &str,
     query_text: &str,
@@ -496,6 +577,30 @@ fn fetch_combined_page(
     Ok(Some((merged, combined_total, l)))
 }
 
+/// The "did you mean" queries for the first page of a search without results,
+/// sent with the page in `results_page_ready`. See docs/did-you-mean-suggestions.md.
+fn zero_hits_suggestions(
+    query_text: &str,
+    search_area_text: &str,
+    params_json_text: &str,
+    total_hits: usize,
+    page_num: usize,
+) -> Vec<SpellingSuggestion> {
+    if total_hits > 0 || page_num > 0 {
+        return Vec::new();
+    }
+
+    let app_data = get_app_data();
+    let params: SearchParams = serde_json::from_str(params_json_text).unwrap_or_default();
+    let search_area_enum = match search_area_text {
+        "Dictionary" => SearchArea::Dictionary,
+        "Library" => SearchArea::Library,
+        _ => SearchArea::Suttas,
+    };
+
+    did_you_mean::search_params_suggestions(&app_data.dbm, query_text, &search_area_enum, &params, SUGGESTIONS_LIMIT)
+}
+
 /// Spawn a background thread to prefetch pages into RESULTS_PAGE_CACHE.
 fn prefetch_pages(
     cache_key: String,
@@ -671,6 +776,14 @@ pub mod qobject {
         #[cxx_name = "databaseValidationResult"]
         fn database_validation_result(self: Pin<&mut SuttaBridge>, database_name: QString, is_valid: bool, message: QString);
 
+        #[qsignal]
+        #[cxx_name = "integrityReportReady"]
+        fn integrity_report_ready(self: Pin<&mut SuttaBridge>, report_json: QString);
+
+        #[qsignal]
+        #[cxx_name = "integrityRepairFinished"]
+        fn integrity_repair_finished(self: Pin<&mut SuttaBridge>, success: bool, message: QString);
+
         #[qsignal]
         #[cxx_name = "documentImportProgress"]
         fn document_import_progress(self: Pin<&mut SuttaBridge>, message: QString);
@@ -740,6 +853,14 @@ pub mod qobject {
         #[cxx_name = "waveformDataReady"]
         fn waveform_data_ready(self: Pin<&mut SuttaBridge>, recording_uid: QString, waveform_json: QString);
 
+        #[qsignal]
+        #[cxx_name = "recordingMarkersDetected"]
+        fn recording_markers_detected(self: Pin<&mut SuttaBridge>, recording_uid: QString, result_json: QString);
+
+        #[qsignal]
+        #[cxx_name = "practiceComparisonReady"]
+        fn practice_comparison_ready(self: Pin<&mut SuttaBridge>, user_recording_uid: QString, report_json: QString);
+
         // Gloss / Prompts history signals (item_type is "gloss" | "prompts").
         // The QML tabs filter on item_type so a single SuttaBridge serves both.
         #[qsignal]
@@ -754,6 +875,22 @@ pub mod qobject {
         #[cxx_name = "historyChanged"]
         fn history_changed(self: Pin<&mut SuttaBridge>, item_type: QString);
 
+        /// A NewContentReport: the runs of saved searches, with their new results.
+        #[qsignal]
+        #[cxx_name = "savedSearchesRunReady"]
+        fn saved_searches_run_ready(self: Pin<&mut SuttaBridge>, report_json: QString);
+
+        /// A list of QuerySuggestion for the query typed in a search input.
+        #[qsignal]
+        #[cxx_name = "querySuggestionsReady"]
+        fn query_suggestions_ready(self: Pin<&mut SuttaBridge>, query_text: QString, suggestions_json: QString);
+
+        /// The result of pair_sync_device() or sync_lan_now():
+        /// {"ok": true, "message": ...} or {"error": ...}.
+        #[qsignal]
+        #[cxx_name = "lanSyncFinished"]
+        fn lan_sync_finished(self: Pin<&mut SuttaBridge>, result_json: QString);
+
         #[qinvokable]
         fn emit_update_window_title(self: Pin<&mut SuttaBridge>, sutta_uid: QString, sutta_ref: QString, sutta_title: QString);
 
@@ -781,6 +918,12 @@ pub mod qobject {
         #[qinvokable]
         fn dictionary_first_query(self: Pin<&mut SuttaBridge>);
 
+        #[qinvokable]
+        fn verify_installation(self: Pin<&mut SuttaBridge>, full: bool);
+
+        #[qinvokable]
+        fn repair_installation(self: Pin<&mut SuttaBridge>, repair_json: QString);
+
         #[qinvokable]
         fn reset_app_settings_to_defaults(self: Pin<&mut SuttaBridge>) -> bool;
 
@@ -931,6 +1074,9 @@ pub mod qobject {
         #[qinvokable]
         fn get_spine_items_for_book_json(self: &SuttaBridge, book_uid: &QString) -> QString;
 
+        #[qinvokable]
+        fn get_chapters_citing_sutta_json(self: &SuttaBridge, sutta_uid: &QString) -> QString;
+
         #[qinvokable]
         fn get_spine_item_uid_by_path(self: &SuttaBridge, book_uid: &QString, resource_path: &QString) -> QString;
 
@@ -956,7 +1102,13 @@ pub mod qobject {
         fn get_book_uid_for_spine_item(self: &SuttaBridge, spine_item_uid: &QString) -> QString;
 
         #[qinvokable]
-        fn import_document(self: Pin<&mut SuttaBridge>, file_path: &QString, book_uid: &QString, title: &QString, author: &QString, language: &QString, document_type: &QString, split_tag: &QString);
+        fn get_pdf_page_for_spine_item(self: &SuttaBridge, spine_item_uid: &QString) -> i32;
+
+        #[qinvokable]
+        fn import_document(self: Pin<&mut SuttaBridge>, file_path: &QString, book_uid: &QString, title: &QString, author: &QString, language: &QString, document_type: &QString, split_tag: &QString, ocr_languages: &QString);
+
+        #[qinvokable]
+        fn is_ocr_available(self: &SuttaBridge) -> bool;
 
         #[qinvokable]
         fn rebuild_search_index(self: Pin<&mut SuttaBridge>);
@@ -1345,9 +1497,33 @@ pub mod qobject {
         #[qinvokable]
         fn generate_waveform_data(self: Pin<&mut SuttaBridge>, recording_uid: &QString, file_path: &QString, num_bars: i32);
 
+        #[qinvokable]
+        fn detect_recording_markers(self: Pin<&mut SuttaBridge>, recording_uid: &QString, file_path: &QString, section_uid: &QString);
+
+        #[qinvokable]
+        fn compare_practice_recording(self: Pin<&mut SuttaBridge>, section_uid: &QString, reference_uid: &QString, user_uid: &QString);
+
+        #[qinvokable]
+        fn get_timing_track(self: &SuttaBridge, section_uid: &QString, recording_uid: &QString, granularity: &QString) -> QString;
+
+        #[qinvokable]
+        fn save_timing_track(self: &SuttaBridge, section_uid: &QString, recording_uid: &QString, track_json: &QString) -> QString;
+
+        #[qinvokable]
+        fn get_timing_position(self: &SuttaBridge, section_uid: &QString, recording_uid: &QString, position_ms: i32) -> QString;
+
+        #[qinvokable]
+        fn get_scansion_html(self: &SuttaBridge, text: &QString) -> QString;
+
+        #[qinvokable]
+        fn get_sutta_scansion_html(self: &SuttaBridge, uid: &QString) -> QString;
+
         #[qinvokable]
         fn export_chanting_data(self: &SuttaBridge, json_selected_uids: &QString, dest_path: &QString) -> QString;
 
+        #[qinvokable]
+        fn export_chanting_audio(self: &SuttaBridge, json_selected_uids: &QString, dest_dir: &QString, options_json: &QString) -> QString;
+
         #[qinvokable]
         fn import_chanting_data(self: &SuttaBridge, zip_path: &QString) -> QString;
 
@@ -1405,6 +1581,151 @@ pub mod qobject {
         #[qinvokable]
         fn move_bookmark_items_to_folder(self: Pin<&mut SuttaBridge>, item_ids_json: &QString, target_folder_id: i32);
 
+        // --- Reading state ---
+
+        #[qinvokable]
+        fn record_reading_progress(self: &SuttaBridge, item_uid: &QString, table_name: &QString, scroll_position: f32, progress: f32, seconds: i32);
+
+        #[qinvokable]
+        fn get_reading_state_json(self: &SuttaBridge, item_uid: &QString, table_name: &QString) -> QString;
+
+        #[qinvokable]
+        fn set_reading_finished(self: &SuttaBridge, item_uid: &QString, table_name: &QString, finished: bool);
+
+        #[qinvokable]
+        fn get_book_reading_progress_json(self: &SuttaBridge, book_uid: &QString) -> QString;
+
+        #[qinvokable]
+        fn get_canon_reading_progress_json(self: &SuttaBridge, nikaya: &QString, group_path_prefix: &QString) -> QString;
+
+        #[qinvokable]
+        fn get_reading_statistics_json(self: &SuttaBridge) -> QString;
+
+        // --- Reading plans ---
+
+        #[qinvokable]
+        fn get_reading_plans_json(self: &SuttaBridge) -> QString;
+
+        #[qinvokable]
+        fn get_reading_plan_items_json(self: &SuttaBridge, plan_uid: &QString) -> QString;
+
+        #[qinvokable]
+        fn get_todays_reading_json(self: &SuttaBridge) -> QString;
+
+        #[qinvokable]
+        fn import_reading_plan_file(self: &SuttaBridge, file_path: &QString) -> QString;
+
+        #[qinvokable]
+        fn export_reading_plan_file(self: &SuttaBridge, plan_uid: &QString, dest_path: &QString) -> QString;
+
+        #[qinvokable]
+        fn generate_reading_plan(self: &SuttaBridge, options_json: &QString) -> QString;
+
+        #[qinvokable]
+        fn delete_reading_plan(self: &SuttaBridge, plan_uid: &QString);
+
+        #[qinvokable]
+        fn enroll_in_reading_plan(self: &SuttaBridge, plan_uid: &QString, start_date: &QString) -> bool;
+
+        #[qinvokable]
+        fn unenroll_from_reading_plan(self: &SuttaBridge, plan_uid: &QString);
+
+        #[qinvokable]
+        fn set_reading_plan_active(self: &SuttaBridge, plan_uid: &QString, active: bool);
+
+        #[qinvokable]
+        fn set_reading_plan_item_completed(self: &SuttaBridge, plan_uid: &QString, item_index: i32, completed: bool);
+
+        // --- Saved searches ---
+
+        #[qinvokable]
+        fn save_search(self: Pin<&mut SuttaBridge>, name: &QString, query_text: &QString, search_area: &QString, params_json: &QString) -> QString;
+
+        #[qinvokable]
+        fn get_saved_searches_json(self: &SuttaBridge) -> QString;
+
+        #[qinvokable]
+        fn get_saved_search_hits_json(self: &SuttaBridge, saved_search_id: i32, only_new: bool) -> QString;
+
+        #[qinvokable]
+        fn rerun_saved_search(self: Pin<&mut SuttaBridge>, saved_search_id: i32);
+
+        #[qinvokable]
+        fn run_saved_searches_if_requested(self: Pin<&mut SuttaBridge>);
+
+        #[qinvokable]
+        fn rename_saved_search(self: &SuttaBridge, saved_search_id: i32, name: &QString) -> bool;
+
+        #[qinvokable]
+        fn delete_saved_search(self: &SuttaBridge, saved_search_id: i32);
+
+        #[qinvokable]
+        fn mark_saved_search_seen(self: &SuttaBridge, saved_search_id: i32);
+
+        // --- Search history ---
+
+        #[qinvokable]
+        fn record_search_history(self: &SuttaBridge, query_text: &QString, search_area: &QString, params_json: &QString, hits_count: i32) -> i32;
+
+        #[qinvokable]
+        fn set_search_history_opened(self: &SuttaBridge, history_id: i32, item_uid: &QString, table_name: &QString, title: &QString);
+
+        #[qinvokable]
+        fn get_search_history_json(self: &SuttaBridge, limit: i32) -> QString;
+
+        #[qinvokable]
+        fn get_search_history_stats_json(self: &SuttaBridge) -> QString;
+
+        #[qinvokable]
+        fn delete_search_history_item(self: &SuttaBridge, history_id: i32);
+
+        #[qinvokable]
+        fn clear_search_history(self: &SuttaBridge);
+
+        #[qinvokable]
+        fn get_search_history_enabled(self: &SuttaBridge) -> bool;
+
+        #[qinvokable]
+        fn set_search_history_enabled(self: &SuttaBridge, enabled: bool);
+
+        #[qinvokable]
+        fn get_query_suggestions(self: Pin<&mut SuttaBridge>, query_text: &QString, search_area: &QString, limit: i32);
+
+        // --- LAN sync ---
+
+        #[qinvokable]
+        fn get_lan_sync_json(self: &SuttaBridge) -> QString;
+
+        #[qinvokable]
+        fn set_sync_lan_enabled(self: &SuttaBridge, enabled: bool);
+
+        #[qinvokable]
+        fn set_sync_lan_port(self: &SuttaBridge, port: i32);
+
+        #[qinvokable]
+        fn set_sync_device_name(self: &SuttaBridge, name: &QString);
+
+        #[qinvokable]
+        fn set_sync_folder(self: &SuttaBridge, folder: &QString);
+
+        #[qinvokable]
+        fn sync_folder_now(self: Pin<&mut SuttaBridge>);
+
+        #[qinvokable]
+        fn create_sync_pairing_code(self: &SuttaBridge) -> QString;
+
+        #[qinvokable]
+        fn get_sync_peers_json(self: &SuttaBridge) -> QString;
+
+        #[qinvokable]
+        fn unpair_sync_device(self: &SuttaBridge, device_id: &QString) -> bool;
+
+        #[qinvokable]
+        fn pair_sync_device(self: Pin<&mut SuttaBridge>, url: &QString, code: &QString);
+
+        #[qinvokable]
+        fn sync_lan_now(self: Pin<&mut SuttaBridge>);
+
         #[qinvokable]
         fn save_last_session(self: Pin<&mut SuttaBridge>, windows_json: &QString);
 
@@ -1644,6 +1965,49 @@ impl qobject::SuttaBridge {
         });
     }
 
+    /// Check the databases and the fulltext indexes in the background.
+    /// Emits integrityReportReady with the report as JSON.
+    pub fn verify_installation(self: Pin<&mut Self>, full: bool) {
+        info(&format!("SuttaBridge::verify_installation() full: {}", full));
+        let qt_thread = self.qt_thread();
+
+        thread::spawn(move || {
+            let assets_dir = get_app_globals().paths.app_assets_dir.clone();
+            let options = integrity::VerifyOptions { full, archives_dir: None };
+            let report = integrity::verify_installation(&assets_dir, &options);
+            let report_json = serde_json::to_string(&report).unwrap_or_default();
+
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().integrity_report_ready(QString::from(report_json));
+            }).unwrap();
+        });
+    }
+
+    /// Apply one repair of an integrity report. An index is rebuilt at once.
+    /// An archive is downloaded from the release of the installed databases
+    /// and staged, to be installed at the next start before the databases
+    /// are opened. Emits integrityRepairFinished.
+    pub fn repair_installation(self: Pin<&mut Self>, repair_json: &QString) {
+        info(&format!("SuttaBridge::repair_installation() {}", repair_json));
+        let qt_thread = self.qt_thread();
+        let repair_json = repair_json.to_string();
+
+        thread::spawn(move || {
+            let result = apply_integrity_repair(&repair_json);
+
+            let (success, message) = match result {
+                Ok(message) => (true, message),
+                Err(e) => {
+                    error(&format!("Repair failed: {}", e));
+                    (false, e)
+                }
+            };
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().integrity_repair_finished(success, QString::from(message));
+            }).unwrap();
+        });
+    }
+
     pub fn dpd_first_query(self: Pin<&mut Self>) {
         info("SuttaBridge::dpd_first_query() start");
 
@@ -1842,11 +2206,13 @@ impl qobject::SuttaBridge {
 
                 match fetch_combined_page(&cache_key, &query_text, &params_json_text, page_num) {
                     Ok(Some((results, total_hits, page_len))) => {
+                        let suggestions = zero_hits_suggestions(&query_text, &search_area_text, &params_json_text, total_hits as usize, page_num);
                         let results_page_data = SearchResultPage {
                             total_hits: total_hits as usize,
                             page_len,
                             page_num,
                             results,
+                            suggestions,
                         };
                         let json = serde_json::to_string(&results_page_data).unwrap_or_default();
                         qt_thread.queue(move |mut qo| {
@@ -1933,6 +2299,7 @@ impl qobject::SuttaBridge {
                                 page_len: cache.page_len,
                                 page_num,
                                 results: cached_results.clone(),
+                                suggestions: Vec::new(),
                             };
                             let json = serde_json::to_string(&results_page).unwrap_or_default();
                             qt_thread.queue(move |mut qo| {
@@ -1987,11 +2354,13 @@ impl qobject::SuttaBridge {
             // Fetch the requested page
             match fetch_and_cache_page(&cache_key, &query_text, &search_area_text, &params_json_text, page_num) {
                 Ok(Some((results, total_hits, page_len))) => {
+                    let suggestions = zero_hits_suggestions(&query_text, &search_area_text, &params_json_text, total_hits as usize, page_num);
                     let results_page_data = SearchResultPage {
                         total_hits: total_hits as usize,
                         page_len,
                         page_num,
                         results,
+                        suggestions,
                     };
                     let json = serde_json::to_string(&results_page_data).unwrap_or_default();
                     qt_thread.queue(move |mut qo| {
@@ -2097,6 +2466,7 @@ impl qobject::SuttaBridge {
                 include_bold_definitions: params.include_comm_bold_definitions,
                 dict_source_uids: params.dict_source_uids.clone(),
                 show_all_snippets: false,
+                fuzzy_distance: 0,
             };
 
             let result = with_fulltext_searcher(|searcher| {
@@ -2844,6 +3214,23 @@ impl qobject::SuttaBridge {
         }
     }
 
+    /// The library chapters citing a sutta, as a JSON list of
+    /// SuttaCitingChapter.
+    pub fn get_chapters_citing_sutta_json(&self, sutta_uid: &QString) -> QString {
+        let app_data = get_app_data();
+        let uid = sutta_uid.to_string();
+        match app_data.dbm.appdata.get_chapters_citing_sutta(&uid) {
+            Ok(chapters) => {
+                let json = serde_json::to_string(&chapters).unwrap_or_else(|_| "[]".to_string());
+                QString::from(json)
+            }
+            Err(e) => {
+                error(&format!("Failed to get the chapters citing {}: {}", uid, e));
+                QString::from("[]")
+            }
+        }
+    }
+
     pub fn get_spine_items_for_book_json(&self, book_uid: &QString) -> QString {
         let app_data = get_app_data();
         let uid = book_uid.to_string();
@@ -3009,7 +3396,24 @@ impl qobject::SuttaBridge {
         }
     }
 
-    pub fn import_document(self: Pin<&mut Self>, file_path: &QString, book_uid: &QString, title: &QString, author: &QString, language: &QString, document_type: &QString, split_tag: &QString) {
+    /// Get the PDF page of a spine item, 0 when it is not a page of a PDF
+    pub fn get_pdf_page_for_spine_item(&self, spine_item_uid: &QString) -> i32 {
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_book_spine_item(&spine_item_uid.to_string()) {
+            Ok(Some(spine_item)) => simsapa_backend::pdf_import::pdf_page_number(&spine_item.resource_path).unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Whether the tesseract command is installed for the OCR of scanned pages
+    pub fn is_ocr_available(&self) -> bool {
+        simsapa_backend::ocr::OcrEngine::new("").is_available()
+    }
+
+    /// Import a document in a background thread. With `ocr_languages`
+    /// (Tesseract language codes, e.g. "eng"), the text of scanned PDF pages
+    /// and of page images is recognized, empty for no OCR.
+    pub fn import_document(self: Pin<&mut Self>, file_path: &QString, book_uid: &QString, title: &QString, author: &QString, language: &QString, document_type: &QString, split_tag: &QString, ocr_languages: &QString) {
         let path_str = file_path.to_string();
         let uid_str = book_uid.to_string();
         let title_str = title.to_string();
@@ -3017,6 +3421,7 @@ impl qobject::SuttaBridge {
         let language_str = language.to_string();
         let doc_type = document_type.to_string();
         let _split_tag_str = split_tag.to_string();
+        let ocr_languages_str = ocr_languages.to_string();
 
         info(&format!("import_document: {} as {} ({})", &path_str, &uid_str, &doc_type));
 
@@ -3054,12 +3459,22 @@ impl qobject::SuttaBridge {
                     app_data.import_epub_to_db(path, &uid_str, custom_title, custom_author, custom_language, None, true)
                 }
                 "pdf" => {
-                    let progress_msg = QString::from("Importing PDF...");
+                    let ocr = if ocr_languages_str.trim().is_empty() { None } else { app_data.ocr_for_import(&ocr_languages_str) };
+                    let progress_msg = QString::from(if ocr.is_some() { "Importing PDF, recognizing scanned pages..." } else { "Importing PDF..." });
+                    qt_thread.queue(move |mut qo| {
+                        qo.as_mut().document_import_progress(progress_msg);
+                    }).unwrap();
+
+                    app_data.import_pdf_to_db_with_ocr(path, &uid_str, custom_title, custom_author, custom_language, true, ocr.as_ref())
+                }
+                "images" => {
+                    let ocr = if ocr_languages_str.trim().is_empty() { None } else { app_data.ocr_for_import(&ocr_languages_str) };
+                    let progress_msg = QString::from(if ocr.is_some() { "Importing page images, recognizing text..." } else { "Importing page images..." });
                     qt_thread.queue(move |mut qo| {
                         qo.as_mut().document_import_progress(progress_msg);
                     }).unwrap();
 
-                    app_data.import_pdf_to_db(path, &uid_str, custom_title, custom_author, custom_language, None, true)
+                    app_data.import_images_to_db(path, &uid_str, custom_title, custom_author, custom_language, true, ocr.as_ref())
                 }
                 "html" => {
                     let progress_msg = QString::from("Importing HTML...");
@@ -3071,6 +3486,14 @@ impl qobject::SuttaBridge {
                     // For now, HTML is imported as a single spine item
                     app_data.import_html_to_db(path, &uid_str, custom_title, custom_author, custom_language, None, true)
                 }
+                "markdown" | "docx" | "odt" | "fb2" => {
+                    let progress_msg = QString::from(format!("Importing {}...", doc_type.to_uppercase()));
+                    qt_thread.queue(move |mut qo| {
+                        qo.as_mut().document_import_progress(progress_msg);
+                    }).unwrap();
+
+                    app_data.import_converted_document_to_db(path, &doc_type, &uid_str, custom_title, custom_author, custom_language, true)
+                }
                 _ => {
                     let error_msg = format!("Unknown document type: {}", doc_type);
                     error(&error_msg);
@@ -3099,6 +3522,9 @@ impl qobject::SuttaBridge {
 
                     simsapa_backend::reinit_fulltext_searcher();
 
+                    let book_title = if title_str.trim().is_empty() { &uid_str } else { &title_str };
+                    saved_searches::request_saved_searches_run(&format!("Book: {}", book_title));
+
                     let success_msg = QString::from(format!("Successfully imported '{}'", &title_str));
                     qt_thread.queue(move |mut qo| {
                         qo.as_mut().document_import_completed(true, success_msg);
@@ -4805,35 +5231,267 @@ impl qobject::SuttaBridge {
         });
     }
 
-    // =========================================================================
-    // Chanting Export / Import
-    // =========================================================================
+    /// Propose phrase range markers from the pauses in a recording, aligned to
+    /// the lines of the section's `content_pali`. Runs the decode + analysis on
+    /// a background thread, saves the merged markers (hand-placed markers are
+    /// kept) and emits `recordingMarkersDetected` with `{"markers_json": ...}`
+    /// or `{"error": ...}`.
+    pub fn detect_recording_markers(self: Pin<&mut Self>, recording_uid: &QString, file_path: &QString, section_uid: &QString) {
+        use simsapa_backend::audio::phrase_markers::{detect_phrase_markers, merge_with_existing_markers, PhraseDetectionParams};
 
-    pub fn export_chanting_data(&self, json_selected_uids: &QString, dest_path: &QString) -> QString {
-        use simsapa_backend::db::chanting_export::export_chanting_to_zip;
+        let uid_str = recording_uid.to_string();
+        let path_str = file_path.to_string();
+        let section_uid_str = section_uid.to_string();
+        let qt_thread = self.qt_thread();
 
-        let json_str = json_selected_uids.to_string();
-        let dest = dest_path.to_string();
+        thread::spawn(move || {
+            let app_data = get_app_data();
 
-        // Parse the JSON: { collections: [...], chants: [...], sections: [...] }
-        let parsed: serde_json::Value = match serde_json::from_str(&json_str) {
-            Ok(v) => v,
-            Err(e) => return QString::from(&format!("{{\"error\": \"Invalid JSON: {}\"}}", e)),
-        };
+            let section = match app_data.dbm.appdata.get_chanting_section_detail(&section_uid_str) {
+                Ok(s) => s,
+                Err(e) => {
+                    warn(&format!("detect_recording_markers(): section lookup failed: {}", e));
+                    None
+                }
+            };
+            let content_pali = section.as_ref().map(|s| s.content_pali.clone());
+            let existing_markers = section
+                .as_ref()
+                .and_then(|s| s.recordings.iter().find(|r| r.uid == uid_str))
+                .and_then(|r| r.markers_json.clone());
+
+            let result = detect_phrase_markers(Path::new(&path_str), content_pali.as_deref(), &PhraseDetectionParams::default())
+                .and_then(|markers| merge_with_existing_markers(existing_markers.as_deref(), &markers))
+                .and_then(|markers_json| {
+                    app_data.dbm.appdata.update_recording_markers(&uid_str, &markers_json)?;
+                    Ok(markers_json)
+                });
 
-        let extract_strings = |key: &str| -> Vec<String> {
-            parsed.get(key)
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-                .unwrap_or_default()
-        };
+            let result_json = match result {
+                Ok(markers_json) => serde_json::json!({ "markers_json": markers_json }).to_string(),
+                Err(e) => {
+                    warn(&format!("detect_recording_markers(): {}", e));
+                    serde_json::json!({ "error": e.to_string() }).to_string()
+                }
+            };
 
-        let collection_uids = extract_strings("collections");
-        let chant_uids = extract_strings("chants");
-        let section_uids = extract_strings("sections");
+            let uid_qstr = QString::from(&uid_str);
+            let json_qstr = QString::from(&result_json);
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().recording_markers_detected(uid_qstr, json_qstr);
+            }).unwrap();
+        });
+    }
 
-        let app_data = get_app_data();
-        let dest_path = std::path::Path::new(&dest);
+    /// Compare a user recording against a reference recording of the same
+    /// section (DTW alignment over level and pitch). The reference's range
+    /// markers define the reported segments. Runs on a background thread and
+    /// emits `practiceComparisonReady` with the report JSON or `{"error": ...}`.
+    pub fn compare_practice_recording(self: Pin<&mut Self>, section_uid: &QString, reference_uid: &QString, user_uid: &QString) {
+        use simsapa_backend::audio::practice_compare::compare_recordings;
+
+        let section_uid_str = section_uid.to_string();
+        let reference_uid_str = reference_uid.to_string();
+        let user_uid_str = user_uid.to_string();
+        let qt_thread = self.qt_thread();
+
+        thread::spawn(move || {
+            let app_data = get_app_data();
+            let recordings_dir = simsapa_backend::get_chanting_recordings_dir();
+
+            let run = || -> Result<String, String> {
+                let section = app_data.dbm.appdata.get_chanting_section_detail(&section_uid_str)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("Section not found: {}", section_uid_str))?;
+                let find = |uid: &str| {
+                    section.recordings.iter()
+                        .find(|r| r.uid == uid)
+                        .ok_or_else(|| format!("Recording not found: {}", uid))
+                };
+                let reference = find(&reference_uid_str)?;
+                let user = find(&user_uid_str)?;
+                let path_of = |file_name: &str| {
+                    let p = PathBuf::from(file_name);
+                    if p.is_absolute() { p } else { recordings_dir.join(file_name) }
+                };
+                let report = compare_recordings(
+                    &path_of(&reference.file_name),
+                    &path_of(&user.file_name),
+                    reference.markers_json.as_deref(),
+                ).map_err(|e| e.to_string())?;
+                serde_json::to_string(&report).map_err(|e| e.to_string())
+            };
+
+            let report_json = match run() {
+                Ok(json) => json,
+                Err(e) => {
+                    warn(&format!("compare_practice_recording(): {}", e));
+                    serde_json::json!({ "error": e }).to_string()
+                }
+            };
+
+            let uid_qstr = QString::from(&user_uid_str);
+            let json_qstr = QString::from(&report_json);
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().practice_comparison_ready(uid_qstr, json_qstr);
+            }).unwrap();
+        });
+    }
+
+    /// Timing track of a recording for karaoke display: the saved track, or
+    /// one generated from the recording's range markers when none is saved or
+    /// a different `granularity` ("word" / "syllable") is asked for. Returns
+    /// `{"saved": bool, "track": {...}}` or `{"error": ...}`.
+    pub fn get_timing_track(&self, section_uid: &QString, recording_uid: &QString, granularity: &QString) -> QString {
+        use simsapa_backend::audio::timing_track::TimingGranularity;
+
+        let section_uid_str = section_uid.to_string();
+        let recording_uid_str = recording_uid.to_string();
+        let requested = TimingGranularity::from_name(&granularity.to_string());
+
+        match load_timing_track(&section_uid_str, &recording_uid_str, requested) {
+            Ok((is_saved, track)) => {
+                let result = serde_json::json!({ "saved": is_saved, "track": track }).to_string();
+                if let Ok(mut cache) = TIMING_TRACK_CACHE.lock() {
+                    *cache = Some(TimingTrackCache {
+                        section_uid: section_uid_str,
+                        recording_uid: recording_uid_str,
+                        track,
+                    });
+                }
+                QString::from(&result)
+            }
+            Err(e) => QString::from(&serde_json::json!({ "error": e }).to_string()),
+        }
+    }
+
+    /// Store a recording's timing track in its section's `metadata_json`. An
+    /// empty `track_json` removes the track.
+    pub fn save_timing_track(&self, section_uid: &QString, recording_uid: &QString, track_json: &QString) -> QString {
+        use simsapa_backend::audio::timing_track::{set_timing_track_in_metadata, TimingTrack};
+
+        let section_uid_str = section_uid.to_string();
+        let track_str = track_json.to_string();
+        let error_json = |msg: String| QString::from(&serde_json::json!({ "error": msg }).to_string());
+
+        let track: Option<TimingTrack> = if track_str.trim().is_empty() {
+            None
+        } else {
+            match serde_json::from_str(&track_str) {
+                Ok(t) => Some(t),
+                Err(e) => return error_json(format!("Invalid timing track: {}", e)),
+            }
+        };
+
+        let app_data = get_app_data();
+        let run = || -> Result<(), String> {
+            let section = app_data.dbm.appdata.get_chanting_sections_by_uids(std::slice::from_ref(&section_uid_str))
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("Section not found: {}", section_uid_str))?;
+            let metadata_json = set_timing_track_in_metadata(section.metadata_json.as_deref(), &recording_uid.to_string(), track.as_ref())
+                .map_err(|e| e.to_string())?;
+            app_data.dbm.appdata.update_chanting_section_metadata(&section_uid_str, metadata_json.as_deref())
+                .map_err(|e| e.to_string())
+        };
+        let result = run();
+
+        if let Ok(mut cache) = TIMING_TRACK_CACHE.lock() {
+            *cache = None;
+        }
+
+        match result {
+            Ok(()) => QString::from("{\"ok\": true}"),
+            Err(e) => {
+                error(&format!("save_timing_track(): {}", e));
+                error_json(e)
+            }
+        }
+    }
+
+    /// The token sounding at `position_ms` in the recording's timing track:
+    /// `{"index", "line", "word", "start_ms", "end_ms", "progress"}`, or `{}`
+    /// between tokens. Polled by the karaoke view while the recording plays.
+    pub fn get_timing_position(&self, section_uid: &QString, recording_uid: &QString, position_ms: i32) -> QString {
+        let section_uid_str = section_uid.to_string();
+        let recording_uid_str = recording_uid.to_string();
+
+        let Ok(mut cache) = TIMING_TRACK_CACHE.lock() else {
+            return QString::from("{}");
+        };
+        let is_cached = cache.as_ref().is_some_and(|c| c.section_uid == section_uid_str && c.recording_uid == recording_uid_str);
+        if !is_cached {
+            match load_timing_track(&section_uid_str, &recording_uid_str, None) {
+                Ok((_, track)) => {
+                    *cache = Some(TimingTrackCache {
+                        section_uid: section_uid_str,
+                        recording_uid: recording_uid_str,
+                        track,
+                    });
+                }
+                Err(e) => return QString::from(&serde_json::json!({ "error": e }).to_string()),
+            }
+        }
+
+        let position = cache.as_ref().and_then(|c| c.track.position_at(position_ms as i64));
+        match position {
+            Some(pos) => QString::from(&serde_json::to_string(&pos).unwrap_or_else(|_| "{}".to_string())),
+            None => QString::from("{}"),
+        }
+    }
+
+    /// Scansion of Pāli verse text (garu / lahu syllables, metre, irregular
+    /// pādas) as HTML. Verses are separated by blank lines.
+    pub fn get_scansion_html(&self, text: &QString) -> QString {
+        use simsapa_backend::prosody::{render_scansion_html, scan_verses};
+        QString::from(&render_scansion_html(&scan_verses(&text.to_string())))
+    }
+
+    /// Scansion of a Pāli sutta's `content_plain`, e.g. a Dhammapada or Sutta
+    /// Nipāta text. Returns an empty string for unknown or non-Pāli suttas.
+    pub fn get_sutta_scansion_html(&self, uid: &QString) -> QString {
+        use simsapa_backend::prosody::{render_scansion_html, scan_verses};
+
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_sutta(&uid.to_string()) {
+            Some(sutta) if sutta.language == "pli" => {
+                let text = sutta.content_plain.unwrap_or_default();
+                QString::from(&render_scansion_html(&scan_verses(&text)))
+            }
+            _ => QString::from(""),
+        }
+    }
+
+    // =========================================================================
+    // Chanting Export / Import
+    // =========================================================================
+
+    pub fn export_chanting_data(&self, json_selected_uids: &QString, dest_path: &QString) -> QString {
+        use simsapa_backend::db::chanting_export::export_chanting_to_zip;
+
+        let json_str = json_selected_uids.to_string();
+        let dest = dest_path.to_string();
+
+        // Parse the JSON: { collections: [...], chants: [...], sections: [...] }
+        let parsed: serde_json::Value = match serde_json::from_str(&json_str) {
+            Ok(v) => v,
+            Err(e) => return QString::from(&format!("{{\"error\": \"Invalid JSON: {}\"}}", e)),
+        };
+
+        let extract_strings = |key: &str| -> Vec<String> {
+            parsed.get(key)
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+                .unwrap_or_default()
+        };
+
+        let collection_uids = extract_strings("collections");
+        let chant_uids = extract_strings("chants");
+        let section_uids = extract_strings("sections");
+
+        let app_data = get_app_data();
+        let dest_path = std::path::Path::new(&dest);
 
         match export_chanting_to_zip(
             &app_data.dbm.appdata,
@@ -4847,6 +5505,61 @@ impl qobject::SuttaBridge {
         }
     }
 
+    /// Export the audio of the selected sections to `dest_dir`.
+    ///
+    /// `options_json` is an `AudioExportOptions` object (`format`,
+    /// `trim_to_markers`, `normalize_loudness`, `concatenate`, ...) plus an
+    /// optional `recording_type` ("reference" by default). Returns
+    /// `{"ok": true, "audio_files": [...], "cue_file": ...}` or `{"error": ...}`.
+    pub fn export_chanting_audio(&self, json_selected_uids: &QString, dest_dir: &QString, options_json: &QString) -> QString {
+        use simsapa_backend::audio::export::AudioExportOptions;
+        use simsapa_backend::db::chanting_export::export_chanting_audio;
+
+        let error_json = |msg: String| QString::from(&serde_json::json!({ "error": msg }).to_string());
+
+        let parsed: serde_json::Value = match serde_json::from_str(&json_selected_uids.to_string()) {
+            Ok(v) => v,
+            Err(e) => return error_json(format!("Invalid JSON: {}", e)),
+        };
+        let extract_strings = |key: &str| -> Vec<String> {
+            parsed.get(key)
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+                .unwrap_or_default()
+        };
+
+        let options_value: serde_json::Value = serde_json::from_str(&options_json.to_string())
+            .unwrap_or_else(|_| serde_json::json!({}));
+        let recording_type = options_value.get("recording_type")
+            .and_then(|v| v.as_str())
+            .unwrap_or("reference")
+            .to_string();
+        let options: AudioExportOptions = match serde_json::from_value(options_value) {
+            Ok(o) => o,
+            Err(e) => return error_json(format!("Invalid export options: {}", e)),
+        };
+
+        let app_data = get_app_data();
+        match export_chanting_audio(
+            &app_data.dbm.appdata,
+            &extract_strings("collections"),
+            &extract_strings("sections"),
+            &recording_type,
+            Path::new(&dest_dir.to_string()),
+            &options,
+        ) {
+            Ok(result) => {
+                let mut json = serde_json::to_value(&result).unwrap_or_else(|_| serde_json::json!({}));
+                json["ok"] = serde_json::json!(true);
+                QString::from(&json.to_string())
+            }
+            Err(e) => {
+                error(&format!("export_chanting_audio(): {}", e));
+                error_json(e.to_string())
+            }
+        }
+    }
+
     pub fn import_chanting_data(&self, zip_path: &QString) -> QString {
         use simsapa_backend::db::chanting_export::import_chanting_from_zip;
 
@@ -5047,6 +5760,619 @@ impl qobject::SuttaBridge {
         }
     }
 
+    // --- Reading state ---
+
+    /// Record reading a sutta or book chapter, called periodically from the
+    /// reading view. `seconds` is the reading time since the last call.
+    pub fn record_reading_progress(&self, item_uid: &QString, table_name: &QString, scroll_position: f32, progress: f32, seconds: i32) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.record_reading_progress(&item_uid.to_string(), &table_name.to_string(), scroll_position, progress, seconds) {
+            error(&format!("record_reading_progress(): {}", e));
+        }
+    }
+
+    /// The ReadingState of an item as JSON, or an empty string if it was not read yet.
+    pub fn get_reading_state_json(&self, item_uid: &QString, table_name: &QString) -> QString {
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_reading_state(&item_uid.to_string(), &table_name.to_string()) {
+            Ok(Some(state)) => QString::from(serde_json::to_string(&state).unwrap_or_default()),
+            Ok(None) => QString::from(""),
+            Err(e) => {
+                error(&format!("get_reading_state_json(): {}", e));
+                QString::from("")
+            }
+        }
+    }
+
+    pub fn set_reading_finished(&self, item_uid: &QString, table_name: &QString, finished: bool) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.set_reading_finished(&item_uid.to_string(), &table_name.to_string(), finished) {
+            error(&format!("set_reading_finished(): {}", e));
+        }
+    }
+
+    pub fn get_book_reading_progress_json(&self, book_uid: &QString) -> QString {
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_book_reading_progress(&book_uid.to_string()) {
+            Ok(progress) => QString::from(serde_json::to_string(&progress).unwrap_or_else(|_| "{}".to_string())),
+            Err(e) => {
+                error(&format!("get_book_reading_progress_json(): {}", e));
+                QString::from("{}")
+            }
+        }
+    }
+
+    /// Canon reading progress as a JSON list of CanonReadingProgress. Empty
+    /// arguments mean all nikāyas with some reading and no group_path filter.
+    pub fn get_canon_reading_progress_json(&self, nikaya: &QString, group_path_prefix: &QString) -> QString {
+        let app_data = get_app_data();
+        let nikaya = nikaya.to_string();
+        let group_path_prefix = group_path_prefix.to_string();
+        let nikaya = Some(nikaya.trim()).filter(|s| !s.is_empty());
+        let group_path_prefix = Some(group_path_prefix.trim()).filter(|s| !s.is_empty());
+        match app_data.dbm.appdata.get_canon_reading_progress(nikaya, group_path_prefix) {
+            Ok(rows) => QString::from(serde_json::to_string(&rows).unwrap_or_else(|_| "[]".to_string())),
+            Err(e) => {
+                error(&format!("get_canon_reading_progress_json(): {}", e));
+                QString::from("[]")
+            }
+        }
+    }
+
+    pub fn get_reading_statistics_json(&self) -> QString {
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_reading_statistics(20) {
+            Ok(stats) => QString::from(serde_json::to_string(&stats).unwrap_or_else(|_| "{}".to_string())),
+            Err(e) => {
+                error(&format!("get_reading_statistics_json(): {}", e));
+                QString::from("{}")
+            }
+        }
+    }
+
+    // --- Reading plans ---
+
+    /// The reading plans as a JSON list of ReadingPlanSummary.
+    pub fn get_reading_plans_json(&self) -> QString {
+        use simsapa_backend::reading_plans::today;
+
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_reading_plans(today()) {
+            Ok(plans) => QString::from(serde_json::to_string(&plans).unwrap_or_else(|_| "[]".to_string())),
+            Err(e) => {
+                error(&format!("get_reading_plans_json(): {}", e));
+                QString::from("[]")
+            }
+        }
+    }
+
+    /// The items of a plan as a JSON list of PlanDayItem.
+    pub fn get_reading_plan_items_json(&self, plan_uid: &QString) -> QString {
+        use simsapa_backend::reading_plans::today;
+
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_reading_plan_items(&plan_uid.to_string(), today()) {
+            Ok(items) => QString::from(serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string())),
+            Err(e) => {
+                error(&format!("get_reading_plan_items_json(): {}", e));
+                QString::from("[]")
+            }
+        }
+    }
+
+    /// Today's items of the enrolled plans as a JSON list of PlanDayItem.
+    pub fn get_todays_reading_json(&self) -> QString {
+        use simsapa_backend::reading_plans::today;
+
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_reading_plan_items_for_day(today()) {
+            Ok(items) => QString::from(serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string())),
+            Err(e) => {
+                error(&format!("get_todays_reading_json(): {}", e));
+                QString::from("[]")
+            }
+        }
+    }
+
+    /// Import a plan JSON file. Returns {"ok": true, "uid": ...} or {"error": ...}.
+    pub fn import_reading_plan_file(&self, file_path: &QString) -> QString {
+        use simsapa_backend::reading_plans::import_plan_file;
+
+        let path = PathBuf::from(file_path.to_string());
+        let app_data = get_app_data();
+        match import_plan_file(&app_data.dbm.appdata, &path) {
+            Ok(plan) => QString::from(serde_json::json!({ "ok": true, "uid": plan.uid }).to_string()),
+            Err(e) => {
+                error(&format!("import_reading_plan_file(): {}", e));
+                QString::from(serde_json::json!({ "error": format!("{}", e) }).to_string())
+            }
+        }
+    }
+
+    /// Write a plan to a JSON file. Returns {"ok": true} or {"error": ...}.
+    pub fn export_reading_plan_file(&self, plan_uid: &QString, dest_path: &QString) -> QString {
+        use simsapa_backend::reading_plans::export_plan_file;
+
+        let path = PathBuf::from(dest_path.to_string());
+        let app_data = get_app_data();
+        match export_plan_file(&app_data.dbm.appdata, &plan_uid.to_string(), &path) {
+            Ok(()) => QString::from(serde_json::json!({ "ok": true }).to_string()),
+            Err(e) => {
+                error(&format!("export_reading_plan_file(): {}", e));
+                QString::from(serde_json::json!({ "error": format!("{}", e) }).to_string())
+            }
+        }
+    }
+
+    /// Generate and save a plan from PlanGeneratorOptions JSON.
+    /// Returns {"ok": true, "uid": ..., "items": n} or {"error": ...}.
+    pub fn generate_reading_plan(&self, options_json: &QString) -> QString {
+        use simsapa_backend::reading_plans::{generate_plan, PlanGeneratorOptions};
+
+        let options: PlanGeneratorOptions = match serde_json::from_str(&options_json.to_string()) {
+            Ok(o) => o,
+            Err(e) => {
+                error(&format!("generate_reading_plan() parse error: {}", e));
+                return QString::from(serde_json::json!({ "error": format!("{}", e) }).to_string());
+            }
+        };
+
+        let app_data = get_app_data();
+        let result = generate_plan(&app_data.dbm.appdata, &options)
+            .and_then(|plan| app_data.dbm.appdata.save_reading_plan(&plan).map(|_| plan));
+        match result {
+            Ok(plan) => QString::from(serde_json::json!({ "ok": true, "uid": plan.uid, "items": plan.items.len() }).to_string()),
+            Err(e) => {
+                error(&format!("generate_reading_plan(): {}", e));
+                QString::from(serde_json::json!({ "error": format!("{}", e) }).to_string())
+            }
+        }
+    }
+
+    pub fn delete_reading_plan(&self, plan_uid: &QString) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.delete_reading_plan(&plan_uid.to_string()) {
+            error(&format!("delete_reading_plan(): {}", e));
+        }
+    }
+
+    /// Start a plan on `start_date` (YYYY-MM-DD), or today if it is empty.
+    pub fn enroll_in_reading_plan(&self, plan_uid: &QString, start_date: &QString) -> bool {
+        use simsapa_backend::reading_plans::parse_plan_date;
+
+        let app_data = get_app_data();
+        let result = parse_plan_date(&start_date.to_string())
+            .and_then(|date| app_data.dbm.appdata.enroll_in_reading_plan(&plan_uid.to_string(), date));
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                error(&format!("enroll_in_reading_plan(): {}", e));
+                false
+            }
+        }
+    }
+
+    pub fn unenroll_from_reading_plan(&self, plan_uid: &QString) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.unenroll_from_reading_plan(&plan_uid.to_string()) {
+            error(&format!("unenroll_from_reading_plan(): {}", e));
+        }
+    }
+
+    pub fn set_reading_plan_active(&self, plan_uid: &QString, active: bool) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.set_reading_plan_active(&plan_uid.to_string(), active) {
+            error(&format!("set_reading_plan_active(): {}", e));
+        }
+    }
+
+    pub fn set_reading_plan_item_completed(&self, plan_uid: &QString, item_index: i32, completed: bool) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.set_reading_plan_item_completed(&plan_uid.to_string(), item_index, completed) {
+            error(&format!("set_reading_plan_item_completed(): {}", e));
+        }
+    }
+
+    // --- Saved searches ---
+
+    /// Save the search as it was run, with its area and SearchParams JSON.
+    /// The first run, in the background, is the baseline for the new results.
+    /// Returns {"ok": true, "id": ...} or {"error": ...}.
+    pub fn save_search(self: Pin<&mut Self>, name: &QString, query_text: &QString, search_area: &QString, params_json: &QString) -> QString {
+        let app_data = get_app_data();
+        let saved_search_id = match saved_searches::save_search(
+            &app_data.dbm, &name.to_string(), &query_text.to_string(), &search_area.to_string(), &params_json.to_string())
+        {
+            Ok(id) => id,
+            Err(e) => {
+                error(&format!("save_search(): {}", e));
+                return QString::from(serde_json::json!({ "error": format!("{}", e) }).to_string());
+            }
+        };
+
+        self.rerun_saved_search(saved_search_id);
+        QString::from(serde_json::json!({ "ok": true, "id": saved_search_id }).to_string())
+    }
+
+    /// The saved searches as a JSON list of SavedSearch.
+    pub fn get_saved_searches_json(&self) -> QString {
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_saved_searches() {
+            Ok(searches) => QString::from(serde_json::to_string(&searches).unwrap_or_else(|_| "[]".to_string())),
+            Err(e) => {
+                error(&format!("get_saved_searches_json(): {}", e));
+                QString::from("[]")
+            }
+        }
+    }
+
+    /// The results of a saved search seen so far, or only the new ones, as a
+    /// JSON list of SavedSearchHit.
+    pub fn get_saved_search_hits_json(&self, saved_search_id: i32, only_new: bool) -> QString {
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_saved_search_hits(saved_search_id, only_new) {
+            Ok(hits) => QString::from(serde_json::to_string(&hits).unwrap_or_else(|_| "[]".to_string())),
+            Err(e) => {
+                error(&format!("get_saved_search_hits_json(): {}", e));
+                QString::from("[]")
+            }
+        }
+    }
+
+    /// Run a saved search in the background and record its results. Emits
+    /// savedSearchesRunReady with the run.
+    pub fn rerun_saved_search(self: Pin<&mut Self>, saved_search_id: i32) {
+        let qt_thread = self.qt_thread();
+
+        thread::spawn(move || {
+            let app_data = get_app_data();
+            let report = match saved_searches::run_saved_search(&app_data.dbm, saved_search_id) {
+                Ok(run) => NewContentReport { reasons: Vec::new(), runs: vec![run] },
+                Err(e) => {
+                    error(&format!("rerun_saved_search(): {}", e));
+                    return;
+                }
+            };
+            let report_json = serde_json::to_string(&report).unwrap_or_default();
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().saved_searches_run_ready(QString::from(report_json));
+            }).unwrap();
+        });
+    }
+
+    /// After new content was imported, run all the saved searches in the
+    /// background. Emits savedSearchesRunReady if some have new results.
+    pub fn run_saved_searches_if_requested(self: Pin<&mut Self>) {
+        let qt_thread = self.qt_thread();
+
+        thread::spawn(move || {
+            let app_data = get_app_data();
+            let report = match saved_searches::run_saved_searches_if_requested(&app_data.dbm) {
+                Ok(Some(report)) if !report.runs.is_empty() => report,
+                Ok(_) => return,
+                Err(e) => {
+                    error(&format!("run_saved_searches_if_requested(): {}", e));
+                    return;
+                }
+            };
+            let report_json = serde_json::to_string(&report).unwrap_or_default();
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().saved_searches_run_ready(QString::from(report_json));
+            }).unwrap();
+        });
+    }
+
+    pub fn rename_saved_search(&self, saved_search_id: i32, name: &QString) -> bool {
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.rename_saved_search(saved_search_id, &name.to_string()) {
+            Ok(()) => true,
+            Err(e) => {
+                error(&format!("rename_saved_search(): {}", e));
+                false
+            }
+        }
+    }
+
+    pub fn delete_saved_search(&self, saved_search_id: i32) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.delete_saved_search(saved_search_id) {
+            error(&format!("delete_saved_search(): {}", e));
+        }
+    }
+
+    pub fn mark_saved_search_seen(&self, saved_search_id: i32) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.mark_saved_search_seen(saved_search_id) {
+            error(&format!("mark_saved_search_seen(): {}", e));
+        }
+    }
+
+    // --- Search history ---
+
+    /// Record a search with its hit count, unless the search history is
+    /// turned off. Returns the history item id, or -1.
+    pub fn record_search_history(&self, query_text: &QString, search_area: &QString, params_json: &QString, hits_count: i32) -> i32 {
+        let app_data = get_app_data();
+        if !app_data.get_search_history_enabled() {
+            return -1;
+        }
+        match search_history::record_search(
+            &app_data.dbm, &query_text.to_string(), &search_area.to_string(), &params_json.to_string(), hits_count)
+        {
+            Ok(Some(history_id)) => history_id,
+            Ok(None) => -1,
+            Err(e) => {
+                error(&format!("record_search_history(): {}", e));
+                -1
+            }
+        }
+    }
+
+    /// Remember the result the user opened from a recorded search.
+    pub fn set_search_history_opened(&self, history_id: i32, item_uid: &QString, table_name: &QString, title: &QString) {
+        if history_id < 0 {
+            return;
+        }
+        let app_data = get_app_data();
+        let title = title.to_string();
+        let title = if title.is_empty() { None } else { Some(title.as_str()) };
+        if let Err(e) = app_data.dbm.appdata.set_search_history_opened(
+            history_id, &item_uid.to_string(), &table_name.to_string(), title)
+        {
+            error(&format!("set_search_history_opened(): {}", e));
+        }
+    }
+
+    /// The recent searches as a JSON list of SearchHistoryItem.
+    pub fn get_search_history_json(&self, limit: i32) -> QString {
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_search_history(limit as i64) {
+            Ok(items) => QString::from(serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string())),
+            Err(e) => {
+                error(&format!("get_search_history_json(): {}", e));
+                QString::from("[]")
+            }
+        }
+    }
+
+    /// SearchHistoryStats as JSON, or "{}".
+    pub fn get_search_history_stats_json(&self) -> QString {
+        let app_data = get_app_data();
+        match app_data.dbm.appdata.get_search_history_stats(10) {
+            Ok(stats) => QString::from(serde_json::to_string(&stats).unwrap_or_else(|_| "{}".to_string())),
+            Err(e) => {
+                error(&format!("get_search_history_stats_json(): {}", e));
+                QString::from("{}")
+            }
+        }
+    }
+
+    pub fn delete_search_history_item(&self, history_id: i32) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.delete_search_history_item(history_id) {
+            error(&format!("delete_search_history_item(): {}", e));
+        }
+    }
+
+    pub fn clear_search_history(&self) {
+        let app_data = get_app_data();
+        if let Err(e) = app_data.dbm.appdata.clear_search_history() {
+            error(&format!("clear_search_history(): {}", e));
+        }
+    }
+
+    pub fn get_search_history_enabled(&self) -> bool {
+        let app_data = get_app_data();
+        app_data.get_search_history_enabled()
+    }
+
+    pub fn set_search_history_enabled(&self, enabled: bool) {
+        let app_data = get_app_data();
+        app_data.set_search_history_enabled(enabled);
+    }
+
+    /// Query suggestions in the background, as the completion lists are
+    /// loaded on first use. Emits querySuggestionsReady with the query, so
+    /// that a stale list can be ignored.
+    pub fn get_query_suggestions(self: Pin<&mut Self>, query_text: &QString, search_area: &QString, limit: i32) {
+        let qt_thread = self.qt_thread();
+        let query_text = query_text.to_string();
+        let search_area = search_area.to_string();
+
+        thread::spawn(move || {
+            let app_data = get_app_data();
+            let include_history = app_data.get_search_history_enabled();
+            let suggestions = match search_history::query_suggestions(
+                &app_data.dbm, &query_text, &search_area, include_history, limit.max(0) as usize)
+            {
+                Ok(suggestions) => suggestions,
+                Err(e) => {
+                    error(&format!("get_query_suggestions(): {}", e));
+                    Vec::new()
+                }
+            };
+            let suggestions_json = serde_json::to_string(&suggestions).unwrap_or_else(|_| "[]".to_string());
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().query_suggestions_ready(QString::from(query_text), QString::from(suggestions_json));
+            }).unwrap();
+        });
+    }
+
+    // --- LAN sync ---
+
+    /// This device's id and name, the LAN sync setting with the URL other
+    /// devices pair with, and the shared sync folder, as JSON.
+    pub fn get_lan_sync_json(&self) -> QString {
+        let app_data = get_app_data();
+        let (device_id, device_name) = sync::device_identity(&app_data.dbm).unwrap_or_else(|e| {
+            error(&format!("get_lan_sync_json(): {}", e));
+            (String::new(), String::new())
+        });
+        let enabled = app_data.get_sync_lan_enabled();
+        let port = app_data.get_sync_lan_port();
+        let url = if enabled { sync::lan_url(port) } else { None };
+        QString::from(serde_json::json!({
+            "device_id": device_id,
+            "device_name": device_name,
+            "enabled": enabled,
+            "port": port,
+            "url": url,
+            "sync_folder": app_data.get_sync_folder(),
+        }).to_string())
+    }
+
+    /// Serve the sync routes on the LAN, from the next start.
+    pub fn set_sync_lan_enabled(&self, enabled: bool) {
+        let app_data = get_app_data();
+        app_data.set_sync_lan_enabled(enabled);
+    }
+
+    pub fn set_sync_lan_port(&self, port: i32) {
+        let app_data = get_app_data();
+        match u16::try_from(port) {
+            Ok(port) if port > 0 => app_data.set_sync_lan_port(port),
+            _ => error(&format!("set_sync_lan_port(): invalid port {}", port)),
+        }
+    }
+
+    pub fn set_sync_device_name(&self, name: &QString) {
+        let app_data = get_app_data();
+        if let Err(e) = sync::set_device_name(&app_data.dbm, &name.to_string()) {
+            error(&format!("set_sync_device_name(): {}", e));
+        }
+    }
+
+    /// The shared folder synced on startup and on exit, an empty path unsets it.
+    pub fn set_sync_folder(&self, folder: &QString) {
+        let app_data = get_app_data();
+        app_data.set_sync_folder(Some(folder.to_string()));
+    }
+
+    /// Sync with the shared sync folder in the background, the result comes
+    /// with lan_sync_finished().
+    pub fn sync_folder_now(self: Pin<&mut Self>) {
+        let qt_thread = self.qt_thread();
+
+        thread::spawn(move || {
+            let app_data = get_app_data();
+            let result_json = match app_data.get_sync_folder() {
+                None => serde_json::json!({ "error": "No sync folder is set." }),
+                Some(folder) => match sync::sync_folder(&app_data.dbm, Path::new(&folder)) {
+                    Ok(report) => serde_json::json!({
+                        "ok": true,
+                        "message": format!("Sync folder: read {} devices, received {}, applied {}",
+                                           report.devices_read, report.received, report.applied),
+                    }),
+                    Err(e) => {
+                        error(&format!("sync_folder_now(): {:#}", e));
+                        serde_json::json!({ "error": format!("{:#}", e) })
+                    }
+                },
+            };
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().lan_sync_finished(QString::from(result_json.to_string()));
+            }).unwrap();
+        });
+    }
+
+    /// A one-time code for another device to pair with this one, as a JSON
+    /// PairingCode or {"error": ...}.
+    pub fn create_sync_pairing_code(&self) -> QString {
+        let app_data = get_app_data();
+        match sync::create_pairing_code(&app_data.dbm) {
+            Ok(code) => QString::from(serde_json::to_string(&code).unwrap_or_default()),
+            Err(e) => {
+                error(&format!("create_sync_pairing_code(): {}", e));
+                QString::from(serde_json::json!({ "error": format!("{}", e) }).to_string())
+            }
+        }
+    }
+
+    /// The paired devices as a JSON list of SyncPeer, without the secrets.
+    pub fn get_sync_peers_json(&self) -> QString {
+        let app_data = get_app_data();
+        let peers = sync::list_peers(&app_data.dbm);
+        QString::from(serde_json::to_string(&peers).unwrap_or_else(|_| "[]".to_string()))
+    }
+
+    pub fn unpair_sync_device(&self, device_id: &QString) -> bool {
+        let app_data = get_app_data();
+        match sync::remove_peer(&app_data.dbm, &device_id.to_string()) {
+            Ok(removed) => removed,
+            Err(e) => {
+                error(&format!("unpair_sync_device(): {}", e));
+                false
+            }
+        }
+    }
+
+    /// Pair with the device at `url` using the code it shows, in the
+    /// background. Emits lanSyncFinished.
+    pub fn pair_sync_device(self: Pin<&mut Self>, url: &QString, code: &QString) {
+        let qt_thread = self.qt_thread();
+        let url = url.to_string().trim().trim_end_matches('/').to_string();
+        let code = code.to_string();
+
+        thread::spawn(move || {
+            let app_data = get_app_data();
+            let result = HttpTransport::new()
+                .and_then(|transport| sync::pair_with(&app_data.dbm, &transport, &url, &code));
+            let result_json = match result {
+                Ok(peer) => serde_json::json!({ "ok": true, "message": format!("Paired with {}.", peer.name) }),
+                Err(e) => {
+                    error(&format!("pair_sync_device(): {:#}", e));
+                    serde_json::json!({ "error": format!("{:#}", e) })
+                }
+            };
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().lan_sync_finished(QString::from(result_json.to_string()));
+            }).unwrap();
+        });
+    }
+
+    /// Sync with the paired devices this device connects to, in the
+    /// background. Emits lanSyncFinished.
+    pub fn sync_lan_now(self: Pin<&mut Self>) {
+        let qt_thread = self.qt_thread();
+
+        thread::spawn(move || {
+            let app_data = get_app_data();
+            let peers: Vec<_> = sync::list_peers(&app_data.dbm).into_iter()
+                .filter(|p| p.url.is_some())
+                .collect();
+
+            let result_json = if peers.is_empty() {
+                serde_json::json!({ "error": "No paired device to sync with." })
+            } else {
+                match HttpTransport::new() {
+                    Ok(transport) => {
+                        let mut lines: Vec<String> = Vec::new();
+                        let mut failed = 0;
+                        for p in &peers {
+                            match sync::sync_with(&app_data.dbm, &transport, p) {
+                                Ok(report) => lines.push(format!("{}: sent {}, received {}, applied {}", p.name, report.sent, report.received, report.applied)),
+                                Err(e) => {
+                                    error(&format!("sync_lan_now(): {}: {:#}", p.name, e));
+                                    lines.push(format!("{}: {:#}", p.name, e));
+                                    failed += 1;
+                                }
+                            }
+                        }
+                        if failed == 0 {
+                            serde_json::json!({ "ok": true, "message": lines.join("\n") })
+                        } else {
+                            serde_json::json!({ "error": lines.join("\n") })
+                        }
+                    }
+                    Err(e) => serde_json::json!({ "error": format!("{:#}", e) }),
+                }
+            };
+            qt_thread.queue(move |mut qo| {
+                qo.as_mut().lan_sync_finished(QString::from(result_json.to_string()));
+            }).unwrap();
+        });
+    }
+
     pub fn save_last_session(self: Pin<&mut Self>, windows_json: &QString) {
         let json_str = windows_json.to_string();
 
@@ -0,0 +1,108 @@
+//! `simsapa_cli backup`: create, list, verify and restore the backups of the
+//! user data, see `simsapa_backend::backup`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::Subcommand;
+
+use simsapa_backend::backup::{self, RestorePlan};
+use simsapa_backend::get_app_data;
+
+#[derive(Subcommand, Debug)]
+pub enum BackupCommands {
+    /// Back up the user data now
+    Create {
+        /// Folder of the backup, by default the one in the settings
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// List the backups, newest first
+    List {
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// Check that a backup is complete and readable
+    #[command(arg_required_else_help = true)]
+    Verify {
+        file: PathBuf,
+    },
+
+    /// Restore the user data missing from the app, and the settings, from a backup
+    #[command(arg_required_else_help = true)]
+    Restore {
+        file: PathBuf,
+
+        /// Only show what the restore would add and change
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Remove all but the newest backups
+    Prune {
+        /// Number of backups to keep, by default the one in the settings
+        #[arg(long)]
+        keep: Option<usize>,
+
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+}
+
+fn print_plan(plan: &RestorePlan) {
+    println!("Backup of {} (app {})", plan.manifest.created_at.format("%Y-%m-%d %H:%M:%S"), plan.manifest.app_version);
+    for c in &plan.categories {
+        println!("{}\t{} in backup\t{} to add", c.category, c.in_backup, c.to_add);
+    }
+    if !plan.settings_changed.is_empty() {
+        println!("settings changed: {}", plan.settings_changed.join(", "));
+    }
+}
+
+fn size_mb(path: &Path) -> f64 {
+    std::fs::metadata(path).map(|m| m.len() as f64 / 1_048_576.0).unwrap_or(0.0)
+}
+
+/// Handle the `backup` CLI commands.
+pub fn backup_command(cmd: BackupCommands) -> Result<()> {
+    let app_data = get_app_data();
+    let dir_or_default = |dir: Option<PathBuf>| dir.unwrap_or_else(|| backup::backups_dir(app_data));
+
+    match cmd {
+        BackupCommands::Create { dir } => {
+            let created = backup::create_backup(app_data, &dir_or_default(dir))?;
+            println!("{} ({:.1} MB)", created.path.display(), size_mb(&created.path));
+        }
+
+        BackupCommands::List { dir } => {
+            for b in backup::list_backups(&dir_or_default(dir))? {
+                println!("{}\t{:.1} MB\t{}", b.created_at.format("%Y-%m-%d %H:%M:%S"), size_mb(&b.path), b.path.display());
+            }
+        }
+
+        BackupCommands::Verify { file } => {
+            let manifest = backup::verify_backup(&file)?;
+            println!("OK: {} files, made {} by app {}", manifest.files.len(),
+                     manifest.created_at.format("%Y-%m-%d %H:%M:%S"), manifest.app_version);
+        }
+
+        BackupCommands::Restore { file, dry_run } => {
+            let plan = backup::restore_backup(app_data, &file, dry_run)?;
+            print_plan(&plan);
+            if dry_run {
+                eprintln!("Dry run, nothing was changed.");
+            }
+        }
+
+        BackupCommands::Prune { keep, dir } => {
+            let keep = keep.unwrap_or_else(|| app_data.get_backup_keep());
+            for path in backup::prune_backups(&dir_or_default(dir), keep)? {
+                println!("Removed {}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -8,6 +8,11 @@ use indicatif::{ProgressBar, ProgressStyle};
 use simsapa_backend::epub_import::import_epub_to_db;
 use simsapa_backend::html_import::import_html_to_db;
 use simsapa_backend::pdf_import::import_pdf_to_db;
+use simsapa_backend::markdown_import::import_markdown_to_db;
+use simsapa_backend::docx_import::import_docx_to_db;
+use simsapa_backend::odt_import::import_odt_to_db;
+use simsapa_backend::fb2_import::import_fb2_to_db;
+use simsapa_backend::sutta_ref_links::link_sutta_refs_in_book;
 use simsapa_backend::logger;
 
 use crate::bootstrap::SuttaImporter;
@@ -75,8 +80,12 @@ impl LibraryImportsImporter {
             "epub" => Ok("epub"),
             "html" | "htm" => Ok("html"),
             "pdf" => Ok("pdf"),
+            "md" | "markdown" => Ok("markdown"),
+            "docx" => Ok("docx"),
+            "odt" => Ok("odt"),
+            "fb2" => Ok("fb2"),
             _ => Err(anyhow::anyhow!(
-                "Unsupported file type: {}. Only .epub, .html, and .pdf files are supported.",
+                "Unsupported file type: {}. Only .epub, .html, .pdf, .md, .docx, .odt and .fb2 files are supported.",
                 extension
             )),
         }
@@ -129,9 +138,29 @@ impl LibraryImportsImporter {
                 import_pdf_to_db(conn, &file_path, &entry.uid, custom_title, custom_author, custom_language, custom_enable_embedded_css, false)
                     .with_context(|| format!("Failed to import PDF: {}", entry.filename))?;
             }
+            "markdown" => {
+                import_markdown_to_db(conn, &file_path, &entry.uid, custom_title, custom_author, custom_language, false)
+                    .with_context(|| format!("Failed to import Markdown: {}", entry.filename))?;
+            }
+            "docx" => {
+                import_docx_to_db(conn, &file_path, &entry.uid, custom_title, custom_author, custom_language, false)
+                    .with_context(|| format!("Failed to import DOCX: {}", entry.filename))?;
+            }
+            "odt" => {
+                import_odt_to_db(conn, &file_path, &entry.uid, custom_title, custom_author, custom_language, false)
+                    .with_context(|| format!("Failed to import ODT: {}", entry.filename))?;
+            }
+            "fb2" => {
+                import_fb2_to_db(conn, &file_path, &entry.uid, custom_title, custom_author, custom_language, false)
+                    .with_context(|| format!("Failed to import FB2: {}", entry.filename))?;
+            }
             _ => unreachable!("Unsupported document type should have been caught earlier"),
         }
 
+        // The suttas are imported before the library, so the references resolve
+        link_sutta_refs_in_book(conn, &entry.uid)
+            .with_context(|| format!("Failed to link sutta references: {}", entry.filename))?;
+
         Ok(())
     }
 
@@ -251,6 +280,14 @@ mod tests {
             LibraryImportsImporter::get_document_type(Path::new("test.pdf")).unwrap(),
             "pdf"
         );
+        assert_eq!(
+            LibraryImportsImporter::get_document_type(Path::new("talk.md")).unwrap(),
+            "markdown"
+        );
+        assert_eq!(
+            LibraryImportsImporter::get_document_type(Path::new("talk.DOCX")).unwrap(),
+            "docx"
+        );
         assert!(LibraryImportsImporter::get_document_type(Path::new("test.txt")).is_err());
         assert!(LibraryImportsImporter::get_document_type(Path::new("test")).is_err());
     }
@@ -1019,6 +1019,13 @@ description = ""
 
     logger::info(&format!("Wrote languages.json to {:?}", languages_json_path));
 
+    // Checksums of the archives, for the app to verify downloads and repairs
+    let release_databases_dir = release_dir.join("databases/");
+    let manifest = simsapa_backend::integrity::write_release_manifest(&release_databases_dir, DB_VERSION)
+        .context("Failed to write the release manifest")?;
+
+    logger::info(&format!("Wrote {} with {} archives", simsapa_backend::integrity::RELEASE_MANIFEST_FILE, manifest.archives.len()));
+
     Ok(())
 }
 
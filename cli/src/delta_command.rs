@@ -0,0 +1,97 @@
+//! `simsapa_cli delta`: create the delta update between two releases of the
+//! databases, and check, stage or apply one, see
+//! `simsapa_backend::asset_delta`.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+
+use simsapa_backend::asset_delta::{self, DeltaManifest};
+use simsapa_backend::get_create_simsapa_app_assets_path;
+
+#[derive(Subcommand, Debug)]
+pub enum DeltaCommands {
+    /// Write the delta from the databases of one release to the next
+    #[command(arg_required_else_help = true)]
+    Create {
+        /// Folder with the databases of the previous release
+        #[arg(long)]
+        old: PathBuf,
+
+        /// Folder with the databases of the new release
+        #[arg(long)]
+        new: PathBuf,
+
+        /// The zip file to write
+        #[arg(long)]
+        out: PathBuf,
+    },
+
+    /// Check that a delta is complete and its checksums match
+    #[command(arg_required_else_help = true)]
+    Verify {
+        file: PathBuf,
+    },
+
+    /// Put a delta in the app assets, to be applied on the next start
+    #[command(arg_required_else_help = true)]
+    Stage {
+        file: PathBuf,
+    },
+
+    /// Apply a delta to the app assets now. The app must not be running.
+    #[command(arg_required_else_help = true)]
+    Apply {
+        file: PathBuf,
+    },
+}
+
+fn print_manifest(m: &DeltaManifest) {
+    println!("Delta from {} to {}, {} changes", m.from_version, m.to_version, m.total_changes());
+    for db in &m.databases {
+        for t in &db.tables {
+            if t.rows + t.deletes > 0 {
+                println!("{}\t{}\t{} rows\t{} deletes", db.file, t.spec.table, t.rows, t.deletes);
+            }
+        }
+    }
+}
+
+/// Handle the `delta` CLI commands.
+pub fn delta_command(cmd: DeltaCommands) -> Result<()> {
+    match cmd {
+        DeltaCommands::Create { old, new, out } => {
+            let version = |dir: &PathBuf| {
+                asset_delta::read_db_version(dir)?
+                    .ok_or_else(|| anyhow!("No db_version in {}", dir.display()))
+            };
+            let (from_version, to_version) = (version(&old)?, version(&new)?);
+            let manifest = asset_delta::create_delta(
+                &old, &new, &asset_delta::release_delta_tables(), &from_version, &to_version, &out,
+            )?;
+            print_manifest(&manifest);
+            let sha256 = asset_delta::sha256_file(&out)?;
+            println!("{}\t{} bytes\tsha256 {}", out.display(), std::fs::metadata(&out)?.len(), sha256);
+        }
+
+        DeltaCommands::Verify { file } => {
+            print_manifest(&asset_delta::verify_delta(&file)?);
+            println!("OK");
+        }
+
+        DeltaCommands::Stage { file } => {
+            let manifest = asset_delta::stage_delta(&file, &get_create_simsapa_app_assets_path())?;
+            println!("Staged the delta to {}, it will be applied on the next start", manifest.to_version);
+        }
+
+        DeltaCommands::Apply { file } => {
+            let report = asset_delta::apply_delta(&file, &get_create_simsapa_app_assets_path())?;
+            for (table, n) in report.changes.iter().filter(|(_, n)| **n > 0) {
+                println!("{}\t{}", table, n);
+            }
+            println!("Updated the databases from {} to {}", report.from_version, report.to_version);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! `simsapa_cli integrity`: check the installed databases and indexes, and
+//! repair them, see `simsapa_backend::integrity`.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Subcommand;
+
+use simsapa_backend::integrity::{self, ArchiveSource, CheckStatus, IntegrityReport, VerifyOptions};
+use simsapa_backend::AppGlobalPaths;
+
+#[derive(Subcommand, Debug)]
+pub enum IntegrityCommands {
+    /// Check the databases, the fulltext indexes, and optionally a folder of release archives
+    Verify {
+        /// Run the full integrity checks, which read every page and index file
+        #[arg(long)]
+        full: bool,
+
+        /// Folder with release archives and their release-manifest.json to check as well
+        #[arg(long)]
+        archives: Option<PathBuf>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Apply the repairs the checks suggest. The app must not be running.
+    Repair {
+        /// Folder with the release archives and their release-manifest.json
+        #[arg(long, conflicts_with = "url")]
+        archives: Option<PathBuf>,
+
+        /// Folder URL of the release archives, e.g. https://github.com/simsapa/simsapa-ng-assets/releases/download/v0.4.1
+        #[arg(long)]
+        url: Option<String>,
+
+        #[arg(long)]
+        full: bool,
+    },
+}
+
+fn print_report(report: &IntegrityReport) {
+    for c in &report.checks {
+        let status = match c.status {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "WARNING",
+            CheckStatus::Failed => "FAILED",
+        };
+        println!("{}\t{}\t{}", status, c.target, c.message);
+    }
+    let repairs = report.repairs();
+    if !repairs.is_empty() {
+        println!();
+        println!("Suggested repairs (simsapa_cli integrity repair):");
+        for r in repairs {
+            println!("  {}", r.describe());
+        }
+    }
+}
+
+/// Handle the `integrity` CLI commands.
+pub fn integrity_command(cmd: IntegrityCommands) -> Result<()> {
+    let paths = AppGlobalPaths::new();
+
+    match cmd {
+        IntegrityCommands::Verify { full, archives, json } => {
+            let options = VerifyOptions { full, archives_dir: archives };
+            let report = integrity::verify_installation(&paths.app_assets_dir, &options);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_report(&report);
+            }
+            if !report.is_ok() {
+                bail!("The integrity check failed");
+            }
+        }
+
+        IntegrityCommands::Repair { archives, url, full } => {
+            let source = match (archives, url) {
+                (Some(dir), _) => Some(ArchiveSource::Dir(dir)),
+                (None, Some(url)) => Some(ArchiveSource::Url(url)),
+                (None, None) => None,
+            };
+            let options = VerifyOptions { full, archives_dir: None };
+            let (report, failed) = integrity::repair_installation(
+                &paths.app_assets_dir, &options, source.as_ref(), &paths.download_temp_folder,
+            );
+            for (repair, e) in &failed {
+                println!("Repair failed, {}: {}", repair.describe(), e);
+            }
+            print_report(&report);
+            if !report.is_ok() {
+                bail!("Some problems remain");
+            }
+        }
+    }
+    Ok(())
+}
@@ -2,6 +2,11 @@ pub mod bootstrap;
 pub mod bootstrap_old;
 pub mod update_provider_models;
 pub mod update_releases_fallback;
+pub mod mcp_server;
+pub mod sync_command;
+pub mod backup_command;
+pub mod delta_command;
+pub mod integrity_command;
 
 use std::path::{Path, PathBuf};
 use std::process::exit;
@@ -21,6 +26,7 @@ use simsapa_backend::db::appdata_models::Sutta;
 use simsapa_backend::asset_helpers::import_suttas_from_db;
 use simsapa_backend::search::indexer;
 use simsapa_backend::search::searcher::{FulltextSearcher, SearchFilters};
+use simsapa_backend::api_server::{self, ApiServerConfig};
 
 fn get_query_results(query: &str, area: SearchArea) -> Vec<SearchResult> {
     let app_data = get_app_data();
@@ -332,22 +338,12 @@ fn suttacentral_lang_code_to_name() -> Result<(), String> {
     Ok(())
 }
 
-/// Import an EPUB file into the appdata database
-fn import_epub(db_path: &Path, epub_path: &Path, book_uid: &str) -> Result<(), String> {
+/// Connect to an appdata database for a book import, creating it and the
+/// books tables when they don't exist.
+fn open_books_db(db_path: &Path) -> Result<SqliteConnection, String> {
     use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-    use simsapa_backend::epub_import::import_epub_to_db;
     use simsapa_backend::helpers::run_fts5_indexes_sql_script;
 
-    println!("Importing EPUB file...");
-    println!("Database: {:?}", db_path);
-    println!("EPUB file: {:?}", epub_path);
-    println!("Book UID: {}", book_uid);
-
-    // Check if EPUB file exists
-    if !epub_path.exists() {
-        return Err(format!("EPUB file not found: {:?}", epub_path));
-    }
-
     // Connect to the database (create if it doesn't exist)
     let mut conn = SqliteConnection::establish(db_path.to_str().unwrap())
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
@@ -390,10 +386,99 @@ fn import_epub(db_path: &Path, epub_path: &Path, book_uid: &str) -> Result<(), S
         println!("Books tables already exist, skipping migrations.");
     }
 
+    Ok(conn)
+}
+
+/// Link the sutta references in the chapters of an imported book. A failure
+/// is reported, the book stays imported.
+fn link_book_sutta_refs(conn: &mut SqliteConnection, book_uid: &str) {
+    use simsapa_backend::sutta_ref_links::link_sutta_refs_in_book;
+
+    match link_sutta_refs_in_book(conn, book_uid) {
+        Ok(n) => println!("Linked {} sutta citations", n),
+        Err(e) => eprintln!("Failed to link the sutta references: {}", e),
+    }
+}
+
+/// Link the sutta references of a book, or of every book, in the appdata database
+fn link_sutta_refs(db_path: &Path, book_uid: Option<&str>) -> Result<(), String> {
+    use simsapa_backend::db::appdata_schema::books;
+    use simsapa_backend::sutta_ref_links::link_sutta_refs_in_book;
+
+    let mut conn = open_books_db(db_path)?;
+
+    let book_uids: Vec<String> = match book_uid {
+        Some(uid) => vec![uid.to_string()],
+        None => books::table
+            .select(books::uid)
+            .order(books::uid.asc())
+            .load(&mut conn)
+            .map_err(|e| format!("Failed to load books: {}", e))?,
+    };
+
+    for uid in &book_uids {
+        let n = link_sutta_refs_in_book(&mut conn, uid)
+            .map_err(|e| format!("Failed to link the sutta references in {}: {}", uid, e))?;
+        println!("{}: {} sutta citations", uid, n);
+    }
+
+    Ok(())
+}
+
+/// Generate a reading plan and write it to a JSON file
+fn generate_reading_plan(db_path: &Path,
+                         uid: &str,
+                         title: &str,
+                         items: simsapa_backend::reading_plans::PlanItemsSource,
+                         days: Option<u32>,
+                         sequential: bool,
+                         output: &Path) -> Result<(), String> {
+    use simsapa_backend::db::DatabaseHandle;
+    use simsapa_backend::reading_plans::{generate_plan, plan_to_json, PlanGeneratorOptions, PlanSchedule};
+
+    let appdata = DatabaseHandle::new(&db_path.to_string_lossy())
+        .map_err(|e| format!("Failed to open {}: {}", db_path.display(), e))?;
+
+    let options = PlanGeneratorOptions {
+        uid: uid.to_string(),
+        title: title.to_string(),
+        description: None,
+        author: None,
+        schedule: if sequential { PlanSchedule::Sequential } else { PlanSchedule::Daily },
+        days,
+        items,
+    };
+    let plan = generate_plan(&appdata, &options)
+        .map_err(|e| format!("Failed to generate the reading plan: {}", e))?;
+    let json = plan_to_json(&plan).map_err(|e| format!("{}", e))?;
+    std::fs::write(output, json)
+        .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+
+    println!("{}: {} items over {} days written to {}", plan.uid, plan.items.len(), plan.days_total(), output.display());
+    Ok(())
+}
+
+/// Import an EPUB file into the appdata database
+fn import_epub(db_path: &Path, epub_path: &Path, book_uid: &str) -> Result<(), String> {
+    use simsapa_backend::epub_import::import_epub_to_db;
+
+    println!("Importing EPUB file...");
+    println!("Database: {:?}", db_path);
+    println!("EPUB file: {:?}", epub_path);
+    println!("Book UID: {}", book_uid);
+
+    // Check if EPUB file exists
+    if !epub_path.exists() {
+        return Err(format!("EPUB file not found: {:?}", epub_path));
+    }
+
+    let mut conn = open_books_db(db_path)?;
+
     // Import the EPUB
     println!("Importing EPUB...");
     import_epub_to_db(&mut conn, epub_path, book_uid, None, None, None, None, true)
         .map_err(|e| format!("Failed to import EPUB: {}", e))?;
+    link_book_sutta_refs(&mut conn, book_uid);
 
     println!("Successfully imported EPUB with UID: {}", book_uid);
 
@@ -402,9 +487,7 @@ fn import_epub(db_path: &Path, epub_path: &Path, book_uid: &str) -> Result<(), S
 
 /// Import an HTML file into the appdata database
 fn import_html(db_path: &Path, html_path: &Path, book_uid: &str) -> Result<(), String> {
-    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
     use simsapa_backend::html_import::import_html_to_db;
-    use simsapa_backend::helpers::run_fts5_indexes_sql_script;
 
     println!("Importing HTML file...");
     println!("Database: {:?}", db_path);
@@ -416,54 +499,127 @@ fn import_html(db_path: &Path, html_path: &Path, book_uid: &str) -> Result<(), S
         return Err(format!("HTML file not found: {:?}", html_path));
     }
 
-    // Connect to the database (create if it doesn't exist)
-    let mut conn = SqliteConnection::establish(db_path.to_str().unwrap())
-        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+    let mut conn = open_books_db(db_path)?;
 
-    // Helper to check if a table exists
-    #[derive(QueryableByName)]
-    struct CountResult {
-        #[diesel(sql_type = diesel::sql_types::BigInt)]
-        count: i64,
+    // Import the HTML
+    println!("Importing HTML...");
+    import_html_to_db(&mut conn, html_path, book_uid, None, None, None, None, true)
+        .map_err(|e| format!("Failed to import HTML: {}", e))?;
+    link_book_sutta_refs(&mut conn, book_uid);
+
+    println!("Successfully imported HTML with UID: {}", book_uid);
+
+    Ok(())
+}
+
+/// Import a Markdown (file or folder), DOCX, ODT or FB2 document into the appdata database
+fn import_converted_document(db_path: &Path, path: &Path, book_uid: &str, document_type: &str) -> Result<(), String> {
+    use simsapa_backend::{docx_import, fb2_import, markdown_import, odt_import};
+
+    let label = document_type.to_uppercase();
+    println!("Importing {} document...", label);
+    println!("Database: {:?}", db_path);
+    println!("{} path: {:?}", label, path);
+    println!("Book UID: {}", book_uid);
+
+    if !path.exists() {
+        return Err(format!("{} path not found: {:?}", label, path));
     }
 
-    let table_exists = |conn: &mut SqliteConnection, table_name: &str| -> bool {
-        let query = format!(
-            "SELECT COUNT(*) as count FROM sqlite_master WHERE type='table' AND name='{}'",
-            table_name
-        );
-        diesel::sql_query(&query)
-            .get_result::<CountResult>(conn)
-            .map(|r| r.count > 0)
-            .unwrap_or(false)
+    let mut conn = open_books_db(db_path)?;
+
+    println!("Importing {}...", label);
+    let result = match document_type {
+        "markdown" => markdown_import::import_markdown_to_db(&mut conn, path, book_uid, None, None, None, true),
+        "docx" => docx_import::import_docx_to_db(&mut conn, path, book_uid, None, None, None, true),
+        "odt" => odt_import::import_odt_to_db(&mut conn, path, book_uid, None, None, None, true),
+        "fb2" => fb2_import::import_fb2_to_db(&mut conn, path, book_uid, None, None, None, true),
+        _ => return Err(format!("Unsupported document type: {}", document_type)),
     };
+    result.map_err(|e| format!("Failed to import {}: {}", label, e))?;
+    link_book_sutta_refs(&mut conn, book_uid);
 
-    // Check if books or book_spine_items_fts tables exist
-    let books_exists = table_exists(&mut conn, "books");
-    let fts_exists = table_exists(&mut conn, "book_spine_items_fts");
+    println!("Successfully imported {} with UID: {}", label, book_uid);
 
-    // Run migrations only if books or FTS table doesn't exist
-    if !books_exists || !fts_exists {
-        println!("Running database migrations...");
-        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../backend/migrations/appdata");
-        conn.run_pending_migrations(MIGRATIONS)
-            .map_err(|e| format!("Failed to run migrations: {}", e))?;
+    Ok(())
+}
 
-        // Run the books FTS5 indexes script
-        println!("Creating FTS5 indexes for books...");
-        let sql_script_path = PathBuf::from("../scripts/books-fts5-indexes.sql");
-        run_fts5_indexes_sql_script(db_path, &sql_script_path)
-            .map_err(|e| format!("Failed to run FTS5 indexes script: {}", e))?;
-    } else {
-        println!("Books tables already exist, skipping migrations.");
+/// The OCR for `import-pdf` and `import-images`, with the Pāli spelling repair
+/// when a DPD database is given.
+fn ocr_for_cli_import(ocr_languages: Option<&str>, dpd_db_path: Option<&Path>) -> Result<Option<simsapa_backend::ocr::Ocr>, String> {
+    use simsapa_backend::ocr::{Ocr, OcrEngine, PaliSpellingCorrector};
+
+    let Some(languages) = ocr_languages else {
+        return Ok(None);
+    };
+    let engine = OcrEngine::new(languages);
+    if !engine.is_available() {
+        return Err("The tesseract command is not available, install Tesseract OCR.".to_string());
     }
+    let corrector = match dpd_db_path {
+        Some(dpd_db_path) => {
+            let mut dpd_conn = SqliteConnection::establish(dpd_db_path.to_str().unwrap())
+                .map_err(|e| format!("Failed to connect to DPD database: {}", e))?;
+            let corrector = PaliSpellingCorrector::from_dpd(&mut dpd_conn)
+                .map_err(|e| format!("Failed to read the DPD word forms: {}", e))?;
+            println!("Pāli spelling repair with {} word forms", corrector.len());
+            Some(corrector)
+        }
+        None => None,
+    };
+    println!("OCR languages: {}", engine.languages());
+    Ok(Some(Ocr::new(engine, corrector)))
+}
 
-    // Import the HTML
-    println!("Importing HTML...");
-    import_html_to_db(&mut conn, html_path, book_uid, None, None, None, None, true)
-        .map_err(|e| format!("Failed to import HTML: {}", e))?;
+/// Import a PDF file into the appdata database
+fn import_pdf(db_path: &Path, pdf_path: &Path, book_uid: &str, ocr_languages: Option<&str>, dpd_db_path: Option<&Path>) -> Result<(), String> {
+    use simsapa_backend::pdf_import::import_pdf_to_db_with_ocr;
 
-    println!("Successfully imported HTML with UID: {}", book_uid);
+    println!("Importing PDF file...");
+    println!("Database: {:?}", db_path);
+    println!("PDF file: {:?}", pdf_path);
+    println!("Book UID: {}", book_uid);
+
+    // Check if PDF file exists
+    if !pdf_path.exists() {
+        return Err(format!("PDF file not found: {:?}", pdf_path));
+    }
+
+    let ocr = ocr_for_cli_import(ocr_languages, dpd_db_path)?;
+    let mut conn = open_books_db(db_path)?;
+
+    // Import the PDF
+    println!("Importing PDF...");
+    import_pdf_to_db_with_ocr(&mut conn, pdf_path, book_uid, None, None, None, None, true, ocr.as_ref())
+        .map_err(|e| format!("Failed to import PDF: {}", e))?;
+    link_book_sutta_refs(&mut conn, book_uid);
+
+    println!("Successfully imported PDF with UID: {}", book_uid);
+
+    Ok(())
+}
+
+/// Import a folder of page images into the appdata database
+fn import_images(db_path: &Path, folder: &Path, book_uid: &str, ocr_languages: Option<&str>, dpd_db_path: Option<&Path>) -> Result<(), String> {
+    use simsapa_backend::image_import::import_images_to_db;
+
+    println!("Importing page images...");
+    println!("Database: {:?}", db_path);
+    println!("Images folder: {:?}", folder);
+    println!("Book UID: {}", book_uid);
+
+    if !folder.is_dir() {
+        return Err(format!("Images folder not found: {:?}", folder));
+    }
+
+    let ocr = ocr_for_cli_import(ocr_languages, dpd_db_path)?;
+    let mut conn = open_books_db(db_path)?;
+
+    import_images_to_db(&mut conn, folder, book_uid, None, None, None, true, ocr.as_ref())
+        .map_err(|e| format!("Failed to import page images: {}", e))?;
+    link_book_sutta_refs(&mut conn, book_uid);
+
+    println!("Successfully imported page images with UID: {}", book_uid);
 
     Ok(())
 }
@@ -829,6 +985,7 @@ fn fulltext_search(
         include_bold_definitions: true,
         dict_source_uids: None,
         show_all_snippets: false,
+        fuzzy_distance: 0,
     };
 
     let (total_hits, results) = match area {
@@ -1230,6 +1387,187 @@ enum Commands {
         uid: String,
     },
 
+    /// Import a Markdown file, or a folder of Markdown files (one per chapter), into the appdata database
+    #[command(arg_required_else_help = true)]
+    ImportMarkdown {
+        /// Path to the appdata.sqlite3 database
+        #[arg(long, value_name = "DB_PATH")]
+        db_path: PathBuf,
+
+        /// Path to the Markdown file or folder to import
+        #[arg(long, value_name = "MARKDOWN_PATH")]
+        markdown_path: PathBuf,
+
+        /// Unique identifier for the book
+        #[arg(long, value_name = "UID")]
+        uid: String,
+    },
+
+    /// Import a Word (DOCX) document into the appdata database
+    #[command(arg_required_else_help = true)]
+    ImportDocx {
+        /// Path to the appdata.sqlite3 database
+        #[arg(long, value_name = "DB_PATH")]
+        db_path: PathBuf,
+
+        /// Path to the DOCX file to import
+        #[arg(long, value_name = "DOCX_PATH")]
+        docx_path: PathBuf,
+
+        /// Unique identifier for the book
+        #[arg(long, value_name = "UID")]
+        uid: String,
+    },
+
+    /// Import an OpenDocument text (ODT) document into the appdata database
+    #[command(arg_required_else_help = true)]
+    ImportOdt {
+        /// Path to the appdata.sqlite3 database
+        #[arg(long, value_name = "DB_PATH")]
+        db_path: PathBuf,
+
+        /// Path to the ODT file to import
+        #[arg(long, value_name = "ODT_PATH")]
+        odt_path: PathBuf,
+
+        /// Unique identifier for the book
+        #[arg(long, value_name = "UID")]
+        uid: String,
+    },
+
+    /// Import a FictionBook (FB2) file into the appdata database
+    #[command(arg_required_else_help = true)]
+    ImportFb2 {
+        /// Path to the appdata.sqlite3 database
+        #[arg(long, value_name = "DB_PATH")]
+        db_path: PathBuf,
+
+        /// Path to the FB2 file to import
+        #[arg(long, value_name = "FB2_PATH")]
+        fb2_path: PathBuf,
+
+        /// Unique identifier for the book
+        #[arg(long, value_name = "UID")]
+        uid: String,
+    },
+
+    /// Link the sutta references in library books and index their citations
+    #[command(arg_required_else_help = true)]
+    LinkSuttaRefs {
+        /// Path to the appdata.sqlite3 database
+        #[arg(long, value_name = "DB_PATH")]
+        db_path: PathBuf,
+
+        /// The book to link, all books when not given
+        #[arg(long, value_name = "UID")]
+        uid: Option<String>,
+    },
+
+    /// Generate a reading plan JSON file from a nikāya, a sutta range, a
+    /// group_path or a library book
+    #[command(arg_required_else_help = true)]
+    GenerateReadingPlan {
+        /// Path to the appdata.sqlite3 database
+        #[arg(long, value_name = "DB_PATH")]
+        db_path: PathBuf,
+
+        /// Plan uid, e.g. mn-in-a-year
+        #[arg(long)]
+        uid: String,
+
+        /// Plan title
+        #[arg(long)]
+        title: String,
+
+        /// All suttas of a nikāya, e.g. mn
+        #[arg(long, conflicts_with_all = ["first_uid", "group_path", "book_uid"])]
+        nikaya: Option<String>,
+
+        /// Language of the nikāya suttas
+        #[arg(long, default_value = "en")]
+        language: String,
+
+        /// Source of the nikāya suttas, e.g. sujato
+        #[arg(long)]
+        source_uid: Option<String>,
+
+        /// First sutta of a range, e.g. sn56.1/en/sujato
+        #[arg(long, conflicts_with_all = ["group_path", "book_uid"])]
+        first_uid: Option<String>,
+
+        /// Last sutta of the range, the end of the nikāya when not given
+        #[arg(long, requires = "first_uid")]
+        last_uid: Option<String>,
+
+        /// The suttas under a group_path, e.g. /sutta-pitaka/digha-nikaya
+        #[arg(long, conflicts_with = "book_uid")]
+        group_path: Option<String>,
+
+        /// The chapters of a library book
+        #[arg(long)]
+        book_uid: Option<String>,
+
+        /// Spread the items over this many days, one a day when not given
+        #[arg(long)]
+        days: Option<u32>,
+
+        /// Read at one's own pace instead of on dates
+        #[arg(long, default_value_t = false)]
+        sequential: bool,
+
+        /// Output JSON file
+        #[arg(long, value_name = "JSON_PATH")]
+        output: PathBuf,
+    },
+
+    /// Import a PDF file into the appdata database
+    #[command(arg_required_else_help = true)]
+    ImportPdf {
+        /// Path to the appdata.sqlite3 database
+        #[arg(long, value_name = "DB_PATH")]
+        db_path: PathBuf,
+
+        /// Path to the PDF file to import
+        #[arg(long, value_name = "PDF_PATH")]
+        pdf_path: PathBuf,
+
+        /// Unique identifier for the book
+        #[arg(long, value_name = "UID")]
+        uid: String,
+
+        /// Recognize the text of scanned pages with Tesseract, in these languages (e.g. "eng")
+        #[arg(long, value_name = "LANGUAGES")]
+        ocr: Option<String>,
+
+        /// Path to dpd.sqlite3, for the Pāli spelling repair of the recognized text
+        #[arg(long, value_name = "DPD_PATH", requires = "ocr")]
+        dpd_path: Option<PathBuf>,
+    },
+
+    /// Import a folder of page images (PNG, TIFF, JPEG) as a book into the appdata database
+    #[command(arg_required_else_help = true)]
+    ImportImages {
+        /// Path to the appdata.sqlite3 database
+        #[arg(long, value_name = "DB_PATH")]
+        db_path: PathBuf,
+
+        /// Path to the folder of page images, in file name order
+        #[arg(long, value_name = "FOLDER")]
+        folder: PathBuf,
+
+        /// Unique identifier for the book
+        #[arg(long, value_name = "UID")]
+        uid: String,
+
+        /// Recognize the text of the pages with Tesseract, in these languages (e.g. "eng")
+        #[arg(long, value_name = "LANGUAGES")]
+        ocr: Option<String>,
+
+        /// Path to dpd.sqlite3, for the Pāli spelling repair of the recognized text
+        #[arg(long, value_name = "DPD_PATH", requires = "ocr")]
+        dpd_path: Option<PathBuf>,
+    },
+
     /// Parse CIPS general-index.csv and generate JSON for topic index
     #[command(arg_required_else_help = true)]
     ParseCipsIndex {
@@ -1337,6 +1675,63 @@ enum Commands {
         #[arg(long, value_name = "OUTPUT_PATH", default_value = DEFAULT_RELEASES_FALLBACK_PATH)]
         output: PathBuf,
     },
+
+    /// Run the localhost API (/search, /word.json, /health, ...) without the
+    /// desktop app, e.g. on a server or in a container.
+    ///
+    /// Routes which open an app window (open_sutta_tab, lookup_window_query,
+    /// ...) answer with 501 Not Implemented.
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 4848, env = "API_PORT")]
+        port: u16,
+
+        /// Address to listen on. Use 0.0.0.0 to accept connections from other hosts,
+        /// /shutdown and /logger then need the API token.
+        #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1")]
+        bind: String,
+
+        /// Reject requests which change state (e.g. /shutdown) with 403 Forbidden
+        #[arg(long, default_value_t = false)]
+        read_only: bool,
+
+        /// Allowed CORS origin, can be repeated. Without it any origin is allowed.
+        #[arg(long = "cors-origin", value_name = "ORIGIN")]
+        cors_origins: Vec<String>,
+
+        /// Base url written into rendered pages for asset links, when the
+        /// server is reached through a proxy or public host name.
+        /// Defaults to http://localhost:<port>
+        #[arg(long, value_name = "URL")]
+        public_url: Option<String>,
+
+        /// Don't serve the bookmark and history routes. Otherwise they require
+        /// the token in api-token.txt in the Simsapa folder (or SIMSAPA_API_TOKEN).
+        #[arg(long, default_value_t = false)]
+        no_user_data: bool,
+    },
+
+    /// Run a Model Context Protocol server on stdin/stdout, exposing sutta
+    /// search, sutta text, DPD lookup, compound deconstruction and reference
+    /// resolution as tools for AI assistants.
+    Mcp,
+
+    /// Pair with another device and sync bookmarks, history, chanting
+    /// collections and settings with it over the LAN
+    #[command(subcommand)]
+    Sync(sync_command::SyncCommands),
+
+    /// Back up the user data, and verify or restore the backups
+    #[command(subcommand)]
+    Backup(backup_command::BackupCommands),
+
+    /// Create, check and apply the delta updates of the databases between releases
+    #[command(subcommand)]
+    Delta(delta_command::DeltaCommands),
+
+    /// Check the installed databases and fulltext indexes, and repair them
+    #[command(subcommand)]
+    Integrity(integrity_command::IntegrityCommands),
 }
 
 /// Default output path for `update-releases-fallback`, resolved at compile time
@@ -1393,18 +1788,43 @@ enum IndexCommands {
     },
 }
 
+/// Run the headless API server. Unless `no_user_data`, the bookmark and
+/// history routes are served, guarded by the local API token.
+fn serve(mut config: ApiServerConfig, no_user_data: bool) -> Result<()> {
+    if !no_user_data {
+        let token_path = &simsapa_backend::get_app_globals().paths.simsapa_api_token_path;
+        config.api_token = Some(api_server::load_or_create_api_token(token_path)?);
+        eprintln!("The bookmark and history routes need the API token from {}", token_path.display());
+    }
+    api_server::run_headless(config)
+}
+
 fn main() {
     // Attempt to load .env file. This might define SIMSAPA_DIR if it's not
     // already in the environment. Clap will pick it up via `env = "SIMSAPA_DIR"`.
     if dotenv().is_err() {
-        println!("Info: No .env file found or failed to load.");
+        eprintln!("Info: No .env file found or failed to load.");
     }
 
     let cli = Cli::parse();
 
+    // AppGlobals reads the API port and url from the environment when
+    // init_app_data() creates them, so set these before that.
+    if let Commands::Serve { port, public_url, .. } = &cli.command {
+        unsafe { std::env::set_var("API_PORT", port.to_string()); }
+        if let Some(url) = public_url {
+            unsafe { std::env::set_var("API_URL", url); }
+        }
+    }
+
+    // The MCP protocol owns stdout, keep log lines out of it.
+    if let Commands::Mcp = &cli.command {
+        unsafe { std::env::set_var("ENABLE_PRINT_LOG", "false"); }
+    }
+
     // Don't initialize app data for bootstrap commands since they need to create directories first
     match &cli.command {
-        Commands::Bootstrap { .. } | Commands::BootstrapOld { .. } | Commands::DhammapadaTipitakaNetExport { .. } | Commands::AppdataStats { .. } | Commands::SuttacentralImportLanguagesList | Commands::SuttacentralLangCodeToName | Commands::ImportEpub { .. } | Commands::ImportHtml { .. } | Commands::ParseCipsIndex { .. } | Commands::ImportLanguage { .. } | Commands::UpdateProviderModels { .. } | Commands::UpdateReleasesFallback { .. } => {
+        Commands::Bootstrap { .. } | Commands::BootstrapOld { .. } | Commands::DhammapadaTipitakaNetExport { .. } | Commands::AppdataStats { .. } | Commands::SuttacentralImportLanguagesList | Commands::SuttacentralLangCodeToName | Commands::ImportEpub { .. } | Commands::ImportHtml { .. } | Commands::ImportPdf { .. } | Commands::ImportImages { .. } | Commands::ImportMarkdown { .. } | Commands::ImportDocx { .. } | Commands::ImportOdt { .. } | Commands::ImportFb2 { .. } | Commands::LinkSuttaRefs { .. } | Commands::GenerateReadingPlan { .. } | Commands::ParseCipsIndex { .. } | Commands::ImportLanguage { .. } | Commands::UpdateProviderModels { .. } | Commands::UpdateReleasesFallback { .. } | Commands::Delta(_) | Commands::Integrity(_) => {
             // Skip app data initialization for bootstrap, export, stats, suttacentral, import, and parse commands
         }
         _ => {
@@ -1509,6 +1929,52 @@ fn main() {
             import_html(&db_path, &html_path, &uid)
         }
 
+        Commands::ImportMarkdown { db_path, markdown_path, uid } => {
+            import_converted_document(&db_path, &markdown_path, &uid, "markdown")
+        }
+
+        Commands::ImportDocx { db_path, docx_path, uid } => {
+            import_converted_document(&db_path, &docx_path, &uid, "docx")
+        }
+
+        Commands::ImportOdt { db_path, odt_path, uid } => {
+            import_converted_document(&db_path, &odt_path, &uid, "odt")
+        }
+
+        Commands::ImportFb2 { db_path, fb2_path, uid } => {
+            import_converted_document(&db_path, &fb2_path, &uid, "fb2")
+        }
+
+        Commands::LinkSuttaRefs { db_path, uid } => {
+            link_sutta_refs(&db_path, uid.as_deref())
+        }
+
+        Commands::GenerateReadingPlan { db_path, uid, title, nikaya, language, source_uid, first_uid, last_uid, group_path, book_uid, days, sequential, output } => {
+            use simsapa_backend::reading_plans::PlanItemsSource;
+
+            let items = if let Some(nikaya) = nikaya {
+                Some(PlanItemsSource::Nikaya { nikaya, language, source_uid })
+            } else if let Some(first_uid) = first_uid {
+                Some(PlanItemsSource::SuttaRange { first_uid, last_uid })
+            } else if let Some(group_path) = group_path {
+                Some(PlanItemsSource::GroupPath { group_path })
+            } else {
+                book_uid.map(|book_uid| PlanItemsSource::Book { book_uid })
+            };
+            match items {
+                Some(items) => generate_reading_plan(&db_path, &uid, &title, items, days, sequential, &output),
+                None => Err("One of --nikaya, --first-uid, --group-path or --book-uid is required".to_string()),
+            }
+        }
+
+        Commands::ImportPdf { db_path, pdf_path, uid, ocr, dpd_path } => {
+            import_pdf(&db_path, &pdf_path, &uid, ocr.as_deref(), dpd_path.as_deref())
+        }
+
+        Commands::ImportImages { db_path, folder, uid, ocr, dpd_path } => {
+            import_images(&db_path, &folder, &uid, ocr.as_deref(), dpd_path.as_deref())
+        }
+
         Commands::ParseCipsIndex { csv_path, json_path, db_path, minify } => {
             parse_cips_index_command(&csv_path, &json_path, db_path.as_deref(), minify)
         }
@@ -1533,6 +1999,36 @@ fn main() {
                 .map_err(|e| format!("{:#}", e))
         }
 
+        Commands::Serve { port, bind, read_only, cors_origins, public_url: _, no_user_data } => {
+            serve(ApiServerConfig { bind, port, read_only, cors_origins, api_token: None }, no_user_data)
+                .map_err(|e| format!("{:#}", e))
+        }
+
+        Commands::Mcp => {
+            mcp_server::run_mcp_stdio()
+                .map_err(|e| format!("{:#}", e))
+        }
+
+        Commands::Sync(subcmd) => {
+            sync_command::sync_command(subcmd)
+                .map_err(|e| format!("{:#}", e))
+        }
+
+        Commands::Backup(subcmd) => {
+            backup_command::backup_command(subcmd)
+                .map_err(|e| format!("{:#}", e))
+        }
+
+        Commands::Delta(subcmd) => {
+            delta_command::delta_command(subcmd)
+                .map_err(|e| format!("{:#}", e))
+        }
+
+        Commands::Integrity(subcmd) => {
+            integrity_command::integrity_command(subcmd)
+                .map_err(|e| format!("{:#}", e))
+        }
+
         Commands::FulltextSearch { query, limit, snippet, lang, source, format, area, output } => {
             let search_area = match area {
                 FulltextSearchArea::Suttas => SearchArea::Suttas,
@@ -0,0 +1,581 @@
+//! Model Context Protocol server over stdio (`simsapa_cli mcp`).
+//!
+//! Speaks newline-delimited JSON-RPC 2.0 on stdin/stdout, so an assistant can
+//! search and quote the local suttas and dictionaries instead of relying on its
+//! memory of them. Tools:
+//!
+//! - `search_suttas`: `SearchQueryTask` over the suttas
+//! - `get_sutta`: segments of one sutta, via `AppData::sutta_to_segments_json()`
+//! - `dpd_lookup`: DPD headwords and roots for a word
+//! - `deconstruct_compound`: DPD deconstructor splits of a compound
+//! - `resolve_reference`: PTS / sutta / DPR references to sutta uids
+//!
+//! Suttas are also readable as resources, `simsapa://sutta/<uid>` (the uid
+//! keeps its slashes, e.g. `simsapa://sutta/mn10/pli/ms`).
+//!
+//! Nothing but protocol messages may be written to stdout, so `main()` turns
+//! off print logging for this command.
+
+use std::io::{BufRead, Write};
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+
+use simsapa_backend::{get_app_data, init_fulltext_searcher, init_sutta_references};
+use simsapa_backend::api_server::routes::{convert_verse_ref_to_sutta_uid, lookup_sutta_with_fallback};
+use simsapa_backend::db::appdata_models::Sutta;
+use simsapa_backend::helpers::{query_text_to_uid_field_query, strip_html};
+use simsapa_backend::logger::info;
+use simsapa_backend::pts_reference_search::{self, parse_pts_reference};
+use simsapa_backend::query_task::SearchQueryTask;
+use simsapa_backend::types::{SearchArea, SearchMode, SearchParams};
+
+/// Newest protocol revision this server implements. Older clients get their
+/// own revision echoed back, the message shapes used here are the same.
+const PROTOCOL_VERSION: &str = "2025-03-26";
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+const SUTTA_URI_PREFIX: &str = "simsapa://sutta/";
+
+const DEFAULT_PAGE_LEN: usize = 10;
+const MAX_PAGE_LEN: usize = 50;
+const MAX_DPD_RESULTS: usize = 20;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC error, sent back as the `error` member of a response.
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+}
+
+/// Read requests from stdin until it closes, answering each on stdout.
+pub fn run_mcp_stdio() -> Result<()> {
+    init_sutta_references();
+    info("MCP server started on stdio");
+
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = line.context("Reading stdin")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_message(&line, &call_tool, &read_resource) {
+            writeln!(stdout, "{}", response).context("Writing stdout")?;
+            stdout.flush().context("Flushing stdout")?;
+        }
+    }
+
+    info("MCP server: stdin closed");
+    Ok(())
+}
+
+type ToolFn<'a> = &'a dyn Fn(&str, &Value) -> Result<Value, RpcError>;
+type ResourceFn<'a> = &'a dyn Fn(&str) -> Result<Value, RpcError>;
+
+/// Handle one line of input. Returns the serialized response, or `None` for
+/// notifications. Tools and resources are passed in so the protocol handling
+/// can be tested without the databases.
+fn handle_message(line: &str, tools: ToolFn, resources: ResourceFn) -> Option<String> {
+    let msg: Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => return Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, format!("Parse error: {}", e))).to_string()),
+    };
+
+    // Batches were dropped from the protocol in 2025-06-18, but are cheap to keep.
+    if let Value::Array(items) = msg {
+        let responses: Vec<Value> = items.iter().filter_map(|m| handle_request(m, tools, resources)).collect();
+        if responses.is_empty() {
+            return None;
+        }
+        return Some(Value::Array(responses).to_string());
+    }
+
+    handle_request(&msg, tools, resources).map(|v| v.to_string())
+}
+
+fn handle_request(msg: &Value, tools: ToolFn, resources: ResourceFn) -> Option<Value> {
+    let id = msg.get("id").cloned();
+    let method = match msg.get("method").and_then(|m| m.as_str()) {
+        Some(m) => m,
+        None => {
+            // A response from the client (we send no requests) or garbage.
+            return id.map(|id| error_response(id, RpcError::new(INVALID_REQUEST, "Missing method")));
+        }
+    };
+
+    // Notifications (no id) get no response.
+    let id = id?;
+
+    let params = msg.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => Ok(initialize_result(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => handle_tools_call(&params, tools),
+        "resources/list" => Ok(json!({ "resources": [] })),
+        "resources/templates/list" => Ok(json!({ "resourceTemplates": resource_templates() })),
+        "resources/read" => handle_resources_read(&params, resources),
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => error_response(id, e),
+    })
+}
+
+fn error_response(id: Value, e: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": e.code, "message": e.message },
+    })
+}
+
+fn initialize_result(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(|v| v.as_str()).unwrap_or(PROTOCOL_VERSION);
+    let version = if SUPPORTED_PROTOCOL_VERSIONS.contains(&requested) { requested } else { PROTOCOL_VERSION };
+
+    json!({
+        "protocolVersion": version,
+        "capabilities": {
+            "tools": { "listChanged": false },
+            "resources": { "subscribe": false, "listChanged": false },
+        },
+        "serverInfo": {
+            "name": "simsapa",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "instructions": "Search and quote the Pāli Canon and its translations from the local Simsapa database. \
+Use search_suttas or resolve_reference to find a sutta uid, then get_sutta to quote its text. \
+Use dpd_lookup and deconstruct_compound for Pāli words.",
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "search_suttas",
+            "description": "Search the suttas by words or by a reference (e.g. 'SN 56.11', 'mn10'). Returns uids, titles and matching snippets.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Search words, or a sutta reference" },
+                    "lang": { "type": "string", "description": "Language code of the texts, e.g. 'pli' or 'en'. All languages when omitted." },
+                    "mode": { "type": "string", "enum": ["fulltext", "contains", "title"], "description": "fulltext (default): stemmed word search; contains: exact substring; title: sutta titles" },
+                    "page": { "type": "integer", "minimum": 0, "description": "Page number, from 0" },
+                    "page_len": { "type": "integer", "minimum": 1, "maximum": MAX_PAGE_LEN },
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "get_sutta",
+            "description": "Get the text of a sutta by uid (e.g. 'mn10/pli/ms', 'sn56.11/en/sujato') as segments keyed by segment id.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "uid": { "type": "string", "description": "Sutta uid. A bare code such as 'mn10' means the Pāli 'mn10/pli/ms'." },
+                },
+                "required": ["uid"],
+            },
+        },
+        {
+            "name": "dpd_lookup",
+            "description": "Look up a Pāli word (inflected forms too) in the Digital Pāḷi Dictionary. Returns headwords with their meanings.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "word": { "type": "string" },
+                },
+                "required": ["word"],
+            },
+        },
+        {
+            "name": "deconstruct_compound",
+            "description": "Split a Pāli compound or sandhi form into its parts, using the DPD deconstructor.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "word": { "type": "string" },
+                },
+                "required": ["word"],
+            },
+        },
+        {
+            "name": "resolve_reference",
+            "description": "Resolve a PTS reference (e.g. 'D ii 20', 'M i 56'), sutta reference ('SN 56.11') or Pāli title to suttas, with their uids.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "field": {
+                        "type": "string",
+                        "enum": ["pts_reference", "sutta_ref", "title_pali", "dpr_reference"],
+                        "description": "Which reference to match. Detected from the query when omitted.",
+                    },
+                },
+                "required": ["query"],
+            },
+        },
+    ])
+}
+
+fn resource_templates() -> Value {
+    json!([
+        {
+            "uriTemplate": format!("{}{{+uid}}", SUTTA_URI_PREFIX),
+            "name": "Sutta",
+            "description": "Sutta segments by uid, e.g. simsapa://sutta/mn10/pli/ms",
+            "mimeType": "application/json",
+        }
+    ])
+}
+
+fn handle_tools_call(params: &Value, tools: ToolFn) -> Result<Value, RpcError> {
+    let name = params.get("name").and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("Missing tool name"))?;
+    let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+    match tools(name, &arguments) {
+        Ok(value) => Ok(tool_result(&value, false)),
+        // Unknown tools and bad arguments are protocol errors, anything else
+        // is reported to the model as a failed tool call.
+        Err(e) if e.code == INVALID_PARAMS => Err(e),
+        Err(e) => Ok(tool_result(&Value::String(e.message), true)),
+    }
+}
+
+fn tool_result(value: &Value, is_error: bool) -> Value {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        v => serde_json::to_string_pretty(v).unwrap_or_default(),
+    };
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
+
+fn handle_resources_read(params: &Value, resources: ResourceFn) -> Result<Value, RpcError> {
+    let uri = params.get("uri").and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::invalid_params("Missing uri"))?;
+    let value = resources(uri)?;
+    Ok(json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": "application/json",
+            "text": serde_json::to_string_pretty(&value).unwrap_or_default(),
+        }]
+    }))
+}
+
+fn sutta_uri(uid: &str) -> String {
+    format!("{}{}", SUTTA_URI_PREFIX, uid)
+}
+
+fn sutta_uid_from_uri(uri: &str) -> Option<&str> {
+    uri.strip_prefix(SUTTA_URI_PREFIX).filter(|uid| !uid.is_empty())
+}
+
+fn str_arg<'a>(args: &'a Value, key: &str) -> Result<&'a str, RpcError> {
+    args.get(key).and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty())
+        .ok_or_else(|| RpcError::invalid_params(format!("Missing argument: {}", key)))
+}
+
+fn opt_str_arg<'a>(args: &'a Value, key: &str) -> Option<&'a str> {
+    args.get(key).and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty())
+}
+
+fn tool_error(e: impl std::fmt::Display) -> RpcError {
+    RpcError::new(-32000, e.to_string())
+}
+
+fn call_tool(name: &str, args: &Value) -> Result<Value, RpcError> {
+    info(&format!("MCP tools/call: {}", name));
+    match name {
+        "search_suttas" => search_suttas(args),
+        "get_sutta" => {
+            let uid = str_arg(args, "uid")?;
+            get_sutta(uid)
+        }
+        "dpd_lookup" => dpd_lookup(str_arg(args, "word")?),
+        "deconstruct_compound" => {
+            let word = str_arg(args, "word")?;
+            let splits = get_app_data().dbm.dpd.dpd_deconstructor_list(word);
+            Ok(json!({ "word": word, "deconstructions": splits }))
+        }
+        "resolve_reference" => resolve_reference(str_arg(args, "query")?, opt_str_arg(args, "field")),
+        _ => Err(RpcError::invalid_params(format!("Unknown tool: {}", name))),
+    }
+}
+
+fn read_resource(uri: &str) -> Result<Value, RpcError> {
+    match sutta_uid_from_uri(uri) {
+        Some(uid) => get_sutta(uid),
+        None => Err(RpcError::invalid_params(format!("Unknown resource: {}", uri))),
+    }
+}
+
+fn search_suttas(args: &Value) -> Result<Value, RpcError> {
+    let query = str_arg(args, "query")?;
+    let page = args.get("page").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+    let page_len = args.get("page_len").and_then(|v| v.as_u64())
+        .map(|n| (n as usize).clamp(1, MAX_PAGE_LEN))
+        .unwrap_or(DEFAULT_PAGE_LEN);
+
+    let mode = match opt_str_arg(args, "mode").unwrap_or("fulltext") {
+        "fulltext" => SearchMode::FulltextMatch,
+        "contains" => SearchMode::ContainsMatch,
+        "title" => SearchMode::TitleMatch,
+        m => return Err(RpcError::invalid_params(format!("Unknown mode: {}", m))),
+    };
+
+    // A reference such as "SN 56.11" is looked up by uid, as in the search bar.
+    let uid_query = query_text_to_uid_field_query(query);
+    let (query_text, mode) = if uid_query.starts_with("uid:") {
+        (uid_query, SearchMode::UidMatch)
+    } else {
+        (query.to_string(), mode)
+    };
+
+    if mode == SearchMode::FulltextMatch {
+        init_fulltext_searcher();
+    }
+
+    let lang = opt_str_arg(args, "lang").map(|s| s.to_string());
+    let params = SearchParams {
+        mode,
+        page_len: Some(page_len),
+        lang_include: lang.is_some(),
+        lang,
+        source: None,
+        source_include: true,
+        enable_regex: false,
+        fuzzy_distance: 0,
+        include_cst_mula: true,
+        include_cst_commentary: true,
+        nikaya_prefix: None,
+        uid_prefix: None,
+        uid_suffix: None,
+        include_ms_mula: true,
+        include_comm_bold_definitions: true,
+        dict_source_uids: None,
+        show_all_snippets: false,
+        snippet_exclude: None,
+    };
+
+    let app_data = get_app_data();
+    let mut task = SearchQueryTask::new(&app_data.dbm, query_text, params, SearchArea::Suttas);
+    let results = task.results_page(page).map_err(tool_error)?;
+    let total_hits = task.total_hits();
+
+    let items: Vec<Value> = results.iter().map(|r| json!({
+        "uid": r.uid,
+        "uri": sutta_uri(&r.uid),
+        "title": r.title,
+        "sutta_ref": r.sutta_ref,
+        "lang": r.lang,
+        "author": r.author,
+        "snippet": strip_html(&r.snippet),
+    })).collect();
+
+    Ok(json!({
+        "total_hits": total_hits,
+        "page": page,
+        "page_len": page_len,
+        "has_more": (page + 1) * page_len < total_hits,
+        "results": items,
+    }))
+}
+
+fn get_sutta(uid: &str) -> Result<Value, RpcError> {
+    let app_data = get_app_data();
+    let uid = convert_verse_ref_to_sutta_uid(uid.trim_matches('/'));
+    let sutta = lookup_sutta_with_fallback(&app_data.dbm, &uid)
+        .ok_or_else(|| tool_error(format!("Sutta not found: {}", uid)))?;
+
+    let mut doc = json!({
+        "uid": sutta.uid,
+        "uri": sutta_uri(&sutta.uid),
+        "sutta_ref": sutta.sutta_ref,
+        "title": sutta.title,
+        "title_pali": sutta.title_pali,
+        "lang": sutta.language,
+        "source_uid": sutta.source_uid,
+    });
+
+    if sutta.content_json.as_deref().is_some_and(|s| !s.is_empty()) {
+        let segments = app_data.sutta_to_segments_json(&sutta, false, false).map_err(tool_error)?;
+        let segments: serde_json::Map<String, Value> = segments.into_iter()
+            .map(|(k, v)| (k, Value::String(strip_html(&v).trim().to_string())))
+            .filter(|(_, v)| v.as_str().is_some_and(|s| !s.is_empty()))
+            .collect();
+        doc["segments"] = Value::Object(segments);
+    } else {
+        doc["text"] = Value::String(sutta_plain_text(&sutta));
+    }
+
+    Ok(doc)
+}
+
+/// Text of a sutta stored as an HTML blob instead of Bilara segments.
+fn sutta_plain_text(sutta: &Sutta) -> String {
+    match (&sutta.content_plain, &sutta.content_html) {
+        (Some(plain), _) if !plain.is_empty() => plain.clone(),
+        (_, Some(html)) => strip_html(html),
+        _ => String::new(),
+    }
+}
+
+fn dpd_lookup(word: &str) -> Result<Value, RpcError> {
+    let results = get_app_data().dbm.dpd.dpd_lookup(word, true, false, None, None).map_err(tool_error)?;
+    let total = results.len();
+
+    let items: Vec<Value> = results.into_iter().take(MAX_DPD_RESULTS).map(|r| json!({
+        "uid": r.uid,
+        "headword": r.title,
+        "meaning": strip_html(&r.snippet),
+    })).collect();
+
+    Ok(json!({ "word": word, "total": total, "results": items }))
+}
+
+fn resolve_reference(query: &str, field: Option<&str>) -> Result<Value, RpcError> {
+    let field = match field {
+        Some(f) => f,
+        None if parse_pts_reference(query).is_some() => "pts_reference",
+        None => "sutta_ref",
+    };
+
+    let mut results = pts_reference_search::search(query, field);
+    // A bare Pāli name doesn't match any sutta_ref, try the titles.
+    if results.is_empty() && field == "sutta_ref" {
+        results = pts_reference_search::search(query, "title_pali");
+    }
+
+    let app_data = get_app_data();
+    let items: Vec<Value> = results.iter().map(|r| {
+        let code = r.url.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+        let uid = format!("{}/pli/ms", code);
+        let exists = !code.is_empty() && app_data.dbm.appdata.get_sutta(&uid).is_some();
+        json!({
+            "sutta_ref": r.sutta_ref,
+            "title_pali": r.title_pali,
+            "pts_reference": r.pts_reference,
+            "dpr_reference": r.dpr_reference,
+            "uid": if exists { Value::String(uid.clone()) } else { Value::Null },
+            "uri": if exists { Value::String(sutta_uri(&uid)) } else { Value::Null },
+            "url": r.url,
+        })
+    }).collect();
+
+    Ok(json!({ "query": query, "field": field, "results": items }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_tools(name: &str, _args: &Value) -> Result<Value, RpcError> {
+        match name {
+            "echo" => Ok(json!({ "ok": true })),
+            "fail" => Err(tool_error("Sutta not found: xx1")),
+            _ => Err(RpcError::invalid_params(format!("Unknown tool: {}", name))),
+        }
+    }
+
+    fn no_resources(uri: &str) -> Result<Value, RpcError> {
+        match sutta_uid_from_uri(uri) {
+            Some(uid) => Ok(json!({ "uid": uid })),
+            None => Err(RpcError::invalid_params("Unknown resource")),
+        }
+    }
+
+    fn call(line: &str) -> Option<Value> {
+        handle_message(line, &no_tools, &no_resources).map(|s| serde_json::from_str(&s).unwrap())
+    }
+
+    #[test]
+    fn test_initialize_echoes_supported_version() {
+        let resp = call(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{}}}"#).unwrap();
+        assert_eq!(resp["id"], json!(1));
+        assert_eq!(resp["result"]["protocolVersion"], json!("2024-11-05"));
+        assert_eq!(resp["result"]["serverInfo"]["name"], json!("simsapa"));
+
+        let resp = call(r#"{"jsonrpc":"2.0","id":2,"method":"initialize","params":{"protocolVersion":"1999-01-01"}}"#).unwrap();
+        assert_eq!(resp["result"]["protocolVersion"], json!(PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn test_notifications_get_no_response() {
+        assert!(call(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#).is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        let resp = call("{not json").unwrap();
+        assert_eq!(resp["error"]["code"], json!(PARSE_ERROR));
+        assert_eq!(resp["id"], Value::Null);
+
+        let resp = call(r#"{"jsonrpc":"2.0","id":"a","method":"nope"}"#).unwrap();
+        assert_eq!(resp["id"], json!("a"));
+        assert_eq!(resp["error"]["code"], json!(METHOD_NOT_FOUND));
+    }
+
+    #[test]
+    fn test_tools_list_names() {
+        let resp = call(r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#).unwrap();
+        let names: Vec<&str> = resp["result"]["tools"].as_array().unwrap().iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["search_suttas", "get_sutta", "dpd_lookup", "deconstruct_compound", "resolve_reference"]);
+    }
+
+    #[test]
+    fn test_tools_call_results() {
+        let resp = call(r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"echo","arguments":{}}}"#).unwrap();
+        assert_eq!(resp["result"]["isError"], json!(false));
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        assert_eq!(serde_json::from_str::<Value>(text).unwrap(), json!({ "ok": true }));
+
+        // A failing tool is a result with isError, not a protocol error.
+        let resp = call(r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"fail"}}"#).unwrap();
+        assert_eq!(resp["result"]["isError"], json!(true));
+        assert_eq!(resp["result"]["content"][0]["text"], json!("Sutta not found: xx1"));
+
+        let resp = call(r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"missing"}}"#).unwrap();
+        assert_eq!(resp["error"]["code"], json!(INVALID_PARAMS));
+    }
+
+    #[test]
+    fn test_resources_read_sutta_uri() {
+        let resp = call(r#"{"jsonrpc":"2.0","id":1,"method":"resources/read","params":{"uri":"simsapa://sutta/mn10/pli/ms"}}"#).unwrap();
+        let content = &resp["result"]["contents"][0];
+        assert_eq!(content["uri"], json!("simsapa://sutta/mn10/pli/ms"));
+        let doc: Value = serde_json::from_str(content["text"].as_str().unwrap()).unwrap();
+        assert_eq!(doc["uid"], json!("mn10/pli/ms"));
+
+        assert_eq!(sutta_uid_from_uri("simsapa://sutta/"), None);
+        assert_eq!(sutta_uid_from_uri("https://suttacentral.net/mn10"), None);
+    }
+}
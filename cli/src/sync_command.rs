@@ -0,0 +1,146 @@
+//! `simsapa_cli sync`: pair with another device and sync the user data with
+//! it over the LAN, see `simsapa_backend::sync`.
+//!
+//! The other device serves the API on the LAN, e.g. with
+//! `simsapa_cli serve --bind 0.0.0.0`, or the app with LAN sync turned on in
+//! Sync Devices. Without a connection between the
+//! devices, `sync folder` syncs through a folder shared by e.g. Syncthing.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Subcommand;
+
+use simsapa_backend::get_app_data;
+use simsapa_backend::sync::{self, HttpTransport};
+
+#[derive(Subcommand, Debug)]
+pub enum SyncCommands {
+    /// Show a one-time code for another device to pair with this one
+    Code,
+
+    /// Pair with a device using the code it shows
+    #[command(arg_required_else_help = true)]
+    Pair {
+        /// The device's API server, e.g. http://192.168.1.20:4848
+        #[arg(long)]
+        url: String,
+
+        /// The six digit pairing code
+        #[arg(long)]
+        code: String,
+    },
+
+    /// Sync with the paired devices this device connects to
+    Now {
+        /// Only sync with this device id
+        #[arg(long, value_name = "DEVICE_ID")]
+        peer: Option<String>,
+    },
+
+    /// List the paired devices
+    Peers,
+
+    /// Forget a paired device
+    #[command(arg_required_else_help = true)]
+    Unpair {
+        device_id: String,
+    },
+
+    /// Show this device's id and name, or set the name other devices see
+    Name {
+        name: Option<String>,
+    },
+
+    /// Sync through a folder shared by e.g. Syncthing, by default the one in
+    /// the settings
+    Folder {
+        /// The shared folder
+        dir: Option<PathBuf>,
+
+        /// Also save DIR in the settings, so the app syncs on startup and exit
+        #[arg(long, requires = "dir")]
+        save: bool,
+    },
+}
+
+/// Handle the `sync` CLI commands.
+pub fn sync_command(cmd: SyncCommands) -> Result<()> {
+    let app_data = get_app_data();
+    let dbm = &app_data.dbm;
+
+    match cmd {
+        SyncCommands::Code => {
+            let code = sync::create_pairing_code(dbm)?;
+            println!("{}", code.code);
+            eprintln!("Valid once, for {} minutes.", sync::PAIRING_CODE_TTL_MS / 60_000);
+        }
+
+        SyncCommands::Pair { url, code } => {
+            let transport = HttpTransport::new()?;
+            let peer = sync::pair_with(dbm, &transport, &url, &code)?;
+            println!("Paired with {} ({})", peer.name, peer.device_id);
+        }
+
+        SyncCommands::Now { peer } => {
+            let peers: Vec<_> = sync::list_peers(dbm).into_iter()
+                .filter(|p| p.url.is_some())
+                .filter(|p| peer.as_ref().is_none_or(|id| &p.device_id == id))
+                .collect();
+            if peers.is_empty() {
+                bail!("No paired device to sync with. Pair first with: simsapa_cli sync pair --url <URL> --code <CODE>");
+            }
+
+            let transport = HttpTransport::new()?;
+            let mut failed = 0;
+            for p in &peers {
+                match sync::sync_with(dbm, &transport, p) {
+                    Ok(report) => println!("{}: sent {}, received {}, applied {}", p.name, report.sent, report.received, report.applied),
+                    Err(e) => {
+                        eprintln!("{}: {:#}", p.name, e);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                bail!("{} of {} syncs failed", failed, peers.len());
+            }
+        }
+
+        SyncCommands::Peers => {
+            for p in sync::list_peers(dbm) {
+                let last = p.last_synced_at.map(|t| t.to_string()).unwrap_or_else(|| "never".to_string());
+                println!("{}\t{}\t{}\tlast synced: {}", p.device_id, p.name, p.url.as_deref().unwrap_or("-"), last);
+            }
+        }
+
+        SyncCommands::Unpair { device_id } => {
+            if !sync::remove_peer(dbm, &device_id)? {
+                bail!("No paired device with id {}", device_id);
+            }
+        }
+
+        SyncCommands::Name { name } => {
+            if let Some(name) = name {
+                sync::set_device_name(dbm, &name)?;
+            }
+            let (device_id, device_name) = sync::device_identity(dbm)?;
+            println!("{}\t{}", device_id, device_name);
+        }
+
+        SyncCommands::Folder { dir, save } => {
+            let Some(dir) = dir.or_else(|| app_data.get_sync_folder().map(PathBuf::from)) else {
+                bail!("No sync folder is set. Pass one with: simsapa_cli sync folder <DIR> --save");
+            };
+            let report = sync::sync_folder(dbm, &dir)?;
+            if save {
+                app_data.set_sync_folder(Some(dir.to_string_lossy().to_string()));
+            }
+            println!("{}: read {} devices, received {}, applied {}, {}",
+                dir.display(), report.devices_read, report.received, report.applied,
+                if report.written { "wrote this device's snapshot" } else { "no local changes to write" });
+        }
+    }
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+#include <chrono>
+#include <future>
 #include <sstream>
 #include <thread>
 
@@ -46,6 +48,8 @@ extern "C" void shutdown_webserver();
 extern "C" bool appdata_db_exists();
 extern "C" void ensure_no_empty_db_files();
 extern "C" void check_delete_files_for_upgrade();
+extern "C" void apply_pending_delta_updates();
+extern "C" void apply_pending_asset_repairs();
 extern "C" void remove_download_temp_folder();
 extern "C" void init_app_globals();
 extern "C" void init_app_data();
@@ -54,6 +58,8 @@ extern "C" void init_android_context(void* java_vm, void* context);
 #endif
 extern "C" void import_user_data_after_upgrade();
 extern "C" void cleanup_stale_legacy_userdata();
+extern "C" void sync_user_data_folder();
+extern "C" void start_backup_scheduler();
 extern "C" void check_and_configure_for_first_start();
 extern "C" bool reconcile_dict_indexes_needed_c();
 extern "C" void reconcile_dict_indexes_blocking_c();
@@ -85,6 +91,22 @@ struct AppGlobals {
 WindowManager* AppGlobals::manager = nullptr;
 GlobalHotkeyManager* AppGlobals::global_hotkey_manager = nullptr;
 
+// Run the sync with the shared sync folder on a worker thread, and wait for it
+// at most `timeout`. A folder on a slow or unreachable network drive then
+// doesn't hold up the start or the exit of the app. A sync which runs over is
+// left to finish in the background: each merge is one database transaction.
+static void sync_user_data_folder_with_timeout(std::chrono::seconds timeout) {
+  std::packaged_task<void()> task(sync_user_data_folder);
+  std::future<void> done = task.get_future();
+  std::thread(std::move(task)).detach();
+
+  if (done.wait_for(timeout) == std::future_status::timeout) {
+    std::ostringstream msg;
+    msg << "The sync folder didn't finish syncing in " << timeout.count() << " seconds, continuing.";
+    log_error_c(msg.str().c_str());
+  }
+}
+
 void callback_run_lookup_query(QString query_text) {
   emit AppGlobals::manager->signal_run_lookup_query(query_text);
 }
@@ -347,6 +369,12 @@ int start(int argc, char* argv[]) {
   // created by prepare_for_database_upgrade().
   check_delete_files_for_upgrade();
 
+  // Install the release archives of an integrity repair, then apply the
+  // downloaded or copied delta updates of the databases, before they are
+  // opened.
+  apply_pending_asset_repairs();
+  apply_pending_delta_updates();
+
   QString os(QSysInfo::productType());
 
   // Initialize a QtWebView / QtWebEngineView. Otherwise the app errors:
@@ -476,6 +504,13 @@ int start(int argc, char* argv[]) {
   // alpha-upgrade bridge completed. No-op when no import-me/ is pending.
   cleanup_stale_legacy_userdata();
 
+  // Merge the changes other devices left in the shared sync folder, if one is
+  // set in the settings.
+  sync_user_data_folder_with_timeout(std::chrono::seconds(10));
+
+  // Back up the user data in the background when a backup is due.
+  start_backup_scheduler();
+
   // Check if this is the first start and configure settings based on system memory
   check_and_configure_for_first_start();
 
@@ -584,6 +619,9 @@ int start(int argc, char* argv[]) {
   log_info_c("app.exec()");
   int status = app.exec();
 
+  // Leave this session's changes in the shared sync folder.
+  sync_user_data_folder_with_timeout(std::chrono::seconds(10));
+
   shutdown_webserver();
   if (daemon_server_thread.joinable()) {
     daemon_server_thread.join();